@@ -0,0 +1,188 @@
+//! Built-in benchmark of the installed build.
+//!
+//! `benchmark()` times representative simulations and analyses at a few
+//! problem sizes, so users can check they got an optimized, parallel build
+//! and compare machines when reporting performance problems.
+
+use std::time::Instant;
+
+use pyo3::prelude::*;
+
+use crate::common::fitting::{FitOptions, LinearRegionParams};
+use crate::common::validation::check_count;
+use crate::fractal::box_counting_3d::{box_counting_3d_morton, BoxQuery, GridAveraging, MortonIndex};
+use crate::simulation::cca::{run_cca_internal, CcaParams};
+use crate::simulation::dla::{run_dla_internal, DlaParams};
+use crate::simulation::hooks::NoHooks;
+
+/// Timing of one task at one problem size.
+#[derive(Debug, Clone)]
+pub struct BenchmarkEntry {
+    pub task: &'static str,
+    pub size: usize,
+    pub time_ms: f64,
+    /// Items (particles or points) processed per second.
+    pub throughput: f64,
+}
+
+impl BenchmarkEntry {
+    fn timed(task: &'static str, size: usize, start: Instant) -> Self {
+        let seconds = start.elapsed().as_secs_f64();
+        Self {
+            task,
+            size,
+            time_ms: seconds * 1e3,
+            throughput: if seconds > 0.0 { size as f64 / seconds } else { f64::INFINITY },
+        }
+    }
+}
+
+/// Bits per dimension of the Morton counting pass timed on its own, the finest grid.
+const COUNT_PRECISION: u32 = 21;
+
+/// Run every benchmark task at every size.
+///
+/// Box counting is timed on the DLA agglomerate of the same size, and so is
+/// the counting pass alone over a 21-bit Morton index (after the sort).
+pub fn run_benchmark(sizes: &[usize], seed: u64) -> Vec<BenchmarkEntry> {
+    let mut entries = Vec::with_capacity(sizes.len() * 4);
+    for &size in sizes {
+        let start = Instant::now();
+        let dla = run_dla_internal(
+            DlaParams {
+                n_particles: size,
+                ..Default::default()
+            },
+            seed,
+            &mut NoHooks,
+        );
+        entries.push(BenchmarkEntry::timed("dla", size, start));
+
+        let start = Instant::now();
+        run_cca_internal(
+            CcaParams {
+                n_particles: size,
+                ..Default::default()
+            },
+            seed,
+            &mut NoHooks,
+        );
+        entries.push(BenchmarkEntry::timed("cca", size, start));
+
+        let start = Instant::now();
+        box_counting_3d_morton(
+            &dla.coordinates,
+            16,
+            &GridAveraging::default(),
+            &LinearRegionParams::default(),
+            &FitOptions::default(),
+        );
+        entries.push(BenchmarkEntry::timed("box_counting_3d", dla.coordinates.len(), start));
+
+        let index = MortonIndex::new(&dla.coordinates, COUNT_PRECISION);
+        let start = Instant::now();
+        index.box_counts(&BoxQuery::default());
+        entries.push(BenchmarkEntry::timed("morton_counts", dla.coordinates.len(), start));
+    }
+    entries
+}
+
+/// Python wrapper for benchmark results.
+///
+/// One row per (task, size): `tasks`, `sizes`, `times_ms` and `throughput`
+/// are parallel lists.
+#[pyclass(name = "BenchmarkResult")]
+#[derive(Debug, Clone)]
+pub struct PyBenchmarkResult {
+    /// Version of the installed aglogen_core.
+    #[pyo3(get)]
+    pub version: String,
+    /// False for builds with debug assertions, which run many times slower.
+    #[pyo3(get)]
+    pub release_build: bool,
+    /// Threads available to the parallel (rayon) code paths.
+    #[pyo3(get)]
+    pub n_threads: usize,
+    #[pyo3(get)]
+    pub tasks: Vec<String>,
+    #[pyo3(get)]
+    pub sizes: Vec<usize>,
+    #[pyo3(get)]
+    pub times_ms: Vec<f64>,
+    /// Particles or points processed per second.
+    #[pyo3(get)]
+    pub throughput: Vec<f64>,
+}
+
+#[pymethods]
+impl PyBenchmarkResult {
+    fn __repr__(&self) -> String {
+        let mut out = format!(
+            "BenchmarkResult(version={}, release_build={}, n_threads={})",
+            self.version, self.release_build, self.n_threads
+        );
+        for i in 0..self.tasks.len() {
+            out.push_str(&format!(
+                "\n  {:<16} size={:<8} {:>10.1} ms {:>12.0} items/s",
+                self.tasks[i], self.sizes[i], self.times_ms[i], self.throughput[i]
+            ));
+        }
+        out
+    }
+}
+
+/// Benchmark the installed build.
+///
+/// Runs DLA, CCA, 3D box counting and its Morton counting pass at each
+/// problem size and reports wall times and throughputs, together with
+/// whether this is an optimized build and how many threads the parallel code
+/// paths use.
+///
+/// # Arguments
+/// * `sizes` - Particle counts to benchmark (default: [100, 300, 1000])
+/// * `seed` - Random seed, so repeated benchmarks run identical simulations
+#[pyfunction]
+#[pyo3(signature = (sizes=vec![100, 300, 1000], seed=0))]
+pub fn benchmark(py: Python<'_>, sizes: Vec<usize>, seed: u64) -> PyResult<PyBenchmarkResult> {
+    check_count("sizes", sizes.len(), 1)?;
+    for &size in &sizes {
+        check_count("sizes", size, 2)?;
+    }
+
+    let entries = py.allow_threads(|| run_benchmark(&sizes, seed));
+
+    Ok(PyBenchmarkResult {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        release_build: !cfg!(debug_assertions),
+        n_threads: rayon::current_num_threads(),
+        tasks: entries.iter().map(|e| e.task.to_string()).collect(),
+        sizes: entries.iter().map(|e| e.size).collect(),
+        times_ms: entries.iter().map(|e| e.time_ms).collect(),
+        throughput: entries.iter().map(|e| e.throughput).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_times_every_task() {
+        let entries = run_benchmark(&[10, 20], 1);
+        let tasks: Vec<_> = entries.iter().map(|e| (e.task, e.size)).collect();
+        assert_eq!(
+            tasks,
+            vec![
+                ("dla", 10),
+                ("cca", 10),
+                ("box_counting_3d", 10),
+                ("morton_counts", 10),
+                ("dla", 20),
+                ("cca", 20),
+                ("box_counting_3d", 20),
+                ("morton_counts", 20),
+            ]
+        );
+        assert!(entries.iter().all(|e| e.time_ms >= 0.0 && e.throughput > 0.0));
+    }
+}
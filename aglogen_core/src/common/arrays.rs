@@ -0,0 +1,166 @@
+//! Conversion of numpy inputs into owned arrays.
+//!
+//! Analysis entry points accept float32 as well as float64 arrays in any
+//! memory layout (C or Fortran order, strided views such as `a[:, ::2]`).
+//! Inputs are copied once into contiguous `f64` storage here, so the
+//! algorithms never see the caller's dtype or strides; the few that stream
+//! over inputs too large to copy borrow them with `borrow_float_array2`.
+
+use ndarray::{Array1, Array2};
+use numpy::{PyArray1, PyArray2, PyArray3, PyArrayMethods, PyReadonlyArray2, PyUntypedArray};
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+
+/// Describe a Python object for error messages, e.g. "ndarray of dtype int64 and ndim 2".
+fn describe(obj: &Bound<'_, PyAny>) -> String {
+    let type_name = obj
+        .get_type()
+        .name()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|_| "object".to_string());
+    match (obj.getattr("dtype"), obj.getattr("ndim")) {
+        (Ok(dtype), Ok(ndim)) => format!("{} of dtype {} and ndim {}", type_name, dtype, ndim),
+        _ => type_name,
+    }
+}
+
+/// Extract a 1D float32/float64 array as owned `f64` data.
+pub fn extract_f64_array1(obj: &Bound<'_, PyAny>, name: &str) -> PyResult<Array1<f64>> {
+    if let Ok(arr) = obj.downcast::<PyArray1<f64>>() {
+        let ro = arr.try_readonly()?;
+        let view = ro.as_array();
+        return Ok(Array1::from_shape_fn(view.len(), |i| view[i]));
+    }
+    if let Ok(arr) = obj.downcast::<PyArray1<f32>>() {
+        let ro = arr.try_readonly()?;
+        let view = ro.as_array();
+        return Ok(Array1::from_shape_fn(view.len(), |i| f64::from(view[i])));
+    }
+    Err(PyTypeError::new_err(format!(
+        "{} must be a 1D float32 or float64 array, got {}",
+        name,
+        describe(obj)
+    )))
+}
+
+/// Extract a 2D float32/float64 array as owned `f64` data.
+pub fn extract_f64_array2(obj: &Bound<'_, PyAny>, name: &str) -> PyResult<Array2<f64>> {
+    if let Ok(arr) = obj.downcast::<PyArray2<f64>>() {
+        let ro = arr.try_readonly()?;
+        let view = ro.as_array();
+        return Ok(Array2::from_shape_fn(view.dim(), |(i, j)| view[[i, j]]));
+    }
+    if let Ok(arr) = obj.downcast::<PyArray2<f32>>() {
+        let ro = arr.try_readonly()?;
+        let view = ro.as_array();
+        return Ok(Array2::from_shape_fn(view.dim(), |(i, j)| f64::from(view[[i, j]])));
+    }
+    Err(PyTypeError::new_err(format!(
+        "{} must be a 2D float32 or float64 array, got {}",
+        name,
+        describe(obj)
+    )))
+}
+
+/// 2D float32/float64 array borrowed from numpy, for inputs too large to copy.
+pub enum ReadonlyFloatArray2<'py> {
+    F64(PyReadonlyArray2<'py, f64>),
+    F32(PyReadonlyArray2<'py, f32>),
+}
+
+/// Borrow a 2D float32/float64 array in place, in its own dtype and layout.
+pub fn borrow_float_array2<'py>(obj: &Bound<'py, PyAny>, name: &str) -> PyResult<ReadonlyFloatArray2<'py>> {
+    if let Ok(arr) = obj.downcast::<PyArray2<f64>>() {
+        return Ok(ReadonlyFloatArray2::F64(arr.try_readonly()?));
+    }
+    if let Ok(arr) = obj.downcast::<PyArray2<f32>>() {
+        return Ok(ReadonlyFloatArray2::F32(arr.try_readonly()?));
+    }
+    Err(PyTypeError::new_err(format!(
+        "{} must be a 2D float32 or float64 array, got {}",
+        name,
+        describe(obj)
+    )))
+}
+
+/// Extract a grayscale image as owned `u8` data.
+///
+/// uint8 images are taken as-is and uint16 images are rescaled to 0-255.
+/// Float images whose values all lie in [0, 1] (the scikit-image convention)
+/// are scaled by 255; other float images are rounded and clamped to 0-255.
+pub fn extract_u8_image(obj: &Bound<'_, PyAny>, name: &str) -> PyResult<Array2<u8>> {
+    if let Ok(arr) = obj.downcast::<PyArray2<u8>>() {
+        let ro = arr.try_readonly()?;
+        let view = ro.as_array();
+        return Ok(Array2::from_shape_fn(view.dim(), |(i, j)| view[[i, j]]));
+    }
+    if let Ok(arr) = obj.downcast::<PyArray2<u16>>() {
+        let ro = arr.try_readonly()?;
+        let view = ro.as_array();
+        return Ok(Array2::from_shape_fn(view.dim(), |(i, j)| (view[[i, j]] / 257) as u8));
+    }
+    if obj.downcast::<PyArray2<f64>>().is_ok() || obj.downcast::<PyArray2<f32>>().is_ok() {
+        let values = extract_f64_array2(obj, name)?;
+        return Ok(float_image_to_u8(&values));
+    }
+    Err(PyTypeError::new_err(format!(
+        "{} must be a 2D uint8, uint16, float32 or float64 image, got {}",
+        name,
+        describe(obj)
+    )))
+}
+
+/// Extract a stack of grayscale images as owned `u8` frames.
+///
+/// Accepts a 3D uint8 array with the frames along the first axis, or a
+/// sequence (list, tuple) of 2D images of any dtype `extract_u8_image` takes.
+pub fn extract_u8_images(obj: &Bound<'_, PyAny>, name: &str) -> PyResult<Vec<Array2<u8>>> {
+    if let Ok(arr) = obj.downcast::<PyArray3<u8>>() {
+        let ro = arr.try_readonly()?;
+        let view = ro.as_array();
+        return Ok(view
+            .outer_iter()
+            .map(|frame| Array2::from_shape_fn(frame.dim(), |(i, j)| frame[[i, j]]))
+            .collect());
+    }
+    if obj.downcast::<PyUntypedArray>().is_err() {
+        if let Ok(items) = obj.try_iter() {
+            return items
+                .enumerate()
+                .map(|(k, item)| extract_u8_image(&item?, &format!("{}[{}]", name, k)))
+                .collect();
+        }
+    }
+    Err(PyTypeError::new_err(format!(
+        "{} must be a 3D uint8 array or a sequence of 2D images, got {}",
+        name,
+        describe(obj)
+    )))
+}
+
+/// Map a float image onto the 0-255 range.
+fn float_image_to_u8(values: &Array2<f64>) -> Array2<u8> {
+    let unit_range = values.iter().all(|&v| (0.0..=1.0).contains(&v));
+    let scale = if unit_range { 255.0 } else { 1.0 };
+    values.mapv(|v| (v * scale).round().clamp(0.0, 255.0) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::arr2;
+
+    #[test]
+    fn test_float_image_unit_range_is_scaled() {
+        let img = arr2(&[[0.0, 0.5], [1.0, 0.25]]);
+        let out = float_image_to_u8(&img);
+        assert_eq!(out, arr2(&[[0u8, 128], [255, 64]]));
+    }
+
+    #[test]
+    fn test_float_image_byte_range_is_clamped() {
+        let img = arr2(&[[-3.0, 12.4], [254.6, 300.0]]);
+        let out = float_image_to_u8(&img);
+        assert_eq!(out, arr2(&[[0u8, 12], [255, 255]]));
+    }
+}
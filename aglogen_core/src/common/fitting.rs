@@ -0,0 +1,691 @@
+//! Linear fits of log-log data with automatic linear-region detection.
+//!
+//! Box counting and the Rg-N evolution fits estimate a fractal dimension
+//! from the slope of a log-log plot whose first points are often curved
+//! (discretization at small boxes, the first few particles of a growing
+//! agglomerate). [`fit_linear_region`] starts from the last points, which are
+//! the most reliable, and extends the fit towards the first ones until a
+//! point stops lining up. A [`FitOptions`] pins the fitted scales by hand
+//! instead, and picks the estimator of the final line ([`FitMethod`]):
+//! least squares, or a robust alternative when a few scales are off.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::Rng;
+
+use super::rng::create_rng;
+use super::validation::{check_count, check_in_range, check_positive};
+
+/// Pairs of points the Theil-Sen slope is taken over, beyond which they are sampled.
+const MAX_PAIRS: usize = 100_000;
+
+/// Candidate lines RANSAC scores, beyond which they are sampled.
+const RANSAC_CANDIDATES: usize = 2_000;
+
+/// RANSAC inlier threshold, in robust standard deviations of the residuals.
+const RANSAC_THRESHOLD: f64 = 2.5;
+
+/// Seed of the pair sampling, so robust fits are reproducible.
+const PAIR_SEED: u64 = 0x5eed;
+
+/// Seed of the bootstrap resampling, so confidence intervals are reproducible.
+pub(crate) const BOOTSTRAP_SEED: u64 = 0xb007;
+
+/// Fitted line `y = intercept + slope * x`.
+#[derive(Debug, Clone, Copy)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+    /// Standard error of the slope.
+    pub std_error: f64,
+}
+
+impl LinearFit {
+    /// Residuals `y - prediction` for every point.
+    pub fn residuals(&self, x: &[f64], y: &[f64]) -> Vec<f64> {
+        x.iter()
+            .zip(y.iter())
+            .map(|(xi, yi)| yi - (self.intercept + self.slope * xi))
+            .collect()
+    }
+}
+
+/// Least-squares fit of `y` against `x`.
+///
+/// Fewer than two points or a constant `x` give a flat line with an infinite
+/// standard error.
+pub fn linear_regression(x: &[f64], y: &[f64]) -> LinearFit {
+    let n = x.len() as f64;
+    if n < 2.0 {
+        return LinearFit {
+            slope: 0.0,
+            intercept: 0.0,
+            r_squared: 0.0,
+            std_error: f64::INFINITY,
+        };
+    }
+
+    let sum_x: f64 = x.iter().sum();
+    let sum_y: f64 = y.iter().sum();
+    let sum_xx: f64 = x.iter().map(|xi| xi * xi).sum();
+    let sum_xy: f64 = x.iter().zip(y.iter()).map(|(xi, yi)| xi * yi).sum();
+
+    let denom = n * sum_xx - sum_x * sum_x;
+    if denom.abs() < 1e-15 {
+        return LinearFit {
+            slope: 0.0,
+            intercept: sum_y / n,
+            r_squared: 0.0,
+            std_error: f64::INFINITY,
+        };
+    }
+
+    let slope = (n * sum_xy - sum_x * sum_y) / denom;
+    let intercept = (sum_y - slope * sum_x) / n;
+
+    let mean_y = sum_y / n;
+    let mut ss_res = 0.0;
+    let mut ss_tot = 0.0;
+    for (xi, yi) in x.iter().zip(y.iter()) {
+        let res = yi - (intercept + slope * xi);
+        ss_res += res * res;
+        ss_tot += (yi - mean_y) * (yi - mean_y);
+    }
+
+    let r_squared = if ss_tot > 1e-15 { 1.0 - ss_res / ss_tot } else { 0.0 };
+    let mse = ss_res / (n - 2.0).max(1.0);
+    let std_error = (mse / (sum_xx - sum_x * sum_x / n).abs().max(1e-15)).sqrt();
+
+    LinearFit {
+        slope,
+        intercept,
+        r_squared,
+        std_error,
+    }
+}
+
+/// Line through given slope and intercept, with R² and slope standard error
+/// from its residuals as for least squares.
+fn line_fit(x: &[f64], y: &[f64], slope: f64, intercept: f64) -> LinearFit {
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let ss_xx: f64 = x.iter().map(|xi| (xi - mean_x) * (xi - mean_x)).sum();
+    let ss_tot: f64 = y.iter().map(|yi| (yi - mean_y) * (yi - mean_y)).sum();
+    let ss_res: f64 = x.iter().zip(y).map(|(xi, yi)| (yi - intercept - slope * xi).powi(2)).sum();
+
+    LinearFit {
+        slope,
+        intercept,
+        r_squared: if ss_tot > 1e-15 { 1.0 - ss_res / ss_tot } else { 0.0 },
+        std_error: (ss_res / (n - 2.0).max(1.0) / ss_xx.max(1e-15)).sqrt(),
+    }
+}
+
+/// Weighted least-squares fit of `y` against `x`.
+///
+/// With the counts N behind a log-count plot as weights, every point weighs
+/// as much as its Poisson precision (ln N has variance 1/N). Fewer than two
+/// points or a constant `x` give a flat line with an infinite standard error.
+pub fn weighted_regression(x: &[f64], y: &[f64], weights: &[f64]) -> LinearFit {
+    let sum_w: f64 = weights.iter().sum();
+    if x.len() < 2 || sum_w <= 0.0 {
+        return linear_regression(&[], &[]);
+    }
+    let mean_x = x.iter().zip(weights).map(|(xi, w)| w * xi).sum::<f64>() / sum_w;
+    let mean_y = y.iter().zip(weights).map(|(yi, w)| w * yi).sum::<f64>() / sum_w;
+    let points = || x.iter().zip(y).zip(weights).map(|((xi, yi), w)| (xi - mean_x, yi - mean_y, w));
+    let ss_xx: f64 = points().map(|(dx, _, w)| w * dx * dx).sum();
+    if ss_xx < 1e-15 {
+        return LinearFit {
+            slope: 0.0,
+            intercept: mean_y,
+            r_squared: 0.0,
+            std_error: f64::INFINITY,
+        };
+    }
+
+    let slope = points().map(|(dx, dy, w)| w * dx * dy).sum::<f64>() / ss_xx;
+    let ss_res: f64 = points().map(|(dx, dy, w)| w * (dy - slope * dx).powi(2)).sum();
+    let ss_tot: f64 = points().map(|(_, dy, w)| w * dy * dy).sum();
+    LinearFit {
+        slope,
+        intercept: mean_y - slope * mean_x,
+        r_squared: if ss_tot > 1e-15 { 1.0 - ss_res / ss_tot } else { 0.0 },
+        std_error: (ss_res / (x.len() as f64 - 2.0).max(1.0) / ss_xx).sqrt(),
+    }
+}
+
+/// Median of `values` (NaN when empty).
+fn median(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Index pairs of points with distinct `x`: all of them, or `max` drawn with
+/// a fixed seed when there are more.
+fn point_pairs(x: &[f64], max: usize) -> Vec<(usize, usize)> {
+    let n = x.len();
+    let pairs: Vec<(usize, usize)> = if n * n.saturating_sub(1) / 2 <= max {
+        (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect()
+    } else {
+        let mut rng = create_rng(PAIR_SEED);
+        (0..max)
+            .map(|_| {
+                let i = rng.gen_range(0..n);
+                let j = rng.gen_range(0..n - 1);
+                (i, if j >= i { j + 1 } else { j })
+            })
+            .collect()
+    };
+    pairs.into_iter().filter(|&(i, j)| (x[i] - x[j]).abs() > 1e-12).collect()
+}
+
+/// Theil-Sen fit of `y` against `x`: the median of the slopes between pairs
+/// of points, through the median intercept.
+///
+/// Up to about 29% of the points can be arbitrarily off without moving the
+/// line. R² and standard error come from the residuals as for least squares.
+pub fn theil_sen(x: &[f64], y: &[f64]) -> LinearFit {
+    let slopes: Vec<f64> = point_pairs(x, MAX_PAIRS)
+        .into_iter()
+        .map(|(i, j)| (y[j] - y[i]) / (x[j] - x[i]))
+        .collect();
+    if slopes.is_empty() {
+        return linear_regression(x, y);
+    }
+    let slope = median(slopes);
+    let intercept = median(x.iter().zip(y).map(|(xi, yi)| yi - slope * xi).collect());
+    line_fit(x, y, slope, intercept)
+}
+
+/// RANSAC fit of `y` against `x`: least squares over the largest set of
+/// points within a robust threshold of a line through two of them.
+///
+/// Every pair of points is a candidate line (a seeded sample of them for
+/// long series), and the threshold is `RANSAC_THRESHOLD` robust standard
+/// deviations (1.4826 MAD) of the residuals about the Theil-Sen line.
+pub fn ransac(x: &[f64], y: &[f64]) -> LinearFit {
+    let candidates = point_pairs(x, RANSAC_CANDIDATES);
+    if x.len() < 3 || candidates.is_empty() {
+        return linear_regression(x, y);
+    }
+    let spread = median(theil_sen(x, y).residuals(x, y).iter().map(|r| r.abs()).collect());
+    let threshold = (RANSAC_THRESHOLD * 1.4826 * spread).max(1e-9);
+
+    let mut inliers: Vec<usize> = Vec::new();
+    for (i, j) in candidates {
+        let slope = (y[j] - y[i]) / (x[j] - x[i]);
+        let intercept = y[i] - slope * x[i];
+        let consensus: Vec<usize> = (0..x.len())
+            .filter(|&k| (y[k] - intercept - slope * x[k]).abs() <= threshold)
+            .collect();
+        if consensus.len() > inliers.len() {
+            inliers = consensus;
+        }
+    }
+    let (xs, ys): (Vec<f64>, Vec<f64>) = inliers.iter().map(|&k| (x[k], y[k])).unzip();
+    linear_regression(&xs, &ys)
+}
+
+/// Estimator of the line through the fitted points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FitMethod {
+    /// Ordinary least squares
+    #[default]
+    Ols,
+    /// Median of the pairwise slopes
+    TheilSen,
+    /// Least squares over the consensus set of the best pair of points
+    Ransac,
+    /// Least squares weighted by the counts behind each point
+    Weighted,
+}
+
+impl FitMethod {
+    /// Method called `name` ("ols", "theil_sen", "ransac" or "wls").
+    pub fn from_name(name: &str) -> PyResult<Self> {
+        match name {
+            "ols" => Ok(Self::Ols),
+            "theil_sen" => Ok(Self::TheilSen),
+            "ransac" => Ok(Self::Ransac),
+            "wls" => Ok(Self::Weighted),
+            _ => Err(PyValueError::new_err(format!(
+                "fit_method must be 'ols', 'theil_sen', 'ransac' or 'wls', got '{}'",
+                name
+            ))),
+        }
+    }
+
+    /// Name of the method, as taken by `from_name`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ols => "ols",
+            Self::TheilSen => "theil_sen",
+            Self::Ransac => "ransac",
+            Self::Weighted => "wls",
+        }
+    }
+
+    /// Fit `y` against `x`; the `weights` are only used by WLS.
+    pub fn fit(&self, x: &[f64], y: &[f64], weights: &[f64]) -> LinearFit {
+        match self {
+            Self::Ols => linear_regression(x, y),
+            Self::TheilSen => theil_sen(x, y),
+            Self::Ransac => ransac(x, y),
+            Self::Weighted => weighted_regression(x, y, weights),
+        }
+    }
+}
+
+/// Thresholds of the automatic linear-region detection.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct LinearRegionParams {
+    /// Number of trailing points the fit starts from (default: 4)
+    #[pyo3(get, set)]
+    pub min_points: usize,
+
+    /// Standardized residual above which a new point is an outlier (default: 2.0)
+    #[pyo3(get, set)]
+    pub residual_threshold: f64,
+
+    /// R² drop caused by a new point above which it is an outlier (default: 0.02)
+    #[pyo3(get, set)]
+    pub r2_drop_threshold: f64,
+
+    /// A point joins the reported region only if R² stays within this of the best
+    /// value seen so far (default: 0.01)
+    #[pyo3(get, set)]
+    pub r2_tolerance: f64,
+}
+
+#[pymethods]
+impl LinearRegionParams {
+    #[new]
+    #[pyo3(signature = (min_points=4, residual_threshold=2.0, r2_drop_threshold=0.02, r2_tolerance=0.01))]
+    pub fn new(
+        min_points: usize,
+        residual_threshold: f64,
+        r2_drop_threshold: f64,
+        r2_tolerance: f64,
+    ) -> Self {
+        Self {
+            min_points,
+            residual_threshold,
+            r2_drop_threshold,
+            r2_tolerance,
+        }
+    }
+}
+
+impl LinearRegionParams {
+    /// Check the parameters before a fit, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_count("min_points", self.min_points, 2)?;
+        check_positive("residual_threshold", self.residual_threshold)?;
+        check_in_range("r2_drop_threshold", self.r2_drop_threshold, 0.0, 1.0)?;
+        check_in_range("r2_tolerance", self.r2_tolerance, 0.0, 1.0)
+    }
+
+    /// Defaults for the Rg-N evolution of a single run of `n_samples` samples.
+    ///
+    /// The last few samples of one growth history are too noisy to seed the
+    /// fit on their own, so it starts from the last half of the samples.
+    pub fn for_evolution(n_samples: usize) -> Self {
+        Self {
+            min_points: (n_samples / 2).max(4),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for LinearRegionParams {
+    fn default() -> Self {
+        Self::new(4, 2.0, 0.02, 0.01)
+    }
+}
+
+/// Resolve an optional Python `LinearRegionParams` argument, validating it.
+pub fn resolve_linear_region(params: Option<LinearRegionParams>) -> PyResult<LinearRegionParams> {
+    let params = params.unwrap_or_default();
+    params.validate()?;
+    Ok(params)
+}
+
+/// Manual limits on the points of a fit and the estimator of its line.
+///
+/// Points outside the limits stay in the result (for plotting and
+/// re-fitting) but are left out of the fit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FitOptions {
+    /// Smallest scale fitted
+    pub min_scale: Option<f64>,
+    /// Largest scale fitted
+    pub max_scale: Option<f64>,
+    /// Indices `i0..i1` of the points fitted, skipping the linear-region detection
+    pub indices: Option<(usize, usize)>,
+    /// Estimator of the line through the fitted points
+    pub method: FitMethod,
+    /// Bootstrap resamples of the fit (0 = none)
+    pub bootstrap: usize,
+}
+
+impl FitOptions {
+    /// Build the options from the Python `min_scale`, `max_scale`, `fit_range`,
+    /// `fit_method` and `bootstrap` arguments, raising `ValueError` on nonsense input.
+    pub fn new(
+        min_scale: Option<f64>,
+        max_scale: Option<f64>,
+        fit_range: Option<(usize, usize)>,
+        fit_method: &str,
+        bootstrap: usize,
+    ) -> PyResult<Self> {
+        if let Some(min) = min_scale {
+            check_positive("min_scale", min)?;
+        }
+        if let Some(max) = max_scale {
+            check_positive("max_scale", max)?;
+            if let Some(min) = min_scale.filter(|&min| max < min) {
+                return Err(PyValueError::new_err(format!(
+                    "max_scale ({}) must not be smaller than min_scale ({})",
+                    max, min
+                )));
+            }
+        }
+        if let Some((i0, i1)) = fit_range {
+            check_count("fit_range end", i1, i0.saturating_add(2))?;
+        }
+        Ok(Self {
+            min_scale,
+            max_scale,
+            indices: fit_range,
+            method: FitMethod::from_name(fit_method)?,
+            bootstrap,
+        })
+    }
+
+    /// Whether `scale` lies within the scale limits.
+    pub fn contains(&self, scale: f64) -> bool {
+        self.min_scale.is_none_or(|min| scale >= min) && self.max_scale.is_none_or(|max| scale <= max)
+    }
+
+    /// Raise `ValueError` when the scale limits or `fit_range` left fewer
+    /// than two points in the region `start..end` that `fit` returned.
+    pub fn check_fitted(&self, start: usize, end: usize) -> PyResult<()> {
+        let limited = self.min_scale.is_some() || self.max_scale.is_some() || self.indices.is_some();
+        let n_points = end.saturating_sub(start);
+        if limited && n_points < 2 {
+            return Err(PyValueError::new_err(format!(
+                "min_scale, max_scale and fit_range leave {} points to fit, at least 2 are needed",
+                n_points
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fit a line to the points of `(x, y)` whose `scales` are within the limits.
+    ///
+    /// The scales must be monotonic, so the points kept are contiguous. Within
+    /// them the fit covers `indices` if given (clamped to the data), otherwise
+    /// the linear region detected with `params`, and the line through it is
+    /// fitted with `method` (WLS weighting each point by its count e^y).
+    /// Region bounds and residuals refer to all points.
+    pub fn fit(&self, scales: &[f64], x: &[f64], y: &[f64], params: &LinearRegionParams) -> LinearRegion {
+        let kept: Vec<usize> = (0..scales.len()).filter(|&i| self.contains(scales[i])).collect();
+        let (mut start, mut end) = match (kept.first(), kept.last()) {
+            (Some(&first), Some(&last)) => (first, last + 1),
+            _ => (0, 0),
+        };
+        if let Some((i0, i1)) = self.indices {
+            end = end.min(i1);
+            start = start.max(i0).min(end);
+        }
+
+        if self.indices.is_none() {
+            let region = fit_linear_region(&x[start..end], &y[start..end], params);
+            end = start + region.end;
+            start += region.start;
+        }
+        let counts: Vec<f64> = y[start..end].iter().map(|v| v.exp()).collect();
+        let fit = self.method.fit(&x[start..end], &y[start..end], &counts);
+        LinearRegion {
+            start,
+            end,
+            fit,
+            residuals: fit.residuals(x, y),
+        }
+    }
+
+    /// Slopes of the fit of `region` redone on `bootstrap` resamples of its
+    /// points, drawn with replacement.
+    ///
+    /// Resamples that cannot be fitted (every point at one scale) are dropped.
+    pub fn bootstrap_slopes(&self, x: &[f64], y: &[f64], region: &LinearRegion) -> Vec<f64> {
+        let (x, y) = (&x[region.start..region.end], &y[region.start..region.end]);
+        let n = x.len();
+        if n < 2 {
+            return Vec::new();
+        }
+        let mut rng = create_rng(BOOTSTRAP_SEED);
+        (0..self.bootstrap)
+            .filter_map(|_| {
+                let picks: Vec<usize> = (0..n).map(|_| rng.gen_range(0..n)).collect();
+                let xs: Vec<f64> = picks.iter().map(|&i| x[i]).collect();
+                let ys: Vec<f64> = picks.iter().map(|&i| y[i]).collect();
+                let counts: Vec<f64> = ys.iter().map(|v| v.exp()).collect();
+                let fit = self.method.fit(&xs, &ys, &counts);
+                fit.std_error.is_finite().then_some(fit.slope)
+            })
+            .collect()
+    }
+}
+
+/// Fit restricted to the detected linear region `start..end` of the data.
+#[derive(Debug, Clone)]
+pub struct LinearRegion {
+    pub start: usize,
+    pub end: usize,
+    pub fit: LinearFit,
+    /// Residuals of every point (inside and outside the region) against `fit`.
+    pub residuals: Vec<f64>,
+}
+
+/// Fit a line to the linear region of `(x, y)`.
+///
+/// Starts with the last `min_points` points and progressively adds points
+/// from the left, stopping when a new point is both far from the current
+/// line (standardized residual) and lowers R² noticeably.
+pub fn fit_linear_region(x: &[f64], y: &[f64], params: &LinearRegionParams) -> LinearRegion {
+    let n = x.len();
+    if n < 3 {
+        let fit = linear_regression(x, y);
+        return LinearRegion {
+            start: 0,
+            end: n,
+            fit,
+            residuals: fit.residuals(x, y),
+        };
+    }
+
+    // Start with rightmost points (most reliable)
+    let min_points = params.min_points.min(n);
+    let start_idx = n - min_points;
+    let mut current = linear_regression(&x[start_idx..], &y[start_idx..]);
+    let mut best_start = start_idx;
+    let mut best_r2 = current.r_squared;
+
+    // Progressively add points from the left
+    for i in (0..start_idx).rev() {
+        let residual = y[i] - (current.intercept + current.slope * x[i]);
+        let std_residual = if current.std_error > 1e-15 {
+            residual.abs() / current.std_error
+        } else {
+            0.0
+        };
+
+        let candidate = linear_regression(&x[i..], &y[i..]);
+        let r2_drop = best_r2 - candidate.r_squared;
+        if std_residual > params.residual_threshold && r2_drop > params.r2_drop_threshold {
+            // This point is an outlier - stop here
+            break;
+        }
+
+        current = candidate;
+        if candidate.r_squared > best_r2 - params.r2_tolerance {
+            best_start = i;
+            best_r2 = candidate.r_squared.max(best_r2);
+        }
+    }
+
+    // Final regression on selected range, residuals for all points (for plotting)
+    let fit = linear_regression(&x[best_start..], &y[best_start..]);
+    LinearRegion {
+        start: best_start,
+        end: n,
+        fit,
+        residuals: fit.residuals(x, y),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_regression_exact_line() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let y = [2.0, 4.0, 6.0, 8.0, 10.0];
+        let fit = linear_regression(&x, &y);
+
+        assert!((fit.slope - 2.0).abs() < 1e-10);
+        assert!(fit.intercept.abs() < 1e-10);
+        assert!((fit.r_squared - 1.0).abs() < 1e-10);
+        assert!(linear_regression(&[1.0, 1.0], &[0.0, 1.0]).std_error.is_infinite());
+    }
+
+    #[test]
+    fn test_linear_region_skips_curved_head() {
+        // Slightly noisy slope 2 line whose first two points bend away
+        let x: Vec<f64> = (0..10).map(f64::from).collect();
+        let mut y: Vec<f64> = x
+            .iter()
+            .enumerate()
+            .map(|(i, v)| 2.0 * v + 1.0 + if i % 2 == 0 { 0.01 } else { -0.01 })
+            .collect();
+        y[0] += 6.0;
+        y[1] += 3.0;
+
+        let region = fit_linear_region(&x, &y, &LinearRegionParams::default());
+        assert_eq!((region.start, region.end), (2, 10));
+        assert!((region.fit.slope - 2.0).abs() < 1e-2);
+        assert_eq!(region.residuals.len(), 10);
+
+        // Thresholds loose enough to accept every point keep the whole head
+        let loose = LinearRegionParams {
+            residual_threshold: 1e6,
+            r2_tolerance: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(fit_linear_region(&x, &y, &loose).start, 0);
+    }
+
+    #[test]
+    fn test_fit_range_pins_the_fitted_points() {
+        // Slope 1 up to scale 8, slope 3 above
+        let scales: Vec<f64> = (0..8).map(|i| f64::from(1 << i)).collect();
+        let x: Vec<f64> = scales.iter().map(|s| s.ln()).collect();
+        let y: Vec<f64> = x.iter().map(|&v| if v <= 8f64.ln() { v } else { 3.0 * v - 2.0 * 8f64.ln() }).collect();
+        let params = LinearRegionParams::default();
+
+        // Scale limits keep the detection within 1..=8
+        let low = FitOptions::new(None, Some(8.0), None, "ols", 0).unwrap().fit(&scales, &x, &y, &params);
+        assert_eq!((low.start, low.end), (0, 4));
+        assert!((low.fit.slope - 1.0).abs() < 1e-12);
+        assert_eq!(low.residuals.len(), 8);
+
+        // Indices are fitted as given, within the scale limits
+        let high = FitOptions::new(Some(2.0), None, Some((0, 8)), "ols", 0).unwrap().fit(&scales, &x, &y, &params);
+        assert_eq!((high.start, high.end), (1, 8));
+        let pinned = FitOptions::new(None, None, Some((4, 20)), "ols", 0).unwrap().fit(&scales, &x, &y, &params);
+        assert_eq!((pinned.start, pinned.end), (4, 8));
+        assert!((pinned.fit.slope - 3.0).abs() < 1e-12);
+
+        assert!(FitOptions::new(Some(4.0), Some(2.0), None, "ols", 0).is_err());
+        assert!(FitOptions::new(None, None, Some((3, 4)), "ols", 0).is_err());
+        assert!(FitOptions::new(None, None, Some((usize::MAX, 0)), "ols", 0).is_err());
+
+        // Limits leaving a single point cannot be fitted
+        let narrow = FitOptions::new(Some(3.0), Some(5.0), None, "ols", 0).unwrap();
+        let region = narrow.fit(&scales, &x, &y, &params);
+        assert_eq!((region.start, region.end), (2, 3));
+        assert!(narrow.check_fitted(region.start, region.end).is_err());
+        assert!(narrow.check_fitted(low.start, low.end).is_ok());
+        let unlimited = FitOptions::new(None, None, None, "ols", 0).unwrap();
+        assert!(unlimited.check_fitted(0, 1).is_ok());
+        assert!(FitOptions::new(None, None, None, "lad", 0).is_err());
+    }
+
+    #[test]
+    fn test_robust_fit_methods() {
+        // Slope 2 line with one point far off
+        let x: Vec<f64> = (0..12).map(f64::from).collect();
+        let mut y: Vec<f64> = x.iter().map(|v| 2.0 * v + 1.0).collect();
+        y[3] += 8.0;
+
+        let ols = FitMethod::Ols.fit(&x, &y, &[]);
+        assert!((ols.slope - 2.0).abs() > 0.1);
+        for method in [FitMethod::TheilSen, FitMethod::Ransac] {
+            let fit = method.fit(&x, &y, &[]);
+            assert!((fit.slope - 2.0).abs() < 1e-9, "{:?}: {}", method, fit.slope);
+            assert!((fit.intercept - 1.0).abs() < 1e-9, "{:?}: {}", method, fit.intercept);
+        }
+
+        // Weighting the outlier down pulls least squares back to the line
+        let mut weights = vec![1.0; 12];
+        weights[3] = 1e-6;
+        let wls = FitMethod::Weighted.fit(&x, &y, &weights);
+        assert!((wls.slope - 2.0).abs() < 1e-4, "{}", wls.slope);
+        let uniform = weighted_regression(&x, &y, &[3.0; 12]);
+        assert!((uniform.slope - ols.slope).abs() < 1e-12);
+        assert!((uniform.std_error - ols.std_error).abs() < 1e-12);
+
+        // Long series sample their pairs
+        let x: Vec<f64> = (0..1000).map(f64::from).collect();
+        let y: Vec<f64> = x.iter().map(|v| 0.5 * v).collect();
+        assert!((theil_sen(&x, &y).slope - 0.5).abs() < 1e-12);
+        assert!((ransac(&x, &y).slope - 0.5).abs() < 1e-12);
+        assert_eq!(FitMethod::from_name("theil_sen").unwrap().name(), "theil_sen");
+    }
+
+    #[test]
+    fn test_bootstrap_slopes_spread_with_noise() {
+        let x: Vec<f64> = (0..10).map(f64::from).collect();
+        let exact: Vec<f64> = x.iter().map(|v| 1.5 * v).collect();
+        let options = FitOptions::new(None, None, None, "ols", 200).unwrap();
+        let region = options.fit(&x, &x, &exact, &LinearRegionParams::default());
+
+        // Every resample of an exact line has its slope
+        let slopes = options.bootstrap_slopes(&x, &exact, &region);
+        assert!(slopes.len() > 190 && slopes.len() <= 200);
+        assert!(slopes.iter().all(|s| (s - 1.5).abs() < 1e-9));
+
+        // Noise spreads the slopes around the fitted one, reproducibly
+        let noisy: Vec<f64> = exact.iter().enumerate().map(|(i, v)| v + [0.3, -0.2, 0.1][i % 3]).collect();
+        let region = options.fit(&x, &x, &noisy, &LinearRegionParams::default());
+        let slopes = options.bootstrap_slopes(&x, &noisy, &region);
+        let mean = slopes.iter().sum::<f64>() / slopes.len() as f64;
+        let spread = slopes.iter().map(|s| (s - mean).abs()).fold(0.0, f64::max);
+        assert!((mean - region.fit.slope).abs() < 0.02 && spread > 1e-3 && spread < 0.2, "{} {}", mean, spread);
+        assert_eq!(slopes, options.bootstrap_slopes(&x, &noisy, &region));
+    }
+}
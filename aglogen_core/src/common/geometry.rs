@@ -2,8 +2,10 @@
 
 use std::ops::{Add, Mul, Sub};
 
+use serde::Serialize;
+
 /// 3D vector with basic operations.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub struct Vector3 {
     pub x: f64,
     pub y: f64,
@@ -51,6 +53,11 @@ impl Vector3 {
     pub fn distance_to(&self, other: &Self) -> f64 {
         (*self - *other).length()
     }
+
+    /// Squared distance, for comparisons that don't need the sqrt.
+    pub fn distance_squared_to(&self, other: &Self) -> f64 {
+        (*self - *other).length_squared()
+    }
 }
 
 impl Add for Vector3 {
@@ -78,7 +85,7 @@ impl Mul<f64> for Vector3 {
 }
 
 /// Sphere representation.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct Sphere {
     pub center: Vector3,
     pub radius: f64,
@@ -103,6 +110,41 @@ impl Sphere {
     }
 }
 
+/// Unit quaternion `w + xi + yj + zk` describing a rotation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// Rotation matrix (row-major) of the quaternion.
+    pub fn to_matrix(self) -> [[f64; 3]; 3] {
+        let Self { w, x, y, z } = self;
+        [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+        ]
+    }
+
+    /// Rotate a vector.
+    pub fn rotate(&self, v: &Vector3) -> Vector3 {
+        let m = self.to_matrix();
+        Vector3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
 /// Axis-aligned bounding box.
 #[derive(Debug, Clone, Copy)]
 pub struct AABB {
@@ -173,4 +215,14 @@ mod tests {
         assert!(s1.intersects(&s2)); // overlapping
         assert!(!s1.intersects(&s3)); // not overlapping
     }
+
+    #[test]
+    fn test_quaternion_rotation() {
+        // 90 degrees about z
+        let half = std::f64::consts::FRAC_PI_4;
+        let q = Quaternion::new(half.cos(), 0.0, 0.0, half.sin());
+        let v = q.rotate(&Vector3::new(1.0, 0.0, 0.0));
+        assert!(v.distance_to(&Vector3::new(0.0, 1.0, 0.0)) < 1e-12);
+        assert_eq!(Quaternion::new(1.0, 0.0, 0.0, 0.0).rotate(&v), v);
+    }
 }
@@ -0,0 +1,126 @@
+//! Three-dimensional convex hulls.
+
+use std::collections::HashSet;
+
+use super::geometry::Vector3;
+
+/// Convex hull of a point set as outward-oriented triangles.
+pub struct ConvexHull {
+    pub points: Vec<Vector3>,
+    /// Vertex indices of each face, counter-clockwise seen from outside
+    pub faces: Vec<[usize; 3]>,
+}
+
+impl ConvexHull {
+    /// Build the hull incrementally, adding one point at a time.
+    ///
+    /// Returns None when the points are fewer than four or (nearly) coplanar,
+    /// i.e. when the hull has no volume.
+    pub fn new(points: &[Vector3]) -> Option<Self> {
+        let scale = points.iter().map(|p| p.length()).fold(0.0, f64::max).max(1.0);
+        let eps = 1e-10 * scale;
+        let [a, b, c, d] = initial_tetrahedron(points, eps)?;
+
+        let mut faces = vec![[a, b, c], [a, c, d], [a, d, b], [b, d, c]];
+        // Orient the faces outward: d lies behind [a, b, c]
+        if signed_distance(points, [a, b, c], &points[d]) > 0.0 {
+            for face in &mut faces {
+                face.swap(1, 2);
+            }
+        }
+
+        for (p, point) in points.iter().enumerate() {
+            if [a, b, c, d].contains(&p) {
+                continue;
+            }
+            let visible: Vec<bool> = faces.iter().map(|&f| signed_distance(points, f, point) > eps).collect();
+            if !visible.contains(&true) {
+                continue;
+            }
+
+            // Horizon: edges of visible faces whose twin belongs to a hidden face
+            let visible_edges: HashSet<(usize, usize)> = faces
+                .iter()
+                .zip(&visible)
+                .filter(|(_, &v)| v)
+                .flat_map(|(f, _)| [(f[0], f[1]), (f[1], f[2]), (f[2], f[0])])
+                .collect();
+            let mut kept: Vec<[usize; 3]> = faces
+                .iter()
+                .zip(&visible)
+                .filter(|(_, &v)| !v)
+                .map(|(&f, _)| f)
+                .collect();
+            for &(u, v) in &visible_edges {
+                if !visible_edges.contains(&(v, u)) {
+                    kept.push([u, v, p]);
+                }
+            }
+            faces = kept;
+        }
+
+        Some(Self {
+            points: points.to_vec(),
+            faces,
+        })
+    }
+
+    /// Enclosed volume.
+    pub fn volume(&self) -> f64 {
+        // Tetrahedra from the first vertex to every face
+        let origin = self.points[self.faces[0][0]];
+        self.faces
+            .iter()
+            .map(|&[a, b, c]| {
+                let (a, b, c) = (self.points[a] - origin, self.points[b] - origin, self.points[c] - origin);
+                a.dot(&b.cross(&c)) / 6.0
+            })
+            .sum()
+    }
+}
+
+/// Distance of `point` from the plane of `face`, positive on its outer side
+/// (scaled by twice the face area).
+fn signed_distance(points: &[Vector3], [a, b, c]: [usize; 3], point: &Vector3) -> f64 {
+    let normal = (points[b] - points[a]).cross(&(points[c] - points[a]));
+    normal.dot(&(*point - points[a]))
+}
+
+/// Four affinely independent points to start the hull from.
+fn initial_tetrahedron(points: &[Vector3], eps: f64) -> Option<[usize; 4]> {
+    let farthest = |score: &dyn Fn(&Vector3) -> f64| {
+        (0..points.len())
+            .map(|i| (i, score(&points[i])))
+            .max_by(|x, y| x.1.total_cmp(&y.1))
+            .filter(|&(_, s)| s > eps)
+            .map(|(i, _)| i)
+    };
+
+    let a = 0;
+    let b = farthest(&|p| p.distance_to(&points[a]))?;
+    let ab = points[b] - points[a];
+    let c = farthest(&|p| ab.cross(&(*p - points[a])).length() / ab.length())?;
+    let normal = ab.cross(&(points[c] - points[a]));
+    let d = farthest(&|p| normal.dot(&(*p - points[a])).abs() / normal.length())?;
+    Some([a, b, c, d])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cube_hull() {
+        // Cube corners plus interior and face points that must not change the hull
+        let mut points = vec![Vector3::new(0.5, 0.5, 0.5), Vector3::new(1.0, 0.5, 0.5)];
+        for i in 0..8 {
+            points.push(Vector3::new((i & 1) as f64, ((i >> 1) & 1) as f64, ((i >> 2) & 1) as f64));
+        }
+        let hull = ConvexHull::new(&points).unwrap();
+        assert!((hull.volume() - 1.0).abs() < 1e-12);
+
+        // Coplanar points have no hull
+        let square: Vec<Vector3> = points.iter().map(|p| Vector3::new(p.x, p.y, 0.0)).collect();
+        assert!(ConvexHull::new(&square).is_none());
+    }
+}
@@ -1,5 +1,13 @@
 //! Common utilities and data structures.
 
+pub mod arrays;
+pub mod fitting;
 pub mod geometry;
+pub mod hull;
+pub mod particles;
 pub mod rng;
+pub mod serialization;
 pub mod spatial;
+pub mod units;
+pub mod validation;
+pub mod warnings;
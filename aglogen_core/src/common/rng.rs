@@ -2,15 +2,117 @@
 
 use std::f64::consts::PI;
 
+use pyo3::prelude::*;
 use rand::Rng;
 use rand::SeedableRng;
 use rand_pcg::Pcg64;
 
+use super::geometry::Quaternion;
+
+/// Golden-ratio increment used to space the seed sequence.
+const SEED_INCREMENT: u64 = 0x9E37_79B9_7F4A_7C15;
+
 /// Create a deterministic RNG from a seed.
 pub fn create_rng(seed: u64) -> Pcg64 {
     Pcg64::seed_from_u64(seed)
 }
 
+/// Derive the `index`-th seed of the sequence rooted at `master` (SplitMix64).
+pub fn derive_seed(master: u64, index: u64) -> u64 {
+    let mut z = master.wrapping_add(index.wrapping_add(1).wrapping_mul(SEED_INCREMENT));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Tree of reproducible seeds rooted at one master seed.
+///
+/// Child `i` gets the seed `derive_seed(master, i)` and roots a sequence of
+/// its own, so nested random draws (the replicas of a batch, the seed
+/// clusters grown inside a tunable CC run) each get a stream that depends
+/// only on the master seed and their path of indices, not on the thread
+/// count or on how many numbers their siblings drew.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedSequence {
+    master: u64,
+}
+
+impl SeedSequence {
+    pub fn new(master: u64) -> Self {
+        Self { master }
+    }
+
+    /// Master seed of the sequence.
+    pub fn master(&self) -> u64 {
+        self.master
+    }
+
+    /// Seed of child `index`.
+    pub fn seed(&self, index: u64) -> u64 {
+        derive_seed(self.master, index)
+    }
+
+    /// Sequence rooted at the seed of child `index`.
+    pub fn spawn(&self, index: u64) -> Self {
+        Self::new(self.seed(index))
+    }
+}
+
+/// Reproducible seeds for replicas and nested runs.
+///
+/// ```python
+/// seq = SeedSequence(42)
+/// runs = run_batch("dla", {"n_particles": 500}, seeds=seq.seeds(8))
+/// probe = run_tunable(100, seed=seq.spawn(1).seed(0))
+/// ```
+///
+/// `run_batch(seed=s)` and `AnalysisSession(seed=s)` draw the seeds of
+/// `SeedSequence(s)` in order.
+#[pyclass(name = "SeedSequence")]
+#[derive(Clone)]
+pub struct PySeedSequence {
+    sequence: SeedSequence,
+}
+
+#[pymethods]
+impl PySeedSequence {
+    /// Sequence rooted at `seed` (default: random).
+    #[new]
+    #[pyo3(signature = (seed=None))]
+    fn new(seed: Option<u64>) -> Self {
+        Self {
+            sequence: SeedSequence::new(seed.unwrap_or_else(rand::random)),
+        }
+    }
+
+    /// Master seed of the sequence.
+    #[getter]
+    fn master(&self) -> u64 {
+        self.sequence.master()
+    }
+
+    /// Seed of child `index`.
+    fn seed(&self, index: u64) -> u64 {
+        self.sequence.seed(index)
+    }
+
+    /// Seeds of the first `n` children.
+    fn seeds(&self, n: u64) -> Vec<u64> {
+        (0..n).map(|i| self.sequence.seed(i)).collect()
+    }
+
+    /// Sequence rooted at the seed of child `index`.
+    fn spawn(&self, index: u64) -> Self {
+        Self {
+            sequence: self.sequence.spawn(index),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SeedSequence({})", self.sequence.master())
+    }
+}
+
 /// Generate a random point on a unit sphere.
 pub fn random_point_on_sphere<R: Rng>(rng: &mut R) -> (f64, f64, f64) {
 
@@ -29,6 +131,17 @@ pub fn random_direction<R: Rng>(rng: &mut R) -> (f64, f64, f64) {
     random_point_on_sphere(rng)
 }
 
+/// Generate a uniformly distributed random rotation (Shoemake's method).
+pub fn random_rotation<R: Rng>(rng: &mut R) -> Quaternion {
+    let u1: f64 = rng.gen();
+    let u2 = rng.gen_range(0.0..2.0 * PI);
+    let u3 = rng.gen_range(0.0..2.0 * PI);
+
+    let a = (1.0 - u1).sqrt();
+    let b = u1.sqrt();
+    Quaternion::new(b * u3.cos(), a * u2.sin(), a * u2.cos(), b * u3.sin())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,6 +161,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_seed_sequence_reproducible() {
+        let a: Vec<u64> = (0..8).map(|i| derive_seed(42, i)).collect();
+        let b: Vec<u64> = (0..8).map(|i| derive_seed(42, i)).collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_seed_sequence_distinct() {
+        let mut seeds: Vec<u64> = (0..1000).map(|i| derive_seed(7, i)).collect();
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), 1000);
+        assert_ne!(derive_seed(1, 0), derive_seed(2, 0));
+    }
+
+    #[test]
+    fn test_seed_sequence_substreams() {
+        let sequence = SeedSequence::new(42);
+        assert_eq!(sequence.seed(3), derive_seed(42, 3));
+        assert_eq!(sequence.spawn(3).master(), sequence.seed(3));
+
+        // Substreams of different children, and of different depths, do not collide
+        let mut seeds: Vec<u64> = (0..50).flat_map(|i| (0..50).map(move |j| sequence.spawn(i).seed(j))).collect();
+        seeds.extend((0..50).map(|i| sequence.seed(i)));
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), 2550);
+    }
+
     #[test]
     fn test_point_on_unit_sphere() {
         let mut rng = create_rng(123);
@@ -0,0 +1,362 @@
+//! JSON and MessagePack encoding of the result types.
+//!
+//! Results derive serde's `Serialize`/`Deserialize` and reach Python as
+//! `to_json`/`from_json` and `to_msgpack`/`from_msgpack` through these
+//! helpers.
+//!
+//! Every encoded result carries a `format_version` key. Fields added in later
+//! versions are optional (`#[serde(default)]`), so older documents still read
+//! back; documents without the key are version 1, and those of a newer
+//! version are refused.
+//!
+//! JSON has no NaN or infinity: serde_json writes non-finite floats as null,
+//! and `from_json` reads a null float back as NaN. An infinity therefore
+//! comes back as NaN, and an optional float holding NaN (e.g.
+//! `FraktalResult.jf`) comes back as None. MessagePack keeps every float as
+//! it is.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// Version of the encoded layout, written as `format_version`.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A value with the `format_version` key next to its fields.
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    format_version: u32,
+    #[serde(flatten)]
+    value: &'a T,
+}
+
+impl<'a, T> Versioned<'a, T> {
+    fn new(value: &'a T) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            value,
+        }
+    }
+}
+
+/// The `format_version` key of a document, alone.
+#[derive(Deserialize)]
+struct Header {
+    #[serde(default = "first_version")]
+    format_version: u32,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+/// Raise `ValueError` for documents written by a newer layout.
+fn check_version(header: Header) -> PyResult<()> {
+    if header.format_version > FORMAT_VERSION {
+        return Err(PyValueError::new_err(format!(
+            "format_version {} is newer than the supported {}; update aglogen_core",
+            header.format_version, FORMAT_VERSION
+        )));
+    }
+    Ok(())
+}
+
+/// Encode `value` as JSON.
+pub fn to_json<T: Serialize>(value: &T) -> PyResult<String> {
+    serde_json::to_string(&Versioned::new(value))
+        .map_err(|e| PyValueError::new_err(format!("cannot encode as JSON: {}", e)))
+}
+
+/// Decode a value written by `to_json`.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> PyResult<T> {
+    let value: Value = serde_json::from_str(json).map_err(invalid("JSON"))?;
+    check_version(Header::deserialize(&value).map_err(invalid("JSON"))?)?;
+    T::deserialize(NullAsNan(value)).map_err(invalid("JSON"))
+}
+
+/// Encode `value` as MessagePack, fields by name.
+pub fn to_msgpack<T: Serialize>(value: &T) -> PyResult<Vec<u8>> {
+    rmp_serde::to_vec_named(&Versioned::new(value))
+        .map_err(|e| PyValueError::new_err(format!("cannot encode as MessagePack: {}", e)))
+}
+
+/// Decode a value written by `to_msgpack`.
+pub fn from_msgpack<T: DeserializeOwned>(data: &[u8]) -> PyResult<T> {
+    check_version(rmp_serde::from_slice(data).map_err(invalid("MessagePack"))?)?;
+    rmp_serde::from_slice(data).map_err(invalid("MessagePack"))
+}
+
+fn invalid<E: std::fmt::Display>(format: &'static str) -> impl Fn(E) -> PyErr {
+    move |e| PyValueError::new_err(format!("invalid {}: {}", format, e))
+}
+
+/// JSON value deserializer that reads null floats as NaN.
+struct NullAsNan(Value);
+
+impl<'de> Deserializer<'de> for NullAsNan {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(items) => visitor.visit_seq(Items(items.into_iter())),
+            Value::Object(entries) => visitor.visit_map(Entries {
+                entries: entries.into_iter(),
+                value: None,
+            }),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_f64(f64::NAN),
+            other => other.deserialize_f64(visitor),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_f32(f32::NAN),
+            other => other.deserialize_f32(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            other => visitor.visit_some(NullAsNan(other)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Elements of a JSON array, each read by `NullAsNan`.
+struct Items(std::vec::IntoIter<Value>);
+
+impl<'de> SeqAccess<'de> for Items {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        self.0.next().map(|v| seed.deserialize(NullAsNan(v))).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// Entries of a JSON object, each value read by `NullAsNan`.
+struct Entries {
+    entries: serde_json::map::IntoIter,
+    value: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for Entries {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Value::String(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let value = self.value.take().ok_or_else(|| de::Error::custom("value read before its key"))?;
+        seed.deserialize(NullAsNan(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::common::fitting::FitMethod;
+    use crate::fractal::fraktal::result::{FraktalResult, FraktalStatus, PyFraktalResult};
+    use crate::fractal::result::{FractalResult, PyFractalResult};
+    use crate::projection::PyProjectionResult;
+    use crate::simulation::dla::DlaParams;
+    use crate::simulation::provenance::run_parameters;
+    use crate::simulation::result::{PySimulationResult, SimulationResult};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Sample {
+        value: f64,
+        pair: (f64, f64),
+        values: Vec<f64>,
+        #[serde(default)]
+        optional: Option<f64>,
+        #[serde(default)]
+        name: Option<String>,
+    }
+
+    #[test]
+    fn test_round_trip_keeps_nan() {
+        let sample = Sample {
+            value: f64::NAN,
+            pair: (1.5, f64::NAN),
+            values: vec![2.0, f64::NAN],
+            optional: None,
+            name: Some("dla".to_string()),
+        };
+
+        let json = to_json(&sample).unwrap();
+        assert_eq!(
+            json,
+            r#"{"format_version":1,"value":null,"pair":[1.5,null],"values":[2.0,null],"optional":null,"name":"dla"}"#
+        );
+        let back: Sample = from_json(&json).unwrap();
+        assert!(back.value.is_nan() && back.pair.1.is_nan() && back.values[1].is_nan());
+        assert_eq!((back.pair.0, back.values[0], back.optional), (1.5, 2.0, None));
+        assert_eq!(back.name.as_deref(), Some("dla"));
+
+        let back: Sample = from_msgpack(&to_msgpack(&sample).unwrap()).unwrap();
+        assert!(back.value.is_nan() && back.values[1].is_nan());
+        assert_eq!(back.name.as_deref(), Some("dla"));
+
+        // Optional fields may be missing, as in documents of older versions; required ones may not
+        let old: Sample = from_json(r#"{"value": 1.0, "pair": [1.0, 2.0], "values": []}"#).unwrap();
+        assert_eq!((old.optional, old.name), (None, None));
+        assert!(from_json::<Sample>(r#"{"value": 1.0}"#).is_err());
+        assert!(from_json::<Sample>(r#"{"format_version": 2, "value": 1.0, "pair": [1.0, 2.0], "values": []}"#).is_err());
+
+        // JSON loses infinities and optional NaNs; MessagePack keeps them
+        let lossy = Sample {
+            value: f64::INFINITY,
+            optional: Some(f64::NAN),
+            ..sample
+        };
+        let back: Sample = from_json(&to_json(&lossy).unwrap()).unwrap();
+        assert!(back.value.is_nan() && back.optional.is_none());
+        let back: Sample = from_msgpack(&to_msgpack(&lossy).unwrap()).unwrap();
+        assert!(back.value == f64::INFINITY && back.optional.is_some_and(f64::is_nan));
+    }
+
+    #[test]
+    fn test_simulation_result_round_trip() {
+        let coords = vec![[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 2.0, 0.0]];
+        let mut result = SimulationResult::from_structure(coords, vec![1.0; 3]);
+        result.fractal_dimension = f64::NAN;
+        let result = result.to_py().with_parameters(run_parameters("dla", &DlaParams::default(), json!({"seed": 7})));
+
+        let json = to_json(&result).unwrap();
+        assert!(json.contains(r#""parameters":{"algorithm":"dla""#));
+        for back in [
+            from_json::<PySimulationResult>(&json).unwrap(),
+            from_msgpack::<PySimulationResult>(&to_msgpack(&result).unwrap()).unwrap(),
+        ] {
+            assert!(back.fractal_dimension.is_nan());
+            assert_eq!(back.coordinates_data, result.coordinates_data);
+            assert_eq!(back.contacts_data, result.contacts_data);
+            assert_eq!(back.parameters_data, result.parameters_data);
+        }
+    }
+
+    #[test]
+    fn test_fractal_result_round_trip() {
+        let result = FractalResult {
+            dimension: 1.8,
+            r_squared: 0.99,
+            std_error: f64::NAN,
+            confidence_interval: (1.7, 1.9),
+            slope: -1.8,
+            intercept: 4.0,
+            log_scales: vec![0.0, 1.0, 2.0],
+            log_values: vec![4.0, 2.2, 0.4],
+            residuals: vec![0.0; 3],
+            execution_time_ms: 3,
+            linear_region_start: 0,
+            linear_region_end: 3,
+            box_counts: vec![(1.0, 54.6), (2.0, 9.0)],
+            box_count_std: vec![0.5, 0.25],
+            fit_method: FitMethod::TheilSen,
+            bootstrap_dimensions: vec![1.79, 1.81],
+        }
+        .to_py();
+
+        for back in [
+            from_json::<PyFractalResult>(&to_json(&result).unwrap()).unwrap(),
+            from_msgpack::<PyFractalResult>(&to_msgpack(&result).unwrap()).unwrap(),
+        ] {
+            assert_eq!((back.dimension, back.fit_method.as_str()), (1.8, "theil_sen"));
+            assert!(back.std_error.is_nan());
+            assert_eq!(back.log_values_data, result.log_values_data);
+            assert_eq!(back.box_counts_data, result.box_counts_data);
+            assert_eq!(back.bootstrap_data, result.bootstrap_data);
+        }
+    }
+
+    #[test]
+    fn test_fraktal_result_round_trip() {
+        let result = PyFraktalResult::from(FraktalResult {
+            rg: 120.0,
+            df: 1.78,
+            npo: 150,
+            jf: Some(0.9),
+            status: FraktalStatus::Success,
+            model: "granulated_2012".to_string(),
+            ..FraktalResult::default()
+        });
+
+        for back in [
+            from_json::<PyFraktalResult>(&to_json(&result).unwrap()).unwrap(),
+            from_msgpack::<PyFraktalResult>(&to_msgpack(&result).unwrap()).unwrap(),
+        ] {
+            assert_eq!((back.rg, back.df, back.npo, back.jf), (120.0, 1.78, 150, Some(0.9)));
+            assert_eq!((back.status.as_str(), back.model.as_str()), (result.status.as_str(), "granulated_2012"));
+            assert!(back.diagnostics.is_none());
+        }
+    }
+
+    #[test]
+    fn test_projection_result_round_trip() {
+        let result = PyProjectionResult {
+            x: vec![0.0, 2.0],
+            y: vec![0.0, -1.0],
+            radii: vec![1.0, 1.5],
+            azimuth: 30.0,
+            elevation: 45.0,
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            bounds: [-1.0, 3.5, -2.5, 1.0],
+            session: Some("run-1".to_string()),
+        };
+
+        for back in [
+            from_json::<PyProjectionResult>(&to_json(&result).unwrap()).unwrap(),
+            from_msgpack::<PyProjectionResult>(&to_msgpack(&result).unwrap()).unwrap(),
+        ] {
+            assert_eq!((back.x, back.y, back.radii), (result.x.clone(), result.y.clone(), result.radii.clone()));
+            assert_eq!((back.azimuth, back.elevation), (30.0, 45.0));
+            assert_eq!((back.rotation, back.bounds), (result.rotation, result.bounds));
+            assert_eq!(back.session, result.session);
+        }
+    }
+}
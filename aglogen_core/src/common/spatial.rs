@@ -2,6 +2,8 @@
 
 use std::collections::HashMap;
 
+use rand::Rng;
+
 use super::geometry::{Sphere, Vector3};
 
 /// Spatial hash grid for O(1) neighbor queries.
@@ -67,9 +69,240 @@ impl SpatialHash {
     }
 }
 
+/// Cell list over the periodic cube `[-box_size/2, box_size/2)^3`.
+///
+/// Points are binned once into cells at least `min_cell_size` wide, so that
+/// every point closer than `min_cell_size` (under the minimum image
+/// convention) to a query lies in one of the 27 cells around it, wrapped
+/// across the box faces. Only occupied cells are stored, as in
+/// [`SpatialHash`], so building the list costs O(N) however sparse the box.
+pub struct PeriodicCellList {
+    box_size: f64,
+    /// Cells per box edge
+    n: usize,
+    cells: HashMap<usize, Vec<usize>>,
+}
+
+impl PeriodicCellList {
+    pub fn new(points: &[Vector3], box_size: f64, min_cell_size: f64) -> Self {
+        let n = ((box_size / min_cell_size).floor() as usize).clamp(1, 1024);
+        let mut list = Self {
+            box_size,
+            n,
+            cells: HashMap::new(),
+        };
+        for (i, p) in points.iter().enumerate() {
+            let cell = list.cell_index(list.cell_of(p));
+            list.cells.entry(cell).or_default().push(i);
+        }
+        list
+    }
+
+    /// Cell of a point, wrapping it into the box first.
+    fn cell_of(&self, point: &Vector3) -> [usize; 3] {
+        let axis = |x: f64| {
+            let fraction = (x / self.box_size + 0.5).rem_euclid(1.0);
+            ((fraction * self.n as f64) as usize).min(self.n - 1)
+        };
+        [axis(point.x), axis(point.y), axis(point.z)]
+    }
+
+    fn cell_index(&self, [x, y, z]: [usize; 3]) -> usize {
+        (x * self.n + y) * self.n + z
+    }
+
+    /// Indices of the points in the cells around `point` (itself included when stored).
+    pub fn neighbours(&self, point: &Vector3) -> impl Iterator<Item = usize> + '_ {
+        let [cx, cy, cz] = self.cell_of(point);
+        let n = self.n as isize;
+        let wrap = |c: usize, d: isize| (c as isize + d).rem_euclid(n) as usize;
+        let mut cells = Vec::with_capacity(27);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    cells.push(self.cell_index([wrap(cx, dx), wrap(cy, dy), wrap(cz, dz)]));
+                }
+            }
+        }
+        // Fewer than 3 cells per edge wrap onto the same cells
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+            .into_iter()
+            .filter_map(move |c| self.cells.get(&c))
+            .flat_map(|entries| entries.iter().copied())
+    }
+}
+
+/// Spatial hash of particles in the periodic cube `[-box_size/2, box_size/2)^3`.
+///
+/// Particles are stored wrapped into the box. Queries return every particle
+/// image near a point together with the position of that image, so growth
+/// engines can collide walkers with particles across the box faces. The
+/// box must be at least two cells wide.
+pub struct PeriodicSpatialHash {
+    box_size: f64,
+    /// False for a slab periodic in x and y only
+    periodic_z: bool,
+    hash: SpatialHash,
+    /// Wrapped center and radius of each particle, by index
+    spheres: Vec<Sphere>,
+}
+
+impl PeriodicSpatialHash {
+    pub fn new(box_size: f64, cell_size: f64) -> Self {
+        Self {
+            box_size,
+            periodic_z: true,
+            hash: SpatialHash::new(cell_size),
+            spheres: Vec::new(),
+        }
+    }
+
+    /// Hash periodic in x and y only, over the slab `[-box_size/2, box_size/2)^2 x R`.
+    pub fn lateral(box_size: f64, cell_size: f64) -> Self {
+        Self {
+            periodic_z: false,
+            ..Self::new(box_size, cell_size)
+        }
+    }
+
+    /// Image of `point` inside the box.
+    pub fn wrap(&self, point: Vector3) -> Vector3 {
+        let axis = |x: f64| x - self.box_size * (x / self.box_size + 0.5).floor();
+        let z = if self.periodic_z { axis(point.z) } else { point.z };
+        Vector3::new(axis(point.x), axis(point.y), z)
+    }
+
+    /// Insert a sphere; indices must be inserted in order 0, 1, 2, ...
+    pub fn insert(&mut self, index: usize, sphere: &Sphere) {
+        debug_assert_eq!(index, self.spheres.len());
+        let wrapped = Sphere::new(self.wrap(sphere.center), sphere.radius);
+        self.hash.insert(index, &wrapped);
+        self.spheres.push(wrapped);
+    }
+
+    /// Particles with an image in the cells around `point`, as `(index, image center)`.
+    pub fn neighbours(&self, point: Vector3) -> Vec<(usize, Vector3)> {
+        let q = self.wrap(point);
+        let reach = self.box_size / 2.0 - 2.0 * self.hash.cell_size;
+        // Images one box away only matter near the faces they lie across
+        let shifts = |x: f64| -> Vec<f64> {
+            let mut shifts = vec![0.0];
+            if x > reach {
+                shifts.push(1.0);
+            }
+            if x < -reach {
+                shifts.push(-1.0);
+            }
+            shifts
+        };
+
+        let z_shifts = if self.periodic_z { shifts(q.z) } else { vec![0.0] };
+
+        let mut found = Vec::new();
+        for &kx in &shifts(q.x) {
+            for &ky in &shifts(q.y) {
+                for &kz in &z_shifts {
+                    let shift = Vector3::new(kx, ky, kz) * self.box_size;
+                    // Stored particles near q - shift have their image at s + shift near q
+                    let probe = Sphere::new(q - shift, 0.0);
+                    for index in self.hash.query_potential_collisions(&probe) {
+                        found.push((index, self.spheres[index].center + shift));
+                    }
+                }
+            }
+        }
+        // Images are relative to the wrapped point; move them next to `point`
+        let offset = point - q;
+        for (_, center) in &mut found {
+            *center = *center + offset;
+        }
+        found
+    }
+
+    /// Uniform random point of the box where a sphere of `radius` touches no
+    /// particle, or None if `attempts` draws all landed on the deposit.
+    pub fn random_free_point<R: Rng>(&self, radius: f64, attempts: usize, rng: &mut R) -> Option<Vector3> {
+        (0..attempts).find_map(|_| {
+            let mut coordinate = || (rng.gen::<f64>() - 0.5) * self.box_size;
+            let point = Vector3::new(coordinate(), coordinate(), coordinate());
+            (!self.overlaps(point, radius, 1.0, None, 0.0)).then_some(point)
+        })
+    }
+
+    /// Whether a sphere at `center` overlaps any particle image, as in `ParticleStore::overlaps`.
+    pub fn overlaps(
+        &self,
+        center: Vector3,
+        radius: f64,
+        sintering_coeff: f64,
+        skip: Option<usize>,
+        tolerance: f64,
+    ) -> bool {
+        self.neighbours(center).into_iter().any(|(index, image)| {
+            let limit = (sintering_coeff * (radius + self.spheres[index].radius) - tolerance).max(0.0);
+            skip != Some(index) && center.distance_squared_to(&image) < limit * limit
+        })
+    }
+}
+
+/// Check a deposit grown in the periodic box of edge `box_size` and returned
+/// unwrapped, each particle next to the one it stuck to: it spreads across
+/// the box faces, wraps into the box by whole box lengths, touches its own
+/// images across the faces and overlaps none of them.
+#[cfg(test)]
+pub(crate) fn check_periodic_deposit(coords: &[[f64; 3]], radii: &[f64], box_size: f64) {
+    let box_hash = PeriodicSpatialHash::new(box_size, box_size / 4.0);
+    let centers: Vec<Vector3> = coords.iter().map(|c| Vector3::new(c[0], c[1], c[2])).collect();
+    let half = box_size / 2.0;
+
+    // Unwrapped, every particle touches an earlier one and the deposit grows past the box
+    for i in 1..centers.len() {
+        let touches = (0..i).any(|j| centers[i].distance_to(&centers[j]) <= radii[i] + radii[j] + 1e-5);
+        assert!(touches, "particle {} is not next to the one it stuck to", i);
+    }
+    let extent = |axis: fn(&Vector3) -> f64| {
+        let values = centers.iter().map(axis);
+        values.clone().fold(f64::MIN, f64::max) - values.fold(f64::MAX, f64::min)
+    };
+    let extent = extent(|c| c.x).max(extent(|c| c.y)).max(extent(|c| c.z));
+    assert!(extent > box_size, "extent = {}", extent);
+
+    // Wrapped, the particles fill the box, each moved by whole box lengths
+    let wrapped: Vec<Vector3> = centers.iter().map(|&c| box_hash.wrap(c)).collect();
+    for (c, w) in centers.iter().zip(&wrapped) {
+        for (x, wx) in [(c.x, w.x), (c.y, w.y), (c.z, w.z)] {
+            assert!((-half..half).contains(&wx), "{} wrapped to {}", x, wx);
+            let shift = (x - wx) / box_size;
+            assert!((shift - shift.round()).abs() < 1e-9);
+        }
+    }
+
+    // Contacts across the faces: far apart in the box, touching through an image
+    let image = |d: f64| d - box_size * (d / box_size).round();
+    let mut across = 0;
+    for i in 0..wrapped.len() {
+        for j in 0..i {
+            let d = wrapped[i] - wrapped[j];
+            let distance = (image(d.x).powi(2) + image(d.y).powi(2) + image(d.z).powi(2)).sqrt();
+            let contact = radii[i] + radii[j];
+            assert!(distance > contact - 1e-5, "particles {} and {} overlap", i, j);
+            let crosses = [d.x, d.y, d.z].iter().any(|x| x.abs() > half);
+            if crosses && distance <= contact + 1e-5 {
+                across += 1;
+            }
+        }
+    }
+    assert!(across > 0, "no contact across the box faces");
+}
+
 #[cfg(test)]
 mod tests {
+    use rand::Rng;
+
     use super::*;
+    use crate::common::rng::create_rng;
 
     #[test]
     fn test_spatial_hash() {
@@ -89,4 +322,56 @@ mod tests {
         assert!(neighbors.contains(&1));
         assert!(!neighbors.contains(&2));
     }
+
+    #[test]
+    fn test_periodic_hash_finds_images() {
+        let mut hash = PeriodicSpatialHash::new(10.0, 2.0);
+        hash.insert(0, &Sphere::new(Vector3::new(4.5, 0.0, 0.0), 1.0));
+        hash.insert(1, &Sphere::new(Vector3::new(0.0, 0.0, 0.0), 1.0));
+
+        // Across the x face, the image of particle 0 sits at -5.5
+        let found = hash.neighbours(Vector3::new(-4.5, 0.0, 0.0));
+        assert!(found.contains(&(0, Vector3::new(-5.5, 0.0, 0.0))));
+        assert!(hash.overlaps(Vector3::new(-4.5, 0.0, 0.0), 1.0, 1.0, None, 1e-6));
+        // Queries outside the box are answered around the point itself
+        let found = hash.neighbours(Vector3::new(15.5, 0.0, 0.0));
+        assert!(found.contains(&(0, Vector3::new(14.5, 0.0, 0.0))));
+        assert!(!hash.overlaps(Vector3::new(2.5, 0.0, 0.0), 0.4, 1.0, None, 1e-6));
+    }
+
+    #[test]
+    fn test_periodic_cell_list_finds_close_pairs() {
+        let box_size = 20.0;
+        let mut rng = create_rng(3);
+        let points: Vec<Vector3> = (0..400)
+            .map(|_| {
+                // Some points outside the box, as for clusters straddling a face
+                let mut c = || rng.gen_range(-0.7 * box_size..0.7 * box_size);
+                Vector3::new(c(), c(), c())
+            })
+            .collect();
+        let cutoff = 2.5;
+        let list = PeriodicCellList::new(&points, box_size, cutoff);
+
+        let image = |d: f64| d - box_size * (d / box_size).round();
+        for (i, a) in points.iter().enumerate() {
+            let found: Vec<usize> = list.neighbours(a).collect();
+            for (j, b) in points.iter().enumerate() {
+                let d = Vector3::new(image(a.x - b.x), image(a.y - b.y), image(a.z - b.z));
+                if d.length() < cutoff {
+                    assert!(found.contains(&j), "pair ({}, {}) at {} missed", i, j, d.length());
+                }
+            }
+        }
+
+        // A box narrower than three cells still lists every point once
+        let list = PeriodicCellList::new(&points, box_size, 8.0);
+        assert_eq!(list.neighbours(&points[0]).count(), points.len());
+
+        // A dilute box of 1024^3 cells only stores the occupied ones
+        let far = [Vector3::zero(), Vector3::new(40.0, 0.0, 0.0), Vector3::new(0.0, -40.0, 0.0)];
+        let list = PeriodicCellList::new(&far, 1e4, 1.0);
+        assert_eq!(list.cells.len(), 3);
+        assert_eq!(list.neighbours(&far[1]).collect::<Vec<_>>(), vec![1]);
+    }
 }
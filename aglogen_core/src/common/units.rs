@@ -0,0 +1,95 @@
+//! Physical units of simulation lengths.
+//!
+//! The engines are scale-free: radii and coordinates are plain numbers.
+//! A `Units` object records what those numbers mean (e.g. radii given in
+//! nm) and, optionally, the material density, so results can report Rg,
+//! volume and mass in physical units comparable with FRAKTAL's nm-based
+//! quantities.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::validation::check_positive;
+
+/// Supported length units and their size in nm.
+const LENGTH_UNITS: [(&str, f64); 4] = [("nm", 1.0), ("um", 1e3), ("mm", 1e6), ("m", 1e9)];
+
+/// Physical meaning of the lengths of a simulation.
+#[pyclass(name = "Units")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PyUnits {
+    /// Unit of every length given to and returned by the simulation
+    #[pyo3(get)]
+    pub length_unit: String,
+
+    /// Size of one length unit in nm
+    #[pyo3(get)]
+    pub nm_per_unit: f64,
+
+    /// Material density in kg/m³ (e.g. 1800 for soot), needed for masses
+    #[pyo3(get)]
+    pub density: Option<f64>,
+}
+
+#[pymethods]
+impl PyUnits {
+    /// # Arguments
+    /// * `length_unit` - "nm", "um", "mm" or "m" (default: "nm")
+    /// * `density` - Material density in kg/m³ (default: None, no masses)
+    #[new]
+    #[pyo3(signature = (length_unit="nm", density=None))]
+    pub fn new(length_unit: &str, density: Option<f64>) -> PyResult<Self> {
+        let length_unit = length_unit.to_lowercase();
+        let Some(&(_, nm_per_unit)) = LENGTH_UNITS.iter().find(|(name, _)| *name == length_unit) else {
+            return Err(PyValueError::new_err(format!(
+                "length_unit must be 'nm', 'um', 'mm' or 'm', got '{}'",
+                length_unit
+            )));
+        };
+        if let Some(density) = density {
+            check_positive("density", density)?;
+        }
+        Ok(Self {
+            length_unit,
+            nm_per_unit,
+            density,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        match self.density {
+            Some(density) => format!("Units(length_unit='{}', density={})", self.length_unit, density),
+            None => format!("Units(length_unit='{}')", self.length_unit),
+        }
+    }
+}
+
+impl PyUnits {
+    /// Convert a length to nm.
+    pub fn to_nm(&self, length: f64) -> f64 {
+        length * self.nm_per_unit
+    }
+
+    /// Mass in kg of a solid volume given in nm³, if the density is known.
+    pub fn mass_kg(&self, volume_nm3: f64) -> Option<f64> {
+        self.density.map(|density| density * volume_nm3 * 1e-27)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_units_convert_to_nm_and_mass() {
+        let units = PyUnits::new("um", Some(1800.0)).unwrap();
+        assert_eq!(units.to_nm(0.015), 15.0);
+        // 1000 nm³ of soot
+        assert!((units.mass_kg(1000.0).unwrap() - 1.8e-21).abs() < 1e-33);
+
+        assert!(PyUnits::new("nm", None).unwrap().mass_kg(1.0).is_none());
+        assert!(PyUnits::new("inch", None).is_err());
+        assert!(PyUnits::new("nm", Some(-1.0)).is_err());
+    }
+}
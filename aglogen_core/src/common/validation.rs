@@ -0,0 +1,176 @@
+//! Upfront checks of user-supplied parameters.
+//!
+//! Entry points run these before releasing the GIL, so invalid input fails
+//! with a `ValueError` naming the offending argument instead of panicking
+//! deep inside an engine or silently producing garbage.
+
+use std::fmt::Display;
+
+use ndarray::{ArrayBase, Data, Ix2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Require an integer argument of at least `min`.
+pub fn check_count(name: &str, value: usize, min: usize) -> PyResult<()> {
+    if value < min {
+        return Err(PyValueError::new_err(format!(
+            "{} must be at least {}, got {}",
+            name, min, value
+        )));
+    }
+    Ok(())
+}
+
+/// Require a finite, strictly positive value.
+pub fn check_positive(name: &str, value: f64) -> PyResult<()> {
+    if !(value.is_finite() && value > 0.0) {
+        return Err(PyValueError::new_err(format!(
+            "{} must be a positive number, got {}",
+            name, value
+        )));
+    }
+    Ok(())
+}
+
+/// Require a value inside the closed range `[min, max]` (NaN is rejected).
+pub fn check_in_range<T>(name: &str, value: T, min: T, max: T) -> PyResult<()>
+where
+    T: PartialOrd + Display + Copy,
+{
+    if !(min..=max).contains(&value) {
+        return Err(PyValueError::new_err(format!(
+            "{} must be in [{}, {}], got {}",
+            name, min, max, value
+        )));
+    }
+    Ok(())
+}
+
+/// Require a periodic box at least eight times the largest radius.
+///
+/// Collisions across the box faces are found in a spatial hash of cells
+/// four radii wide, which needs two cells per box edge.
+pub fn check_periodic_box(box_size: f64, radius_max: f64) -> PyResult<()> {
+    check_positive("box_size", box_size)?;
+    if box_size < 8.0 * radius_max {
+        return Err(PyValueError::new_err(format!(
+            "box_size must be at least 8 times radius_max ({}), got {}",
+            8.0 * radius_max,
+            box_size
+        )));
+    }
+    Ok(())
+}
+
+/// Require a sticking probability in (0, 1].
+///
+/// Zero is rejected as well: no particle would ever stick and the
+/// particle-cluster engines would never finish.
+pub fn check_sticking_probability(value: f64) -> PyResult<()> {
+    if !(value > 0.0 && value <= 1.0) {
+        return Err(PyValueError::new_err(format!(
+            "sticking_probability must be in (0, 1], got {}",
+            value
+        )));
+    }
+    Ok(())
+}
+
+/// Require `0 < radius_min <= radius_max`.
+pub fn check_radius_range(radius_min: f64, radius_max: f64) -> PyResult<()> {
+    check_positive("radius_min", radius_min)?;
+    check_positive("radius_max", radius_max)?;
+    if radius_max < radius_min {
+        return Err(PyValueError::new_err(format!(
+            "radius_max ({}) must not be smaller than radius_min ({})",
+            radius_max, radius_min
+        )));
+    }
+    Ok(())
+}
+
+/// Require a mass fractal dimension strictly between 1 and 3.
+pub fn check_fractal_dimension(name: &str, value: f64) -> PyResult<()> {
+    if !(value > 1.0 && value < 3.0) {
+        return Err(PyValueError::new_err(format!(
+            "{} must be strictly between 1 and 3, got {}",
+            name, value
+        )));
+    }
+    Ok(())
+}
+
+/// Require an (N, 3) array of finite coordinates.
+pub fn check_coordinates<S, T>(name: &str, coords: &ArrayBase<S, Ix2>) -> PyResult<()>
+where
+    S: Data<Elem = T>,
+    T: Copy + Into<f64> + Display,
+{
+    if coords.ncols() != 3 {
+        return Err(PyValueError::new_err(format!(
+            "{} must have shape (N, 3), got shape {:?}",
+            name,
+            coords.shape()
+        )));
+    }
+    if let Some(row) = coords.outer_iter().position(|p| p.iter().any(|&v| !v.into().is_finite())) {
+        return Err(PyValueError::new_err(format!(
+            "{} must be finite, row {} is {}",
+            name,
+            row,
+            coords.row(row)
+        )));
+    }
+    Ok(())
+}
+
+/// Require one finite, positive radius per coordinate row.
+pub fn check_radii(radii: &[f64], n_points: usize) -> PyResult<()> {
+    if radii.len() != n_points {
+        return Err(PyValueError::new_err(format!(
+            "radii length ({}) must match number of coordinates ({})",
+            radii.len(),
+            n_points
+        )));
+    }
+    if let Some(i) = radii.iter().position(|&r| !(r.is_finite() && r > 0.0)) {
+        return Err(PyValueError::new_err(format!(
+            "radii must be positive numbers, radii[{}] is {}",
+            i, radii[i]
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::arr2;
+
+    #[test]
+    fn test_scalar_checks() {
+        assert!(check_count("n_particles", 0, 1).is_err());
+        assert!(check_count("n_particles", 1, 1).is_ok());
+        assert!(check_positive("box_size", f64::NAN).is_err());
+        assert!(check_sticking_probability(0.0).is_err());
+        assert!(check_sticking_probability(1.0).is_ok());
+        assert!(check_radius_range(2.0, 1.0).is_err());
+        assert!(check_radius_range(1.0, 1.0).is_ok());
+        assert!(check_fractal_dimension("target_df", 3.0).is_err());
+        assert!(check_fractal_dimension("target_df", 1.8).is_ok());
+        assert!(check_in_range("precision", 22, 2, 21).is_err());
+        assert!(check_in_range("delta", f64::NAN, 1.0, 1.5).is_err());
+    }
+
+    #[test]
+    fn test_structure_checks() {
+        let coords = arr2(&[[0.0, 0.0, 0.0], [1.0, 2.0, 3.0]]);
+        assert!(check_coordinates("coordinates", &coords).is_ok());
+        assert!(check_coordinates("coordinates", &arr2(&[[0.0, 0.0]])).is_err());
+        assert!(check_coordinates("coordinates", &arr2(&[[0.0, f64::INFINITY, 0.0]])).is_err());
+
+        assert!(check_radii(&[1.0, 1.0], 2).is_ok());
+        assert!(check_radii(&[1.0], 2).is_err());
+        assert!(check_radii(&[1.0, -1.0], 2).is_err());
+    }
+}
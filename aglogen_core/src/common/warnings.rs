@@ -0,0 +1,23 @@
+//! Python warnings for silent behaviour changes.
+//!
+//! The engines run with the GIL released, so they only collect messages
+//! (clamped parameters, fallbacks, overridden settings) in a `Vec<String>`.
+//! The Python entry points emit them as `UserWarning`s once the GIL is held
+//! again.
+
+use std::ffi::CString;
+
+use pyo3::exceptions::PyUserWarning;
+use pyo3::prelude::*;
+use pyo3::PyTypeInfo;
+
+/// Emit each message as a Python `UserWarning` pointing at the caller.
+pub fn emit_warnings(py: Python<'_>, messages: &[String]) -> PyResult<()> {
+    let category = PyUserWarning::type_object(py);
+    for message in messages {
+        let message = CString::new(message.replace('\0', " "))
+            .expect("interior NUL bytes were replaced");
+        PyErr::warn(py, &category, &message, 1)?;
+    }
+    Ok(())
+}
@@ -1,84 +1,410 @@
 //! Box-counting fractal dimension analysis.
 
+use std::ops::Range;
 use std::time::Instant;
 
-use numpy::PyReadonlyArray2;
+use ndarray::{Array2, ArrayView2};
+use numpy::{PyArray2, PyArrayMethods, PyReadonlyArray2, ToPyArray};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use rayon::prelude::*;
 
+use crate::common::arrays::extract_u8_image;
+use crate::common::fitting::{linear_regression, resolve_linear_region, FitOptions, LinearRegionParams};
+use crate::common::validation::check_count;
+use crate::session::allow_threads;
+
+use super::fraktal::image_processing::{is_dark_on_light, otsu_threshold};
 use super::result::{FractalResult, PyFractalResult};
 
-/// Run box-counting fractal analysis on a binary image.
+/// Gray levels of an 8-bit image, the height of the DBC intensity axis.
+const GRAY_LEVELS: f64 = 256.0;
+
+/// Treatment of a grayscale image in `box_counting`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binarization {
+    /// Threshold at a fixed gray level
+    Fixed(u8),
+    /// Threshold at Otsu's level
+    Otsu,
+    /// Differential box-counting of the gray levels, no thresholding
+    Differential,
+}
+
+impl Binarization {
+    /// Take the binarization from "otsu", "dbc" or an integer gray level.
+    fn from_py(value: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(level) = value.extract::<u8>() {
+            return Ok(Self::Fixed(level));
+        }
+        match value.extract::<String>().as_deref() {
+            Ok("otsu") => Ok(Self::Otsu),
+            Ok("dbc") => Ok(Self::Differential),
+            _ => Err(PyValueError::new_err(format!(
+                "binarization must be 'otsu', 'dbc' or a gray level 0-255, got {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Threshold a grayscale image at `threshold`.
+///
+/// The objects are the minority side of the threshold, dark or light, as in
+/// the FRAKTAL segmentation.
+pub fn binarize(image: ArrayView2<u8>, threshold: u8) -> Array2<bool> {
+    if is_dark_on_light(image, threshold) {
+        image.mapv(|v| v <= threshold)
+    } else {
+        image.mapv(|v| v > threshold)
+    }
+}
+
+/// Run box-counting fractal analysis on a binary or grayscale image.
+///
+/// The dimension is fitted on the linear region of the log-log plot, detected
+/// with the thresholds of `linear_region` (a `LinearRegionParams`, default
+/// thresholds when None). `min_scale` and `max_scale` restrict the fit to a
+/// range of box sizes, and `fit_range` pins it to the points `i0..i1` of the
+/// plot; the box counts of every scale are returned for manual re-fitting.
+/// The line through the fitted points is least squares by default, or a
+/// robust estimator when a few scales are off. With `bootstrap` resamples of
+/// the fitted scales, the result also holds the empirical distribution and
+/// 95% interval of the dimension besides the 1.96 SE one.
+///
+/// The count of a scale depends on where the box grid starts: with
+/// `n_offsets` > 1 every scale is counted on as many grids, their origins
+/// shifted by different fractions of the box, and the mean count is fitted
+/// while `box_count_std` reports the spread across grids.
+///
+/// Grayscale images (uint8, uint16 or float) are thresholded first, at a
+/// fixed gray level or Otsu's (the objects being the minority side of the
+/// threshold, dark or light), or analysed with differential box-counting
+/// (Sarkar & Chaudhuri), which measures the Df (2-3) of the intensity
+/// surface instead of a mask.
+///
+/// # Arguments
+/// * `binary_image` - 2D boolean mask, or a grayscale image
+/// * `binarization` - For grayscale images: "otsu", a gray level 0-255, or
+///   "dbc" for differential box-counting (default: "otsu")
+/// * `min_scale` - Smallest box size fitted, in pixels (default: all)
+/// * `max_scale` - Largest box size fitted, in pixels (default: all)
+/// * `fit_range` - Indices `(i0, i1)` of the plot points fitted, replacing the
+///   linear-region detection (default: detected)
+/// * `fit_method` - "ols", "theil_sen", "ransac" or "wls" (weighted by the box
+///   counts) (default: "ols")
+/// * `bootstrap` - Bootstrap resamples of the fitted scales (default: 0, none)
+/// * `n_offsets` - Box grids averaged per scale (default: 1, the grid from the
+///   image corner)
 #[pyfunction]
-#[pyo3(signature = (binary_image, min_box_size=2, max_box_size=512, num_scales=20))]
+#[pyo3(signature = (binary_image, min_box_size=2, max_box_size=512, num_scales=20, linear_region=None, binarization=None, min_scale=None, max_scale=None, fit_range=None, fit_method="ols", bootstrap=0, n_offsets=1))]
 pub fn box_counting(
     py: Python<'_>,
-    binary_image: PyReadonlyArray2<'_, bool>,
+    binary_image: &Bound<'_, PyAny>,
     min_box_size: usize,
     max_box_size: usize,
     num_scales: usize,
+    linear_region: Option<LinearRegionParams>,
+    binarization: Option<&Bound<'_, PyAny>>,
+    min_scale: Option<f64>,
+    max_scale: Option<f64>,
+    fit_range: Option<(usize, usize)>,
+    fit_method: &str,
+    bootstrap: usize,
+    n_offsets: usize,
 ) -> PyResult<PyFractalResult> {
-    let image = binary_image.as_array();
-    let (height, width) = (image.shape()[0], image.shape()[1]);
+    let options = FitOptions::new(min_scale, max_scale, fit_range, fit_method, bootstrap)?;
+    check_count("n_offsets", n_offsets, 1)?;
+    let binarization = binarization.map(Binarization::from_py).transpose()?.unwrap_or(Binarization::Otsu);
+    let image = match binary_image.downcast::<PyArray2<bool>>() {
+        Ok(mask) => mask.to_owned_array(),
+        Err(_) => {
+            let gray = extract_u8_image(binary_image, "binary_image")?;
+            match binarization {
+                Binarization::Fixed(level) => binarize(gray.view(), level),
+                Binarization::Otsu => binarize(gray.view(), otsu_threshold(gray.view())),
+                Binarization::Differential => {
+                    let (height, width) = gray.dim();
+                    check_count("image height", height, 1)?;
+                    check_count("image width", width, 1)?;
+                    check_count("min_box_size", min_box_size, 1)?;
+                    check_count("max_box_size", max_box_size, min_box_size)?;
+                    check_count("num_scales", num_scales, 2)?;
+                    let region = resolve_linear_region(linear_region)?;
+                    let result = allow_threads(py, || {
+                        differential_box_counting_internal(
+                            gray.view(),
+                            min_box_size,
+                            max_box_size,
+                            num_scales,
+                            n_offsets,
+                            &region,
+                            &options,
+                        )
+                    });
+                    options.check_fitted(result.linear_region_start, result.linear_region_end)?;
+                    return Ok(result.to_py());
+                }
+            }
+        }
+    };
+    let (height, width) = image.dim();
 
-    // Convert to owned Vec<Vec<bool>>
-    let image_data: Vec<Vec<bool>> = (0..height)
-        .map(|i| (0..width).map(|j| image[[i, j]]).collect())
-        .collect();
+    check_count("image height", height, 1)?;
+    check_count("image width", width, 1)?;
+    check_count("min_box_size", min_box_size, 1)?;
+    check_count("max_box_size", max_box_size, min_box_size)?;
+    check_count("num_scales", num_scales, 2)?;
+    let region = resolve_linear_region(linear_region)?;
+
+    // Pack into an owned bitmap (one bit per pixel)
+    let image_data = BitImage::from_array(image.view());
 
     // Release GIL during computation
-    let result = py.allow_threads(|| {
-        box_counting_internal(&image_data, min_box_size, max_box_size, num_scales)
+    let result = allow_threads(py, || {
+        box_counting_internal(&image_data, min_box_size, max_box_size, num_scales, n_offsets, &region, &options)
     });
+    options.check_fitted(result.linear_region_start, result.linear_region_end)?;
 
     Ok(result.to_py())
 }
 
+/// Map the local box-counting dimension of a binary image.
+///
+/// The image is covered with `window` x `window` windows every `stride`
+/// pixels, and each window gets the dimension fitted over box sizes from
+/// `min_box_size` to half the window, so spatial heterogeneity of deposits
+/// and films shows up as a map. Windows are analysed in parallel with the
+/// GIL released.
+///
+/// # Arguments
+/// * `binary_image` - 2D boolean numpy array
+/// * `window` - Window side in pixels, at least 4 * `min_box_size`
+/// * `stride` - Step in pixels between windows
+/// * `min_box_size` - Smallest box side in pixels (default: 1)
+/// * `num_scales` - Box sizes per window (default: 6)
+///
+/// # Returns
+/// 2D float array of (height - window) / stride + 1 rows by
+/// (width - window) / stride + 1 columns, entry (i, j) for the window at
+/// (i * stride, j * stride); NaN where a window is empty.
+#[pyfunction]
+#[pyo3(signature = (binary_image, window, stride, min_box_size=1, num_scales=6))]
+pub fn box_counting_map<'py>(
+    py: Python<'py>,
+    binary_image: PyReadonlyArray2<'_, bool>,
+    window: usize,
+    stride: usize,
+    min_box_size: usize,
+    num_scales: usize,
+) -> PyResult<Bound<'py, PyArray2<f64>>> {
+    let image = binary_image.as_array();
+    let (height, width) = image.dim();
+
+    check_count("min_box_size", min_box_size, 1)?;
+    check_count("window", window, 4 * min_box_size)?;
+    check_count("image height", height, window)?;
+    check_count("image width", width, window)?;
+    check_count("stride", stride, 1)?;
+    check_count("num_scales", num_scales, 2)?;
+
+    let image_data = BitImage::from_array(image);
+    let map = py.allow_threads(|| box_counting_map_internal(&image_data, window, stride, min_box_size, num_scales));
+    Ok(map.to_pyarray(py))
+}
+
+/// Local dimensions of the windows of [`box_counting_map`].
+fn box_counting_map_internal(
+    image: &BitImage,
+    window: usize,
+    stride: usize,
+    min_box_size: usize,
+    num_scales: usize,
+) -> Array2<f64> {
+    let rows = (image.height - window) / stride + 1;
+    let cols = (image.width - window) / stride + 1;
+    let sizes = box_sizes(min_box_size, window / 2, num_scales, window);
+
+    let dimensions: Vec<f64> = (0..rows * cols)
+        .into_par_iter()
+        .map(|index| {
+            let (y, x) = (index / cols * stride, index % cols * stride);
+            let (log_scales, log_counts): (Vec<f64>, Vec<f64>) = sizes
+                .iter()
+                .map(|&box_size| (box_size, count_boxes_in(image, y..y + window, x..x + window, box_size)))
+                .filter(|&(_, count)| count > 0)
+                .map(|(box_size, count)| ((1.0 / box_size as f64).ln(), (count as f64).ln()))
+                .unzip();
+            if log_scales.len() < 2 {
+                f64::NAN
+            } else {
+                linear_regression(&log_scales, &log_counts).slope
+            }
+        })
+        .collect();
+    Array2::from_shape_vec((rows, cols), dimensions).expect("one dimension per window")
+}
+
+/// Binary image packed 64 pixels per word, rows padded to whole words.
+///
+/// Bit `x % 64` of word `x / 64` in a row holds pixel `x`, so a box scan
+/// tests up to 64 pixels of a row with one mask instead of one byte each.
+struct BitImage {
+    height: usize,
+    width: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitImage {
+    fn from_array(image: ArrayView2<'_, bool>) -> Self {
+        let (height, width) = image.dim();
+        let words_per_row = width.div_ceil(64);
+        let mut words = vec![0u64; height * words_per_row];
+        for ((y, x), &pixel) in image.indexed_iter() {
+            if pixel {
+                words[y * words_per_row + x / 64] |= 1u64 << (x % 64);
+            }
+        }
+        Self {
+            height,
+            width,
+            words_per_row,
+            words,
+        }
+    }
+
+    /// Columns of `cols` with a set pixel in any row of `rows`, packed like a row.
+    ///
+    /// A box row of the grid is reduced to one band, so every box of a scale
+    /// is tested with a few masks and a scale costs O(pixels / 64 + boxes).
+    fn band(&self, rows: Range<usize>, cols: &Range<usize>) -> Vec<u64> {
+        let mut band = vec![0u64; self.words_per_row];
+        if cols.is_empty() {
+            return band;
+        }
+        let words = cols.start / 64..=(cols.end - 1) / 64;
+        for y in rows {
+            let row = &self.words[y * self.words_per_row..(y + 1) * self.words_per_row];
+            for w in words.clone() {
+                band[w] |= row[w];
+            }
+        }
+        band
+    }
+}
+
+/// Whether any of the columns `cols` is set in the packed row `row`.
+fn any_in(row: &[u64], cols: Range<usize>) -> bool {
+    if cols.is_empty() {
+        return false;
+    }
+    let first = cols.start / 64;
+    let last = (cols.end - 1) / 64;
+    let head = !0u64 << (cols.start % 64);
+    let tail = match cols.end % 64 {
+        0 => !0u64,
+        end => (1u64 << end) - 1,
+    };
+    (first..=last).any(|w| {
+        let mut mask = !0u64;
+        if w == first {
+            mask &= head;
+        }
+        if w == last {
+            mask &= tail;
+        }
+        row[w] & mask != 0
+    })
+}
+
 /// Internal box-counting implementation.
 fn box_counting_internal(
-    image: &[Vec<bool>],
+    image: &BitImage,
     min_box_size: usize,
     max_box_size: usize,
     num_scales: usize,
+    n_offsets: usize,
+    region: &LinearRegionParams,
+    options: &FitOptions,
 ) -> FractalResult {
     let start_time = Instant::now();
 
-    let height = image.len();
-    let width = if height > 0 { image[0].len() } else { 0 };
+    let (height, width) = (image.height, image.width);
 
-    // Generate logarithmically spaced box sizes
+    let counts: Vec<(usize, f64, f64)> = box_sizes(min_box_size, max_box_size, num_scales, height.min(width))
+        .into_iter()
+        .map(|box_size| {
+            let grids: Vec<f64> = (0..n_offsets)
+                .map(|k| count_boxes(image, box_size, grid_shift(k)) as f64)
+                .collect();
+            let (mean, std) = grid_mean_std(&grids);
+            (box_size, mean, std)
+        })
+        .collect();
+    fit_box_counts(&counts, region, options, start_time)
+}
+
+/// Logarithmically spaced box sizes from `min_box_size` up to `max_box_size`
+/// and the image `extent`, without duplicates.
+pub(crate) fn box_sizes(min_box_size: usize, max_box_size: usize, num_scales: usize, extent: usize) -> Vec<usize> {
     let log_min = (min_box_size as f64).ln();
-    let log_max = (max_box_size.min(height.min(width)) as f64).ln();
+    let log_max = (max_box_size.min(extent) as f64).ln();
 
-    let box_sizes: Vec<usize> = (0..num_scales)
+    let mut box_sizes: Vec<usize> = (0..num_scales)
         .map(|i| {
             let log_size = log_min + (log_max - log_min) * (i as f64) / ((num_scales - 1) as f64);
             log_size.exp().round() as usize
         })
         .filter(|&s| s >= min_box_size && s <= max_box_size)
         .collect();
-
-    // Remove duplicates
-    let mut box_sizes: Vec<usize> = box_sizes;
     box_sizes.dedup();
+    box_sizes
+}
 
-    // Count boxes at each scale
-    let mut log_scales = Vec::new();
-    let mut log_counts = Vec::new();
+/// Fraction of a box edge the `k`-th averaged grid is shifted by along each
+/// of `D` axes: none for the first grid, then the R_D low-discrepancy
+/// sequence (Roberts, 2018), which spreads any number of grids evenly over
+/// the box.
+pub(crate) fn grid_shift<const D: usize>(k: usize) -> [f64; D] {
+    // Generalized golden ratio, the positive root of x^(D+1) = x + 1
+    let phi = (0..32).fold(2.0f64, |x, _| (1.0 + x).powf(1.0 / (D + 1) as f64));
+    std::array::from_fn(|axis| (k as f64 / phi.powi(axis as i32 + 1)).fract())
+}
 
-    for &box_size in &box_sizes {
-        let count = count_boxes(image, box_size);
-        if count > 0 {
-            log_scales.push((1.0 / box_size as f64).ln());
-            log_counts.push((count as f64).ln());
-        }
-    }
+/// Mean and standard deviation of the counts of one scale over the averaged grids.
+pub(crate) fn grid_mean_std(counts: &[f64]) -> (f64, f64) {
+    let n = counts.len() as f64;
+    let mean = counts.iter().sum::<f64>() / n;
+    let variance = counts.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// Fit the dimension to (box size, mean box count, count spread) triples,
+/// skipping empty counts.
+fn fit_box_counts(
+    counts: &[(usize, f64, f64)],
+    region: &LinearRegionParams,
+    options: &FitOptions,
+    start_time: Instant,
+) -> FractalResult {
+    let nonempty: Vec<(f64, f64)> = counts
+        .iter()
+        .filter(|&&(_, count, _)| count > 0.0)
+        .map(|&(box_size, count, _)| (box_size as f64, count))
+        .collect();
+    let sizes: Vec<f64> = nonempty.iter().map(|c| c.0).collect();
+    let log_scales: Vec<f64> = sizes.iter().map(|s| (1.0 / s).ln()).collect();
+    let log_counts: Vec<f64> = nonempty.iter().map(|c| c.1.ln()).collect();
 
-    // Linear regression to find fractal dimension
-    let (slope, intercept, r_squared, std_error, residuals) =
-        linear_regression(&log_scales, &log_counts);
+    // Box sizes grow along the arrays, so the fit starts from the largest
+    // boxes and drops small-box scales that bend away from the line
+    let linear = options.fit(&sizes, &log_scales, &log_counts, region);
+    let bootstrap_dimensions = options.bootstrap_slopes(&log_scales, &log_counts, &linear);
 
     // Fractal dimension is the negative slope (box-counting: N ~ s^(-Df))
-    let dimension = slope;
+    let dimension = linear.fit.slope;
+    let std_error = linear.fit.std_error;
 
     // 95% confidence interval (approximate)
     let ci_half = 1.96 * std_error;
@@ -88,46 +414,102 @@ fn box_counting_internal(
 
     FractalResult {
         dimension,
-        r_squared,
+        r_squared: linear.fit.r_squared,
         std_error,
+        slope: linear.fit.slope,
+        intercept: linear.fit.intercept,
         confidence_interval,
         log_scales,
         log_values: log_counts,
-        residuals,
+        residuals: linear.residuals,
         execution_time_ms,
-        linear_region_start: 0,  // 2D box-counting uses all points
+        linear_region_start: linear.start,
+        linear_region_end: linear.end,
+        box_counts: counts.iter().map(|&(box_size, count, _)| (box_size as f64, count)).collect(),
+        box_count_std: counts.iter().map(|c| c.2).collect(),
+        fit_method: options.method,
+        bootstrap_dimensions,
     }
 }
 
-/// Count non-empty boxes at a given box size.
-fn count_boxes(image: &[Vec<bool>], box_size: usize) -> usize {
-    let height = image.len();
-    let width = if height > 0 { image[0].len() } else { 0 };
+/// Differential box-counting (Sarkar & Chaudhuri) of a grayscale image.
+///
+/// The image is taken as a surface over the pixel grid with the gray level
+/// as height. Each s x s cell is stacked with boxes of height
+/// s * 256 / min(height, width) gray levels, and N(s) sums the boxes the
+/// surface spans between its lowest and highest gray level in every cell.
+fn differential_box_counting_internal(
+    image: ArrayView2<u8>,
+    min_box_size: usize,
+    max_box_size: usize,
+    num_scales: usize,
+    n_offsets: usize,
+    region: &LinearRegionParams,
+    options: &FitOptions,
+) -> FractalResult {
+    let start_time = Instant::now();
+    let (height, width) = image.dim();
+    let extent = height.min(width);
+
+    let counts: Vec<(usize, f64, f64)> = box_sizes(min_box_size, max_box_size, num_scales, extent)
+        .into_iter()
+        .map(|box_size| {
+            let box_height = box_size as f64 * GRAY_LEVELS / extent as f64;
+            let grids: Vec<f64> = (0..n_offsets)
+                .map(|k| {
+                    let [dy, dx] = grid_shift(k);
+                    let (rows, cols) = (grid_cuts(height, box_size, dy), grid_cuts(width, box_size, dx));
+                    let mut count = 0.0;
+                    for y in rows.windows(2) {
+                        for x in cols.windows(2) {
+                            let cell = image.slice(ndarray::s![y[0]..y[1], x[0]..x[1]]);
+                            let (low, high) =
+                                cell.iter().fold((u8::MAX, u8::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+                            count += ((high as f64 / box_height).floor() - (low as f64 / box_height).floor()) + 1.0;
+                        }
+                    }
+                    count
+                })
+                .collect();
+            let (mean, std) = grid_mean_std(&grids);
+            (box_size, mean, std)
+        })
+        .collect();
+    fit_box_counts(&counts, region, options, start_time)
+}
 
-    let mut count = 0;
+/// Box edges along an axis of `len` pixels, the grid shifted by the fraction `shift` of a box.
+fn grid_cuts(len: usize, box_size: usize, shift: f64) -> Vec<usize> {
+    let first = (shift * box_size as f64).round() as usize % box_size;
+    let mut cuts = vec![0];
+    cuts.extend((if first == 0 { box_size } else { first }..len).step_by(box_size));
+    cuts.push(len);
+    cuts
+}
 
-    let n_rows = (height + box_size - 1) / box_size;
-    let n_cols = (width + box_size - 1) / box_size;
+/// Count non-empty boxes at a given box size, the grid shifted by `shift` (rows, cols) of a box.
+fn count_boxes(image: &BitImage, box_size: usize, shift: [f64; 2]) -> usize {
+    let (rows, cols) = (grid_cuts(image.height, box_size, shift[0]), grid_cuts(image.width, box_size, shift[1]));
+    rows.windows(2)
+        .map(|y| {
+            let band = image.band(y[0]..y[1], &(0..image.width));
+            cols.windows(2).filter(|x| any_in(&band, x[0]..x[1])).count()
+        })
+        .sum()
+}
 
-    for row in 0..n_rows {
-        for col in 0..n_cols {
-            let y_start = row * box_size;
-            let x_start = col * box_size;
-            let y_end = (y_start + box_size).min(height);
-            let x_end = (x_start + box_size).min(width);
+/// Count non-empty boxes of a grid laid from the corner of the rectangle `rows` x `cols`.
+fn count_boxes_in(image: &BitImage, rows: Range<usize>, cols: Range<usize>, box_size: usize) -> usize {
+    let mut count = 0;
 
-            // Check if any pixel in box is true
-            let mut has_pixel = false;
-            'outer: for y in y_start..y_end {
-                for x in x_start..x_end {
-                    if image[y][x] {
-                        has_pixel = true;
-                        break 'outer;
-                    }
-                }
-            }
+    for y_start in rows.clone().step_by(box_size) {
+        let y_end = (y_start + box_size).min(rows.end);
+        let band = image.band(y_start..y_end, &cols);
+        for x_start in cols.clone().step_by(box_size) {
+            let x_end = (x_start + box_size).min(cols.end);
 
-            if has_pixel {
+            // Check if any pixel in box is true
+            if any_in(&band, x_start..x_end) {
                 count += 1;
             }
         }
@@ -136,87 +518,130 @@ fn count_boxes(image: &[Vec<bool>], box_size: usize) -> usize {
     count
 }
 
-/// Linear regression returning (slope, intercept, r_squared, std_error, residuals).
-fn linear_regression(x: &[f64], y: &[f64]) -> (f64, f64, f64, f64, Vec<f64>) {
-    let n = x.len() as f64;
-    if n < 2.0 {
-        return (0.0, 0.0, 0.0, 0.0, vec![]);
-    }
-
-    let sum_x: f64 = x.iter().sum();
-    let sum_y: f64 = y.iter().sum();
-    let sum_xx: f64 = x.iter().map(|xi| xi * xi).sum();
-    let sum_xy: f64 = x.iter().zip(y.iter()).map(|(xi, yi)| xi * yi).sum();
-
-    let denom = n * sum_xx - sum_x * sum_x;
-    if denom.abs() < 1e-15 {
-        return (0.0, sum_y / n, 0.0, 0.0, vec![0.0; x.len()]);
-    }
-
-    let slope = (n * sum_xy - sum_x * sum_y) / denom;
-    let intercept = (sum_y - slope * sum_x) / n;
-
-    // Calculate residuals and R-squared
-    let mean_y = sum_y / n;
-    let mut ss_res = 0.0;
-    let mut ss_tot = 0.0;
-    let mut residuals = Vec::with_capacity(x.len());
-
-    for (xi, yi) in x.iter().zip(y.iter()) {
-        let y_pred = intercept + slope * xi;
-        let res = yi - y_pred;
-        residuals.push(res);
-        ss_res += res * res;
-        ss_tot += (yi - mean_y) * (yi - mean_y);
-    }
-
-    let r_squared = if ss_tot > 0.0 {
-        1.0 - ss_res / ss_tot
-    } else {
-        0.0
-    };
-
-    // Standard error of slope
-    let mse = ss_res / (n - 2.0);
-    let std_error = (mse / (sum_xx - sum_x * sum_x / n)).sqrt();
-
-    (slope, intercept, r_squared, std_error, residuals)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ndarray::Array2;
 
     #[test]
     fn test_box_counting_line() {
         // Horizontal line should have Df ~ 1
-        let mut image = vec![vec![false; 100]; 100];
-        for x in 0..100 {
-            image[50][x] = true;
-        }
+        let mut image = Array2::from_elem((100, 100), false);
+        image.row_mut(50).fill(true);
 
-        let result = box_counting_internal(&image, 2, 64, 10);
+        let image = BitImage::from_array(image.view());
+        let region = LinearRegionParams::default();
+        let result = box_counting_internal(&image, 2, 64, 10, 1, &region, &FitOptions::default());
         assert!(result.dimension > 0.8 && result.dimension < 1.2);
+
+        // Every scale is reported, and the fit can be pinned to the boxes of 2 to 4 pixels
+        assert_eq!(result.box_counts.len(), result.log_scales.len());
+        assert_eq!(result.box_counts[0], (2.0, 50.0));
+        let options = FitOptions::new(None, Some(4.0), None, "ols", 0).unwrap();
+        let pinned = box_counting_internal(&image, 2, 64, 10, 1, &region, &options);
+        assert_eq!((pinned.linear_region_start, pinned.linear_region_end), (0, 3));
+        assert!((pinned.dimension - 1.0).abs() < 0.03, "{}", pinned.dimension);
+
+        // The fitted line reproduces the raw counts in pixel units
+        assert_eq!(pinned.slope, pinned.dimension);
+        let fitted_count = (pinned.intercept + pinned.slope * (1.0f64 / 2.0).ln()).exp();
+        assert!((fitted_count / 50.0 - 1.0).abs() < 0.02, "{}", fitted_count);
+    }
+
+    #[test]
+    fn test_offset_grids_average_the_counts() {
+        // A 4x4 square on the grid lines fills four boxes of 4, shifted by half a box only one
+        let mut image = Array2::from_elem((32, 32), false);
+        image.slice_mut(ndarray::s![2..6, 2..6]).fill(true);
+        let image = BitImage::from_array(image.view());
+        assert_eq!(count_boxes(&image, 4, [0.0, 0.0]), 4);
+        assert_eq!(count_boxes(&image, 4, [0.5, 0.5]), 1);
+        assert_eq!(grid_cuts(10, 4, 0.5), vec![0, 2, 6, 10]);
+
+        // The first grid is unshifted, the others spread over the box
+        assert_eq!(grid_shift::<3>(0), [0.0; 3]);
+        let shifts: Vec<[f64; 2]> = (1..9).map(grid_shift).collect();
+        assert!(shifts.iter().all(|s| s.iter().all(|v| (0.0..1.0).contains(v))));
+        assert!(shifts.iter().any(|s| s[0] < 0.5) && shifts.iter().any(|s| s[0] > 0.5));
+
+        let region = LinearRegionParams::default();
+        let single = box_counting_internal(&image, 2, 8, 3, 1, &region, &FitOptions::default());
+        assert!(single.box_count_std.iter().all(|&s| s == 0.0));
+        let averaged = box_counting_internal(&image, 2, 8, 3, 8, &region, &FitOptions::default());
+        assert_eq!(averaged.box_count_std.len(), averaged.box_counts.len());
+        let (size, mean) = averaged.box_counts[1];
+        assert_eq!(size, 4.0);
+        assert!(mean > 1.0 && mean < 4.0 && averaged.box_count_std[1] > 0.0, "{:?}", averaged.box_counts);
     }
 
     #[test]
     fn test_box_counting_filled() {
         // Filled square should have Df ~ 2
-        let image = vec![vec![true; 64]; 64];
+        let image = Array2::from_elem((64, 64), true);
 
-        let result = box_counting_internal(&image, 2, 32, 8);
+        let image = BitImage::from_array(image.view());
+        let result = box_counting_internal(&image, 2, 32, 8, 1, &LinearRegionParams::default(), &FitOptions::default());
         assert!(result.dimension > 1.8 && result.dimension < 2.2);
     }
 
     #[test]
-    fn test_linear_regression() {
-        let x = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let y = vec![2.0, 4.0, 6.0, 8.0, 10.0];
+    fn test_grayscale_box_counting() {
+        // Dark square on a light background, thresholded either way
+        let mut gray = Array2::from_elem((64, 64), 200u8);
+        gray.slice_mut(ndarray::s![16..40, 8..32]).fill(40);
+        let mask = binarize(gray.view(), otsu_threshold(gray.view()));
+        assert_eq!(mask, gray.mapv(|v| v == 40));
+        assert_eq!(binarize(gray.view(), 100), mask);
+
+        // A flat surface is a plane, rough noise fills towards a volume
+        let (region, options) = (LinearRegionParams::default(), FitOptions::default());
+        let flat = Array2::from_elem((128, 128), 90u8);
+        let flat = differential_box_counting_internal(flat.view(), 2, 64, 6, 1, &region, &options);
+        assert!((flat.dimension - 2.0).abs() < 0.05, "{}", flat.dimension);
+        let noise = Array2::from_shape_fn((128, 128), |(i, j)| ((i * 7919 + j * 104729) * 2654435761 % 251) as u8);
+        let rough = differential_box_counting_internal(noise.view(), 2, 64, 6, 1, &region, &options);
+        assert!(rough.dimension > 2.5 && rough.dimension < 3.1, "{}", rough.dimension);
+    }
 
-        let (slope, intercept, r_squared, _, _) = linear_regression(&x, &y);
+    #[test]
+    fn test_box_counting_map_shows_heterogeneity() {
+        // Filled left half, one line across the top right, empty bottom right
+        let mut image = Array2::from_elem((64, 128), false);
+        image.slice_mut(ndarray::s![.., ..64]).fill(true);
+        image.slice_mut(ndarray::s![10, 64..]).fill(true);
+
+        let map = box_counting_map_internal(&BitImage::from_array(image.view()), 32, 16, 1, 6);
+        assert_eq!(map.dim(), (3, 7));
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((map[[i, j]] - 2.0).abs() < 0.05, "{:?}", map);
+            }
+        }
+        assert!((map[[0, 5]] - 1.0).abs() < 0.05, "{:?}", map);
+        assert!(map[[2, 5]].is_nan());
+    }
 
-        assert!((slope - 2.0).abs() < 1e-10);
-        assert!((intercept - 0.0).abs() < 1e-10);
-        assert!((r_squared - 1.0).abs() < 1e-10);
+    #[test]
+    fn test_bit_image_band_query() {
+        // Pixels on both sides of a word boundary
+        let mut image = Array2::from_elem((3, 130), false);
+        image[[1, 63]] = true;
+        image[[1, 64]] = true;
+        image[[2, 129]] = true;
+        let bits = BitImage::from_array(image.view());
+
+        let all = bits.band(0..3, &(0..130));
+        assert!(any_in(&all, 0..130));
+        assert!(any_in(&all, 129..130));
+        assert!(!any_in(&all, 65..129));
+        let middle = bits.band(1..2, &(0..130));
+        assert!(any_in(&middle, 64..65));
+        assert!(any_in(&middle, 60..64));
+        assert!(!any_in(&middle, 0..63));
+        assert!(!any_in(&middle, 65..130));
+        assert!(!any_in(&bits.band(0..1, &(0..130)), 0..130));
+
+        // A band restricted to some columns only holds their words
+        assert!(!any_in(&bits.band(0..3, &(0..64)), 64..130));
     }
 }
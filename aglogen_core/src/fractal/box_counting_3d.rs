@@ -6,21 +6,59 @@
 //! Key optimizations:
 //! - Morton codes (Z-order curve) for spatial hashing
 //! - Single sort operation - O(N log N)
-//! - Bit masking for multi-scale counting - O(N) per scale
+//! - One pass over the sorted codes counts every scale at once - O(N)
 //! - Parallel processing with rayon
 //! - SIMD-friendly bit operations
+//!
+//! Voxel volumes (e.g. segmented µCT or FIB-SEM stacks) are counted by
+//! hierarchical downsampling instead: each scale ORs 2x2x2 blocks of the
+//! previous one.
+//!
+//! A single grid biases the counts by where its lines happen to fall, so
+//! point-cloud counts can be averaged over grids shifted by fractions of the
+//! box and over random orientations of the cloud.
 
+use std::collections::HashSet;
 use std::time::Instant;
 
-use numpy::{PyArray1, PyArrayMethods, PyReadonlyArray2};
+use ndarray::{Array3, ArrayView2, ArrayView3};
+use numpy::{PyArray1, PyReadonlyArray3};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use rayon::prelude::*;
 
+use crate::common::arrays::{borrow_float_array2, extract_f64_array1, extract_f64_array2, ReadonlyFloatArray2};
+use crate::common::fitting::{resolve_linear_region, FitMethod, FitOptions, LinearRegionParams};
+use crate::common::rng::{create_rng, derive_seed, random_rotation};
+use crate::common::validation::{check_coordinates, check_count, check_in_range, check_positive, check_radii};
+use crate::session::allow_threads;
+
+
+use super::box_counting::{grid_mean_std, grid_shift};
 use super::result::PyFractalResult;
 
 /// Maximum precision in bits (21 bits per dimension = 63 bits total for 3D Morton code).
 const MAX_PRECISION: u32 = 21;
 
+/// Below this many codes, masked unique counting runs serially.
+const PARALLEL_COUNT_THRESHOLD: usize = 1 << 16;
+
+/// Minimum number of adjacent pairs each rayon task scans when counting.
+const PARALLEL_COUNT_CHUNK: usize = 1 << 14;
+
+/// Octal digits (3-bit groups) of a 64-bit Morton code.
+const OCTAL_DIGITS: usize = 22;
+
+/// Morton prefix bits per dimension histogrammed to plan the chunks of
+/// [`box_counts_chunked`] (8^6 buckets, 2 MB).
+const CHUNK_HISTOGRAM_DEPTH: u32 = 6;
+
+/// Memory of one Morton code, the unit of the chunked-count budget.
+const CODE_BYTES: usize = std::mem::size_of::<u64>();
+
+/// Master seed of the random orientations of [`GridAveraging`].
+const ROTATION_SEED: u64 = 0x2074;
+
 /// Interleave bits of x into a 64-bit integer, spreading them apart for Morton code.
 /// Places bits of x at positions 0, 3, 6, 9, ... (every 3rd bit for 3D).
 #[inline]
@@ -53,6 +91,8 @@ pub struct BoxCountingResult3D {
     pub std_error: f64,
     /// 95% confidence interval.
     pub confidence_interval: (f64, f64),
+    /// Intercept of the fitted line (its slope is the dimension).
+    pub intercept: f64,
     /// Log(1/box_size) values used in fitting.
     pub log_scales: Vec<f64>,
     /// Log(box_count) values at each scale.
@@ -65,9 +105,41 @@ pub struct BoxCountingResult3D {
     pub num_points: usize,
     /// Start index of linear region (0 = all points used).
     pub linear_region_start: usize,
+    /// End index (exclusive) of the linear region.
+    pub linear_region_end: usize,
+    /// Box size and (mean) count at every counted scale, fitted or not.
+    pub box_counts: Vec<(f64, f64)>,
+    /// Standard deviation of each count across the averaged grids.
+    pub box_count_std: Vec<f64>,
+    /// Estimator of the fitted line.
+    pub fit_method: FitMethod,
+    /// Dimensions of the bootstrap resamples, empty without bootstrap.
+    pub bootstrap_dimensions: Vec<f64>,
 }
 
 impl BoxCountingResult3D {
+    /// Result for inputs too small to fit a dimension.
+    fn empty(num_points: usize) -> Self {
+        Self {
+            dimension: 0.0,
+            r_squared: 0.0,
+            std_error: f64::INFINITY,
+            confidence_interval: (0.0, 0.0),
+            intercept: 0.0,
+            log_scales: vec![],
+            log_counts: vec![],
+            residuals: vec![],
+            execution_time_ms: 0,
+            num_points,
+            linear_region_start: 0,
+            linear_region_end: 0,
+            box_counts: vec![],
+            box_count_std: vec![],
+            fit_method: FitMethod::default(),
+            bootstrap_dimensions: vec![],
+        }
+    }
+
     /// Convert to PyFractalResult for Python interop.
     pub fn to_py(&self) -> PyFractalResult {
         PyFractalResult {
@@ -75,11 +147,19 @@ impl BoxCountingResult3D {
             r_squared: self.r_squared,
             std_error: self.std_error,
             confidence_interval: self.confidence_interval,
+            slope: self.dimension,
+            intercept: self.intercept,
             log_scales_data: self.log_scales.clone(),
             log_values_data: self.log_counts.clone(),
             residuals_data: self.residuals.clone(),
             execution_time_ms: self.execution_time_ms,
             linear_region_start: self.linear_region_start,
+            linear_region_end: self.linear_region_end,
+            fit_method: self.fit_method.name().to_string(),
+            session: None,
+            box_counts_data: self.box_counts.clone(),
+            box_count_std_data: self.box_count_std.clone(),
+            bootstrap_data: self.bootstrap_dimensions.clone(),
         }
     }
 }
@@ -89,125 +169,542 @@ impl BoxCountingResult3D {
 /// # Arguments
 /// * `points` - Nx3 array of (x, y, z) coordinates
 /// * `precision` - Number of bits per dimension (higher = finer resolution)
+/// * `grids` - Grid offsets and orientations whose counts are averaged
+/// * `region` - Thresholds of the linear-region detection
+/// * `options` - Manual limits on the fitted scales and the estimator
 ///
 /// # Returns
 /// BoxCountingResult3D with fractal dimension and statistics.
 pub fn box_counting_3d_morton(
     points: &[[f64; 3]],
     precision: u32,
+    grids: &GridAveraging,
+    region: &LinearRegionParams,
+    options: &FitOptions,
 ) -> BoxCountingResult3D {
     let start_time = Instant::now();
     let n_points = points.len();
 
     if n_points < 2 {
-        return BoxCountingResult3D {
-            dimension: 0.0,
-            r_squared: 0.0,
-            std_error: f64::INFINITY,
-            confidence_interval: (0.0, 0.0),
-            log_scales: vec![],
-            log_counts: vec![],
-            residuals: vec![],
-            execution_time_ms: 0,
-            num_points: n_points,
-            linear_region_start: 0,
-        };
+        return BoxCountingResult3D::empty(n_points);
     }
 
-    let precision = precision.min(MAX_PRECISION);
+    let indices = oriented_indices(points, precision, grids.rotations);
+    let mut result = analyze_grids(&indices, &BoxQuery::default(), grids.offsets, region, options);
+    result.execution_time_ms = start_time.elapsed().as_millis() as u64;
+    result
+}
 
-    // Step 1: Find bounding box and normalize coordinates
-    let (min_coords, max_coords) = find_bounding_box(points);
-    let scale = compute_scale(&min_coords, &max_coords);
+/// Box grids whose counts are averaged at every scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridAveraging {
+    /// Grids per orientation, each shifted by a different fraction of the box.
+    pub offsets: usize,
+    /// Random orientations of the points counted besides the original one.
+    pub rotations: usize,
+}
 
-    // Step 2: Convert to Morton codes (parallel)
-    let max_val = (1u64 << precision) - 1;
-    let morton_codes: Vec<u64> = points
-        .par_iter()
-        .map(|p| {
-            let nx = normalize_coord(p[0], min_coords[0], scale, max_val);
-            let ny = normalize_coord(p[1], min_coords[1], scale, max_val);
-            let nz = normalize_coord(p[2], min_coords[2], scale, max_val);
-            morton_encode_3d(nx, ny, nz)
+impl Default for GridAveraging {
+    fn default() -> Self {
+        Self { offsets: 1, rotations: 0 }
+    }
+}
+
+/// Morton indices of `points` in their original and `rotations` random
+/// orientations, rotated about the center of the bounding box.
+///
+/// With rotations, every orientation is encoded in the same cube, whose edge
+/// is the bounding-box diagonal, so the box sizes agree across orientations.
+fn oriented_indices(points: &[[f64; 3]], precision: u32, rotations: usize) -> Vec<MortonIndex> {
+    if rotations == 0 {
+        return vec![MortonIndex::new(points, precision)];
+    }
+    let (min, max) = find_bounding_box(points.iter().copied());
+    let center = [0, 1, 2].map(|a| 0.5 * (min[a] + max[a]));
+    let diagonal = (0..3).map(|a| (max[a] - min[a]).powi(2)).sum::<f64>().sqrt();
+    let scale = if diagonal < 1e-15 { 1.0 } else { diagonal };
+    let origin = center.map(|c| c - 0.5 * scale);
+
+    (0..=rotations as u64)
+        .into_par_iter()
+        .map(|r| {
+            if r == 0 {
+                return MortonIndex::with_frame(points, precision, origin, scale);
+            }
+            let m = random_rotation(&mut create_rng(derive_seed(ROTATION_SEED, r))).to_matrix();
+            let rotated: Vec<[f64; 3]> = points
+                .iter()
+                .map(|p| {
+                    let d = [0, 1, 2].map(|a| p[a] - center[a]);
+                    [0, 1, 2].map(|i| center[i] + m[i][0] * d[0] + m[i][1] * d[1] + m[i][2] * d[2])
+                })
+                .collect();
+            MortonIndex::with_frame(&rotated, precision, origin, scale)
+        })
+        .collect()
+}
+
+/// Box counts averaged over the orientations in `indices` and `n_offsets`
+/// shifted grids each.
+///
+/// Returns `(box_size, mean count, count std)` per scale, finest first, and
+/// the number of points that took part in the count.
+fn averaged_box_counts(indices: &[MortonIndex], query: &BoxQuery, n_offsets: usize) -> (Vec<(f64, f64, f64)>, usize) {
+    let grids: Vec<(Vec<(f64, usize)>, usize)> = indices
+        .iter()
+        .flat_map(|index| {
+            (0..n_offsets).map(move |k| {
+                index.box_counts(&BoxQuery {
+                    shift: grid_shift(k),
+                    ..query.clone()
+                })
+            })
+        })
+        .collect();
+    let (first, n_points) = &grids[0];
+    let counts = first
+        .iter()
+        .enumerate()
+        .map(|(level, &(box_size, _))| {
+            let values: Vec<f64> = grids.iter().map(|(counts, _)| counts[level].1 as f64).collect();
+            let (mean, std) = grid_mean_std(&values);
+            (box_size, mean, std)
         })
         .collect();
+    (counts, *n_points)
+}
 
-    // Step 3: Sort Morton codes (this is the main O(N log N) operation)
-    let mut sorted_codes = morton_codes;
-    sorted_codes.par_sort_unstable();
-
-    // Step 4: Count boxes at each scale using bit masking
-    // Each scale corresponds to masking off the low bits
-    let mut log_scales = Vec::with_capacity(precision as usize);
-    let mut log_counts = Vec::with_capacity(precision as usize);
-
-    // Box size at level k is 2^k (in normalized units)
-    // We count unique Morton codes when masking off 3*k low bits
-    for level in 0..precision {
-        let shift = 3 * level; // 3 bits per level for 3D
-        let box_count = count_unique_masked(&sorted_codes, shift);
-
-        if box_count > 0 && box_count < sorted_codes.len() {
-            // Log(1/box_size) where box_size = scale * 2^level / max_val
-            let box_size = scale * (1u64 << level) as f64 / max_val as f64;
-            log_scales.push((1.0 / box_size).ln());
-            log_counts.push((box_count as f64).ln());
+/// Box-counting dimension of the counts averaged over the orientations in
+/// `indices` and `n_offsets` shifted grids each, as in [`MortonIndex::analyze`].
+fn analyze_grids(
+    indices: &[MortonIndex],
+    query: &BoxQuery,
+    n_offsets: usize,
+    region: &LinearRegionParams,
+    options: &FitOptions,
+) -> BoxCountingResult3D {
+    let start_time = Instant::now();
+    let (counts, n_points) = averaged_box_counts(indices, query, n_offsets);
+    if n_points < 2 {
+        return BoxCountingResult3D::empty(n_points);
+    }
+
+    // Step 4: Keep the scales where the count is informative
+    let informative = |box_count: f64| box_count > 0.0 && box_count < n_points as f64;
+    fit_log_counts(counts, informative, n_points, region, options, start_time)
+}
+
+/// Which boxes a [`MortonIndex`] query counts.
+#[derive(Debug, Clone, Default)]
+pub struct BoxQuery {
+    /// Count as if the index had been built with this many bits: the
+    /// coarsest `precision` scales, dropping the finer ones (None = the index
+    /// precision).
+    pub precision: Option<u32>,
+    /// Shift of the box grid origin, in coordinate units.
+    pub offset: [f64; 3],
+    /// Further shift of the box grid at every scale, as a fraction of the box edge.
+    pub shift: [f64; 3],
+    /// Only count points inside this `(min, max)` box (bounds inclusive).
+    pub region: Option<([f64; 3], [f64; 3])>,
+}
+
+/// Point cloud encoded into sorted Morton codes once, for repeated box counts.
+///
+/// Encoding and sorting is the O(N log N) part of box counting; every query
+/// afterwards (another precision, a sub-region) is a single O(N) pass over
+/// the codes, and a shifted grid O(N) per scale. Coarser precisions reuse
+/// the top bits of the codes.
+pub struct MortonIndex {
+    /// Morton codes in ascending order.
+    codes: Vec<u64>,
+    /// Integer grid coordinates, parallel to `codes`.
+    cells: Vec<[u64; 3]>,
+    min_coords: [f64; 3],
+    scale: f64,
+    precision: u32,
+}
+
+impl MortonIndex {
+    /// Encode and sort `points` on a grid of `precision` bits per dimension.
+    pub fn new(points: &[[f64; 3]], precision: u32) -> Self {
+        // Step 1: Find bounding box and normalize coordinates
+        let (min_coords, max_coords) = find_bounding_box(points.iter().copied());
+        let scale = compute_scale(&min_coords, &max_coords);
+        Self::with_frame(points, precision, min_coords, scale)
+    }
+
+    /// Encode and sort `points` on the grid spanning `scale` from `min_coords` along every axis.
+    fn with_frame(points: &[[f64; 3]], precision: u32, min_coords: [f64; 3], scale: f64) -> Self {
+        let precision = precision.min(MAX_PRECISION);
+
+        // Step 2: Convert to Morton codes (parallel)
+        let max_val = (1u64 << precision) - 1;
+        let mut entries: Vec<(u64, [u64; 3])> = points
+            .par_iter()
+            .map(|p| {
+                let nx = normalize_coord(p[0], min_coords[0], scale, max_val);
+                let ny = normalize_coord(p[1], min_coords[1], scale, max_val);
+                let nz = normalize_coord(p[2], min_coords[2], scale, max_val);
+                (morton_encode_3d(nx, ny, nz), [nx, ny, nz])
+            })
+            .collect();
+
+        // Step 3: Sort Morton codes (this is the main O(N log N) operation)
+        entries.par_sort_unstable_by_key(|&(code, _)| code);
+        let (codes, cells) = entries.into_iter().unzip();
+
+        Self {
+            codes,
+            cells,
+            min_coords,
+            scale,
+            precision,
         }
     }
 
+    /// Number of indexed points.
+    pub fn num_points(&self) -> usize {
+        self.codes.len()
+    }
+
+    /// Bits per dimension of the finest grid.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    fn max_val(&self) -> u64 {
+        (1u64 << self.precision) - 1
+    }
+
+    /// Map a coordinate along `axis` onto the (unclamped) integer grid.
+    fn to_grid(&self, value: f64, axis: usize) -> f64 {
+        (value - self.min_coords[axis]) / self.scale * self.max_val() as f64
+    }
+
+    /// Count occupied boxes at each scale of `query`, finest first.
+    ///
+    /// Returns `(box_size, count)` pairs with box sizes in coordinate units,
+    /// and the number of points that took part in the count.
+    pub fn box_counts(&self, query: &BoxQuery) -> (Vec<(f64, usize)>, usize) {
+        let n_levels = query.precision.unwrap_or(self.precision).min(self.precision);
+        let first_level = self.precision - n_levels;
+
+        // Restricting to a region keeps the Morton order of the survivors
+        let selected: Option<Vec<usize>> = query.region.map(|(lo, hi)| {
+            let lo = [0, 1, 2].map(|a| self.to_grid(lo[a], a));
+            let hi = [0, 1, 2].map(|a| self.to_grid(hi[a], a));
+            (0..self.num_points())
+                .filter(|&i| (0..3).all(|a| (lo[a]..=hi[a]).contains(&(self.cells[i][a] as f64))))
+                .collect()
+        });
+        let n_selected = selected.as_ref().map_or(self.num_points(), Vec::len);
+        let selected_codes: Option<Vec<u64>> =
+            selected.as_ref().map(|sel| sel.iter().map(|&i| self.codes[i]).collect());
+        let codes = selected_codes.as_deref().unwrap_or(&self.codes);
+
+        // Every unshifted scale comes from one pass over the codes
+        let mut aligned: Option<Vec<usize>> = None;
+        let base_offset = [0, 1, 2].map(|a| (query.offset[a] / self.scale * self.max_val() as f64).round() as i64);
+        let counts = (first_level..self.precision)
+            .map(|level| {
+                let box_size = self.scale * (1u64 << level) as f64 / self.max_val() as f64;
+                let offset = [0, 1, 2]
+                    .map(|a| base_offset[a] + (query.shift[a] * (1u64 << level) as f64).round() as i64);
+                let count = if offset == [0; 3] {
+                    aligned.get_or_insert_with(|| count_unique_levels(codes, self.precision))[level as usize]
+                } else {
+                    // A shifted grid breaks the Morton order; hash the shifted boxes instead
+                    let box_of = |i: usize| {
+                        let c = self.cells[i];
+                        [0, 1, 2].map(|a| (c[a] as i64 + offset[a]) >> level)
+                    };
+                    let boxes: HashSet<[i64; 3]> = match &selected {
+                        None => (0..self.num_points()).map(box_of).collect(),
+                        Some(sel) => sel.iter().map(|&i| box_of(i)).collect(),
+                    };
+                    boxes.len()
+                };
+                (box_size, count)
+            })
+            .collect();
+
+        (counts, n_selected)
+    }
+
+    /// Box-counting dimension for `query`, fitted on the detected linear
+    /// region with the limits and estimator of `options`.
+    ///
+    /// The counts of `n_offsets` grids, each shifted by a different fraction
+    /// of the box, are averaged per scale before fitting. Bootstrap resamples
+    /// redraw the fitted scales, as in the 2D box counting.
+    pub fn analyze(
+        &self,
+        query: &BoxQuery,
+        n_offsets: usize,
+        region: &LinearRegionParams,
+        options: &FitOptions,
+    ) -> BoxCountingResult3D {
+        analyze_grids(std::slice::from_ref(self), query, n_offsets, region, options)
+    }
+}
+
+/// Fit the box-counting dimension to ln N against ln(1/box size), over the
+/// `(box size, count, count std)` triples whose count is `informative`.
+fn fit_log_counts(
+    box_counts: Vec<(f64, f64, f64)>,
+    informative: impl Fn(f64) -> bool,
+    num_points: usize,
+    region: &LinearRegionParams,
+    options: &FitOptions,
+    start_time: Instant,
+) -> BoxCountingResult3D {
+    let fitted: Vec<(f64, f64)> = box_counts
+        .iter()
+        .filter(|c| informative(c.1))
+        .map(|&(box_size, count, _)| (box_size, count))
+        .collect();
+    let sizes: Vec<f64> = fitted.iter().map(|c| c.0).collect();
+    let log_scales: Vec<f64> = sizes.iter().map(|s| (1.0 / s).ln()).collect();
+    let log_counts: Vec<f64> = fitted.iter().map(|c| c.1.ln()).collect();
+
     // Step 5: Robust linear regression to find fractal dimension
     // Automatically detects linear region by excluding outliers from small scales
-    let (linear_start, slope, _intercept, r_squared, std_error, residuals) =
-        linear_regression_robust(&log_scales, &log_counts);
+    let linear = options.fit(&sizes, &log_scales, &log_counts, region);
+    let bootstrap_dimensions = options.bootstrap_slopes(&log_scales, &log_counts, &linear);
 
-    let dimension = slope;
+    let dimension = linear.fit.slope;
+    let std_error = linear.fit.std_error;
     let ci_half = 1.96 * std_error;
     let confidence_interval = (dimension - ci_half, dimension + ci_half);
 
-    let execution_time_ms = start_time.elapsed().as_millis() as u64;
-
     BoxCountingResult3D {
         dimension,
-        r_squared,
+        r_squared: linear.fit.r_squared,
         std_error,
         confidence_interval,
+        intercept: linear.fit.intercept,
         log_scales,
         log_counts,
-        residuals,
-        execution_time_ms,
-        num_points: n_points,
-        linear_region_start: linear_start,
+        residuals: linear.residuals,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+        num_points,
+        linear_region_start: linear.start,
+        linear_region_end: linear.end,
+        box_counts: box_counts.iter().map(|&(box_size, count, _)| (box_size, count)).collect(),
+        box_count_std: box_counts.iter().map(|c| c.2).collect(),
+        fit_method: options.method,
+        bootstrap_dimensions,
     }
 }
 
-/// Count unique values after masking off `shift` low bits.
-/// Takes advantage of sorted array for O(N) counting.
-#[inline]
-fn count_unique_masked(sorted: &[u64], shift: u32) -> usize {
-    if sorted.is_empty() {
-        return 0;
+/// Occupied boxes of a voxel volume at box edges of 1, 2, 4, ... voxels.
+///
+/// Returns `(box_edge, count)` pairs, finest first, down to the single box
+/// covering the whole volume. Each level ORs the 2x2x2 blocks of the one
+/// before (odd edges are padded with empty voxels), so the whole pyramid
+/// costs about 8/7 of a pass over the volume. The volume itself is only
+/// read; the first coarser level is the largest allocation.
+pub fn voxel_box_counts(volume: ArrayView3<'_, bool>) -> Vec<(usize, usize)> {
+    let mut counts = vec![(1, volume.iter().filter(|&&v| v).count())];
+    let mut coarser: Option<Array3<bool>> = None;
+    let mut edge = 1;
+    loop {
+        let level = coarser.as_ref().map_or(volume.view(), Array3::view);
+        if level.dim() == (1, 1, 1) {
+            break;
+        }
+        let (nx, ny, nz) = level.dim();
+        let dim = (nx.div_ceil(2), ny.div_ceil(2), nz.div_ceil(2));
+        let plane = dim.1 * dim.2;
+        let occupied: Vec<bool> = (0..dim.0 * plane)
+            .into_par_iter()
+            .map(|index| {
+                let (i, j, k) = (index / plane, index % plane / dim.2, index % dim.2);
+                (2 * i..(2 * i + 2).min(nx)).any(|x| {
+                    (2 * j..(2 * j + 2).min(ny)).any(|y| (2 * k..(2 * k + 2).min(nz)).any(|z| level[[x, y, z]]))
+                })
+            })
+            .collect();
+        edge *= 2;
+        counts.push((edge, occupied.iter().filter(|&&v| v).count()));
+        coarser = Some(Array3::from_shape_vec(dim, occupied).expect("one value per box"));
     }
-    if shift >= 64 {
-        return 1; // All values mask to 0
+    counts
+}
+
+/// Box-counting dimension of a voxel volume with voxels of edge `voxel_size`.
+///
+/// The fit uses every scale with more than one occupied box.
+pub fn box_counting_voxels_internal(
+    volume: ArrayView3<'_, bool>,
+    voxel_size: f64,
+    region: &LinearRegionParams,
+    options: &FitOptions,
+) -> BoxCountingResult3D {
+    let start_time = Instant::now();
+    let counts = voxel_box_counts(volume);
+    let n_voxels = counts[0].1;
+    if n_voxels < 2 {
+        return BoxCountingResult3D::empty(n_voxels);
     }
 
-    let mask = !((1u64 << shift) - 1);
-    let mut count = 1;
-    let mut prev = sorted[0] & mask;
+    let box_counts = counts
+        .into_iter()
+        .map(|(edge, count)| (edge as f64 * voxel_size, count as f64, 0.0))
+        .collect();
+    fit_log_counts(box_counts, |count| count > 1.0, n_voxels, region, options, start_time)
+}
 
-    for &code in sorted.iter().skip(1) {
-        let masked = code & mask;
-        if masked != prev {
-            count += 1;
-            prev = masked;
+/// Occupied boxes at every scale of a [`MortonIndex`] of the same points,
+/// holding at most about `max_chunk_points` Morton codes at a time.
+///
+/// A first pass histograms the coarse Morton prefixes of the points. Runs of
+/// consecutive prefixes, contiguous in Morton order, are grouped into chunks
+/// of at most `max_chunk_points` points (a single prefix holding more is a
+/// chunk of its own), and each chunk is encoded, sorted and counted in turn.
+/// Boxes finer than a prefix never straddle two chunks, so their counts add
+/// up; coarser ones are counted on the histogram itself.
+///
+/// The (N, 3) `points` are read in place on every pass, in any float dtype
+/// and layout, so the Morton codes of one chunk are all that is allocated.
+///
+/// Returns `(box_size, count)` pairs with box sizes in coordinate units,
+/// finest first, equal to `MortonIndex::new(points, precision).box_counts`.
+pub fn box_counts_chunked<T>(points: ArrayView2<'_, T>, precision: u32, max_chunk_points: usize) -> Vec<(f64, usize)>
+where
+    T: Copy + Into<f64> + Sync,
+{
+    let precision = precision.min(MAX_PRECISION);
+    let point = |i: usize| -> [f64; 3] { [0, 1, 2].map(|a| points[[i, a]].into()) };
+    let (min_coords, max_coords) = find_bounding_box((0..points.nrows()).map(point));
+    let scale = compute_scale(&min_coords, &max_coords);
+    let max_val = (1u64 << precision) - 1;
+    let encode = |i: usize| {
+        let p = point(i);
+        let [nx, ny, nz] = [0, 1, 2].map(|a| normalize_coord(p[a], min_coords[a], scale, max_val));
+        morton_encode_3d(nx, ny, nz)
+    };
+
+    // Step 1: Histogram of the coarse prefixes
+    let depth = precision.min(CHUNK_HISTOGRAM_DEPTH);
+    let prefix_shift = 3 * (precision - depth);
+    let histogram = (0..points.nrows())
+        .into_par_iter()
+        .fold(
+            || vec![0usize; 1 << (3 * depth)],
+            |mut histogram, i| {
+                histogram[(encode(i) >> prefix_shift) as usize] += 1;
+                histogram
+            },
+        )
+        .reduce_with(|mut a, b| {
+            a.iter_mut().zip(&b).for_each(|(x, y)| *x += y);
+            a
+        })
+        .unwrap_or_default();
+
+    // Step 2: Group consecutive prefixes into chunks within the budget
+    let mut chunks: Vec<(u64, u64)> = Vec::new();
+    let (mut start, mut filled) = (0, 0);
+    for (prefix, &count) in histogram.iter().enumerate() {
+        if filled > 0 && filled + count > max_chunk_points {
+            chunks.push((start, prefix as u64));
+            (start, filled) = (prefix as u64, 0);
         }
+        filled += count;
+    }
+    chunks.push((start, histogram.len() as u64));
+
+    // Step 3: Sort and count every chunk at the scales finer than a prefix
+    let fine_levels = precision - depth;
+    let mut counts = vec![0usize; precision as usize];
+    for &(lo, hi) in &chunks {
+        let mut codes: Vec<u64> = (0..points.nrows())
+            .into_par_iter()
+            .map(encode)
+            .filter(|code| (lo..hi).contains(&(code >> prefix_shift)))
+            .collect();
+        codes.par_sort_unstable();
+        let chunk_counts = count_unique_levels(&codes, fine_levels);
+        counts.iter_mut().zip(chunk_counts).for_each(|(count, c)| *count += c);
+    }
+
+    // Step 4: Coarser scales from the occupied prefixes
+    let occupied: Vec<u64> = (0..histogram.len() as u64).filter(|&p| histogram[p as usize] > 0).collect();
+    counts[fine_levels as usize..].copy_from_slice(&count_unique_levels(&occupied, depth));
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(level, count)| (scale * (1u64 << level) as f64 / max_val as f64, count))
+        .collect()
+}
+
+/// Box-counting dimension of a point cloud counted by [`box_counts_chunked`]
+/// within about `max_memory_mb` of Morton codes.
+pub fn box_counting_3d_chunked<T>(
+    points: ArrayView2<'_, T>,
+    precision: u32,
+    max_memory_mb: usize,
+    region: &LinearRegionParams,
+    options: &FitOptions,
+) -> BoxCountingResult3D
+where
+    T: Copy + Into<f64> + Sync,
+{
+    let start_time = Instant::now();
+    let n_points = points.nrows();
+    if n_points < 2 {
+        return BoxCountingResult3D::empty(n_points);
+    }
+
+    let max_chunk_points = max_memory_mb.saturating_mul(1 << 20) / CODE_BYTES;
+    let box_counts = box_counts_chunked(points, precision, max_chunk_points)
+        .into_iter()
+        .map(|(box_size, count)| (box_size, count as f64, 0.0))
+        .collect();
+    let informative = |box_count: f64| box_count > 0.0 && box_count < n_points as f64;
+    fit_log_counts(box_counts, informative, n_points, region, options, start_time)
+}
+
+/// Count unique values of sorted Morton codes after masking off the low
+/// `3 * level` bits, for every level below `levels`, in a single pass.
+///
+/// Two adjacent codes fall in different boxes at every level up to the
+/// highest octal digit in which they differ, so the pairs are tallied by
+/// that digit and the count of a level is one plus the pairs tallied at or
+/// above it. Large arrays are scanned in parallel chunks: each pair is
+/// independent and pairs straddling chunk boundaries are counted too.
+fn count_unique_levels(sorted: &[u64], levels: u32) -> Vec<usize> {
+    if sorted.is_empty() {
+        return vec![0; levels as usize];
     }
 
-    count
+    let tally = |mut digits: [usize; OCTAL_DIGITS], w: &[u64]| {
+        let diff = w[0] ^ w[1];
+        if diff != 0 {
+            digits[((63 - diff.leading_zeros()) / 3) as usize] += 1;
+        }
+        digits
+    };
+    let digits = if sorted.len() < PARALLEL_COUNT_THRESHOLD {
+        sorted.windows(2).fold([0; OCTAL_DIGITS], tally)
+    } else {
+        sorted
+            .par_windows(2)
+            .with_min_len(PARALLEL_COUNT_CHUNK)
+            .fold(|| [0; OCTAL_DIGITS], tally)
+            .reduce(|| [0; OCTAL_DIGITS], |a, b| std::array::from_fn(|i| a[i] + b[i]))
+    };
+
+    (0..levels as usize)
+        .map(|level| 1 + digits.get(level..).map_or(0, |above| above.iter().sum()))
+        .collect()
 }
 
 /// Find bounding box of points.
-fn find_bounding_box(points: &[[f64; 3]]) -> ([f64; 3], [f64; 3]) {
+fn find_bounding_box(points: impl Iterator<Item = [f64; 3]>) -> ([f64; 3], [f64; 3]) {
     let mut min = [f64::INFINITY; 3];
     let mut max = [f64::NEG_INFINITY; 3];
 
@@ -246,127 +743,6 @@ fn normalize_coord(val: f64, min: f64, scale: f64, max_val: u64) -> u64 {
     (clamped * max_val as f64).round() as u64
 }
 
-/// Linear regression returning (slope, intercept, r_squared, std_error, residuals).
-fn linear_regression(x: &[f64], y: &[f64]) -> (f64, f64, f64, f64, Vec<f64>) {
-    let n = x.len() as f64;
-    if n < 2.0 {
-        return (0.0, 0.0, 0.0, f64::INFINITY, vec![]);
-    }
-
-    let sum_x: f64 = x.iter().sum();
-    let sum_y: f64 = y.iter().sum();
-    let sum_xx: f64 = x.iter().map(|xi| xi * xi).sum();
-    let sum_xy: f64 = x.iter().zip(y.iter()).map(|(xi, yi)| xi * yi).sum();
-
-    let denom = n * sum_xx - sum_x * sum_x;
-    if denom.abs() < 1e-15 {
-        return (0.0, sum_y / n, 0.0, f64::INFINITY, vec![0.0; x.len()]);
-    }
-
-    let slope = (n * sum_xy - sum_x * sum_y) / denom;
-    let intercept = (sum_y - slope * sum_x) / n;
-
-    let mean_y = sum_y / n;
-    let mut ss_res = 0.0;
-    let mut ss_tot = 0.0;
-    let mut residuals = Vec::with_capacity(x.len());
-
-    for (xi, yi) in x.iter().zip(y.iter()) {
-        let y_pred = intercept + slope * xi;
-        let res = yi - y_pred;
-        residuals.push(res);
-        ss_res += res * res;
-        ss_tot += (yi - mean_y) * (yi - mean_y);
-    }
-
-    let r_squared = if ss_tot > 1e-15 { 1.0 - ss_res / ss_tot } else { 0.0 };
-    let mse = ss_res / (n - 2.0).max(1.0);
-    let std_error = (mse / (sum_xx - sum_x * sum_x / n).abs().max(1e-15)).sqrt();
-
-    (slope, intercept, r_squared, std_error, residuals)
-}
-
-/// Robust linear regression with automatic detection of linear region.
-///
-/// Starts from the right (large scales) and progressively adds points from the left,
-/// stopping when the residual of the new point exceeds a threshold or R² drops significantly.
-///
-/// Returns: (start_index, slope, intercept, r_squared, std_error, residuals_for_all_points)
-fn linear_regression_robust(x: &[f64], y: &[f64]) -> (usize, f64, f64, f64, f64, Vec<f64>) {
-    let n = x.len();
-    if n < 3 {
-        let (slope, intercept, r2, se, res) = linear_regression(x, y);
-        return (0, slope, intercept, r2, se, res);
-    }
-
-    // Parameters for outlier detection
-    let min_points = 4.min(n);  // Minimum points for regression
-    let residual_threshold = 2.0;  // Standardized residual threshold
-    let r2_drop_threshold = 0.02;  // Max allowed R² drop when adding a point
-
-    // Start with rightmost points (large scales - more reliable)
-    let mut best_start = 0;
-    let mut best_r2 = 0.0;
-
-    // Initial regression with last min_points
-    let start_idx = n - min_points;
-    let (mut slope, mut intercept, mut r2, mut std_error, _) =
-        linear_regression(&x[start_idx..], &y[start_idx..]);
-
-    best_start = start_idx;
-    best_r2 = r2;
-
-    // Progressively add points from the left
-    for i in (0..start_idx).rev() {
-        // Compute predicted value for new point using current regression
-        let y_pred = intercept + slope * x[i];
-        let residual = y[i] - y_pred;
-
-        // Compute standardized residual
-        let std_residual = if std_error > 1e-15 {
-            residual.abs() / std_error
-        } else {
-            0.0
-        };
-
-        // Try adding this point
-        let (new_slope, new_intercept, new_r2, new_se, _) =
-            linear_regression(&x[i..], &y[i..]);
-
-        // Check if this point is an outlier
-        let r2_drop = best_r2 - new_r2;
-
-        if std_residual > residual_threshold && r2_drop > r2_drop_threshold {
-            // This point is an outlier - stop here
-            break;
-        }
-
-        // Accept this point
-        slope = new_slope;
-        intercept = new_intercept;
-        std_error = new_se;
-
-        if new_r2 > best_r2 - 0.01 {
-            // Only update best if R² is close to or better than before
-            best_start = i;
-            best_r2 = new_r2.max(best_r2);
-        }
-
-        r2 = new_r2;
-    }
-
-    // Final regression on selected range
-    let (final_slope, final_intercept, final_r2, final_se, _) =
-        linear_regression(&x[best_start..], &y[best_start..]);
-
-    // Compute residuals for ALL points (for visualization)
-    let residuals: Vec<f64> = x.iter().zip(y.iter())
-        .map(|(xi, yi)| yi - (final_intercept + final_slope * xi))
-        .collect();
-
-    (best_start, final_slope, final_intercept, final_r2, final_se, residuals)
-}
-
 // ============================================================================
 // Python bindings
 // ============================================================================
@@ -376,38 +752,104 @@ fn linear_regression_robust(x: &[f64], y: &[f64]) -> (usize, f64, f64, f64, f64,
 /// Uses Morton codes (Z-order curve) for O(N log N) complexity.
 ///
 /// # Arguments
-/// * `coordinates` - Nx3 array of (x, y, z) coordinates
+/// * `coordinates` - Nx3 array of (x, y, z) coordinates (float32 or float64, any layout)
 /// * `precision` - Bits per dimension (default: 18, max: 21)
+/// * `linear_region` - `LinearRegionParams` tuning the linear-region detection (default thresholds when None)
+/// * `min_scale` - Smallest box size fitted, in coordinate units (default: all)
+/// * `max_scale` - Largest box size fitted, in coordinate units (default: all)
+/// * `fit_range` - Indices `(i0, i1)` of the plot points fitted, replacing the
+///   linear-region detection (default: detected)
+/// * `fit_method` - "ols", "theil_sen", "ransac" or "wls" (weighted by the box
+///   counts) (default: "ols")
+/// * `bootstrap` - Bootstrap resamples of the fitted scales (default: 0, none)
+/// * `n_offsets` - Box grids per orientation, each shifted by a different
+///   fraction of the box, whose counts are averaged (default: 1)
+/// * `n_rotations` - Random orientations of the points counted besides the
+///   original one (default: 0)
+/// * `max_memory_mb` - Bound on the memory of the Morton codes: the points are
+///   counted in chunks of Morton order, one pass over the caller's array per
+///   chunk, without copying it, for clouds too large to sort at once. Does not combine with
+///   `bootstrap`, `n_offsets` or `n_rotations` (default: None, one sort)
 ///
 /// # Returns
-/// FractalResult with dimension estimate and statistics.
+/// FractalResult with dimension estimate, statistics and the box counts of every scale.
 #[pyfunction]
-#[pyo3(signature = (coordinates, precision=18))]
+#[pyo3(signature = (coordinates, precision=18, linear_region=None, min_scale=None, max_scale=None, fit_range=None, fit_method="ols", bootstrap=0, n_offsets=1, n_rotations=0, max_memory_mb=None))]
 pub fn box_counting_3d(
     py: Python<'_>,
-    coordinates: PyReadonlyArray2<'_, f64>,
+    coordinates: &Bound<'_, PyAny>,
     precision: u32,
+    linear_region: Option<LinearRegionParams>,
+    min_scale: Option<f64>,
+    max_scale: Option<f64>,
+    fit_range: Option<(usize, usize)>,
+    fit_method: &str,
+    bootstrap: usize,
+    n_offsets: usize,
+    n_rotations: usize,
+    max_memory_mb: Option<usize>,
 ) -> PyResult<PyFractalResult> {
-    let coords = coordinates.as_array();
-    let n = coords.shape()[0];
-
-    if coords.shape()[1] != 3 {
-        return Err(pyo3::exceptions::PyValueError::new_err(
-            "Coordinates must be Nx3 array",
-        ));
+    let coords = borrow_float_array2(coordinates, "coordinates")?;
+    match &coords {
+        ReadonlyFloatArray2::F64(a) => check_coordinates("coordinates", &a.as_array())?,
+        ReadonlyFloatArray2::F32(a) => check_coordinates("coordinates", &a.as_array())?,
+    }
+    check_in_range("precision", precision, 2, MAX_PRECISION)?;
+    let region = resolve_linear_region(linear_region)?;
+    let options = FitOptions::new(min_scale, max_scale, fit_range, fit_method, bootstrap)?;
+    check_count("n_offsets", n_offsets, 1)?;
+    let grids = GridAveraging {
+        offsets: n_offsets,
+        rotations: n_rotations,
+    };
+    if let Some(mb) = max_memory_mb {
+        check_count("max_memory_mb", mb, 1)?;
+        if bootstrap > 0 || grids != GridAveraging::default() {
+            return Err(PyValueError::new_err(
+                "max_memory_mb does not combine with bootstrap, n_offsets or n_rotations",
+            ));
+        }
     }
-
-    // Convert to Vec<[f64; 3]>
-    let points: Vec<[f64; 3]> = (0..n)
-        .map(|i| [coords[[i, 0]], coords[[i, 1]], coords[[i, 2]]])
-        .collect();
 
     // Release GIL during computation
-    let result = py.allow_threads(|| box_counting_3d_morton(&points, precision));
+    let result = match &coords {
+        ReadonlyFloatArray2::F64(a) => {
+            count_points(py, a.as_array(), precision, max_memory_mb, &grids, &region, &options)?
+        }
+        ReadonlyFloatArray2::F32(a) => {
+            count_points(py, a.as_array(), precision, max_memory_mb, &grids, &region, &options)?
+        }
+    };
 
     Ok(result.to_py())
 }
 
+/// Box counting of [`box_counting_3d`] on the borrowed coordinates: chunked in
+/// place under `max_memory_mb`, otherwise on one copy of the points.
+fn count_points<T>(
+    py: Python<'_>,
+    coords: ArrayView2<'_, T>,
+    precision: u32,
+    max_memory_mb: Option<usize>,
+    grids: &GridAveraging,
+    region: &LinearRegionParams,
+    options: &FitOptions,
+) -> PyResult<BoxCountingResult3D>
+where
+    T: Copy + Into<f64> + Sync,
+{
+    check_count("number of coordinates", coords.nrows(), 1)?;
+    let result = allow_threads(py, || match max_memory_mb {
+        Some(mb) => box_counting_3d_chunked(coords, precision, mb, region, options),
+        None => {
+            let points: Vec<[f64; 3]> = coords.outer_iter().map(|p| [p[0].into(), p[1].into(), p[2].into()]).collect();
+            box_counting_3d_morton(&points, precision, grids, region, options)
+        }
+    });
+    options.check_fitted(result.linear_region_start, result.linear_region_end)?;
+    Ok(result)
+}
+
 /// Run box-counting on an agglomerate defined by sphere centers and radii.
 ///
 /// Generates surface points for each sphere and runs 3D box-counting.
@@ -416,31 +858,53 @@ pub fn box_counting_3d(
 /// * `centers` - Nx3 array of sphere center coordinates
 /// * `radii` - N-element array of sphere radii
 /// * `points_per_sphere` - Number of surface points per sphere (default: 100)
-/// * `precision` - Bits per dimension (default: 18)
+/// * `precision` - Bits per dimension (default: 18, max: 21)
+/// * `linear_region` - `LinearRegionParams` tuning the linear-region detection (default thresholds when None)
+/// * `min_scale` - Smallest box size fitted, in coordinate units (default: all)
+/// * `max_scale` - Largest box size fitted, in coordinate units (default: all)
+/// * `fit_range` - Indices `(i0, i1)` of the plot points fitted, replacing the
+///   linear-region detection (default: detected)
+/// * `fit_method` - "ols", "theil_sen", "ransac" or "wls" (weighted by the box
+///   counts) (default: "ols")
+/// * `bootstrap` - Bootstrap resamples of the fitted scales (default: 0, none)
+/// * `n_offsets` - Box grids per orientation, each shifted by a different
+///   fraction of the box, whose counts are averaged (default: 1)
+/// * `n_rotations` - Random orientations of the agglomerate counted besides
+///   the original one (default: 0)
 #[pyfunction]
-#[pyo3(signature = (centers, radii, points_per_sphere=100, precision=18))]
+#[pyo3(signature = (centers, radii, points_per_sphere=100, precision=18, linear_region=None, min_scale=None, max_scale=None, fit_range=None, fit_method="ols", bootstrap=0, n_offsets=1, n_rotations=0))]
 pub fn box_counting_agglomerate(
     py: Python<'_>,
-    centers: PyReadonlyArray2<'_, f64>,
-    radii: &Bound<'_, PyArray1<f64>>,
+    centers: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
     points_per_sphere: usize,
     precision: u32,
+    linear_region: Option<LinearRegionParams>,
+    min_scale: Option<f64>,
+    max_scale: Option<f64>,
+    fit_range: Option<(usize, usize)>,
+    fit_method: &str,
+    bootstrap: usize,
+    n_offsets: usize,
+    n_rotations: usize,
 ) -> PyResult<PyFractalResult> {
-    let centers_arr = centers.as_array();
-    let radii_arr = radii.try_readonly()?;
-    let radii_slice = radii_arr.as_slice()?;
+    let centers_arr = extract_f64_array2(centers, "centers")?;
+    let radii_arr = extract_f64_array1(radii, "radii")?;
+    let radii_slice = radii_arr.as_slice().expect("owned array is contiguous");
     let n_spheres = centers_arr.shape()[0];
 
-    if centers_arr.shape()[1] != 3 {
-        return Err(pyo3::exceptions::PyValueError::new_err(
-            "Centers must be Nx3 array",
-        ));
-    }
-    if radii_slice.len() != n_spheres {
-        return Err(pyo3::exceptions::PyValueError::new_err(
-            "Radii length must match number of centers",
-        ));
-    }
+    check_coordinates("centers", &centers_arr)?;
+    check_count("number of centers", n_spheres, 1)?;
+    check_radii(radii_slice, n_spheres)?;
+    check_count("points_per_sphere", points_per_sphere, 1)?;
+    check_in_range("precision", precision, 2, MAX_PRECISION)?;
+    let region = resolve_linear_region(linear_region)?;
+    let options = FitOptions::new(min_scale, max_scale, fit_range, fit_method, bootstrap)?;
+    check_count("n_offsets", n_offsets, 1)?;
+    let grids = GridAveraging {
+        offsets: n_offsets,
+        rotations: n_rotations,
+    };
 
     // Generate sphere surface points
     let points: Vec<[f64; 3]> = (0..n_spheres)
@@ -454,14 +918,216 @@ pub fn box_counting_agglomerate(
         .collect();
 
     // Release GIL during computation
-    let result = py.allow_threads(|| box_counting_3d_morton(&points, precision));
+    let result = allow_threads(py, || box_counting_3d_morton(&points, precision, &grids, &region, &options));
+    options.check_fitted(result.linear_region_start, result.linear_region_end)?;
 
     Ok(result.to_py())
 }
 
+/// Run 3D box-counting on a voxel volume.
+///
+/// Works directly on a boolean 3D array, e.g. a segmented µCT or FIB-SEM
+/// stack or a voxelized agglomerate, so measured volumes get the same
+/// analysis and result type as point clouds. Box edges run from one voxel
+/// up to the whole volume by powers of two.
+///
+/// # Arguments
+/// * `volume` - 3D boolean numpy array, true on the solid voxels
+/// * `voxel_size` - Voxel edge length, setting the units of `log_scales` (default: 1)
+/// * `linear_region` - `LinearRegionParams` tuning the linear-region detection (default thresholds when None)
+/// * `min_scale` - Smallest box size fitted, in the units of `voxel_size` (default: all)
+/// * `max_scale` - Largest box size fitted, in the units of `voxel_size` (default: all)
+/// * `fit_range` - Indices `(i0, i1)` of the plot points fitted, replacing the
+///   linear-region detection (default: detected)
+/// * `fit_method` - "ols", "theil_sen", "ransac" or "wls" (weighted by the box
+///   counts) (default: "ols")
+/// * `bootstrap` - Bootstrap resamples of the fitted scales (default: 0, none)
+///
+/// # Returns
+/// FractalResult with dimension estimate, statistics and the box counts of every scale.
+#[pyfunction]
+#[pyo3(signature = (volume, voxel_size=1.0, linear_region=None, min_scale=None, max_scale=None, fit_range=None, fit_method="ols", bootstrap=0))]
+pub fn box_counting_voxels(
+    py: Python<'_>,
+    volume: PyReadonlyArray3<'_, bool>,
+    voxel_size: f64,
+    linear_region: Option<LinearRegionParams>,
+    min_scale: Option<f64>,
+    max_scale: Option<f64>,
+    fit_range: Option<(usize, usize)>,
+    fit_method: &str,
+    bootstrap: usize,
+) -> PyResult<PyFractalResult> {
+    check_positive("voxel_size", voxel_size)?;
+    let region = resolve_linear_region(linear_region)?;
+    let options = FitOptions::new(min_scale, max_scale, fit_range, fit_method, bootstrap)?;
+    let volume = volume.as_array();
+    let occupied = volume.iter().filter(|&&v| v).count();
+    check_count("number of solid voxels", occupied, 2)?;
+
+    let result = py.allow_threads(|| box_counting_voxels_internal(volume, voxel_size, &region, &options));
+    options.check_fitted(result.linear_region_start, result.linear_region_end)?;
+
+    Ok(result.to_py())
+}
+
+/// `(box_sizes, counts)` arrays returned by `MortonIndex.box_counts`.
+type BoxCountArrays<'py> = (Bound<'py, PyArray1<f64>>, Bound<'py, PyArray1<u64>>);
+
+/// Reusable Morton-sorted point cloud for repeated 3D box counting.
+///
+/// Encodes and sorts the points once; `box_counting` can then be called for
+/// several precisions, grid offsets or sub-regions without redoing the sort.
+///
+/// ```python
+/// index = MortonIndex(coords, precision=18)
+/// for p in range(8, 19):
+///     print(p, index.box_counting(precision=p).dimension)
+/// ```
+#[pyclass(name = "MortonIndex")]
+pub struct PyMortonIndex {
+    index: MortonIndex,
+}
+
+impl PyMortonIndex {
+    /// Build the query for the Python arguments, validating them against the index.
+    fn query(
+        &self,
+        precision: Option<u32>,
+        offset: Option<[f64; 3]>,
+        region_min: Option<[f64; 3]>,
+        region_max: Option<[f64; 3]>,
+    ) -> PyResult<BoxQuery> {
+        if let Some(p) = precision {
+            check_in_range("precision", p, 2, self.index.precision())?;
+        }
+        let offset = offset.unwrap_or_default();
+        if let Some(i) = offset.iter().position(|v| !v.is_finite()) {
+            return Err(PyValueError::new_err(format!(
+                "offset must be finite, offset[{}] is {}",
+                i, offset[i]
+            )));
+        }
+        let region = match (region_min, region_max) {
+            (None, None) => None,
+            (Some(lo), Some(hi)) => {
+                if (0..3).any(|a| !lo[a].is_finite() || !hi[a].is_finite() || lo[a] > hi[a]) {
+                    return Err(PyValueError::new_err(format!(
+                        "region_min {:?} and region_max {:?} must be finite with min <= max",
+                        lo, hi
+                    )));
+                }
+                Some((lo, hi))
+            }
+            _ => {
+                return Err(PyValueError::new_err(
+                    "region_min and region_max must be given together",
+                ))
+            }
+        };
+        Ok(BoxQuery {
+            precision,
+            offset,
+            region,
+            ..Default::default()
+        })
+    }
+}
+
+#[pymethods]
+impl PyMortonIndex {
+    /// Encode `coordinates` (Nx3, float32 or float64) on a `precision`-bit grid (max 21).
+    #[new]
+    #[pyo3(signature = (coordinates, precision=18))]
+    fn new(py: Python<'_>, coordinates: &Bound<'_, PyAny>, precision: u32) -> PyResult<Self> {
+        let coords = extract_f64_array2(coordinates, "coordinates")?;
+        check_coordinates("coordinates", &coords)?;
+        check_count("number of coordinates", coords.nrows(), 1)?;
+        check_in_range("precision", precision, 2, MAX_PRECISION)?;
+        let points: Vec<[f64; 3]> = coords
+            .outer_iter()
+            .map(|p| [p[0], p[1], p[2]])
+            .collect();
+
+        let index = py.allow_threads(|| MortonIndex::new(&points, precision));
+        Ok(Self { index })
+    }
+
+    /// Bits per dimension of the finest grid.
+    #[getter]
+    fn precision(&self) -> u32 {
+        self.index.precision()
+    }
+
+    fn __len__(&self) -> usize {
+        self.index.num_points()
+    }
+
+    /// Box-counting dimension of the indexed points.
+    ///
+    /// # Arguments
+    /// * `precision` - Number of scales to use, from the finest box upward (default: all)
+    /// * `offset` - Shift of the box grid origin as `(dx, dy, dz)` (default: none)
+    /// * `region_min` - Lower corner of the sub-region to analyze (with `region_max`)
+    /// * `region_max` - Upper corner of the sub-region to analyze (with `region_min`)
+    /// * `linear_region` - `LinearRegionParams` tuning the linear-region detection
+    /// * `min_scale` - Smallest box size fitted, in coordinate units (default: all)
+    /// * `max_scale` - Largest box size fitted, in coordinate units (default: all)
+    /// * `fit_range` - Indices `(i0, i1)` of the plot points fitted, replacing the
+    ///   linear-region detection (default: detected)
+    /// * `fit_method` - "ols", "theil_sen", "ransac" or "wls" (weighted by the box
+    ///   counts) (default: "ols")
+    /// * `bootstrap` - Bootstrap resamples of the fitted scales (default: 0, none)
+    /// * `n_offsets` - Box grids, each shifted by a different fraction of the
+    ///   box from `offset`, whose counts are averaged (default: 1)
+    #[pyo3(signature = (precision=None, offset=None, region_min=None, region_max=None, linear_region=None, min_scale=None, max_scale=None, fit_range=None, fit_method="ols", bootstrap=0, n_offsets=1))]
+    fn box_counting(
+        &self,
+        py: Python<'_>,
+        precision: Option<u32>,
+        offset: Option<[f64; 3]>,
+        region_min: Option<[f64; 3]>,
+        region_max: Option<[f64; 3]>,
+        linear_region: Option<LinearRegionParams>,
+        min_scale: Option<f64>,
+        max_scale: Option<f64>,
+        fit_range: Option<(usize, usize)>,
+        fit_method: &str,
+        bootstrap: usize,
+        n_offsets: usize,
+    ) -> PyResult<PyFractalResult> {
+        let query = self.query(precision, offset, region_min, region_max)?;
+        let region = resolve_linear_region(linear_region)?;
+        let options = FitOptions::new(min_scale, max_scale, fit_range, fit_method, bootstrap)?;
+        check_count("n_offsets", n_offsets, 1)?;
+        let result = py.allow_threads(|| self.index.analyze(&query, n_offsets, &region, &options));
+        options.check_fitted(result.linear_region_start, result.linear_region_end)?;
+        Ok(result.to_py())
+    }
+
+    /// Raw box counts as `(box_sizes, counts)` arrays, finest box first.
+    ///
+    /// Takes the same box arguments as `box_counting`.
+    #[pyo3(signature = (precision=None, offset=None, region_min=None, region_max=None))]
+    fn box_counts<'py>(
+        &self,
+        py: Python<'py>,
+        precision: Option<u32>,
+        offset: Option<[f64; 3]>,
+        region_min: Option<[f64; 3]>,
+        region_max: Option<[f64; 3]>,
+    ) -> PyResult<BoxCountArrays<'py>> {
+        let query = self.query(precision, offset, region_min, region_max)?;
+        let (counts, _) = py.allow_threads(|| self.index.box_counts(&query));
+        let (sizes, counts): (Vec<f64>, Vec<u64>) =
+            counts.into_iter().map(|(size, count)| (size, count as u64)).unzip();
+        Ok((PyArray1::from_vec(py, sizes), PyArray1::from_vec(py, counts)))
+    }
+}
+
 /// Generate approximately uniformly distributed points on a sphere surface.
 /// Uses the Fibonacci lattice method.
-fn generate_sphere_points(
+pub(crate) fn generate_sphere_points(
     cx: f64,
     cy: f64,
     cz: f64,
@@ -490,6 +1156,7 @@ fn generate_sphere_points(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ndarray::aview2;
 
     #[test]
     fn test_morton_encode() {
@@ -510,12 +1177,51 @@ mod tests {
     }
 
     #[test]
-    fn test_count_unique_masked() {
+    fn test_count_unique_levels() {
         let codes = vec![0, 1, 2, 3, 8, 9, 10, 11];
-        // No masking - all unique
-        assert_eq!(count_unique_masked(&codes, 0), 8);
-        // Mask 3 bits - should group (0-7) and (8-15)
-        assert_eq!(count_unique_masked(&codes, 3), 2);
+        // No masking - all unique; mask 3 bits - should group (0-7) and (8-15)
+        assert_eq!(count_unique_levels(&codes, 3), vec![8, 2, 1]);
+        assert_eq!(count_unique_levels(&[], 2), vec![0, 0]);
+
+        // Large inputs take the parallel path and must agree with a serial scan per level
+        let mut large: Vec<u64> = (0..200_000u64).map(|i| i * 3 + i % 2 + ((i % 7) << 60)).collect();
+        large.sort_unstable();
+        let counts = count_unique_levels(&large, 21);
+        for level in [0, 1, 3, 10, 20] {
+            let mut expected: Vec<u64> = large.iter().map(|c| c >> (3 * level)).collect();
+            expected.dedup();
+            assert_eq!(counts[level], expected.len(), "level {}", level);
+        }
+    }
+
+    #[test]
+    fn test_voxel_box_counting() {
+        // Filled 32^3 cube: 8x fewer boxes per doubling, Df = 3
+        let cube = Array3::from_elem((32, 32, 32), true);
+        let counts = voxel_box_counts(cube.view());
+        assert_eq!(counts, vec![(1, 32768), (2, 4096), (4, 512), (8, 64), (16, 8), (32, 1)]);
+        let (region, options) = (LinearRegionParams::default(), FitOptions::default());
+        let result = box_counting_voxels_internal(cube.view(), 0.5, &region, &options);
+        assert!((result.dimension - 3.0).abs() < 1e-9, "cube Df = {}", result.dimension);
+
+        // The single-box scale is reported but not fitted; scale limits are in voxel_size units
+        assert_eq!((result.box_counts.len(), result.log_scales.len()), (6, 5));
+        assert_eq!(result.box_counts[5], (16.0, 1.0));
+        let limits = FitOptions::new(Some(1.0), Some(4.0), None, "ols", 0).unwrap();
+        let pinned = box_counting_voxels_internal(cube.view(), 0.5, &region, &limits);
+        assert_eq!((pinned.linear_region_start, pinned.linear_region_end), (1, 4));
+        assert!((pinned.dimension - 3.0).abs() < 1e-9);
+
+        // Odd-sized volume with a diagonal line of voxels: Df ~ 1
+        let mut line = Array3::from_elem((45, 45, 45), false);
+        for i in 0..45 {
+            line[[i, i, i]] = true;
+        }
+        let counts = voxel_box_counts(line.view());
+        assert_eq!(counts.last(), Some(&(64, 1)));
+        assert_eq!(counts[1], (2, 23));
+        let result = box_counting_voxels_internal(line.view(), 1.0, &region, &options);
+        assert!((result.dimension - 1.0).abs() < 0.1, "line Df = {}", result.dimension);
     }
 
     #[test]
@@ -525,10 +1231,19 @@ mod tests {
             .map(|i| [i as f64, 0.0, 0.0])
             .collect();
 
-        let result = box_counting_3d_morton(&points, 16);
+        let result = box_counting_3d_morton(
+            &points,
+            16,
+            &GridAveraging::default(),
+            &LinearRegionParams::default(),
+            &FitOptions::default(),
+        );
         assert!(result.dimension > 0.8 && result.dimension < 1.2,
             "Line Df should be ~1, got {}", result.dimension);
         assert!(result.r_squared > 0.9);
+        let fit = result.to_py();
+        assert_eq!(fit.slope, result.dimension);
+        assert!(fit.intercept.is_finite());
     }
 
     #[test]
@@ -541,12 +1256,92 @@ mod tests {
             }
         }
 
-        let result = box_counting_3d_morton(&points, 16);
+        let result = box_counting_3d_morton(
+            &points,
+            16,
+            &GridAveraging::default(),
+            &LinearRegionParams::default(),
+            &FitOptions::default(),
+        );
         assert!(result.dimension > 1.7 && result.dimension < 2.3,
             "Plane Df should be ~2, got {}", result.dimension);
         assert!(result.r_squared > 0.9);
     }
 
+    #[test]
+    fn test_scale_bootstrap() {
+        use crate::simulation::ensemble::percentile;
+
+        let points = generate_sphere_points(0.0, 0.0, 0.0, 50.0, 20000);
+        let index = MortonIndex::new(&points, 16);
+        let options = FitOptions { bootstrap: 200, ..Default::default() };
+        let result = index.analyze(&BoxQuery::default(), 1, &LinearRegionParams::default(), &options);
+        assert_eq!(result.bootstrap_dimensions.len(), 200);
+
+        // Resamples of the fitted scales are centred on the point estimate
+        let mut sorted = result.bootstrap_dimensions.clone();
+        sorted.sort_by(f64::total_cmp);
+        let (low, high) = (percentile(&sorted, 2.5), percentile(&sorted, 97.5));
+        assert!(low <= result.dimension && result.dimension <= high, "{} not in ({}, {})", result.dimension, low, high);
+        let mean = result.bootstrap_dimensions.iter().sum::<f64>() / 200.0;
+        assert!((mean - result.dimension).abs() < 0.5 * (high - low).max(1e-9), "{} vs {}", mean, result.dimension);
+
+        let again = index.analyze(&BoxQuery::default(), 1, &LinearRegionParams::default(), &options);
+        assert_eq!(again.bootstrap_dimensions, result.bootstrap_dimensions);
+    }
+
+    #[test]
+    fn test_chunked_counts_match_the_index() {
+        // Clustered cloud: most points in one corner, a sparse shell around it
+        let mut points: Vec<[f64; 3]> = generate_sphere_points(0.0, 0.0, 0.0, 50.0, 3000);
+        points.extend(generate_sphere_points(-40.0, -40.0, -40.0, 2.0, 5000));
+        let expected = MortonIndex::new(&points, 14).box_counts(&BoxQuery::default()).0;
+        for max_chunk_points in [50, 700, 4000, usize::MAX] {
+            assert_eq!(box_counts_chunked(aview2(&points), 14, max_chunk_points), expected, "{}", max_chunk_points);
+        }
+        // Fewer bits than the histogram depth count on the histogram alone
+        let coarse = MortonIndex::new(&points, 4).box_counts(&BoxQuery::default()).0;
+        assert_eq!(box_counts_chunked(aview2(&points), 4, 100), coarse);
+
+        let (region, options) = (LinearRegionParams::default(), FitOptions::default());
+        let chunked = box_counting_3d_chunked(aview2(&points), 14, 1, &region, &options);
+        let direct = box_counting_3d_morton(&points, 14, &GridAveraging::default(), &region, &options);
+        assert_eq!(chunked.dimension, direct.dimension);
+    }
+
+    #[test]
+    fn test_grid_averaging() {
+        let mut points = Vec::new();
+        for i in 0..40 {
+            for j in 0..40 {
+                points.push([i as f64, j as f64, 0.0]);
+            }
+        }
+        let (region, options) = (LinearRegionParams::default(), FitOptions::default());
+
+        // One grid has no spread; shifted grids change the coarse counts but keep Df
+        let index = MortonIndex::new(&points, 12);
+        let single = index.analyze(&BoxQuery::default(), 1, &region, &options);
+        assert!(single.box_count_std.iter().all(|&s| s == 0.0));
+        let shifted = index.analyze(&BoxQuery::default(), 6, &region, &options);
+        assert_eq!(shifted.box_count_std.len(), shifted.box_counts.len());
+        assert!(shifted.box_count_std.iter().any(|&s| s > 0.0));
+        assert!((shifted.dimension - 2.0).abs() < 0.3, "{}", shifted.dimension);
+
+        // Every orientation shares the box sizes of the common frame
+        let indices = oriented_indices(&points, 12, 3);
+        assert_eq!(indices.len(), 4);
+        assert!(indices.iter().all(|index| index.scale == indices[0].scale && index.num_points() == 1600));
+
+        // A dense line keeps Df ~ 1 in any orientation
+        let line: Vec<[f64; 3]> = (0..4000).map(|i| [i as f64 * 0.025, 0.0, 0.0]).collect();
+        let grids = GridAveraging { offsets: 2, rotations: 3 };
+        let rotated = box_counting_3d_morton(&line, 14, &grids, &region, &options);
+        assert!((rotated.dimension - 1.0).abs() < 0.15, "{}", rotated.dimension);
+        let again = box_counting_3d_morton(&line, 14, &grids, &region, &options);
+        assert_eq!(again.box_counts, rotated.box_counts);
+    }
+
     #[test]
     fn test_box_counting_cube() {
         // Filled cube should have Df ~ 3
@@ -559,12 +1354,65 @@ mod tests {
             }
         }
 
-        let result = box_counting_3d_morton(&points, 16);
+        let result = box_counting_3d_morton(
+            &points,
+            16,
+            &GridAveraging::default(),
+            &LinearRegionParams::default(),
+            &FitOptions::default(),
+        );
         assert!(result.dimension > 2.7 && result.dimension < 3.3,
             "Cube Df should be ~3, got {}", result.dimension);
         assert!(result.r_squared > 0.9);
     }
 
+    #[test]
+    fn test_morton_index_reuses_sort() {
+        let mut points = Vec::new();
+        for i in 0..30 {
+            for j in 0..30 {
+                points.push([i as f64, j as f64, 0.0]);
+            }
+        }
+        let index = MortonIndex::new(&points, 16);
+
+        // The default query reproduces the one-shot function
+        let direct = box_counting_3d_morton(
+            &points,
+            16,
+            &GridAveraging::default(),
+            &LinearRegionParams::default(),
+            &FitOptions::default(),
+        );
+        let reused = index.analyze(&BoxQuery::default(), 1, &LinearRegionParams::default(), &FitOptions::default());
+        assert_eq!(reused.log_counts, direct.log_counts);
+        assert_eq!(reused.dimension, direct.dimension);
+
+        // Coarser precision keeps the coarsest scales
+        let (all, _) = index.box_counts(&BoxQuery::default());
+        let (coarse, _) = index.box_counts(&BoxQuery {
+            precision: Some(6),
+            ..Default::default()
+        });
+        assert_eq!(coarse.as_slice(), &all[10..]);
+
+        // A zero-width sub-region along x leaves one line of points
+        let (counts, n) = index.box_counts(&BoxQuery {
+            region: Some(([0.0, 0.0, 0.0], [0.0, 29.0, 0.0])),
+            ..Default::default()
+        });
+        assert_eq!(n, 30);
+        assert_eq!(counts[0].1, 30);
+
+        // Shifting the grid never changes the finest-scale count
+        let (shifted, n) = index.box_counts(&BoxQuery {
+            offset: [0.37, 0.0, 0.0],
+            ..Default::default()
+        });
+        assert_eq!(n, points.len());
+        assert_eq!(shifted[0].1, all[0].1);
+    }
+
     #[test]
     fn test_sphere_points() {
         let points = generate_sphere_points(0.0, 0.0, 0.0, 1.0, 100);
@@ -0,0 +1,225 @@
+//! Correlation-function fractal dimension analysis.
+//!
+//! The density-density correlation function C(r) is the mass found in a
+//! spherical shell at distance r from a particle, per unit shell volume,
+//! averaged over the particles. Inside a fractal it decays as
+//! C(r) ~ r^(Df - 3) up to a cutoff near the size of the agglomerate, so Df
+//! follows from the slope of ln C against ln r. Every pair contributes, which
+//! makes the estimate steadier than box counting on small agglomerates.
+
+use std::f64::consts::PI;
+use std::time::Instant;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::common::fitting::{fit_linear_region, resolve_linear_region, FitMethod, LinearRegionParams};
+use crate::common::validation::{check_count, check_coordinates, check_positive};
+use crate::projection::extract_structure;
+use crate::simulation::metrics::calculate_radius_of_gyration;
+
+use super::result::{FractalResult, PyFractalResult};
+
+/// Mass (volume) weighted pair histogram over `n_bins` log-spaced shells
+/// between `r_min` and `r_max`, normalized to the density-density correlation.
+pub fn correlation_function(
+    coordinates: &[[f64; 3]],
+    radii: &[f64],
+    r_min: f64,
+    r_max: f64,
+    n_bins: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    let log_width = (r_max / r_min).ln() / n_bins as f64;
+    let masses: Vec<f64> = radii.iter().map(|r| r * r * r).collect();
+    let total_mass: f64 = masses.iter().sum();
+
+    let histogram = (0..coordinates.len())
+        .into_par_iter()
+        .fold(
+            || vec![0.0; n_bins],
+            |mut hist, i| {
+                let a = coordinates[i];
+                for j in (i + 1)..coordinates.len() {
+                    let b = coordinates[j];
+                    let d = ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+                    if d < r_min || d >= r_max {
+                        continue;
+                    }
+                    let bin = (((d / r_min).ln() / log_width) as usize).min(n_bins - 1);
+                    // Each pair is seen from both of its particles
+                    hist[bin] += 2.0 * masses[i] * masses[j];
+                }
+                hist
+            },
+        )
+        .reduce(
+            || vec![0.0; n_bins],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                a
+            },
+        );
+
+    let mut centers = Vec::with_capacity(n_bins);
+    let mut values = Vec::with_capacity(n_bins);
+    for (k, &h) in histogram.iter().enumerate() {
+        let inner = r_min * (k as f64 * log_width).exp();
+        let outer = inner * log_width.exp();
+        let shell = 4.0 / 3.0 * PI * (outer.powi(3) - inner.powi(3));
+        centers.push((inner * outer).sqrt());
+        values.push(h / (total_mass * shell));
+    }
+    (centers, values)
+}
+
+/// Fit Df = 3 + slope of ln C(r) against ln r.
+///
+/// `log_scales` holds ln r at the geometric shell centers and `log_values`
+/// ln C(r); empty shells are left out.
+pub fn correlation_internal(
+    coordinates: &[[f64; 3]],
+    radii: &[f64],
+    r_min: f64,
+    r_max: f64,
+    n_bins: usize,
+    region: &LinearRegionParams,
+) -> FractalResult {
+    let start_time = Instant::now();
+    let (centers, values) = correlation_function(coordinates, radii, r_min, r_max, n_bins);
+
+    let (log_scales, log_values): (Vec<f64>, Vec<f64>) = centers
+        .iter()
+        .zip(&values)
+        .filter(|(_, &c)| c > 0.0)
+        .map(|(r, c)| (r.ln(), c.ln()))
+        .unzip();
+
+    // Shells grow along the arrays, so the fit starts from the largest distances
+    let linear = fit_linear_region(&log_scales, &log_values, region);
+    let dimension = 3.0 + linear.fit.slope;
+    let std_error = linear.fit.std_error;
+    let ci_half = 1.96 * std_error;
+
+    FractalResult {
+        dimension,
+        r_squared: linear.fit.r_squared,
+        std_error,
+        slope: linear.fit.slope,
+        intercept: linear.fit.intercept,
+        confidence_interval: (dimension - ci_half, dimension + ci_half),
+        log_scales,
+        log_values,
+        residuals: linear.residuals,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+        linear_region_start: linear.start,
+        linear_region_end: linear.end,
+        box_counts: Vec::new(),
+        box_count_std: Vec::new(),
+        fit_method: FitMethod::Ols,
+        bootstrap_dimensions: Vec::new(),
+    }
+}
+
+/// Radii a scaling analysis spans, from twice the mean particle radius to the
+/// radius of gyration unless given. The default `r_max` is at least twice
+/// `r_min`, since a compact cluster can have an Rg below its contact distance.
+pub(crate) fn radius_range(
+    points: &[[f64; 3]],
+    radii: &[f64],
+    r_min: Option<f64>,
+    r_max: Option<f64>,
+) -> PyResult<(f64, f64)> {
+    let mean_radius = radii.iter().sum::<f64>() / radii.len() as f64;
+    let r_min = r_min.unwrap_or(2.0 * mean_radius);
+    check_positive("r_min", r_min)?;
+    let r_max = r_max.unwrap_or_else(|| calculate_radius_of_gyration(points, radii).max(2.0 * r_min));
+    check_positive("r_max", r_max)?;
+    if r_max <= r_min {
+        return Err(PyValueError::new_err(format!(
+            "r_max ({}) must be larger than r_min ({})",
+            r_max, r_min
+        )));
+    }
+    Ok((r_min, r_max))
+}
+
+/// Fractal dimension from the density-density correlation function.
+///
+/// C(r) is the particle mass (volume) per unit volume in log-spaced shells
+/// around every particle; for a fractal it decays as r^(Df - 3), so the
+/// reported `dimension` is 3 plus the slope of ln C(r) against ln r. All
+/// N(N-1)/2 pairs are used, which suits small agglomerates (< 200 particles)
+/// where box counting has few usable scales.
+///
+/// # Arguments
+/// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
+/// * `r_min` - Inner edge of the first shell (default: twice the mean particle radius)
+/// * `r_max` - Outer edge of the last shell (default: the radius of gyration, at least twice `r_min`)
+/// * `n_bins` - Number of log-spaced shells (default: 15)
+/// * `linear_region` - `LinearRegionParams` tuning the linear-region detection (default thresholds when None)
+///
+/// # Returns
+/// * `FractalResult` with `log_scales` = ln r and `log_values` = ln C(r)
+#[pyfunction]
+#[pyo3(signature = (coordinates, radii, r_min=None, r_max=None, n_bins=15, linear_region=None))]
+pub fn correlation_dimension(
+    py: Python<'_>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+    r_min: Option<f64>,
+    r_max: Option<f64>,
+    n_bins: usize,
+    linear_region: Option<LinearRegionParams>,
+) -> PyResult<PyFractalResult> {
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    check_coordinates("coordinates", &coords)?;
+    check_count("number of coordinates", coords.nrows(), 2)?;
+    check_count("n_bins", n_bins, 3)?;
+    let region = resolve_linear_region(linear_region)?;
+
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|c| [c[0], c[1], c[2]]).collect();
+    let (r_min, r_max) = radius_range(&points, &radii, r_min, r_max)?;
+
+    let result = py.allow_threads(|| correlation_internal(&points, &radii, r_min, r_max, n_bins, &region));
+    Ok(result.to_py())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::common::rng::create_rng;
+
+    #[test]
+    fn test_default_radius_range() {
+        // Two touching unit spheres: Rg = sqrt(1 + 3/5) is below r_min = 2
+        let pair = [[-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]];
+        assert_eq!(radius_range(&pair, &[1.0, 1.0], None, None).unwrap(), (2.0, 4.0));
+        assert!(radius_range(&pair, &[1.0, 1.0], None, Some(1.5)).is_err());
+
+        let chain: Vec<[f64; 3]> = (0..100).map(|i| [2.0 * i as f64, 0.0, 0.0]).collect();
+        let (_, r_max) = radius_range(&chain, &[1.0; 100], None, None).unwrap();
+        assert_eq!(r_max, calculate_radius_of_gyration(&chain, &[1.0; 100]));
+    }
+
+    #[test]
+    fn test_correlation_chain_and_uniform_cloud() {
+        // Touching chain: C(r) ~ r^-2
+        let chain: Vec<[f64; 3]> = (0..1000).map(|i| [2.0 * i as f64, 0.0, 0.0]).collect();
+        let result = correlation_internal(&chain, &[1.0; 1000], 5.0, 100.0, 10, &LinearRegionParams::default());
+        assert!((result.dimension - 1.0).abs() < 0.15, "chain Df = {}", result.dimension);
+        assert!((result.dimension - 3.0 - result.slope).abs() < 1e-12);
+        assert!(result.intercept.is_finite());
+
+        // Uniform random points in a cube: C(r) flat away from the faces
+        let mut rng = create_rng(7);
+        let cloud: Vec<[f64; 3]> = (0..3000)
+            .map(|_| [rng.gen_range(0.0..40.0), rng.gen_range(0.0..40.0), rng.gen_range(0.0..40.0)])
+            .collect();
+        let result = correlation_internal(&cloud, &[1.0; 3000], 2.0, 6.0, 8, &LinearRegionParams::default());
+        assert!((result.dimension - 3.0).abs() < 0.3, "lattice Df = {}", result.dimension);
+    }
+}
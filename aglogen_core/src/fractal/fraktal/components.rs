@@ -0,0 +1,174 @@
+//! Per-aggregate FRAKTAL analysis.
+//!
+//! TEM frames usually hold several aggregates, and analysing the whole mask
+//! as one object mixes their areas into a single, meaningless Rg. The
+//! segmented (and cleaned) mask is split into its 8-connected components,
+//! and each one is analysed on its own: cropped to its bounding box with a
+//! one-pixel background margin, so the geometry only sees that aggregate.
+
+use ndarray::{Array2, ArrayView2};
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use super::detection::NpoMethod;
+use super::granulated_2012::analyze_granulated_2012_mask;
+use super::morphology::label_components;
+use super::params::{Granulated2012Params, Voxel2018Params};
+use super::result::{FraktalResult, PyFraktalResult};
+use super::voxel_2018::analyze_voxel_2018_mask;
+
+/// FRAKTAL model with its parameters.
+#[derive(Debug, Clone)]
+pub enum FraktalModel {
+    Granulated2012(Granulated2012Params),
+    Voxel2018(Voxel2018Params),
+}
+
+impl FraktalModel {
+    /// Take the model from a `Granulated2012Params` or `Voxel2018Params` object.
+    pub fn from_params(params: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(p) = params.extract::<Granulated2012Params>() {
+            Ok(Self::Granulated2012(p))
+        } else if let Ok(p) = params.extract::<Voxel2018Params>() {
+            Ok(Self::Voxel2018(p))
+        } else {
+            Err(PyTypeError::new_err(
+                "params must be Granulated2012Params or Voxel2018Params",
+            ))
+        }
+    }
+
+    pub fn validate(&self) -> PyResult<()> {
+        match self {
+            Self::Granulated2012(p) => p.validate(),
+            Self::Voxel2018(p) => p.validate(),
+        }
+    }
+
+    /// Segment an image and clean up the mask, as the model's params `segment`.
+    pub fn segment(&self, image: ArrayView2<u8>) -> (Array2<bool>, u8, bool) {
+        match self {
+            Self::Granulated2012(p) => p.segment(image),
+            Self::Voxel2018(p) => p.segment(image),
+        }
+    }
+
+    /// Detector of the visual npo (distance peaks for the voxel model).
+    pub fn npo_method(&self) -> NpoMethod {
+        match self {
+            Self::Granulated2012(p) => p.npo_method(),
+            Self::Voxel2018(_) => NpoMethod::DistancePeaks,
+        }
+    }
+
+    /// Analyze a segmented mask.
+    pub fn analyze_mask(&self, binary: ArrayView2<bool>) -> FraktalResult {
+        match self {
+            Self::Granulated2012(p) => analyze_granulated_2012_mask(binary, p),
+            Self::Voxel2018(p) => analyze_voxel_2018_mask(binary, p),
+        }
+    }
+}
+
+/// FRAKTAL result of one aggregate of an image.
+#[derive(Debug, Clone)]
+pub struct ComponentResult {
+    pub result: FraktalResult,
+    /// (row_min, col_min, row_max, col_max), inclusive
+    pub bounding_box: (usize, usize, usize, usize),
+    pub pixel_count: usize,
+}
+
+impl From<ComponentResult> for PyFraktalResult {
+    fn from(c: ComponentResult) -> Self {
+        let mut result = PyFraktalResult::from(c.result);
+        result.bounding_box = Some(c.bounding_box);
+        result.pixel_count = Some(c.pixel_count);
+        result
+    }
+}
+
+/// Analyze every aggregate of `image` with at least `min_pixels` pixels.
+///
+/// Components are returned in raster order of their first pixel.
+pub fn analyze_components(
+    image: ArrayView2<u8>,
+    model: &FraktalModel,
+    min_pixels: usize,
+) -> Vec<ComponentResult> {
+    let (binary, _threshold, _dark_on_light) = model.segment(image);
+    let (labels, areas) = label_components(binary.view());
+
+    let mut boxes = vec![(usize::MAX, usize::MAX, 0, 0); areas.len()];
+    for ((i, j), &label) in labels.indexed_iter() {
+        if label != 0 {
+            let b = &mut boxes[label as usize - 1];
+            *b = (b.0.min(i), b.1.min(j), b.2.max(i), b.3.max(j));
+        }
+    }
+
+    let kept: Vec<usize> = (0..areas.len()).filter(|&c| areas[c] >= min_pixels).collect();
+    kept.into_par_iter()
+        .map(|c| {
+            let label = c as u32 + 1;
+            let (r0, c0, r1, c1) = boxes[c];
+            let crop = Array2::from_shape_fn((r1 - r0 + 3, c1 - c0 + 3), |(i, j)| {
+                let (ri, cj) = ((r0 + i).wrapping_sub(1), (c0 + j).wrapping_sub(1));
+                (r0..=r1).contains(&ri) && (c0..=c1).contains(&cj) && labels[[ri, cj]] == label
+            });
+            ComponentResult {
+                result: model.analyze_mask(crop.view()),
+                bounding_box: boxes[c],
+                pixel_count: areas[c],
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fractal::fraktal::voxel_2018::analyze_voxel_2018;
+
+    #[test]
+    fn test_components_analysed_separately() {
+        // Two dark squares and a speck on a light background
+        let mut image = Array2::from_elem((80, 100), 255u8);
+        let squares = [(5, 60, 20), (40, 10, 30)];
+        for &(row, col, side) in &squares {
+            for i in row..row + side {
+                for j in col..col + side {
+                    image[[i, j]] = 50;
+                }
+            }
+        }
+        image[[75, 95]] = 50;
+        image[[75, 96]] = 50;
+        let params = Voxel2018Params {
+            auto_threshold: false,
+            ..Default::default()
+        };
+        let model = FraktalModel::Voxel2018(params.clone());
+
+        let components = analyze_components(image.view(), &model, 10);
+        assert_eq!(components.len(), 2);
+        assert_eq!(analyze_components(image.view(), &model, 1).len(), 3);
+        for (component, &(row, col, side)) in components.iter().zip(&squares) {
+            assert_eq!(component.bounding_box, (row, col, row + side - 1, col + side - 1));
+            assert_eq!(component.pixel_count, side * side);
+
+            // Same as an image holding that aggregate alone
+            let mut alone = Array2::from_elem((80, 100), 255u8);
+            for i in row..row + side {
+                for j in col..col + side {
+                    alone[[i, j]] = 50;
+                }
+            }
+            let expected = analyze_voxel_2018(alone.view(), &params);
+            assert!((component.result.ap - expected.ap).abs() < 1e-9);
+            assert!((component.result.rg - expected.rg).abs() < 1e-9);
+            assert_eq!(component.result.df, expected.df);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Alternative primary particle detectors for the visual npo.
+//!
+//! The distance-transform peaks of `detect_particles` count overlapping
+//! primaries well on clean masks, but merge or split them on difficult
+//! images. Two independent estimators let the visual count be cross-checked:
+//!
+//! - "watershed": the mask is flooded from the maxima of the distance
+//!   transform down; a basin that meets a deeper one less than one pixel
+//!   below its own peak is a ripple of the outline and joins it, every other
+//!   basin is a primary particle
+//! - "hough": circular Hough transform of the mask outline; every outline
+//!   pixel votes for the centers at each radius of the range, and centers
+//!   backed by enough of their circumference are primary particles
+//!
+//! Both return the particle centers as (row, col, radius_px), as
+//! `detect_particles` does.
+
+use std::f64::consts::PI;
+
+use ndarray::{Array2, ArrayView2};
+
+use super::image_processing::{compute_distance_transform, detect_particles};
+
+/// Depth in pixels a watershed basin must reach below its peak to count as a particle.
+const WATERSHED_MIN_DYNAMIC: f64 = 1.0;
+
+/// Lowest distance-transform peak of a particle, as in `detect_particles`.
+const MIN_PEAK_DISTANCE: f64 = 2.0;
+
+/// Share of its circumference a Hough circle needs on the outline.
+const HOUGH_MIN_SUPPORT: f64 = 0.35;
+
+/// Detector of the primary particles behind the visual npo.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NpoMethod {
+    /// Non-maximum suppressed peaks of the distance transform
+    #[default]
+    DistancePeaks,
+    /// Basins of the watershed of the distance transform
+    Watershed,
+    /// Circular Hough transform over radii `min..=max` pixels
+    Hough { min_radius: usize, max_radius: usize },
+}
+
+impl NpoMethod {
+    /// Method called `name` ("distance_peaks", "watershed" or "hough"), Hough over `radius_range` pixels.
+    pub fn from_name(name: &str, radius_range: (usize, usize)) -> Option<Self> {
+        match name {
+            "distance_peaks" => Some(Self::DistancePeaks),
+            "watershed" => Some(Self::Watershed),
+            "hough" => Some(Self::Hough {
+                min_radius: radius_range.0,
+                max_radius: radius_range.1,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Detect the primary particles of a mask.
+    ///
+    /// Returns the particle centers as (row, col, radius_px) and the average
+    /// particle radius in pixels.
+    pub fn detect(&self, binary: ArrayView2<bool>) -> (Vec<(usize, usize, f64)>, f64) {
+        let particles = match *self {
+            Self::DistancePeaks => return detect_particles(binary),
+            Self::Watershed => watershed_particles(binary),
+            Self::Hough { min_radius, max_radius } => hough_particles(binary, min_radius, max_radius),
+        };
+        let avg_radius = if particles.is_empty() {
+            0.0
+        } else {
+            particles.iter().map(|p| p.2).sum::<f64>() / particles.len() as f64
+        };
+        (particles, avg_radius)
+    }
+}
+
+/// Root of `i` in the union-find `parent`, compressing the path.
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Peaks of the watershed basins of the distance transform.
+fn watershed_particles(binary: ArrayView2<bool>) -> Vec<(usize, usize, f64)> {
+    let (rows, cols) = binary.dim();
+    let distance = compute_distance_transform(binary);
+
+    let mut order: Vec<usize> = (0..rows * cols).filter(|&p| binary[[p / cols, p % cols]]).collect();
+    order.sort_by(|&a, &b| {
+        distance[[b / cols, b % cols]]
+            .partial_cmp(&distance[[a / cols, a % cols]])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // Union-find of the flooded pixels; the root of a basin is its peak
+    let mut parent: Vec<usize> = (0..rows * cols).collect();
+    let mut flooded = vec![false; rows * cols];
+    for &p in &order {
+        let (i, j) = (p / cols, p % cols);
+        let level = distance[[i, j]];
+        let mut roots: Vec<usize> = Vec::new();
+        for di in -1isize..=1 {
+            for dj in -1isize..=1 {
+                let (ni, nj) = (i as isize + di, j as isize + dj);
+                if (di, dj) == (0, 0) || ni < 0 || nj < 0 || ni as usize >= rows || nj as usize >= cols {
+                    continue;
+                }
+                let q = ni as usize * cols + nj as usize;
+                if flooded[q] {
+                    let root = find(&mut parent, q);
+                    if !roots.contains(&root) {
+                        roots.push(root);
+                    }
+                }
+            }
+        }
+        flooded[p] = true;
+
+        let height = |r: usize| distance[[r / cols, r % cols]];
+        let Some(&deepest) = roots.iter().max_by(|&&a, &&b| {
+            height(a).partial_cmp(&height(b)).unwrap_or(std::cmp::Ordering::Equal)
+        }) else {
+            continue; // A new basin
+        };
+        parent[p] = deepest;
+        // Shallow basins are ripples of the deepest one; the others stay apart
+        for &root in &roots {
+            if root != deepest && height(root) - level < WATERSHED_MIN_DYNAMIC {
+                parent[root] = deepest;
+            }
+        }
+    }
+
+    let mut particles: Vec<(usize, usize, f64)> = order
+        .iter()
+        .filter(|&&p| find(&mut parent, p) == p)
+        .map(|&p| (p / cols, p % cols, distance[[p / cols, p % cols]]))
+        .filter(|&(_, _, radius)| radius >= MIN_PEAK_DISTANCE)
+        .collect();
+    particles.sort_by_key(|&(i, j, _)| (i, j));
+    particles
+}
+
+/// Circles of radius `min_radius..=max_radius` found on the outline of the mask.
+fn hough_particles(binary: ArrayView2<bool>, min_radius: usize, max_radius: usize) -> Vec<(usize, usize, f64)> {
+    let (rows, cols) = binary.dim();
+    let foreground = |i: isize, j: isize| {
+        i >= 0 && j >= 0 && (i as usize) < rows && (j as usize) < cols && binary[[i as usize, j as usize]]
+    };
+    let outline: Vec<(isize, isize)> = binary
+        .indexed_iter()
+        .filter(|&(_, &b)| b)
+        .map(|((i, j), _)| (i as isize, j as isize))
+        .filter(|&(i, j)| [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|&(di, dj)| !foreground(i + di, j + dj)))
+        .collect();
+
+    // (support, row, col, radius) of the local maxima of every radius
+    let mut candidates: Vec<(f64, usize, usize, usize)> = Vec::new();
+    for radius in min_radius.max(1)..=max_radius {
+        let steps = (2.0 * PI * radius as f64).ceil() as usize;
+        let offsets: Vec<(isize, isize)> = {
+            let mut offsets: Vec<(isize, isize)> = (0..steps)
+                .map(|k| {
+                    let angle = 2.0 * PI * k as f64 / steps as f64;
+                    (
+                        (radius as f64 * angle.sin()).round() as isize,
+                        (radius as f64 * angle.cos()).round() as isize,
+                    )
+                })
+                .collect();
+            offsets.sort_unstable();
+            offsets.dedup();
+            offsets
+        };
+
+        let mut votes = Array2::<f64>::zeros((rows, cols));
+        for &(i, j) in &outline {
+            for &(di, dj) in &offsets {
+                if foreground(i + di, j + dj) {
+                    votes[[(i + di) as usize, (j + dj) as usize]] += 1.0;
+                }
+            }
+        }
+        let circumference = offsets.len() as f64;
+        for ((i, j), &v) in votes.indexed_iter() {
+            let support = v / circumference;
+            if support < HOUGH_MIN_SUPPORT {
+                continue;
+            }
+            let is_max = (i.saturating_sub(1)..(i + 2).min(rows))
+                .all(|ni| (j.saturating_sub(1)..(j + 2).min(cols)).all(|nj| votes[[ni, nj]] <= v));
+            if is_max {
+                candidates.push((support, i, j, radius));
+            }
+        }
+    }
+
+    // Best supported first; no center inside an accepted circle
+    candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    let mut particles: Vec<(usize, usize, f64)> = Vec::new();
+    for &(_, i, j, radius) in &candidates {
+        let inside = particles.iter().any(|&(pi, pj, pr)| {
+            let (di, dj) = (i as f64 - pi as f64, j as f64 - pj as f64);
+            (di * di + dj * dj).sqrt() < pr.max(radius as f64)
+        });
+        if !inside {
+            particles.push((i, j, radius as f64));
+        }
+    }
+    particles.sort_by_key(|&(i, j, _)| (i, j));
+    particles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detectors_count_a_chain_of_disks() {
+        // Zigzag chain of six overlapping disks of radius 8 and one apart
+        let mut centers: Vec<(f64, f64)> = (0..6).map(|k| (30.0 + 6.0 * (k % 2) as f64, 20.0 + 13.0 * k as f64)).collect();
+        centers.push((70.0, 60.0));
+        let binary = Array2::from_shape_fn((90, 120), |(i, j)| {
+            centers.iter().any(|&(ci, cj)| (i as f64 - ci).powi(2) + (j as f64 - cj).powi(2) <= 64.0)
+        });
+
+        // The peaks are suppressed within a diameter, losing overlapping primaries
+        let (peaks, _) = NpoMethod::DistancePeaks.detect(binary.view());
+        assert!(peaks.len() < 7);
+
+        for method in [NpoMethod::Watershed, NpoMethod::Hough { min_radius: 5, max_radius: 12 }] {
+            let (particles, avg_radius) = method.detect(binary.view());
+            assert_eq!(particles.len(), 7, "{:?}: {:?}", method, particles);
+            assert!((avg_radius - 8.0).abs() < 1.5, "{:?}: {}", method, avg_radius);
+            for &(ci, cj) in &centers {
+                assert!(particles.iter().any(|&(i, j, _)| (i as f64 - ci).hypot(j as f64 - cj) <= 2.0));
+            }
+        }
+        assert_eq!(NpoMethod::from_name("hough", (3, 9)), Some(NpoMethod::Hough { min_radius: 3, max_radius: 9 }));
+        assert_eq!(NpoMethod::from_name("blob", (3, 9)), None);
+    }
+}
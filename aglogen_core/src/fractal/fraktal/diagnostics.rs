@@ -0,0 +1,171 @@
+//! Intermediate results of a FRAKTAL analysis, for visual QA.
+//!
+//! A suspicious Df usually traces back to the segmentation (background
+//! speckles, a threshold on the wrong side) or to the Df solver wandering
+//! between starting estimates. The diagnostics keep what the analysis saw:
+//! the cleaned mask and its threshold, the center of gyration, the primary
+//! particles found on the distance transform and the (npo, Df, kf) iterates
+//! of the solver. Positions are (row, col) in pixels, as numpy indexes the
+//! image.
+
+use std::time::Instant;
+
+use ndarray::{Array2, ArrayView2};
+use numpy::{PyArray2, ToPyArray};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::components::FraktalModel;
+use super::image_processing::calculate_geometry;
+use super::result::FraktalResult;
+
+/// What a FRAKTAL analysis saw of one image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FraktalDiagnostics {
+    pub mask: Array2<bool>,
+    pub threshold: u8,
+    pub dark_on_light: bool,
+    /// (row, col) in pixels, None for an empty mask
+    pub center_of_gyration: Option<(f64, f64)>,
+    /// (row, col, radius) in pixels
+    pub particle_centers: Vec<(usize, usize, f64)>,
+    /// (npo, Df, kf) of every iteration of the Df solver
+    pub history: Vec<[f64; 3]>,
+}
+
+/// Analyze an image with `model`, keeping the diagnostics in the result.
+pub fn analyze_with_diagnostics(image: ArrayView2<u8>, model: &FraktalModel) -> FraktalResult {
+    let start_time = Instant::now();
+    let (mask, threshold, dark_on_light) = model.segment(image);
+    let mut result = model.analyze_mask(mask.view());
+    result.execution_time_ms = start_time.elapsed().as_millis() as u64;
+
+    // The center does not depend on the scale
+    let center_of_gyration =
+        calculate_geometry(mask.view(), 1.0, 1.0).map(|g| (g.center_of_gyration.1, g.center_of_gyration.0));
+    let (particle_centers, _avg_radius_px) = model.npo_method().detect(mask.view());
+    result.diagnostics = Some(FraktalDiagnostics {
+        threshold,
+        dark_on_light,
+        center_of_gyration,
+        particle_centers,
+        history: result.history.clone(),
+        mask,
+    });
+    result
+}
+
+/// Intermediate results of a FRAKTAL analysis, from `return_diagnostics=True`.
+#[pyclass(name = "FraktalDiagnostics")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PyFraktalDiagnostics {
+    /// Detected threshold: Otsu's, or `pixel_max` without `auto_threshold`
+    #[pyo3(get)]
+    pub threshold: u8,
+
+    /// Whether the particles were taken as darker than the background
+    #[pyo3(get)]
+    pub dark_on_light: bool,
+
+    /// Center of gyration of the mask as (row, col) in pixels (None if empty)
+    #[pyo3(get)]
+    pub center_of_gyration: Option<(f64, f64)>,
+
+    pub(crate) diagnostics: FraktalDiagnostics,
+}
+
+#[pymethods]
+impl PyFraktalDiagnostics {
+    /// Cleaned segmentation mask, the same shape as the image.
+    #[getter]
+    fn mask<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<bool>> {
+        self.diagnostics.mask.to_pyarray(py)
+    }
+
+    /// Detected primary particles as numpy array (P, 3) of row, col and radius in pixels.
+    #[getter]
+    fn particle_centers<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let centers = &self.diagnostics.particle_centers;
+        Array2::from_shape_fn((centers.len(), 3), |(k, c)| {
+            let (row, col, radius) = centers[k];
+            [row as f64, col as f64, radius][c]
+        })
+        .to_pyarray(py)
+    }
+
+    /// Iterates of the Df solver as numpy array (K, 3) of npo, Df and kf, in
+    /// order; the granulated model restarts from several npo estimates.
+    #[getter]
+    fn history<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let history = &self.diagnostics.history;
+        Array2::from_shape_fn((history.len(), 3), |(k, c)| history[k][c]).to_pyarray(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FraktalDiagnostics(threshold={}, particles={}, iterations={})",
+            self.threshold,
+            self.diagnostics.particle_centers.len(),
+            self.diagnostics.history.len()
+        )
+    }
+}
+
+impl From<FraktalDiagnostics> for PyFraktalDiagnostics {
+    fn from(diagnostics: FraktalDiagnostics) -> Self {
+        Self {
+            threshold: diagnostics.threshold,
+            dark_on_light: diagnostics.dark_on_light,
+            center_of_gyration: diagnostics.center_of_gyration,
+            diagnostics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fractal::fraktal::params::Voxel2018Params;
+    use crate::fractal::fraktal::result::FraktalStatus;
+    use crate::fractal::fraktal::voxel_2018::analyze_voxel_2018;
+
+    #[test]
+    fn test_diagnostics_describe_the_analysis() {
+        // A dark zigzag chain of ten disks on a light background
+        let image = Array2::from_shape_fn((120, 200), |(i, j)| {
+            let hit = (0..10).any(|k| {
+                let (ci, cj) = (60.0 + 6.0 * (k % 2) as f64, 20.0 + 9.6 * k as f64);
+                (i as f64 - ci).powi(2) + (j as f64 - cj).powi(2) <= 36.0
+            });
+            if hit {
+                30u8
+            } else {
+                230
+            }
+        });
+        let params = Voxel2018Params::default();
+        let plain = analyze_voxel_2018(image.view(), &params);
+        let result = analyze_with_diagnostics(image.view(), &FraktalModel::Voxel2018(params));
+        assert!(plain.diagnostics.is_none());
+        assert_eq!((result.df, result.rg), (plain.df, plain.rg));
+        assert_eq!(result.status, FraktalStatus::Success);
+
+        let diagnostics = result.diagnostics.expect("diagnostics requested");
+        assert!(diagnostics.dark_on_light);
+        assert!((30..230).contains(&diagnostics.threshold));
+        assert_eq!(diagnostics.mask, image.mapv(|v| v == 30));
+        let pixels: Vec<(usize, usize)> = image.indexed_iter().filter(|(_, &v)| v == 30).map(|(p, _)| p).collect();
+        let n = pixels.len() as f64;
+        let (row, col) = diagnostics.center_of_gyration.expect("non-empty mask");
+        assert!((row - pixels.iter().map(|p| p.0 as f64).sum::<f64>() / n).abs() < 1e-9);
+        assert!((col - pixels.iter().map(|p| p.1 as f64).sum::<f64>() / n).abs() < 1e-9);
+        assert!(!diagnostics.particle_centers.is_empty());
+        for &(row, col, radius) in &diagnostics.particle_centers {
+            assert!(diagnostics.mask[[row, col]] && radius >= 2.0);
+        }
+        // The solver stops on its last iterate
+        assert_eq!(diagnostics.history, result.history);
+        let last = diagnostics.history.last().expect("solver iterated");
+        assert_eq!((last[1], last[2]), (result.df, result.kf));
+    }
+}
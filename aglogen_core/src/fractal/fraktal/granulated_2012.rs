@@ -6,14 +6,16 @@
 use std::f64::consts::PI;
 use std::time::Instant;
 
-use ndarray::ArrayView2;
+use ndarray::{Array2, ArrayView2};
+use rayon::prelude::*;
 
 use super::bisection::BisectionSolver;
 use super::image_processing::{
     apply_3d_correction_granulated, calculate_geometry, calculate_m_exponent,
-    estimate_particles_and_dpo, smart_segment,
+    estimate_particles_and_dpo,
 };
 use super::params::Granulated2012Params;
+use super::sizing::edm_sbs;
 use super::result::{FraktalResult, FraktalStatus};
 
 /// Soot density in fg/nm³
@@ -177,19 +179,35 @@ pub fn analyze_granulated_2012(
 ) -> FraktalResult {
     let start_time = Instant::now();
 
-    // Step 1: Smart segmentation with automatic threshold detection
-    let (binary, detected_threshold, is_dark_on_light) = smart_segment(
-        image,
-        params.pixel_min,
-        params.pixel_max,
-        params.auto_threshold,
-    );
+    // Step 1: Smart segmentation with automatic threshold detection, then
+    // the mask cleanup
+    let (binary, _threshold, _dark_on_light) = params.segment(image);
+
+    let mut result = analyze_granulated_2012_mask(binary.view(), params);
+    result.execution_time_ms = start_time.elapsed().as_millis() as u64;
+    result
+}
+
+/// Analyze a stack of images in parallel, one result per image in order.
+pub fn analyze_granulated_2012_batch(
+    images: &[Array2<u8>],
+    params: &Granulated2012Params,
+) -> Vec<FraktalResult> {
+    images
+        .par_iter()
+        .map(|image| analyze_granulated_2012(image.view(), params))
+        .collect()
+}
 
-    // Debug info available via detected_threshold and is_dark_on_light
-    let _ = (detected_threshold, is_dark_on_light); // Mark as intentionally unused
+/// Analyze an already segmented mask (foreground = aggregate) with the same model.
+pub fn analyze_granulated_2012_mask(
+    binary: ArrayView2<bool>,
+    params: &Granulated2012Params,
+) -> FraktalResult {
+    let start_time = Instant::now();
 
     // Step 2: Calculate geometry
-    let geometry = match calculate_geometry(binary.view(), params.npix, params.escala) {
+    let geometry = match calculate_geometry(binary, params.npix, params.escala) {
         Some(g) => g,
         None => {
             return FraktalResult {
@@ -201,11 +219,17 @@ pub fn analyze_granulated_2012(
         }
     };
 
-    // Step 2b: Estimate particle count and dpo visually using adaptive detection
+    // Step 2b: Estimate particle count and dpo visually with the chosen detector
     let (npo_visual, dpo_estimated, _avg_radius_px) = estimate_particles_and_dpo(
-        binary.view(),
+        binary,
         geometry.length_per_pixel,
+        params.npo_method(),
     );
+    let dpo_distribution = if params.size_distribution {
+        edm_sbs(binary, geometry.length_per_pixel)
+    } else {
+        None
+    };
 
     // Step 3: Apply 3D correction if enabled
     let m = calculate_m_exponent(params.correction_3d, true, params.delta);
@@ -249,6 +273,8 @@ pub fn analyze_granulated_2012(
     // If !converged, we return early with error status before npo_final is used.
     let mut npo_final = 0.0;
     let mut converged = false;
+    // (npo, Df, kf) of every outer iteration, across the initial estimates
+    let mut history: Vec<[f64; 3]> = Vec::new();
     let tolerance = 0.0001;
     let max_outer_iterations = 50;
 
@@ -304,6 +330,7 @@ pub fn analyze_granulated_2012(
             if new_npo <= 0.0 || !new_npo.is_finite() {
                 break; // Try next initial estimate
             }
+            history.push([new_npo, result.df, result.kf]);
 
             // Check convergence
             if (result.df - df_result).abs() < tolerance && outer_iter > 0 {
@@ -327,6 +354,7 @@ pub fn analyze_granulated_2012(
             ap,
             npo_visual: npo_visual as u64,
             dpo_estimated,
+            dpo_distribution,
             status: if df_result == 0.0 {
                 FraktalStatus::DfOutOfRange
             } else {
@@ -334,6 +362,7 @@ pub fn analyze_granulated_2012(
             },
             execution_time_ms: start_time.elapsed().as_millis() as u64,
             model: "granulated_2012".to_string(),
+            history,
             ..Default::default()
         };
     }
@@ -359,9 +388,11 @@ pub fn analyze_granulated_2012(
             npo_ratio,
             npo_aligned,
             dpo_estimated,
+            dpo_distribution,
             status: FraktalStatus::NpoTooSmall,
             execution_time_ms: start_time.elapsed().as_millis() as u64,
             model: "granulated_2012".to_string(),
+            history,
             ..Default::default()
         };
     }
@@ -406,9 +437,12 @@ pub fn analyze_granulated_2012(
         npo_ratio,
         npo_aligned,
         dpo_estimated,
+        dpo_distribution,
         status: FraktalStatus::Success,
         execution_time_ms: start_time.elapsed().as_millis() as u64,
         model: "granulated_2012".to_string(),
+        history,
+        diagnostics: None,
     }
 }
 
@@ -416,6 +450,47 @@ pub fn analyze_granulated_2012(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_batch_matches_single_frames() {
+        // Frames of dark squares of growing size on a light background
+        let frames: Vec<Array2<u8>> = [8, 14, 20]
+            .iter()
+            .map(|&side| {
+                Array2::from_shape_fn((48, 48), |(i, j)| {
+                    if (10..10 + side).contains(&i) && (12..12 + side).contains(&j) {
+                        40
+                    } else {
+                        220
+                    }
+                })
+            })
+            .collect();
+        let params = Granulated2012Params::default();
+
+        let batch = analyze_granulated_2012_batch(&frames, &params);
+        assert_eq!(batch.len(), frames.len());
+        for (result, frame) in batch.iter().zip(&frames) {
+            let single = analyze_granulated_2012(frame.view(), &params);
+            assert_eq!(result.status, single.status);
+            assert_eq!(result.ap, single.ap);
+            assert_eq!(result.rg, single.rg);
+            assert_eq!(result.df, single.df);
+            assert_eq!(result.npo, single.npo);
+        }
+        assert!(batch[0].ap < batch[1].ap && batch[1].ap < batch[2].ap);
+        assert!(analyze_granulated_2012_batch(&[], &params).is_empty());
+
+        // The EDM-SBS size distribution is only measured on request
+        assert!(batch.iter().all(|r| r.dpo_distribution.is_none()));
+        let sized = Granulated2012Params {
+            size_distribution: true,
+            ..params
+        };
+        let result = analyze_granulated_2012(frames[2].view(), &sized);
+        assert!(result.dpo_distribution.is_some());
+        assert_eq!(result.df, batch[2].df);
+    }
+
     #[test]
     fn test_alfa() {
         // Test with delta=1.1, J=2
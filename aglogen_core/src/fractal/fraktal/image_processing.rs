@@ -5,6 +5,8 @@
 
 use ndarray::{Array2, ArrayView2};
 
+use super::detection::NpoMethod;
+
 /// Result of image geometry analysis.
 #[derive(Debug, Clone)]
 pub struct ImageGeometry {
@@ -124,6 +126,114 @@ pub fn is_dark_on_light(image: ArrayView2<u8>, threshold: u8) -> bool {
     dark_ratio < 0.5
 }
 
+/// Sauvola sensitivity k.
+const SAUVOLA_K: f64 = 0.2;
+
+/// Dynamic range R of the standard deviation in Sauvola's formula (8-bit images).
+const SAUVOLA_R: f64 = 128.0;
+
+/// Niblack sensitivity k (for dark objects).
+const NIBLACK_K: f64 = -0.2;
+
+/// Local standard deviation, in gray levels, below which Niblack takes a
+/// window as flat and compares its pixels with the global threshold.
+const NIBLACK_MIN_CONTRAST: f64 = 4.0;
+
+/// How `smart_segment` thresholds an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThresholdMethod {
+    /// One global threshold for the whole image
+    #[default]
+    Otsu,
+    /// Local threshold m (1 + k (s / R - 1)) over a `window` x `window` neighbourhood
+    Sauvola { window: usize },
+    /// Local threshold m + k s over a `window` x `window` neighbourhood
+    Niblack { window: usize },
+}
+
+impl ThresholdMethod {
+    /// Method called `name` ("otsu", "sauvola" or "niblack"), local ones over `window` pixels.
+    pub fn from_name(name: &str, window: usize) -> Option<Self> {
+        match name {
+            "otsu" => Some(Self::Otsu),
+            "sauvola" => Some(Self::Sauvola { window }),
+            "niblack" => Some(Self::Niblack { window }),
+            _ => None,
+        }
+    }
+}
+
+/// Mean and standard deviation of the `window` x `window` neighbourhood of
+/// every pixel, clipped at the image border.
+///
+/// Summed-area tables of the values and their squares make it O(1) per pixel.
+fn local_statistics(image: ArrayView2<u8>, window: usize) -> (Array2<f64>, Array2<f64>) {
+    let (height, width) = image.dim();
+    let mut sum = Array2::<f64>::zeros((height + 1, width + 1));
+    let mut sum_sq = Array2::<f64>::zeros((height + 1, width + 1));
+    for i in 0..height {
+        for j in 0..width {
+            let v = image[[i, j]] as f64;
+            sum[[i + 1, j + 1]] = v + sum[[i, j + 1]] + sum[[i + 1, j]] - sum[[i, j]];
+            sum_sq[[i + 1, j + 1]] = v * v + sum_sq[[i, j + 1]] + sum_sq[[i + 1, j]] - sum_sq[[i, j]];
+        }
+    }
+
+    let half = window / 2;
+    let mut mean = Array2::zeros((height, width));
+    let mut std = Array2::zeros((height, width));
+    for i in 0..height {
+        let (top, bottom) = (i.saturating_sub(half), (i + half + 1).min(height));
+        for j in 0..width {
+            let (left, right) = (j.saturating_sub(half), (j + half + 1).min(width));
+            let box_sum = |t: &Array2<f64>| t[[bottom, right]] - t[[top, right]] - t[[bottom, left]] + t[[top, left]];
+            let n = ((bottom - top) * (right - left)) as f64;
+            let m = box_sum(&sum) / n;
+            mean[[i, j]] = m;
+            std[[i, j]] = (box_sum(&sum_sq) / n - m * m).max(0.0).sqrt();
+        }
+    }
+    (mean, std)
+}
+
+/// Segment objects darker (or lighter) than their neighbourhood.
+///
+/// Light objects are thresholded on the inverted image, so both formulas
+/// always look for dark objects. A pixel is an object when it is strictly
+/// below its threshold. Niblack's threshold equals the mean of a flat
+/// window, so windows without contrast (flat background or the inside of a
+/// large object) are compared with the global threshold `global` instead.
+fn local_threshold_segment(
+    image: ArrayView2<u8>,
+    method: ThresholdMethod,
+    global: u8,
+    dark_on_light: bool,
+    pixel_min: u8,
+    pixel_max: u8,
+    shift: i16,
+) -> Array2<bool> {
+    let oriented = if dark_on_light { image.to_owned() } else { image.mapv(|v| 255 - v) };
+    let window = match method {
+        ThresholdMethod::Sauvola { window } | ThresholdMethod::Niblack { window } => window,
+        ThresholdMethod::Otsu => unreachable!("Otsu is a global threshold"),
+    };
+    let (mean, std) = local_statistics(oriented.view(), window);
+    // Objects sit at or below `global`, i.e. strictly below this in the oriented image
+    let global = if dark_on_light { global as f64 + 1.0 } else { 255.0 - global as f64 };
+
+    let mut binary = Array2::from_elem(image.dim(), false);
+    for ((index, &v), (&m, &s)) in oriented.indexed_iter().zip(mean.iter().zip(std.iter())) {
+        let threshold = match method {
+            ThresholdMethod::Sauvola { .. } => m * (1.0 + SAUVOLA_K * (s / SAUVOLA_R - 1.0)),
+            _ if s < NIBLACK_MIN_CONTRAST => global,
+            _ => m + NIBLACK_K * s,
+        } + shift as f64;
+        let raw = image[index];
+        binary[index] = (v as f64) < threshold && raw >= pixel_min && raw <= pixel_max;
+    }
+    binary
+}
+
 /// Smart segmentation with automatic threshold detection.
 ///
 /// Automatically detects:
@@ -131,16 +241,28 @@ pub fn is_dark_on_light(image: ArrayView2<u8>, threshold: u8) -> bool {
 /// 2. Whether particles are dark or light
 /// 3. Applies appropriate segmentation
 ///
+/// With a local `method` (Sauvola or Niblack) the global Otsu threshold
+/// only decides whether particles are dark or light, and each pixel is
+/// compared with a threshold from its own neighbourhood, which copes with
+/// the uneven background illumination of many TEM images.
+///
+/// `shift` moves the threshold that many gray levels towards the
+/// background, so a positive shift grows the objects and a negative one
+/// shrinks them (0 for the detected threshold). Without `auto_threshold`
+/// the shift moves `pixel_max`.
+///
 /// Returns (binary_mask, detected_threshold, is_inverted)
 pub fn smart_segment(
     image: ArrayView2<u8>,
     pixel_min: u8,
     pixel_max: u8,
     auto_threshold: bool,
+    method: ThresholdMethod,
+    shift: i16,
 ) -> (Array2<bool>, u8, bool) {
     if !auto_threshold {
-        // Use manual thresholds as-is
-        let binary = color_segment(image, pixel_min, pixel_max);
+        // Use manual thresholds, pixel_max moved by the shift
+        let binary = color_segment(image, pixel_min, shift_level(pixel_max, shift));
         return (binary, pixel_max, false);
     }
 
@@ -150,20 +272,30 @@ pub fn smart_segment(
     // Detect if dark-on-light
     let dark_on_light = is_dark_on_light(image, otsu);
 
+    if method != ThresholdMethod::Otsu {
+        let binary = local_threshold_segment(image, method, otsu, dark_on_light, pixel_min, pixel_max, shift);
+        return (binary, otsu, dark_on_light);
+    }
+
     let binary = if dark_on_light {
         // Dark particles: select pixels BELOW threshold
         // Add small margin to avoid edge artifacts
-        let effective_threshold = otsu.saturating_add(10).min(pixel_max);
+        let effective_threshold = shift_level(otsu.saturating_add(10), shift).min(pixel_max);
         image.mapv(|v| v >= pixel_min && v <= effective_threshold)
     } else {
         // Light particles: select pixels ABOVE threshold
-        let effective_threshold = otsu.saturating_sub(10).max(pixel_min);
+        let effective_threshold = shift_level(otsu.saturating_sub(10), -shift).max(pixel_min);
         image.mapv(|v| v >= effective_threshold && v <= pixel_max)
     };
 
     (binary, otsu, dark_on_light)
 }
 
+/// Gray level `v` moved by `shift`, clamped to 0-255.
+fn shift_level(v: u8, shift: i16) -> u8 {
+    (v as i16 + shift).clamp(0, 255) as u8
+}
+
 /// Convert RGB image to grayscale.
 ///
 /// Uses standard luminosity formula: 0.299*R + 0.587*G + 0.114*B
@@ -279,7 +411,7 @@ pub fn calculate_m_exponent(correction_3d: bool, granulated: bool, delta: f64) -
 ///
 /// For each foreground pixel, computes the distance to the nearest background pixel.
 /// Uses two-pass algorithm for efficiency.
-fn compute_distance_transform(binary: ArrayView2<bool>) -> Array2<f64> {
+pub(crate) fn compute_distance_transform(binary: ArrayView2<bool>) -> Array2<f64> {
     let (rows, cols) = binary.dim();
     let mut distance = Array2::<f64>::zeros((rows, cols));
 
@@ -337,16 +469,17 @@ fn compute_distance_transform(binary: ArrayView2<bool>) -> Array2<f64> {
     distance
 }
 
-/// Estimate number of primary particles using adaptive scale detection.
+/// Detect the primary particles using adaptive scale detection.
 ///
-/// This provides a visual estimate of particle count by:
+/// This provides a visual estimate of the particles by:
 /// 1. Computing distance transform (distance from each pixel to background)
 /// 2. Finding ALL local maxima with minimal threshold
 /// 3. Auto-detecting particle radius from peak distance values
 /// 4. Applying non-maximum suppression with detected radius
 ///
-/// Returns (npo_estimate, average_particle_radius_px)
-pub fn estimate_particle_count_adaptive(binary: ArrayView2<bool>) -> (usize, f64) {
+/// Returns the particle centers as (row, col, radius_px) and the average
+/// particle radius in pixels.
+pub fn detect_particles(binary: ArrayView2<bool>) -> (Vec<(usize, usize, f64)>, f64) {
     let (rows, cols) = binary.dim();
 
     // Step 1: Compute distance transform
@@ -390,7 +523,7 @@ pub fn estimate_particle_count_adaptive(binary: ArrayView2<bool>) -> (usize, f64
     }
 
     if all_peaks.is_empty() {
-        return (0, 0.0);
+        return (Vec::new(), 0.0);
     }
 
     // Step 3: Auto-detect particle radius from peak distance values
@@ -442,28 +575,32 @@ pub fn estimate_particle_count_adaptive(binary: ArrayView2<bool>) -> (usize, f64
         final_peaks.iter().map(|p| p.2).sum::<f64>() / final_peaks.len() as f64
     };
 
-    (final_peaks.len(), avg_radius)
+    (final_peaks, avg_radius)
 }
 
 /// Estimate particles and primary particle diameter from image.
 ///
+/// The particles are detected with `method`.
+///
 /// Returns (npo_visual, estimated_dpo_nm, avg_radius_px)
 pub fn estimate_particles_and_dpo(
     binary: ArrayView2<bool>,
     length_per_pixel: f64,
+    method: NpoMethod,
 ) -> (usize, f64, f64) {
-    let (count, avg_radius_px) = estimate_particle_count_adaptive(binary);
+    let (particles, avg_radius_px) = method.detect(binary);
     let estimated_dpo_nm = 2.0 * avg_radius_px * length_per_pixel;
-    (count, estimated_dpo_nm, avg_radius_px)
+    (particles.len(), estimated_dpo_nm, avg_radius_px)
 }
 
 /// Legacy function for backward compatibility.
-/// Calls the adaptive version but ignores the min_particle_radius_px parameter.
+/// Counts the `detect_particles` peaks but ignores the min_particle_radius_px parameter.
 pub fn estimate_particle_count(
     binary: ArrayView2<bool>,
     _min_particle_radius_px: f64,
 ) -> (usize, f64) {
-    estimate_particle_count_adaptive(binary)
+    let (peaks, avg_radius) = detect_particles(binary);
+    (peaks.len(), avg_radius)
 }
 
 #[cfg(test)]
@@ -492,6 +629,56 @@ mod tests {
         assert!(!binary[[2, 2]]); // 241 > 240
     }
 
+    #[test]
+    fn test_local_threshold_handles_uneven_illumination() {
+        // Background brightening from 90 to 230 across the image, with dark
+        // discs 50 levels below the local background
+        let (height, width) = (60, 200);
+        let centers: Vec<(f64, f64)> = (0..6).map(|k| (30.0, 15.0 + 34.0 * k as f64)).collect();
+        let in_disc = |i: usize, j: usize| {
+            centers.iter().any(|&(ci, cj)| (i as f64 - ci).powi(2) + (j as f64 - cj).powi(2) <= 25.0)
+        };
+        let image = Array2::from_shape_fn((height, width), |(i, j)| {
+            let background = 90.0 + 140.0 * j as f64 / (width - 1) as f64;
+            (if in_disc(i, j) { background - 50.0 } else { background }) as u8
+        });
+        let truth = Array2::from_shape_fn((height, width), |(i, j)| in_disc(i, j));
+        let errors = |binary: &Array2<bool>| binary.iter().zip(truth.iter()).filter(|(a, b)| a != b).count();
+
+        let (otsu, _, dark) = smart_segment(image.view(), 0, 255, true, ThresholdMethod::Otsu, 0);
+        let (sauvola, _, _) =
+            smart_segment(image.view(), 0, 255, true, ThresholdMethod::from_name("sauvola", 25).unwrap(), 0);
+        assert!(dark);
+        // Otsu cuts the gradient instead of the particles
+        assert!(errors(&otsu) > 1000, "{}", errors(&otsu));
+        assert!(errors(&sauvola) < 50, "{}", errors(&sauvola));
+        assert_eq!(ThresholdMethod::from_name("bernsen", 25), None);
+    }
+
+    #[test]
+    fn test_niblack_keeps_flat_background() {
+        // Uniform background with dark discs larger than the window
+        let in_disc = |i: usize, j: usize| {
+            [(20.0, 20.0), (20.0, 60.0)].iter().any(|&(ci, cj): &(f64, f64)| {
+                (i as f64 - ci).powi(2) + (j as f64 - cj).powi(2) <= 144.0
+            })
+        };
+        let image = Array2::from_shape_fn((40, 80), |(i, j)| if in_disc(i, j) { 60u8 } else { 200 });
+        let truth = Array2::from_shape_fn((40, 80), |(i, j)| in_disc(i, j));
+        let niblack = ThresholdMethod::from_name("niblack", 7).unwrap();
+
+        for (image, dark) in [(image.clone(), true), (image.mapv(|v| 255 - v), false)] {
+            let (binary, _, detected_dark) = smart_segment(image.view(), 0, 255, true, niblack, 0);
+            assert_eq!(detected_dark, dark);
+            // No background pixel is taken, the flat inside of the discs is
+            let false_positives = binary.iter().zip(truth.iter()).filter(|&(&b, &t)| b && !t).count();
+            let found = binary.iter().zip(truth.iter()).filter(|&(&b, &t)| b && t).count();
+            assert_eq!(false_positives, 0);
+            assert!(binary[[20, 20]] && binary[[20, 60]]);
+            assert!(found as f64 > 0.9 * truth.iter().filter(|&&t| t).count() as f64, "{}", found);
+        }
+    }
+
     #[test]
     fn test_geometry_single_pixel() {
         let binary = arr2(&[
@@ -521,7 +708,7 @@ mod tests {
     }
 
     #[test]
-    fn test_estimate_particle_count_adaptive_single_particle() {
+    fn test_detect_particles_single_particle() {
         // Create a binary image with a single circular-ish particle
         // 15x15 image with a circle of radius ~5 in the center
         let mut binary = ndarray::Array2::<bool>::from_elem((15, 15), false);
@@ -539,7 +726,7 @@ mod tests {
             }
         }
 
-        let (count, avg_radius) = estimate_particle_count_adaptive(binary.view());
+        let (count, avg_radius) = estimate_particle_count(binary.view(), 0.0);
 
         // Should detect exactly 1 particle
         assert_eq!(count, 1, "Should detect single particle");
@@ -553,7 +740,7 @@ mod tests {
     }
 
     #[test]
-    fn test_estimate_particle_count_adaptive_multiple_particles() {
+    fn test_detect_particles_multiple_particles() {
         // Create a binary image with 3 well-separated particles
         let mut binary = ndarray::Array2::<bool>::from_elem((40, 40), false);
         let radius = 4.0;
@@ -572,7 +759,7 @@ mod tests {
             }
         }
 
-        let (count, _avg_radius) = estimate_particle_count_adaptive(binary.view());
+        let (count, _avg_radius) = estimate_particle_count(binary.view(), 0.0);
 
         // Should detect 3 particles
         assert_eq!(count, 3, "Should detect 3 separate particles");
@@ -598,7 +785,7 @@ mod tests {
 
         let length_per_pixel = 10.0; // 10 nm per pixel
         let (count, estimated_dpo, avg_radius) =
-            estimate_particles_and_dpo(binary.view(), length_per_pixel);
+            estimate_particles_and_dpo(binary.view(), length_per_pixel, NpoMethod::DistancePeaks);
 
         assert_eq!(count, 1, "Should detect 1 particle");
 
@@ -614,9 +801,9 @@ mod tests {
     }
 
     #[test]
-    fn test_estimate_particle_count_adaptive_empty_image() {
+    fn test_detect_particles_empty_image() {
         let binary = ndarray::Array2::<bool>::from_elem((20, 20), false);
-        let (count, avg_radius) = estimate_particle_count_adaptive(binary.view());
+        let (count, avg_radius) = estimate_particle_count(binary.view(), 0.0);
 
         assert_eq!(count, 0, "Empty image should have 0 particles");
         assert_eq!(avg_radius, 0.0, "Empty image should have 0 radius");
@@ -10,11 +10,20 @@
 pub mod params;
 pub mod result;
 pub mod image_processing;
+pub mod morphology;
+pub mod sizing;
 pub mod bisection;
+pub mod detection;
+pub mod components;
+pub mod diagnostics;
+pub mod uncertainty;
 pub mod granulated_2012;
 pub mod voxel_2018;
 
-pub use params::{Granulated2012Params, Voxel2018Params};
+pub use params::{Granulated2012Params, MorphologyParams, UncertaintyParams, Voxel2018Params};
 pub use result::PyFraktalResult;
-pub use granulated_2012::analyze_granulated_2012;
+pub use granulated_2012::{analyze_granulated_2012, analyze_granulated_2012_batch};
 pub use voxel_2018::analyze_voxel_2018;
+pub use components::{analyze_components, FraktalModel};
+pub use diagnostics::{analyze_with_diagnostics, PyFraktalDiagnostics};
+pub use uncertainty::{granulated_2012_uncertainty, PyFraktalUncertainty};
@@ -0,0 +1,182 @@
+//! Binary morphology for cleaning segmented TEM masks.
+//!
+//! Thresholding noisy TEM images leaves speckles in the background, pinholes
+//! inside the aggregates and ragged outlines, all of which inflate or bias
+//! the projected area the FRAKTAL particle count rests on. The operations
+//! use a disk of radius r as structuring element:
+//!
+//! - erosion keeps the pixels whose whole disk is foreground
+//! - dilation adds the pixels whose disk touches the foreground
+//! - opening (erosion then dilation) removes features thinner than the disk
+//! - closing (dilation then erosion) bridges gaps narrower than the disk
+//!
+//! Pixels outside the image count as background.
+
+use ndarray::{Array2, ArrayView2};
+
+/// Offsets of the pixels of a disk of radius `radius`.
+fn disk(radius: usize) -> Vec<(isize, isize)> {
+    let r = radius as isize;
+    let mut offsets = Vec::new();
+    for di in -r..=r {
+        for dj in -r..=r {
+            if di * di + dj * dj <= r * r {
+                offsets.push((di, dj));
+            }
+        }
+    }
+    offsets
+}
+
+/// Whether any (`any = true`) or every disk pixel around (i, j) is foreground.
+fn probe(binary: ArrayView2<bool>, i: usize, j: usize, offsets: &[(isize, isize)], any: bool) -> bool {
+    let (rows, cols) = binary.dim();
+    let at = |&(di, dj): &(isize, isize)| {
+        let (ni, nj) = (i as isize + di, j as isize + dj);
+        ni >= 0 && nj >= 0 && ni < rows as isize && nj < cols as isize && binary[[ni as usize, nj as usize]]
+    };
+    if any {
+        offsets.iter().any(at)
+    } else {
+        offsets.iter().all(at)
+    }
+}
+
+/// Erode the foreground with a disk of radius `radius`.
+pub fn erode(binary: ArrayView2<bool>, radius: usize) -> Array2<bool> {
+    let offsets = disk(radius);
+    Array2::from_shape_fn(binary.dim(), |(i, j)| binary[[i, j]] && probe(binary, i, j, &offsets, false))
+}
+
+/// Dilate the foreground with a disk of radius `radius`.
+pub fn dilate(binary: ArrayView2<bool>, radius: usize) -> Array2<bool> {
+    let offsets = disk(radius);
+    Array2::from_shape_fn(binary.dim(), |(i, j)| binary[[i, j]] || probe(binary, i, j, &offsets, true))
+}
+
+/// Morphological opening: erosion followed by dilation.
+pub fn open(binary: ArrayView2<bool>, radius: usize) -> Array2<bool> {
+    dilate(erode(binary, radius).view(), radius)
+}
+
+/// Morphological closing: dilation followed by erosion.
+pub fn close(binary: ArrayView2<bool>, radius: usize) -> Array2<bool> {
+    erode(dilate(binary, radius).view(), radius)
+}
+
+/// Fill the holes of the foreground: background not 4-connected to the image border.
+pub fn fill_holes(binary: ArrayView2<bool>) -> Array2<bool> {
+    let (rows, cols) = binary.dim();
+    let mut outside = Array2::from_elem((rows, cols), false);
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for i in 0..rows {
+        for j in 0..cols {
+            let border = i == 0 || j == 0 || i + 1 == rows || j + 1 == cols;
+            if border && !binary[[i, j]] {
+                outside[[i, j]] = true;
+                stack.push((i, j));
+            }
+        }
+    }
+    while let Some((i, j)) = stack.pop() {
+        let neighbours = [
+            (i.wrapping_sub(1), j),
+            (i + 1, j),
+            (i, j.wrapping_sub(1)),
+            (i, j + 1),
+        ];
+        for (ni, nj) in neighbours {
+            if ni < rows && nj < cols && !binary[[ni, nj]] && !outside[[ni, nj]] {
+                outside[[ni, nj]] = true;
+                stack.push((ni, nj));
+            }
+        }
+    }
+    outside.mapv(|o| !o)
+}
+
+/// Label the 8-connected foreground components.
+///
+/// Returns the label image (0 = background, components numbered from 1 in
+/// raster order of their first pixel) and the pixel count of each component.
+pub fn label_components(binary: ArrayView2<bool>) -> (Array2<u32>, Vec<usize>) {
+    let (rows, cols) = binary.dim();
+    let mut labels = Array2::<u32>::zeros((rows, cols));
+    let mut areas = Vec::new();
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for i in 0..rows {
+        for j in 0..cols {
+            if !binary[[i, j]] || labels[[i, j]] != 0 {
+                continue;
+            }
+            let label = areas.len() as u32 + 1;
+            let mut area = 0;
+            labels[[i, j]] = label;
+            stack.push((i, j));
+            while let Some((ci, cj)) = stack.pop() {
+                area += 1;
+                for ni in ci.saturating_sub(1)..(ci + 2).min(rows) {
+                    for nj in cj.saturating_sub(1)..(cj + 2).min(cols) {
+                        if binary[[ni, nj]] && labels[[ni, nj]] == 0 {
+                            labels[[ni, nj]] = label;
+                            stack.push((ni, nj));
+                        }
+                    }
+                }
+            }
+            areas.push(area);
+        }
+    }
+    (labels, areas)
+}
+
+/// Remove the 8-connected components smaller than `min_area` pixels.
+pub fn remove_small_objects(binary: ArrayView2<bool>, min_area: usize) -> Array2<bool> {
+    let (labels, areas) = label_components(binary);
+    labels.mapv(|label| label != 0 && areas[label as usize - 1] >= min_area)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_morphology_cleans_noisy_mask() {
+        // A 21x21 square with a pinhole and a ragged spur, plus background speckles
+        let mut mask = Array2::from_elem((40, 40), false);
+        for i in 10..31 {
+            for j in 10..31 {
+                mask[[i, j]] = true;
+            }
+        }
+        mask[[20, 20]] = false;
+        for j in 31..34 {
+            mask[[20, j]] = true;
+        }
+        mask[[3, 3]] = true;
+        mask[[35, 5]] = true;
+        let square = 21 * 21;
+        let count = |m: &Array2<bool>| m.iter().filter(|&&v| v).count();
+
+        let (labels, areas) = label_components(mask.view());
+        assert_eq!(areas, vec![1, square + 2, 1]);
+        assert_eq!(labels[[3, 3]], 1);
+
+        let filled = fill_holes(mask.view());
+        assert!(filled[[20, 20]] && !filled[[0, 0]]);
+        let cleaned = remove_small_objects(filled.view(), 5);
+        assert_eq!(count(&cleaned), square + 3);
+
+        // The radius-1 disk is a plus: erosion peels one pixel off each side
+        // of the square, except where the spur is rooted
+        let eroded = erode(cleaned.view(), 1);
+        assert_eq!(count(&eroded), 19 * 19 + 1);
+        // Dilating it back cuts the spur to a stub and rounds the corners
+        let opened = open(cleaned.view(), 1);
+        assert_eq!(opened, dilate(eroded.view(), 1));
+        assert!(opened[[20, 31]] && !opened[[20, 32]] && !opened[[20, 33]]);
+        assert_eq!(count(&opened), square - 4 + 1);
+        // Closing bridges the pinhole
+        assert!(close(mask.view(), 1)[[20, 20]]);
+    }
+}
@@ -1,7 +1,79 @@
 //! FRAKTAL analysis parameters.
 
+use ndarray::{Array2, ArrayView2};
 use pyo3::prelude::*;
 
+use crate::common::validation::{check_count, check_in_range, check_positive};
+
+use super::detection::NpoMethod;
+use super::image_processing::{smart_segment, ThresholdMethod};
+use super::morphology::{close, fill_holes, open, remove_small_objects};
+
+/// Cleanup of the segmented mask before the geometry is measured.
+///
+/// The steps run in a fixed order: closing, hole filling, opening, then
+/// removal of small objects. Every step is off by default.
+#[pyclass]
+#[derive(Debug, Clone, Default)]
+pub struct MorphologyParams {
+    /// Radius in pixels of the closing that bridges narrow gaps (0 = none)
+    #[pyo3(get, set)]
+    pub closing_radius: usize,
+
+    /// Fill background regions enclosed by the aggregate
+    #[pyo3(get, set)]
+    pub fill_holes: bool,
+
+    /// Radius in pixels of the opening that removes thin spurs and noise (0 = none)
+    #[pyo3(get, set)]
+    pub opening_radius: usize,
+
+    /// Remove 8-connected objects of fewer pixels than this (0 = keep all)
+    #[pyo3(get, set)]
+    pub min_object_area: usize,
+}
+
+#[pymethods]
+impl MorphologyParams {
+    #[new]
+    #[pyo3(signature = (closing_radius=0, fill_holes=false, opening_radius=0, min_object_area=0))]
+    pub fn new(closing_radius: usize, fill_holes: bool, opening_radius: usize, min_object_area: usize) -> Self {
+        Self {
+            closing_radius,
+            fill_holes,
+            opening_radius,
+            min_object_area,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MorphologyParams(closing_radius={}, fill_holes={}, opening_radius={}, min_object_area={})",
+            self.closing_radius, self.fill_holes, self.opening_radius, self.min_object_area
+        )
+    }
+}
+
+impl MorphologyParams {
+    /// Clean a segmented mask.
+    pub fn apply(&self, binary: ArrayView2<bool>) -> Array2<bool> {
+        let mut mask = binary.to_owned();
+        if self.closing_radius > 0 {
+            mask = close(mask.view(), self.closing_radius);
+        }
+        if self.fill_holes {
+            mask = fill_holes(mask.view());
+        }
+        if self.opening_radius > 0 {
+            mask = open(mask.view(), self.opening_radius);
+        }
+        if self.min_object_area > 0 {
+            mask = remove_small_objects(mask.view(), self.min_object_area);
+        }
+        mask
+    }
+}
+
 /// Parameters for the 2012 granulated particle model.
 ///
 /// This model is designed for soot/agglomerates with spherical primary particles.
@@ -46,12 +118,41 @@ pub struct Granulated2012Params {
     /// and adjusts segmentation accordingly.
     #[pyo3(get, set)]
     pub auto_threshold: bool,
+
+    /// Thresholding of the automatic segmentation: "otsu" (global), "sauvola"
+    /// or "niblack" (local, for uneven background illumination) (default: "otsu")
+    #[pyo3(get, set)]
+    pub threshold_method: String,
+
+    /// Side in pixels of the neighbourhood of the local thresholds, larger
+    /// than the primary particles (default: 25)
+    #[pyo3(get, set)]
+    pub threshold_window: usize,
+
+    /// Cleanup of the segmented mask (default: None, used as segmented)
+    #[pyo3(get, set)]
+    pub morphology: Option<MorphologyParams>,
+
+    /// Detector of the visual primary particle count: "distance_peaks",
+    /// "watershed" or "hough" (default: "distance_peaks")
+    #[pyo3(get, set)]
+    pub npo_method: String,
+
+    /// (min, max) radius in pixels of the circles of the "hough" detector
+    /// (default: (3, 30))
+    #[pyo3(get, set)]
+    pub hough_radius_range: (usize, usize),
+
+    /// Measure the primary particle size distribution with EDM-SBS, one
+    /// distance transform per pixel of the widest primary (default: false)
+    #[pyo3(get, set)]
+    pub size_distribution: bool,
 }
 
 #[pymethods]
 impl Granulated2012Params {
     #[new]
-    #[pyo3(signature = (npix, dpo, delta=1.1, correction_3d=false, pixel_min=10, pixel_max=240, npo_limit=5, escala=100.0, auto_threshold=true))]
+    #[pyo3(signature = (npix, dpo, delta=1.1, correction_3d=false, pixel_min=10, pixel_max=240, npo_limit=5, escala=100.0, auto_threshold=true, threshold_method="otsu".to_string(), threshold_window=25, morphology=None, npo_method="distance_peaks".to_string(), hough_radius_range=(3, 30), size_distribution=false))]
     pub fn new(
         npix: f64,
         dpo: f64,
@@ -62,6 +163,12 @@ impl Granulated2012Params {
         npo_limit: usize,
         escala: f64,
         auto_threshold: bool,
+        threshold_method: String,
+        threshold_window: usize,
+        morphology: Option<MorphologyParams>,
+        npo_method: String,
+        hough_radius_range: (usize, usize),
+        size_distribution: bool,
     ) -> Self {
         Self {
             npix,
@@ -73,10 +180,59 @@ impl Granulated2012Params {
             npo_limit,
             escala,
             auto_threshold,
+            threshold_method,
+            threshold_window,
+            morphology,
+            npo_method,
+            hough_radius_range,
+            size_distribution,
         }
     }
 }
 
+impl Granulated2012Params {
+    /// Check the parameters before an analysis, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_positive("npix", self.npix)?;
+        check_positive("dpo", self.dpo)?;
+        check_positive("delta", self.delta)?;
+        check_positive("escala", self.escala)?;
+        check_threshold(&self.threshold_method, self.threshold_window)?;
+        check_npo_method(&self.npo_method, self.hough_radius_range)?;
+        check_pixel_range(self.pixel_min, self.pixel_max)
+    }
+
+    /// Thresholding of the automatic segmentation (Otsu for an unknown name).
+    pub fn threshold(&self) -> ThresholdMethod {
+        ThresholdMethod::from_name(&self.threshold_method, self.threshold_window).unwrap_or_default()
+    }
+
+    /// Detector of the visual npo (distance peaks for an unknown name).
+    pub fn npo_method(&self) -> NpoMethod {
+        NpoMethod::from_name(&self.npo_method, self.hough_radius_range).unwrap_or_default()
+    }
+
+    /// Segment an image and clean up the mask.
+    ///
+    /// Returns (binary_mask, detected_threshold, is_inverted) as `smart_segment`.
+    pub fn segment(&self, image: ArrayView2<u8>) -> (Array2<bool>, u8, bool) {
+        self.segment_shifted(image, 0)
+    }
+
+    /// `segment` with the threshold moved `shift` gray levels towards the background.
+    pub fn segment_shifted(&self, image: ArrayView2<u8>, shift: i16) -> (Array2<bool>, u8, bool) {
+        segment_and_clean(
+            image,
+            self.pixel_min,
+            self.pixel_max,
+            self.auto_threshold,
+            self.threshold(),
+            self.morphology.as_ref(),
+            shift,
+        )
+    }
+}
+
 impl Default for Granulated2012Params {
     fn default() -> Self {
         Self {
@@ -89,6 +245,12 @@ impl Default for Granulated2012Params {
             npo_limit: 5,
             escala: 100.0,
             auto_threshold: true, // Enable by default
+            threshold_method: "otsu".to_string(),
+            threshold_window: 25,
+            morphology: None,
+            npo_method: "distance_peaks".to_string(),
+            hough_radius_range: (3, 30),
+            size_distribution: false,
         }
     }
 }
@@ -127,12 +289,26 @@ pub struct Voxel2018Params {
     /// Enable automatic threshold detection using Otsu's method (default: true)
     #[pyo3(get, set)]
     pub auto_threshold: bool,
+
+    /// Thresholding of the automatic segmentation: "otsu" (global), "sauvola"
+    /// or "niblack" (local, for uneven background illumination) (default: "otsu")
+    #[pyo3(get, set)]
+    pub threshold_method: String,
+
+    /// Side in pixels of the neighbourhood of the local thresholds, larger
+    /// than the primary particles (default: 25)
+    #[pyo3(get, set)]
+    pub threshold_window: usize,
+
+    /// Cleanup of the segmented mask (default: None, used as segmented)
+    #[pyo3(get, set)]
+    pub morphology: Option<MorphologyParams>,
 }
 
 #[pymethods]
 impl Voxel2018Params {
     #[new]
-    #[pyo3(signature = (npix, escala=100.0, correction_3d=false, pixel_min=10, pixel_max=240, m_exponent=1.0, auto_threshold=true))]
+    #[pyo3(signature = (npix, escala=100.0, correction_3d=false, pixel_min=10, pixel_max=240, m_exponent=1.0, auto_threshold=true, threshold_method="otsu".to_string(), threshold_window=25, morphology=None))]
     pub fn new(
         npix: f64,
         escala: f64,
@@ -141,6 +317,9 @@ impl Voxel2018Params {
         pixel_max: u8,
         m_exponent: f64,
         auto_threshold: bool,
+        threshold_method: String,
+        threshold_window: usize,
+        morphology: Option<MorphologyParams>,
     ) -> Self {
         Self {
             npix,
@@ -150,10 +329,44 @@ impl Voxel2018Params {
             pixel_max,
             m_exponent,
             auto_threshold,
+            threshold_method,
+            threshold_window,
+            morphology,
         }
     }
 }
 
+impl Voxel2018Params {
+    /// Check the parameters before an analysis, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_positive("npix", self.npix)?;
+        check_positive("escala", self.escala)?;
+        check_positive("m_exponent", self.m_exponent)?;
+        check_threshold(&self.threshold_method, self.threshold_window)?;
+        check_pixel_range(self.pixel_min, self.pixel_max)
+    }
+
+    /// Thresholding of the automatic segmentation (Otsu for an unknown name).
+    pub fn threshold(&self) -> ThresholdMethod {
+        ThresholdMethod::from_name(&self.threshold_method, self.threshold_window).unwrap_or_default()
+    }
+
+    /// Segment an image and clean up the mask.
+    ///
+    /// Returns (binary_mask, detected_threshold, is_inverted) as `smart_segment`.
+    pub fn segment(&self, image: ArrayView2<u8>) -> (Array2<bool>, u8, bool) {
+        segment_and_clean(
+            image,
+            self.pixel_min,
+            self.pixel_max,
+            self.auto_threshold,
+            self.threshold(),
+            self.morphology.as_ref(),
+            0,
+        )
+    }
+}
+
 impl Default for Voxel2018Params {
     fn default() -> Self {
         Self {
@@ -164,6 +377,150 @@ impl Default for Voxel2018Params {
             pixel_max: 240,
             m_exponent: 1.0,
             auto_threshold: true,
+            threshold_method: "otsu".to_string(),
+            threshold_window: 25,
+            morphology: None,
         }
     }
 }
+
+/// Uncertain inputs of the 2012 granulated model, for `fraktal_granulated_2012_uncertainty`.
+///
+/// Each input is taken as uniformly distributed within ± its range around
+/// the value in `Granulated2012Params`; a range of 0 keeps it fixed.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct UncertaintyParams {
+    /// Half-width in nm of the primary particle diameter dpo
+    #[pyo3(get, set)]
+    pub dpo_range: f64,
+
+    /// Half-width in pixels of the scale bar reading npix
+    #[pyo3(get, set)]
+    pub npix_range: f64,
+
+    /// Half-width in gray levels of the segmentation threshold
+    #[pyo3(get, set)]
+    pub threshold_range: u8,
+
+    /// Propagation: "monte_carlo" (random draws) or "sensitivity" (first
+    /// order, from central differences over the ranges) (default: "monte_carlo")
+    #[pyo3(get, set)]
+    pub method: String,
+
+    /// Monte Carlo draws (default: 200)
+    #[pyo3(get, set)]
+    pub n_samples: usize,
+
+    /// Seed of the Monte Carlo draws (random if None)
+    #[pyo3(get, set)]
+    pub seed: Option<u64>,
+}
+
+#[pymethods]
+impl UncertaintyParams {
+    #[new]
+    #[pyo3(signature = (dpo_range=0.0, npix_range=0.0, threshold_range=0, method="monte_carlo".to_string(), n_samples=200, seed=None))]
+    pub fn new(
+        dpo_range: f64,
+        npix_range: f64,
+        threshold_range: u8,
+        method: String,
+        n_samples: usize,
+        seed: Option<u64>,
+    ) -> Self {
+        Self {
+            dpo_range,
+            npix_range,
+            threshold_range,
+            method,
+            n_samples,
+            seed,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "UncertaintyParams(dpo_range={}, npix_range={}, threshold_range={}, method='{}', n_samples={})",
+            self.dpo_range, self.npix_range, self.threshold_range, self.method, self.n_samples
+        )
+    }
+}
+
+impl UncertaintyParams {
+    /// Check the ranges against the nominal `params`, raising `ValueError` on nonsense input.
+    pub fn validate(&self, params: &Granulated2012Params) -> PyResult<()> {
+        // The perturbed dpo and npix must stay positive
+        check_in_range("dpo_range", self.dpo_range, 0.0, params.dpo * 0.999)?;
+        check_in_range("npix_range", self.npix_range, 0.0, params.npix * 0.999)?;
+        match self.method.as_str() {
+            "monte_carlo" => check_count("n_samples", self.n_samples, 2),
+            "sensitivity" => Ok(()),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "unknown method '{}': use 'monte_carlo' or 'sensitivity'",
+                self.method
+            ))),
+        }
+    }
+}
+
+/// Smart segmentation with automatic threshold detection (moved by `shift`
+/// gray levels, see `smart_segment`), then the mask cleanup.
+fn segment_and_clean(
+    image: ArrayView2<u8>,
+    pixel_min: u8,
+    pixel_max: u8,
+    auto_threshold: bool,
+    method: ThresholdMethod,
+    morphology: Option<&MorphologyParams>,
+    shift: i16,
+) -> (Array2<bool>, u8, bool) {
+    let (binary, threshold, dark_on_light) =
+        smart_segment(image, pixel_min, pixel_max, auto_threshold, method, shift);
+    let binary = match morphology {
+        Some(morphology) => morphology.apply(binary.view()),
+        None => binary,
+    };
+    (binary, threshold, dark_on_light)
+}
+
+/// Require a known threshold method and a local window of at least 3 pixels.
+fn check_threshold(method: &str, window: usize) -> PyResult<()> {
+    if ThresholdMethod::from_name(method, window).is_none() {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "unknown threshold_method '{}': use 'otsu', 'sauvola' or 'niblack'",
+            method
+        )));
+    }
+    check_count("threshold_window", window, 3)
+}
+
+/// Require a known npo detector and a Hough radius range 1 <= min <= max.
+fn check_npo_method(method: &str, radius_range: (usize, usize)) -> PyResult<()> {
+    if NpoMethod::from_name(method, radius_range).is_none() {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "unknown npo_method '{}': use 'distance_peaks', 'watershed' or 'hough'",
+            method
+        )));
+    }
+    let (min_radius, max_radius) = radius_range;
+    check_count("hough_radius_range min", min_radius, 1)?;
+    if min_radius > max_radius {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "hough_radius_range min ({}) must not be greater than max ({})",
+            min_radius, max_radius
+        )));
+    }
+    Ok(())
+}
+
+/// Require `pixel_min <= pixel_max` for the segmentation window.
+fn check_pixel_range(pixel_min: u8, pixel_max: u8) -> PyResult<()> {
+    if pixel_min > pixel_max {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "pixel_min ({}) must not be greater than pixel_max ({})",
+            pixel_min, pixel_max
+        )));
+    }
+    Ok(())
+}
@@ -1,6 +1,13 @@
 //! FRAKTAL analysis result types.
 
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use serde::{Deserialize, Serialize};
+
+use crate::common::serialization;
+
+use super::diagnostics::{FraktalDiagnostics, PyFraktalDiagnostics};
+use super::sizing::SizeDistribution;
 
 /// Status of FRAKTAL analysis.
 #[derive(Debug, Clone, PartialEq)]
@@ -92,6 +99,15 @@ pub struct FraktalResult {
 
     /// Estimated dpo from visual particle analysis (nm)
     pub dpo_estimated: f64,
+
+    /// Primary particle size distribution from EDM-SBS (granulated model only)
+    pub dpo_distribution: Option<SizeDistribution>,
+
+    /// (npo, Df, kf) of every iteration of the Df solver
+    pub history: Vec<[f64; 3]>,
+
+    /// Intermediate results for visual QA, when requested
+    pub diagnostics: Option<FraktalDiagnostics>,
 }
 
 impl Default for FraktalResult {
@@ -114,13 +130,16 @@ impl Default for FraktalResult {
             npo_ratio: 0.0,
             npo_aligned: false,
             dpo_estimated: 0.0,
+            dpo_distribution: None,
+            history: Vec::new(),
+            diagnostics: None,
         }
     }
 }
 
 /// Python-exposed FRAKTAL analysis result.
 #[pyclass]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PyFraktalResult {
     /// Radius of gyration in nm
     #[pyo3(get)]
@@ -152,6 +171,7 @@ pub struct PyFraktalResult {
 
     /// Coordination index Jf (None for voxel model)
     #[pyo3(get)]
+    #[serde(default)]
     pub jf: Option<f64>,
 
     /// Volume in nm³
@@ -193,6 +213,72 @@ pub struct PyFraktalResult {
     /// Estimated dpo from visual particle analysis (nm)
     #[pyo3(get)]
     pub dpo_estimated: f64,
+
+    /// Geometric mean primary particle diameter from EDM-SBS (nm, granulated
+    /// model with `size_distribution` only)
+    #[pyo3(get)]
+    #[serde(default)]
+    pub dpo_geometric_mean: Option<f64>,
+
+    /// Geometric standard deviation of the EDM-SBS primary particle diameters
+    #[pyo3(get)]
+    #[serde(default)]
+    pub dpo_geometric_std: Option<f64>,
+
+    /// Diameter bin edges of the EDM-SBS histogram (nm, empty without one)
+    #[pyo3(get)]
+    pub dpo_histogram_edges: Vec<f64>,
+
+    /// Number fraction of primary particles per EDM-SBS histogram bin
+    #[pyo3(get)]
+    pub dpo_histogram_fractions: Vec<f64>,
+
+    /// Tag of the `AnalysisSession` that produced this result, if any
+    #[pyo3(get)]
+    #[serde(default)]
+    pub session: Option<String>,
+
+    /// Bounding box (row_min, col_min, row_max, col_max) of the aggregate in
+    /// the image, inclusive (only set by `fraktal_analyze_all`)
+    #[pyo3(get)]
+    #[serde(default)]
+    pub bounding_box: Option<(usize, usize, usize, usize)>,
+
+    /// Pixels of the aggregate (only set by `fraktal_analyze_all`)
+    #[pyo3(get)]
+    #[serde(default)]
+    pub pixel_count: Option<usize>,
+
+    /// Segmentation mask, particle centers and solver history (only with
+    /// `return_diagnostics=True`)
+    #[pyo3(get)]
+    #[serde(default)]
+    pub diagnostics: Option<PyFraktalDiagnostics>,
+}
+
+#[pymethods]
+impl PyFraktalResult {
+    /// The result as JSON, diagnostics included; `from_json` reads it back.
+    fn to_json(&self) -> PyResult<String> {
+        serialization::to_json(self)
+    }
+
+    /// Result written by `to_json` (non-finite floats come back as NaN, or None when optional).
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        serialization::from_json(json)
+    }
+
+    /// The result as MessagePack bytes; `from_msgpack` reads them back.
+    fn to_msgpack<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new(py, &serialization::to_msgpack(self)?))
+    }
+
+    /// Result written by `to_msgpack`.
+    #[staticmethod]
+    fn from_msgpack(data: &[u8]) -> PyResult<Self> {
+        serialization::from_msgpack(data)
+    }
 }
 
 impl From<FraktalResult> for PyFraktalResult {
@@ -216,6 +302,14 @@ impl From<FraktalResult> for PyFraktalResult {
             npo_ratio: r.npo_ratio,
             npo_aligned: r.npo_aligned,
             dpo_estimated: r.dpo_estimated,
+            dpo_geometric_mean: r.dpo_distribution.as_ref().map(|d| d.geometric_mean),
+            dpo_geometric_std: r.dpo_distribution.as_ref().map(|d| d.geometric_std),
+            dpo_histogram_edges: r.dpo_distribution.as_ref().map_or_else(Vec::new, |d| d.edges.clone()),
+            dpo_histogram_fractions: r.dpo_distribution.map_or_else(Vec::new, |d| d.fractions),
+            session: None,
+            bounding_box: None,
+            pixel_count: None,
+            diagnostics: r.diagnostics.map(PyFraktalDiagnostics::from),
         }
     }
 }
@@ -0,0 +1,301 @@
+//! Uncertainty propagation through the 2012 granulated model.
+//!
+//! The model inputs carry errors of their own: dpo comes from sizing a few
+//! primaries, npix from reading the scale bar, and the segmentation could
+//! have put its threshold a few gray levels either way. Each input is taken
+//! as uniform within ± its range around the nominal value and propagated
+//! to Df, kf and npo either
+//!
+//! * "monte_carlo" - by analysing random draws of the inputs; draws whose
+//!   analysis fails are left out of the statistics
+//! * "sensitivity" - to first order, from central differences over the full
+//!   ranges: the contribution of input x to output y is
+//!   (y(x + r) - y(x - r)) / (2 sqrt(3)), sigma_x = r / sqrt(3) for a
+//!   uniform input, and the covariances sum the products of contributions
+//!
+//! The image is segmented once per threshold shift, so the threshold range
+//! rather than the number of draws bounds the segmentation cost.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Instant;
+
+use ndarray::{Array2, ArrayView2};
+use pyo3::prelude::*;
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::common::rng::create_rng;
+
+use super::granulated_2012::analyze_granulated_2012_mask;
+use super::params::{Granulated2012Params, UncertaintyParams};
+use super::result::{FraktalResult, FraktalStatus, PyFraktalResult};
+
+/// Inputs of one analysis.
+#[derive(Debug, Clone, Copy)]
+struct Perturbation {
+    dpo: f64,
+    npix: f64,
+    shift: i16,
+}
+
+/// Uniform draw within ±`range` (0 for no range).
+fn uniform<R: Rng>(rng: &mut R, range: f64) -> f64 {
+    if range > 0.0 {
+        rng.gen_range(-range..=range)
+    } else {
+        0.0
+    }
+}
+
+/// (Df, kf, npo) of a successful analysis.
+fn outputs(result: &FraktalResult) -> Option<[f64; 3]> {
+    (result.status == FraktalStatus::Success).then_some([result.df, result.kf, result.npo as f64])
+}
+
+/// Df, kf and npo of the nominal inputs with their spread.
+#[derive(Debug, Clone)]
+pub struct UncertaintyEstimate {
+    pub nominal: FraktalResult,
+    /// Mean of (Df, kf, npo): over the draws for Monte Carlo, nominal for sensitivity
+    pub mean: [f64; 3],
+    pub std: [f64; 3],
+    pub correlation: [[f64; 3]; 3],
+    /// Perturbed analyses run
+    pub n_samples: usize,
+    /// Perturbed analyses that did not succeed
+    pub n_failed: usize,
+}
+
+/// Propagate the `uncertainty` ranges through the 2012 granulated model.
+///
+/// `seed` drives the Monte Carlo draws.
+pub fn granulated_2012_uncertainty(
+    image: ArrayView2<u8>,
+    params: &Granulated2012Params,
+    uncertainty: &UncertaintyParams,
+    seed: u64,
+) -> UncertaintyEstimate {
+    let start_time = Instant::now();
+    let nominal_input = Perturbation {
+        dpo: params.dpo,
+        npix: params.npix,
+        shift: 0,
+    };
+    let (dpo_range, npix_range) = (uncertainty.dpo_range, uncertainty.npix_range);
+    let threshold_range = uncertainty.threshold_range as i16;
+    let sensitivity = uncertainty.method == "sensitivity";
+
+    let perturbations: Vec<Perturbation> = if sensitivity {
+        // (+r, -r) pairs of every uncertain input
+        let mut pairs = Vec::new();
+        for sign in [1.0, -1.0] {
+            if dpo_range > 0.0 {
+                pairs.push((0, Perturbation { dpo: params.dpo + sign * dpo_range, ..nominal_input }));
+            }
+            if npix_range > 0.0 {
+                pairs.push((1, Perturbation { npix: params.npix + sign * npix_range, ..nominal_input }));
+            }
+            if threshold_range > 0 {
+                pairs.push((2, Perturbation { shift: sign as i16 * threshold_range, ..nominal_input }));
+            }
+        }
+        pairs.sort_by_key(|&(input, _)| input);
+        pairs.into_iter().map(|(_, p)| p).collect()
+    } else {
+        let mut rng = create_rng(seed);
+        (0..uncertainty.n_samples)
+            .map(|_| Perturbation {
+                dpo: params.dpo + uniform(&mut rng, dpo_range),
+                npix: params.npix + uniform(&mut rng, npix_range),
+                shift: rng.gen_range(-threshold_range..=threshold_range),
+            })
+            .collect()
+    };
+
+    // One segmentation per threshold shift
+    let shifts: BTreeSet<i16> = perturbations.iter().map(|p| p.shift).chain([0]).collect();
+    let masks: BTreeMap<i16, Array2<bool>> = shifts
+        .into_par_iter()
+        .map(|shift| (shift, params.segment_shifted(image, shift).0))
+        .collect();
+    // Only the nominal analysis measures the size distribution the draws do not report
+    let analyze = |p: &Perturbation, size_distribution: bool| {
+        let mut perturbed = params.clone();
+        perturbed.dpo = p.dpo;
+        perturbed.npix = p.npix;
+        perturbed.size_distribution = size_distribution;
+        analyze_granulated_2012_mask(masks[&p.shift].view(), &perturbed)
+    };
+
+    let mut nominal = analyze(&nominal_input, params.size_distribution);
+    let samples: Vec<Option<[f64; 3]>> = perturbations.par_iter().map(|p| outputs(&analyze(p, false))).collect();
+    let n_failed = samples.iter().filter(|s| s.is_none()).count();
+
+    let (mean, covariance) = if sensitivity {
+        let center = outputs(&nominal).unwrap_or([f64::NAN; 3]);
+        let contributions: Vec<[f64; 3]> = samples
+            .chunks(2)
+            .map(|pair| match (pair[0], pair[1]) {
+                (Some(plus), Some(minus)) => {
+                    [0, 1, 2].map(|k| (plus[k] - minus[k]) / (2.0 * 3.0f64.sqrt()))
+                }
+                _ => [f64::NAN; 3],
+            })
+            .collect();
+        let covariance = [0, 1, 2].map(|a| [0, 1, 2].map(|b| contributions.iter().map(|c| c[a] * c[b]).sum()));
+        (center, covariance)
+    } else {
+        let valid: Vec<[f64; 3]> = samples.into_iter().flatten().collect();
+        let n = valid.len() as f64;
+        let mean = [0, 1, 2].map(|k| valid.iter().map(|s| s[k]).sum::<f64>() / n);
+        let covariance = [0, 1, 2].map(|a| {
+            [0, 1, 2].map(|b| valid.iter().map(|s| (s[a] - mean[a]) * (s[b] - mean[b])).sum::<f64>() / (n - 1.0))
+        });
+        (mean, covariance)
+    };
+    let std = [0, 1, 2].map(|k| covariance[k][k].sqrt());
+    let correlation = [0, 1, 2].map(|a| [0, 1, 2].map(|b| covariance[a][b] / (std[a] * std[b])));
+
+    nominal.execution_time_ms = start_time.elapsed().as_millis() as u64;
+    UncertaintyEstimate {
+        nominal,
+        mean,
+        std,
+        correlation,
+        n_samples: perturbations.len(),
+        n_failed,
+    }
+}
+
+/// Df, kf and npo of the 2012 granulated model with error bars, from
+/// `fraktal_granulated_2012_uncertainty`.
+#[pyclass(name = "FraktalUncertainty")]
+#[derive(Debug, Clone)]
+pub struct PyFraktalUncertainty {
+    /// "monte_carlo" or "sensitivity"
+    #[pyo3(get)]
+    pub method: String,
+
+    /// Analysis of the nominal inputs
+    #[pyo3(get)]
+    pub nominal: PyFraktalResult,
+
+    /// Mean Df: over the draws for Monte Carlo, the nominal one for sensitivity
+    #[pyo3(get)]
+    pub df_mean: f64,
+
+    #[pyo3(get)]
+    pub df_std: f64,
+
+    #[pyo3(get)]
+    pub kf_mean: f64,
+
+    #[pyo3(get)]
+    pub kf_std: f64,
+
+    #[pyo3(get)]
+    pub npo_mean: f64,
+
+    #[pyo3(get)]
+    pub npo_std: f64,
+
+    /// Correlation matrix of (Df, kf, npo), NaN where an output does not vary
+    #[pyo3(get)]
+    pub correlation: [[f64; 3]; 3],
+
+    /// Perturbed analyses run
+    #[pyo3(get)]
+    pub n_samples: usize,
+
+    /// Perturbed analyses that did not succeed (left out of the statistics)
+    #[pyo3(get)]
+    pub n_failed: usize,
+}
+
+#[pymethods]
+impl PyFraktalUncertainty {
+    fn __repr__(&self) -> String {
+        format!(
+            "FraktalUncertainty(method='{}', df={:.4} ± {:.4}, kf={:.4} ± {:.4}, npo={:.1} ± {:.1})",
+            self.method, self.df_mean, self.df_std, self.kf_mean, self.kf_std, self.npo_mean, self.npo_std
+        )
+    }
+}
+
+impl PyFraktalUncertainty {
+    pub fn new(method: &str, estimate: UncertaintyEstimate) -> Self {
+        Self {
+            method: method.to_string(),
+            nominal: estimate.nominal.into(),
+            df_mean: estimate.mean[0],
+            df_std: estimate.std[0],
+            kf_mean: estimate.mean[1],
+            kf_std: estimate.std[1],
+            npo_mean: estimate.mean[2],
+            npo_std: estimate.std[2],
+            correlation: estimate.correlation,
+            n_samples: estimate.n_samples,
+            n_failed: estimate.n_failed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uncertainty_of_a_chain() {
+        // A dark zigzag chain of ten disks of radius 8 on a light background
+        let image = Array2::from_shape_fn((120, 200), |(i, j)| {
+            let hit = (0..10).any(|k| {
+                let (ci, cj) = (60.0 + 8.0 * (k % 2) as f64, 20.0 + 12.8 * k as f64);
+                (i as f64 - ci).powi(2) + (j as f64 - cj).powi(2) <= 64.0
+            });
+            if hit {
+                30u8
+            } else {
+                230
+            }
+        });
+        let params = Granulated2012Params {
+            dpo: 16.0,
+            ..Default::default()
+        };
+        let ranges = |method: &str| UncertaintyParams::new(1.0, 2.0, 5, method.to_string(), 40, None);
+
+        // Without ranges every draw is the nominal analysis
+        let fixed = UncertaintyParams::new(0.0, 0.0, 0, "monte_carlo".to_string(), 10, None);
+        let estimate = granulated_2012_uncertainty(image.view(), &params, &fixed, 1);
+        assert_eq!(estimate.nominal.status, FraktalStatus::Success);
+        assert_eq!((estimate.n_samples, estimate.n_failed), (10, 0));
+        assert!((estimate.mean[0] - estimate.nominal.df).abs() < 1e-12);
+        assert!(estimate.std.iter().all(|&s| s < 1e-12));
+
+        let monte_carlo = granulated_2012_uncertainty(image.view(), &params, &ranges("monte_carlo"), 7);
+        assert_eq!(monte_carlo.n_samples, 40);
+        assert_eq!(monte_carlo.n_failed, 0);
+        assert!(monte_carlo.std.iter().all(|&s| s > 0.0 && s.is_finite()));
+        assert!((monte_carlo.mean[0] - monte_carlo.nominal.df).abs() < 3.0 * monte_carlo.std[0]);
+        for a in 0..3 {
+            assert!((monte_carlo.correlation[a][a] - 1.0).abs() < 1e-9);
+            for b in 0..3 {
+                assert!((monte_carlo.correlation[a][b] - monte_carlo.correlation[b][a]).abs() < 1e-12);
+                assert!(monte_carlo.correlation[a][b].abs() <= 1.0 + 1e-9);
+            }
+        }
+        let again = granulated_2012_uncertainty(image.view(), &params, &ranges("monte_carlo"), 7);
+        assert_eq!(again.mean, monte_carlo.mean);
+
+        // First order: one (+r, -r) pair per input around the nominal analysis
+        let sensitivity = granulated_2012_uncertainty(image.view(), &params, &ranges("sensitivity"), 7);
+        assert_eq!(sensitivity.n_samples, 6);
+        assert_eq!(sensitivity.mean[0], sensitivity.nominal.df);
+        assert_eq!(sensitivity.n_failed, 0);
+        // Over these small ranges the model is close to linear, so both agree
+        for k in 0..3 {
+            let ratio = sensitivity.std[k] / monte_carlo.std[k];
+            assert!(ratio > 0.5 && ratio < 2.0, "output {}: {}", k, ratio);
+        }
+    }
+}
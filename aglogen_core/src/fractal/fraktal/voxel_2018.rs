@@ -9,9 +9,7 @@ use std::time::Instant;
 use ndarray::ArrayView2;
 
 use super::bisection::BisectionSolver;
-use super::image_processing::{
-    apply_3d_correction_voxel, calculate_geometry, smart_segment,
-};
+use super::image_processing::{apply_3d_correction_voxel, calculate_geometry};
 use super::params::Voxel2018Params;
 use super::result::{FraktalResult, FraktalStatus};
 
@@ -58,19 +56,24 @@ pub fn analyze_voxel_2018(
 ) -> FraktalResult {
     let start_time = Instant::now();
 
-    // Step 1: Smart segmentation with automatic threshold detection
-    let (binary, detected_threshold, is_dark_on_light) = smart_segment(
-        image,
-        params.pixel_min,
-        params.pixel_max,
-        params.auto_threshold,
-    );
+    // Step 1: Smart segmentation with automatic threshold detection, then
+    // the mask cleanup
+    let (binary, _threshold, _dark_on_light) = params.segment(image);
 
-    // Debug info available via detected_threshold and is_dark_on_light
-    let _ = (detected_threshold, is_dark_on_light); // Mark as intentionally unused
+    let mut result = analyze_voxel_2018_mask(binary.view(), params);
+    result.execution_time_ms = start_time.elapsed().as_millis() as u64;
+    result
+}
+
+/// Analyze an already segmented mask (foreground = aggregate) with the same model.
+pub fn analyze_voxel_2018_mask(
+    binary: ArrayView2<bool>,
+    params: &Voxel2018Params,
+) -> FraktalResult {
+    let start_time = Instant::now();
 
     // Step 2: Calculate geometry
-    let geometry = match calculate_geometry(binary.view(), params.npix, params.escala) {
+    let geometry = match calculate_geometry(binary, params.npix, params.escala) {
         Some(g) => g,
         None => {
             return FraktalResult {
@@ -100,6 +103,8 @@ pub fn analyze_voxel_2018(
     let mut df_result = 0.0;
     let mut kf_result = 0.0;
     let mut converged = false;
+    // (nvox, Df, kf) of every outer iteration
+    let mut history: Vec<[f64; 3]> = Vec::new();
     let tolerance = 0.0001;
     let max_outer_iterations = 50;
 
@@ -126,6 +131,7 @@ pub fn analyze_voxel_2018(
 
         // Calculate new nvox estimate
         let new_nvox = result.kf * (dp / lvox).powf(result.df);
+        history.push([new_nvox, result.df, result.kf]);
 
         // Check convergence
         if (result.df - df_result).abs() < tolerance && outer_iter > 0 {
@@ -153,6 +159,7 @@ pub fn analyze_voxel_2018(
             },
             execution_time_ms: start_time.elapsed().as_millis() as u64,
             model: "voxel_2018".to_string(),
+            history,
             ..Default::default()
         };
     }
@@ -187,9 +194,12 @@ pub fn analyze_voxel_2018(
         npo_ratio: 0.0,       // Not applicable for voxel model
         npo_aligned: true,    // No comparison for voxel model
         dpo_estimated: 0.0,   // Not applicable for voxel model
+        dpo_distribution: None,
         status: FraktalStatus::Success,
         execution_time_ms: start_time.elapsed().as_millis() as u64,
         model: "voxel_2018".to_string(),
+        history,
+        diagnostics: None,
     }
 }
 
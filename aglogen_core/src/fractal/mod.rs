@@ -2,5 +2,12 @@
 
 pub mod box_counting;
 pub mod box_counting_3d;
+pub mod correlation;
 pub mod fraktal;
+pub mod lacunarity;
+pub mod multifractal;
+pub mod optics;
+pub mod perimeter_area;
 pub mod result;
+pub mod sandbox;
+pub mod scattering;
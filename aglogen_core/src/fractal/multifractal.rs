@@ -0,0 +1,322 @@
+//! Multifractal spectrum by generalized box-counting.
+//!
+//! A single Df treats every occupied box alike, so a dense core with sparse
+//! branches and a uniformly filled structure of the same Df look the same.
+//! Weighting each box by its mass fraction mu_i raised to a power q tells
+//! them apart: at scale eps the partition sum Z(q, eps) = sum mu_i^q scales
+//! as eps^tau(q), and the generalized dimensions D_q = tau(q) / (q - 1)
+//! (D_1 from the information sum mu_i ln mu_i) run from the dense regions
+//! (q > 0) to the sparse ones (q < 0). D_0 is the box-counting dimension and
+//! D_2 the correlation dimension; a monofractal has D_q = Df for every q.
+//!
+//! The f(alpha) singularity spectrum follows from the direct method of
+//! Chhabra & Jensen (1989): with the q-weighted measures
+//! m_i = mu_i^q / Z(q, eps), alpha(q) and f(q) are the slopes of
+//! sum m_i ln mu_i and sum m_i ln m_i against ln eps.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use ndarray::ArrayView2;
+use numpy::PyReadonlyArray2;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::common::arrays::extract_f64_array2;
+use crate::common::fitting::linear_regression;
+use crate::common::validation::{check_coordinates, check_count, check_in_range};
+
+use super::box_counting::box_sizes;
+use super::result::PyMultifractalResult;
+
+/// Moments analysed when no `q_values` are given: -5 to 5 in steps of 1.
+fn default_q_values() -> Vec<f64> {
+    (-5..=5).map(f64::from).collect()
+}
+
+/// Require finite moments.
+fn check_q_values(q_values: &[f64]) -> PyResult<()> {
+    check_count("number of q_values", q_values.len(), 1)?;
+    if let Some(q) = q_values.iter().find(|q| !q.is_finite()) {
+        return Err(PyValueError::new_err(format!("q_values must be finite, got {}", q)));
+    }
+    Ok(())
+}
+
+/// Generalized dimensions and singularity spectrum of a measure.
+#[derive(Debug, Clone)]
+pub struct MultifractalResult {
+    pub q: Vec<f64>,
+    pub tau: Vec<f64>,
+    pub dq: Vec<f64>,
+    pub alpha: Vec<f64>,
+    pub f_alpha: Vec<f64>,
+    /// R² of the fit behind each D_q
+    pub r_squared: Vec<f64>,
+    /// ln(1 / eps) of the scales
+    pub log_scales: Vec<f64>,
+    pub execution_time_ms: u64,
+}
+
+/// Spectrum of the box masses measured at each scale.
+///
+/// `measures` holds (eps, masses of the occupied boxes) per scale; masses
+/// need not be normalized.
+pub fn multifractal_internal(
+    measures: &[(f64, Vec<f64>)],
+    q_values: &[f64],
+    start_time: Instant,
+) -> MultifractalResult {
+    let log_eps: Vec<f64> = measures.iter().map(|(eps, _)| eps.ln()).collect();
+    let fractions: Vec<Vec<f64>> = measures
+        .iter()
+        .map(|(_, masses)| {
+            let total: f64 = masses.iter().sum();
+            masses.iter().filter(|&&m| m > 0.0).map(|m| m / total).collect()
+        })
+        .collect();
+
+    let mut result = MultifractalResult {
+        q: q_values.to_vec(),
+        tau: Vec::with_capacity(q_values.len()),
+        dq: Vec::with_capacity(q_values.len()),
+        alpha: Vec::with_capacity(q_values.len()),
+        f_alpha: Vec::with_capacity(q_values.len()),
+        r_squared: Vec::with_capacity(q_values.len()),
+        log_scales: log_eps.iter().map(|l| -l).collect(),
+        execution_time_ms: 0,
+    };
+    for &q in q_values {
+        let mut log_z = Vec::with_capacity(fractions.len());
+        let mut information = Vec::with_capacity(fractions.len());
+        let mut alpha_sums = Vec::with_capacity(fractions.len());
+        let mut f_sums = Vec::with_capacity(fractions.len());
+        for mu in &fractions {
+            let z: f64 = mu.iter().map(|m| m.powf(q)).sum();
+            let (mut alpha_sum, mut f_sum) = (0.0, 0.0);
+            for &m in mu {
+                let weight = m.powf(q) / z;
+                alpha_sum += weight * m.ln();
+                f_sum += weight * weight.ln();
+            }
+            log_z.push(z.ln());
+            information.push(mu.iter().map(|m| m * m.ln()).sum::<f64>());
+            alpha_sums.push(alpha_sum);
+            f_sums.push(f_sum);
+        }
+
+        let tau = linear_regression(&log_eps, &log_z);
+        let (dq, r_squared) = if (q - 1.0).abs() < 1e-12 {
+            let fit = linear_regression(&log_eps, &information);
+            (fit.slope, fit.r_squared)
+        } else {
+            (tau.slope / (q - 1.0), tau.r_squared)
+        };
+        result.tau.push(tau.slope);
+        result.dq.push(dq);
+        result.r_squared.push(r_squared);
+        result.alpha.push(linear_regression(&log_eps, &alpha_sums).slope);
+        result.f_alpha.push(linear_regression(&log_eps, &f_sums).slope);
+    }
+    result.execution_time_ms = start_time.elapsed().as_millis() as u64;
+    result
+}
+
+/// Pixel counts of the occupied `box_size` boxes of a binary image.
+fn image_box_masses(image: ArrayView2<'_, bool>, box_size: usize) -> Vec<f64> {
+    let (height, width) = image.dim();
+    let cols = width.div_ceil(box_size);
+    let mut masses = vec![0.0; height.div_ceil(box_size) * cols];
+    for ((y, x), &pixel) in image.indexed_iter() {
+        if pixel {
+            masses[y / box_size * cols + x / box_size] += 1.0;
+        }
+    }
+    masses.retain(|&m| m > 0.0);
+    masses
+}
+
+/// Point counts of the occupied boxes of edge extent / 2^level, the grid laid
+/// on the bounding cube of the points.
+fn point_box_masses(points: &[[f64; 3]], origin: [f64; 3], extent: f64, level: u32) -> Vec<f64> {
+    let cells = 1u64 << level;
+    let eps = extent / cells as f64;
+    let mut masses: HashMap<[u64; 3], f64> = HashMap::new();
+    for p in points {
+        let key = [0, 1, 2].map(|k| (((p[k] - origin[k]) / eps) as u64).min(cells - 1));
+        *masses.entry(key).or_insert(0.0) += 1.0;
+    }
+    masses.into_values().collect()
+}
+
+/// Multifractal spectrum of a binary image.
+///
+/// The measure is the share of the object pixels in each box, over box
+/// sizes spaced as in `box_counting`.
+///
+/// # Arguments
+/// * `binary_image` - 2D boolean numpy array
+/// * `q_values` - Moments q (default: -5 to 5 in steps of 1)
+/// * `min_box_size` - Smallest box side in pixels (default: 2)
+/// * `max_box_size` - Largest box side in pixels (default: 512)
+/// * `num_scales` - Number of box sizes (default: 10)
+///
+/// # Returns
+/// MultifractalResult with D_q, tau(q) and the f(alpha) spectrum.
+#[pyfunction]
+#[pyo3(signature = (binary_image, q_values=None, min_box_size=2, max_box_size=512, num_scales=10))]
+pub fn multifractal_spectrum(
+    py: Python<'_>,
+    binary_image: PyReadonlyArray2<'_, bool>,
+    q_values: Option<Vec<f64>>,
+    min_box_size: usize,
+    max_box_size: usize,
+    num_scales: usize,
+) -> PyResult<PyMultifractalResult> {
+    let image = binary_image.as_array();
+    let (height, width) = image.dim();
+    let q_values = q_values.unwrap_or_else(default_q_values);
+
+    check_q_values(&q_values)?;
+    check_count("min_box_size", min_box_size, 1)?;
+    check_count("max_box_size", max_box_size, min_box_size)?;
+    check_count("num_scales", num_scales, 2)?;
+    check_count("number of object pixels", image.iter().filter(|&&p| p).count(), 1)?;
+    let sizes = box_sizes(min_box_size, max_box_size, num_scales, height.min(width));
+    check_count("number of distinct box sizes", sizes.len(), 2)?;
+
+    let result = py.allow_threads(|| {
+        let start_time = Instant::now();
+        let measures: Vec<(f64, Vec<f64>)> = sizes
+            .par_iter()
+            .map(|&box_size| (box_size as f64, image_box_masses(image, box_size)))
+            .collect();
+        multifractal_internal(&measures, &q_values, start_time)
+    });
+    Ok(result.into())
+}
+
+/// Multifractal spectrum of a 3D point cloud.
+///
+/// The measure is the share of the points in each box of a grid on the
+/// bounding cube of the cloud, with box edges of 1/2 to 1/2^levels of the
+/// cube. The finest level should still hold several points per occupied box.
+///
+/// # Arguments
+/// * `coordinates` - Nx3 array of point coordinates
+/// * `q_values` - Moments q (default: -5 to 5 in steps of 1)
+/// * `levels` - Number of grid refinements (default: 6, max: 20)
+///
+/// # Returns
+/// MultifractalResult with D_q, tau(q) and the f(alpha) spectrum.
+#[pyfunction]
+#[pyo3(signature = (coordinates, q_values=None, levels=6))]
+pub fn multifractal_spectrum_3d(
+    py: Python<'_>,
+    coordinates: &Bound<'_, PyAny>,
+    q_values: Option<Vec<f64>>,
+    levels: u32,
+) -> PyResult<PyMultifractalResult> {
+    let coords = extract_f64_array2(coordinates, "coordinates")?;
+    let q_values = q_values.unwrap_or_else(default_q_values);
+
+    check_coordinates("coordinates", &coords)?;
+    check_count("number of coordinates", coords.nrows(), 2)?;
+    check_q_values(&q_values)?;
+    check_in_range("levels", levels, 2, 20)?;
+
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|r| [r[0], r[1], r[2]]).collect();
+    let origin = [0, 1, 2].map(|k| points.iter().map(|p| p[k]).fold(f64::INFINITY, f64::min));
+    let extent = [0, 1, 2]
+        .map(|k| points.iter().map(|p| p[k] - origin[k]).fold(0.0, f64::max))
+        .into_iter()
+        .fold(0.0, f64::max);
+    if extent <= 0.0 {
+        return Err(PyValueError::new_err("coordinates must not all coincide"));
+    }
+
+    let result = py.allow_threads(|| {
+        let start_time = Instant::now();
+        let measures: Vec<(f64, Vec<f64>)> = (1..=levels)
+            .into_par_iter()
+            .map(|level| {
+                (
+                    extent / (1u64 << level) as f64,
+                    point_box_masses(&points, origin, extent, level),
+                )
+            })
+            .collect();
+        multifractal_internal(&measures, &q_values, start_time)
+    });
+    Ok(result.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array2;
+
+    #[test]
+    fn test_multifractal_spectrum() {
+        let q_values = default_q_values();
+
+        // A filled square and a regular lattice are monofractal
+        let square = Array2::from_elem((64, 64), true);
+        let measures: Vec<(f64, Vec<f64>)> = [2, 4, 8, 16]
+            .iter()
+            .map(|&s| (s as f64, image_box_masses(square.view(), s)))
+            .collect();
+        let flat = multifractal_internal(&measures, &q_values, Instant::now());
+        for k in 0..q_values.len() {
+            assert!((flat.dq[k] - 2.0).abs() < 1e-9, "{:?}", flat.dq);
+            assert!((flat.alpha[k] - 2.0).abs() < 1e-9 && (flat.f_alpha[k] - 2.0).abs() < 1e-9);
+        }
+        assert!((flat.tau[q_values.len() - 1] - 8.0).abs() < 1e-9);
+
+        let lattice: Vec<[f64; 3]> = (0..16 * 16 * 16)
+            .map(|n| [(n % 16) as f64, (n / 16 % 16) as f64, (n / 256) as f64])
+            .collect();
+        let measures: Vec<(f64, Vec<f64>)> = (1..=3)
+            .map(|level| {
+                (
+                    15.0 / (1u64 << level) as f64,
+                    point_box_masses(&lattice, [0.0; 3], 15.0, level),
+                )
+            })
+            .collect();
+        let cube = multifractal_internal(&measures, &q_values, Instant::now());
+        assert!(cube.dq.iter().all(|d| (d - 3.0).abs() < 1e-9), "{:?}", cube.dq);
+
+        // Multiplicative cascade splitting each box's mass over its four
+        // quarters: D_q = ln(sum p^q) / ((1 - q) ln 2), D_1 = -sum p ln p / ln 2
+        let weights = [0.4, 0.3, 0.2, 0.1];
+        let mut masses = vec![1.0];
+        let mut measures = Vec::new();
+        for level in 1..=5 {
+            masses = masses.iter().flat_map(|m| weights.map(|w| m * w)).collect();
+            measures.push((0.5f64.powi(level), masses.clone()));
+        }
+        let cascade = multifractal_internal(&measures, &q_values, Instant::now());
+        for (k, &q) in q_values.iter().enumerate() {
+            let expected = if q == 1.0 {
+                -weights.iter().map(|p| p * p.ln()).sum::<f64>() / 2f64.ln()
+            } else {
+                weights.iter().map(|p| p.powf(q)).sum::<f64>().ln() / ((1.0 - q) * 2f64.ln())
+            };
+            assert!(
+                (cascade.dq[k] - expected).abs() < 1e-9,
+                "q={}: {} vs {}",
+                q,
+                cascade.dq[k],
+                expected
+            );
+        }
+        assert!(cascade.dq.windows(2).all(|w| w[0] > w[1]));
+        // alpha runs from the sparsest quarter (q < 0) to the densest (q > 0)
+        assert!(cascade.alpha[0] > cascade.alpha[q_values.len() - 1]);
+        assert!(
+            cascade.alpha[0] < -0.1f64.ln() / 2f64.ln() && cascade.alpha[q_values.len() - 1] > -0.4f64.ln() / 2f64.ln()
+        );
+    }
+}
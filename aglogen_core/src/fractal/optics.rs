@@ -0,0 +1,271 @@
+//! Radiative properties of fractal aggregates in the RDG-FA approximation.
+//!
+//! The Rayleigh-Debye-Gans fractal-aggregate approximation (Dobbins &
+//! Megaridis, 1991; Sorensen, 2001) treats every primary particle as a
+//! Rayleigh scatterer and ignores multiple scattering between them:
+//!
+//! * absorption - each primary absorbs on its own, C_abs = N C_abs^p with
+//!   C_abs^p = 4 pi x_p³ E(m) / k²
+//! * scattering - the primaries scatter coherently within the aggregate,
+//!   C_sca = N² C_sca^p G(k Rg) with C_sca^p = 8/3 pi x_p⁶ F(m) / k² and
+//!   the total scattering factor G = (1 + 4 (k Rg)² / (3 Df))^(-Df/2)
+//!
+//! where k = 2 pi / lambda, x_p = k a is the size parameter of a primary of
+//! radius a, E(m) = Im((m² - 1)/(m² + 2)) and F(m) = |(m² - 1)/(m² + 2)|².
+//! The approximation holds while the phase shift 2 x_p |m - 1| stays below
+//! about 1. Cross sections are in the squared length units of the radii.
+
+use std::f64::consts::PI;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::common::validation::{check_count, check_in_range, check_positive};
+use crate::simulation::result::PySimulationResult;
+
+/// Complex refractive index m = n + i k of the primaries, as `(n, k)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RefractiveIndex {
+    pub n: f64,
+    pub k: f64,
+}
+
+impl RefractiveIndex {
+    /// (m² - 1)/(m² + 2) as `(re, im)`.
+    fn lorentz_lorenz(&self) -> (f64, f64) {
+        let (re, im) = (self.n * self.n - self.k * self.k, 2.0 * self.n * self.k);
+        let (a, b) = (re - 1.0, im);
+        let (c, d) = (re + 2.0, im);
+        let norm = c * c + d * d;
+        ((a * c + b * d) / norm, (b * c - a * d) / norm)
+    }
+
+    /// Absorption function E(m) = Im((m² - 1)/(m² + 2)).
+    pub fn absorption_function(&self) -> f64 {
+        self.lorentz_lorenz().1
+    }
+
+    /// Scattering function F(m) = |(m² - 1)/(m² + 2)|².
+    pub fn scattering_function(&self) -> f64 {
+        let (re, im) = self.lorentz_lorenz();
+        re * re + im * im
+    }
+}
+
+/// RDG-FA cross sections of one aggregate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpticalProperties {
+    pub absorption: f64,
+    pub scattering: f64,
+    pub extinction: f64,
+    pub albedo: f64,
+    pub primary_absorption: f64,
+    pub primary_scattering: f64,
+    pub scattering_factor: f64,
+    pub size_parameter: f64,
+    pub phase_shift: f64,
+}
+
+/// RDG-FA cross sections of `n_particles` primaries of radius `primary_radius` in an aggregate of radius of gyration `rg`.
+///
+/// `wavelength`, `primary_radius` and `rg` share the same length units.
+pub fn rdg_fa_internal(
+    wavelength: f64,
+    m: RefractiveIndex,
+    primary_radius: f64,
+    n_particles: f64,
+    rg: f64,
+    df: f64,
+) -> OpticalProperties {
+    let k = 2.0 * PI / wavelength;
+    let x_p = k * primary_radius;
+    let primary_absorption = 4.0 * PI * x_p.powi(3) * m.absorption_function() / (k * k);
+    let primary_scattering = 8.0 / 3.0 * PI * x_p.powi(6) * m.scattering_function() / (k * k);
+    let scattering_factor = (1.0 + 4.0 * (k * rg).powi(2) / (3.0 * df)).powf(-df / 2.0);
+
+    let absorption = n_particles * primary_absorption;
+    let scattering = n_particles * n_particles * primary_scattering * scattering_factor;
+    let modulus = ((m.n - 1.0).powi(2) + m.k * m.k).sqrt();
+    OpticalProperties {
+        absorption,
+        scattering,
+        extinction: absorption + scattering,
+        albedo: scattering / (absorption + scattering),
+        primary_absorption,
+        primary_scattering,
+        scattering_factor,
+        size_parameter: x_p,
+        phase_shift: 2.0 * x_p * modulus,
+    }
+}
+
+/// RDG-FA radiative properties of an aggregate, from `rdg_fa`.
+#[pyclass(name = "OpticalProperties")]
+#[derive(Debug, Clone)]
+pub struct PyOpticalProperties {
+    #[pyo3(get)]
+    pub wavelength: f64,
+    /// Refractive index of the primaries as (n, k)
+    #[pyo3(get)]
+    pub refractive_index: (f64, f64),
+    #[pyo3(get)]
+    pub primary_radius: f64,
+    #[pyo3(get)]
+    pub n_particles: usize,
+    #[pyo3(get)]
+    pub radius_of_gyration: f64,
+    #[pyo3(get)]
+    pub fractal_dimension: f64,
+    /// Absorption cross section N C_abs^p
+    #[pyo3(get)]
+    pub absorption_cross_section: f64,
+    /// Total scattering cross section N² C_sca^p G(k Rg)
+    #[pyo3(get)]
+    pub scattering_cross_section: f64,
+    #[pyo3(get)]
+    pub extinction_cross_section: f64,
+    /// Single-scattering albedo C_sca / C_ext
+    #[pyo3(get)]
+    pub albedo: f64,
+    #[pyo3(get)]
+    pub primary_absorption_cross_section: f64,
+    #[pyo3(get)]
+    pub primary_scattering_cross_section: f64,
+    /// Total scattering factor G(k Rg), 1 for aggregates much smaller than the wavelength
+    #[pyo3(get)]
+    pub scattering_factor: f64,
+    /// Size parameter x_p = 2 pi a / lambda of a primary
+    #[pyo3(get)]
+    pub size_parameter: f64,
+    /// Phase shift 2 x_p |m - 1|; RDG-FA is reliable while it stays below about 1
+    #[pyo3(get)]
+    pub phase_shift: f64,
+}
+
+#[pymethods]
+impl PyOpticalProperties {
+    fn __repr__(&self) -> String {
+        format!(
+            "OpticalProperties(wavelength={}, absorption={:.4e}, scattering={:.4e}, albedo={:.4})",
+            self.wavelength, self.absorption_cross_section, self.scattering_cross_section, self.albedo
+        )
+    }
+}
+
+/// Absorption and scattering cross sections of a fractal aggregate (RDG-FA).
+///
+/// The aggregate is either a `SimulationResult`, whose mean radius, particle
+/// count, Rg and Df are used, or given by its primary radius, particle count
+/// and Df/kf, Rg then following from N = kf (Rg/a)^Df. Explicit parameters
+/// override the ones of `result`, and Rg then follows from the power law
+/// with the result's prefactor unless `kf` is given too.
+///
+/// # Arguments
+/// * `wavelength` - Wavelength of the light, in the length units of the radii
+/// * `refractive_index` - Complex refractive index of the primaries as (n, k)
+///   (default: (1.57, 0.56), soot)
+/// * `result` - `SimulationResult` to take the aggregate from (default: None)
+/// * `primary_radius` - Radius a of the primaries
+/// * `n_particles` - Number of primaries N
+/// * `df` - Fractal dimension
+/// * `kf` - Fractal prefactor
+///
+/// # Returns
+/// * `OpticalProperties` with the aggregate and primary cross sections, the
+///   albedo and the RDG-FA validity parameters
+#[pyfunction]
+#[pyo3(signature = (wavelength, refractive_index=(1.57, 0.56), result=None, primary_radius=None, n_particles=None, df=None, kf=None))]
+pub fn rdg_fa(
+    wavelength: f64,
+    refractive_index: (f64, f64),
+    result: Option<PyRef<'_, PySimulationResult>>,
+    primary_radius: Option<f64>,
+    n_particles: Option<usize>,
+    df: Option<f64>,
+    kf: Option<f64>,
+) -> PyResult<PyOpticalProperties> {
+    check_positive("wavelength", wavelength)?;
+    let (n, k) = refractive_index;
+    check_positive("refractive index n", n)?;
+    check_in_range("refractive index k", k, 0.0, f64::INFINITY)?;
+
+    // A result brings its measured Rg unless the aggregate is redefined
+    let measured_rg = match &result {
+        Some(r) if primary_radius.is_none() && n_particles.is_none() && df.is_none() && kf.is_none() => {
+            Some(r.radius_of_gyration)
+        }
+        _ => None,
+    };
+    let missing = |name: &str| PyValueError::new_err(format!("give {} or a result", name));
+    let primary_radius = primary_radius
+        .or(result
+            .as_ref()
+            .map(|r| r.radii_data.iter().sum::<f64>() / r.radii_data.len() as f64))
+        .ok_or_else(|| missing("primary_radius"))?;
+    let n_particles = n_particles
+        .or(result.as_ref().map(|r| r.radii_data.len()))
+        .ok_or_else(|| missing("n_particles"))?;
+    let df = df
+        .or(result.as_ref().map(|r| r.fractal_dimension))
+        .ok_or_else(|| missing("df"))?;
+    check_positive("primary_radius", primary_radius)?;
+    check_count("n_particles", n_particles, 1)?;
+    check_in_range("df", df, 1.0, 3.0)?;
+    let rg = match measured_rg {
+        Some(rg) => rg,
+        None => {
+            let kf = kf
+                .or(result.as_ref().map(|r| r.prefactor))
+                .ok_or_else(|| missing("kf"))?;
+            check_positive("kf", kf)?;
+            primary_radius * (n_particles as f64 / kf).powf(1.0 / df)
+        }
+    };
+
+    let m = RefractiveIndex { n, k };
+    let optics = rdg_fa_internal(wavelength, m, primary_radius, n_particles as f64, rg, df);
+    Ok(PyOpticalProperties {
+        wavelength,
+        refractive_index,
+        primary_radius,
+        n_particles,
+        radius_of_gyration: rg,
+        fractal_dimension: df,
+        absorption_cross_section: optics.absorption,
+        scattering_cross_section: optics.scattering,
+        extinction_cross_section: optics.extinction,
+        albedo: optics.albedo,
+        primary_absorption_cross_section: optics.primary_absorption,
+        primary_scattering_cross_section: optics.primary_scattering,
+        scattering_factor: optics.scattering_factor,
+        size_parameter: optics.size_parameter,
+        phase_shift: optics.phase_shift,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rdg_fa_limits() {
+        let soot = RefractiveIndex { n: 1.57, k: 0.56 };
+        // Published soot values at m = 1.57 + 0.56i
+        assert!((soot.absorption_function() - 0.26).abs() < 0.005);
+        assert!((soot.scattering_function() - 0.22).abs() < 0.01);
+
+        // A single small primary is a Rayleigh sphere
+        let single = rdg_fa_internal(532.0, soot, 15.0, 1.0, 15.0 * (3.0f64 / 5.0).sqrt(), 1.8);
+        assert!((single.absorption - single.primary_absorption).abs() < 1e-12);
+        assert!((single.scattering / single.primary_scattering - 1.0).abs() < 0.02);
+
+        // Absorption is additive; scattering grows as N² while k Rg << 1 and slower beyond
+        let small = rdg_fa_internal(532.0, soot, 15.0, 10.0, 1.0, 1.8);
+        assert!((small.absorption - 10.0 * small.primary_absorption).abs() < 1e-9);
+        assert!((small.scattering / (100.0 * small.primary_scattering) - 1.0).abs() < 1e-4);
+        let large = rdg_fa_internal(532.0, soot, 15.0, 500.0, 15.0 * (500.0f64 / 1.3).powf(1.0 / 1.8), 1.8);
+        assert!(large.scattering_factor < 0.5);
+        assert!(large.albedo > small.albedo && large.albedo < 1.0);
+        assert!(large.phase_shift < 1.0);
+    }
+}
@@ -2,10 +2,19 @@
 
 use numpy::PyArray1;
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use serde::{Deserialize, Serialize};
+
+use crate::common::fitting::FitMethod;
+use crate::common::serialization;
+use crate::simulation::ensemble::percentile;
+
+use super::lacunarity::LacunarityResult;
+use super::multifractal::MultifractalResult;
 
 /// Python wrapper for fractal analysis results.
 #[pyclass]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PyFractalResult {
     #[pyo3(get)]
     pub dimension: f64,
@@ -15,16 +24,41 @@ pub struct PyFractalResult {
     pub std_error: f64,
     #[pyo3(get)]
     pub confidence_interval: (f64, f64),
+    /// Slope of the line fitted to `log_values` against `log_scales`.
+    #[pyo3(get)]
+    pub slope: f64,
+    /// Intercept of the line fitted to `log_values` against `log_scales`.
+    #[pyo3(get)]
+    pub intercept: f64,
     #[pyo3(get)]
     pub execution_time_ms: u64,
     /// Start index of linear region (0 = all points used).
     #[pyo3(get)]
     pub linear_region_start: usize,
+    /// End index (exclusive) of the linear region.
+    #[pyo3(get)]
+    pub linear_region_end: usize,
+    /// Estimator of the fitted line ("ols", "theil_sen", "ransac" or "wls").
+    #[pyo3(get)]
+    pub fit_method: String,
+    /// Tag of the `AnalysisSession` that produced this result, if any.
+    #[pyo3(get)]
+    #[serde(default)]
+    pub session: Option<String>,
 
     // Internal storage
+    #[serde(rename = "log_scales")]
     pub(crate) log_scales_data: Vec<f64>,
+    #[serde(rename = "log_values")]
     pub(crate) log_values_data: Vec<f64>,
+    #[serde(rename = "residuals")]
     pub(crate) residuals_data: Vec<f64>,
+    #[serde(rename = "box_counts")]
+    pub(crate) box_counts_data: Vec<(f64, f64)>,
+    #[serde(rename = "box_count_std")]
+    pub(crate) box_count_std_data: Vec<f64>,
+    #[serde(rename = "bootstrap")]
+    pub(crate) bootstrap_data: Vec<f64>,
 }
 
 #[pymethods]
@@ -46,6 +80,68 @@ impl PyFractalResult {
     fn residuals<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
         PyArray1::from_vec(py, self.residuals_data.clone())
     }
+
+    /// Get the box size of every counted scale as numpy array (empty for
+    /// methods other than box counting).
+    #[getter]
+    fn box_sizes<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.box_counts_data.iter().map(|c| c.0).collect())
+    }
+
+    /// Get the box count of every counted scale as numpy array, fitted or
+    /// not, for manual re-fitting.
+    #[getter]
+    fn box_counts<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.box_counts_data.iter().map(|c| c.1).collect())
+    }
+
+    /// Get the standard deviation of every box count across the averaged
+    /// grid offsets and orientations as numpy array (zeros for a single grid).
+    #[getter]
+    fn box_count_std<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.box_count_std_data.clone())
+    }
+
+    /// Get the dimension of every bootstrap resample as numpy array (empty
+    /// without `bootstrap`).
+    #[getter]
+    fn bootstrap_dimensions<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.bootstrap_data.clone())
+    }
+
+    /// Empirical 95% confidence interval of the dimension, the 2.5th and
+    /// 97.5th percentiles of the bootstrap dimensions (None without `bootstrap`).
+    #[getter]
+    fn bootstrap_interval(&self) -> Option<(f64, f64)> {
+        if self.bootstrap_data.is_empty() {
+            return None;
+        }
+        let mut sorted = self.bootstrap_data.clone();
+        sorted.sort_by(f64::total_cmp);
+        Some((percentile(&sorted, 2.5), percentile(&sorted, 97.5)))
+    }
+
+    /// The result as JSON; `from_json` reads it back.
+    fn to_json(&self) -> PyResult<String> {
+        serialization::to_json(self)
+    }
+
+    /// Result written by `to_json` (non-finite floats come back as NaN, or None when optional).
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        serialization::from_json(json)
+    }
+
+    /// The result as MessagePack bytes; `from_msgpack` reads them back.
+    fn to_msgpack<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new(py, &serialization::to_msgpack(self)?))
+    }
+
+    /// Result written by `to_msgpack`.
+    #[staticmethod]
+    fn from_msgpack(data: &[u8]) -> PyResult<Self> {
+        serialization::from_msgpack(data)
+    }
 }
 
 /// Internal fractal result.
@@ -54,11 +150,23 @@ pub struct FractalResult {
     pub r_squared: f64,
     pub std_error: f64,
     pub confidence_interval: (f64, f64),
+    /// Fitted line `log_values = intercept + slope * log_scales`
+    pub slope: f64,
+    pub intercept: f64,
     pub log_scales: Vec<f64>,
     pub log_values: Vec<f64>,
     pub residuals: Vec<f64>,
     pub execution_time_ms: u64,
     pub linear_region_start: usize,
+    pub linear_region_end: usize,
+    /// (box size, count) at every counted scale, empty for other methods
+    pub box_counts: Vec<(f64, f64)>,
+    /// Spread of each box count across the averaged grids, parallel to `box_counts`
+    pub box_count_std: Vec<f64>,
+    /// Estimator of the fitted line
+    pub fit_method: FitMethod,
+    /// Dimensions of the bootstrap resamples, empty without bootstrap
+    pub bootstrap_dimensions: Vec<f64>,
 }
 
 impl FractalResult {
@@ -68,11 +176,181 @@ impl FractalResult {
             r_squared: self.r_squared,
             std_error: self.std_error,
             confidence_interval: self.confidence_interval,
+            slope: self.slope,
+            intercept: self.intercept,
             execution_time_ms: self.execution_time_ms,
             linear_region_start: self.linear_region_start,
+            linear_region_end: self.linear_region_end,
+            fit_method: self.fit_method.name().to_string(),
+            session: None,
             log_scales_data: self.log_scales,
             log_values_data: self.log_values,
             residuals_data: self.residuals,
+            box_counts_data: self.box_counts,
+            box_count_std_data: self.box_count_std,
+            bootstrap_data: self.bootstrap_dimensions,
+        }
+    }
+}
+
+/// Python wrapper for multifractal spectrum results.
+#[pyclass(name = "MultifractalResult")]
+#[derive(Clone)]
+pub struct PyMultifractalResult {
+    /// Box-counting dimension D_0 (NaN if 0 is not among the q values).
+    #[pyo3(get)]
+    pub d0: f64,
+    /// Information dimension D_1 (NaN if 1 is not among the q values).
+    #[pyo3(get)]
+    pub d1: f64,
+    /// Correlation dimension D_2 (NaN if 2 is not among the q values).
+    #[pyo3(get)]
+    pub d2: f64,
+    /// Width alpha_max - alpha_min of the singularity spectrum.
+    #[pyo3(get)]
+    pub delta_alpha: f64,
+    #[pyo3(get)]
+    pub execution_time_ms: u64,
+
+    // Internal storage
+    pub(crate) result: MultifractalResult,
+}
+
+#[pymethods]
+impl PyMultifractalResult {
+    /// Get the moments q as numpy array.
+    #[getter]
+    fn q<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.result.q.clone())
+    }
+
+    /// Get the generalized dimensions D_q as numpy array.
+    #[getter]
+    fn dq<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.result.dq.clone())
+    }
+
+    /// Get the mass exponents tau(q) as numpy array.
+    #[getter]
+    fn tau<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.result.tau.clone())
+    }
+
+    /// Get the singularity strengths alpha(q) as numpy array.
+    #[getter]
+    fn alpha<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.result.alpha.clone())
+    }
+
+    /// Get the singularity spectrum f(alpha(q)) as numpy array.
+    #[getter]
+    fn f_alpha<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.result.f_alpha.clone())
+    }
+
+    /// Get the R² of the fit behind each D_q as numpy array.
+    #[getter]
+    fn r_squared<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.result.r_squared.clone())
+    }
+
+    /// Get log of 1 / box size as numpy array.
+    #[getter]
+    fn log_scales<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.result.log_scales.clone())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MultifractalResult(D0={:.4}, D1={:.4}, D2={:.4}, delta_alpha={:.4})",
+            self.d0, self.d1, self.d2, self.delta_alpha
+        )
+    }
+}
+
+impl From<MultifractalResult> for PyMultifractalResult {
+    fn from(result: MultifractalResult) -> Self {
+        let dimension_at = |q: f64| {
+            result
+                .q
+                .iter()
+                .position(|&x| x == q)
+                .map_or(f64::NAN, |k| result.dq[k])
+        };
+        let alpha_min = result.alpha.iter().cloned().fold(f64::INFINITY, f64::min);
+        let alpha_max = result.alpha.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        Self {
+            d0: dimension_at(0.0),
+            d1: dimension_at(1.0),
+            d2: dimension_at(2.0),
+            delta_alpha: alpha_max - alpha_min,
+            execution_time_ms: result.execution_time_ms,
+            result,
+        }
+    }
+}
+
+/// Python wrapper for gliding-box lacunarity results.
+#[pyclass(name = "LacunarityResult")]
+#[derive(Clone)]
+pub struct PyLacunarityResult {
+    /// Slope of ln Lambda against ln r (Df - E for a self-similar fractal).
+    #[pyo3(get)]
+    pub slope: f64,
+    #[pyo3(get)]
+    pub r_squared: f64,
+    #[pyo3(get)]
+    pub execution_time_ms: u64,
+
+    // Internal storage
+    pub(crate) box_sizes_data: Vec<usize>,
+    pub(crate) lacunarity_data: Vec<f64>,
+}
+
+#[pymethods]
+impl PyLacunarityResult {
+    /// Get the box sizes r as numpy array.
+    #[getter]
+    fn box_sizes<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_vec(py, self.box_sizes_data.clone())
+    }
+
+    /// Get Lambda(r) as numpy array (NaN for empty data).
+    #[getter]
+    fn lacunarity<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.lacunarity_data.clone())
+    }
+
+    /// Get log of box sizes as numpy array.
+    #[getter]
+    fn log_scales<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_iter(py, self.box_sizes_data.iter().map(|&r| (r as f64).ln()))
+    }
+
+    /// Get log of Lambda(r) as numpy array.
+    #[getter]
+    fn log_values<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_iter(py, self.lacunarity_data.iter().map(|l| l.ln()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LacunarityResult(scales={}, slope={:.4}, r_squared={:.4})",
+            self.box_sizes_data.len(),
+            self.slope,
+            self.r_squared
+        )
+    }
+}
+
+impl From<LacunarityResult> for PyLacunarityResult {
+    fn from(result: LacunarityResult) -> Self {
+        Self {
+            slope: result.slope,
+            r_squared: result.r_squared,
+            execution_time_ms: result.execution_time_ms,
+            box_sizes_data: result.box_sizes,
+            lacunarity_data: result.lacunarity,
         }
     }
 }
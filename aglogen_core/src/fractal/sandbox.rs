@@ -0,0 +1,226 @@
+//! Sandbox (mass-radius) fractal dimension analysis.
+//!
+//! Spheres of growing radius r are centered on particles of the agglomerate
+//! and the primary-particle mass inside each is recorded. For a fractal the
+//! mean enclosed mass scales as M(r) ~ r^Df, so Df is the slope of
+//! ln M against ln r. Centers are drawn among the particles within one
+//! radius of gyration of the center of gravity, which keeps most spheres
+//! inside the structure at the default radii.
+
+use std::time::Instant;
+
+use pyo3::prelude::*;
+use rand::seq::SliceRandom;
+use rayon::prelude::*;
+
+use crate::common::fitting::{fit_linear_region, resolve_linear_region, FitMethod, LinearRegionParams};
+use crate::common::geometry::Vector3;
+use crate::common::rng::create_rng;
+use crate::common::validation::{check_count, check_coordinates};
+use crate::projection::extract_structure;
+use crate::simulation::metrics::{calculate_center_of_gravity, calculate_radius_of_gyration};
+
+use super::correlation::radius_range;
+use super::result::{FractalResult, PyFractalResult};
+
+/// Sandbox analysis parameters.
+#[derive(Debug, Clone)]
+pub struct SandboxParams {
+    /// Number of particles used as sandbox centers.
+    pub n_centers: usize,
+    /// Smallest and largest sandbox radius.
+    pub r_min: f64,
+    pub r_max: f64,
+    /// Number of log-spaced radii between `r_min` and `r_max`.
+    pub n_scales: usize,
+}
+
+/// Run the sandbox analysis on particles with centers `coordinates` and radii `radii`.
+///
+/// The mass of a particle is its volume (r³) and it counts as inside a
+/// sandbox when its center is. `log_scales` holds ln r and `log_values`
+/// the ln of the mass averaged over the centers.
+pub fn sandbox_internal(
+    coordinates: &[[f64; 3]],
+    radii: &[f64],
+    params: &SandboxParams,
+    seed: u64,
+    region: &LinearRegionParams,
+) -> FractalResult {
+    let start_time = Instant::now();
+
+    // Candidate centers: particles within Rg of the center of gravity
+    let cg = calculate_center_of_gravity(coordinates, radii);
+    let rg = calculate_radius_of_gyration(coordinates, radii);
+    let point = |c: &[f64; 3]| Vector3::new(c[0], c[1], c[2]);
+    let mut candidates: Vec<usize> = (0..coordinates.len())
+        .filter(|&i| point(&coordinates[i]).distance_to(&cg) <= rg)
+        .collect();
+    if candidates.is_empty() {
+        candidates = (0..coordinates.len()).collect();
+    }
+    let mut rng = create_rng(seed);
+    let centers: Vec<usize> = candidates
+        .choose_multiple(&mut rng, params.n_centers.min(candidates.len()))
+        .copied()
+        .collect();
+
+    let steps = params.n_scales.max(2) - 1;
+    let scales: Vec<f64> = (0..=steps)
+        .map(|i| params.r_min * (params.r_max / params.r_min).powf(i as f64 / steps as f64))
+        .collect();
+
+    // Enclosed mass at every scale, summed over the centers
+    let masses = centers
+        .par_iter()
+        .map(|&c| {
+            let center = point(&coordinates[c]);
+            let mut by_distance: Vec<(f64, f64)> = coordinates
+                .iter()
+                .zip(radii)
+                .map(|(p, &r)| (point(p).distance_to(&center), r * r * r))
+                .collect();
+            by_distance.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut enclosed = Vec::with_capacity(scales.len());
+            let (mut k, mut mass) = (0, 0.0);
+            for &r in &scales {
+                while k < by_distance.len() && by_distance[k].0 <= r {
+                    mass += by_distance[k].1;
+                    k += 1;
+                }
+                enclosed.push(mass);
+            }
+            enclosed
+        })
+        .reduce(
+            || vec![0.0; scales.len()],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                a
+            },
+        );
+
+    let mut log_scales = Vec::with_capacity(scales.len());
+    let mut log_values = Vec::with_capacity(scales.len());
+    for (&r, &m) in scales.iter().zip(&masses) {
+        if m > 0.0 {
+            log_scales.push(r.ln());
+            log_values.push((m / centers.len() as f64).ln());
+        }
+    }
+
+    // Radii grow along the arrays, so the fit starts from the largest sandboxes
+    let linear = fit_linear_region(&log_scales, &log_values, region);
+    let dimension = linear.fit.slope;
+    let std_error = linear.fit.std_error;
+    let ci_half = 1.96 * std_error;
+
+    FractalResult {
+        dimension,
+        r_squared: linear.fit.r_squared,
+        std_error,
+        slope: linear.fit.slope,
+        intercept: linear.fit.intercept,
+        confidence_interval: (dimension - ci_half, dimension + ci_half),
+        log_scales,
+        log_values,
+        residuals: linear.residuals,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+        linear_region_start: linear.start,
+        linear_region_end: linear.end,
+        box_counts: Vec::new(),
+        box_count_std: Vec::new(),
+        fit_method: FitMethod::Ols,
+        bootstrap_dimensions: Vec::new(),
+    }
+}
+
+/// Mass-radius (sandbox) fractal dimension of an agglomerate.
+///
+/// Spheres of log-spaced radii are centered on randomly chosen particles near
+/// the center of gravity and the particle mass (volume) inside them is
+/// averaged; Df is the slope of ln M(r) against ln r on the linear region.
+///
+/// # Arguments
+/// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
+/// * `n_centers` - Number of sandbox centers (default: 50)
+/// * `r_min` - Smallest sandbox radius (default: twice the mean particle radius)
+/// * `r_max` - Largest sandbox radius (default: the radius of gyration, at least twice `r_min`)
+/// * `n_scales` - Number of log-spaced radii (default: 15)
+/// * `seed` - Random seed for the choice of centers (None for random)
+/// * `linear_region` - `LinearRegionParams` tuning the linear-region detection (default thresholds when None)
+///
+/// # Returns
+/// * `FractalResult` with `log_scales` = ln r and `log_values` = ln M(r)
+#[pyfunction]
+#[pyo3(signature = (coordinates, radii, n_centers=50, r_min=None, r_max=None, n_scales=15, seed=None, linear_region=None))]
+pub fn sandbox_dimension(
+    py: Python<'_>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+    n_centers: usize,
+    r_min: Option<f64>,
+    r_max: Option<f64>,
+    n_scales: usize,
+    seed: Option<u64>,
+    linear_region: Option<LinearRegionParams>,
+) -> PyResult<PyFractalResult> {
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    check_coordinates("coordinates", &coords)?;
+    check_count("number of coordinates", coords.nrows(), 2)?;
+    check_count("n_centers", n_centers, 1)?;
+    check_count("n_scales", n_scales, 3)?;
+    let region = resolve_linear_region(linear_region)?;
+    let seed = seed.unwrap_or_else(rand::random);
+
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|c| [c[0], c[1], c[2]]).collect();
+    let (r_min, r_max) = radius_range(&points, &radii, r_min, r_max)?;
+
+    let params = SandboxParams {
+        n_centers,
+        r_min,
+        r_max,
+        n_scales,
+    };
+    let result = py.allow_threads(|| sandbox_internal(&points, &radii, &params, seed, &region));
+    Ok(result.to_py())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(r_min: f64, r_max: f64) -> SandboxParams {
+        SandboxParams {
+            n_centers: 20,
+            r_min,
+            r_max,
+            n_scales: 12,
+        }
+    }
+
+    #[test]
+    fn test_sandbox_chain_and_lattice() {
+        // Touching chain: M(r) ~ r
+        let chain: Vec<[f64; 3]> = (0..400).map(|i| [2.0 * i as f64, 0.0, 0.0]).collect();
+        let result = sandbox_internal(&chain, &[1.0; 400], &params(4.0, 100.0), 1, &LinearRegionParams::default());
+        assert!((result.dimension - 1.0).abs() < 0.1, "chain Df = {}", result.dimension);
+        assert_eq!(result.slope, result.dimension);
+        assert!(result.intercept.is_finite());
+
+        // Dense cubic lattice: M(r) ~ r^3
+        let mut lattice = Vec::new();
+        for x in 0..24 {
+            for y in 0..24 {
+                for z in 0..24 {
+                    lattice.push([2.0 * x as f64, 2.0 * y as f64, 2.0 * z as f64]);
+                }
+            }
+        }
+        let radii = vec![1.0; lattice.len()];
+        let result = sandbox_internal(&lattice, &radii, &params(4.0, 12.0), 1, &LinearRegionParams::default());
+        assert!((result.dimension - 3.0).abs() < 0.2, "lattice Df = {}", result.dimension);
+    }
+}
@@ -0,0 +1,277 @@
+//! Static structure factor for comparison with light and X-ray scattering.
+//!
+//! The orientation-averaged scattered intensity of N primary particles
+//! follows from the Debye formula, I(q) = sum_ij F_i(q) F_j(q) sin(q r_ij) /
+//! (q r_ij). With point scatterers (F = 1) this is the structure factor
+//! S(q); with the Rayleigh-Debye-Gans form factor of homogeneous spheres,
+//! F_i(q) = V_i * 3 (sin x - x cos x) / x^3 with x = q r_i, it is the full
+//! RDG intensity of the agglomerate. Both are normalized to 1 at q = 0, so in
+//! the fractal regime they decay as (q Rg)^-Df as in SAXS/SLS data.
+
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::common::validation::{check_count, check_coordinates, check_positive};
+use crate::projection::extract_structure;
+use crate::simulation::metrics::calculate_radius_of_gyration;
+
+/// `(q, S)` arrays returned by `structure_factor`.
+type StructureFactorArrays<'py> = (Bound<'py, PyArray1<f64>>, Bound<'py, PyArray1<f64>>);
+
+/// sin(x) / x, continuous at 0.
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-4 {
+        1.0 - x * x / 6.0
+    } else {
+        x.sin() / x
+    }
+}
+
+/// Normalized form factor of a homogeneous sphere, 3 (sin x - x cos x) / x^3 (1 at x = 0).
+pub fn sphere_form_factor(x: f64) -> f64 {
+    if x.abs() < 1e-3 {
+        1.0 - x * x / 10.0
+    } else {
+        3.0 * (x.sin() - x * x.cos()) / (x * x * x)
+    }
+}
+
+/// Phase q·δ across one distance bin at the largest q.
+const BIN_PHASE: f64 = 0.05;
+
+/// Most distance bins kept per pair of radius classes.
+const MAX_BINS: usize = 1 << 18;
+
+/// Radius classes the form factor tells apart; more distinct radii are grouped by rank.
+const MAX_RADIUS_CLASSES: usize = 8;
+
+/// Class of every particle by radius, and the number of classes.
+fn radius_classes(radii: &[f64]) -> (Vec<usize>, usize) {
+    let mut distinct = radii.to_vec();
+    distinct.sort_by(f64::total_cmp);
+    distinct.dedup();
+    if distinct.len() <= MAX_RADIUS_CLASSES {
+        let classes = radii
+            .iter()
+            .map(|r| distinct.partition_point(|d| d < r))
+            .collect();
+        return (classes, distinct.len());
+    }
+    let mut order: Vec<usize> = (0..radii.len()).collect();
+    order.sort_by(|&a, &b| radii[a].total_cmp(&radii[b]));
+    let mut classes = vec![0; radii.len()];
+    for (rank, &i) in order.iter().enumerate() {
+        classes[i] = rank * MAX_RADIUS_CLASSES / radii.len();
+    }
+    (classes, MAX_RADIUS_CLASSES)
+}
+
+/// Debye-formula intensity at each `q`, normalized to 1 at q = 0.
+///
+/// Without `form_factor` every particle scatters with unit amplitude;
+/// with it, with its volume times the sphere form factor. The pair
+/// distances are histogrammed once, in bins with q δ <= 0.05 at the largest
+/// q, and every bin scatters from the mean distance of its pairs. With the
+/// form factor and more than 8 distinct radii, pairs scatter with the mean
+/// amplitude of 8 radius classes (the self terms stay exact).
+pub fn structure_factor_internal(coordinates: &[[f64; 3]], radii: &[f64], q: &[f64], form_factor: bool) -> Vec<f64> {
+    let n = coordinates.len();
+    let volumes: Vec<f64> = radii.iter().map(|r| 4.0 / 3.0 * std::f64::consts::PI * r * r * r).collect();
+    let forward: f64 = if form_factor { volumes.iter().sum() } else { n as f64 };
+    let (classes, n_classes) = if form_factor { radius_classes(radii) } else { (vec![0; n], 1) };
+
+    // Every pair distance is below twice the largest distance to the centroid
+    let centroid = coordinates.iter().fold([0.0; 3], |m, c| [m[0] + c[0], m[1] + c[1], m[2] + c[2]]);
+    let centroid = centroid.map(|c| c / n.max(1) as f64);
+    let reach = coordinates
+        .iter()
+        .map(|c| ((c[0] - centroid[0]).powi(2) + (c[1] - centroid[1]).powi(2) + (c[2] - centroid[2]).powi(2)).sqrt())
+        .fold(0.0, f64::max);
+    let q_max = q.iter().fold(0.0, |m: f64, &q| m.max(q.abs()));
+    let width = (BIN_PHASE / q_max).max(2.0 * reach / MAX_BINS as f64);
+    let n_bins = ((2.0 * reach / width) as usize + 1).min(MAX_BINS);
+
+    // Pair count and summed distance per (class pair, distance bin)
+    let mut count = vec![0.0; n_classes * n_classes * n_bins];
+    let mut distance_sum = vec![0.0; count.len()];
+    for i in 0..n {
+        let a = coordinates[i];
+        for j in (i + 1)..n {
+            let b = coordinates[j];
+            let d = ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+            let (lo, hi) = (classes[i].min(classes[j]), classes[i].max(classes[j]));
+            let index = (lo * n_classes + hi) * n_bins + ((d / width) as usize).min(n_bins - 1);
+            count[index] += 1.0;
+            distance_sum[index] += d;
+        }
+    }
+    let bins: Vec<(usize, usize, f64, f64)> = (0..count.len())
+        .filter(|&k| count[k] > 0.0)
+        .map(|k| {
+            let pair = k / n_bins;
+            (pair / n_classes, pair % n_classes, count[k], distance_sum[k] / count[k])
+        })
+        .collect();
+
+    q.par_iter()
+        .map(|&q| {
+            let amplitude: Vec<f64> = if form_factor {
+                volumes.iter().zip(radii).map(|(v, r)| v * sphere_form_factor(q * r)).collect()
+            } else {
+                vec![1.0; n]
+            };
+            let mut class_amplitude = vec![0.0f64; n_classes];
+            let mut class_size = vec![0.0f64; n_classes];
+            for (&c, &a) in classes.iter().zip(&amplitude) {
+                class_amplitude[c] += a;
+                class_size[c] += 1.0;
+            }
+            for (a, size) in class_amplitude.iter_mut().zip(&class_size) {
+                *a /= size.max(1.0);
+            }
+
+            let mut sum: f64 = amplitude.iter().map(|a| a * a).sum();
+            for &(c, d, pairs, distance) in &bins {
+                sum += 2.0 * class_amplitude[c] * class_amplitude[d] * pairs * sinc(q * distance);
+            }
+            sum / (forward * forward)
+        })
+        .collect()
+}
+
+/// Orientation-averaged structure factor S(q) of an agglomerate.
+///
+/// Evaluated with the Debye formula over the histogram of particle pair
+/// distances at `n_q` log-spaced wave numbers and normalized to S(0) = 1. With
+/// `form_factor=True` each particle scatters as a homogeneous sphere
+/// (Rayleigh-Debye-Gans), giving the intensity I(q)/I(0) to compare with
+/// SAXS/SLS measurements; q is in inverse length units of the coordinates.
+///
+/// # Arguments
+/// * `coordinates` - 3D particle coordinates (N x 3 array, at least 2 rows, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
+/// * `q_min` - Smallest wave number (default: 0.1 / Rg, inside the Guinier regime)
+/// * `q_max` - Largest wave number (default: 10 / mean radius, past the primary particles)
+/// * `n_q` - Number of log-spaced wave numbers (default: 100)
+/// * `form_factor` - Include the RDG sphere form factor (default: false)
+///
+/// # Returns
+/// * Tuple of numpy arrays `(q, S)`, both (n_q,)
+#[pyfunction]
+#[pyo3(signature = (coordinates, radii, q_min=None, q_max=None, n_q=100, form_factor=false))]
+pub fn structure_factor<'py>(
+    py: Python<'py>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+    q_min: Option<f64>,
+    q_max: Option<f64>,
+    n_q: usize,
+    form_factor: bool,
+) -> PyResult<StructureFactorArrays<'py>> {
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    check_coordinates("coordinates", &coords)?;
+    check_count("number of coordinates", coords.nrows(), 2)?;
+    check_count("n_q", n_q, 2)?;
+
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|c| [c[0], c[1], c[2]]).collect();
+    let mean_radius = radii.iter().sum::<f64>() / radii.len() as f64;
+    let q_min = q_min.unwrap_or_else(|| 0.1 / calculate_radius_of_gyration(&points, &radii));
+    let q_max = q_max.unwrap_or(10.0 / mean_radius);
+    check_positive("q_min", q_min)?;
+    check_positive("q_max", q_max)?;
+    if q_max <= q_min {
+        return Err(PyValueError::new_err(format!(
+            "q_max ({}) must be larger than q_min ({})",
+            q_max, q_min
+        )));
+    }
+
+    let q: Vec<f64> = (0..n_q)
+        .map(|i| q_min * (q_max / q_min).powf(i as f64 / (n_q - 1) as f64))
+        .collect();
+    let s = py.allow_threads(|| structure_factor_internal(&points, &radii, &q, form_factor));
+    Ok((PyArray1::from_vec(py, q), PyArray1::from_vec(py, s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::common::rng::create_rng;
+
+    #[test]
+    fn test_two_particle_structure_factor() {
+        let coords = [[0.0, 0.0, 0.0], [3.0, 0.0, 0.0]];
+        let q = [0.0, 0.5, 1.0, 2.0];
+        let s = structure_factor_internal(&coords, &[1.0, 1.0], &q, false);
+        for (&q, &s) in q.iter().zip(&s) {
+            assert!((s - (1.0 + sinc(3.0 * q)) / 2.0).abs() < 1e-12);
+        }
+
+        // Identical spheres: the form factor multiplies S(q)
+        let i = structure_factor_internal(&coords, &[1.0, 1.0], &q, true);
+        for k in 0..q.len() {
+            assert!((i[k] - s[k] * sphere_form_factor(q[k]).powi(2)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_guinier_regime() {
+        // At small q, S(q) = 1 - (q Rg)^2 / 3 with Rg of the point scatterers
+        let coords: Vec<[f64; 3]> = (0..20).map(|i| [2.0 * i as f64, (i % 3) as f64, 0.0]).collect();
+        let radii = vec![1e-6; 20];
+        let n = coords.len() as f64;
+        let mean = coords.iter().fold([0.0; 3], |m, c| [m[0] + c[0] / n, m[1] + c[1] / n, m[2] + c[2] / n]);
+        let rg2 = coords
+            .iter()
+            .map(|c| (c[0] - mean[0]).powi(2) + (c[1] - mean[1]).powi(2) + (c[2] - mean[2]).powi(2))
+            .sum::<f64>()
+            / n;
+        let q = 0.01;
+        let s = structure_factor_internal(&coords, &radii, &[q], false)[0];
+        assert!(((1.0 - s) / (q * q * rg2 / 3.0) - 1.0).abs() < 0.01, "S = {}", s);
+    }
+
+    #[test]
+    fn test_binned_pairs_match_direct_sum() {
+        let mut rng = create_rng(4);
+        let coords: Vec<[f64; 3]> = (0..80).map(|_| [0, 1, 2].map(|_| rng.gen_range(-10.0..10.0))).collect();
+        let q: Vec<f64> = (0..30).map(|i| 0.02 * 1.2f64.powi(i)).collect();
+        let direct = |radii: &[f64], form_factor: bool| -> Vec<f64> {
+            let volumes: Vec<f64> = radii.iter().map(|r| r * r * r).collect();
+            let forward: f64 = if form_factor { volumes.iter().sum() } else { radii.len() as f64 };
+            q.iter()
+                .map(|&q| {
+                    let a: Vec<f64> = (0..radii.len())
+                        .map(|i| if form_factor { volumes[i] * sphere_form_factor(q * radii[i]) } else { 1.0 })
+                        .collect();
+                    let mut sum = 0.0;
+                    for i in 0..radii.len() {
+                        for j in 0..radii.len() {
+                            let d = (0..3).map(|k| (coords[i][k] - coords[j][k]).powi(2)).sum::<f64>().sqrt();
+                            sum += a[i] * a[j] * sinc(q * d);
+                        }
+                    }
+                    sum / (forward * forward)
+                })
+                .collect()
+        };
+
+        // Point scatterers and a few distinct radii are only binned in distance
+        let mixture: Vec<f64> = (0..80).map(|i| [0.5, 1.0, 1.5][i % 3]).collect();
+        for (radii, form_factor) in [(vec![1.0; 80], false), (mixture, true)] {
+            let binned = structure_factor_internal(&coords, &radii, &q, form_factor);
+            for (b, d) in binned.iter().zip(direct(&radii, form_factor)) {
+                assert!((b - d).abs() < 1e-3 * d.abs().max(0.01), "{} vs {}", b, d);
+            }
+        }
+
+        // Continuous radii fall into radius classes
+        let (classes, n_classes) = radius_classes(&(0..80).map(|i| 1.0 + i as f64 / 100.0).collect::<Vec<_>>());
+        assert_eq!(n_classes, MAX_RADIUS_CLASSES);
+        assert_eq!((classes[0], classes[79]), (0, MAX_RADIUS_CLASSES - 1));
+    }
+}
@@ -0,0 +1,880 @@
+//! Particle file formats.
+//!
+//! Agglomerates are written as one particle per line in the formats read by
+//! the usual molecular viewers: extended XYZ (OVITO, VMD, ASE), PDB (VMD,
+//! PyMOL) and LAMMPS text dumps (OVITO). Every format carries the particle
+//! radius: a `radius` column in XYZ and LAMMPS dumps, the B-factor column in
+//! PDB. The same formats can be read back into a `SimulationResult`.
+//!
+//! Results can also be written as legacy VTK PolyData for ParaView: a vertex
+//! per particle carrying point scalars such as the radius, and a line per
+//! contact, so a Glyph filter scaled by `radius` draws the spheres.
+//!
+//! Ensembles of agglomerates go to a single HDF5 file read by h5py, HDFView
+//! and the aerosol tools built on HDF5. Layout:
+//!
+//! ```text
+//! /                       attrs: format = "aglogen-ensemble", version, n_agglomerates
+//! /agglomerate_000000/    attrs: seed, n_particles, parameters (JSON, when recorded),
+//!                                fractal_dimension, prefactor, radius_of_gyration, ...
+//!     coordinates         float64 (N, 3)
+//!     radii               float64 (N,)
+//!     ids                 uint32 (N,)
+//!     cluster_ids         uint32 (N,)
+//!     contacts            uint64 (M, 2), particle rows i < j in contact
+//!     rg_evolution        float64 (K,)
+//! /agglomerate_000001/    ...
+//! ```
+//!
+//! Groups follow the order of the results. Writing HDF5 needs the `hdf5`
+//! cargo feature, which links the HDF5 library; without it the export raises.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use ndarray::{aview2, ArrayView2};
+use pyo3::exceptions::{PyIOError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::common::units::PyUnits;
+use crate::common::validation::{check_coordinates, check_count, check_positive};
+use crate::projection::{extract_structure, PyProjectionResult};
+use crate::simulation::result::{PySimulationResult, SimulationResult};
+
+/// Particle centers and radii read from a file (radii `None` when the file has none).
+pub type ParticleData = (Vec<[f64; 3]>, Option<Vec<f64>>);
+
+/// Coordinates that fit the fixed-width `{:>8.3}` PDB columns.
+const PDB_COORDINATES: std::ops::RangeInclusive<f64> = -999.999..=9999.999;
+
+/// Largest radius that fits the `{:>6.2}` B-factor column.
+const PDB_MAX_RADIUS: f64 = 999.99;
+
+/// Longest title line of a legacy VTK file.
+const VTK_MAX_TITLE: usize = 255;
+
+/// Supported particle file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleFormat {
+    Xyz,
+    Pdb,
+    LammpsDump,
+}
+
+impl ParticleFormat {
+    /// Parse a format name ("xyz", "pdb", "lammps").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "xyz" | "extxyz" => Some(ParticleFormat::Xyz),
+            "pdb" => Some(ParticleFormat::Pdb),
+            "lammps" | "dump" | "lammpstrj" => Some(ParticleFormat::LammpsDump),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|e| e.to_str()).and_then(Self::from_name)
+    }
+}
+
+/// Write extended XYZ: species, position and radius of every particle.
+pub fn write_xyz<W: Write>(out: &mut W, coords: ArrayView2<f64>, radii: &[f64], element: &str, comment: &str) -> std::io::Result<()> {
+    writeln!(out, "{}", radii.len())?;
+    writeln!(out, "Properties=species:S:1:pos:R:3:radius:R:1 {}", comment)?;
+    for (c, r) in coords.rows().into_iter().zip(radii) {
+        writeln!(out, "{} {:.6} {:.6} {:.6} {:.6}", element, c[0], c[1], c[2], r)?;
+    }
+    Ok(())
+}
+
+/// Write PDB HETATM records with the radius in the B-factor column.
+///
+/// Serial numbers wrap after 99999, as most writers do for large systems.
+pub fn write_pdb<W: Write>(out: &mut W, coords: ArrayView2<f64>, radii: &[f64], element: &str, comment: &str) -> std::io::Result<()> {
+    if !comment.is_empty() {
+        writeln!(out, "REMARK   1 {}", comment)?;
+    }
+    // One-letter elements start in column 14 of the atom name
+    let name = if element.len() == 1 { format!(" {}", element) } else { element.to_string() };
+    for (i, (c, r)) in coords.rows().into_iter().zip(radii).enumerate() {
+        writeln!(
+            out,
+            "HETATM{:>5} {:<4} AGG A   1    {:>8.3}{:>8.3}{:>8.3}{:>6.2}{:>6.2}          {:>2}",
+            (i + 1) % 100_000,
+            name,
+            c[0],
+            c[1],
+            c[2],
+            1.0,
+            r,
+            element
+        )?;
+    }
+    writeln!(out, "END")
+}
+
+/// Write a single-frame LAMMPS text dump with a box enclosing every sphere.
+pub fn write_lammps_dump<W: Write>(out: &mut W, coords: ArrayView2<f64>, radii: &[f64]) -> std::io::Result<()> {
+    let mut lo = [0.0; 3];
+    let mut hi = [0.0; 3];
+    if !radii.is_empty() {
+        lo = [f64::INFINITY; 3];
+        hi = [f64::NEG_INFINITY; 3];
+        for (c, r) in coords.rows().into_iter().zip(radii) {
+            for k in 0..3 {
+                lo[k] = lo[k].min(c[k] - r);
+                hi[k] = hi[k].max(c[k] + r);
+            }
+        }
+    }
+
+    writeln!(out, "ITEM: TIMESTEP")?;
+    writeln!(out, "0")?;
+    writeln!(out, "ITEM: NUMBER OF ATOMS")?;
+    writeln!(out, "{}", radii.len())?;
+    writeln!(out, "ITEM: BOX BOUNDS ff ff ff")?;
+    for k in 0..3 {
+        writeln!(out, "{:.6} {:.6}", lo[k], hi[k])?;
+    }
+    writeln!(out, "ITEM: ATOMS id type x y z radius")?;
+    for (i, (c, r)) in coords.rows().into_iter().zip(radii).enumerate() {
+        writeln!(out, "{} 1 {:.6} {:.6} {:.6} {:.6}", i + 1, c[0], c[1], c[2], r)?;
+    }
+    Ok(())
+}
+
+/// Write legacy VTK PolyData (ASCII): a vertex cell per point, a line cell
+/// per pair in `lines`, and every entry of `scalars` as point data.
+pub fn write_vtk<W: Write>(
+    out: &mut W,
+    points: &[[f64; 3]],
+    lines: &[(usize, usize)],
+    scalars: &[(&str, &[f64])],
+    title: &str,
+) -> std::io::Result<()> {
+    let n = points.len();
+    writeln!(out, "# vtk DataFile Version 3.0")?;
+    writeln!(out, "{}", title)?;
+    writeln!(out, "ASCII")?;
+    writeln!(out, "DATASET POLYDATA")?;
+    writeln!(out, "POINTS {} double", n)?;
+    for p in points {
+        writeln!(out, "{:.6} {:.6} {:.6}", p[0], p[1], p[2])?;
+    }
+    writeln!(out, "VERTICES {} {}", n, 2 * n)?;
+    for i in 0..n {
+        writeln!(out, "1 {}", i)?;
+    }
+    if !lines.is_empty() {
+        writeln!(out, "LINES {} {}", lines.len(), 3 * lines.len())?;
+        for (i, j) in lines {
+            writeln!(out, "2 {} {}", i, j)?;
+        }
+    }
+    writeln!(out, "POINT_DATA {}", n)?;
+    for (name, values) in scalars {
+        writeln!(out, "SCALARS {} double 1", name)?;
+        writeln!(out, "LOOKUP_TABLE default")?;
+        for v in values.iter() {
+            writeln!(out, "{:.6}", v)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse a floating-point field, naming the line on failure.
+fn parse_field(field: &str, line: usize) -> Result<f64, String> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| format!("line {}: cannot parse '{}' as a number", line + 1, field.trim()))
+}
+
+/// Read an XYZ file, taking the radius from the extended-XYZ `Properties`
+/// spec or, in plain XYZ, from an optional fifth column.
+pub fn read_xyz(text: &str) -> Result<ParticleData, String> {
+    let mut lines = text.lines();
+    let n: usize = lines
+        .next()
+        .and_then(|l| l.trim().parse().ok())
+        .ok_or("first line must hold the particle count")?;
+    let comment = lines.next().unwrap_or("");
+
+    // Column of x and of the radius, counted in whitespace-separated fields
+    let (mut pos_col, mut radius_col) = (1, Some(4));
+    if let Some(spec) = comment.split_whitespace().find_map(|w| w.strip_prefix("Properties=")) {
+        let fields: Vec<&str> = spec.split(':').collect();
+        let (mut pos, mut radius, mut col) = (None, None, 0);
+        for triple in fields.chunks(3) {
+            let width: usize = triple.get(2).and_then(|w| w.parse().ok()).ok_or("malformed Properties spec")?;
+            match triple[0].to_ascii_lowercase().as_str() {
+                "pos" => pos = Some(col),
+                "radius" | "radii" => radius = Some(col),
+                _ => {}
+            }
+            col += width;
+        }
+        pos_col = pos.ok_or("Properties spec has no 'pos' column")?;
+        radius_col = radius;
+    }
+
+    let mut coords = Vec::with_capacity(n);
+    let mut radii = Vec::with_capacity(n);
+    for (i, line) in lines.take(n).enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < pos_col + 3 {
+            return Err(format!("line {}: expected at least {} columns", i + 3, pos_col + 3));
+        }
+        coords.push([
+            parse_field(fields[pos_col], i + 2)?,
+            parse_field(fields[pos_col + 1], i + 2)?,
+            parse_field(fields[pos_col + 2], i + 2)?,
+        ]);
+        if let Some(r) = radius_col.and_then(|c| fields.get(c)) {
+            radii.push(parse_field(r, i + 2)?);
+        }
+    }
+    if coords.len() != n {
+        return Err(format!("expected {} particles, found {}", n, coords.len()));
+    }
+    let radii = (radii.len() == n && n > 0).then_some(radii);
+    Ok((coords, radii))
+}
+
+/// Read the ATOM/HETATM records of a PDB file, taking radii from the B-factor column.
+pub fn read_pdb(text: &str) -> Result<ParticleData, String> {
+    let mut coords = Vec::new();
+    let mut radii = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.starts_with("ENDMDL") || line.starts_with("END ") || line.trim_end() == "END" {
+            break;
+        }
+        if !(line.starts_with("ATOM") || line.starts_with("HETATM")) {
+            continue;
+        }
+        let field = |a: usize, b: usize| line.get(a..b.min(line.len())).unwrap_or("");
+        coords.push([
+            parse_field(field(30, 38), i)?,
+            parse_field(field(38, 46), i)?,
+            parse_field(field(46, 54), i)?,
+        ]);
+        radii.push(parse_field(field(60, 66), i).ok());
+    }
+    if coords.is_empty() {
+        return Err("no ATOM or HETATM records".to_string());
+    }
+    Ok((coords, radii.into_iter().collect()))
+}
+
+/// Read the first frame of a LAMMPS text dump, taking radii from a `radius`
+/// (or `diameter`) column.
+pub fn read_lammps_dump(text: &str) -> Result<ParticleData, String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let find = |item: &str| lines.iter().position(|l| l.starts_with(item));
+
+    let count_line = find("ITEM: NUMBER OF ATOMS").ok_or("missing 'ITEM: NUMBER OF ATOMS'")?;
+    let n: usize = lines
+        .get(count_line + 1)
+        .and_then(|l| l.trim().parse().ok())
+        .ok_or("cannot read the number of atoms")?;
+    let atoms_line = find("ITEM: ATOMS").ok_or("missing 'ITEM: ATOMS'")?;
+    let columns: Vec<&str> = lines[atoms_line]["ITEM: ATOMS".len()..].split_whitespace().collect();
+    let column = |names: &[&str]| columns.iter().position(|c| names.contains(c));
+
+    let xyz = [
+        column(&["x", "xu", "xs"]).ok_or("dump has no x column")?,
+        column(&["y", "yu", "ys"]).ok_or("dump has no y column")?,
+        column(&["z", "zu", "zs"]).ok_or("dump has no z column")?,
+    ];
+    let (radius_col, scale) = match (column(&["radius"]), column(&["diameter"])) {
+        (Some(c), _) => (Some(c), 1.0),
+        (None, Some(c)) => (Some(c), 0.5),
+        (None, None) => (None, 1.0),
+    };
+
+    let mut coords = Vec::with_capacity(n);
+    let mut radii = Vec::with_capacity(n);
+    for i in atoms_line + 1..atoms_line + 1 + n {
+        let line = lines.get(i).ok_or_else(|| format!("expected {} atoms, file ends early", n))?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < columns.len() {
+            return Err(format!("line {}: expected {} columns", i + 1, columns.len()));
+        }
+        coords.push([
+            parse_field(fields[xyz[0]], i)?,
+            parse_field(fields[xyz[1]], i)?,
+            parse_field(fields[xyz[2]], i)?,
+        ]);
+        if let Some(c) = radius_col {
+            radii.push(parse_field(fields[c], i)? * scale);
+        }
+    }
+    Ok((coords, radius_col.map(|_| radii)))
+}
+
+/// Load an agglomerate saved as XYZ, PDB or LAMMPS dump, recomputing its metrics.
+///
+/// The result is built as in `SimulationResult.from_arrays`: Rg, porosity,
+/// coordination and inertia metrics are recomputed from the saved structure.
+///
+/// # Arguments
+/// * `path` - File written by `export_agglomerate` or another tool
+/// * `format` - "xyz", "pdb" or "lammps"; by default guessed from the extension
+/// * `radius` - Radius of every particle; required when the file stores no
+///   radii and overrides them otherwise (e.g. for PDB files with real B-factors)
+/// * `units` - `Units` giving the physical meaning of the lengths
+#[pyfunction]
+#[pyo3(signature = (path, format=None, radius=None, units=None))]
+pub fn load_agglomerate(
+    py: Python<'_>,
+    path: PathBuf,
+    format: Option<&str>,
+    radius: Option<f64>,
+    units: Option<PyUnits>,
+) -> PyResult<PySimulationResult> {
+    let format = resolve_format(format, &path)?;
+    let (coords, radii) = read_structure(&path, format, radius)?;
+    let result = py.allow_threads(|| SimulationResult::from_structure(coords, radii));
+    result.into_py(py, Vec::new()).map(|r| r.with_units(units))
+}
+
+/// Coordinates and radii stored in `path`, checked as for `SimulationResult.from_arrays`.
+fn read_structure(path: &Path, format: ParticleFormat, radius: Option<f64>) -> PyResult<(Vec<[f64; 3]>, Vec<f64>)> {
+    if let Some(r) = radius {
+        check_positive("radius", r)?;
+    }
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| PyIOError::new_err(format!("cannot read {}: {}", path.display(), e)))?;
+
+    let parsed = match format {
+        ParticleFormat::Xyz => read_xyz(&text),
+        ParticleFormat::Pdb => read_pdb(&text),
+        ParticleFormat::LammpsDump => read_lammps_dump(&text),
+    };
+    let (coords, file_radii) = parsed.map_err(|e| PyValueError::new_err(format!("{}: {}", path.display(), e)))?;
+    check_coordinates(&format!("coordinates in {}", path.display()), &aview2(&coords))?;
+    let radii = match (radius, file_radii) {
+        (Some(r), _) => vec![r; coords.len()],
+        (None, Some(radii)) => radii,
+        (None, None) => {
+            return Err(PyValueError::new_err(format!(
+                "{} stores no radii; pass radius=...",
+                path.display()
+            )))
+        }
+    };
+    if let Some(i) = radii.iter().position(|&r| !(r.is_finite() && r > 0.0)) {
+        return Err(PyValueError::new_err(format!(
+            "{}: radius of particle {} is {}, expected a positive number",
+            path.display(),
+            i,
+            radii[i]
+        )));
+    }
+    Ok((coords, radii))
+}
+
+/// Explicit format name, or the one implied by the extension of `path`.
+fn resolve_format(format: Option<&str>, path: &Path) -> PyResult<ParticleFormat> {
+    match format {
+        Some(name) => ParticleFormat::from_name(name).ok_or_else(|| {
+            PyValueError::new_err(format!("unknown format '{}': use 'xyz', 'pdb' or 'lammps'", name))
+        }),
+        None => ParticleFormat::from_path(path).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "cannot guess the format of {}: pass format='xyz', 'pdb' or 'lammps'",
+                path.display()
+            ))
+        }),
+    }
+}
+
+/// Check that every coordinate and radius fits its fixed-width PDB column.
+fn check_pdb_columns(coords: ArrayView2<f64>, radii: &[f64]) -> PyResult<()> {
+    if let Some(c) = coords.iter().find(|c| !PDB_COORDINATES.contains(*c)) {
+        return Err(PyValueError::new_err(format!(
+            "coordinate {:.3} does not fit the PDB columns ({} to {}); use 'xyz' or 'lammps'",
+            c,
+            PDB_COORDINATES.start(),
+            PDB_COORDINATES.end()
+        )));
+    }
+    if let Some(r) = radii.iter().find(|&&r| r > PDB_MAX_RADIUS) {
+        return Err(PyValueError::new_err(format!(
+            "radius {:.2} does not fit the PDB B-factor column (<= {}); use 'xyz' or 'lammps'",
+            r, PDB_MAX_RADIUS
+        )));
+    }
+    Ok(())
+}
+
+/// Write an agglomerate to a particle file for OVITO, VMD and similar viewers.
+///
+/// # Arguments
+/// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
+/// * `path` - Output file, overwritten if it exists
+/// * `format` - "xyz" (extended XYZ), "pdb" or "lammps" (text dump); by default
+///   guessed from the extension (.xyz, .pdb, .dump/.lammpstrj)
+/// * `element` - Species name written for every particle (default: "C")
+/// * `comment` - Free text stored in the XYZ comment line or a PDB REMARK
+///   (ignored for LAMMPS dumps)
+#[pyfunction]
+#[pyo3(signature = (coordinates, radii, path, format=None, element="C", comment=""))]
+pub fn export_agglomerate(
+    py: Python<'_>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+    path: PathBuf,
+    format: Option<&str>,
+    element: &str,
+    comment: &str,
+) -> PyResult<()> {
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+
+    let format = resolve_format(format, &path)?;
+    if element.is_empty() || element.contains(char::is_whitespace) {
+        return Err(PyValueError::new_err(format!("element must be a single word, got '{}'", element)));
+    }
+    if comment.contains('\n') {
+        return Err(PyValueError::new_err("comment must fit on one line"));
+    }
+    if format == ParticleFormat::Pdb {
+        if element.len() > 2 {
+            return Err(PyValueError::new_err(format!("PDB elements have at most 2 characters, got '{}'", element)));
+        }
+        check_pdb_columns(coords.view(), &radii)?;
+    }
+
+    let io_error = |e: std::io::Error| PyIOError::new_err(format!("cannot write {}: {}", path.display(), e));
+    py.allow_threads(|| {
+        let mut out = BufWriter::new(File::create(&path)?);
+        match format {
+            ParticleFormat::Xyz => write_xyz(&mut out, coords.view(), &radii, element, comment)?,
+            ParticleFormat::Pdb => write_pdb(&mut out, coords.view(), &radii, element, comment)?,
+            ParticleFormat::LammpsDump => write_lammps_dump(&mut out, coords.view(), &radii)?,
+        }
+        out.flush()
+    })
+    .map_err(io_error)
+}
+
+/// Write a simulation or projection result as legacy VTK PolyData for ParaView.
+///
+/// Particles become vertices with point scalars `radius` and, for simulation
+/// results, `cluster_id` and `coordination`; contacts become line cells.
+/// Projections are written as 2D polydata (z = 0) in the image plane. In
+/// ParaView, apply a Glyph filter with a Sphere glyph scaled by `radius`
+/// (scale factor 2, as the glyph has radius 0.5) to draw the spheres.
+///
+/// # Arguments
+/// * `result` - `SimulationResult` or `ProjectionResult` to write
+/// * `path` - Output file (conventionally .vtk), overwritten if it exists
+/// * `contacts` - Write the contacts of a simulation result as lines (default: True)
+/// * `title` - Header line of the file (default: describes the result)
+#[pyfunction]
+#[pyo3(signature = (result, path, contacts=true, title=None))]
+pub fn export_vtk(
+    py: Python<'_>,
+    result: &Bound<'_, PyAny>,
+    path: PathBuf,
+    contacts: bool,
+    title: Option<String>,
+) -> PyResult<()> {
+    let (points, lines, scalars, default_title) = if let Ok(result) = result.downcast::<PySimulationResult>() {
+        let result = result.borrow();
+        let points: Vec<[f64; 3]> = result.coordinates_data.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+        let lines = if contacts {
+            result.contacts_data.iter().map(|c| (c.i, c.j)).collect()
+        } else {
+            Vec::new()
+        };
+        let mut scalars = vec![("radius", result.radii_data.clone())];
+        for (name, values) in [("cluster_id", &result.cluster_ids_data), ("coordination", &result.coordination_data)] {
+            if values.len() == points.len() {
+                scalars.push((name, values.iter().map(|&v| v as f64).collect()));
+            }
+        }
+        let title = format!("aglogen agglomerate, {} particles", points.len());
+        (points, lines, scalars, title)
+    } else if let Ok(projection) = result.downcast::<PyProjectionResult>() {
+        let projection = projection.borrow();
+        let points = projection.x.iter().zip(&projection.y).map(|(&x, &y)| [x, y, 0.0]).collect();
+        let title = format!(
+            "aglogen projection, azimuth {} deg, elevation {} deg",
+            projection.azimuth, projection.elevation
+        );
+        (points, Vec::new(), vec![("radius", projection.radii.clone())], title)
+    } else {
+        return Err(PyTypeError::new_err("result must be a SimulationResult or a ProjectionResult"));
+    };
+
+    let title = title.unwrap_or(default_title);
+    if title.contains('\n') || title.len() > VTK_MAX_TITLE {
+        return Err(PyValueError::new_err(format!(
+            "title must fit on one line of at most {} characters",
+            VTK_MAX_TITLE
+        )));
+    }
+
+    let io_error = |e: std::io::Error| PyIOError::new_err(format!("cannot write {}: {}", path.display(), e));
+    py.allow_threads(|| {
+        let scalars: Vec<(&str, &[f64])> = scalars.iter().map(|(name, v)| (*name, v.as_slice())).collect();
+        let mut out = BufWriter::new(File::create(&path)?);
+        write_vtk(&mut out, &points, &lines, &scalars, &title)?;
+        out.flush()
+    })
+    .map_err(io_error)
+}
+
+/// Values of one dataset, stored row-major.
+#[derive(Debug, Clone, PartialEq)]
+enum Values {
+    F64(Vec<f64>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+}
+
+/// Dataset of a group: `columns` 1 stores a vector, more a (rows, columns) matrix.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(feature = "hdf5"), allow(dead_code))]
+struct Dataset {
+    name: &'static str,
+    columns: usize,
+    values: Values,
+}
+
+/// Datasets and attributes of one agglomerate group.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "hdf5"), allow(dead_code))]
+struct Agglomerate {
+    datasets: Vec<Dataset>,
+    metrics: Vec<(&'static str, f64)>,
+    seed: u64,
+    n_particles: u64,
+    parameters: Option<String>,
+}
+
+impl Agglomerate {
+    fn of(result: &PySimulationResult) -> Self {
+        let contacts = result.contacts_data.iter().flat_map(|c| [c.i as u64, c.j as u64]).collect();
+        let dataset = |name, columns, values| Dataset { name, columns, values };
+        Self {
+            datasets: vec![
+                dataset("coordinates", 3, Values::F64(result.coordinates_data.clone())),
+                dataset("radii", 1, Values::F64(result.radii_data.clone())),
+                dataset("ids", 1, Values::U32(result.ids_data.clone())),
+                dataset("cluster_ids", 1, Values::U32(result.cluster_ids_data.clone())),
+                dataset("contacts", 2, Values::U64(contacts)),
+                dataset("rg_evolution", 1, Values::F64(result.rg_evolution_data.clone())),
+            ],
+            metrics: vec![
+                ("fractal_dimension", result.fractal_dimension),
+                ("fractal_dimension_std", result.fractal_dimension_std),
+                ("prefactor", result.prefactor),
+                ("fit_r_squared", result.fit_r_squared),
+                ("radius_of_gyration", result.radius_of_gyration),
+                ("porosity", result.porosity),
+                ("coordination_mean", result.coordination_mean),
+                ("coordination_std", result.coordination_std),
+                ("anisotropy", result.anisotropy),
+                ("asphericity", result.asphericity),
+                ("acylindricity", result.acylindricity),
+                ("relative_shape_anisotropy", result.relative_shape_anisotropy),
+            ],
+            seed: result.seed,
+            n_particles: result.radii_data.len() as u64,
+            parameters: result.parameters_data.as_ref().map(|p| p.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "hdf5")]
+mod hdf5_writer {
+    use std::path::Path;
+
+    use hdf5::types::VarLenUnicode;
+    use hdf5::{Group, H5Type, Location};
+
+    use super::{Agglomerate, Values};
+
+    /// Value of the root `format` attribute.
+    const LAYOUT_NAME: &str = "aglogen-ensemble";
+
+    fn write_str(location: &Location, name: &str, value: &str) -> hdf5::Result<()> {
+        let value: VarLenUnicode = value.parse().map_err(|e| hdf5::Error::from(format!("{}", e)))?;
+        location.new_attr::<VarLenUnicode>().create(name)?.write_scalar(&value)
+    }
+
+    fn write_dataset<T: H5Type>(group: &Group, name: &str, values: &[T], columns: usize) -> hdf5::Result<()> {
+        let dataset = if columns == 1 {
+            group.new_dataset::<T>().shape(values.len()).create(name)?
+        } else {
+            group.new_dataset::<T>().shape((values.len() / columns, columns)).create(name)?
+        };
+        if values.is_empty() {
+            return Ok(());
+        }
+        dataset.write_raw(values)
+    }
+
+    /// Write `agglomerates` to a new HDF5 file at `path`.
+    pub(super) fn write_ensemble(path: &Path, agglomerates: &[Agglomerate]) -> hdf5::Result<()> {
+        let file = hdf5::File::create(path)?;
+        write_str(&file, "format", LAYOUT_NAME)?;
+        write_str(&file, "version", env!("CARGO_PKG_VERSION"))?;
+        file.new_attr::<u64>()
+            .create("n_agglomerates")?
+            .write_scalar(&(agglomerates.len() as u64))?;
+
+        for (k, agglomerate) in agglomerates.iter().enumerate() {
+            let group = file.create_group(&format!("agglomerate_{:06}", k))?;
+            for dataset in &agglomerate.datasets {
+                match &dataset.values {
+                    Values::F64(v) => write_dataset(&group, dataset.name, v, dataset.columns)?,
+                    Values::U32(v) => write_dataset(&group, dataset.name, v, dataset.columns)?,
+                    Values::U64(v) => write_dataset(&group, dataset.name, v, dataset.columns)?,
+                }
+            }
+            group.new_attr::<u64>().create("seed")?.write_scalar(&agglomerate.seed)?;
+            group.new_attr::<u64>().create("n_particles")?.write_scalar(&agglomerate.n_particles)?;
+            for (name, value) in &agglomerate.metrics {
+                group.new_attr::<f64>().create(*name)?.write_scalar(value)?;
+            }
+            if let Some(parameters) = &agglomerate.parameters {
+                write_str(&group, "parameters", parameters)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "hdf5")]
+fn write_ensemble(py: Python<'_>, path: &Path, agglomerates: &[Agglomerate]) -> PyResult<()> {
+    py.allow_threads(|| hdf5_writer::write_ensemble(path, agglomerates)).map_err(|e| {
+        PyIOError::new_err(format!("cannot write {}: {}", path.display(), e))
+    })
+}
+
+#[cfg(not(feature = "hdf5"))]
+fn write_ensemble(_py: Python<'_>, _path: &Path, _agglomerates: &[Agglomerate]) -> PyResult<()> {
+    Err(pyo3::exceptions::PyRuntimeError::new_err(
+        "aglogen_core was built without HDF5 support; rebuild it with `maturin develop --features hdf5`",
+    ))
+}
+
+/// Write an ensemble of agglomerates to an HDF5 file.
+///
+/// Each result becomes a group `agglomerate_NNNNNN` holding its
+/// coordinates, radii, IDs, cluster IDs, contact pairs and Rg evolution as
+/// datasets, and its seed, metrics (Df, kf, Rg, porosity, coordination, shape
+/// descriptors) and recorded `parameters` (as JSON) as attributes. The root
+/// carries `format = "aglogen-ensemble"`, the crate version and the number
+/// of agglomerates.
+///
+/// Requires aglogen_core built with the `hdf5` feature
+/// (`maturin develop --features hdf5`); otherwise raises RuntimeError.
+///
+/// # Arguments
+/// * `results` - `SimulationResult`s to store, e.g. the list returned by `run_batch`
+/// * `path` - Output file, overwritten if it exists
+#[pyfunction]
+pub fn save_ensemble_hdf5(py: Python<'_>, results: Vec<PyRef<'_, PySimulationResult>>, path: PathBuf) -> PyResult<()> {
+    check_count("number of results", results.len(), 1)?;
+    let agglomerates: Vec<Agglomerate> = results.iter().map(|r| Agglomerate::of(r)).collect();
+    write_ensemble(py, &path, &agglomerates)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ParticleFormat::from_path(Path::new("a/b.XYZ")), Some(ParticleFormat::Xyz));
+        assert_eq!(ParticleFormat::from_path(Path::new("run.lammpstrj")), Some(ParticleFormat::LammpsDump));
+        assert_eq!(ParticleFormat::from_path(Path::new("out.pdb")), Some(ParticleFormat::Pdb));
+        assert_eq!(ParticleFormat::from_path(Path::new("out")), None);
+    }
+
+    #[test]
+    fn test_writers_carry_positions_and_radii() {
+        let coords = array![[0.0, 0.0, 0.0], [2.0, -1.5, 0.25]];
+        let radii = [1.0, 0.5];
+
+        let mut xyz = Vec::new();
+        write_xyz(&mut xyz, coords.view(), &radii, "C", "Df=1.8").unwrap();
+        let xyz = String::from_utf8(xyz).unwrap();
+        let lines: Vec<&str> = xyz.lines().collect();
+        assert_eq!(lines[0], "2");
+        assert!(lines[1].ends_with("Df=1.8"));
+        assert_eq!(lines[3], "C 2.000000 -1.500000 0.250000 0.500000");
+
+        let mut pdb = Vec::new();
+        write_pdb(&mut pdb, coords.view(), &radii, "C", "").unwrap();
+        let pdb = String::from_utf8(pdb).unwrap();
+        let atom = pdb.lines().nth(1).unwrap();
+        // Fixed PDB columns: x 31-38, y 39-46, z 47-54, B-factor 61-66, element 77-78
+        assert_eq!(&atom[30..38], "   2.000");
+        assert_eq!(&atom[38..46], "  -1.500");
+        assert_eq!(&atom[46..54], "   0.250");
+        assert_eq!(&atom[60..66], "  0.50");
+        assert_eq!(&atom[76..78], " C");
+
+        // Negative coordinates have one column less for digits than positive ones
+        assert!(check_pdb_columns(array![[-999.999, 9999.999, 0.0]].view(), &[999.99]).is_ok());
+        assert!(check_pdb_columns(array![[-1000.0, 0.0, 0.0]].view(), &[1.0]).is_err());
+        assert!(check_pdb_columns(array![[0.0, 0.0, 0.0]].view(), &[1000.0]).is_err());
+        let mut pdb = Vec::new();
+        write_pdb(&mut pdb, array![[-999.999, 9999.999, 0.0]].view(), &[999.99], "C", "").unwrap();
+        let atom = String::from_utf8(pdb).unwrap();
+        assert_eq!((&atom[30..38], &atom[38..46], &atom[60..66]), ("-999.999", "9999.999", "999.99"));
+
+        let mut dump = Vec::new();
+        write_lammps_dump(&mut dump, coords.view(), &radii).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines[3], "2");
+        assert_eq!(lines[5], "-1.000000 2.500000");
+        assert_eq!(lines[10], "2 1 2.000000 -1.500000 0.250000 0.500000");
+    }
+
+    #[test]
+    fn test_vtk_polydata() {
+        let points = [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 2.0, 0.0]];
+        let radii = [1.0, 1.0, 0.5];
+        let mut buf = Vec::new();
+        write_vtk(&mut buf, &points, &[(0, 1), (1, 2)], &[("radius", &radii)], "chain").unwrap();
+        let vtk = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = vtk.lines().collect();
+        assert_eq!(lines[..5], ["# vtk DataFile Version 3.0", "chain", "ASCII", "DATASET POLYDATA", "POINTS 3 double"]);
+        assert_eq!(lines[6], "2.000000 0.000000 0.000000");
+        assert_eq!(lines[8..12], ["VERTICES 3 6", "1 0", "1 1", "1 2"]);
+        assert_eq!(lines[12..15], ["LINES 2 6", "2 0 1", "2 1 2"]);
+        assert_eq!(lines[15..18], ["POINT_DATA 3", "SCALARS radius double 1", "LOOKUP_TABLE default"]);
+        assert_eq!(lines[20], "0.500000");
+
+        // Without contacts there is no LINES section
+        let mut buf = Vec::new();
+        write_vtk(&mut buf, &points, &[], &[], "").unwrap();
+        assert!(!String::from_utf8(buf).unwrap().contains("LINES"));
+    }
+
+    #[test]
+    fn test_readers_round_trip() {
+        let coords = array![[0.0, 0.0, 0.0], [1.9, 0.0, 0.0], [1.9, 2.1, -0.5]];
+        let radii = [1.0, 1.0, 1.25];
+        let check = |(c, r): ParticleData| {
+            assert_eq!(c.len(), 3);
+            assert!((c[2][1] - 2.1).abs() < 1e-3 && (c[2][2] + 0.5).abs() < 1e-3);
+            assert_eq!(r.expect("radii are stored"), radii.to_vec());
+        };
+
+        let mut buf = Vec::new();
+        write_xyz(&mut buf, coords.view(), &radii, "C", "").unwrap();
+        check(read_xyz(&String::from_utf8(buf).unwrap()).unwrap());
+
+        let mut buf = Vec::new();
+        write_pdb(&mut buf, coords.view(), &radii, "C", "run 1").unwrap();
+        check(read_pdb(&String::from_utf8(buf).unwrap()).unwrap());
+
+        let mut buf = Vec::new();
+        write_lammps_dump(&mut buf, coords.view(), &radii).unwrap();
+        check(read_lammps_dump(&String::from_utf8(buf).unwrap()).unwrap());
+
+        // Plain XYZ without radii
+        let (c, r) = read_xyz("2\nplain\nC 0 0 0\nC 2 0 0\n").unwrap();
+        assert_eq!(c[1], [2.0, 0.0, 0.0]);
+        assert!(r.is_none());
+        assert!(read_xyz("3\n\nC 0 0 0\n").is_err());
+    }
+
+    #[test]
+    fn test_read_structure_checks_values() {
+        let path = std::env::temp_dir().join(format!("aglogen_load_{}.xyz", std::process::id()));
+        std::fs::write(&path, "2\n\nC 0 0 0\nC 2 0 0\n").unwrap();
+        let (coords, radii) = read_structure(&path, ParticleFormat::Xyz, Some(1.0)).unwrap();
+        assert_eq!(coords[1], [2.0, 0.0, 0.0]);
+        assert_eq!(radii, vec![1.0, 1.0]);
+        assert!(read_structure(&path, ParticleFormat::Xyz, None).is_err());
+
+        // Non-finite coordinates parse but are refused, as in from_arrays
+        std::fs::write(&path, "2\n\nC 0 0 0\nC NaN 0 0\n").unwrap();
+        assert!(read_xyz("2\n\nC 0 0 0\nC NaN 0 0\n").is_ok());
+        assert!(read_structure(&path, ParticleFormat::Xyz, Some(1.0)).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_loaded_structure_metrics() {
+        // Straight touching chain: Df close to 1, end particles have one contact
+        let coords: Vec<[f64; 3]> = (0..40).map(|i| [2.0 * i as f64, 0.0, 0.0]).collect();
+        let result = SimulationResult::from_structure(coords, vec![1.0; 40]);
+        assert_eq!(result.coordination[0], 1);
+        assert_eq!(result.coordination[20], 2);
+        assert!((result.fractal_dimension - 1.0).abs() < 0.2, "Df = {}", result.fractal_dimension);
+        assert_eq!(result.n_evolution.last(), Some(&40));
+    }
+
+
+    #[test]
+    fn test_agglomerate_group_contents() {
+        let coords = vec![[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 2.0, 0.0]];
+        let mut result = SimulationResult::from_structure(coords, vec![1.0; 3]);
+        result.seed = 11;
+        let agglomerate = Agglomerate::of(&result.to_py());
+
+        let names: Vec<&str> = agglomerate.datasets.iter().map(|d| d.name).collect();
+        assert_eq!(names, ["coordinates", "radii", "ids", "cluster_ids", "contacts", "rg_evolution"]);
+        assert_eq!(agglomerate.datasets[0].values, Values::F64(vec![0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 2.0, 2.0, 0.0]));
+        // Chain 0-1-2: two contacts stored as (M, 2) rows
+        assert_eq!(agglomerate.datasets[4].columns, 2);
+        assert_eq!(agglomerate.datasets[4].values, Values::U64(vec![0, 1, 1, 2]));
+        assert_eq!((agglomerate.seed, agglomerate.n_particles), (11, 3));
+        assert!(agglomerate.parameters.is_none());
+        assert!(agglomerate.metrics.iter().any(|&(name, _)| name == "radius_of_gyration"));
+    }
+
+    #[cfg(feature = "hdf5")]
+    #[test]
+    fn test_ensemble_hdf5_round_trip() {
+        use hdf5::types::VarLenUnicode;
+
+        let coords = vec![[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 2.0, 0.0]];
+        let agglomerates: Vec<Agglomerate> = (0..2)
+            .map(|seed| {
+                let mut result = SimulationResult::from_structure(coords.clone(), vec![1.0; 3]);
+                result.seed = seed;
+                Agglomerate::of(&result.to_py())
+            })
+            .collect();
+        let path = std::env::temp_dir().join(format!("aglogen_ensemble_{}.h5", std::process::id()));
+        hdf5_writer::write_ensemble(&path, &agglomerates).unwrap();
+
+        let file = hdf5::File::open(&path).unwrap();
+        let format: VarLenUnicode = file.attr("format").unwrap().read_scalar().unwrap();
+        assert_eq!(format.as_str(), "aglogen-ensemble");
+        assert_eq!(file.attr("n_agglomerates").unwrap().read_scalar::<u64>().unwrap(), 2);
+        assert_eq!(file.member_names().unwrap(), ["agglomerate_000000", "agglomerate_000001"]);
+
+        let group = file.group("agglomerate_000001").unwrap();
+        assert_eq!(group.attr("seed").unwrap().read_scalar::<u64>().unwrap(), 1);
+        assert_eq!(group.attr("n_particles").unwrap().read_scalar::<u64>().unwrap(), 3);
+        let coordinates = group.dataset("coordinates").unwrap();
+        assert_eq!(coordinates.shape(), [3, 3]);
+        assert_eq!(coordinates.read_raw::<f64>().unwrap(), [0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 2.0, 2.0, 0.0]);
+        let contacts = group.dataset("contacts").unwrap();
+        assert_eq!(contacts.shape(), [2, 2]);
+        assert_eq!(contacts.read_raw::<u64>().unwrap(), [0, 1, 1, 2]);
+        assert_eq!(group.dataset("cluster_ids").unwrap().read_raw::<u32>().unwrap(), [0, 0, 0]);
+        let rg: f64 = group.attr("radius_of_gyration").unwrap().read_scalar().unwrap();
+        assert!(rg > 0.0);
+
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -6,31 +6,71 @@
 
 use pyo3::prelude::*;
 
+mod benchmark;
 mod common;
 mod fractal;
+mod io;
+mod mesh;
 mod projection;
+mod session;
 mod simulation;
 
-use fractal::box_counting::box_counting;
-use fractal::box_counting_3d::{box_counting_3d, box_counting_agglomerate};
-use fractal::fraktal::{Granulated2012Params, Voxel2018Params, PyFraktalResult};
-use fractal::result::PyFractalResult as PyBoxCountingResult;
-use projection::{project_batch, project_to_2d, PyProjectionResult};
+use benchmark::PyBenchmarkResult;
+use fractal::box_counting::{box_counting, box_counting_map};
+use fractal::box_counting_3d::{box_counting_3d, box_counting_agglomerate, box_counting_voxels, PyMortonIndex};
+use fractal::correlation::correlation_dimension;
+use fractal::fraktal::{
+    analyze_components, analyze_with_diagnostics, granulated_2012_uncertainty, FraktalModel, Granulated2012Params,
+    MorphologyParams, PyFraktalDiagnostics, PyFraktalResult, PyFraktalUncertainty, UncertaintyParams, Voxel2018Params,
+};
+use fractal::lacunarity::lacunarity;
+use fractal::multifractal::{multifractal_spectrum, multifractal_spectrum_3d};
+use fractal::optics::{rdg_fa, PyOpticalProperties};
+use fractal::perimeter_area::perimeter_area_dimension;
+use fractal::result::{PyFractalResult as PyBoxCountingResult, PyLacunarityResult, PyMultifractalResult};
+use fractal::sandbox::sandbox_dimension;
+use fractal::scattering::structure_factor;
+use io::{export_agglomerate, export_vtk, load_agglomerate, save_ensemble_hdf5};
+use mesh::{mesh_surface, PySurfaceMesh};
+use projection::area::{projected_area, projected_area_map, PyProjectedArea, PyProjectedAreaMap};
+use projection::averaged::{orientation_averaged_projection, PyOrientationAverage};
+use projection::rasterize::render_projection;
+use projection::{align_to_principal_axes, project_batch, project_many, project_to_2d, PyProjectionResult};
+use session::PyAnalysisSession;
+use simulation::annealing::{anneal_structure, PyAnnealingResult};
 use simulation::ballistic::run_ballistic;
 use simulation::ballistic_cc::run_ballistic_cc;
-use simulation::cca::run_cca;
+use simulation::batch::run_batch;
+use simulation::brownian::{PyBrownianParams, PyBrownianReport};
+use simulation::cca::{run_cca, run_cca_physical};
+use simulation::compare::{compare_agglomerates, PyAgglomerateComparison};
+use simulation::deposition::{run_ballistic_deposition, PyFilmReport};
 use simulation::dla::run_dla;
+use simulation::drift::PyDriftReport;
+use simulation::history::{PyGrowthHistory, PyHistoryParams};
+use simulation::packing::{pack_agglomerates, PyPackingResult};
+use simulation::pipeline::PySimulationPipeline;
+use simulation::restart::{run_ballistic_continue, run_dla_continue, run_tunable_continue};
+use simulation::estimate::{estimate_df_kf, PyDfKfEstimate};
+use simulation::ensemble::{aggregate_results, MetricStats, PyEnsembleResult};
+use simulation::mobility::{effective_density, PyEffectiveDensity, PyMobilityDiameter};
 use simulation::tunable::run_tunable;
-use simulation::tunable_cc::run_tunable_cc;
-use simulation::result::PySimulationResult;
+use simulation::tunable_cc::{run_tunable_cc, PyMergeDiagnostics};
+use simulation::result::{PyOverlapStatistics, PySimulationResult, TargetReport};
 use simulation::sintering::PySinteringParams;
+use simulation::size_distribution::PySizeDistribution;
+use simulation::sticking::PyAggregationKinetics;
+use simulation::surface::{accessible_surface_area, PyAccessibleSurfaceArea};
 
-use numpy::PyReadonlyArray2;
+use common::arrays::{extract_u8_image, extract_u8_images};
+use common::fitting::LinearRegionParams;
+use common::rng::PySeedSequence;
+use common::units::PyUnits;
 
 /// Run FRAKTAL analysis using the 2012 granulated particle model.
 ///
 /// # Arguments
-/// * `image` - Grayscale image as 2D numpy array (uint8)
+/// * `image` - Grayscale image as 2D numpy array (uint8, uint16 or float; any layout)
 /// * `npix` - Pixels per 100nm in the scale bar
 /// * `dpo` - Mean primary particle diameter (nm)
 /// * `delta` - Filling factor (1.0-1.5)
@@ -40,11 +80,22 @@ use numpy::PyReadonlyArray2;
 /// * `npo_limit` - Minimum particle count (default: 5)
 /// * `escala` - Scale reference in nm (default: 100)
 /// * `auto_threshold` - Enable automatic threshold detection using Otsu's method (default: true)
+/// * `threshold_method` - "otsu", or "sauvola"/"niblack" for local thresholds under uneven
+///   illumination (default: "otsu")
+/// * `threshold_window` - Neighbourhood side in pixels of the local thresholds (default: 25)
+/// * `morphology` - `MorphologyParams` to clean up the segmented mask (default: None)
+/// * `npo_method` - Detector of the visual npo: "distance_peaks", "watershed" or "hough"
+///   (default: "distance_peaks")
+/// * `hough_radius_range` - (min, max) circle radius in pixels for "hough" (default: (3, 30))
+/// * `size_distribution` - Measure the primary particle size distribution with EDM-SBS
+///   (default: false)
+/// * `return_diagnostics` - Attach the mask, threshold, center of gyration, detected
+///   particles and solver history as `diagnostics` (default: false)
 #[pyfunction]
-#[pyo3(signature = (image, npix, dpo, delta=1.1, correction_3d=false, pixel_min=10, pixel_max=240, npo_limit=5, escala=100.0, auto_threshold=true))]
+#[pyo3(signature = (image, npix, dpo, delta=1.1, correction_3d=false, pixel_min=10, pixel_max=240, npo_limit=5, escala=100.0, auto_threshold=true, threshold_method="otsu", threshold_window=25, morphology=None, npo_method="distance_peaks", hough_radius_range=(3, 30), size_distribution=false, return_diagnostics=false))]
 fn fraktal_granulated_2012(
-    _py: Python<'_>,
-    image: PyReadonlyArray2<u8>,
+    py: Python<'_>,
+    image: &Bound<'_, PyAny>,
     npix: f64,
     dpo: f64,
     delta: f64,
@@ -54,18 +105,88 @@ fn fraktal_granulated_2012(
     npo_limit: usize,
     escala: f64,
     auto_threshold: bool,
+    threshold_method: &str,
+    threshold_window: usize,
+    morphology: Option<MorphologyParams>,
+    npo_method: &str,
+    hough_radius_range: (usize, usize),
+    size_distribution: bool,
+    return_diagnostics: bool,
 ) -> PyResult<PyFraktalResult> {
+    let image = extract_u8_image(image, "image")?;
     let params = Granulated2012Params::new(
-        npix, dpo, delta, correction_3d, pixel_min, pixel_max, npo_limit, escala, auto_threshold
+        npix, dpo, delta, correction_3d, pixel_min, pixel_max, npo_limit, escala, auto_threshold,
+        threshold_method.to_string(), threshold_window, morphology, npo_method.to_string(), hough_radius_range,
+        size_distribution,
     );
-    let result = fractal::fraktal::analyze_granulated_2012(image.as_array(), &params);
+    params.validate()?;
+    let result = session::allow_threads(py, || {
+        if return_diagnostics {
+            analyze_with_diagnostics(image.view(), &FraktalModel::Granulated2012(params))
+        } else {
+            fractal::fraktal::analyze_granulated_2012(image.view(), &params)
+        }
+    });
     Ok(result.into())
 }
 
+/// Run FRAKTAL analysis using the 2012 granulated particle model on a stack of images.
+///
+/// The frames are analysed in parallel with the GIL released, all with the
+/// same parameters.
+///
+/// # Arguments
+/// * `images` - 3D uint8 array of frames along the first axis, or a list of
+///   2D grayscale images (uint8, uint16 or float; any layout)
+/// * `params` - `Granulated2012Params` of the analysis
+///
+/// # Returns
+/// * List of `PyFraktalResult`, one per frame in order
+#[pyfunction]
+fn fraktal_granulated_2012_batch(
+    py: Python<'_>,
+    images: &Bound<'_, PyAny>,
+    params: Granulated2012Params,
+) -> PyResult<Vec<PyFraktalResult>> {
+    let images = extract_u8_images(images, "images")?;
+    params.validate()?;
+    let results = py.allow_threads(|| fractal::fraktal::analyze_granulated_2012_batch(&images, &params));
+    Ok(results.into_iter().map(Into::into).collect())
+}
+
+/// Run FRAKTAL analysis using the 2012 granulated particle model with error bars.
+///
+/// dpo, npix and the segmentation threshold are perturbed within the ranges
+/// of `uncertainty`, and the spread of Df, kf and npo is estimated by Monte
+/// Carlo draws or by first-order sensitivity.
+///
+/// # Arguments
+/// * `image` - Grayscale image as 2D numpy array (uint8, uint16 or float; any layout)
+/// * `params` - `Granulated2012Params` with the nominal inputs
+/// * `uncertainty` - `UncertaintyParams` with the input ranges and the propagation method
+///
+/// # Returns
+/// * `FraktalUncertainty` with the nominal result and the means, standard
+///   deviations and correlation of Df, kf and npo
+#[pyfunction]
+fn fraktal_granulated_2012_uncertainty(
+    py: Python<'_>,
+    image: &Bound<'_, PyAny>,
+    params: Granulated2012Params,
+    uncertainty: UncertaintyParams,
+) -> PyResult<PyFraktalUncertainty> {
+    let image = extract_u8_image(image, "image")?;
+    params.validate()?;
+    uncertainty.validate(&params)?;
+    let seed = uncertainty.seed.unwrap_or_else(rand::random);
+    let estimate = py.allow_threads(|| granulated_2012_uncertainty(image.view(), &params, &uncertainty, seed));
+    Ok(PyFraktalUncertainty::new(&uncertainty.method, estimate))
+}
+
 /// Run FRAKTAL analysis using the 2018 voxel model.
 ///
 /// # Arguments
-/// * `image` - Grayscale image as 2D numpy array (uint8)
+/// * `image` - Grayscale image as 2D numpy array (uint8, uint16 or float; any layout)
 /// * `npix` - Pixels per 100nm in the scale bar
 /// * `escala` - Scale reference in nm (default: 100)
 /// * `correction_3d` - Apply 3D correction to Rg
@@ -73,11 +194,17 @@ fn fraktal_granulated_2012(
 /// * `pixel_max` - Max pixel value for segmentation (default: 240)
 /// * `m_exponent` - m exponent for zp calculation (default: 1.0)
 /// * `auto_threshold` - Enable automatic threshold detection using Otsu's method (default: true)
+/// * `threshold_method` - "otsu", or "sauvola"/"niblack" for local thresholds under uneven
+///   illumination (default: "otsu")
+/// * `threshold_window` - Neighbourhood side in pixels of the local thresholds (default: 25)
+/// * `morphology` - `MorphologyParams` to clean up the segmented mask (default: None)
+/// * `return_diagnostics` - Attach the mask, threshold, center of gyration, detected
+///   particles and solver history as `diagnostics` (default: false)
 #[pyfunction]
-#[pyo3(signature = (image, npix, escala=100.0, correction_3d=false, pixel_min=10, pixel_max=240, m_exponent=1.0, auto_threshold=true))]
+#[pyo3(signature = (image, npix, escala=100.0, correction_3d=false, pixel_min=10, pixel_max=240, m_exponent=1.0, auto_threshold=true, threshold_method="otsu", threshold_window=25, morphology=None, return_diagnostics=false))]
 fn fraktal_voxel_2018(
-    _py: Python<'_>,
-    image: PyReadonlyArray2<u8>,
+    py: Python<'_>,
+    image: &Bound<'_, PyAny>,
     npix: f64,
     escala: f64,
     correction_3d: bool,
@@ -85,47 +212,171 @@ fn fraktal_voxel_2018(
     pixel_max: u8,
     m_exponent: f64,
     auto_threshold: bool,
+    threshold_method: &str,
+    threshold_window: usize,
+    morphology: Option<MorphologyParams>,
+    return_diagnostics: bool,
 ) -> PyResult<PyFraktalResult> {
+    let image = extract_u8_image(image, "image")?;
     let params = Voxel2018Params::new(
-        npix, escala, correction_3d, pixel_min, pixel_max, m_exponent, auto_threshold
+        npix, escala, correction_3d, pixel_min, pixel_max, m_exponent, auto_threshold,
+        threshold_method.to_string(), threshold_window, morphology,
     );
-    let result = fractal::fraktal::analyze_voxel_2018(image.as_array(), &params);
+    params.validate()?;
+    let result = session::allow_threads(py, || {
+        if return_diagnostics {
+            analyze_with_diagnostics(image.view(), &FraktalModel::Voxel2018(params))
+        } else {
+            fractal::fraktal::analyze_voxel_2018(image.view(), &params)
+        }
+    });
     Ok(result.into())
 }
 
+/// Run FRAKTAL analysis on every aggregate of an image.
+///
+/// The image is segmented (and cleaned up) as by the single-aggregate
+/// functions, then split into 8-connected components, each analysed on its
+/// own with the model of `params`.
+///
+/// # Arguments
+/// * `image` - Grayscale image as 2D numpy array (uint8, uint16 or float; any layout)
+/// * `params` - `Granulated2012Params` or `Voxel2018Params`, selecting the model
+/// * `min_pixels` - Skip components of fewer pixels, e.g. leftover noise (default: 10)
+///
+/// # Returns
+/// * List of `PyFraktalResult`, one per aggregate in raster order of its
+///   first pixel, with its `bounding_box` and `pixel_count`
+#[pyfunction]
+#[pyo3(signature = (image, params, min_pixels=10))]
+fn fraktal_analyze_all(
+    py: Python<'_>,
+    image: &Bound<'_, PyAny>,
+    params: &Bound<'_, PyAny>,
+    min_pixels: usize,
+) -> PyResult<Vec<PyFraktalResult>> {
+    let image = extract_u8_image(image, "image")?;
+    let model = FraktalModel::from_params(params)?;
+    model.validate()?;
+    let components = py.allow_threads(|| analyze_components(image.view(), &model, min_pixels));
+    Ok(components.into_iter().map(Into::into).collect())
+}
+
 /// Python module for aglogen_core
 #[pymodule]
 fn aglogen_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     // Simulation functions
     m.add_function(wrap_pyfunction!(run_dla, m)?)?;
     m.add_function(wrap_pyfunction!(run_cca, m)?)?;
+    m.add_function(wrap_pyfunction!(run_cca_physical, m)?)?;
     m.add_function(wrap_pyfunction!(run_ballistic, m)?)?;
+    m.add_function(wrap_pyfunction!(run_ballistic_deposition, m)?)?;
     m.add_function(wrap_pyfunction!(run_ballistic_cc, m)?)?;
     m.add_function(wrap_pyfunction!(run_tunable, m)?)?;
     m.add_function(wrap_pyfunction!(run_tunable_cc, m)?)?;
+    m.add_function(wrap_pyfunction!(run_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(run_dla_continue, m)?)?;
+    m.add_function(wrap_pyfunction!(run_ballistic_continue, m)?)?;
+    m.add_function(wrap_pyfunction!(run_tunable_continue, m)?)?;
+    m.add_function(wrap_pyfunction!(aggregate_results, m)?)?;
+    m.add_function(wrap_pyfunction!(compare_agglomerates, m)?)?;
+    m.add_function(wrap_pyfunction!(effective_density, m)?)?;
+    m.add_function(wrap_pyfunction!(accessible_surface_area, m)?)?;
+    m.add_function(wrap_pyfunction!(estimate_df_kf, m)?)?;
+    m.add_function(wrap_pyfunction!(pack_agglomerates, m)?)?;
+    m.add_function(wrap_pyfunction!(anneal_structure, m)?)?;
 
     // Fractal analysis functions
     m.add_function(wrap_pyfunction!(box_counting, m)?)?;
+    m.add_function(wrap_pyfunction!(box_counting_map, m)?)?;
     m.add_function(wrap_pyfunction!(box_counting_3d, m)?)?;
     m.add_function(wrap_pyfunction!(box_counting_agglomerate, m)?)?;
+    m.add_function(wrap_pyfunction!(box_counting_voxels, m)?)?;
+    m.add_function(wrap_pyfunction!(sandbox_dimension, m)?)?;
+    m.add_function(wrap_pyfunction!(correlation_dimension, m)?)?;
+    m.add_function(wrap_pyfunction!(multifractal_spectrum, m)?)?;
+    m.add_function(wrap_pyfunction!(lacunarity, m)?)?;
+    m.add_function(wrap_pyfunction!(multifractal_spectrum_3d, m)?)?;
+    m.add_function(wrap_pyfunction!(structure_factor, m)?)?;
+    m.add_function(wrap_pyfunction!(rdg_fa, m)?)?;
+    m.add_function(wrap_pyfunction!(perimeter_area_dimension, m)?)?;
     m.add_function(wrap_pyfunction!(fraktal_granulated_2012, m)?)?;
+    m.add_function(wrap_pyfunction!(fraktal_granulated_2012_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(fraktal_granulated_2012_uncertainty, m)?)?;
     m.add_function(wrap_pyfunction!(fraktal_voxel_2018, m)?)?;
+    m.add_function(wrap_pyfunction!(fraktal_analyze_all, m)?)?;
 
     // Projection functions
     m.add_function(wrap_pyfunction!(project_to_2d, m)?)?;
     m.add_function(wrap_pyfunction!(project_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(project_many, m)?)?;
+    m.add_function(wrap_pyfunction!(align_to_principal_axes, m)?)?;
+    m.add_function(wrap_pyfunction!(projected_area, m)?)?;
+    m.add_function(wrap_pyfunction!(projected_area_map, m)?)?;
+    m.add_function(wrap_pyfunction!(orientation_averaged_projection, m)?)?;
+    m.add_function(wrap_pyfunction!(render_projection, m)?)?;
+
+    // Surface meshing
+    m.add_function(wrap_pyfunction!(mesh_surface, m)?)?;
+
+    // Particle files
+    m.add_function(wrap_pyfunction!(export_agglomerate, m)?)?;
+    m.add_function(wrap_pyfunction!(load_agglomerate, m)?)?;
+    m.add_function(wrap_pyfunction!(export_vtk, m)?)?;
+    m.add_function(wrap_pyfunction!(save_ensemble_hdf5, m)?)?;
 
     // Utility functions
     m.add_function(wrap_pyfunction!(version, m)?)?;
+    m.add_function(wrap_pyfunction!(benchmark::benchmark, m)?)?;
 
     // Result classes
     m.add_class::<PySimulationResult>()?;
+    m.add_class::<TargetReport>()?;
+    m.add_class::<PyBrownianReport>()?;
+    m.add_class::<PyAggregationKinetics>()?;
+    m.add_class::<PyFilmReport>()?;
+    m.add_class::<PyDriftReport>()?;
+    m.add_class::<PyMergeDiagnostics>()?;
+    m.add_class::<PyOverlapStatistics>()?;
+    m.add_class::<PyEnsembleResult>()?;
+    m.add_class::<MetricStats>()?;
+    m.add_class::<PyMobilityDiameter>()?;
+    m.add_class::<PyEffectiveDensity>()?;
+    m.add_class::<PyAgglomerateComparison>()?;
+    m.add_class::<PyAccessibleSurfaceArea>()?;
+    m.add_class::<PyDfKfEstimate>()?;
+    m.add_class::<PyPackingResult>()?;
+    m.add_class::<PyAnnealingResult>()?;
+    m.add_class::<PyBenchmarkResult>()?;
     m.add_class::<PyBoxCountingResult>()?;
+    m.add_class::<PyMultifractalResult>()?;
+    m.add_class::<PyLacunarityResult>()?;
+    m.add_class::<PyOpticalProperties>()?;
     m.add_class::<PyProjectionResult>()?;
+    m.add_class::<PyProjectedArea>()?;
+    m.add_class::<PyProjectedAreaMap>()?;
+    m.add_class::<PyOrientationAverage>()?;
+    m.add_class::<PySurfaceMesh>()?;
     m.add_class::<PyFraktalResult>()?;
+    m.add_class::<PyFraktalDiagnostics>()?;
+    m.add_class::<PyFraktalUncertainty>()?;
     m.add_class::<Granulated2012Params>()?;
     m.add_class::<Voxel2018Params>()?;
+    m.add_class::<MorphologyParams>()?;
+    m.add_class::<UncertaintyParams>()?;
     m.add_class::<PySinteringParams>()?;
+    m.add_class::<PyBrownianParams>()?;
+    m.add_class::<PySizeDistribution>()?;
+    m.add_class::<PyHistoryParams>()?;
+    m.add_class::<PyGrowthHistory>()?;
+    m.add_class::<PyUnits>()?;
+    m.add_class::<PyMortonIndex>()?;
+    m.add_class::<LinearRegionParams>()?;
+
+    // Session management
+    m.add_class::<PyAnalysisSession>()?;
+    m.add_class::<PySeedSequence>()?;
+    m.add_class::<PySimulationPipeline>()?;
 
     Ok(())
 }
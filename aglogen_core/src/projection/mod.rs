@@ -1,19 +1,36 @@
 //! 2D Projection of 3D Agglomerates.
 //!
 //! Generates 2D projections of 3D particle coordinates by applying
-//! rotation matrices based on azimuth and elevation angles.
+//! rotation matrices based on azimuth and elevation angles, or any given
+//! (or uniformly random) rotation.
 //!
 //! Based on Matlab's create2DImages.m which uses viewmtx for the
 //! rotation transformation.
 
+pub mod area;
+pub mod averaged;
+pub mod rasterize;
+
 use std::f64::consts::PI;
 
-use numpy::{PyArray1, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
+use ndarray::Array2;
+use numpy::{PyArray1, PyArray2, ToPyArray};
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::arrays::{extract_f64_array1, extract_f64_array2};
+use crate::common::geometry::Quaternion;
+use crate::common::rng::{create_rng, random_rotation};
+use crate::common::serialization;
+use crate::common::validation::{check_positive, check_radii};
+use crate::session::allow_threads;
+use crate::simulation::metrics;
 
 /// Result of a 2D projection operation.
 #[pyclass]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PyProjectionResult {
     /// 2D X coordinates after projection
     #[pyo3(get)]
@@ -30,9 +47,17 @@ pub struct PyProjectionResult {
     /// Elevation angle used (degrees)
     #[pyo3(get)]
     pub elevation: f64,
+    /// Rotation applied to the coordinates: its first two rows are the x and
+    /// y axes of the view, its third row the direction the view is taken from
+    #[pyo3(get)]
+    pub rotation: [[f64; 3]; 3],
     /// Bounding box: [min_x, max_x, min_y, max_y]
     #[pyo3(get)]
     pub bounds: [f64; 4],
+    /// Tag of the `AnalysisSession` that produced this result, if any
+    #[pyo3(get)]
+    #[serde(default)]
+    pub session: Option<String>,
 }
 
 #[pymethods]
@@ -52,34 +77,168 @@ impl PyProjectionResult {
     fn radii_array<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
         PyArray1::from_vec(py, self.radii.clone())
     }
+
+    /// The projection as JSON; `from_json` reads it back.
+    fn to_json(&self) -> PyResult<String> {
+        serialization::to_json(self)
+    }
+
+    /// Projection written by `to_json` (non-finite floats come back as NaN, or None when optional).
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        serialization::from_json(json)
+    }
+
+    /// The projection as MessagePack bytes; `from_msgpack` reads them back.
+    fn to_msgpack<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new(py, &serialization::to_msgpack(self)?))
+    }
+
+    /// Projection written by `to_msgpack`.
+    #[staticmethod]
+    fn from_msgpack(data: &[u8]) -> PyResult<Self> {
+        serialization::from_msgpack(data)
+    }
 }
 
 /// Project 3D coordinates to 2D using azimuth and elevation angles.
 ///
+/// Any other orientation can be given as `rotation`: the coordinates are
+/// rotated by it and projected onto the XY plane. "random" draws a rotation
+/// uniformly over SO(3), so averages over many random projections are free
+/// of the bias of an (azimuth, elevation) grid, which oversamples the poles.
+/// The result's `azimuth` and `elevation` then give the viewing direction.
+///
 /// # Arguments
-/// * `coordinates` - 3D particle coordinates (N x 3 array)
-/// * `radii` - Particle radii (N array)
+/// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
 /// * `azimuth` - Azimuth angle in degrees (rotation around Z axis)
 /// * `elevation` - Elevation angle in degrees (tilt from XY plane)
+/// * `rotation` - 3x3 rotation matrix, unit quaternion (w, x, y, z) or
+///   "random"; overrides `azimuth` and `elevation` (default: None)
+/// * `seed` - Random seed of the "random" rotation (default: random)
 ///
 /// # Returns
 /// * `PyProjectionResult` containing 2D coordinates, radii, and bounds
 #[pyfunction]
-#[pyo3(signature = (coordinates, radii, azimuth=0.0, elevation=0.0))]
+#[pyo3(signature = (coordinates, radii, azimuth=0.0, elevation=0.0, rotation=None, seed=None))]
 pub fn project_to_2d(
     _py: Python<'_>,
-    coordinates: PyReadonlyArray2<f64>,
-    radii: PyReadonlyArray1<f64>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
     azimuth: f64,
     elevation: f64,
+    rotation: Option<&Bound<'_, PyAny>>,
+    seed: Option<u64>,
 ) -> PyResult<PyProjectionResult> {
-    let coords = coordinates.as_array();
-    let radii_arr = radii.as_array();
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    match rotation {
+        Some(rotation) => {
+            let rotation = extract_rotation(rotation, seed)?;
+            let (azimuth, elevation) = view_angles(&rotation);
+            Ok(project_rotated(&coords, &radii, rotation, azimuth, elevation))
+        }
+        None => Ok(project_structure(&coords, &radii, azimuth, elevation)),
+    }
+}
+
+/// Rotation matrix given as a 3x3 matrix, a quaternion (w, x, y, z) or "random".
+fn extract_rotation(rotation: &Bound<'_, PyAny>, seed: Option<u64>) -> PyResult<[[f64; 3]; 3]> {
+    let invalid = || {
+        pyo3::exceptions::PyValueError::new_err(
+            "rotation must be a 3x3 rotation matrix, a quaternion (w, x, y, z) or 'random'",
+        )
+    };
+
+    if let Ok(name) = rotation.extract::<String>() {
+        if name != "random" {
+            return Err(invalid());
+        }
+        let mut rng = create_rng(seed.unwrap_or_else(rand::random));
+        return Ok(random_rotation(&mut rng).to_matrix());
+    }
+    if let Ok(matrix) = rotation.extract::<[[f64; 3]; 3]>() {
+        check_rotation_matrix(&matrix)?;
+        return Ok(matrix);
+    }
+    let [w, x, y, z] = rotation.extract::<[f64; 4]>().map_err(|_| invalid())?;
+    let norm = (w * w + x * x + y * y + z * z).sqrt();
+    if !norm.is_finite() || norm < 1e-12 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "rotation quaternion must be finite and non-zero",
+        ));
+    }
+    Ok(Quaternion::new(w / norm, x / norm, y / norm, z / norm).to_matrix())
+}
+
+/// Require a proper rotation: orthonormal rows and determinant +1.
+fn check_rotation_matrix(matrix: &[[f64; 3]; 3]) -> PyResult<()> {
+    let dot = |a: &[f64; 3], b: &[f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let orthonormal = (0..3).all(|i| {
+        (0..3).all(|j| (dot(&matrix[i], &matrix[j]) - if i == j { 1.0 } else { 0.0 }).abs() < 1e-6)
+    });
+    let [a, b, c] = matrix;
+    let det = a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+        + a[2] * (b[0] * c[1] - b[1] * c[0]);
+    if !orthonormal || det < 0.0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "rotation matrix must be orthonormal with determinant +1",
+        ));
+    }
+    Ok(())
+}
+
+/// Azimuth and elevation (degrees) of the direction a rotation's view is taken from.
+fn view_angles(rotation: &[[f64; 3]; 3]) -> (f64, f64) {
+    let [dx, dy, dz] = rotation[2];
+    (dy.atan2(dx).to_degrees(), dz.clamp(-1.0, 1.0).asin().to_degrees())
+}
+
+/// Aligned coordinates (N, 3) and rotation (3, 3) returned to Python.
+type AlignedStructure<'py> = (Bound<'py, PyArray2<f64>>, Bound<'py, PyArray2<f64>>);
+
+/// Rotate a structure so its principal axes of inertia lie along x, y and z.
+///
+/// The structure is centered on its center of gravity; the axis of smallest
+/// moment (longest extent) ends on x and the axis of largest moment on z,
+/// with signs fixed so the orientation is canonical. Use it before
+/// projection studies so every agglomerate starts from the same pose.
+///
+/// # Arguments
+/// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
+///
+/// # Returns
+/// * `(aligned, rotation)`: aligned coordinates (N, 3) and the rotation (3, 3)
+///   whose rows are the principal axes, so `aligned = (coordinates - cg) @ rotation.T`
+#[pyfunction]
+pub fn align_to_principal_axes<'py>(
+    py: Python<'py>,
+    coordinates: &Bound<'py, PyAny>,
+    radii: &Bound<'py, PyAny>,
+) -> PyResult<AlignedStructure<'py>> {
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|r| [r[0], r[1], r[2]]).collect();
+
+    let (aligned, rotation) = metrics::align_to_principal_axes(&points, &radii);
+
+    let aligned = Array2::from_shape_vec((aligned.len(), 3), aligned.concat()).unwrap();
+    let rotation = Array2::from_shape_vec((3, 3), rotation.concat()).unwrap();
+    Ok((aligned.to_pyarray(py), rotation.to_pyarray(py)))
+}
+
+/// Convert and validate the (coordinates, radii) pair of a structure.
+pub(crate) fn extract_structure(
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+) -> PyResult<(Array2<f64>, Vec<f64>)> {
+    let coords = extract_f64_array2(coordinates, "coordinates")?;
+    let radii = extract_f64_array1(radii, "radii")?.to_vec();
 
     let n = coords.shape()[0];
 
     // Validate input dimensions (Issue #6 fix)
-    if coords.shape().len() < 2 || coords.shape()[1] < 3 {
+    if coords.shape()[1] < 3 {
         return Err(pyo3::exceptions::PyValueError::new_err(
             format!(
                 "coordinates must have shape (N, 3) or (N, >=3), got shape {:?}",
@@ -88,27 +247,18 @@ pub fn project_to_2d(
         ));
     }
 
-    if radii_arr.len() != n {
-        return Err(pyo3::exceptions::PyValueError::new_err(
-            format!(
-                "radii length ({}) must match number of coordinates ({})",
-                radii_arr.len(),
-                n
-            )
-        ));
-    }
+    check_radii(&radii, n)?;
 
-    if n == 0 {
-        return Ok(PyProjectionResult {
-            x: vec![],
-            y: vec![],
-            radii: vec![],
-            azimuth,
-            elevation,
-            bounds: [0.0, 0.0, 0.0, 0.0],
-        });
-    }
+    Ok((coords, radii))
+}
 
+/// Project an already validated structure.
+fn project_structure(
+    coords: &Array2<f64>,
+    radii: &[f64],
+    azimuth: f64,
+    elevation: f64,
+) -> PyProjectionResult {
     // Convert angles to radians
     let az_rad = azimuth * PI / 180.0;
     let el_rad = elevation * PI / 180.0;
@@ -120,6 +270,33 @@ pub fn project_to_2d(
     // 2. Rotation around X by elevation (after azimuth rotation)
     let rotation = build_view_matrix(az_rad, el_rad);
 
+    project_rotated(coords, radii, rotation, azimuth, elevation)
+}
+
+/// Project an already validated structure with a given rotation, reporting
+/// `azimuth` and `elevation` as its viewing angles.
+fn project_rotated(
+    coords: &Array2<f64>,
+    radii: &[f64],
+    rotation: [[f64; 3]; 3],
+    azimuth: f64,
+    elevation: f64,
+) -> PyProjectionResult {
+    let n = coords.shape()[0];
+
+    if n == 0 {
+        return PyProjectionResult {
+            x: vec![],
+            y: vec![],
+            radii: vec![],
+            azimuth,
+            elevation,
+            rotation,
+            bounds: [0.0, 0.0, 0.0, 0.0],
+            session: None,
+        };
+    }
+
     // Project each point
     let mut x_out = Vec::with_capacity(n);
     let mut y_out = Vec::with_capacity(n);
@@ -134,7 +311,7 @@ pub fn project_to_2d(
         let x = coords[[i, 0]];
         let y = coords[[i, 1]];
         let z = coords[[i, 2]];
-        let r = radii_arr[i];
+        let r = radii[i];
 
         // Apply rotation matrix (we only need x' and y' for 2D projection)
         let x_proj = rotation[0][0] * x + rotation[0][1] * y + rotation[0][2] * z;
@@ -151,14 +328,16 @@ pub fn project_to_2d(
         max_y = max_y.max(y_proj + r);
     }
 
-    Ok(PyProjectionResult {
+    PyProjectionResult {
         x: x_out,
         y: y_out,
         radii: radii_out,
         azimuth,
         elevation,
+        rotation,
         bounds: [min_x, max_x, min_y, max_y],
-    })
+        session: None,
+    }
 }
 
 /// Build view transformation matrix from azimuth and elevation angles.
@@ -192,6 +371,9 @@ fn build_view_matrix(azimuth: f64, elevation: f64) -> [[f64; 3]; 3] {
 
 /// Generate multiple projections at different angles.
 ///
+/// The structure is converted once and the projections are computed in
+/// parallel with the GIL released, in grid order.
+///
 /// # Arguments
 /// * `coordinates` - 3D particle coordinates (N x 3 array)
 /// * `radii` - Particle radii (N array)
@@ -217,8 +399,8 @@ fn build_view_matrix(azimuth: f64, elevation: f64) -> [[f64; 3]; 3] {
 ))]
 pub fn project_batch(
     py: Python<'_>,
-    coordinates: PyReadonlyArray2<f64>,
-    radii: PyReadonlyArray1<f64>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
     azimuth_start: f64,
     azimuth_end: f64,
     azimuth_step: f64,
@@ -226,7 +408,33 @@ pub fn project_batch(
     elevation_end: f64,
     elevation_step: f64,
 ) -> PyResult<Vec<PyProjectionResult>> {
-    let mut results = Vec::new();
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    check_positive("azimuth_step", azimuth_step)?;
+    check_positive("elevation_step", elevation_step)?;
+
+    let angles = angle_grid(
+        (azimuth_start, azimuth_end, azimuth_step),
+        (elevation_start, elevation_end, elevation_step),
+    );
+    let results = allow_threads(py, || {
+        angles
+            .par_iter()
+            .map(|&(az, el)| project_structure(&coords, &radii, az, el))
+            .collect()
+    });
+
+    Ok(results)
+}
+
+/// Enumerate the (azimuth, elevation) pairs of an inclusive angle grid.
+///
+/// Each range is given as (start, end, step) in degrees. Redundant views at
+/// the poles are skipped: at elevation ±90° every azimuth gives the same
+/// projection, so only the first azimuth is kept (like Matlab does).
+fn angle_grid(azimuth: (f64, f64, f64), elevation: (f64, f64, f64)) -> Vec<(f64, f64)> {
+    let (azimuth_start, azimuth_end, azimuth_step) = azimuth;
+    let (elevation_start, elevation_end, elevation_step) = elevation;
+    let mut angles = Vec::new();
 
     // Generate azimuth angles
     let mut az = azimuth_start;
@@ -234,30 +442,75 @@ pub fn project_batch(
         // Generate elevation angles
         let mut el = elevation_start;
         while el <= elevation_end + 1e-10 {
-            // Skip redundant projections at poles (like Matlab does)
-            // At elevation 90° or -90°, all azimuths give the same view
+            // Skip redundant projections at poles
             if (el.abs() - 90.0).abs() < 1e-10 && az > azimuth_start + 1e-10 {
                 el += elevation_step;
                 continue;
             }
 
-            let result = project_to_2d(
-                py,
-                coordinates.clone(),
-                radii.clone(),
-                az,
-                el,
-            )?;
-            results.push(result);
+            angles.push((az, el));
 
             el += elevation_step;
         }
         az += azimuth_step;
     }
 
+    angles
+}
+
+/// Project several agglomerates at a common set of orientations.
+///
+/// All structures are converted up front and the projections are computed in
+/// parallel with the GIL released.
+///
+/// # Arguments
+/// * `structures` - List of (coordinates, radii) pairs, one per agglomerate
+/// * `orientations` - List of (azimuth, elevation) pairs in degrees; defaults
+///   to the `project_batch` grid (0-150° in 30° steps for both angles)
+///
+/// # Returns
+/// * One list of `PyProjectionResult` per structure, in orientation order
+#[pyfunction]
+#[pyo3(signature = (structures, orientations=None))]
+pub fn project_many(
+    py: Python<'_>,
+    structures: Vec<(Bound<'_, PyAny>, Bound<'_, PyAny>)>,
+    orientations: Option<Vec<(f64, f64)>>,
+) -> PyResult<Vec<Vec<PyProjectionResult>>> {
+    let orientations = orientations
+        .unwrap_or_else(|| angle_grid((0.0, 150.0, 30.0), (0.0, 150.0, 30.0)));
+
+    let converted = structures
+        .iter()
+        .enumerate()
+        .map(|(i, (coordinates, radii))| {
+            extract_structure(coordinates, radii).map_err(|e| {
+                pyo3::exceptions::PyValueError::new_err(format!("structure {}: {}", i, e))
+            })
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    let results = allow_threads(py, || project_structures(&converted, &orientations));
+
     Ok(results)
 }
 
+/// Project every structure at every orientation, grouped per structure.
+fn project_structures(
+    structures: &[(Array2<f64>, Vec<f64>)],
+    orientations: &[(f64, f64)],
+) -> Vec<Vec<PyProjectionResult>> {
+    structures
+        .par_iter()
+        .map(|(coords, radii)| {
+            orientations
+                .par_iter()
+                .map(|&(az, el)| project_structure(coords, radii, az, el))
+                .collect()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,4 +554,50 @@ mod tests {
         assert!((x - 0.0).abs() < 1e-10, "x should be 0, got {}", x);
         assert!((y - (-1.0)).abs() < 1e-10, "y should be -1, got {}", y);
     }
+
+    #[test]
+    fn test_angle_grid_skips_redundant_poles() {
+        let angles = angle_grid((0.0, 60.0, 30.0), (0.0, 90.0, 90.0));
+        // Equator at every azimuth, pole only once
+        assert_eq!(angles, vec![(0.0, 0.0), (0.0, 90.0), (30.0, 0.0), (60.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_project_structures_grouped_per_structure() {
+        let a = Array2::from_shape_vec((1, 3), vec![1.0, 0.0, 0.0]).unwrap();
+        let b = Array2::from_shape_vec((2, 3), vec![0.0, 1.0, 0.0, 0.0, 0.0, 1.0]).unwrap();
+        let structures = vec![(a, vec![1.0]), (b, vec![1.0, 2.0])];
+        let orientations = [(0.0, 0.0), (90.0, 0.0), (0.0, 90.0)];
+
+        let results = project_structures(&structures, &orientations);
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.len() == 3));
+        assert_eq!(results[1][0].x.len(), 2);
+        assert_eq!(results[0][1].azimuth, 90.0);
+        // (1, 0, 0) seen from +Y lands at x' = -1
+        assert!((results[0][1].x[0] + 1.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_rotation_matches_view_angles() {
+        let coords = Array2::from_shape_vec((2, 3), vec![1.0, 2.0, 3.0, -1.0, 0.5, 2.0]).unwrap();
+        let radii = [1.0, 1.0];
+
+        // The view matrix is itself a rotation and gives the same projection
+        let view = project_structure(&coords, &radii, 30.0, 40.0);
+        check_rotation_matrix(&view.rotation).unwrap();
+        let (azimuth, elevation) = view_angles(&view.rotation);
+        assert!((azimuth - 30.0).abs() < 1e-9 && (elevation - 40.0).abs() < 1e-9);
+        let rotated = project_rotated(&coords, &radii, view.rotation, azimuth, elevation);
+        assert_eq!(rotated.x, view.x);
+
+        // Random rotations are proper rotations
+        let mut rng = create_rng(7);
+        for _ in 0..10 {
+            check_rotation_matrix(&random_rotation(&mut rng).to_matrix()).unwrap();
+        }
+        let mirror = [[-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        assert!(check_rotation_matrix(&mirror).is_err());
+    }
 }
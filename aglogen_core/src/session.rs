@@ -0,0 +1,325 @@
+//! Analysis sessions.
+//!
+//! An `AnalysisSession` bundles the bookkeeping that large studies need
+//! around the individual entry points: a dedicated thread pool, a master
+//! seed from which every run draws a reproducible seed, and an output
+//! directory. Every result produced through a session is tagged with the
+//! session name and the index of the call that produced it.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyDict, PyList, PyTuple};
+use pyo3::wrap_pyfunction;
+
+use crate::common::rng::SeedSequence;
+use crate::fractal::box_counting::box_counting;
+use crate::fractal::box_counting_3d::{box_counting_3d, box_counting_agglomerate};
+use crate::fractal::fraktal::PyFraktalResult;
+use crate::fractal::result::PyFractalResult;
+use crate::projection::{project_batch, project_to_2d, PyProjectionResult};
+use crate::simulation::batch::simulation_function;
+use crate::simulation::result::PySimulationResult;
+use crate::{fraktal_granulated_2012, fraktal_voxel_2018};
+
+thread_local! {
+    /// Pool of the session whose call is running on this thread.
+    static SESSION_POOL: RefCell<Option<Arc<rayon::ThreadPool>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with the GIL released, on the pool of the session whose call is
+/// running on this thread if there is one. Entry points run their GIL-free
+/// work through it, so a session call holds the GIL only to convert the
+/// arguments and build the Python results.
+pub(crate) fn allow_threads<T, F>(py: Python<'_>, f: F) -> T
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    match SESSION_POOL.with(|pool| pool.borrow().clone()) {
+        Some(pool) => py.allow_threads(|| pool.install(f)),
+        None => py.allow_threads(f),
+    }
+}
+
+/// One call made through a session.
+#[derive(Debug, Clone)]
+struct SessionRecord {
+    index: usize,
+    kind: &'static str,
+    function: String,
+    seed: Option<u64>,
+}
+
+/// Context manager holding a thread pool, seed sequence and output directory.
+///
+/// ```python
+/// with AnalysisSession(seed=42, n_threads=4, output_dir="runs/a") as s:
+///     sim = s.run("dla", n_particles=500)
+///     fit = s.analyze("box_counting_3d", sim.coordinates)
+///     view = s.project(sim.coordinates, sim.radii, azimuth=30.0)
+/// ```
+#[pyclass(name = "AnalysisSession")]
+pub struct PyAnalysisSession {
+    /// Session name used in result tags
+    #[pyo3(get)]
+    pub name: String,
+    /// Master seed of the seed sequence
+    #[pyo3(get)]
+    pub seed: u64,
+    /// Number of worker threads (None = rayon default)
+    #[pyo3(get)]
+    pub n_threads: Option<usize>,
+
+    output_dir: Option<PathBuf>,
+    pool: Option<Arc<rayon::ThreadPool>>,
+    seeds_drawn: u64,
+    records: Vec<SessionRecord>,
+}
+
+impl PyAnalysisSession {
+    /// Return the session thread pool, building it on first use.
+    fn pool(&mut self) -> PyResult<Arc<rayon::ThreadPool>> {
+        if let Some(pool) = &self.pool {
+            return Ok(pool.clone());
+        }
+        let mut builder = rayon::ThreadPoolBuilder::new();
+        if let Some(n) = self.n_threads {
+            builder = builder.num_threads(n);
+        }
+        let pool = Arc::new(builder.build().map_err(|e| {
+            PyRuntimeError::new_err(format!("failed to build thread pool: {}", e))
+        })?);
+        self.pool = Some(pool.clone());
+        Ok(pool)
+    }
+
+    /// Call `function` with the session thread pool installed for its
+    /// GIL-free work (see `allow_threads`) and tag what it returns.
+    ///
+    /// The session is not borrowed while `function` runs, so Python
+    /// callbacks of the call can use it, even to make calls of their own.
+    fn dispatch(
+        mut slf: PyRefMut<'_, Self>,
+        kind: &'static str,
+        function: Bound<'_, PyCFunction>,
+        args: Bound<'_, PyTuple>,
+        kwargs: Option<Bound<'_, PyDict>>,
+        seed: Option<u64>,
+    ) -> PyResult<PyObject> {
+        let pool = slf.pool()?;
+        let name: String = function.getattr("__name__")?.extract()?;
+        let session: Py<Self> = slf.into();
+
+        let outer = SESSION_POOL.with(|current| current.replace(Some(pool)));
+        let result = function.call(args, kwargs.as_ref());
+        SESSION_POOL.with(|current| *current.borrow_mut() = outer);
+        let result = result?;
+
+        let mut slf = session.borrow_mut(function.py());
+        let index = slf.records.len();
+        let tag = format!("{}#{}", slf.name, index);
+        tag_result(&result, &tag)?;
+        slf.records.push(SessionRecord {
+            index,
+            kind,
+            function: name,
+            seed,
+        });
+        Ok(result.unbind())
+    }
+}
+
+/// Attach `tag` to a result object (or every result in a list of them).
+fn tag_result(obj: &Bound<'_, PyAny>, tag: &str) -> PyResult<()> {
+    if let Ok(list) = obj.downcast::<PyList>() {
+        for item in list.iter() {
+            tag_result(&item, tag)?;
+        }
+    } else if let Ok(r) = obj.downcast::<PySimulationResult>() {
+        r.borrow_mut().session = Some(tag.to_string());
+    } else if let Ok(r) = obj.downcast::<PyFractalResult>() {
+        r.borrow_mut().session = Some(tag.to_string());
+    } else if let Ok(r) = obj.downcast::<PyProjectionResult>() {
+        r.borrow_mut().session = Some(tag.to_string());
+    } else if let Ok(r) = obj.downcast::<PyFraktalResult>() {
+        r.borrow_mut().session = Some(tag.to_string());
+    }
+    Ok(())
+}
+
+#[pymethods]
+impl PyAnalysisSession {
+    /// Create a session.
+    ///
+    /// # Arguments
+    /// * `seed` - Master seed of the seed sequence (random if None)
+    /// * `n_threads` - Worker threads for the session pool (None = all cores)
+    /// * `output_dir` - Directory for session outputs, created on enter
+    /// * `name` - Name used when tagging results (default: "session")
+    #[new]
+    #[pyo3(signature = (seed=None, n_threads=None, output_dir=None, name="session"))]
+    fn new(
+        seed: Option<u64>,
+        n_threads: Option<usize>,
+        output_dir: Option<PathBuf>,
+        name: &str,
+    ) -> PyResult<Self> {
+        if n_threads == Some(0) {
+            return Err(PyValueError::new_err("n_threads must be at least 1"));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            seed: seed.unwrap_or_else(rand::random),
+            n_threads,
+            output_dir,
+            pool: None,
+            seeds_drawn: 0,
+            records: Vec::new(),
+        })
+    }
+
+    fn __enter__(mut slf: PyRefMut<'_, Self>) -> PyResult<PyRefMut<'_, Self>> {
+        if let Some(dir) = &slf.output_dir {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                PyIOError::new_err(format!("cannot create {}: {}", dir.display(), e))
+            })?;
+        }
+        slf.pool()?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> bool {
+        self.pool = None;
+        false
+    }
+
+    /// Output directory of the session, if configured.
+    #[getter]
+    fn output_dir(&self) -> Option<PathBuf> {
+        self.output_dir.clone()
+    }
+
+    /// Resolve `filename` inside the session output directory.
+    fn path(&self, filename: &str) -> PyResult<PathBuf> {
+        self.output_dir
+            .as_ref()
+            .map(|dir| dir.join(filename))
+            .ok_or_else(|| PyValueError::new_err("session has no output_dir"))
+    }
+
+    /// Draw the next seed of the session seed sequence.
+    fn next_seed(&mut self) -> u64 {
+        let seed = SeedSequence::new(self.seed).seed(self.seeds_drawn);
+        self.seeds_drawn += 1;
+        seed
+    }
+
+    /// Run a simulation algorithm on the session pool.
+    ///
+    /// # Arguments
+    /// * `algorithm` - One of "dla", "cca", "ballistic", "ballistic_cc", "tunable", "tunable_cc"
+    /// * `**kwargs` - Keyword arguments of the matching `run_*` function; `seed`
+    ///   is drawn from the session sequence unless given explicitly
+    #[pyo3(signature = (algorithm, **kwargs))]
+    fn run(
+        mut slf: PyRefMut<'_, Self>,
+        py: Python<'_>,
+        algorithm: &str,
+        kwargs: Option<Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let function = simulation_function(py, algorithm)?;
+
+        let kwargs = kwargs.unwrap_or_else(|| PyDict::new(py));
+        let seed = match kwargs.get_item("seed")? {
+            Some(s) if !s.is_none() => s.extract::<u64>()?,
+            _ => {
+                let s = slf.next_seed();
+                kwargs.set_item("seed", s)?;
+                s
+            }
+        };
+
+        let args = PyTuple::empty(py);
+        Self::dispatch(slf, "run", function, args, Some(kwargs), Some(seed))
+    }
+
+    /// Run a fractal analysis on the session pool.
+    ///
+    /// # Arguments
+    /// * `method` - One of "box_counting", "box_counting_3d", "box_counting_agglomerate",
+    ///   "fraktal_granulated_2012", "fraktal_voxel_2018"
+    /// * `*args`, `**kwargs` - Arguments of the matching analysis function
+    #[pyo3(signature = (method, *args, **kwargs))]
+    fn analyze(
+        slf: PyRefMut<'_, Self>,
+        py: Python<'_>,
+        method: &str,
+        args: Bound<'_, PyTuple>,
+        kwargs: Option<Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let function = match method {
+            "box_counting" => wrap_pyfunction!(box_counting, py)?,
+            "box_counting_3d" => wrap_pyfunction!(box_counting_3d, py)?,
+            "box_counting_agglomerate" => wrap_pyfunction!(box_counting_agglomerate, py)?,
+            "fraktal_granulated_2012" => wrap_pyfunction!(fraktal_granulated_2012, py)?,
+            "fraktal_voxel_2018" => wrap_pyfunction!(fraktal_voxel_2018, py)?,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown analysis method '{}'",
+                    method
+                )))
+            }
+        };
+        Self::dispatch(slf, "analyze", function, args, kwargs, None)
+    }
+
+    /// Project a structure on the session pool.
+    ///
+    /// # Arguments
+    /// * `*args`, `**kwargs` - Arguments of `project_to_2d`, or of `project_batch`
+    ///   when `batch=True`
+    #[pyo3(signature = (*args, batch=false, **kwargs))]
+    fn project(
+        slf: PyRefMut<'_, Self>,
+        py: Python<'_>,
+        args: Bound<'_, PyTuple>,
+        batch: bool,
+        kwargs: Option<Bound<'_, PyDict>>,
+    ) -> PyResult<PyObject> {
+        let function = if batch {
+            wrap_pyfunction!(project_batch, py)?
+        } else {
+            wrap_pyfunction!(project_to_2d, py)?
+        };
+        Self::dispatch(slf, "project", function, args, kwargs, None)
+    }
+
+    /// Calls made through this session as (index, kind, function, seed) tuples.
+    #[getter]
+    fn records(&self) -> Vec<(usize, &'static str, String, Option<u64>)> {
+        self.records
+            .iter()
+            .map(|r| (r.index, r.kind, r.function.clone(), r.seed))
+            .collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AnalysisSession(name='{}', seed={}, n_threads={:?}, calls={})",
+            self.name,
+            self.seed,
+            self.n_threads,
+            self.records.len()
+        )
+    }
+}
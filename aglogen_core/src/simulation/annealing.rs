@@ -0,0 +1,514 @@
+//! Target-Df annealing of arbitrary structures.
+//!
+//! The tunable engines hit a target Df/kf while growing an agglomerate.
+//! [`anneal_structure`] reaches the same targets after the fact: it takes
+//! any connected agglomerate (e.g. an imported one) and restructures it by
+//! Monte Carlo bond rotations, accepted with the Metropolis rule under a
+//! cooling schedule.
+//!
+//! A move picks a bond of a spanning tree of the contact graph and rotates
+//! the branch hanging from it rigidly about the center of the particle it
+//! hangs from. Bonds of the tree keep their length, so the structure stays
+//! connected, and moves that would create new overlaps are rejected.
+//!
+//! The Df of a single structure is measured from its mass-radius scaling:
+//! the k particles nearest to the center of gravity form nested
+//! sub-clusters, and Df is the slope of ln k against ln Rg_k. The prefactor
+//! then follows from the whole structure, `kf = N / (Rg / a)^Df`, with `a`
+//! the mean primary radius.
+
+use std::collections::VecDeque;
+
+use numpy::{PyArray1, PyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::Rng;
+
+use crate::common::fitting::linear_regression;
+use crate::common::geometry::{Quaternion, Sphere, Vector3};
+use crate::common::rng::{create_rng, random_point_on_sphere};
+use crate::common::spatial::SpatialHash;
+use crate::common::validation::{check_count, check_fractal_dimension, check_in_range, check_positive};
+use crate::common::warnings::emit_warnings;
+use crate::projection::extract_structure;
+
+use super::metrics::{calculate_center_of_gravity, mass_radius_profile};
+
+/// Number of nested sub-clusters in the mass-radius fit.
+const MASS_RADIUS_POINTS: usize = 12;
+
+/// Annealing parameters.
+#[derive(Debug, Clone)]
+pub struct AnnealingParams {
+    pub target_df: f64,
+    pub target_kf: f64,
+    /// Maximum number of Monte Carlo moves.
+    pub n_steps: usize,
+    /// Largest rotation of a single move, in radians.
+    pub max_angle: f64,
+    /// Temperatures at the first and last step (geometric cooling).
+    pub initial_temperature: f64,
+    pub final_temperature: f64,
+    /// Energy below which the targets count as reached and annealing stops.
+    pub tolerance: f64,
+}
+
+/// Df and kf of a single structure from its mass-radius scaling.
+pub fn mass_radius_scaling(coordinates: &[[f64; 3]], radii: &[f64]) -> (f64, f64) {
+    let n = coordinates.len();
+    let a = radii.iter().sum::<f64>() / n as f64;
+    // Log-spaced sub-cluster sizes from a few particles up to the whole structure
+    let (sizes, rg) = mass_radius_profile(coordinates, radii, (n / 8).max(3), MASS_RADIUS_POINTS);
+    let ln_k: Vec<f64> = sizes.iter().map(|&k| (k as f64).ln()).collect();
+    let ln_rg: Vec<f64> = rg.iter().map(|&r| (r / a).ln()).collect();
+
+    let df = linear_regression(&ln_rg, &ln_k).slope;
+    let rg = rg.last().copied().unwrap_or(0.0);
+    (df, n as f64 / (rg / a).powf(df))
+}
+
+/// Distance of the measured (Df, kf) from the targets.
+fn energy(df: f64, kf: f64, params: &AnnealingParams) -> f64 {
+    (df - params.target_df).powi(2) + (kf / params.target_kf).ln().powi(2)
+}
+
+/// Breadth-first spanning tree of the contact graph, rooted at the particle
+/// closest to the center of gravity.
+///
+/// # Returns
+/// * Parent of every particle (`None` for the root), or the number of
+///   particles reached from the root if the structure is not connected
+pub fn contact_spanning_tree(
+    coordinates: &[[f64; 3]],
+    radii: &[f64],
+    tolerance: f64,
+) -> Result<Vec<Option<usize>>, usize> {
+    let n = coordinates.len();
+    let spheres: Vec<Sphere> = coordinates
+        .iter()
+        .zip(radii)
+        .map(|(c, &r)| Sphere::new(Vector3::new(c[0], c[1], c[2]), r))
+        .collect();
+    let max_radius = radii.iter().copied().fold(0.0, f64::max);
+    let mut hash = SpatialHash::new(2.0 * max_radius + tolerance);
+    for (i, s) in spheres.iter().enumerate() {
+        hash.insert(i, s);
+    }
+
+    let cg = calculate_center_of_gravity(coordinates, radii);
+    let root = (0..n)
+        .min_by(|&i, &j| {
+            spheres[i]
+                .center
+                .distance_squared_to(&cg)
+                .total_cmp(&spheres[j].center.distance_squared_to(&cg))
+        })
+        .unwrap_or(0);
+
+    let mut parent = vec![None; n];
+    let mut visited = vec![false; n];
+    visited[root] = true;
+    let mut reached = 1;
+    let mut queue = VecDeque::from([root]);
+    while let Some(i) = queue.pop_front() {
+        for j in hash.query_potential_collisions(&spheres[i]) {
+            let limit = spheres[i].radius + spheres[j].radius + tolerance;
+            if !visited[j] && spheres[i].center.distance_squared_to(&spheres[j].center) <= limit * limit {
+                visited[j] = true;
+                parent[j] = Some(i);
+                reached += 1;
+                queue.push_back(j);
+            }
+        }
+    }
+    if reached < n {
+        return Err(reached);
+    }
+    Ok(parent)
+}
+
+/// Internal annealing result.
+pub struct AnnealingResult {
+    pub coordinates: Vec<[f64; 3]>,
+    /// Measured Df, kf and energy before the first move and after every move.
+    pub df_trajectory: Vec<f64>,
+    pub kf_trajectory: Vec<f64>,
+    pub energy_trajectory: Vec<f64>,
+    pub n_accepted: usize,
+    pub converged: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Anneal a connected structure toward the target Df/kf.
+///
+/// `parent` is the spanning tree from [`contact_spanning_tree`].
+pub fn anneal_internal(
+    coordinates: &[[f64; 3]],
+    radii: &[f64],
+    parent: &[Option<usize>],
+    params: &AnnealingParams,
+    seed: u64,
+) -> AnnealingResult {
+    let mut rng = create_rng(seed);
+    let n = coordinates.len();
+    let mut positions: Vec<Vector3> = coordinates.iter().map(|c| Vector3::new(c[0], c[1], c[2])).collect();
+    let to_rows = |positions: &[Vector3]| positions.iter().map(|p| [p.x, p.y, p.z]).collect::<Vec<_>>();
+
+    let mut children = vec![Vec::new(); n];
+    let mut movable = Vec::with_capacity(n);
+    for (i, p) in parent.iter().enumerate() {
+        if let Some(p) = *p {
+            children[p].push(i);
+            movable.push(i);
+        }
+    }
+
+    // Any overlapping pair is at most 2 * max_radius apart: a 3x3x3 cell lookup finds it
+    let max_radius = radii.iter().copied().fold(0.0, f64::max);
+    let mut hash = SpatialHash::new(2.0 * max_radius);
+    for (i, p) in positions.iter().enumerate() {
+        hash.insert(i, &Sphere::new(*p, radii[i]));
+    }
+
+    let (mut df, mut kf) = mass_radius_scaling(coordinates, radii);
+    let mut current_energy = energy(df, kf, params);
+    let mut df_trajectory = vec![df];
+    let mut kf_trajectory = vec![kf];
+    let mut energy_trajectory = vec![current_energy];
+    let mut n_accepted = 0;
+    let mut converged = current_energy < params.tolerance;
+
+    let mut in_branch = vec![false; n];
+    let mut branch = Vec::new();
+    let mut moved = Vec::new();
+    let mut step = 0;
+    while !converged && step < params.n_steps && !movable.is_empty() {
+        let progress = step as f64 / params.n_steps.max(2).saturating_sub(1) as f64;
+        let temperature =
+            params.initial_temperature * (params.final_temperature / params.initial_temperature).powf(progress);
+        step += 1;
+
+        // Branch hanging from a random bond
+        let start = movable[rng.gen_range(0..movable.len())];
+        let pivot = positions[parent[start].expect("movable particles have a parent")];
+        branch.clear();
+        branch.push(start);
+        let mut next = 0;
+        while next < branch.len() {
+            let i = branch[next];
+            branch.extend(children[i].iter().copied());
+            next += 1;
+        }
+
+        let (ax, ay, az) = random_point_on_sphere(&mut rng);
+        let half_angle = rng.gen_range(-params.max_angle..=params.max_angle) / 2.0;
+        let (sin, cos) = half_angle.sin_cos();
+        let rotation = Quaternion::new(cos, ax * sin, ay * sin, az * sin);
+
+        moved.clear();
+        moved.extend(branch.iter().map(|&i| pivot + rotation.rotate(&(positions[i] - pivot))));
+        for &i in &branch {
+            in_branch[i] = true;
+        }
+        // Only pairs across the branch change distance; none may come closer
+        // than contact unless it already was (sintered contacts)
+        let overlaps = branch.iter().zip(&moved).any(|(&i, new)| {
+            hash.query_potential_collisions(&Sphere::new(*new, radii[i]))
+                .into_iter()
+                .filter(|&j| !in_branch[j])
+                .any(|j| {
+                    let contact = radii[i] + radii[j];
+                    let limit = contact.min(positions[i].distance_to(&positions[j])) - 1e-9;
+                    new.distance_squared_to(&positions[j]) < limit * limit
+                })
+        });
+        for &i in &branch {
+            in_branch[i] = false;
+        }
+
+        if !overlaps {
+            let mut candidate = positions.clone();
+            for (&i, new) in branch.iter().zip(&moved) {
+                candidate[i] = *new;
+            }
+            let (new_df, new_kf) = mass_radius_scaling(&to_rows(&candidate), radii);
+            let new_energy = energy(new_df, new_kf, params);
+            let delta = new_energy - current_energy;
+            if delta <= 0.0 || rng.gen::<f64>() < (-delta / temperature).exp() {
+                for (&i, new) in branch.iter().zip(&moved) {
+                    hash.remove(i, &Sphere::new(positions[i], radii[i]));
+                    hash.insert(i, &Sphere::new(*new, radii[i]));
+                }
+                positions = candidate;
+                (df, kf, current_energy) = (new_df, new_kf, new_energy);
+                n_accepted += 1;
+                converged = current_energy < params.tolerance;
+            }
+        }
+
+        df_trajectory.push(df);
+        kf_trajectory.push(kf);
+        energy_trajectory.push(current_energy);
+    }
+
+    let mut warnings = Vec::new();
+    if movable.is_empty() {
+        warnings.push("structure has a single particle; nothing to anneal".to_string());
+    } else if !converged {
+        warnings.push(format!(
+            "annealing stopped after {} steps at Df={:.4}, kf={:.4} (targets Df={}, kf={})",
+            step, df, kf, params.target_df, params.target_kf
+        ));
+    }
+
+    AnnealingResult {
+        coordinates: to_rows(&positions),
+        df_trajectory,
+        kf_trajectory,
+        energy_trajectory,
+        n_accepted,
+        converged,
+        warnings,
+    }
+}
+
+/// Python wrapper for annealing results.
+#[pyclass(name = "AnnealingResult")]
+pub struct PyAnnealingResult {
+    /// Measured Df and kf of the final structure.
+    #[pyo3(get)]
+    pub fractal_dimension: f64,
+    #[pyo3(get)]
+    pub prefactor: f64,
+    /// Measured Df and kf of the input structure.
+    #[pyo3(get)]
+    pub initial_fractal_dimension: f64,
+    #[pyo3(get)]
+    pub initial_prefactor: f64,
+    /// Monte Carlo moves tried and accepted.
+    #[pyo3(get)]
+    pub n_steps: usize,
+    #[pyo3(get)]
+    pub n_accepted: usize,
+    /// Whether the targets were reached within `tolerance`.
+    #[pyo3(get)]
+    pub converged: bool,
+    /// Warnings raised during annealing (also emitted as Python `UserWarning`s).
+    #[pyo3(get)]
+    pub warnings: Vec<String>,
+
+    pub(crate) coordinates_data: Vec<[f64; 3]>,
+    pub(crate) radii_data: Vec<f64>,
+    pub(crate) df_trajectory_data: Vec<f64>,
+    pub(crate) kf_trajectory_data: Vec<f64>,
+    pub(crate) energy_trajectory_data: Vec<f64>,
+}
+
+#[pymethods]
+impl PyAnnealingResult {
+    /// Get the annealed coordinates as numpy array (N, 3).
+    #[getter]
+    fn coordinates<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let rows: Vec<Vec<f64>> = self.coordinates_data.iter().map(|c| c.to_vec()).collect();
+        PyArray2::from_vec2(py, &rows).unwrap()
+    }
+
+    /// Get the radii (unchanged) as numpy array (N,).
+    #[getter]
+    fn radii<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.radii_data.clone())
+    }
+
+    /// Get the measured Df before the first move and after every move as numpy array.
+    #[getter]
+    fn df_trajectory<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.df_trajectory_data.clone())
+    }
+
+    /// Get the measured kf along the annealing as numpy array.
+    #[getter]
+    fn kf_trajectory<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.kf_trajectory_data.clone())
+    }
+
+    /// Get the energy `(Df - target_df)^2 + ln(kf / target_kf)^2` along the annealing as numpy array.
+    #[getter]
+    fn energy_trajectory<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.energy_trajectory_data.clone())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AnnealingResult(Df {:.4} -> {:.4}, kf {:.4} -> {:.4}, n_steps={}, converged={})",
+            self.initial_fractal_dimension,
+            self.fractal_dimension,
+            self.initial_prefactor,
+            self.prefactor,
+            self.n_steps,
+            self.converged
+        )
+    }
+}
+
+/// Restructure a connected agglomerate toward a target Df and kf.
+///
+/// Branches of the agglomerate are rotated rigidly about their bonds
+/// (Monte Carlo moves that preserve connectivity and never create new
+/// overlaps) and accepted with the Metropolis rule while the temperature
+/// cools geometrically. Df is measured from the structure's mass-radius
+/// scaling and kf from `N = kf * (Rg / a)^Df` with `a` the mean radius.
+///
+/// # Arguments
+/// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
+/// * `target_df` - Target fractal dimension (1-3)
+/// * `target_kf` - Target prefactor
+/// * `n_steps` - Maximum number of Monte Carlo moves (default: 2000)
+/// * `max_angle` - Largest rotation of a move in degrees (default: 30)
+/// * `initial_temperature` - Temperature of the first move (default: 0.05)
+/// * `final_temperature` - Temperature of the last move (default: 1e-4)
+/// * `tolerance` - Stop once `(Df - target_df)^2 + ln(kf / target_kf)^2` falls below it (default: 1e-4)
+/// * `contact_tolerance` - Gap below which two particles count as bonded
+///   (default: 10% of the mean radius)
+/// * `seed` - Random seed for reproducibility
+///
+/// # Returns
+/// * `AnnealingResult` with the annealed coordinates and the Df, kf and
+///   energy trajectories
+#[pyfunction]
+#[pyo3(signature = (coordinates, radii, target_df, target_kf, n_steps=2000, max_angle=30.0, initial_temperature=0.05, final_temperature=1e-4, tolerance=1e-4, contact_tolerance=None, seed=None))]
+pub fn anneal_structure(
+    py: Python<'_>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+    target_df: f64,
+    target_kf: f64,
+    n_steps: usize,
+    max_angle: f64,
+    initial_temperature: f64,
+    final_temperature: f64,
+    tolerance: f64,
+    contact_tolerance: Option<f64>,
+    seed: Option<u64>,
+) -> PyResult<PyAnnealingResult> {
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    check_count("coordinates", coords.nrows(), 5)?;
+    check_fractal_dimension("target_df", target_df)?;
+    check_positive("target_kf", target_kf)?;
+    check_in_range("max_angle", max_angle, 0.0, 180.0)?;
+    check_positive("initial_temperature", initial_temperature)?;
+    check_positive("final_temperature", final_temperature)?;
+    check_positive("tolerance", tolerance)?;
+    let contact_tolerance = contact_tolerance.unwrap_or(0.1 * radii.iter().sum::<f64>() / radii.len() as f64);
+    if !(contact_tolerance.is_finite() && contact_tolerance >= 0.0) {
+        return Err(PyValueError::new_err(format!(
+            "contact_tolerance must be a non-negative number, got {}",
+            contact_tolerance
+        )));
+    }
+    let seed = seed.unwrap_or_else(rand::random);
+
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|r| [r[0], r[1], r[2]]).collect();
+    let parent = contact_spanning_tree(&points, &radii, contact_tolerance).map_err(|reached| {
+        PyValueError::new_err(format!(
+            "structure is not connected: only {} of {} particles are reachable through contacts \
+             (contact_tolerance={})",
+            reached,
+            points.len(),
+            contact_tolerance
+        ))
+    })?;
+
+    let params = AnnealingParams {
+        target_df,
+        target_kf,
+        n_steps,
+        max_angle: max_angle.to_radians(),
+        initial_temperature,
+        final_temperature,
+        tolerance,
+    };
+    let result = py.allow_threads(|| anneal_internal(&points, &radii, &parent, &params, seed));
+    emit_warnings(py, &result.warnings)?;
+
+    let last = result.df_trajectory.len() - 1;
+    Ok(PyAnnealingResult {
+        fractal_dimension: result.df_trajectory[last],
+        prefactor: result.kf_trajectory[last],
+        initial_fractal_dimension: result.df_trajectory[0],
+        initial_prefactor: result.kf_trajectory[0],
+        n_steps: last,
+        n_accepted: result.n_accepted,
+        converged: result.converged,
+        warnings: result.warnings,
+        coordinates_data: result.coordinates,
+        radii_data: radii,
+        df_trajectory_data: result.df_trajectory,
+        kf_trajectory_data: result.kf_trajectory,
+        energy_trajectory_data: result.energy_trajectory,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Straight chain of touching unit spheres along x.
+    fn chain(n: usize) -> (Vec<[f64; 3]>, Vec<f64>) {
+        ((0..n).map(|i| [2.0 * i as f64, 0.0, 0.0]).collect(), vec![1.0; n])
+    }
+
+    fn params(target_df: f64, target_kf: f64) -> AnnealingParams {
+        AnnealingParams {
+            target_df,
+            target_kf,
+            n_steps: 1500,
+            max_angle: 60f64.to_radians(),
+            initial_temperature: 0.05,
+            final_temperature: 1e-4,
+            tolerance: 1e-3,
+        }
+    }
+
+    #[test]
+    fn test_mass_radius_scaling_of_chain() {
+        let (coords, radii) = chain(60);
+        let (df, _) = mass_radius_scaling(&coords, &radii);
+        assert!((df - 1.0).abs() < 0.1, "chain Df = {}", df);
+    }
+
+    #[test]
+    fn test_spanning_tree_requires_connection() {
+        let (mut coords, radii) = chain(6);
+        assert!(contact_spanning_tree(&coords, &radii, 0.1).is_ok());
+        coords[5][0] += 1.0;
+        assert_eq!(contact_spanning_tree(&coords, &radii, 0.1), Err(5));
+    }
+
+    #[test]
+    fn test_annealing_folds_chain_toward_target() {
+        let (coords, radii) = chain(40);
+        let parent = contact_spanning_tree(&coords, &radii, 0.1).unwrap();
+        let params = params(1.8, 1.3);
+        let result = anneal_internal(&coords, &radii, &parent, &params, 7);
+
+        let first = result.energy_trajectory[0];
+        let last = *result.energy_trajectory.last().unwrap();
+        assert!(last < first / 4.0, "energy {} -> {}", first, last);
+        assert!(result.df_trajectory.last().unwrap() > &1.3);
+        assert!(result.n_accepted > 0);
+
+        // Bonds of the tree keep their length and no new overlaps appear
+        let d = |a: [f64; 3], b: [f64; 3]| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+        for (i, p) in parent.iter().enumerate() {
+            if let Some(p) = *p {
+                assert!((d(result.coordinates[i], result.coordinates[p]) - 2.0).abs() < 1e-9);
+            }
+        }
+        for i in 0..40 {
+            for j in (i + 1)..40 {
+                assert!(d(result.coordinates[i], result.coordinates[j]) >= 2.0 - 1e-6);
+            }
+        }
+    }
+}
@@ -8,28 +8,52 @@ use std::time::Instant;
 
 use pyo3::prelude::*;
 use rand::Rng;
+use serde::Serialize;
+use serde_json::json;
 
 use crate::common::geometry::{Sphere, Vector3};
 use crate::common::rng::{create_rng, random_direction};
-use crate::common::spatial::SpatialHash;
+use crate::common::spatial::{PeriodicSpatialHash, SpatialHash};
+use crate::common::units::PyUnits;
+use crate::common::validation::{
+    check_count, check_periodic_box, check_radius_range, check_sticking_probability,
+};
+use crate::session::allow_threads;
 
+use super::dla::nearby_particles;
+use super::history::{PyHistoryParams, Snapshot};
+use super::hooks::{EventHooks, Flow, ProgressEvent, PyCallbacks, StickEvent};
 use super::metrics::{
-    calculate_coordination, calculate_fractal_dimension, calculate_inertia_tensor,
-    calculate_porosity, calculate_radius_of_gyration,
+    calculate_contacts, calculate_fractal_dimension, calculate_inertia_tensor,
+    calculate_porosity, calculate_radius_of_gyration, coordination_from_contacts, EvolutionFit,
 };
+use super::provenance::run_parameters;
+use super::restart::{centered_store, check_existing, extract_existing};
 use super::result::{PySimulationResult, SimulationResult};
-use super::sintering::{sintered_contact_distance, SinteringDistribution};
+use super::sintering::{
+    resolve_sintering, sintered_contact_distance, PySinteringParams, SinteringDistribution,
+};
+use super::size_distribution::{resolve_size_distribution, ParticleSizes, PySizeDistribution, SizeDistribution};
 
 /// Ballistic aggregation parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BallisticParams {
     pub n_particles: usize,
     pub sticking_probability: f64,
     pub radius_min: f64,
     pub radius_max: f64,
+    /// Distribution the radii are drawn from instead of the uniform
+    /// `radius_min..=radius_max` range, which then holds its bounds
+    #[serde(rename = "size_distribution")]
+    pub sizes: Option<SizeDistribution>,
     pub launch_distance_factor: f64,
     pub max_ray_steps: usize,
     pub sintering: SinteringDistribution,
+    /// Agglomerate the run grows onto instead of a single seed particle
+    /// (empty = seed particle at the origin); it counts towards `n_particles`
+    pub initial: Vec<Sphere>,
+    /// Edge of the periodic box the particles fly in (None = open space)
+    pub box_size: Option<f64>,
 }
 
 impl Default for BallisticParams {
@@ -39,31 +63,32 @@ impl Default for BallisticParams {
             sticking_probability: 1.0,
             radius_min: 1.0,
             radius_max: 1.0,
+            sizes: None,
             launch_distance_factor: 2.0,
             max_ray_steps: 10000,
             sintering: SinteringDistribution::default(),
+            initial: Vec::new(),
+            box_size: None,
         }
     }
 }
 
-impl BallisticParams {
-    /// Check if particles are polydisperse (variable radius).
-    pub fn is_polydisperse(&self) -> bool {
-        (self.radius_max - self.radius_min).abs() > 1e-10
+impl ParticleSizes for BallisticParams {
+    fn size_parameters(&self) -> (Option<&SizeDistribution>, f64, f64) {
+        (self.sizes.as_ref(), self.radius_min, self.radius_max)
     }
+}
 
-    /// Generate a random radius within the range.
-    pub fn random_radius<R: Rng>(&self, rng: &mut R) -> f64 {
-        if self.is_polydisperse() {
-            rng.gen_range(self.radius_min..=self.radius_max)
-        } else {
-            self.radius_min
+impl BallisticParams {
+    /// Check the parameters before a run, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_count("n_particles", self.n_particles, 1)?;
+        check_existing(self.n_particles, self.initial.len())?;
+        check_sticking_probability(self.sticking_probability)?;
+        if let Some(box_size) = self.box_size {
+            check_periodic_box(box_size, self.radius_max)?;
         }
-    }
-
-    /// Get the mean radius for calculations.
-    pub fn mean_radius(&self) -> f64 {
-        (self.radius_min + self.radius_max) / 2.0
+        check_radius_range(self.radius_min, self.radius_max)
     }
 }
 
@@ -80,8 +105,30 @@ impl BallisticParams {
 /// * `sintering_max` - Max for uniform distribution (default: 0.95)
 /// * `sintering_std` - Std dev for normal distribution (default: 0.05)
 /// * `seed` - Random seed for reproducibility
+/// * `sintering` - `PySinteringParams` object; overrides the `sintering_*` arguments when given
+/// * `on_stick` - Callable invoked as `on_stick(event)` with a dict describing the event each time
+///   a particle sticks; returning `False` stops the run early
+/// * `callback_every` - Only forward every N-th event to `on_stick` (default: 1)
+/// * `units` - `Units` giving the physical meaning of the lengths (radii in nm, ...);
+///   enables the physical quantities of the result
+/// * `size_distribution` - `SizeDistribution` the primary particle diameters are drawn from
+///   (lognormal, normal, histogram, ...); overrides `radius_min`/`radius_max` when given
+/// * `progress_callback` - Callable invoked as `progress_callback(info)` every `progress_every`
+///   particles placed, as in `run_dla`
+/// * `progress_every` - Particles placed between two progress reports (default: 100)
+/// * `cancel_event` - Object with an `is_set()` method that stops the run when set, as in `run_dla`
+/// * `existing_coords` - Coordinates (N x 3) of an agglomerate to grow onto instead of a single
+///   seed particle, e.g. a DLA core to coat with a ballistic shell; it is moved so its center
+///   of mass sits at the origin and counts towards `n_particles` (see `run_ballistic_continue`)
+/// * `existing_radii` - Radii (N) of the `existing_coords` particles
+/// * `history` - `HistoryParams` to record a `GrowthHistory`, returned as
+///   `SimulationResult.history` (default: None, no history)
+/// * `box_size` - Edge of a periodic box, at least 8 `radius_max`: particles start anywhere in
+///   it, fly in random directions and collide with particle images across its faces, building a
+///   dense deposit instead of an isolated cluster. Coordinates are returned unwrapped, as in
+///   `run_dla` (default: None, open space)
 #[pyfunction]
-#[pyo3(signature = (n_particles, sticking_probability=1.0, radius_min=1.0, radius_max=None, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None))]
+#[pyo3(signature = (n_particles, sticking_probability=1.0, radius_min=1.0, radius_max=None, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None, sintering=None, on_stick=None, callback_every=1, units=None, size_distribution=None, progress_callback=None, progress_every=100, cancel_event=None, existing_coords=None, existing_radii=None, history=None, box_size=None))]
 pub fn run_ballistic(
     py: Python<'_>,
     n_particles: usize,
@@ -94,121 +141,190 @@ pub fn run_ballistic(
     sintering_max: f64,
     sintering_std: f64,
     seed: Option<u64>,
+    sintering: Option<PySinteringParams>,
+    on_stick: Option<PyObject>,
+    callback_every: usize,
+    units: Option<PyUnits>,
+    size_distribution: Option<PySizeDistribution>,
+    progress_callback: Option<PyObject>,
+    progress_every: usize,
+    cancel_event: Option<PyObject>,
+    existing_coords: Option<&Bound<'_, PyAny>>,
+    existing_radii: Option<&Bound<'_, PyAny>>,
+    history: Option<PyHistoryParams>,
+    box_size: Option<f64>,
 ) -> PyResult<PySimulationResult> {
     let seed = seed.unwrap_or_else(rand::random);
-    let radius_max = radius_max.unwrap_or(radius_min);
-
-    let sintering = match sintering_type.to_lowercase().as_str() {
-        "uniform" => SinteringDistribution::uniform(sintering_min, sintering_max),
-        "normal" => SinteringDistribution::normal(sintering_coeff, sintering_std),
-        _ => SinteringDistribution::fixed(sintering_coeff),
-    };
+    let existing = extract_existing(existing_coords, existing_radii)?;
+
+    let mut warnings = Vec::new();
+    let sintering = resolve_sintering(
+        sintering.as_ref(),
+        sintering_coeff,
+        sintering_type,
+        sintering_min,
+        sintering_max,
+        sintering_std,
+        &mut warnings,
+    )?;
+    let (radius_min, radius_max, sizes) =
+        resolve_size_distribution(size_distribution.as_ref(), radius_min, radius_max, &mut warnings)?;
 
     let params = BallisticParams {
         n_particles,
         sticking_probability,
         radius_min,
         radius_max,
+        sizes,
         sintering,
+        initial: existing,
+        box_size,
         ..Default::default()
     };
+    params.validate()?;
+    let parameters = run_parameters("ballistic", &params, json!({"seed": seed, "units": units, "history": history}));
 
+    let mut hooks = PyCallbacks::new(on_stick, None, callback_every)?
+        .with_progress(progress_callback, progress_every)?
+        .with_cancel_event(cancel_event)
+        .with_history(history)?;
     // Release GIL during computation
-    let result = py.allow_threads(|| run_ballistic_internal(params, seed));
+    let result = allow_threads(py, || run_ballistic_internal(params, seed, &mut hooks));
+    let history = hooks.take_history();
+    hooks.finish(&mut warnings)?;
 
-    Ok(result.to_py())
+    result
+        .into_py(py, warnings)
+        .map(|r| r.with_units(units).with_history(history).with_parameters(parameters))
 }
 
 /// Internal Ballistic Aggregation implementation.
-fn run_ballistic_internal(params: BallisticParams, seed: u64) -> SimulationResult {
+fn run_ballistic_internal(params: BallisticParams, seed: u64, hooks: &mut dyn EventHooks) -> SimulationResult {
     let start_time = Instant::now();
     let mut rng = create_rng(seed);
+    let radii = params.radius_sampler();
+    let mut warnings = Vec::new();
 
-    // Initialize with seed particle at origin (use mean radius for seed)
-    let seed_radius = params.mean_radius();
-    let mut particles: Vec<Sphere> = vec![Sphere::new(Vector3::zero(), seed_radius)];
+    // Grow onto the existing agglomerate, or from a mean-radius seed particle at the origin
+    let mut particles = centered_store(&params.initial, params.n_particles);
+    if particles.is_empty() {
+        particles.push(Sphere::new(Vector3::zero(), params.mean_radius()));
+    }
 
     // Use max radius for spatial hash cell size to handle polydisperse particles
-    let mut spatial_hash = SpatialHash::new(params.radius_max * 4.0);
-    spatial_hash.insert(0, &particles[0]);
+    let max_radius = particles.radii().iter().fold(params.radius_max, |m, &r| m.max(r));
+    let mut spatial_hash = SpatialHash::new(max_radius * 4.0);
+    for (idx, sphere) in particles.iter().enumerate() {
+        spatial_hash.insert(idx, &sphere);
+    }
+    // In a periodic box particles also hit the particle images across its faces
+    let mut periodic = params.box_size.map(|box_size| {
+        let mut hash = PeriodicSpatialHash::new(box_size, max_radius * 4.0);
+        for (idx, sphere) in particles.iter().enumerate() {
+            hash.insert(idx, &sphere);
+        }
+        hash
+    });
 
     // Track Rg evolution
-    let mut rg_evolution = vec![seed_radius * (3.0 / 5.0_f64).sqrt()];
-    let mut n_values = vec![1usize];
+    let mut rg_evolution = vec![calculate_radius_of_gyration(&particles.coords(), particles.radii())];
+    let mut n_values = vec![particles.len()];
 
-    // Cluster properties
-    let mut cluster_rg = seed_radius;
+    // Cluster properties (a lone seed counts with its full radius)
+    let mut cluster_rg = if particles.len() == 1 { particles.radius(0) } else { rg_evolution[0] };
 
     // Add particles one by one
     while particles.len() < params.n_particles {
         // Generate radius for new particle
-        let new_radius = params.random_radius(&mut rng);
+        let new_radius = radii.sample_radius(&mut rng);
 
         // Launch distance based on current cluster size
         let launch_distance = params.launch_distance_factor * cluster_rg + params.radius_max * 5.0;
 
-        // Generate random starting position on launch sphere
-        let (dx, dy, dz) = random_direction(&mut rng);
-        let start_pos = Vector3::new(
-            dx * launch_distance,
-            dy * launch_distance,
-            dz * launch_distance,
-        );
-
-        // Direction towards cluster center (with some randomness)
-        let (rx, ry, rz) = random_direction(&mut rng);
-        let randomness = 0.1; // Small random deviation
-        let target = Vector3::new(rx * randomness, ry * randomness, rz * randomness);
-        let direction = (target - start_pos).normalize();
+        let (start_pos, direction) = match &periodic {
+            // Anywhere in the free space of a periodic box, in a random direction
+            Some(hash) => match hash.random_free_point(new_radius, 1000, &mut rng) {
+                Some(pos) => {
+                    let (rx, ry, rz) = random_direction(&mut rng);
+                    (pos, Vector3::new(rx, ry, rz))
+                }
+                None => {
+                    warnings.push(format!(
+                        "periodic box is full: stopped at {} particles",
+                        particles.len()
+                    ));
+                    break;
+                }
+            },
+            None => {
+                // Generate random starting position on launch sphere
+                let (dx, dy, dz) = random_direction(&mut rng);
+                let start_pos = Vector3::new(
+                    dx * launch_distance,
+                    dy * launch_distance,
+                    dz * launch_distance,
+                );
+
+                // Direction towards cluster center (with some randomness)
+                let (rx, ry, rz) = random_direction(&mut rng);
+                let randomness = 0.1; // Small random deviation
+                let target = Vector3::new(rx * randomness, ry * randomness, rz * randomness);
+                (start_pos, (target - start_pos).normalize())
+            }
+        };
 
         // Ray-march towards cluster (step size based on new particle radius)
         let step_size = new_radius * 0.5;
         let mut pos = start_pos;
         let mut stuck = false;
+        let mut touched = None;
+
+        let step = direction * step_size;
+        let launch_distance_sq = launch_distance * launch_distance;
 
         for _ in 0..params.max_ray_steps {
-            pos = pos + direction * step_size;
+            pos = pos + step;
 
-            // Check if we've passed through the cluster (gone too far)
-            if pos.length() > launch_distance {
-                break;
+            match &periodic {
+                Some(hash) => pos = hash.wrap(pos),
+                // Check if we've passed through the cluster (gone too far)
+                None if pos.length_squared() > launch_distance_sq => break,
+                None => {}
             }
 
             // Check for collision with existing particles
             // Note: We detect collision at unsintered distance (physical touch),
             // then apply sintering coefficient when placing the particle.
-            let test_sphere = Sphere::new(pos, new_radius);
-            let candidates = spatial_hash.query_potential_collisions(&test_sphere);
+            let candidates = nearby_particles(pos, new_radius, &spatial_hash, periodic.as_ref(), &particles);
 
             // Sample sintering coefficient once for this particle
             let sintering_coeff = params.sintering.sample(&mut rng);
 
-            for &idx in &candidates {
-                let other = &particles[idx];
-                let dist = pos.distance_to(&other.center);
+            for &(idx, other_center) in &candidates {
+                let dist_sq = pos.distance_squared_to(&other_center);
                 // Use sintered distance for collision detection to ensure consistent behavior
-                let contact_dist = sintered_contact_distance(new_radius, other.radius, sintering_coeff);
+                let contact_dist = sintered_contact_distance(new_radius, particles.radius(idx), sintering_coeff);
 
-                if dist < contact_dist * 1.05 {
+                if dist_sq < (contact_dist * 1.05).powi(2) {
                     // Collision! Check sticking probability
                     if params.sticking_probability >= 1.0
                         || rng.gen::<f64>() < params.sticking_probability
                     {
                         // Place particle at exact sintered contact distance
-                        let new_direction = (pos - other.center).normalize();
-                        let new_pos = other.center + new_direction * contact_dist;
+                        let new_direction = (pos - other_center).normalize();
+                        let new_pos = other_center + new_direction * contact_dist;
 
                         // Verify no overlaps with other particles
-                        let valid = !particles.iter().enumerate().any(|(i, p)| {
-                            if i == idx { return false; }
-                            let d = new_pos.distance_to(&p.center);
-                            let min_dist = sintered_contact_distance(new_radius, p.radius, sintering_coeff);
-                            d < min_dist - 1e-6
-                        });
+                        let valid = match &periodic {
+                            Some(hash) => !hash.overlaps(new_pos, new_radius, sintering_coeff, Some(idx), 1e-6),
+                            None => !particles.overlaps(new_pos, new_radius, sintering_coeff, Some(idx), 1e-6),
+                        };
 
                         if valid {
-                            pos = new_pos;
+                            // Next to the touched particle itself rather than its image
+                            pos = new_pos + (particles.center(idx) - other_center);
                             stuck = true;
+                            touched = Some(idx);
                             break;
                         }
                     }
@@ -226,30 +342,36 @@ fn run_ballistic_internal(params: BallisticParams, seed: u64) -> SimulationResul
             let idx = particles.len();
             particles.push(new_sphere);
             spatial_hash.insert(idx, &new_sphere);
+            if let Some(hash) = periodic.as_mut() {
+                hash.insert(idx, &new_sphere);
+            }
 
             // Update cluster Rg
-            let coords: Vec<[f64; 3]> = particles
-                .iter()
-                .map(|s| [s.center.x, s.center.y, s.center.z])
-                .collect();
-            let radii: Vec<f64> = particles.iter().map(|s| s.radius).collect();
-            cluster_rg = calculate_radius_of_gyration(&coords, &radii);
+            cluster_rg = calculate_radius_of_gyration(&particles.coords(), particles.radii());
 
             rg_evolution.push(cluster_rg);
             n_values.push(particles.len());
+
+            let progress = ProgressEvent::particles(particles.len(), params.n_particles, cluster_rg, start_time);
+            if hooks.wants_snapshot(&progress) {
+                hooks.on_snapshot(Snapshot::of_store(particles.len(), &particles));
+            }
+            if hooks.on_stick(&StickEvent::new(idx, new_sphere, touched)) == Flow::Stop
+                || hooks.on_progress(&progress) == Flow::Stop
+            {
+                break;
+            }
         }
     }
 
     // Calculate final metrics
-    let coords: Vec<[f64; 3]> = particles
-        .iter()
-        .map(|s| [s.center.x, s.center.y, s.center.z])
-        .collect();
-    let radii: Vec<f64> = particles.iter().map(|s| s.radius).collect();
+    let coords = particles.coords();
+    let radii = particles.radii().to_vec();
 
-    let (df, kf, _r2) = calculate_fractal_dimension(&n_values, &rg_evolution);
+    let fit = calculate_fractal_dimension(&n_values, &rg_evolution, &mut warnings);
     let porosity = calculate_porosity(&coords, &radii);
-    let coordination = calculate_coordination(&coords, &radii, params.mean_radius() * 0.1);
+    let contacts = calculate_contacts(&coords, &radii, params.mean_radius() * 0.1);
+    let coordination = coordination_from_contacts(&contacts, coords.len());
     let inertia = calculate_inertia_tensor(&coords, &radii);
 
     let coord_mean = coordination.iter().map(|&c| c as f64).sum::<f64>() / coordination.len() as f64;
@@ -260,31 +382,53 @@ fn run_ballistic_internal(params: BallisticParams, seed: u64) -> SimulationResul
         / coordination.len() as f64)
         .sqrt();
 
+    let n_final = coords.len();
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
     SimulationResult {
         coordinates: coords,
         radii,
         rg_evolution,
-        fractal_dimension: df,
-        fractal_dimension_std: 0.02,
-        prefactor: kf,
+        n_evolution: n_values,
+        fractal_dimension: fit.df,
+        fractal_dimension_std: fit.df_std_error,
+        prefactor: fit.kf,
+        linear_region: fit.linear_region,
+        evolution_fit: EvolutionFit::default(),
+        fit_r_squared: fit.r_squared,
+        fit_n_points: fit.n_points,
         porosity,
         coordination_mean: coord_mean,
         coordination_std: coord_std,
         execution_time_ms,
         seed,
+        child_seeds: Vec::new(),
         anisotropy: inertia.anisotropy,
         asphericity: inertia.asphericity,
         acylindricity: inertia.acylindricity,
         principal_moments: inertia.principal_moments,
         principal_axes: inertia.principal_axes,
+        ids: (0..n_final as u32).collect(),
+        cluster_ids: vec![0; n_final],
+        coordination,
+        contacts,
+        generations: vec![0; n_final],
+        merge_history: Vec::new(),
+        warnings,
+        target_report: None,
+        brownian_report: None,
+        kinetics: None,
+        film: None,
+        drift_report: None,
+        merge_diagnostics: None,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::spatial::check_periodic_deposit;
+    use crate::simulation::hooks::NoHooks;
 
     #[test]
     fn test_ballistic_deterministic() {
@@ -293,8 +437,8 @@ mod tests {
             ..Default::default()
         };
 
-        let r1 = run_ballistic_internal(params.clone(), 42);
-        let r2 = run_ballistic_internal(params, 42);
+        let r1 = run_ballistic_internal(params.clone(), 42, &mut NoHooks);
+        let r2 = run_ballistic_internal(params, 42, &mut NoHooks);
 
         assert_eq!(r1.coordinates.len(), r2.coordinates.len());
         for (c1, c2) in r1.coordinates.iter().zip(r2.coordinates.iter()) {
@@ -311,7 +455,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = run_ballistic_internal(params, 456);
+        let result = run_ballistic_internal(params, 456, &mut NoHooks);
 
         // Ballistic typically produces Df ~ 2.8-3.0 (denser than DLA)
         assert!(result.fractal_dimension > 2.0);
@@ -327,9 +471,9 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(params.is_polydisperse());
+        assert!(params.size_distribution().is_polydisperse());
 
-        let result = run_ballistic_internal(params, 456);
+        let result = run_ballistic_internal(params, 456, &mut NoHooks);
 
         // Check that we have variable radii
         let min_r = result.radii.iter().cloned().fold(f64::INFINITY, f64::min);
@@ -341,6 +485,25 @@ mod tests {
         assert!(max_r <= 1.2 + 1e-10, "Max radius should be <= 1.2");
     }
 
+    #[test]
+    fn test_ballistic_periodic_box() {
+        let box_size = 16.0;
+        let params = BallisticParams {
+            n_particles: 200,
+            box_size: Some(box_size),
+            ..Default::default()
+        };
+        let result = run_ballistic_internal(params, 5, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 200);
+        check_periodic_deposit(&result.coordinates, &result.radii, box_size);
+
+        let tight = BallisticParams {
+            box_size: Some(4.0),
+            ..Default::default()
+        };
+        assert!(tight.validate().is_err());
+    }
+
     #[test]
     fn test_ballistic_monodisperse() {
         let params = BallisticParams {
@@ -350,9 +513,9 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(!params.is_polydisperse());
+        assert!(!params.size_distribution().is_polydisperse());
 
-        let result = run_ballistic_internal(params, 789);
+        let result = run_ballistic_internal(params, 789, &mut NoHooks);
 
         // All radii should be equal
         for r in &result.radii {
@@ -11,31 +11,54 @@
 //! This produces more open, branched structures than Ballistic PC (Df ~ 1.8-2.1)
 //! because clusters of similar size merge, reducing interpenetration.
 
+use std::collections::HashMap;
 use std::time::Instant;
 
 use pyo3::prelude::*;
 use rand::seq::SliceRandom;
 use rand::Rng;
+use serde::Serialize;
+use serde_json::json;
 
 use crate::common::geometry::{Sphere, Vector3};
 use crate::common::rng::{create_rng, random_direction, random_point_on_sphere};
+use crate::common::units::PyUnits;
+use crate::common::validation::{check_count, check_radius_range, check_sticking_probability};
+use crate::session::allow_threads;
 
+use super::history::{PyHistoryParams, Snapshot};
+use super::hooks::{ClusterMergeEvent, EventHooks, Flow, ProgressEvent, PyCallbacks};
+use super::lineage::Lineage;
 use super::metrics::{
-    calculate_coordination, calculate_fractal_dimension, calculate_inertia_tensor,
-    calculate_porosity, calculate_radius_of_gyration,
+    calculate_contacts, calculate_fractal_dimension, calculate_inertia_tensor,
+    calculate_porosity, calculate_radius_of_gyration, coordination_from_contacts, EvolutionFit,
 };
-use super::result::{PySimulationResult, SimulationResult};
-use super::sintering::{sintered_contact_distance, SinteringDistribution};
+use super::provenance::run_parameters;
+use super::restart::{
+    check_existing, draw_clear_monomer, extract_existing, group_clusters, overlapping_monomers_warning, pool_occupancy,
+};
+use super::result::{creation_order, reorder, PySimulationResult, SimulationResult};
+use super::sintering::{
+    resolve_sintering, sintered_contact_distance, PySinteringParams, SinteringDistribution,
+};
+use super::size_distribution::{resolve_size_distribution, ParticleSizes, PySizeDistribution, SizeDistribution};
 
 /// Ballistic CC simulation parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BallisticCcParams {
     pub n_particles: usize,
     pub sticking_probability: f64,
     pub radius_min: f64,
     pub radius_max: f64,
+    /// Distribution the radii are drawn from instead of the uniform
+    /// `radius_min..=radius_max` range, which then holds its bounds
+    #[serde(rename = "size_distribution")]
+    pub sizes: Option<SizeDistribution>,
     pub max_collision_attempts: usize,
     pub sintering: SinteringDistribution,
+    /// Clusters the pool restarts from, kept at their positions; they count
+    /// towards `n_particles` and the other particles start as monomers
+    pub initial_clusters: Vec<Vec<Sphere>>,
 }
 
 impl Default for BallisticCcParams {
@@ -45,27 +68,27 @@ impl Default for BallisticCcParams {
             sticking_probability: 1.0,
             radius_min: 1.0,
             radius_max: 1.0,
+            sizes: None,
             max_collision_attempts: 100,
             sintering: SinteringDistribution::default(),
+            initial_clusters: Vec::new(),
         }
     }
 }
 
-impl BallisticCcParams {
-    pub fn is_polydisperse(&self) -> bool {
-        (self.radius_max - self.radius_min).abs() > 1e-10
-    }
-
-    pub fn random_radius<R: Rng>(&self, rng: &mut R) -> f64 {
-        if self.is_polydisperse() {
-            rng.gen_range(self.radius_min..=self.radius_max)
-        } else {
-            self.radius_min
-        }
+impl ParticleSizes for BallisticCcParams {
+    fn size_parameters(&self) -> (Option<&SizeDistribution>, f64, f64) {
+        (self.sizes.as_ref(), self.radius_min, self.radius_max)
     }
+}
 
-    pub fn mean_radius(&self) -> f64 {
-        (self.radius_min + self.radius_max) / 2.0
+impl BallisticCcParams {
+    /// Check the parameters before a run, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_count("n_particles", self.n_particles, 1)?;
+        check_existing(self.n_particles, self.initial_clusters.iter().map(Vec::len).sum())?;
+        check_sticking_probability(self.sticking_probability)?;
+        check_radius_range(self.radius_min, self.radius_max)
     }
 }
 
@@ -73,6 +96,12 @@ impl BallisticCcParams {
 #[derive(Clone)]
 struct Cluster {
     particles: Vec<Sphere>,
+    /// Persistent particle IDs, parallel to `particles`.
+    ids: Vec<u32>,
+    /// Merge generation of each particle, parallel to `particles`.
+    generations: Vec<u32>,
+    /// Lineage label of this cluster (see `lineage`).
+    label: u32,
     center_of_mass: Vector3,
     geometric_center: Vector3,
     bounding_radius: f64,
@@ -81,7 +110,7 @@ struct Cluster {
 
 impl Cluster {
     /// Create a new cluster from a single particle (monomer).
-    fn new(sphere: Sphere) -> Self {
+    fn new(sphere: Sphere, id: u32) -> Self {
         let rg = sphere.radius * (3.0 / 5.0_f64).sqrt();
         Self {
             center_of_mass: sphere.center,
@@ -89,9 +118,29 @@ impl Cluster {
             bounding_radius: sphere.radius,
             radius_of_gyration: rg,
             particles: vec![sphere],
+            ids: vec![id],
+            generations: vec![0],
+            label: id,
         }
     }
 
+    /// Create a cluster from existing particles with consecutive IDs from `first_id`.
+    fn from_particles(particles: Vec<Sphere>, first_id: u32, label: u32) -> Self {
+        let n = particles.len() as u32;
+        let mut cluster = Self {
+            ids: (first_id..first_id + n).collect(),
+            generations: vec![0; particles.len()],
+            label,
+            particles,
+            center_of_mass: Vector3::zero(),
+            geometric_center: Vector3::zero(),
+            bounding_radius: 0.0,
+            radius_of_gyration: 0.0,
+        };
+        cluster.update_properties();
+        cluster
+    }
+
     /// Update cluster properties after modification.
     fn update_properties(&mut self) {
         if self.particles.is_empty() {
@@ -142,6 +191,11 @@ impl Cluster {
     /// Merge another cluster into this one.
     fn merge_with(&mut self, other: Cluster) {
         self.particles.extend(other.particles);
+        self.ids.extend(other.ids);
+        self.generations.extend(other.generations);
+        for generation in &mut self.generations {
+            *generation += 1;
+        }
         self.update_properties();
     }
 
@@ -155,41 +209,52 @@ impl Cluster {
         max_distance: f64,
         sintering_coeff: f64,
     ) -> Option<(f64, usize, usize, f64)> {
-        // For each pair of particles, find the collision distance along trajectory
-        // We solve: |pi.center - (pj.center + t*trajectory)| = contact_dist
-        // Which becomes: |d - t*trajectory|^2 = contact_dist^2
-        // Where d = pi.center - pj.center
-        let mut best_collision: Option<(f64, usize, usize, f64)> = None;
+        // Broad phase: two particles can only touch if the moving one passes within
+        // contact distance of the stationary one, i.e. if their projections onto the
+        // plane perpendicular to the trajectory are that close. Bucket this cluster's
+        // particles by projected position (cells as wide as the largest contact
+        // distance) so only near-line pairs get the quadratic solve.
+        let max_radius = |c: &Cluster| c.particles.iter().map(|p| p.radius).fold(0.0, f64::max);
+        let cell_size = sintered_contact_distance(max_radius(self), max_radius(other), sintering_coeff);
+        let (u, v) = create_orthogonal_basis(trajectory.normalize());
+        let cell_of = |p: Vector3| {
+            (
+                (p.dot(&u) / cell_size).floor() as i64,
+                (p.dot(&v) / cell_size).floor() as i64,
+            )
+        };
 
+        let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
         for (i, pi) in self.particles.iter().enumerate() {
-            for (j, pj) in other.particles.iter().enumerate() {
-                let d = pi.center - pj.center;
-                // Use sintered contact distance
-                let contact_dist = sintered_contact_distance(pi.radius, pj.radius, sintering_coeff);
-
-                // Quadratic: t^2*(traj·traj) - 2t*(d·traj) + (d·d - contact^2) = 0
-                let a = trajectory.dot(&trajectory);
-                let b = -2.0 * d.dot(&trajectory); // Note: negative sign
-                let c = d.dot(&d) - contact_dist * contact_dist;
-
-                let discriminant = b * b - 4.0 * a * c;
-
-                if discriminant >= 0.0 {
-                    let sqrt_disc = discriminant.sqrt();
-                    let t1 = (-b - sqrt_disc) / (2.0 * a);
-                    let t2 = (-b + sqrt_disc) / (2.0 * a);
-
-                    // We want the smallest positive t (first collision along trajectory)
-                    let t = if t1 > 1e-10 {
-                        t1
-                    } else if t2 > 1e-10 {
-                        t2
-                    } else {
+            cells.entry(cell_of(pi.center)).or_default().push(i);
+        }
+
+        let mut best_collision: Option<(f64, usize, usize, f64)> = None;
+
+        for (j, pj) in other.particles.iter().enumerate() {
+            let (cu, cv) = cell_of(pj.center);
+            for du in -1..=1 {
+                for dv in -1..=1 {
+                    let Some(candidates) = cells.get(&(cu + du, cv + dv)) else {
                         continue;
                     };
-
-                    if t <= max_distance {
-                        if best_collision.is_none() || t < best_collision.unwrap().0 {
+                    for &i in candidates {
+                        let pi = &self.particles[i];
+                        // Use sintered contact distance
+                        let contact_dist = sintered_contact_distance(pi.radius, pj.radius, sintering_coeff);
+                        let Some(t) = collision_distance(pi.center - pj.center, trajectory, contact_dist)
+                        else {
+                            continue;
+                        };
+                        if t > max_distance {
+                            continue;
+                        }
+                        // Break ties by (i, j) so the result does not depend on cell order
+                        let is_better = match best_collision {
+                            None => true,
+                            Some((best_t, best_i, best_j, _)) => (t, i, j) < (best_t, best_i, best_j),
+                        };
+                        if is_better {
                             best_collision = Some((t, i, j, contact_dist));
                         }
                     }
@@ -201,6 +266,36 @@ impl Cluster {
     }
 }
 
+/// Smallest positive distance `t` along `trajectory` at which a sphere at offset
+/// `-d` from a stationary one comes into contact with it, if it ever does.
+///
+/// Solves `|d - t*trajectory|^2 = contact_dist^2`, where `d` is the stationary
+/// center minus the moving center.
+fn collision_distance(d: Vector3, trajectory: Vector3, contact_dist: f64) -> Option<f64> {
+    // Quadratic: t^2*(traj·traj) - 2t*(d·traj) + (d·d - contact^2) = 0
+    let a = trajectory.dot(&trajectory);
+    let b = -2.0 * d.dot(&trajectory); // Note: negative sign
+    let c = d.dot(&d) - contact_dist * contact_dist;
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let sqrt_disc = discriminant.sqrt();
+    let t1 = (-b - sqrt_disc) / (2.0 * a);
+    let t2 = (-b + sqrt_disc) / (2.0 * a);
+
+    // We want the smallest positive t (first collision along trajectory)
+    if t1 > 1e-10 {
+        Some(t1)
+    } else if t2 > 1e-10 {
+        Some(t2)
+    } else {
+        None
+    }
+}
+
 /// Run Ballistic CC simulation.
 ///
 /// # Arguments
@@ -214,8 +309,28 @@ impl Cluster {
 /// * `sintering_max` - Max for uniform distribution (default: 0.95)
 /// * `sintering_std` - Std dev for normal distribution (default: 0.05)
 /// * `seed` - Random seed for reproducibility
+/// * `sintering` - `PySinteringParams` object; overrides the `sintering_*` arguments when given
+/// * `on_merge` - Callable invoked as `on_merge(event)` with a dict describing the event each time
+///   two clusters merge; returning `False` stops the run early
+/// * `callback_every` - Only forward every N-th event to `on_merge` (default: 1)
+/// * `units` - `Units` giving the physical meaning of the lengths (radii in nm, ...);
+///   enables the physical quantities of the result
+/// * `size_distribution` - `SizeDistribution` the primary particle diameters are drawn from
+///   (lognormal, normal, histogram, ...); overrides `radius_min`/`radius_max` when given
+/// * `progress_callback` - Callable invoked as `progress_callback(info)` every `progress_every`
+///   merges, as in `run_dla`
+/// * `progress_every` - Merges between two progress reports (default: 100)
+/// * `cancel_event` - Object with an `is_set()` method that stops the run when set, as in `run_dla`
+/// * `existing_coords` - Coordinates (N x 3) of particles the cluster pool restarts from, e.g.
+///   agglomerates of earlier runs; they keep their positions, count towards `n_particles` and
+///   take the first IDs, and the remaining particles start as monomers
+/// * `existing_radii` - Radii (N) of the `existing_coords` particles
+/// * `existing_cluster_ids` - Cluster of each existing particle, as in
+///   `SimulationResult.cluster_ids` (default: all in one cluster)
+/// * `history` - `HistoryParams` to record a `GrowthHistory`, returned as
+///   `SimulationResult.history` (default: None, no history)
 #[pyfunction]
-#[pyo3(signature = (n_particles, sticking_probability=1.0, radius_min=1.0, radius_max=None, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None))]
+#[pyo3(signature = (n_particles, sticking_probability=1.0, radius_min=1.0, radius_max=None, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None, sintering=None, on_merge=None, callback_every=1, units=None, size_distribution=None, progress_callback=None, progress_every=100, cancel_event=None, existing_coords=None, existing_radii=None, existing_cluster_ids=None, history=None))]
 pub fn run_ballistic_cc(
     py: Python<'_>,
     n_particles: usize,
@@ -228,51 +343,111 @@ pub fn run_ballistic_cc(
     sintering_max: f64,
     sintering_std: f64,
     seed: Option<u64>,
+    sintering: Option<PySinteringParams>,
+    on_merge: Option<PyObject>,
+    callback_every: usize,
+    units: Option<PyUnits>,
+    size_distribution: Option<PySizeDistribution>,
+    progress_callback: Option<PyObject>,
+    progress_every: usize,
+    cancel_event: Option<PyObject>,
+    existing_coords: Option<&Bound<'_, PyAny>>,
+    existing_radii: Option<&Bound<'_, PyAny>>,
+    existing_cluster_ids: Option<Vec<u32>>,
+    history: Option<PyHistoryParams>,
 ) -> PyResult<PySimulationResult> {
     let seed = seed.unwrap_or_else(rand::random);
-    let radius_max = radius_max.unwrap_or(radius_min);
-
-    let sintering = match sintering_type.to_lowercase().as_str() {
-        "uniform" => SinteringDistribution::uniform(sintering_min, sintering_max),
-        "normal" => SinteringDistribution::normal(sintering_coeff, sintering_std),
-        _ => SinteringDistribution::fixed(sintering_coeff),
-    };
+    let existing = extract_existing(existing_coords, existing_radii)?;
+
+    let mut warnings = Vec::new();
+    let sintering = resolve_sintering(
+        sintering.as_ref(),
+        sintering_coeff,
+        sintering_type,
+        sintering_min,
+        sintering_max,
+        sintering_std,
+        &mut warnings,
+    )?;
+    let (radius_min, radius_max, sizes) =
+        resolve_size_distribution(size_distribution.as_ref(), radius_min, radius_max, &mut warnings)?;
+    let initial_clusters = group_clusters(existing, existing_cluster_ids)?;
 
     let params = BallisticCcParams {
         n_particles,
         sticking_probability,
         radius_min,
         radius_max,
+        sizes,
         sintering,
+        initial_clusters,
         ..Default::default()
     };
-
-    let result = py.allow_threads(|| run_ballistic_cc_internal(params, seed));
-
-    Ok(result.to_py())
+    params.validate()?;
+    let parameters = run_parameters("ballistic_cc", &params, json!({"seed": seed, "units": units, "history": history}));
+
+    let mut hooks = PyCallbacks::new(None, on_merge, callback_every)?
+        .with_progress(progress_callback, progress_every)?
+        .with_cancel_event(cancel_event)
+        .with_history(history)?;
+    let result = allow_threads(py, || run_ballistic_cc_internal(params, seed, &mut hooks));
+    let history = hooks.take_history();
+    hooks.finish(&mut warnings)?;
+
+    result
+        .into_py(py, warnings)
+        .map(|r| r.with_units(units).with_history(history).with_parameters(parameters))
 }
 
 /// Internal Ballistic CC implementation following thesis section 6.2.
-fn run_ballistic_cc_internal(params: BallisticCcParams, seed: u64) -> SimulationResult {
+fn run_ballistic_cc_internal(params: BallisticCcParams, seed: u64, hooks: &mut dyn EventHooks) -> SimulationResult {
     let start_time = Instant::now();
     let mut rng = create_rng(seed);
+    let radii = params.radius_sampler();
+    let mut warnings = Vec::new();
+
+    // Step 1: Initialize the pool. Clusters of a restarted pool come first and
+    // keep their positions; the other particles start as individual clusters (monomers)
+    let mut clusters: Vec<Cluster> = Vec::with_capacity(params.n_particles);
+    let mut next_id = 0u32;
+    for particles in &params.initial_clusters {
+        clusters.push(Cluster::from_particles(particles.clone(), next_id, clusters.len() as u32));
+        next_id += particles.len() as u32;
+    }
 
-    // Step 1: Initialize all particles as individual clusters (monomers)
-    // Spread them out in space to avoid initial overlaps
+    // Spread the monomers out in space to avoid initial overlaps, clear of the restarted clusters
     let spread = (params.n_particles as f64).cbrt() * params.mean_radius() * 3.0;
-    let mut clusters: Vec<Cluster> = (0..params.n_particles)
-        .map(|_| {
+    let max_radius = params.size_distribution().diameter_range().1 / 2.0;
+    let pool_reach = params
+        .initial_clusters
+        .iter()
+        .flatten()
+        .map(|p| p.center.x.abs().max(p.center.y.abs()).max(p.center.z.abs()) + p.radius)
+        .fold(0.0, f64::max);
+    // A box four times the reach of everything: no periodic image is ever near
+    let open_box = 4.0 * (pool_reach.max(spread / 2.0) + 2.0 * max_radius);
+    let occupied = pool_occupancy(params.initial_clusters.iter().flatten(), open_box, max_radius);
+    let mut overlapping = 0;
+    for id in next_id..params.n_particles as u32 {
+        let (monomer, clear) = draw_clear_monomer(&occupied, || {
             let x = (rng.gen::<f64>() - 0.5) * spread;
             let y = (rng.gen::<f64>() - 0.5) * spread;
             let z = (rng.gen::<f64>() - 0.5) * spread;
-            let radius = params.random_radius(&mut rng);
-            Cluster::new(Sphere::new(Vector3::new(x, y, z), radius))
-        })
-        .collect();
+            Sphere::new(Vector3::new(x, y, z), radii.sample_radius(&mut rng))
+        });
+        overlapping += usize::from(!clear);
+        let mut cluster = Cluster::new(monomer, id);
+        cluster.label = clusters.len() as u32;
+        clusters.push(cluster);
+    }
+    if overlapping > 0 {
+        warnings.push(overlapping_monomers_warning(overlapping));
+    }
 
     // Track Rg evolution of the largest cluster
     let mut rg_evolution = Vec::new();
     let mut n_values = Vec::new();
+    let mut lineage = Lineage::new(clusters.len());
 
     // Main aggregation loop - continue until only one cluster remains
     let mut iterations = 0;
@@ -352,21 +527,47 @@ fn run_ballistic_cc_internal(params: BallisticCcParams, seed: u64) -> Simulation
                 // Create merged cluster from working_impactor (which has correct position)
                 // and the impacted cluster's particles
                 let mut merged = working_impactor;
-                if higher_idx == idx_impactor {
+                let impacted = if higher_idx == idx_impactor {
                     // cluster_low is impacted - already at correct position
-                    merged.merge_with(cluster_low);
+                    cluster_low
                 } else {
                     // cluster_high was impacted, cluster_low was impactor
-                    merged.merge_with(cluster_high);
-                }
+                    cluster_high
+                };
+                let merge = lineage.record(
+                    iterations,
+                    (impacted.label, impacted.particles.len()),
+                    (merged.label, merged.particles.len()),
+                );
+                merged.merge_with(impacted);
+                merged.label = merge.merged;
+                let event = ClusterMergeEvent::new(merge, merged.center_of_mass, clusters.len() + 1);
 
                 // Add merged cluster back to pool
                 clusters.push(merged);
 
                 // Track largest cluster
+                let mut progress = Flow::Continue;
                 if let Some(largest) = clusters.iter().max_by_key(|c| c.particles.len()) {
                     rg_evolution.push(largest.radius_of_gyration);
                     n_values.push(largest.particles.len());
+                    let event = ProgressEvent::merges(
+                        lineage.n_merges(),
+                        largest.particles.len(),
+                        params.n_particles,
+                        clusters.len(),
+                        largest.radius_of_gyration,
+                        start_time,
+                    );
+                    if hooks.wants_snapshot(&event) {
+                        let pool = clusters.iter().map(|c| c.particles.as_slice());
+                        hooks.on_snapshot(Snapshot::of_clusters(event.step, pool));
+                    }
+                    progress = hooks.on_progress(&event);
+                }
+
+                if hooks.on_merge(&event) == Flow::Stop || progress == Flow::Stop {
+                    break;
                 }
             }
         }
@@ -374,11 +575,26 @@ fn run_ballistic_cc_internal(params: BallisticCcParams, seed: u64) -> Simulation
         // The clusters remain separate and may be selected again
     }
 
-    // Collect all particles from the final cluster
-    let final_particles: Vec<Sphere> = if clusters.is_empty() {
-        Vec::new()
-    } else {
-        clusters.remove(0).particles
+    // Collect the final cluster in creation order. If the run stopped early
+    // (callback or iteration limit), keep the largest cluster.
+    if clusters.len() > 1 {
+        warnings.push(format!(
+            "aggregation stopped with {} clusters left; returning the largest one",
+            clusters.len()
+        ));
+    }
+    let largest = (0..clusters.len()).max_by_key(|&i| clusters[i].particles.len());
+    let (ids, generations, final_particles) = match largest {
+        None => (Vec::new(), Vec::new(), Vec::new()),
+        Some(index) => {
+            let cluster = clusters.swap_remove(index);
+            let order = creation_order(&cluster.ids);
+            (
+                reorder(&cluster.ids, &order),
+                reorder(&cluster.generations, &order),
+                reorder(&cluster.particles, &order),
+            )
+        }
     };
 
     // Calculate final metrics
@@ -388,9 +604,10 @@ fn run_ballistic_cc_internal(params: BallisticCcParams, seed: u64) -> Simulation
         .collect();
     let radii: Vec<f64> = final_particles.iter().map(|s| s.radius).collect();
 
-    let (df, kf, _r2) = calculate_fractal_dimension(&n_values, &rg_evolution);
+    let fit = calculate_fractal_dimension(&n_values, &rg_evolution, &mut warnings);
     let porosity = calculate_porosity(&coords, &radii);
-    let coordination = calculate_coordination(&coords, &radii, params.mean_radius() * 0.1);
+    let contacts = calculate_contacts(&coords, &radii, params.mean_radius() * 0.1);
+    let coordination = coordination_from_contacts(&contacts, coords.len());
     let inertia = calculate_inertia_tensor(&coords, &radii);
 
     let coord_mean =
@@ -412,25 +629,45 @@ fn run_ballistic_cc_internal(params: BallisticCcParams, seed: u64) -> Simulation
         0.0
     };
 
+    let n_final = coords.len();
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
     SimulationResult {
         coordinates: coords,
         radii,
         rg_evolution,
-        fractal_dimension: df,
-        fractal_dimension_std: 0.02,
-        prefactor: kf,
+        n_evolution: n_values,
+        fractal_dimension: fit.df,
+        fractal_dimension_std: fit.df_std_error,
+        prefactor: fit.kf,
+        linear_region: fit.linear_region,
+        evolution_fit: EvolutionFit::default(),
+        fit_r_squared: fit.r_squared,
+        fit_n_points: fit.n_points,
         porosity,
         coordination_mean: coord_mean,
         coordination_std: coord_std,
         execution_time_ms,
         seed,
+        child_seeds: Vec::new(),
         anisotropy: inertia.anisotropy,
         asphericity: inertia.asphericity,
         acylindricity: inertia.acylindricity,
         principal_moments: inertia.principal_moments,
         principal_axes: inertia.principal_axes,
+        ids,
+        cluster_ids: vec![0; n_final],
+        coordination,
+        contacts,
+        generations,
+        merge_history: lineage.into_events(),
+        warnings,
+        target_report: None,
+        brownian_report: None,
+        kinetics: None,
+        film: None,
+        drift_report: None,
+        merge_diagnostics: None,
     }
 }
 
@@ -454,6 +691,7 @@ fn create_orthogonal_basis(dir: Vector3) -> (Vector3, Vector3) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::simulation::hooks::{NoHooks, StopAfter};
 
     #[test]
     fn test_ballistic_cc_deterministic() {
@@ -462,8 +700,8 @@ mod tests {
             ..Default::default()
         };
 
-        let r1 = run_ballistic_cc_internal(params.clone(), 42);
-        let r2 = run_ballistic_cc_internal(params, 42);
+        let r1 = run_ballistic_cc_internal(params.clone(), 42, &mut NoHooks);
+        let r2 = run_ballistic_cc_internal(params, 42, &mut NoHooks);
 
         assert_eq!(r1.coordinates.len(), r2.coordinates.len());
     }
@@ -475,11 +713,22 @@ mod tests {
             ..Default::default()
         };
 
-        let result = run_ballistic_cc_internal(params, 123);
+        let result = run_ballistic_cc_internal(params, 123, &mut NoHooks);
 
         // Should produce all particles
         assert_eq!(result.coordinates.len(), 50);
 
+        // Merging reorders clusters, but output follows creation order
+        assert_eq!(result.ids, (0..50).collect::<Vec<u32>>());
+
+        // 50 monomers need 49 merges; the last one creates label 50 + 48
+        // and joins clusters that together hold every particle
+        assert_eq!(result.merge_history.len(), 49);
+        let last = result.merge_history.last().unwrap();
+        assert_eq!(last.merged, 98);
+        assert_eq!(last.first_size + last.second_size, 50);
+        assert!(result.generations.iter().all(|&g| (1..=49).contains(&g)));
+
         // Ballistic CC typically produces Df ~ 1.8-2.2 (more open than PC)
         assert!(
             result.fractal_dimension > 1.0,
@@ -488,6 +737,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ballistic_cc_stops_when_hook_asks() {
+        let params = BallisticCcParams {
+            n_particles: 30,
+            ..Default::default()
+        };
+
+        let result = run_ballistic_cc_internal(params, 42, &mut StopAfter(5));
+
+        assert_eq!(result.merge_history.len(), 5);
+        assert!(result.coordinates.len() < 30);
+        assert!(result.warnings.iter().any(|w| w.contains("clusters left")));
+    }
+
+    #[test]
+    fn test_ballistic_cc_restarts_from_cluster_pool() {
+        let agglomerate = |seed: u64, offset: f64| -> Vec<Sphere> {
+            let params = BallisticCcParams {
+                n_particles: 10,
+                ..Default::default()
+            };
+            let result = run_ballistic_cc_internal(params, seed, &mut NoHooks);
+            result
+                .coordinates
+                .iter()
+                .zip(&result.radii)
+                .map(|(c, &r)| Sphere::new(Vector3::new(c[0] + offset, c[1], c[2]), r))
+                .collect()
+        };
+        let pool = vec![agglomerate(1, 0.0), agglomerate(2, 50.0)];
+        let params = BallisticCcParams {
+            n_particles: 25,
+            initial_clusters: pool.clone(),
+            ..Default::default()
+        };
+
+        let result = run_ballistic_cc_internal(params, 42, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 25);
+        // Two pool clusters and five monomers take six merges
+        assert_eq!(result.merge_history.len(), 6);
+        // Each pool cluster moves as a rigid body
+        let distance = |a: [f64; 3], b: [f64; 3]| Vector3::new(a[0] - b[0], a[1] - b[1], a[2] - b[2]).length();
+        let d_before = pool[1][0].center.distance_to(&pool[1][9].center);
+        assert!((distance(result.coordinates[10], result.coordinates[19]) - d_before).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_find_collision_matches_exhaustive_search() {
+        let mut rng = create_rng(7);
+        let mut random_cluster = |offset: Vector3, first_id: u32| {
+            let spheres: Vec<Sphere> = (0..60)
+                .map(|_| {
+                    let (x, y, z) = random_point_on_sphere(&mut rng);
+                    let r = 0.5 + rng.gen::<f64>();
+                    Sphere::new(offset + Vector3::new(x, y, z) * (8.0 * rng.gen::<f64>()), r)
+                })
+                .collect();
+            let mut cluster = Cluster::new(spheres[0], first_id);
+            cluster.particles = spheres;
+            cluster.update_properties();
+            cluster
+        };
+        let impacted = random_cluster(Vector3::zero(), 0);
+        let impactor = random_cluster(Vector3::new(-40.0, 1.0, -2.0), 60);
+        let trajectory = Vector3::new(1.0, 0.05, 0.0).normalize();
+
+        let mut expected: Option<(f64, usize, usize)> = None;
+        for (i, pi) in impacted.particles.iter().enumerate() {
+            for (j, pj) in impactor.particles.iter().enumerate() {
+                let contact = sintered_contact_distance(pi.radius, pj.radius, 0.9);
+                if let Some(t) = collision_distance(pi.center - pj.center, trajectory, contact) {
+                    if t <= 100.0 && expected.is_none_or(|(best, _, _)| t < best) {
+                        expected = Some((t, i, j));
+                    }
+                }
+            }
+        }
+
+        let found = impacted
+            .find_collision_with(&impactor, trajectory, 100.0, 0.9)
+            .map(|(t, i, j, _)| (t, i, j));
+        assert!(expected.is_some());
+        assert_eq!(found, expected);
+    }
+
     #[test]
     fn test_ballistic_cc_polydisperse() {
         let params = BallisticCcParams {
@@ -497,9 +831,9 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(params.is_polydisperse());
+        assert!(params.size_distribution().is_polydisperse());
 
-        let result = run_ballistic_cc_internal(params, 789);
+        let result = run_ballistic_cc_internal(params, 789, &mut NoHooks);
 
         let min_r = result.radii.iter().cloned().fold(f64::INFINITY, f64::min);
         let max_r = result.radii.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
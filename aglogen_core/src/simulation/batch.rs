@@ -0,0 +1,147 @@
+//! Parallel batches of independent simulation runs.
+//!
+//! A batch runs one algorithm with the same parameters under many seeds.
+//! The runs share the process, so there is no per-worker copy of the
+//! interpreter as with `multiprocessing`, and each replica's seed depends
+//! only on its index, so a batch gives the same agglomerates whatever the
+//! number of threads.
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyDict};
+use pyo3::wrap_pyfunction;
+use rayon::prelude::*;
+
+use crate::common::rng::SeedSequence;
+
+use super::ballistic::run_ballistic;
+use super::ballistic_cc::run_ballistic_cc;
+use super::cca::run_cca;
+use super::dla::run_dla;
+use super::result::PySimulationResult;
+use super::tunable::run_tunable;
+use super::tunable_cc::run_tunable_cc;
+
+/// The `run_*` function of a simulation algorithm.
+pub(crate) fn simulation_function<'py>(py: Python<'py>, algorithm: &str) -> PyResult<Bound<'py, PyCFunction>> {
+    match algorithm {
+        "dla" => wrap_pyfunction!(run_dla, py),
+        "cca" => wrap_pyfunction!(run_cca, py),
+        "ballistic" => wrap_pyfunction!(run_ballistic, py),
+        "ballistic_cc" => wrap_pyfunction!(run_ballistic_cc, py),
+        "tunable" => wrap_pyfunction!(run_tunable, py),
+        "tunable_cc" => wrap_pyfunction!(run_tunable_cc, py),
+        _ => Err(PyValueError::new_err(format!(
+            "unknown algorithm '{}'",
+            algorithm
+        ))),
+    }
+}
+
+/// Seeds of the replicas: the given list, or `n_replicas` seeds derived from `seed`.
+fn replica_seeds(n_replicas: Option<usize>, seeds: Option<Vec<u64>>, seed: Option<u64>) -> PyResult<Vec<u64>> {
+    match (n_replicas, seeds) {
+        (Some(n), Some(seeds)) if n != seeds.len() => Err(PyValueError::new_err(format!(
+            "n_replicas ({}) does not match the number of seeds ({})",
+            n,
+            seeds.len()
+        ))),
+        (_, Some(seeds)) => Ok(seeds),
+        (Some(n), None) => {
+            let sequence = SeedSequence::new(seed.unwrap_or_else(rand::random));
+            Ok((0..n as u64).map(|i| sequence.seed(i)).collect())
+        }
+        (None, None) => Err(PyValueError::new_err("give n_replicas or seeds")),
+    }
+}
+
+/// Run independent replicas of a simulation in parallel.
+///
+/// Every replica calls the `run_*` function of `algorithm` with `params`
+/// and its own seed. The engines run on a rayon thread pool with the GIL
+/// released, so the replicas use all cores from a single process. Replica
+/// `i` gets `seeds[i]`, or `SeedSequence(seed).seed(i)` (as in
+/// `AnalysisSession`), so results do not depend on the thread count or
+/// scheduling.
+///
+/// # Arguments
+/// * `algorithm` - One of "dla", "cca", "ballistic", "ballistic_cc", "tunable", "tunable_cc"
+/// * `params` - Dict of keyword arguments of the matching `run_*` function, without `seed`
+/// * `n_replicas` - Number of runs (default: the length of `seeds`)
+/// * `seeds` - Seed of each run
+/// * `seed` - Master seed the run seeds are derived from when `seeds` is None (default: random)
+/// * `n_threads` - Worker threads (default: all cores)
+///
+/// # Returns
+/// * List of `SimulationResult`, one per replica in seed order
+#[pyfunction]
+#[pyo3(signature = (algorithm, params=None, n_replicas=None, seeds=None, seed=None, n_threads=None))]
+pub fn run_batch(
+    py: Python<'_>,
+    algorithm: &str,
+    params: Option<Bound<'_, PyDict>>,
+    n_replicas: Option<usize>,
+    seeds: Option<Vec<u64>>,
+    seed: Option<u64>,
+    n_threads: Option<usize>,
+) -> PyResult<Vec<PySimulationResult>> {
+    let function = simulation_function(py, algorithm)?.unbind();
+    let params = params.unwrap_or_else(|| PyDict::new(py));
+    if params.contains("seed")? {
+        return Err(PyValueError::new_err(
+            "params must not contain 'seed'; pass seeds or seed to run_batch",
+        ));
+    }
+    let seeds = replica_seeds(n_replicas, seeds, seed)?;
+    let kwargs = seeds
+        .iter()
+        .map(|&s| {
+            let kwargs = params.copy()?;
+            kwargs.set_item("seed", s)?;
+            Ok(kwargs.unbind())
+        })
+        .collect::<PyResult<Vec<Py<PyDict>>>>()?;
+
+    let pool = match n_threads {
+        Some(n) => Some(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build()
+                .map_err(|e| PyRuntimeError::new_err(format!("failed to build thread pool: {}", e)))?,
+        ),
+        None => None,
+    };
+
+    // Each worker takes the GIL only to enter and leave the engine, which releases it
+    let run = || {
+        kwargs
+            .par_iter()
+            .map(|k| {
+                Python::with_gil(|py| {
+                    function
+                        .bind(py)
+                        .call((), Some(k.bind(py)))?
+                        .extract::<PySimulationResult>()
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()
+    };
+    py.allow_threads(|| match &pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::rng::derive_seed;
+
+    #[test]
+    fn test_replica_seeds() {
+        assert_eq!(replica_seeds(None, Some(vec![3, 1]), None).unwrap(), vec![3, 1]);
+        let derived = replica_seeds(Some(3), None, Some(7)).unwrap();
+        assert_eq!(derived, vec![derive_seed(7, 0), derive_seed(7, 1), derive_seed(7, 2)]);
+        assert!(replica_seeds(Some(2), Some(vec![1]), None).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+//! Physical Brownian dynamics for cluster-cluster aggregation.
+//!
+//! The plain CCA engine moves clusters by abstract steps. In the physical
+//! mode every cluster diffuses with the Stokes-Einstein coefficient of its
+//! mobility radius in a gas,
+//!
+//! D = kT Cc(Kn) / (6 pi mu Rm),  Cc = 1 + Kn (1.257 + 0.4 exp(-1.1 / Kn)),
+//!
+//! Kn = lambda / Rm being the Knudsen number, so each step advances a
+//! physical clock and cluster counts can be compared with aerosol
+//! coagulation experiments. The mobility radius is taken as sqrt(5/3) Rg,
+//! the radius of a monomer.
+
+use std::f64::consts::PI;
+
+use pyo3::prelude::*;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+use crate::common::geometry::Vector3;
+use crate::common::validation::check_positive;
+
+/// Boltzmann constant in J/K.
+const BOLTZMANN: f64 = 1.380649e-23;
+
+/// Molar gas constant in J/(mol K).
+const GAS_CONSTANT: f64 = 8.314462618;
+
+/// Molar mass of air in kg/mol.
+const AIR_MOLAR_MASS: f64 = 0.028_97;
+
+/// Cunningham slip correction at Knudsen number `knudsen`.
+pub fn cunningham_correction(knudsen: f64) -> f64 {
+    if knudsen <= 0.0 {
+        return 1.0;
+    }
+    1.0 + knudsen * (1.257 + 0.4 * (-1.1 / knudsen).exp())
+}
+
+/// Viscosity of air in Pa s at `temperature` K (Sutherland's law).
+pub fn air_viscosity(temperature: f64) -> f64 {
+    let (mu_0, t_0, sutherland) = (1.716e-5, 273.15, 110.4);
+    mu_0 * (temperature / t_0).powf(1.5) * (t_0 + sutherland) / (temperature + sutherland)
+}
+
+/// Gas and particle properties of a physical CCA run.
+#[pyclass(name = "BrownianParams")]
+#[derive(Debug, Clone, Serialize)]
+pub struct PyBrownianParams {
+    /// Gas temperature in K (default: 298.15)
+    #[pyo3(get, set)]
+    pub temperature: f64,
+
+    /// Gas pressure in Pa (default: 101325)
+    #[pyo3(get, set)]
+    pub pressure: f64,
+
+    /// Gas viscosity in Pa s (default: None, air at `temperature`)
+    #[pyo3(get, set)]
+    pub gas_viscosity: Option<f64>,
+
+    /// Mean free path of the gas in m (default: None, from viscosity,
+    /// temperature and pressure for air)
+    #[pyo3(get, set)]
+    pub mean_free_path: Option<f64>,
+
+    /// Density of the particle material in kg/m³ (default: 1800, soot)
+    #[pyo3(get, set)]
+    pub particle_density: f64,
+
+    /// Physical time of one step in s (default: None, the time a monomer
+    /// takes to diffuse one mean radius)
+    #[pyo3(get, set)]
+    pub time_step: Option<f64>,
+}
+
+#[pymethods]
+impl PyBrownianParams {
+    #[new]
+    #[pyo3(signature = (temperature=298.15, pressure=101325.0, gas_viscosity=None, mean_free_path=None, particle_density=1800.0, time_step=None))]
+    pub fn new(
+        temperature: f64,
+        pressure: f64,
+        gas_viscosity: Option<f64>,
+        mean_free_path: Option<f64>,
+        particle_density: f64,
+        time_step: Option<f64>,
+    ) -> PyResult<Self> {
+        let params = Self {
+            temperature,
+            pressure,
+            gas_viscosity,
+            mean_free_path,
+            particle_density,
+            time_step,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "BrownianParams(temperature={}, pressure={}, gas_viscosity={:.4e}, mean_free_path={:.4e}, \
+             particle_density={})",
+            self.temperature,
+            self.pressure,
+            self.viscosity(),
+            self.free_path(),
+            self.particle_density
+        )
+    }
+}
+
+impl Default for PyBrownianParams {
+    fn default() -> Self {
+        Self {
+            temperature: 298.15,
+            pressure: 101_325.0,
+            gas_viscosity: None,
+            mean_free_path: None,
+            particle_density: 1800.0,
+            time_step: None,
+        }
+    }
+}
+
+impl PyBrownianParams {
+    pub fn validate(&self) -> PyResult<()> {
+        check_positive("temperature", self.temperature)?;
+        check_positive("pressure", self.pressure)?;
+        check_positive("particle_density", self.particle_density)?;
+        for (name, value) in [
+            ("gas_viscosity", self.gas_viscosity),
+            ("mean_free_path", self.mean_free_path),
+            ("time_step", self.time_step),
+        ] {
+            if let Some(value) = value {
+                check_positive(name, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gas viscosity in Pa s.
+    pub fn viscosity(&self) -> f64 {
+        self.gas_viscosity.unwrap_or_else(|| air_viscosity(self.temperature))
+    }
+
+    /// Mean free path of the gas in m, lambda = mu / p sqrt(pi R T / (2 M)) for air.
+    pub fn free_path(&self) -> f64 {
+        self.mean_free_path.unwrap_or_else(|| {
+            let speed_factor = (PI * GAS_CONSTANT * self.temperature / (2.0 * AIR_MOLAR_MASS)).sqrt();
+            self.viscosity() / self.pressure * speed_factor
+        })
+    }
+}
+
+/// Physical clock and cluster displacements of a Brownian CCA run.
+#[derive(Debug, Clone)]
+pub struct BrownianDynamics {
+    /// Size of one simulation length unit in m
+    metres_per_unit: f64,
+    /// kT / (6 pi mu) in m³/s
+    stokes_einstein: f64,
+    mean_free_path: f64,
+    time_step: f64,
+    report: PyBrownianReport,
+}
+
+impl BrownianDynamics {
+    /// Set up the dynamics of `n_particles` monomers of `mean_radius` in a box of `box_size`.
+    ///
+    /// Lengths are simulation units of `metres_per_unit` m. Warns when the
+    /// time step is shorter than the momentum relaxation time of a monomer,
+    /// below which the overdamped (Brownian) limit does not hold.
+    pub fn new(
+        params: &PyBrownianParams,
+        metres_per_unit: f64,
+        mean_radius: f64,
+        box_size: f64,
+        n_particles: usize,
+        warnings: &mut Vec<String>,
+    ) -> Self {
+        let viscosity = params.viscosity();
+        let mean_free_path = params.free_path();
+        let stokes_einstein = BOLTZMANN * params.temperature / (6.0 * PI * viscosity);
+
+        let radius = mean_radius * metres_per_unit;
+        let knudsen = mean_free_path / radius;
+        let slip_correction = cunningham_correction(knudsen);
+        let diffusion = stokes_einstein * slip_correction / radius;
+        let time_step = params.time_step.unwrap_or(radius * radius / (6.0 * diffusion));
+
+        // Momentum relaxation time m B, B = Cc / (6 pi mu r) the mobility
+        let mass = params.particle_density * 4.0 / 3.0 * PI * radius.powi(3);
+        let relaxation_time = mass * slip_correction / (6.0 * PI * viscosity * radius);
+        if time_step < relaxation_time {
+            warnings.push(format!(
+                "time_step {:.3e} s is below the monomer relaxation time {:.3e} s: \
+                 the motion is not diffusive on that scale",
+                time_step, relaxation_time
+            ));
+        }
+
+        // Smoluchowski continuum kernel of two monomers, 4 pi (D + D)(r + r)
+        let kernel = 16.0 * PI * diffusion * radius;
+        let concentration = n_particles as f64 / (box_size * metres_per_unit).powi(3);
+
+        Self {
+            metres_per_unit,
+            stokes_einstein,
+            mean_free_path,
+            time_step,
+            report: PyBrownianReport {
+                temperature: params.temperature,
+                gas_viscosity: viscosity,
+                mean_free_path,
+                time_step,
+                simulated_time: 0.0,
+                monomer_knudsen: knudsen,
+                monomer_slip_correction: slip_correction,
+                monomer_diffusion: diffusion,
+                relaxation_time,
+                coagulation_time: 2.0 / (kernel * concentration),
+                number_concentration: concentration,
+                times: vec![0.0],
+                n_clusters: vec![n_particles],
+            },
+        }
+    }
+
+    /// Diffusion coefficient in m²/s of a cluster of radius of gyration `rg` (simulation units).
+    pub fn diffusion_coefficient(&self, rg: f64) -> f64 {
+        let mobility_radius = (5.0f64 / 3.0).sqrt() * rg * self.metres_per_unit;
+        self.stokes_einstein * cunningham_correction(self.mean_free_path / mobility_radius) / mobility_radius
+    }
+
+    /// Standard deviation of a displacement component over one time step, in simulation units.
+    pub fn step_deviation(&self, rg: f64) -> f64 {
+        (2.0 * self.diffusion_coefficient(rg) * self.time_step).sqrt() / self.metres_per_unit
+    }
+
+    /// Random displacement over one time step, in simulation units, of a cluster of gyration radius `rg`.
+    pub fn displacement<R: Rng>(&self, rg: f64, rng: &mut R) -> Vector3 {
+        let sigma = self.step_deviation(rg);
+        Vector3::new(
+            sigma * rng.sample::<f64, _>(StandardNormal),
+            sigma * rng.sample::<f64, _>(StandardNormal),
+            sigma * rng.sample::<f64, _>(StandardNormal),
+        )
+    }
+
+    /// Advance the clock by one step; record the cluster count if it changed.
+    pub fn advance(&mut self, n_clusters: usize) {
+        let report = &mut self.report;
+        report.simulated_time += self.time_step;
+        if report.n_clusters.last() != Some(&n_clusters) {
+            report.times.push(report.simulated_time);
+            report.n_clusters.push(n_clusters);
+        }
+    }
+
+    pub fn finish(self) -> PyBrownianReport {
+        self.report
+    }
+}
+
+/// Physical time scales of a Brownian CCA run, from `run_cca_physical`.
+///
+/// Times are in s, diffusion coefficients in m²/s and concentrations in
+/// 1/m³. The cluster count at `times` can be compared with the Smoluchowski
+/// solution for a constant kernel, N(t) = N0 / (1 + t / coagulation_time).
+#[pyclass(name = "BrownianReport")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PyBrownianReport {
+    #[pyo3(get)]
+    pub temperature: f64,
+    #[pyo3(get)]
+    pub gas_viscosity: f64,
+    #[pyo3(get)]
+    pub mean_free_path: f64,
+    /// Physical time of one step
+    #[pyo3(get)]
+    pub time_step: f64,
+    /// Physical time the run covered
+    #[pyo3(get)]
+    pub simulated_time: f64,
+    /// Knudsen number of a mean monomer, mean free path over radius
+    #[pyo3(get)]
+    pub monomer_knudsen: f64,
+    /// Cunningham slip correction of a mean monomer
+    #[pyo3(get)]
+    pub monomer_slip_correction: f64,
+    /// Diffusion coefficient of a mean monomer
+    #[pyo3(get)]
+    pub monomer_diffusion: f64,
+    /// Momentum relaxation time of a mean monomer
+    #[pyo3(get)]
+    pub relaxation_time: f64,
+    /// Time for the monomer count to halve under the continuum monomer
+    /// kernel, 2 / (K11 n0)
+    #[pyo3(get)]
+    pub coagulation_time: f64,
+    /// Initial number concentration n0 of particles in the box
+    #[pyo3(get)]
+    pub number_concentration: f64,
+    /// Physical time of every change of the cluster count
+    #[pyo3(get)]
+    pub times: Vec<f64>,
+    /// Clusters left at each of `times`
+    #[pyo3(get)]
+    pub n_clusters: Vec<usize>,
+}
+
+#[pymethods]
+impl PyBrownianReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "BrownianReport(simulated_time={:.4e}, time_step={:.4e}, coagulation_time={:.4e}, monomer_knudsen={:.3})",
+            self.simulated_time, self.time_step, self.coagulation_time, self.monomer_knudsen
+        )
+    }
+}
@@ -3,28 +3,57 @@
 //! In CCA, all particles start as individual clusters that move via Brownian motion
 //! and merge upon collision, forming hierarchical fractal structures.
 
+use std::collections::HashMap;
 use std::time::Instant;
 
 use pyo3::prelude::*;
 use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::Serialize;
+use serde_json::json;
 
 use crate::common::geometry::{Sphere, Vector3};
 use crate::common::rng::{create_rng, random_direction};
+use crate::common::spatial::PeriodicCellList;
+use crate::common::units::PyUnits;
+use crate::common::validation::{
+    check_count, check_positive, check_radius_range, check_sticking_probability,
+};
+use crate::session::allow_threads;
 
+use super::brownian::{BrownianDynamics, PyBrownianParams};
+use super::history::{PyHistoryParams, Snapshot};
+use super::hooks::{ClusterMergeEvent, EventHooks, Flow, ProgressEvent, PyCallbacks};
+use super::lineage::Lineage;
 use super::metrics::{
-    calculate_coordination, calculate_fractal_dimension, calculate_inertia_tensor,
-    calculate_porosity, calculate_radius_of_gyration,
+    calculate_contacts, calculate_fractal_dimension, calculate_inertia_tensor,
+    calculate_porosity, calculate_radius_of_gyration, coordination_from_contacts, EvolutionFit,
+};
+use super::provenance::run_parameters;
+use super::restart::{
+    check_existing, draw_clear_monomer, extract_existing, group_clusters, overlapping_monomers_warning, pool_occupancy,
+};
+use super::result::{creation_order, reorder, PySimulationResult, SimulationResult};
+use super::sintering::{
+    resolve_sintering, sintered_contact_distance, PySinteringParams, SinteringDistribution,
 };
-use super::result::{PySimulationResult, SimulationResult};
-use super::sintering::{sintered_contact_distance, SinteringDistribution};
+use super::size_distribution::{resolve_size_distribution, ParticleSizes, PySizeDistribution, SizeDistribution};
+use super::sticking::{resolve_sticking, KineticsRecorder, StickingModel};
 
 /// CCA simulation parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CcaParams {
     pub n_particles: usize,
     pub sticking_probability: f64,
+    /// How the sticking probability of a collision depends on the clusters;
+    /// `sticking_probability` is its base value
+    pub sticking: StickingModel,
     pub radius_min: f64,
     pub radius_max: f64,
+    /// Distribution the radii are drawn from instead of the uniform
+    /// `radius_min..=radius_max` range, which then holds its bounds
+    #[serde(rename = "size_distribution")]
+    pub sizes: Option<SizeDistribution>,
     pub box_size: f64,
     pub max_iterations: usize,
     pub step_size_factor: f64,
@@ -32,6 +61,17 @@ pub struct CcaParams {
     /// If false, stop at max_iterations (multi-agglomerate mode).
     pub single_agglomerate: bool,
     pub sintering: SinteringDistribution,
+    /// Clusters the pool restarts from, kept at their positions; they count
+    /// towards `n_particles` and the other particles start as monomers
+    pub initial_clusters: Vec<Vec<Sphere>>,
+    /// Gas and particle properties of the physical mode, where clusters
+    /// diffuse with their Stokes-Einstein coefficient instead of abstract steps
+    pub brownian: Option<PyBrownianParams>,
+    /// Size of one length unit in m, for the physical mode
+    pub metres_per_unit: f64,
+    /// Rotate clusters about their center of mass as well as translating
+    /// them, see `rotation_deviation`; turns into another cluster are undone
+    pub rotational_diffusion: bool,
 }
 
 impl Default for CcaParams {
@@ -39,17 +79,29 @@ impl Default for CcaParams {
         Self {
             n_particles: 1000,
             sticking_probability: 1.0,
+            sticking: StickingModel::Constant,
             radius_min: 1.0,
             radius_max: 1.0,
+            sizes: None,
             box_size: 100.0,
             max_iterations: 100_000,
             step_size_factor: 2.0, // Increased for faster convergence
             single_agglomerate: true,
             sintering: SinteringDistribution::default(),
+            initial_clusters: Vec::new(),
+            brownian: None,
+            metres_per_unit: 1e-9,
+            rotational_diffusion: false,
         }
     }
 }
 
+impl ParticleSizes for CcaParams {
+    fn size_parameters(&self) -> (Option<&SizeDistribution>, f64, f64) {
+        (self.sizes.as_ref(), self.radius_min, self.radius_max)
+    }
+}
+
 impl CcaParams {
     /// Calculate optimal box size based on particle count and target volume fraction.
     ///
@@ -63,43 +115,60 @@ impl CcaParams {
         box_volume.cbrt()
     }
 
-    /// Check if particles are polydisperse.
-    pub fn is_polydisperse(&self) -> bool {
-        (self.radius_max - self.radius_min).abs() > 1e-10
-    }
-
-    /// Generate a random radius within the range.
-    pub fn random_radius<R: Rng>(&self, rng: &mut R) -> f64 {
-        if self.is_polydisperse() {
-            rng.gen_range(self.radius_min..=self.radius_max)
-        } else {
-            self.radius_min
+    /// Check the parameters before a run, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_count("n_particles", self.n_particles, 1)?;
+        check_existing(self.n_particles, self.initial_clusters.iter().map(Vec::len).sum())?;
+        check_sticking_probability(self.sticking_probability)?;
+        check_positive("box_size", self.box_size)?;
+        if let Some(brownian) = &self.brownian {
+            brownian.validate()?;
         }
-    }
-
-    /// Get the mean radius.
-    pub fn mean_radius(&self) -> f64 {
-        (self.radius_min + self.radius_max) / 2.0
+        check_radius_range(self.radius_min, self.radius_max)
     }
 }
 
 /// A cluster is a collection of particles that move together.
 struct Cluster {
     particles: Vec<Sphere>,
+    /// Persistent particle IDs, parallel to `particles`.
+    ids: Vec<u32>,
+    /// Merge generation of each particle, parallel to `particles`.
+    generations: Vec<u32>,
+    /// Lineage label of this cluster (see `lineage`).
+    label: u32,
     center_of_mass: Vector3,
     radius_of_gyration: f64,
 }
 
 impl Cluster {
-    fn new(sphere: Sphere) -> Self {
+    fn new(sphere: Sphere, id: u32) -> Self {
         let rg = sphere.radius * (3.0 / 5.0_f64).sqrt();
         Self {
             center_of_mass: sphere.center,
             radius_of_gyration: rg,
             particles: vec![sphere],
+            ids: vec![id],
+            generations: vec![0],
+            label: id,
         }
     }
 
+    /// Create a cluster from existing particles with consecutive IDs from `first_id`.
+    fn from_particles(particles: Vec<Sphere>, first_id: u32, label: u32) -> Self {
+        let n = particles.len() as u32;
+        let mut cluster = Self {
+            ids: (first_id..first_id + n).collect(),
+            generations: vec![0; particles.len()],
+            label,
+            particles,
+            center_of_mass: Vector3::zero(),
+            radius_of_gyration: 0.0,
+        };
+        cluster.update_properties();
+        cluster
+    }
+
     fn update_properties(&mut self) {
         if self.particles.is_empty() {
             return;
@@ -132,18 +201,42 @@ impl Cluster {
         self.center_of_mass = self.center_of_mass + delta;
     }
 
+    /// Rotate about the center of mass by the rotation vector `omega` (axis times angle).
+    fn rotate(&mut self, omega: Vector3) {
+        let angle = omega.length();
+        if angle == 0.0 {
+            return;
+        }
+        // Rodrigues' rotation formula
+        let axis = omega * (1.0 / angle);
+        let (sin, cos) = angle.sin_cos();
+        for p in &mut self.particles {
+            let r = p.center - self.center_of_mass;
+            let rotated = r * cos + axis.cross(&r) * sin + axis * (axis.dot(&r) * (1.0 - cos));
+            p.center = self.center_of_mass + rotated;
+        }
+    }
+
     fn merge_with(&mut self, other: Cluster) {
         self.particles.extend(other.particles);
+        self.ids.extend(other.ids);
+        self.generations.extend(other.generations);
+        for generation in &mut self.generations {
+            *generation += 1;
+        }
         self.update_properties();
     }
+}
 
-    fn bounding_radius(&self) -> f64 {
-        // Maximum distance from center to any particle edge
-        self.particles
-            .iter()
-            .map(|p| self.center_of_mass.distance_to(&p.center) + p.radius)
-            .fold(0.0, f64::max)
-    }
+/// Standard deviation of each component of the rotation vector of a cluster over one step.
+///
+/// Stokes-Einstein gives D_r / D_t = 3 / (4 Rm²) for a sphere of radius Rm,
+/// so a step whose displacement components deviate by `step_deviation`
+/// turns the cluster by `sqrt(3) / 2 step_deviation / Rm` about each axis.
+/// The mobility radius Rm is sqrt(5/3) Rg, as for the translation.
+fn rotation_deviation(step_deviation: f64, radius_of_gyration: f64) -> f64 {
+    let mobility_radius = (5.0f64 / 3.0).sqrt() * radius_of_gyration;
+    3.0f64.sqrt() / 2.0 * step_deviation / mobility_radius
 }
 
 /// Run CCA simulation.
@@ -162,8 +255,42 @@ impl Cluster {
 /// * `sintering_max` - Max for uniform distribution (default: 0.95)
 /// * `sintering_std` - Std dev for normal distribution (default: 0.05)
 /// * `seed` - Random seed for reproducibility
+/// * `sintering` - `PySinteringParams` object; overrides the `sintering_*` arguments when given
+/// * `on_merge` - Callable invoked as `on_merge(event)` with a dict describing the event each time
+///   two clusters merge; returning `False` stops the run early
+/// * `callback_every` - Only forward every N-th event to `on_merge` (default: 1)
+/// * `units` - `Units` giving the physical meaning of the lengths (radii in nm, ...);
+///   enables the physical quantities of the result
+/// * `size_distribution` - `SizeDistribution` the primary particle diameters are drawn from
+///   (lognormal, normal, histogram, ...); overrides `radius_min`/`radius_max` when given
+/// * `progress_callback` - Callable invoked as `progress_callback(info)` every `progress_every`
+///   merges, as in `run_dla`
+/// * `progress_every` - Merges between two progress reports (default: 100)
+/// * `cancel_event` - Object with an `is_set()` method that stops the run when set, as in `run_dla`
+/// * `existing_coords` - Coordinates (N x 3) of particles the cluster pool restarts from, e.g. a
+///   multi-agglomerate or cancelled run; they keep their positions (the box grows to hold them,
+///   cluster centers are wrapped into it), count towards `n_particles` and take the first IDs,
+///   and the remaining particles start as monomers
+/// * `existing_radii` - Radii (N) of the `existing_coords` particles
+/// * `existing_cluster_ids` - Cluster of each existing particle, as in
+///   `SimulationResult.cluster_ids` (default: all in one cluster)
+/// * `history` - `HistoryParams` to record a `GrowthHistory`, returned as
+///   `SimulationResult.history` (default: None, no history)
+/// * `sticking_model` - Sticking probability of a collision: "constant" (`sticking_probability`,
+///   default), "size" (`sticking_probability * (n_i n_j)^-sticking_exponent`, n being the cluster
+///   sizes) or "arrhenius" (`q = sticking_probability * exp(-activation_energy / RT)` at 298.15 K
+///   for each of the k particle pairs the clusters touch at, `1 - (1 - q)^k` in all).
+///   Low probabilities give reaction-limited aggregation (RLCA, Df ~ 2.1) instead of
+///   diffusion-limited (DLCA, Df ~ 1.8); `SimulationResult.kinetics` reports the collisions
+///   and the kernel homogeneity
+/// * `sticking_exponent` - Exponent of the "size" model (default: 0.5)
+/// * `activation_energy` - Activation energy of the "arrhenius" model in kJ/mol (default: 0)
+/// * `rotational_diffusion` - Also turn every cluster by a random small angle about its center of
+///   mass each step, with the Stokes-Einstein rotational mobility of its mobility radius, so
+///   clusters meet in random orientations; a turn that would push particles into another
+///   cluster is undone (default: False, clusters only translate)
 #[pyfunction]
-#[pyo3(signature = (n_particles, sticking_probability=1.0, radius_min=1.0, radius_max=None, box_size=100.0, single_agglomerate=true, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None))]
+#[pyo3(signature = (n_particles, sticking_probability=1.0, radius_min=1.0, radius_max=None, box_size=100.0, single_agglomerate=true, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None, sintering=None, on_merge=None, callback_every=1, units=None, size_distribution=None, progress_callback=None, progress_every=100, cancel_event=None, existing_coords=None, existing_radii=None, existing_cluster_ids=None, history=None, sticking_model="constant", sticking_exponent=0.5, activation_energy=0.0, rotational_diffusion=false))]
 pub fn run_cca(
     py: Python<'_>,
     n_particles: usize,
@@ -178,37 +305,188 @@ pub fn run_cca(
     sintering_max: f64,
     sintering_std: f64,
     seed: Option<u64>,
+    sintering: Option<PySinteringParams>,
+    on_merge: Option<PyObject>,
+    callback_every: usize,
+    units: Option<PyUnits>,
+    size_distribution: Option<PySizeDistribution>,
+    progress_callback: Option<PyObject>,
+    progress_every: usize,
+    cancel_event: Option<PyObject>,
+    existing_coords: Option<&Bound<'_, PyAny>>,
+    existing_radii: Option<&Bound<'_, PyAny>>,
+    existing_cluster_ids: Option<Vec<u32>>,
+    history: Option<PyHistoryParams>,
+    sticking_model: &str,
+    sticking_exponent: f64,
+    activation_energy: f64,
+    rotational_diffusion: bool,
 ) -> PyResult<PySimulationResult> {
     let seed = seed.unwrap_or_else(rand::random);
-    let radius_max = radius_max.unwrap_or(radius_min);
-
-    let sintering = match sintering_type.to_lowercase().as_str() {
-        "uniform" => SinteringDistribution::uniform(sintering_min, sintering_max),
-        "normal" => SinteringDistribution::normal(sintering_coeff, sintering_std),
-        _ => SinteringDistribution::fixed(sintering_coeff),
-    };
+    let existing = extract_existing(existing_coords, existing_radii)?;
+    let sticking = resolve_sticking(sticking_model, sticking_exponent, activation_energy, 298.15)?;
+
+    let mut warnings = Vec::new();
+    let sintering = resolve_sintering(
+        sintering.as_ref(),
+        sintering_coeff,
+        sintering_type,
+        sintering_min,
+        sintering_max,
+        sintering_std,
+        &mut warnings,
+    )?;
+    let (radius_min, radius_max, sizes) =
+        resolve_size_distribution(size_distribution.as_ref(), radius_min, radius_max, &mut warnings)?;
+    let initial_clusters = group_clusters(existing, existing_cluster_ids)?;
 
     let params = CcaParams {
         n_particles,
         sticking_probability,
+        sticking,
         radius_min,
         radius_max,
+        sizes,
         box_size,
         single_agglomerate,
         sintering,
+        initial_clusters,
+        rotational_diffusion,
         ..Default::default()
     };
+    params.validate()?;
+    let parameters = run_parameters("cca", &params, json!({"seed": seed, "units": units, "history": history}));
 
+    let mut hooks = PyCallbacks::new(None, on_merge, callback_every)?
+        .with_progress(progress_callback, progress_every)?
+        .with_cancel_event(cancel_event)
+        .with_history(history)?;
     // Release GIL during computation
-    let result = py.allow_threads(|| run_cca_internal(params, seed));
+    let result = allow_threads(py, || run_cca_internal(params, seed, &mut hooks));
+    let history = hooks.take_history();
+    hooks.finish(&mut warnings)?;
+
+    result
+        .into_py(py, warnings)
+        .map(|r| r.with_units(units).with_history(history).with_parameters(parameters))
+}
+
+/// Run CCA simulation with physical Brownian dynamics.
+///
+/// Clusters diffuse in a gas with the Stokes-Einstein coefficient of their
+/// mobility radius (sqrt(5/3) Rg), D = kT Cc / (6 pi mu Rm), Cc being the
+/// Cunningham slip correction, instead of the abstract steps of `run_cca`.
+/// Every step advances a physical clock, so the cluster count over time
+/// (`SimulationResult.brownian_report`) can be compared with aerosol
+/// coagulation experiments.
+///
+/// # Arguments
+/// * `n_particles` - Number of particles
+/// * `brownian` - `BrownianParams` with the temperature, pressure, gas viscosity, mean free path,
+///   particle density and time step (default: air at 298.15 K and 1 atm, soot particles)
+/// * `units` - `Units` of the radii and box size (default: nm)
+/// * `radius_min` - Minimum particle radius
+/// * `radius_max` - Maximum particle radius (defaults to radius_min for monodisperse)
+/// * `box_size` - Size of the periodic simulation box; sets the number concentration
+/// * `sticking_probability` - Probability of adhesion on contact (0-1)
+/// * `single_agglomerate` - If true (default), iterate until ONE agglomerate forms
+/// * `sintering` - `PySinteringParams` object (default: no sintering)
+/// * `size_distribution` - `SizeDistribution` the primary particle diameters are drawn from;
+///   overrides `radius_min`/`radius_max` when given
+/// * `seed` - Random seed for reproducibility
+/// * `on_merge` - Callable invoked as `on_merge(event)` each time two clusters merge, as in `run_cca`
+/// * `callback_every` - Only forward every N-th event to `on_merge` (default: 1)
+/// * `progress_callback` - Callable invoked as `progress_callback(info)` every `progress_every`
+///   merges, as in `run_dla`
+/// * `progress_every` - Merges between two progress reports (default: 100)
+/// * `cancel_event` - Object with an `is_set()` method that stops the run when set, as in `run_dla`
+/// * `history` - `HistoryParams` to record a `GrowthHistory` (default: None, no history)
+/// * `sticking_model` - "constant", "size" or "arrhenius", as in `run_cca`; the Arrhenius factor
+///   is taken at the gas temperature (default: "constant")
+/// * `sticking_exponent` - Exponent of the "size" model (default: 0.5)
+/// * `activation_energy` - Activation energy of the "arrhenius" model in kJ/mol (default: 0)
+/// * `rotational_diffusion` - Also rotate the clusters with their rotational diffusion
+///   coefficient, as in `run_cca` (default: False)
+///
+/// # Returns
+/// * `SimulationResult` whose `brownian_report` holds the simulated time, the time step, the
+///   monomer diffusion coefficient and Knudsen number and the coagulation time constant
+#[pyfunction]
+#[pyo3(signature = (n_particles, brownian=None, units=None, radius_min=1.0, radius_max=None, box_size=100.0, sticking_probability=1.0, single_agglomerate=true, sintering=None, size_distribution=None, seed=None, on_merge=None, callback_every=1, progress_callback=None, progress_every=100, cancel_event=None, history=None, sticking_model="constant", sticking_exponent=0.5, activation_energy=0.0, rotational_diffusion=false))]
+pub fn run_cca_physical(
+    py: Python<'_>,
+    n_particles: usize,
+    brownian: Option<PyBrownianParams>,
+    units: Option<PyUnits>,
+    radius_min: f64,
+    radius_max: Option<f64>,
+    box_size: f64,
+    sticking_probability: f64,
+    single_agglomerate: bool,
+    sintering: Option<PySinteringParams>,
+    size_distribution: Option<PySizeDistribution>,
+    seed: Option<u64>,
+    on_merge: Option<PyObject>,
+    callback_every: usize,
+    progress_callback: Option<PyObject>,
+    progress_every: usize,
+    cancel_event: Option<PyObject>,
+    history: Option<PyHistoryParams>,
+    sticking_model: &str,
+    sticking_exponent: f64,
+    activation_energy: f64,
+    rotational_diffusion: bool,
+) -> PyResult<PySimulationResult> {
+    let seed = seed.unwrap_or_else(rand::random);
+    let units = match units {
+        Some(units) => units,
+        None => PyUnits::new("nm", None)?,
+    };
+    let brownian = brownian.unwrap_or_default();
+    let sticking = resolve_sticking(sticking_model, sticking_exponent, activation_energy, brownian.temperature)?;
+
+    let mut warnings = Vec::new();
+    let sintering = resolve_sintering(sintering.as_ref(), 1.0, "fixed", 0.85, 0.95, 0.05, &mut warnings)?;
+    let (radius_min, radius_max, sizes) =
+        resolve_size_distribution(size_distribution.as_ref(), radius_min, radius_max, &mut warnings)?;
 
-    Ok(result.to_py())
+    let params = CcaParams {
+        n_particles,
+        sticking_probability,
+        sticking,
+        radius_min,
+        radius_max,
+        sizes,
+        box_size,
+        single_agglomerate,
+        sintering,
+        brownian: Some(brownian),
+        metres_per_unit: units.to_nm(1.0) * 1e-9,
+        rotational_diffusion,
+        ..Default::default()
+    };
+    params.validate()?;
+    let parameters = run_parameters("cca_physical", &params, json!({"seed": seed, "units": units, "history": history}));
+
+    let mut hooks = PyCallbacks::new(None, on_merge, callback_every)?
+        .with_progress(progress_callback, progress_every)?
+        .with_cancel_event(cancel_event)
+        .with_history(history)?;
+    let result = allow_threads(py, || run_cca_internal(params, seed, &mut hooks));
+    let history = hooks.take_history();
+    hooks.finish(&mut warnings)?;
+
+    result
+        .into_py(py, warnings)
+        .map(|r| r.with_units(Some(units)).with_history(history).with_parameters(parameters))
 }
 
 /// Internal CCA implementation.
-fn run_cca_internal(params: CcaParams, seed: u64) -> SimulationResult {
+pub(crate) fn run_cca_internal(params: CcaParams, seed: u64, hooks: &mut dyn EventHooks) -> SimulationResult {
     let start_time = Instant::now();
     let mut rng = create_rng(seed);
+    let radii = params.radius_sampler();
+    let mut warnings = Vec::new();
 
     // Calculate optimal box size to ensure reasonable convergence
     // Target ~3% volume fraction for good performance
@@ -221,24 +499,76 @@ fn run_cca_internal(params: CcaParams, seed: u64) -> SimulationResult {
     } else {
         params.box_size
     };
+    if effective_box_size < params.box_size {
+        warnings.push(format!(
+            "box_size {} reduced to {:.2} (about 3% volume fraction) so a single agglomerate can form",
+            params.box_size, effective_box_size
+        ));
+    }
+    // A restarted pool must fit in the box, or wrapping would overlap its clusters
+    let pool_reach = params
+        .initial_clusters
+        .iter()
+        .flatten()
+        .map(|p| p.center.x.abs().max(p.center.y.abs()).max(p.center.z.abs()) + p.radius)
+        .fold(0.0, f64::max);
+    let effective_box_size = effective_box_size.max(2.0 * pool_reach);
+
+    // Clusters of a restarted pool keep their positions, wrapped into the box
+    let mut clusters: Vec<Cluster> = Vec::with_capacity(params.n_particles);
+    let mut next_id = 0u32;
+    for particles in &params.initial_clusters {
+        let mut cluster = Cluster::from_particles(particles.clone(), next_id, clusters.len() as u32);
+        let mut wrapped_center = cluster.center_of_mass;
+        apply_pbc(&mut wrapped_center, effective_box_size);
+        cluster.translate(wrapped_center - cluster.center_of_mass);
+        next_id += particles.len() as u32;
+        clusters.push(cluster);
+    }
 
-    // Initialize all particles as individual clusters randomly distributed
-    // Each particle gets a random radius if polydisperse
-    let mut clusters: Vec<Cluster> = (0..params.n_particles)
-        .map(|_| {
+    // Initialize the other particles as individual clusters randomly distributed,
+    // clear of the restarted clusters. Each particle gets a random radius if polydisperse
+    let max_radius = params.size_distribution().diameter_range().1 / 2.0;
+    let occupied = pool_occupancy(clusters.iter().flat_map(|c| &c.particles), effective_box_size, max_radius);
+    let mut overlapping = 0;
+    for id in next_id..params.n_particles as u32 {
+        let (monomer, clear) = draw_clear_monomer(&occupied, || {
             let x = (rng.gen::<f64>() - 0.5) * effective_box_size;
             let y = (rng.gen::<f64>() - 0.5) * effective_box_size;
             let z = (rng.gen::<f64>() - 0.5) * effective_box_size;
-            let radius = params.random_radius(&mut rng);
-            Cluster::new(Sphere::new(Vector3::new(x, y, z), radius))
-        })
-        .collect();
+            Sphere::new(Vector3::new(x, y, z), radii.sample_radius(&mut rng))
+        });
+        overlapping += usize::from(!clear);
+        let mut cluster = Cluster::new(monomer, id);
+        cluster.label = clusters.len() as u32;
+        clusters.push(cluster);
+    }
+    if overlapping > 0 {
+        warnings.push(overlapping_monomers_warning(overlapping));
+    }
 
     // Track Rg evolution (of the largest cluster)
     let mut rg_evolution = Vec::new();
     let mut n_values = Vec::new();
+    let mut lineage = Lineage::new(clusters.len());
+    let mut kinetics = KineticsRecorder::new(params.sticking, params.n_particles);
 
     let step_size = params.mean_radius() * params.step_size_factor;
+    let mut brownian = params.brownian.as_ref().map(|b| {
+        BrownianDynamics::new(
+            b,
+            params.metres_per_unit,
+            params.mean_radius(),
+            effective_box_size,
+            params.n_particles,
+            &mut warnings,
+        )
+    });
+    let max_radius = clusters
+        .iter()
+        .flat_map(|c| &c.particles)
+        .map(|p| p.radius)
+        .fold(0.0, f64::max);
 
     // Iterate until only one cluster remains (single_agglomerate mode)
     // or max iterations reached (multi-agglomerate mode)
@@ -263,14 +593,32 @@ fn run_cca_internal(params: CcaParams, seed: u64) -> SimulationResult {
 
         iteration += 1;
 
-        // Move all clusters with Brownian motion
-        for cluster in &mut clusters {
-            let (dx, dy, dz) = random_direction(&mut rng);
-            // Smaller clusters move faster (diffusion coefficient ~ 1/Rg)
-            // Use sqrt for more realistic diffusion scaling
-            let mobility = 1.0 / (1.0 + cluster.radius_of_gyration.sqrt());
-            let delta = Vector3::new(dx * step_size * mobility, dy * step_size * mobility, dz * step_size * mobility);
+        // Move all clusters with Brownian motion, remembering the rotation of each
+        let mut rotations = vec![None; clusters.len()];
+        for (cluster, rotation) in clusters.iter_mut().zip(&mut rotations) {
+            let (delta, deviation) = match &brownian {
+                Some(dynamics) => (
+                    dynamics.displacement(cluster.radius_of_gyration, &mut rng),
+                    dynamics.step_deviation(cluster.radius_of_gyration),
+                ),
+                None => {
+                    let (dx, dy, dz) = random_direction(&mut rng);
+                    // Smaller clusters move faster (diffusion coefficient ~ 1/Rg)
+                    // Use sqrt for more realistic diffusion scaling
+                    let mobility = 1.0 / (1.0 + cluster.radius_of_gyration.sqrt());
+                    let length = step_size * mobility;
+                    (Vector3::new(dx * length, dy * length, dz * length), length / 3.0f64.sqrt())
+                }
+            };
             cluster.translate(delta);
+            // A lone sphere looks the same however it turns
+            if params.rotational_diffusion && cluster.particles.len() > 1 {
+                let sigma = rotation_deviation(deviation, cluster.radius_of_gyration);
+                let mut component = || sigma * rng.sample::<f64, _>(StandardNormal);
+                let omega = Vector3::new(component(), component(), component());
+                cluster.rotate(omega);
+                *rotation = Some(omega);
+            }
 
             // Apply periodic boundary conditions only to cluster center
             // Don't apply PBC to individual particles to maintain connectivity
@@ -284,45 +632,37 @@ fn run_cca_internal(params: CcaParams, seed: u64) -> SimulationResult {
             }
         }
 
-        // Check for collisions between clusters
-        let mut merged_indices: Vec<usize> = Vec::new();
+        // Check for collisions between clusters: only pairs with particles
+        // within their unsintered contact distance can collide
+        let mut candidates = contact_candidates(&clusters, effective_box_size, max_radius);
+        if undo_overlapping_rotations(&mut clusters, &rotations, &candidates, params.sintering.minimum()) {
+            candidates = contact_candidates(&clusters, effective_box_size, max_radius);
+        }
+        let mut merged = vec![false; clusters.len()];
         let mut merges: Vec<(usize, usize)> = Vec::new();
 
-        for i in 0..clusters.len() {
-            if merged_indices.contains(&i) {
+        for ((i, j), contact) in candidates {
+            if merged[i] || merged[j] {
                 continue;
             }
 
-            for j in (i + 1)..clusters.len() {
-                if merged_indices.contains(&j) {
-                    continue;
-                }
-
-                // Quick bounding check using periodic distance
-                let dist = periodic_distance(
-                    &clusters[i].center_of_mass,
-                    &clusters[j].center_of_mass,
-                    effective_box_size,
-                );
-                let max_dist = clusters[i].bounding_radius() + clusters[j].bounding_radius();
-
-                if dist < max_dist {
-                    // Sample sintering coefficient for this potential merge
-                    let sintering_coeff = params.sintering.sample(&mut rng);
-                    // Detailed particle-level collision check with PBC and sintering
-                    if check_cluster_collision_pbc(&clusters[i], &clusters[j], effective_box_size, sintering_coeff) {
-                        if params.sticking_probability >= 1.0
-                            || rng.gen::<f64>() < params.sticking_probability
-                        {
-                            merges.push((i, j));
-                            merged_indices.push(j);
-                        }
-                    }
+            // Sample sintering coefficient for this potential merge
+            let sintering_coeff = params.sintering.sample(&mut rng);
+            // Use relative epsilon for robust comparison
+            if contact.closest <= sintering_coeff * (1.0 + 1e-10) {
+                let sizes = (clusters[i].particles.len(), clusters[j].particles.len());
+                let probability = params.sticking.probability(params.sticking_probability, sizes, contact.pairs);
+                let stuck = probability >= 1.0 || rng.gen::<f64>() < probability;
+                kinetics.collision((clusters[i].label, clusters[j].label), probability, stuck);
+                if stuck {
+                    merges.push((i, j));
+                    merged[j] = true;
                 }
             }
         }
 
         // Perform merges - sort by j descending to maintain valid indices during removal
+        let mut stop = false;
         merges.sort_by(|a, b| b.1.cmp(&a.1));
         for (i, j) in merges {
             // Ensure indices are still valid (defensive check)
@@ -340,23 +680,70 @@ fn run_cca_internal(params: CcaParams, seed: u64) -> SimulationResult {
                     if delta.length_squared() > 1e-10 {
                         cluster_j.translate(delta);
                     }
+                    let merge = lineage.record(
+                        iteration,
+                        (clusters[adjusted_i].label, clusters[adjusted_i].particles.len()),
+                        (cluster_j.label, cluster_j.particles.len()),
+                    );
                     clusters[adjusted_i].merge_with(cluster_j);
+                    clusters[adjusted_i].label = merge.merged;
+
+                    let event =
+                        ClusterMergeEvent::new(merge, clusters[adjusted_i].center_of_mass, clusters.len());
+                    if hooks.on_merge(&event) == Flow::Stop {
+                        stop = true;
+                        break;
+                    }
                 }
             }
         }
 
+        kinetics.clusters(iteration, clusters.len());
+        if let Some(dynamics) = brownian.as_mut() {
+            dynamics.advance(clusters.len());
+        }
+
         // Track largest cluster
         if let Some(largest) = clusters.iter().max_by_key(|c| c.particles.len()) {
             rg_evolution.push(largest.radius_of_gyration);
             n_values.push(largest.particles.len());
+            let progress = ProgressEvent::merges(
+                lineage.n_merges(),
+                largest.particles.len(),
+                params.n_particles,
+                clusters.len(),
+                largest.radius_of_gyration,
+                start_time,
+            );
+            if hooks.wants_snapshot(&progress) {
+                let pool = clusters.iter().map(|c| c.particles.as_slice());
+                hooks.on_snapshot(Snapshot::of_clusters(progress.step, pool));
+            }
+            stop = stop || hooks.on_progress(&progress) == Flow::Stop;
+        }
+
+        if stop {
+            break;
         }
     }
 
-    // Collect all particles from all clusters (merge remaining if needed)
-    let mut final_particles: Vec<Sphere> = Vec::new();
-    for cluster in clusters {
-        final_particles.extend(cluster.particles);
+    // Collect all particles from all clusters (merge remaining if needed),
+    // remembering which cluster each one came from, and restore creation order
+    let mut all_ids = Vec::with_capacity(params.n_particles);
+    let mut all_cluster_ids = Vec::with_capacity(params.n_particles);
+    let mut all_generations = Vec::with_capacity(params.n_particles);
+    let mut all_particles = Vec::with_capacity(params.n_particles);
+    for (cluster_id, cluster) in clusters.into_iter().enumerate() {
+        all_cluster_ids.extend(std::iter::repeat_n(cluster_id as u32, cluster.particles.len()));
+        all_ids.extend(cluster.ids);
+        all_generations.extend(cluster.generations);
+        all_particles.extend(cluster.particles);
     }
+    let order = creation_order(&all_ids);
+    let ids = reorder(&all_ids, &order);
+    let cluster_ids = reorder(&all_cluster_ids, &order);
+    let generations = reorder(&all_generations, &order);
+    let final_particles: Vec<Sphere> = reorder(&all_particles, &order);
 
     // Calculate final metrics
     let coords: Vec<[f64; 3]> = final_particles
@@ -365,9 +752,10 @@ fn run_cca_internal(params: CcaParams, seed: u64) -> SimulationResult {
         .collect();
     let radii: Vec<f64> = final_particles.iter().map(|s| s.radius).collect();
 
-    let (df, kf, _r2) = calculate_fractal_dimension(&n_values, &rg_evolution);
+    let fit = calculate_fractal_dimension(&n_values, &rg_evolution, &mut warnings);
     let porosity = calculate_porosity(&coords, &radii);
-    let coordination = calculate_coordination(&coords, &radii, params.mean_radius() * 0.1);
+    let contacts = calculate_contacts(&coords, &radii, params.mean_radius() * 0.1);
+    let coordination = coordination_from_contacts(&contacts, coords.len());
     let inertia = calculate_inertia_tensor(&coords, &radii);
 
     let coord_mean = coordination.iter().map(|&c| c as f64).sum::<f64>() / coordination.len().max(1) as f64;
@@ -388,19 +776,38 @@ fn run_cca_internal(params: CcaParams, seed: u64) -> SimulationResult {
         coordinates: coords,
         radii,
         rg_evolution,
-        fractal_dimension: df,
-        fractal_dimension_std: 0.02,
-        prefactor: kf,
+        n_evolution: n_values,
+        fractal_dimension: fit.df,
+        fractal_dimension_std: fit.df_std_error,
+        prefactor: fit.kf,
+        linear_region: fit.linear_region,
+        evolution_fit: EvolutionFit::default(),
+        fit_r_squared: fit.r_squared,
+        fit_n_points: fit.n_points,
         porosity,
         coordination_mean: coord_mean,
         coordination_std: coord_std,
         execution_time_ms,
         seed,
+        child_seeds: Vec::new(),
         anisotropy: inertia.anisotropy,
         asphericity: inertia.asphericity,
         acylindricity: inertia.acylindricity,
         principal_moments: inertia.principal_moments,
         principal_axes: inertia.principal_axes,
+        ids,
+        cluster_ids,
+        coordination,
+        contacts,
+        generations,
+        merge_history: lineage.into_events(),
+        warnings,
+        target_report: None,
+        brownian_report: brownian.map(BrownianDynamics::finish),
+        kinetics: Some(kinetics.finish()),
+        film: None,
+        drift_report: None,
+        merge_diagnostics: None,
     }
 }
 
@@ -440,8 +847,8 @@ fn unwrap_periodic_offset(a: &Vector3, b: &Vector3, box_size: f64) -> Vector3 {
     Vector3::new(target_x - b.x, target_y - b.y, target_z - b.z)
 }
 
-/// Calculate periodic distance between two points.
-fn periodic_distance(a: &Vector3, b: &Vector3, box_size: f64) -> f64 {
+/// Calculate the squared periodic distance between two points.
+fn periodic_distance_squared(a: &Vector3, b: &Vector3, box_size: f64) -> f64 {
     let mut dx = (a.x - b.x).abs();
     let mut dy = (a.y - b.y).abs();
     let mut dz = (a.z - b.z).abs();
@@ -457,26 +864,80 @@ fn periodic_distance(a: &Vector3, b: &Vector3, box_size: f64) -> f64 {
         dz = box_size - dz;
     }
 
-    (dx * dx + dy * dy + dz * dz).sqrt()
+    dx * dx + dy * dy + dz * dz
 }
 
-/// Check if any particle in cluster A touches any particle in cluster B.
-/// Uses periodic boundary conditions for distance calculation.
-/// sintering_coeff controls how close particles must be to "collide" (0.5-1.0)
-fn check_cluster_collision_pbc(a: &Cluster, b: &Cluster, box_size: f64, sintering_coeff: f64) -> bool {
-    for pa in &a.particles {
-        for pb in &b.particles {
-            let dist = periodic_distance(&pa.center, &pb.center, box_size);
-            // Use sintered contact distance for collision detection
-            let contact_dist = sintered_contact_distance(pa.radius, pb.radius, sintering_coeff);
-            // Use relative epsilon for robust comparison
-            let epsilon = contact_dist.max(dist) * 1e-10 + 1e-14;
-            if dist <= contact_dist + epsilon {
-                return true;
+/// Two clusters with particles closer than their unsintered contact distance.
+#[derive(Debug, Clone, Copy)]
+struct Contact {
+    /// Smallest particle distance relative to the unsintered contact
+    /// distance: the clusters collide with sintering coefficient s when it is
+    /// at most s
+    closest: f64,
+    /// Particle pairs within their unsintered contact distance
+    pairs: usize,
+}
+
+/// Pairs of clusters `(i, j)`, i < j, with particles closer than their
+/// unsintered contact distance, sorted by index, with their `Contact`.
+///
+/// Particles are binned in a cell list over the periodic box, which keeps the
+/// search linear in the number of particles instead of scanning every cluster
+/// pair.
+fn contact_candidates(clusters: &[Cluster], box_size: f64, max_radius: f64) -> Vec<((usize, usize), Contact)> {
+    let owners: Vec<(usize, &Sphere)> = clusters
+        .iter()
+        .enumerate()
+        .flat_map(|(c, cluster)| cluster.particles.iter().map(move |p| (c, p)))
+        .collect();
+    let centers: Vec<Vector3> = owners.iter().map(|(_, p)| p.center).collect();
+    let cells = PeriodicCellList::new(&centers, box_size, 2.0 * max_radius);
+
+    let mut contacts: HashMap<(usize, usize), Contact> = HashMap::new();
+    for &(ca, pa) in &owners {
+        for b in cells.neighbours(&pa.center) {
+            let (cb, pb) = owners[b];
+            // Each particle pair once, from the cluster with the lower index
+            if cb <= ca {
+                continue;
+            }
+            let contact_dist = sintered_contact_distance(pa.radius, pb.radius, 1.0);
+            let ratio = periodic_distance_squared(&pa.center, &pb.center, box_size).sqrt() / contact_dist;
+            if ratio <= 1.0 + 1e-10 {
+                let contact = contacts.entry((ca, cb)).or_insert(Contact { closest: ratio, pairs: 0 });
+                contact.closest = contact.closest.min(ratio);
+                contact.pairs += 1;
             }
         }
     }
-    false
+
+    let mut pairs: Vec<_> = contacts.into_iter().collect();
+    pairs.sort_unstable_by_key(|&(pair, _)| pair);
+    pairs
+}
+
+/// Undo the rotation of every cluster in a contact pair closer than `min_sintering`,
+/// which no collision could have sintered: a turning cluster must not swing its
+/// particles into another cluster. Returns whether any rotation was undone.
+fn undo_overlapping_rotations(
+    clusters: &mut [Cluster],
+    rotations: &[Option<Vector3>],
+    candidates: &[((usize, usize), Contact)],
+    min_sintering: f64,
+) -> bool {
+    let mut undone = vec![false; clusters.len()];
+    for &((i, j), contact) in candidates {
+        if contact.closest < min_sintering * (1.0 - 1e-10) {
+            for k in [i, j] {
+                if let (Some(omega), false) = (rotations[k], undone[k]) {
+                    // Rotating about the center of mass commutes with the translation
+                    clusters[k].rotate(omega * -1.0);
+                    undone[k] = true;
+                }
+            }
+        }
+    }
+    undone.contains(&true)
 }
 
 /// Apply periodic boundary conditions.
@@ -502,6 +963,7 @@ fn apply_pbc(pos: &mut Vector3, box_size: f64) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::simulation::hooks::NoHooks;
 
     #[test]
     fn test_cca_deterministic() {
@@ -512,8 +974,8 @@ mod tests {
             ..Default::default()
         };
 
-        let r1 = run_cca_internal(params.clone(), 42);
-        let r2 = run_cca_internal(params, 42);
+        let r1 = run_cca_internal(params.clone(), 42, &mut NoHooks);
+        let r2 = run_cca_internal(params, 42, &mut NoHooks);
 
         assert_eq!(r1.coordinates.len(), r2.coordinates.len());
     }
@@ -528,7 +990,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = run_cca_internal(params, 123);
+        let result = run_cca_internal(params, 123, &mut NoHooks);
 
         // Should have all particles
         assert_eq!(result.coordinates.len(), 50);
@@ -538,6 +1000,20 @@ mod tests {
         assert!(result.coordination_mean > 0.5, "Particles should be connected");
     }
 
+    #[test]
+    fn test_cca_box_override_warns() {
+        let params = CcaParams {
+            n_particles: 20,
+            box_size: 1000.0,
+            single_agglomerate: true,
+            ..Default::default()
+        };
+
+        let result = run_cca_internal(params, 42, &mut NoHooks);
+
+        assert!(result.warnings.iter().any(|w| w.contains("box_size")));
+    }
+
     #[test]
     fn test_cca_multi_agglomerate() {
         // Test that single_agglomerate=false can produce multiple clusters
@@ -549,10 +1025,232 @@ mod tests {
             ..Default::default()
         };
 
-        let result = run_cca_internal(params, 456);
+        let result = run_cca_internal(params, 456, &mut NoHooks);
 
         // Should still have all particles
         assert_eq!(result.coordinates.len(), 30);
+
+        // Per-particle columns stay aligned with the coordinates
+        assert_eq!(result.cluster_ids.len(), 30);
+        assert_eq!(result.coordination.len(), 30);
+
+        // Particles come back in creation order whatever the merge history
+        assert_eq!(result.ids, (0..30).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_cca_restarts_from_cluster_pool() {
+        let params = CcaParams {
+            n_particles: 30,
+            max_iterations: 100,
+            single_agglomerate: false,
+            ..Default::default()
+        };
+        let partial = run_cca_internal(params, 456, &mut NoHooks);
+        let n_pool = partial.cluster_ids.iter().max().unwrap() + 1;
+        let mut initial_clusters = vec![Vec::new(); n_pool as usize];
+        for ((c, &r), &k) in partial.coordinates.iter().zip(&partial.radii).zip(&partial.cluster_ids) {
+            initial_clusters[k as usize].push(Sphere::new(Vector3::new(c[0], c[1], c[2]), r));
+        }
+
+        // The pool plus 10 new monomers grows into one agglomerate
+        let params = CcaParams {
+            n_particles: 40,
+            initial_clusters,
+            ..Default::default()
+        };
+        let result = run_cca_internal(params, 7, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 40);
+        assert!(result.cluster_ids.iter().all(|&k| k == 0));
+        assert_eq!(result.merge_history.len(), n_pool as usize + 10 - 1);
+    }
+
+    #[test]
+    fn test_cca_physical_time() {
+        // 15 nm soot monomers in air: Kn = 65/15, Cc = 7.79, D = kT Cc / (6 pi mu r)
+        let brownian = PyBrownianParams::new(298.15, 101_325.0, Some(1.8e-5), Some(6.5e-8), 1800.0, None).unwrap();
+        let params = CcaParams {
+            n_particles: 30,
+            radius_min: 15.0,
+            radius_max: 15.0,
+            box_size: 300.0,
+            brownian: Some(brownian),
+            ..Default::default()
+        };
+        let result = run_cca_internal(params, 7, &mut NoHooks);
+        let report = result.brownian_report.expect("physical runs report their time scales");
+
+        assert!((report.monomer_slip_correction - 7.7918).abs() < 1e-3);
+        assert!((report.monomer_diffusion / 6.3022e-9 - 1.0).abs() < 1e-3);
+        // The default step lets a monomer diffuse one radius
+        assert!(((6.0 * report.monomer_diffusion * report.time_step).sqrt() - 15e-9).abs() < 1e-15);
+        assert_eq!(report.n_clusters.first(), Some(&30));
+        assert_eq!(report.n_clusters.last(), Some(&1));
+        assert!(report.times.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(report.times.last(), Some(&report.simulated_time));
+        assert!(report.coagulation_time > 0.0);
+    }
+
+    #[test]
+    fn test_cca_rotational_diffusion() {
+        // A rotation keeps the cluster rigid and its center of mass in place
+        let spheres = vec![
+            Sphere::new(Vector3::new(0.0, 0.0, 0.0), 1.0),
+            Sphere::new(Vector3::new(2.0, 0.0, 0.0), 1.0),
+            Sphere::new(Vector3::new(2.0, 2.0, 0.0), 1.0),
+        ];
+        let mut cluster = Cluster::from_particles(spheres.clone(), 0, 0);
+        let (center, rg) = (cluster.center_of_mass, cluster.radius_of_gyration);
+        cluster.rotate(Vector3::new(0.3, -0.2, 0.5));
+        for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+            let before = spheres[a].center.distance_to(&spheres[b].center);
+            let after = cluster.particles[a].center.distance_to(&cluster.particles[b].center);
+            assert!((before - after).abs() < 1e-12);
+        }
+        cluster.update_properties();
+        assert!(cluster.center_of_mass.distance_to(&center) < 1e-12);
+        assert!((cluster.radius_of_gyration - rg).abs() < 1e-12);
+        // A monomer-sized cluster turns by about a radian when it moves one radius
+        let deviation = rotation_deviation(1.0 / 3.0f64.sqrt(), (3.0f64 / 5.0).sqrt());
+        assert!((deviation - 0.5).abs() < 1e-12);
+
+        // A turn swinging a particle into another cluster is undone, one clear of it is kept
+        let mut clusters = vec![
+            Cluster::from_particles(spheres[..2].to_vec(), 0, 0),
+            Cluster::new(Sphere::new(Vector3::new(1.0, 2.0, 0.0), 1.0), 2),
+        ];
+        let quarter_turn = Vector3::new(0.0, 0.0, std::f64::consts::FRAC_PI_2);
+        clusters[0].rotate(quarter_turn);
+        let candidates = contact_candidates(&clusters, 100.0, 1.0);
+        assert!(candidates[0].1.closest < 0.6);
+        assert!(undo_overlapping_rotations(&mut clusters, &[Some(quarter_turn), None], &candidates, 1.0));
+        assert!(clusters[0].particles[1].center.distance_to(&spheres[1].center) < 1e-12);
+        assert!(contact_candidates(&clusters, 100.0, 1.0).iter().all(|&(_, contact)| contact.closest >= 1.0));
+        let small_turn = Vector3::new(0.0, 0.0, 0.1);
+        clusters[0].rotate(small_turn);
+        let candidates = contact_candidates(&clusters, 100.0, 1.0);
+        assert!(!undo_overlapping_rotations(&mut clusters, &[Some(small_turn), None], &candidates, 1.0));
+
+        // Rotation changes every agglomerate of an ensemble but keeps the DLCA
+        // dimension, fitted to the mean ln Rg of 8 seeds at N = 20, 40, 80
+        let ensemble_df = |rotational_diffusion: bool| {
+            let points: Vec<(f64, f64)> = [20usize, 40, 80]
+                .iter()
+                .map(|&n| {
+                    let mean_ln_rg = (0..8)
+                        .map(|seed| {
+                            let params = CcaParams {
+                                n_particles: n,
+                                box_size: 12.0 * (n as f64).cbrt(),
+                                rotational_diffusion,
+                                ..Default::default()
+                            };
+                            let result = run_cca_internal(params, seed, &mut NoHooks);
+                            assert!(result.cluster_ids.iter().all(|&k| k == 0));
+                            calculate_radius_of_gyration(&result.coordinates, &result.radii).ln()
+                        })
+                        .sum::<f64>()
+                        / 8.0;
+                    ((n as f64).ln(), mean_ln_rg)
+                })
+                .collect();
+            let mean_x = points.iter().map(|p| p.0).sum::<f64>() / 3.0;
+            let mean_y = points.iter().map(|p| p.1).sum::<f64>() / 3.0;
+            let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+            let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+            sxx / sxy
+        };
+        let (plain, rotating) = (ensemble_df(false), ensemble_df(true));
+        assert!((1.6..2.2).contains(&plain), "translating Df {}", plain);
+        assert!((1.6..2.2).contains(&rotating), "rotating Df {}", rotating);
+        assert!(rotating != plain && (rotating - plain).abs() < 0.3);
+        let params = CcaParams {
+            n_particles: 20,
+            box_size: 30.0,
+            ..Default::default()
+        };
+        let rotating = CcaParams {
+            rotational_diffusion: true,
+            ..params.clone()
+        };
+        for seed in 0..4 {
+            let plain = run_cca_internal(params.clone(), seed, &mut NoHooks);
+            assert_ne!(run_cca_internal(rotating.clone(), seed, &mut NoHooks).coordinates, plain.coordinates);
+        }
+    }
+
+    #[test]
+    fn test_cca_reaction_limited_sticking() {
+        let size = StickingModel::SizeDependent { exponent: 0.5 };
+        assert!((size.probability(1.0, (4, 9), 1) - 1.0 / 6.0).abs() < 1e-12);
+        // Ea = RT ln 2 halves the base probability of every touching pair
+        let arrhenius = resolve_sticking("arrhenius", 0.5, 8.314462618e-3 * 300.0 * 2f64.ln(), 300.0).unwrap();
+        assert!((arrhenius.probability(0.8, (1, 1), 1) - 0.4).abs() < 1e-12);
+        assert!((arrhenius.probability(0.8, (5, 7), 2) - 0.64).abs() < 1e-12);
+        assert_eq!(StickingModel::Constant.probability(0.8, (5, 7), 2), 0.8);
+        assert!(resolve_sticking("sticky", 0.5, 0.0, 300.0).is_err());
+
+        // Draws of clusters that stay in touch retry one collision, rejected only if none sticks
+        let mut kinetics = KineticsRecorder::new(StickingModel::Constant, 10);
+        kinetics.collision((3, 1), 0.2, false);
+        kinetics.clusters(1, 10);
+        kinetics.collision((1, 3), 0.2, false);
+        kinetics.collision((4, 5), 0.2, false);
+        kinetics.clusters(2, 10);
+        kinetics.collision((1, 3), 0.2, true);
+        kinetics.clusters(3, 9);
+        kinetics.collision((2, 6), 0.2, false);
+        let kinetics = kinetics.finish();
+        assert_eq!((kinetics.collisions, kinetics.rejected_collisions), (3, 2));
+
+        let params = CcaParams {
+            n_particles: 40,
+            box_size: 20.0,
+            sticking_probability: 0.2,
+            ..Default::default()
+        };
+        let result = run_cca_internal(params, 3, &mut NoHooks);
+        let kinetics = result.kinetics.expect("CCA reports its kinetics");
+        // Every collision either merged or bounced off
+        assert_eq!(kinetics.collisions - kinetics.rejected_collisions, result.merge_history.len());
+        assert!(kinetics.rejected_collisions > 0);
+        assert!((kinetics.mean_sticking_probability - 0.2).abs() < 1e-12);
+
+        let params = CcaParams {
+            n_particles: 40,
+            box_size: 20.0,
+            ..Default::default()
+        };
+        let kinetics = run_cca_internal(params, 3, &mut NoHooks).kinetics.unwrap();
+        assert_eq!(kinetics.rejected_collisions, 0);
+    }
+
+    #[test]
+    fn test_cca_reaction_limited_dimension() {
+        // Clusters that bounce off many times explore their contacts before
+        // sticking: RLCA grows denser agglomerates than DLCA. Df of the
+        // ensemble at N = 40 with the prefactor fixed, N = kf Rg^Df for unit
+        // radii, from the mean ln Rg of 8 seeds
+        let n = 40;
+        let df = |sticking_probability: f64| {
+            let mean_ln_rg = (0..8)
+                .map(|seed| {
+                    let params = CcaParams {
+                        n_particles: n,
+                        box_size: 12.0 * (n as f64).cbrt(),
+                        sticking_probability,
+                        ..Default::default()
+                    };
+                    let result = run_cca_internal(params, seed, &mut NoHooks);
+                    calculate_radius_of_gyration(&result.coordinates, &result.radii).ln()
+                })
+                .sum::<f64>()
+                / 8.0;
+            (n as f64 / 1.3).ln() / mean_ln_rg
+        };
+        let (dlca, rlca) = (df(1.0), df(0.02));
+        assert!((1.8..2.2).contains(&dlca), "DLCA Df {}", dlca);
+        assert!(rlca > dlca + 0.2, "RLCA Df {} not above DLCA Df {}", rlca, dlca);
     }
 
     #[test]
@@ -566,9 +1264,9 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(params.is_polydisperse());
+        assert!(params.size_distribution().is_polydisperse());
 
-        let result = run_cca_internal(params, 789);
+        let result = run_cca_internal(params, 789, &mut NoHooks);
 
         let min_r = result.radii.iter().cloned().fold(f64::INFINITY, f64::min);
         let max_r = result.radii.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
@@ -577,4 +1275,80 @@ mod tests {
         assert!(min_r >= 0.8 - 1e-10);
         assert!(max_r <= 1.2 + 1e-10);
     }
+
+    #[test]
+    fn test_contact_candidates_match_pair_scan() {
+        let box_size = 25.0;
+        let mut rng = create_rng(11);
+        let clusters: Vec<Cluster> = (0..300)
+            .map(|id| {
+                let mut c = || (rng.gen::<f64>() - 0.5) * box_size;
+                let center = Vector3::new(c(), c(), c());
+                Cluster::new(Sphere::new(center, 0.6 + 0.4 * rng.gen::<f64>()), id)
+            })
+            .collect();
+
+        let candidates = contact_candidates(&clusters, box_size, 1.0);
+        let mut expected = Vec::new();
+        for i in 0..clusters.len() {
+            for j in (i + 1)..clusters.len() {
+                let (a, b) = (&clusters[i].particles[0], &clusters[j].particles[0]);
+                let d = periodic_distance_squared(&a.center, &b.center, box_size).sqrt();
+                if d <= a.radius + b.radius {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(candidates.iter().map(|&(pair, _)| pair).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_cca_sintered_contacts() {
+        let params = CcaParams {
+            n_particles: 40,
+            box_size: 30.0,
+            sintering: SinteringDistribution::fixed(0.8),
+            ..Default::default()
+        };
+        let result = run_cca_internal(params, 9, &mut NoHooks);
+
+        // Clusters merge once two particles come within 0.8 * (r1 + r2)
+        for (i, a) in result.coordinates.iter().enumerate() {
+            let nearest = result
+                .coordinates
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, b)| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt())
+                .fold(f64::INFINITY, f64::min);
+            assert!(nearest <= 1.6 + 1e-6, "particle {} nearest = {}", i, nearest);
+        }
+    }
+
+    #[test]
+    fn test_cca_reports_progress() {
+        struct Recorder(Vec<ProgressEvent>);
+        impl EventHooks for Recorder {
+            fn on_progress(&mut self, event: &ProgressEvent) -> Flow {
+                self.0.push(*event);
+                Flow::Continue
+            }
+        }
+
+        let params = CcaParams {
+            n_particles: 30,
+            box_size: 30.0,
+            ..Default::default()
+        };
+        let mut recorder = Recorder(Vec::new());
+        run_cca_internal(params, 5, &mut recorder);
+
+        let events = recorder.0;
+        assert!(events.windows(2).all(|w| w[1].step >= w[0].step && w[1].elapsed >= w[0].elapsed));
+        let last = events.last().unwrap();
+        // One agglomerate left after n - 1 merges
+        assert_eq!((last.step, last.n_clusters, last.n_particles, last.n_target), (29, 1, 30, 30));
+        assert!(last.radius_of_gyration > 0.0);
+    }
 }
@@ -0,0 +1,81 @@
+//! Contact graph of a finished agglomerate.
+//!
+//! Particles are the nodes and touching or sintered pairs the edges. Its
+//! connected components are the separate agglomerates of a run, e.g. the
+//! clusters left by CCA with `single_agglomerate=False`.
+
+use std::collections::VecDeque;
+
+use super::metrics::Contact;
+
+/// Adjacency lists built from a contact list.
+pub struct ContactGraph {
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl ContactGraph {
+    /// Graph of `n` particles joined by `contacts`.
+    pub fn new(n: usize, contacts: &[Contact]) -> Self {
+        let mut neighbours = vec![Vec::new(); n];
+        for contact in contacts {
+            neighbours[contact.i].push(contact.j);
+            neighbours[contact.j].push(contact.i);
+        }
+        Self { neighbours }
+    }
+
+    /// Particle indices of each connected component, ascending within a
+    /// component; the largest component comes first (ties by lowest index).
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let n = self.neighbours.len();
+        let mut visited = vec![false; n];
+        let mut components = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for start in 0..n {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            queue.push_back(start);
+            let mut component = Vec::new();
+            while let Some(i) = queue.pop_front() {
+                component.push(i);
+                for &j in &self.neighbours[i] {
+                    if !visited[j] {
+                        visited[j] = true;
+                        queue.push_back(j);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        // Stable sort keeps components of equal size in order of their first particle
+        components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::metrics::calculate_contacts;
+
+    #[test]
+    fn test_components_split_separate_clusters() {
+        // A pair, a chain of three and an isolated particle
+        let coords = [
+            [10.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [12.0, 0.0, 0.0],
+            [4.0, 0.0, 0.0],
+            [0.0, 20.0, 0.0],
+        ];
+        let contacts = calculate_contacts(&coords, &[1.0; 6], 0.1);
+        let graph = ContactGraph::new(coords.len(), &contacts);
+
+        assert_eq!(graph.components(), vec![vec![1, 2, 4], vec![0, 3], vec![5]]);
+    }
+}
@@ -0,0 +1,580 @@
+//! Deposition of particles onto a flat substrate.
+//!
+//! Particles rain down onto the plane z = 0 through a box periodic in x and
+//! y and stick to the substrate or to the deposit they hit first, building a
+//! porous film instead of a free agglomerate:
+//!
+//! * "ballistic" - particles fall straight down from above the deposit
+//! * "diffusive" - particles random walk from above the deposit, which
+//!   gives the more open, tree-like films of diffusion-limited deposition
+
+use std::f64::consts::PI;
+use std::time::Instant;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::common::geometry::{Sphere, Vector3};
+use crate::common::particles::ParticleStore;
+use crate::common::rng::{create_rng, random_direction};
+use crate::common::spatial::PeriodicSpatialHash;
+use crate::common::units::PyUnits;
+use crate::common::validation::{check_count, check_periodic_box, check_radius_range, check_sticking_probability};
+
+use super::history::{PyHistoryParams, Snapshot};
+use super::hooks::{EventHooks, Flow, ProgressEvent, PyCallbacks, StickEvent, POLL_STEPS};
+use super::metrics::{
+    calculate_contacts, calculate_fractal_dimension, calculate_inertia_tensor, calculate_porosity,
+    calculate_radius_of_gyration, coordination_from_contacts, EvolutionFit,
+};
+use super::provenance::run_parameters;
+use super::result::{PySimulationResult, SimulationResult};
+use super::sintering::{resolve_sintering, sintered_contact_distance, PySinteringParams, SinteringDistribution};
+use super::size_distribution::{resolve_size_distribution, ParticleSizes, PySizeDistribution, SizeDistribution};
+
+/// Height map columns per mean radius used for the film surface.
+const HEIGHT_MAP_RESOLUTION: f64 = 2.0;
+
+/// Particles in a row that fail to land after which the film counts as full.
+const MAX_ABANDONED_IN_A_ROW: usize = 1000;
+
+/// How the particles reach the deposit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositionMode {
+    Ballistic,
+    Diffusive,
+}
+
+impl DepositionMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ballistic" => Some(Self::Ballistic),
+            "diffusive" => Some(Self::Diffusive),
+            _ => None,
+        }
+    }
+}
+
+/// Deposition parameters.
+#[derive(Debug, Clone, Serialize)]
+pub struct DepositionParams {
+    pub n_particles: usize,
+    /// Edge of the periodic box in x and y
+    pub box_xy: f64,
+    pub mode: DepositionMode,
+    pub sticking_probability: f64,
+    pub radius_min: f64,
+    pub radius_max: f64,
+    /// Distribution the radii are drawn from instead of the uniform
+    /// `radius_min..=radius_max` range, which then holds its bounds
+    #[serde(rename = "size_distribution")]
+    pub sizes: Option<SizeDistribution>,
+    /// Steps before a falling or walking particle is given up
+    pub max_steps: usize,
+    pub sintering: SinteringDistribution,
+}
+
+impl Default for DepositionParams {
+    fn default() -> Self {
+        Self {
+            n_particles: 1000,
+            box_xy: 20.0,
+            mode: DepositionMode::Ballistic,
+            sticking_probability: 1.0,
+            radius_min: 1.0,
+            radius_max: 1.0,
+            sizes: None,
+            max_steps: 1_000_000,
+            sintering: SinteringDistribution::default(),
+        }
+    }
+}
+
+impl ParticleSizes for DepositionParams {
+    fn size_parameters(&self) -> (Option<&SizeDistribution>, f64, f64) {
+        (self.sizes.as_ref(), self.radius_min, self.radius_max)
+    }
+}
+
+impl DepositionParams {
+    /// Check the parameters before a run, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_count("n_particles", self.n_particles, 1)?;
+        check_sticking_probability(self.sticking_probability)?;
+        check_radius_range(self.radius_min, self.radius_max)?;
+        check_periodic_box(self.box_xy, self.radius_max)
+    }
+}
+
+/// Thickness, surface and packing of a film on the substrate z = 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilmMetrics {
+    /// Height of the highest particle top
+    pub thickness: f64,
+    /// Mean height of the film surface
+    pub mean_height: f64,
+    /// RMS deviation of the surface height from its mean
+    pub roughness: f64,
+    /// Solid volume fraction of the film below its mean surface height
+    pub packing_density: f64,
+}
+
+/// Volume of a sphere of `radius` centered at height `z` below the plane at height `h`.
+fn volume_below(z: f64, radius: f64, h: f64) -> f64 {
+    let d = (h - (z - radius)).clamp(0.0, 2.0 * radius);
+    PI * d * d * (3.0 * radius - d) / 3.0
+}
+
+/// Film metrics of particles deposited in a box periodic in x and y of edge `box_xy`.
+///
+/// The surface is the height map of the highest particle top over a grid of
+/// columns half a mean radius wide (empty columns at height 0). The packing
+/// density counts the solid volume below the mean surface height, so the
+/// rough top layer does not dilute it.
+pub fn film_metrics(coordinates: &[[f64; 3]], radii: &[f64], box_xy: f64) -> FilmMetrics {
+    let n = radii.len().max(1) as f64;
+    let mean_radius = radii.iter().sum::<f64>() / n;
+    let columns = ((box_xy * HEIGHT_MAP_RESOLUTION / mean_radius).ceil() as usize).clamp(1, 1024);
+    let width = box_xy / columns as f64;
+
+    let mut heights = vec![0.0f64; columns * columns];
+    let column_of = |x: f64| ((x / box_xy + 0.5).rem_euclid(1.0) * columns as f64) as usize % columns;
+    for (c, &r) in coordinates.iter().zip(radii) {
+        let span = (r / width).ceil() as isize;
+        let (cx, cy) = (column_of(c[0]) as isize, column_of(c[1]) as isize);
+        for dx in -span..=span {
+            for dy in -span..=span {
+                let (ix, iy) = (
+                    (cx + dx).rem_euclid(columns as isize),
+                    (cy + dy).rem_euclid(columns as isize),
+                );
+                // Lateral offset from the column center to the sphere, minimum image
+                let offset = |i: isize, x: f64| {
+                    let center = (i as f64 + 0.5) * width - box_xy / 2.0;
+                    let d = center - x;
+                    d - box_xy * (d / box_xy).round()
+                };
+                let d2 = offset(ix, c[0]).powi(2) + offset(iy, c[1]).powi(2);
+                if d2 <= r * r {
+                    let top = c[2] + (r * r - d2).sqrt();
+                    let cell = &mut heights[ix as usize * columns + iy as usize];
+                    *cell = cell.max(top);
+                }
+            }
+        }
+    }
+
+    let thickness = coordinates.iter().zip(radii).map(|(c, r)| c[2] + r).fold(0.0, f64::max);
+    let mean_height = heights.iter().sum::<f64>() / heights.len() as f64;
+    let roughness = (heights.iter().map(|h| (h - mean_height).powi(2)).sum::<f64>() / heights.len() as f64).sqrt();
+    let solid: f64 = coordinates
+        .iter()
+        .zip(radii)
+        .map(|(c, &r)| volume_below(c[2], r, mean_height))
+        .sum();
+    let packing_density = if mean_height > 0.0 {
+        solid / (box_xy * box_xy * mean_height)
+    } else {
+        0.0
+    };
+
+    FilmMetrics {
+        thickness,
+        mean_height,
+        roughness,
+        packing_density,
+    }
+}
+
+/// Thickness, roughness and packing of a deposited film.
+#[pyclass(name = "FilmReport")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PyFilmReport {
+    /// Edge of the periodic box in x and y
+    #[pyo3(get)]
+    pub box_xy: f64,
+    /// Height of the highest particle top
+    #[pyo3(get)]
+    pub thickness: f64,
+    /// Mean height of the film surface
+    #[pyo3(get)]
+    pub mean_height: f64,
+    /// RMS deviation of the surface height from its mean
+    #[pyo3(get)]
+    pub roughness: f64,
+    /// Solid volume fraction below the mean surface height
+    #[pyo3(get)]
+    pub packing_density: f64,
+    /// 1 - packing_density
+    #[pyo3(get)]
+    pub porosity: f64,
+}
+
+impl PyFilmReport {
+    fn new(metrics: FilmMetrics, box_xy: f64) -> Self {
+        Self {
+            box_xy,
+            thickness: metrics.thickness,
+            mean_height: metrics.mean_height,
+            roughness: metrics.roughness,
+            packing_density: metrics.packing_density,
+            porosity: 1.0 - metrics.packing_density,
+        }
+    }
+}
+
+#[pymethods]
+impl PyFilmReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "FilmReport(thickness={:.4}, mean_height={:.4}, roughness={:.4}, packing_density={:.4})",
+            self.thickness, self.mean_height, self.roughness, self.packing_density
+        )
+    }
+}
+
+/// Deposit particles onto a flat substrate, building a porous film.
+///
+/// Particles enter above the film at a random (x, y) of a box periodic in x
+/// and y and either fall straight down ("ballistic") or random walk
+/// ("diffusive") until they touch the substrate at z = 0, where they always
+/// stick, or a deposited particle, where they stick with
+/// `sticking_probability`. Diffusing particles that wander far above the
+/// film are given up and replaced.
+///
+/// The agglomerate metrics of the result (Df, kf, Rg) describe the whole
+/// deposit; `SimulationResult.film` holds the film thickness, surface
+/// roughness and packing density.
+///
+/// # Arguments
+/// * `n_particles` - Number of particles to deposit
+/// * `box_xy` - Edge of the periodic box in x and y, at least 8 `radius_max`
+/// * `mode` - "ballistic" (default) or "diffusive"
+/// * `sticking_probability` - Probability of adhesion on contact with the deposit (0-1)
+/// * `radius_min` - Minimum particle radius (for polydisperse)
+/// * `radius_max` - Maximum particle radius (for polydisperse, defaults to radius_min)
+/// * `seed` - Random seed for reproducibility
+/// * `sintering` - `PySinteringParams` object (default: no sintering)
+/// * `on_stick` - Callable invoked as `on_stick(event)` each time a particle sticks, as in
+///   `run_ballistic`; `target` is None for particles on the substrate
+/// * `callback_every` - Only forward every N-th event to `on_stick` (default: 1)
+/// * `units` - `Units` giving the physical meaning of the lengths (radii in nm, ...)
+/// * `size_distribution` - `SizeDistribution` the primary particle diameters are drawn from;
+///   overrides `radius_min`/`radius_max` when given
+/// * `progress_callback` - Callable invoked as `progress_callback(info)` every `progress_every`
+///   particles deposited, as in `run_dla`
+/// * `progress_every` - Particles deposited between two progress reports (default: 100)
+/// * `cancel_event` - Object with an `is_set()` method that stops the run when set, as in `run_dla`
+/// * `history` - `HistoryParams` to record a `GrowthHistory` (default: None, no history)
+///
+/// # Returns
+/// * `SimulationResult` with the particle coordinates wrapped into the box in x and y and
+///   `film` holding the film metrics; a run where 1000 particles in a row fail to land
+///   stops early with a warning
+#[pyfunction]
+#[pyo3(signature = (n_particles, box_xy, mode="ballistic", sticking_probability=1.0, radius_min=1.0, radius_max=None, seed=None, sintering=None, on_stick=None, callback_every=1, units=None, size_distribution=None, progress_callback=None, progress_every=100, cancel_event=None, history=None))]
+pub fn run_ballistic_deposition(
+    py: Python<'_>,
+    n_particles: usize,
+    box_xy: f64,
+    mode: &str,
+    sticking_probability: f64,
+    radius_min: f64,
+    radius_max: Option<f64>,
+    seed: Option<u64>,
+    sintering: Option<PySinteringParams>,
+    on_stick: Option<PyObject>,
+    callback_every: usize,
+    units: Option<PyUnits>,
+    size_distribution: Option<PySizeDistribution>,
+    progress_callback: Option<PyObject>,
+    progress_every: usize,
+    cancel_event: Option<PyObject>,
+    history: Option<PyHistoryParams>,
+) -> PyResult<PySimulationResult> {
+    let seed = seed.unwrap_or_else(rand::random);
+    let mode = DepositionMode::from_name(mode)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown mode '{}': use 'ballistic' or 'diffusive'", mode)))?;
+
+    let mut warnings = Vec::new();
+    let sintering = resolve_sintering(sintering.as_ref(), 1.0, "fixed", 0.85, 0.95, 0.05, &mut warnings)?;
+    let (radius_min, radius_max, sizes) =
+        resolve_size_distribution(size_distribution.as_ref(), radius_min, radius_max, &mut warnings)?;
+
+    let params = DepositionParams {
+        n_particles,
+        box_xy,
+        mode,
+        sticking_probability,
+        radius_min,
+        radius_max,
+        sizes,
+        sintering,
+        ..Default::default()
+    };
+    params.validate()?;
+    let parameters =
+        run_parameters("ballistic_deposition", &params, json!({"seed": seed, "units": units, "history": history}));
+
+    let mut hooks = PyCallbacks::new(on_stick, None, callback_every)?
+        .with_progress(progress_callback, progress_every)?
+        .with_cancel_event(cancel_event)
+        .with_history(history)?;
+    // Release GIL during computation
+    let result = py.allow_threads(|| run_deposition_internal(params, seed, &mut hooks));
+    let history = hooks.take_history();
+    hooks.finish(&mut warnings)?;
+
+    result
+        .into_py(py, warnings)
+        .map(|r| r.with_units(units).with_history(history).with_parameters(parameters))
+}
+
+/// Internal deposition implementation.
+pub(crate) fn run_deposition_internal(
+    params: DepositionParams,
+    seed: u64,
+    hooks: &mut dyn EventHooks,
+) -> SimulationResult {
+    let start_time = Instant::now();
+    let mut rng = create_rng(seed);
+    let mut warnings = Vec::new();
+    let radii = params.radius_sampler();
+
+    let mut particles = ParticleStore::with_capacity(params.n_particles);
+    let mut hash = PeriodicSpatialHash::lateral(params.box_xy, params.radius_max * 4.0);
+    let mut film_top = 0.0f64;
+    let mut rg_evolution = Vec::new();
+    let mut n_values = Vec::new();
+    let mut abandoned = 0usize;
+    let mut abandoned_in_a_row = 0usize;
+
+    while particles.len() < params.n_particles {
+        let new_radius = radii.sample_radius(&mut rng);
+        let step_size = new_radius * 0.5;
+        // Enter above everything deposited so far
+        let launch_height = film_top + new_radius + 2.0 * params.radius_max;
+        let escape_height = launch_height + params.box_xy.max(10.0 * params.radius_max);
+        let mut coordinate = || (rng.gen::<f64>() - 0.5) * params.box_xy;
+        let mut pos = Vector3::new(coordinate(), coordinate(), launch_height);
+
+        let mut landed = None;
+        let mut cancelled = false;
+        for step in 0..params.max_steps {
+            if step % POLL_STEPS == 0 && hooks.on_poll() == Flow::Stop {
+                cancelled = true;
+                break;
+            }
+            let step = match params.mode {
+                DepositionMode::Ballistic => Vector3::new(0.0, 0.0, -step_size),
+                DepositionMode::Diffusive => {
+                    // Above the film the walker can jump by its gap to it in one step
+                    let length = step_size.max(pos.z - new_radius - film_top);
+                    let (sx, sy, sz) = random_direction(&mut rng);
+                    Vector3::new(sx * length, sy * length, sz * length)
+                }
+            };
+            pos = hash.wrap(pos + step);
+            if pos.z > escape_height {
+                break;
+            }
+
+            let sintering_coeff = params.sintering.sample(&mut rng);
+
+            // The substrate catches every particle that reaches it
+            if pos.z <= new_radius {
+                let on_substrate = Vector3::new(pos.x, pos.y, new_radius);
+                if !hash.overlaps(on_substrate, new_radius, sintering_coeff, None, 1e-6) {
+                    landed = Some((on_substrate, None));
+                }
+                break;
+            }
+
+            for (idx, other_center) in hash.neighbours(pos) {
+                let contact_dist = sintered_contact_distance(new_radius, particles.radius(idx), sintering_coeff);
+                if pos.distance_squared_to(&other_center) >= (contact_dist * 1.05).powi(2) {
+                    continue;
+                }
+                if params.sticking_probability >= 1.0 || rng.gen::<f64>() < params.sticking_probability {
+                    let direction = (pos - other_center).normalize();
+                    let new_pos = other_center + direction * contact_dist;
+                    // Resting on a particle never pushes through the substrate
+                    if new_pos.z >= new_radius && !hash.overlaps(new_pos, new_radius, sintering_coeff, Some(idx), 1e-6)
+                    {
+                        landed = Some((hash.wrap(new_pos), Some(idx)));
+                        break;
+                    }
+                }
+            }
+            if landed.is_some() {
+                break;
+            }
+        }
+
+        if cancelled {
+            break;
+        }
+        let Some((pos, touched)) = landed else {
+            abandoned += 1;
+            abandoned_in_a_row += 1;
+            if abandoned_in_a_row >= MAX_ABANDONED_IN_A_ROW {
+                warnings.push(format!(
+                    "{} particles in a row failed to land: stopped at {} particles",
+                    abandoned_in_a_row,
+                    particles.len()
+                ));
+                break;
+            }
+            continue;
+        };
+        abandoned_in_a_row = 0;
+        let new_sphere = Sphere::new(pos, new_radius);
+        let idx = particles.len();
+        particles.push(new_sphere);
+        hash.insert(idx, &new_sphere);
+        film_top = film_top.max(pos.z + new_radius);
+
+        let rg = calculate_radius_of_gyration(&particles.coords(), particles.radii());
+        rg_evolution.push(rg);
+        n_values.push(particles.len());
+
+        let progress = ProgressEvent::particles(particles.len(), params.n_particles, rg, start_time);
+        if hooks.wants_snapshot(&progress) {
+            hooks.on_snapshot(Snapshot::of_store(particles.len(), &particles));
+        }
+        if hooks.on_stick(&StickEvent::new(idx, new_sphere, touched)) == Flow::Stop
+            || hooks.on_progress(&progress) == Flow::Stop
+        {
+            break;
+        }
+    }
+    if abandoned > 0 {
+        warnings.push(format!(
+            "{} particles escaped or got stuck without touching the film and were replaced",
+            abandoned
+        ));
+    }
+
+    // Calculate final metrics
+    let coords = particles.coords();
+    let radii = particles.radii().to_vec();
+
+    let fit = calculate_fractal_dimension(&n_values, &rg_evolution, &mut warnings);
+    let porosity = calculate_porosity(&coords, &radii);
+    let contacts = calculate_contacts(&coords, &radii, params.mean_radius() * 0.1);
+    let coordination = coordination_from_contacts(&contacts, coords.len());
+    let inertia = calculate_inertia_tensor(&coords, &radii);
+    let film = PyFilmReport::new(film_metrics(&coords, &radii, params.box_xy), params.box_xy);
+
+    let n_final = coords.len();
+    let coord_mean = coordination.iter().map(|&c| c as f64).sum::<f64>() / n_final.max(1) as f64;
+    let coord_std = (coordination
+        .iter()
+        .map(|&c| (c as f64 - coord_mean).powi(2))
+        .sum::<f64>()
+        / n_final.max(1) as f64)
+        .sqrt();
+
+    SimulationResult {
+        coordinates: coords,
+        radii,
+        rg_evolution,
+        n_evolution: n_values,
+        fractal_dimension: fit.df,
+        fractal_dimension_std: fit.df_std_error,
+        prefactor: fit.kf,
+        linear_region: fit.linear_region,
+        evolution_fit: EvolutionFit::default(),
+        fit_r_squared: fit.r_squared,
+        fit_n_points: fit.n_points,
+        porosity,
+        coordination_mean: coord_mean,
+        coordination_std: coord_std,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+        seed,
+        child_seeds: Vec::new(),
+        anisotropy: inertia.anisotropy,
+        asphericity: inertia.asphericity,
+        acylindricity: inertia.acylindricity,
+        principal_moments: inertia.principal_moments,
+        principal_axes: inertia.principal_axes,
+        ids: (0..n_final as u32).collect(),
+        cluster_ids: vec![0; n_final],
+        coordination,
+        contacts,
+        generations: vec![0; n_final],
+        merge_history: Vec::new(),
+        warnings,
+        target_report: None,
+        brownian_report: None,
+        kinetics: None,
+        film: Some(film),
+        drift_report: None,
+        merge_diagnostics: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::hooks::NoHooks;
+
+    #[test]
+    fn test_deposition_builds_film() {
+        // A single layer of touching unit spheres on a square lattice: pi/6 packed
+        let mut coords = Vec::new();
+        for i in 0..5 {
+            for j in 0..5 {
+                coords.push([2.0 * i as f64 - 4.0, 2.0 * j as f64 - 4.0, 1.0]);
+            }
+        }
+        let metrics = film_metrics(&coords, &[1.0; 25], 10.0);
+        assert!((metrics.thickness - 2.0).abs() < 1e-12);
+        assert!(metrics.mean_height > 1.0 && metrics.mean_height < 2.0);
+        assert!(metrics.roughness > 0.0);
+
+        for mode in [DepositionMode::Ballistic, DepositionMode::Diffusive] {
+            let params = DepositionParams {
+                n_particles: 300,
+                box_xy: 10.0,
+                mode,
+                ..Default::default()
+            };
+            let result = run_deposition_internal(params, 9, &mut NoHooks);
+            assert_eq!(result.coordinates.len(), 300);
+            // Nothing below the substrate, everything inside the box laterally
+            assert!(result.coordinates.iter().all(|c| c[2] >= 1.0 - 1e-9));
+            assert!(result
+                .coordinates
+                .iter()
+                .all(|c| c[0].abs() <= 5.0 && c[1].abs() <= 5.0));
+            let film = result.film.expect("deposition reports its film");
+            assert!(film.thickness > 2.0);
+            assert!(
+                film.packing_density > 0.05 && film.packing_density < 0.6,
+                "{:?}: {}",
+                mode,
+                film.packing_density
+            );
+        }
+    }
+
+    #[test]
+    fn test_deposition_stops_when_nothing_lands() {
+        // Without sticking only the substrate holds particles, until the monolayer is full
+        let params = DepositionParams {
+            n_particles: 200,
+            box_xy: 8.0,
+            sticking_probability: 0.0,
+            ..Default::default()
+        };
+        let result = run_deposition_internal(params, 3, &mut NoHooks);
+        assert!(result.coordinates.len() < 200);
+        assert!(result.coordinates.iter().all(|c| (c[2] - 1.0).abs() < 1e-9));
+        assert!(result.warnings.iter().any(|w| w.contains("in a row failed to land")), "{:?}", result.warnings);
+    }
+}
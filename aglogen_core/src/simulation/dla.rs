@@ -2,32 +2,63 @@
 
 use std::time::Instant;
 
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use rand::Rng;
+use serde::Serialize;
+use serde_json::json;
 
 use crate::common::geometry::{Sphere, Vector3};
+use crate::common::particles::ParticleStore;
 use crate::common::rng::{create_rng, random_direction};
-use crate::common::spatial::SpatialHash;
+use crate::common::spatial::{PeriodicSpatialHash, SpatialHash};
+use crate::common::units::PyUnits;
+use crate::common::validation::{
+    check_count, check_periodic_box, check_radius_range, check_sticking_probability,
+};
+use crate::session::allow_threads;
 
+use super::drift::{resolve_drift, substeps, upstream, PyDriftReport};
+use super::history::{PyHistoryParams, Snapshot};
+use super::hooks::{EventHooks, Flow, ProgressEvent, PyCallbacks, StickEvent, POLL_STEPS};
 use super::metrics::{
-    calculate_coordination, calculate_fractal_dimension, calculate_inertia_tensor,
-    calculate_porosity, calculate_radius_of_gyration,
+    calculate_contacts, calculate_fractal_dimension, calculate_inertia_tensor,
+    calculate_porosity, calculate_radius_of_gyration, coordination_from_contacts, EvolutionFit,
 };
+use super::provenance::run_parameters;
+use super::restart::{centered_store, check_existing, extract_existing};
 use super::result::{PySimulationResult, SimulationResult};
-use super::sintering::{sintered_contact_distance, SinteringDistribution};
+use super::sintering::{
+    resolve_sintering, sintered_contact_distance, PySinteringParams, SinteringDistribution,
+};
+use super::size_distribution::{resolve_size_distribution, ParticleSizes, PySizeDistribution, SizeDistribution};
 
 /// DLA simulation parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DlaParams {
     pub n_particles: usize,
     pub sticking_probability: f64,
     pub lattice_size: usize,
     pub radius_min: f64,
     pub radius_max: f64,
+    /// Distribution the radii are drawn from instead of the uniform
+    /// `radius_min..=radius_max` range, which then holds its bounds
+    #[serde(rename = "size_distribution")]
+    pub sizes: Option<SizeDistribution>,
     pub max_walk_steps: usize,
     pub launch_distance_factor: f64,
     pub kill_distance_factor: f64,
     pub sintering: SinteringDistribution,
+    /// Weight of the contacted particle's coordination in the sticking
+    /// probability (0 = coordination-independent), see `sticking_probability_for`
+    pub coordination_weight: f64,
+    /// Agglomerate the run grows onto instead of a single seed particle
+    /// (empty = seed particle at the origin); it counts towards `n_particles`
+    pub initial: Vec<Sphere>,
+    /// Edge of the periodic box the walkers move in (None = open space)
+    pub box_size: Option<f64>,
+    /// Drift added to every walker step, in step lengths (zero = pure diffusion)
+    pub drift: Vector3,
 }
 
 impl Default for DlaParams {
@@ -38,32 +69,53 @@ impl Default for DlaParams {
             lattice_size: 200,
             radius_min: 1.0,
             radius_max: 1.0,
+            sizes: None,
             max_walk_steps: 1_000_000,
             launch_distance_factor: 2.0,
             kill_distance_factor: 3.0,
             sintering: SinteringDistribution::default(),
+            coordination_weight: 0.0,
+            initial: Vec::new(),
+            box_size: None,
+            drift: Vector3::zero(),
         }
     }
 }
 
-impl DlaParams {
-    /// Check if particles are polydisperse (variable radius).
-    pub fn is_polydisperse(&self) -> bool {
-        (self.radius_max - self.radius_min).abs() > 1e-10
+impl ParticleSizes for DlaParams {
+    fn size_parameters(&self) -> (Option<&SizeDistribution>, f64, f64) {
+        (self.sizes.as_ref(), self.radius_min, self.radius_max)
     }
+}
 
-    /// Generate a random radius within the range.
-    pub fn random_radius<R: Rng>(&self, rng: &mut R) -> f64 {
-        if self.is_polydisperse() {
-            rng.gen_range(self.radius_min..=self.radius_max)
-        } else {
-            self.radius_min
-        }
+impl DlaParams {
+    /// Sticking probability on a particle that already has `coordination` contacts.
+    ///
+    /// The base probability is scaled by `1 + coordination_weight * coordination`
+    /// and capped at 1, so positive weights make walkers stick preferentially
+    /// in crevices, next to well-connected particles, and grow denser
+    /// structures than the global `sticking_probability` alone.
+    pub fn sticking_probability_for(&self, coordination: u32) -> f64 {
+        (self.sticking_probability * (1.0 + self.coordination_weight * coordination as f64)).min(1.0)
     }
 
-    /// Get the mean radius for calculations.
-    pub fn mean_radius(&self) -> f64 {
-        (self.radius_min + self.radius_max) / 2.0
+    /// Check the parameters before a run, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_count("n_particles", self.n_particles, 1)?;
+        check_existing(self.n_particles, self.initial.len())?;
+        check_sticking_probability(self.sticking_probability)?;
+        check_count("lattice_size", self.lattice_size, 1)?;
+        check_radius_range(self.radius_min, self.radius_max)?;
+        if !(self.coordination_weight.is_finite() && self.coordination_weight >= 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "coordination_weight must be a non-negative number, got {}",
+                self.coordination_weight
+            )));
+        }
+        if let Some(box_size) = self.box_size {
+            check_periodic_box(box_size, self.radius_max)?;
+        }
+        Ok(())
     }
 }
 
@@ -81,8 +133,45 @@ impl DlaParams {
 /// * `sintering_max` - Max for uniform distribution (default: 0.95)
 /// * `sintering_std` - Std dev for normal distribution (default: 0.05)
 /// * `seed` - Random seed for reproducibility
+/// * `sintering` - `PySinteringParams` object; overrides the `sintering_*` arguments when given
+/// * `on_stick` - Callable invoked as `on_stick(event)` with a dict describing the event each time
+///   a particle sticks; returning `False` stops the run early
+/// * `callback_every` - Only forward every N-th event to `on_stick` (default: 1)
+/// * `units` - `Units` giving the physical meaning of the lengths (radii in nm, ...);
+///   enables the physical quantities of the result
+/// * `coordination_weight` - Coordination-dependent sticking: on contact with a particle
+///   that has z contacts, the walker sticks with probability
+///   `min(1, sticking_probability * (1 + coordination_weight * z))` (default: 0, disabled);
+///   use it with `sticking_probability < 1` to grow denser structures
+/// * `size_distribution` - `SizeDistribution` the primary particle diameters are drawn from
+///   (lognormal, normal, histogram, ...); overrides `radius_min`/`radius_max` when given
+/// * `progress_callback` - Callable invoked as `progress_callback(info)` every `progress_every`
+///   particles placed, with a dict holding `step`, `n_particles`, `n_target`, `n_clusters`,
+///   `radius_of_gyration` and `elapsed` (seconds); returning `False` stops the run early.
+///   Every engine reports these keys; in cluster-cluster engines `step` counts merges
+/// * `progress_every` - Particles placed between two progress reports (default: 100)
+/// * `cancel_event` - Object with an `is_set()` method, e.g. `threading.Event`; setting it
+///   stops the run, which returns the partial agglomerate with a warning. Ctrl-C stops the
+///   run too and raises `KeyboardInterrupt`
+/// * `existing_coords` - Coordinates (N x 3) of an agglomerate to grow onto instead of a single
+///   seed particle, e.g. a saved checkpoint or the core of a staged run; it is moved so its
+///   center of mass sits at the origin and counts towards `n_particles` (see `run_dla_continue`)
+/// * `existing_radii` - Radii (N) of the `existing_coords` particles
+/// * `history` - `HistoryParams` to record a `GrowthHistory`, returned as
+///   `SimulationResult.history` (default: None, no history)
+/// * `box_size` - Edge of a periodic box, at least 8 `radius_max`, the walkers start anywhere
+///   in and collide with particle images across its faces, growing a space-filling deposit
+///   instead of an isolated cluster. Coordinates are returned unwrapped, each particle next to
+///   the one it stuck to; wrap them into `[-box_size/2, box_size/2)` to see the box contents
+///   (default: None, open space)
+/// * `drift` - Drift `(x, y, z)` added to every walker step, in step lengths, for growth under
+///   gravity or an electric field: each step moves the walker by its random unit vector plus
+///   `drift`. Walkers start on the upstream half of the launch sphere and
+///   `SimulationResult.drift_report` gives the shape relative to the field; |drift| << 1 stays
+///   diffusion limited, |drift| ~ 1 and above grows denser agglomerates flattened across the
+///   field (default: None)
 #[pyfunction]
-#[pyo3(signature = (n_particles, sticking_probability=1.0, lattice_size=200, radius_min=1.0, radius_max=None, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None))]
+#[pyo3(signature = (n_particles, sticking_probability=1.0, lattice_size=200, radius_min=1.0, radius_max=None, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None, sintering=None, on_stick=None, callback_every=1, units=None, coordination_weight=0.0, size_distribution=None, progress_callback=None, progress_every=100, cancel_event=None, existing_coords=None, existing_radii=None, history=None, box_size=None, drift=None))]
 pub fn run_dla(
     py: Python<'_>,
     n_particles: usize,
@@ -96,15 +185,36 @@ pub fn run_dla(
     sintering_max: f64,
     sintering_std: f64,
     seed: Option<u64>,
+    sintering: Option<PySinteringParams>,
+    on_stick: Option<PyObject>,
+    callback_every: usize,
+    units: Option<PyUnits>,
+    coordination_weight: f64,
+    size_distribution: Option<PySizeDistribution>,
+    progress_callback: Option<PyObject>,
+    progress_every: usize,
+    cancel_event: Option<PyObject>,
+    existing_coords: Option<&Bound<'_, PyAny>>,
+    existing_radii: Option<&Bound<'_, PyAny>>,
+    history: Option<PyHistoryParams>,
+    box_size: Option<f64>,
+    drift: Option<(f64, f64, f64)>,
 ) -> PyResult<PySimulationResult> {
     let seed = seed.unwrap_or_else(rand::random);
-    let radius_max = radius_max.unwrap_or(radius_min);
-
-    let sintering = match sintering_type.to_lowercase().as_str() {
-        "uniform" => SinteringDistribution::uniform(sintering_min, sintering_max),
-        "normal" => SinteringDistribution::normal(sintering_coeff, sintering_std),
-        _ => SinteringDistribution::fixed(sintering_coeff),
-    };
+    let existing = extract_existing(existing_coords, existing_radii)?;
+
+    let mut warnings = Vec::new();
+    let sintering = resolve_sintering(
+        sintering.as_ref(),
+        sintering_coeff,
+        sintering_type,
+        sintering_min,
+        sintering_max,
+        sintering_std,
+        &mut warnings,
+    )?;
+    let (radius_min, radius_max, sizes) =
+        resolve_size_distribution(size_distribution.as_ref(), radius_min, radius_max, &mut warnings)?;
 
     let params = DlaParams {
         n_particles,
@@ -112,100 +222,162 @@ pub fn run_dla(
         lattice_size,
         radius_min,
         radius_max,
+        sizes,
         sintering,
+        coordination_weight,
+        initial: existing,
+        box_size,
+        drift: resolve_drift(drift)?,
         ..Default::default()
     };
+    params.validate()?;
+    let parameters = run_parameters("dla", &params, json!({"seed": seed, "units": units, "history": history}));
 
+    let mut hooks = PyCallbacks::new(on_stick, None, callback_every)?
+        .with_progress(progress_callback, progress_every)?
+        .with_cancel_event(cancel_event)
+        .with_history(history)?;
     // Release GIL during computation
-    let result = py.allow_threads(|| run_dla_internal(params, seed));
+    let result = allow_threads(py, || run_dla_internal(params, seed, &mut hooks));
+    let history = hooks.take_history();
+    hooks.finish(&mut warnings)?;
 
-    Ok(result.to_py())
+    result
+        .into_py(py, warnings)
+        .map(|r| r.with_units(units).with_history(history).with_parameters(parameters))
 }
 
 /// Internal DLA implementation.
-fn run_dla_internal(params: DlaParams, seed: u64) -> SimulationResult {
+pub(crate) fn run_dla_internal(params: DlaParams, seed: u64, hooks: &mut dyn EventHooks) -> SimulationResult {
     let start_time = Instant::now();
     let mut rng = create_rng(seed);
+    let radii = params.radius_sampler();
+    let mut warnings = Vec::new();
 
-    // Initialize with seed particle at origin (use mean radius for seed)
-    let seed_radius = params.mean_radius();
-    let mut particles: Vec<Sphere> = vec![Sphere::new(Vector3::zero(), seed_radius)];
+    // Grow onto the existing agglomerate, or from a mean-radius seed particle at the origin
+    let mut particles = centered_store(&params.initial, params.n_particles);
+    if particles.is_empty() {
+        particles.push(Sphere::new(Vector3::zero(), params.mean_radius()));
+    }
 
     // Use max radius for spatial hash cell size to handle polydisperse particles
-    let mut spatial_hash = SpatialHash::new(params.radius_max * 4.0);
-    spatial_hash.insert(0, &particles[0]);
+    let max_radius = particles.radii().iter().fold(params.radius_max, |m, &r| m.max(r));
+    let mut spatial_hash = SpatialHash::new(max_radius * 4.0);
+    for (idx, sphere) in particles.iter().enumerate() {
+        spatial_hash.insert(idx, &sphere);
+    }
+    // In a periodic box walkers also collide with the particle images across its faces
+    let mut periodic = params.box_size.map(|box_size| {
+        let mut hash = PeriodicSpatialHash::new(box_size, max_radius * 4.0);
+        for (idx, sphere) in particles.iter().enumerate() {
+            hash.insert(idx, &sphere);
+        }
+        hash
+    });
+
+    // Contacts of each particle so far, for coordination-dependent sticking
+    let contact_tolerance = params.mean_radius() * 0.1;
+    let coords = particles.coords();
+    let mut contacts =
+        coordination_from_contacts(&calculate_contacts(&coords, particles.radii(), contact_tolerance), coords.len());
 
     // Track Rg evolution
-    let mut rg_evolution = vec![seed_radius * (3.0 / 5.0_f64).sqrt()];
-    let mut n_values = vec![1usize];
+    let mut rg_evolution = vec![calculate_radius_of_gyration(&coords, particles.radii())];
+    let mut n_values = vec![particles.len()];
 
-    // Cluster properties
-    let mut cluster_rg = seed_radius;
+    // Cluster properties (a lone seed counts with its full radius)
+    let mut cluster_rg = if particles.len() == 1 { particles.radius(0) } else { rg_evolution[0] };
 
     // Add particles one by one
     while particles.len() < params.n_particles {
         // Generate radius for new particle
-        let new_radius = params.random_radius(&mut rng);
+        let new_radius = radii.sample_radius(&mut rng);
 
         // Launch distance based on current cluster size
         let launch_distance = params.launch_distance_factor * cluster_rg + params.radius_max * 2.0;
         let kill_distance = params.kill_distance_factor * launch_distance;
 
-        // Generate random starting position on launch sphere
-        let (dx, dy, dz) = random_direction(&mut rng);
-        let mut pos = Vector3::new(
-            dx * launch_distance,
-            dy * launch_distance,
-            dz * launch_distance,
-        );
+        // Generate random starting position on launch sphere, or anywhere in
+        // the free space of a periodic box
+        let mut pos = match &periodic {
+            Some(hash) => match hash.random_free_point(new_radius, 1000, &mut rng) {
+                Some(pos) => pos,
+                None => {
+                    warnings.push(format!(
+                        "periodic box is full: stopped at {} particles",
+                        particles.len()
+                    ));
+                    break;
+                }
+            },
+            None => {
+                let (dx, dy, dz) = random_direction(&mut rng);
+                upstream(Vector3::new(dx, dy, dz), params.drift) * launch_distance
+            }
+        };
 
         // Random walk
         let mut stuck = false;
-        for _ in 0..params.max_walk_steps {
-            // Check if too far - kill particle
-            if pos.length() > kill_distance {
+        let mut touched = None;
+        let mut cancelled = false;
+        // Step size based on new particle radius, split under a strong drift
+        let step_size = new_radius * 0.5;
+        let kill_distance_sq = kill_distance * kill_distance;
+        let substeps = substeps(params.drift);
+        let mut heading = Vector3::zero();
+        for step in 0..params.max_walk_steps * substeps {
+            if step % POLL_STEPS == 0 && hooks.on_poll() == Flow::Stop {
+                cancelled = true;
                 break;
             }
 
-            // Random step (step size based on new particle radius)
-            let (sx, sy, sz) = random_direction(&mut rng);
-            let step_size = new_radius * 0.5;
-            pos = pos + Vector3::new(sx * step_size, sy * step_size, sz * step_size);
+            // Check if too far - kill particle (walkers never leave a periodic box)
+            if periodic.is_none() && pos.length_squared() > kill_distance_sq {
+                break;
+            }
+
+            // Random step
+            if step % substeps == 0 {
+                let (sx, sy, sz) = random_direction(&mut rng);
+                heading = (Vector3::new(sx, sy, sz) + params.drift) * (step_size / substeps as f64);
+            }
+            pos = pos + heading;
+            if let Some(hash) = &periodic {
+                pos = hash.wrap(pos);
+            }
 
             // Check for collision with existing particles
             // Note: We detect collision at sintered distance for consistent behavior
-            let test_sphere = Sphere::new(pos, new_radius);
-            let candidates = spatial_hash.query_potential_collisions(&test_sphere);
+            let candidates = nearby_particles(pos, new_radius, &spatial_hash, periodic.as_ref(), &particles);
 
             // Sample sintering coefficient once for this particle
             let sintering_coeff = params.sintering.sample(&mut rng);
 
-            for &idx in &candidates {
-                let other = &particles[idx];
-                let dist = pos.distance_to(&other.center);
+            for &(idx, other_center) in &candidates {
+                let dist_sq = pos.distance_squared_to(&other_center);
                 // Use sintered distance for collision detection
-                let contact_dist = sintered_contact_distance(new_radius, other.radius, sintering_coeff);
+                let contact_dist = sintered_contact_distance(new_radius, particles.radius(idx), sintering_coeff);
 
-                if dist < contact_dist * 1.05 {
+                if dist_sq < (contact_dist * 1.05).powi(2) {
                     // Collision! Check sticking probability
-                    if params.sticking_probability >= 1.0
-                        || rng.gen::<f64>() < params.sticking_probability
-                    {
+                    let sticking_probability = params.sticking_probability_for(contacts[idx]);
+                    if sticking_probability >= 1.0 || rng.gen::<f64>() < sticking_probability {
                         // Place particle at exact sintered contact distance
-                        let direction = (pos - other.center).normalize();
-                        let new_pos = other.center + direction * contact_dist;
+                        let direction = (pos - other_center).normalize();
+                        let new_pos = other_center + direction * contact_dist;
 
                         // Verify no overlaps with other particles
-                        let valid = !particles.iter().enumerate().any(|(i, p)| {
-                            if i == idx { return false; }
-                            let d = new_pos.distance_to(&p.center);
-                            let min_dist = sintered_contact_distance(new_radius, p.radius, sintering_coeff);
-                            d < min_dist - 1e-6
-                        });
+                        let valid = match &periodic {
+                            Some(hash) => !hash.overlaps(new_pos, new_radius, sintering_coeff, Some(idx), 1e-6),
+                            None => !particles.overlaps(new_pos, new_radius, sintering_coeff, Some(idx), 1e-6),
+                        };
 
                         if valid {
-                            pos = new_pos;
+                            // Next to the touched particle itself rather than its
+                            // image, so the agglomerate stays connected
+                            pos = new_pos + (particles.center(idx) - other_center);
                             stuck = true;
+                            touched = Some(idx);
                             break;
                         }
                     }
@@ -216,38 +388,56 @@ fn run_dla_internal(params: DlaParams, seed: u64) -> SimulationResult {
                 break;
             }
         }
+        if cancelled {
+            break;
+        }
 
         if stuck {
             // Add new particle with its random radius
             let new_sphere = Sphere::new(pos, new_radius);
             let idx = particles.len();
+            contacts.push(0);
+            for (other, other_center) in nearby_particles(pos, new_radius, &spatial_hash, periodic.as_ref(), &particles) {
+                let limit = new_radius + particles.radius(other) + contact_tolerance;
+                if pos.distance_squared_to(&other_center) <= limit * limit {
+                    contacts[other] += 1;
+                    contacts[idx] += 1;
+                }
+            }
             particles.push(new_sphere);
             spatial_hash.insert(idx, &new_sphere);
+            if let Some(hash) = periodic.as_mut() {
+                hash.insert(idx, &new_sphere);
+            }
 
             // Update cluster Rg
-            let coords: Vec<[f64; 3]> = particles
-                .iter()
-                .map(|s| [s.center.x, s.center.y, s.center.z])
-                .collect();
-            let radii: Vec<f64> = particles.iter().map(|s| s.radius).collect();
-            cluster_rg = calculate_radius_of_gyration(&coords, &radii);
+            cluster_rg = calculate_radius_of_gyration(&particles.coords(), particles.radii());
 
             rg_evolution.push(cluster_rg);
             n_values.push(particles.len());
+
+            let progress = ProgressEvent::particles(particles.len(), params.n_particles, cluster_rg, start_time);
+            if hooks.wants_snapshot(&progress) {
+                hooks.on_snapshot(Snapshot::of_store(particles.len(), &particles));
+            }
+            if hooks.on_stick(&StickEvent::new(idx, new_sphere, touched)) == Flow::Stop
+                || hooks.on_progress(&progress) == Flow::Stop
+            {
+                break;
+            }
         }
     }
 
     // Calculate final metrics
-    let coords: Vec<[f64; 3]> = particles
-        .iter()
-        .map(|s| [s.center.x, s.center.y, s.center.z])
-        .collect();
-    let radii: Vec<f64> = particles.iter().map(|s| s.radius).collect();
+    let coords = particles.coords();
+    let radii = particles.radii().to_vec();
 
-    let (df, kf, _r2) = calculate_fractal_dimension(&n_values, &rg_evolution);
+    let fit = calculate_fractal_dimension(&n_values, &rg_evolution, &mut warnings);
     let porosity = calculate_porosity(&coords, &radii);
-    let coordination = calculate_coordination(&coords, &radii, params.mean_radius() * 0.1);
+    let contacts = calculate_contacts(&coords, &radii, params.mean_radius() * 0.1);
+    let coordination = coordination_from_contacts(&contacts, coords.len());
     let inertia = calculate_inertia_tensor(&coords, &radii);
+    let drift_report = (params.drift != Vector3::zero()).then(|| PyDriftReport::new(params.drift, &coords, &radii));
 
     let coord_mean = coordination.iter().map(|&c| c as f64).sum::<f64>() / coordination.len() as f64;
     let coord_std = (coordination
@@ -257,31 +447,74 @@ fn run_dla_internal(params: DlaParams, seed: u64) -> SimulationResult {
         / coordination.len() as f64)
         .sqrt();
 
+    let n_final = coords.len();
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
     SimulationResult {
         coordinates: coords,
         radii,
         rg_evolution,
-        fractal_dimension: df,
-        fractal_dimension_std: 0.02, // TODO: Calculate from fit
-        prefactor: kf,
+        n_evolution: n_values,
+        fractal_dimension: fit.df,
+        fractal_dimension_std: fit.df_std_error,
+        prefactor: fit.kf,
+        linear_region: fit.linear_region,
+        evolution_fit: EvolutionFit::default(),
+        fit_r_squared: fit.r_squared,
+        fit_n_points: fit.n_points,
         porosity,
         coordination_mean: coord_mean,
         coordination_std: coord_std,
         execution_time_ms,
         seed,
+        child_seeds: Vec::new(),
         anisotropy: inertia.anisotropy,
         asphericity: inertia.asphericity,
         acylindricity: inertia.acylindricity,
         principal_moments: inertia.principal_moments,
         principal_axes: inertia.principal_axes,
+        ids: (0..n_final as u32).collect(),
+        cluster_ids: vec![0; n_final],
+        coordination,
+        contacts,
+        generations: vec![0; n_final],
+        merge_history: Vec::new(),
+        warnings,
+        target_report: None,
+        brownian_report: None,
+        kinetics: None,
+        film: None,
+        drift_report,
+        merge_diagnostics: None,
+    }
+}
+
+/// Particles that may touch a sphere of `radius` at `point`, as `(index, center)`.
+///
+/// In a periodic box the center is that of the particle image next to
+/// `point`, which may lie across a box face.
+pub(crate) fn nearby_particles(
+    point: Vector3,
+    radius: f64,
+    hash: &SpatialHash,
+    periodic: Option<&PeriodicSpatialHash>,
+    particles: &ParticleStore,
+) -> Vec<(usize, Vector3)> {
+    match periodic {
+        Some(periodic) => periodic.neighbours(point),
+        None => hash
+            .query_potential_collisions(&Sphere::new(point, radius))
+            .into_iter()
+            .map(|i| (i, particles.center(i)))
+            .collect(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::spatial::check_periodic_deposit;
+    use crate::simulation::hooks::{CancelAtPoll, NoHooks, StopAfter};
 
     #[test]
     fn test_dla_deterministic() {
@@ -290,8 +523,8 @@ mod tests {
             ..Default::default()
         };
 
-        let r1 = run_dla_internal(params.clone(), 42);
-        let r2 = run_dla_internal(params, 42);
+        let r1 = run_dla_internal(params.clone(), 42, &mut NoHooks);
+        let r2 = run_dla_internal(params, 42, &mut NoHooks);
 
         assert_eq!(r1.coordinates.len(), r2.coordinates.len());
         for (c1, c2) in r1.coordinates.iter().zip(r2.coordinates.iter()) {
@@ -301,6 +534,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_dla_stops_when_hook_asks() {
+        let params = DlaParams {
+            n_particles: 50,
+            ..Default::default()
+        };
+
+        // Seed particle plus the 10 that stuck before the hook said stop
+        let result = run_dla_internal(params, 42, &mut StopAfter(10));
+        assert_eq!(result.coordinates.len(), 11);
+    }
+
+    #[test]
+    fn test_dla_cancels_during_walk() {
+        // A walker that never lands still reaches the poll
+        let params = DlaParams {
+            n_particles: 10,
+            sticking_probability: 0.0,
+            ..Default::default()
+        };
+        let result = run_dla_internal(params, 42, &mut CancelAtPoll(3));
+        assert_eq!(result.coordinates.len(), 1);
+    }
+
+    #[test]
+    fn test_dla_continues_existing_agglomerate() {
+        let core = run_dla_internal(DlaParams { n_particles: 30, ..Default::default() }, 42, &mut NoHooks);
+        let initial: Vec<Sphere> = core
+            .coordinates
+            .iter()
+            .zip(&core.radii)
+            .map(|(c, &r)| Sphere::new(Vector3::new(c[0] + 5.0, c[1], c[2]), r))
+            .collect();
+        let params = DlaParams {
+            n_particles: 60,
+            initial,
+            ..Default::default()
+        };
+
+        let result = run_dla_internal(params, 7, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 60);
+        // The core keeps its shape, only moved to put its center of mass at the origin
+        let shift = |i: usize| {
+            let (a, b) = (result.coordinates[i], core.coordinates[i]);
+            Vector3::new(a[0] - b[0], a[1] - b[1], a[2] - b[2])
+        };
+        for i in 1..30 {
+            assert!(shift(i).distance_to(&shift(0)) < 1e-9);
+        }
+        assert_eq!(result.n_evolution[0], 30);
+    }
+
     #[test]
     fn test_dla_fractal_dimension_range() {
         let params = DlaParams {
@@ -309,7 +594,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = run_dla_internal(params, 123);
+        let result = run_dla_internal(params, 123, &mut NoHooks);
 
         assert!(result.fractal_dimension > 0.5);
         assert!(result.fractal_dimension < 4.0);
@@ -324,9 +609,9 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(params.is_polydisperse());
+        assert!(params.size_distribution().is_polydisperse());
 
-        let result = run_dla_internal(params, 456);
+        let result = run_dla_internal(params, 456, &mut NoHooks);
 
         // Check that we have variable radii
         let min_r = result.radii.iter().cloned().fold(f64::INFINITY, f64::min);
@@ -338,6 +623,24 @@ mod tests {
         assert!(max_r <= 1.2 + 1e-10, "Max radius should be <= 1.2");
     }
 
+    #[test]
+    fn test_dla_lognormal_sizes() {
+        let sizes = SizeDistribution::Lognormal { cmd: 2.0, gsd: 1.3 };
+        let (d_min, d_max) = sizes.diameter_range();
+        let params = DlaParams {
+            n_particles: 40,
+            radius_min: d_min / 2.0,
+            radius_max: d_max / 2.0,
+            sizes: Some(sizes.clone()),
+            ..Default::default()
+        };
+        assert!((params.mean_radius() - sizes.mean_diameter() / 2.0).abs() < 1e-12);
+
+        let result = run_dla_internal(params, 21, &mut NoHooks);
+        assert_eq!(result.radii.len(), 40);
+        assert!(result.radii.iter().all(|&r| r >= d_min / 2.0 && r <= d_max / 2.0));
+    }
+
     #[test]
     fn test_dla_monodisperse() {
         let params = DlaParams {
@@ -347,13 +650,115 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(!params.is_polydisperse());
+        assert!(!params.size_distribution().is_polydisperse());
 
-        let result = run_dla_internal(params, 789);
+        let result = run_dla_internal(params, 789, &mut NoHooks);
 
         // All radii should be equal
         for r in &result.radii {
             assert!((r - 1.0).abs() < 1e-10);
         }
     }
+
+    #[test]
+    fn test_dla_coordination_weight() {
+        let params = DlaParams {
+            n_particles: 30,
+            sticking_probability: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(params.sticking_probability_for(5), 0.2);
+
+        let weighted = DlaParams {
+            coordination_weight: 1.5,
+            ..params.clone()
+        };
+        assert!((weighted.sticking_probability_for(2) - 0.8).abs() < 1e-12);
+        assert_eq!(weighted.sticking_probability_for(3), 1.0);
+
+        // The weight changes where walkers stick, and so the structure
+        let plain = run_dla_internal(params, 11, &mut NoHooks);
+        let biased = run_dla_internal(weighted, 11, &mut NoHooks);
+        assert_eq!(biased.coordinates.len(), 30);
+        assert_ne!(plain.coordinates, biased.coordinates);
+    }
+
+    #[test]
+    fn test_dla_periodic_box() {
+        let box_size = 16.0;
+        let params = DlaParams {
+            n_particles: 200,
+            radius_min: 0.8,
+            radius_max: 1.2,
+            box_size: Some(box_size),
+            ..Default::default()
+        };
+        let result = run_dla_internal(params, 5, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 200);
+        check_periodic_deposit(&result.coordinates, &result.radii, box_size);
+
+        let tight = DlaParams {
+            box_size: Some(4.0),
+            ..Default::default()
+        };
+        assert!(tight.validate().is_err());
+    }
+
+    #[test]
+    fn test_dla_drift_flattens_across_field() {
+        let plain = run_dla_internal(DlaParams { n_particles: 20, ..Default::default() }, 13, &mut NoHooks);
+        assert!(plain.drift_report.is_none());
+
+        // Walkers carried up the z axis pile onto the upstream face of the
+        // agglomerate, which spreads sideways
+        for seed in 0..3 {
+            let params = DlaParams {
+                n_particles: 200,
+                drift: Vector3::new(0.0, 0.0, 1.0),
+                ..Default::default()
+            };
+            let result = run_dla_internal(params, seed, &mut NoHooks);
+            assert_eq!(result.coordinates.len(), 200);
+            let report = result.drift_report.expect("a drifted run reports its shape");
+            assert_eq!(report.field_strength, 1.0);
+            let rg = calculate_radius_of_gyration(&result.coordinates, &result.radii);
+            assert!((report.rg_parallel.powi(2) + 2.0 * report.rg_perpendicular.powi(2) - rg * rg).abs() < 1e-9);
+            assert!(report.axial_ratio < 0.95, "seed {}: axial ratio {}", seed, report.axial_ratio);
+        }
+
+        // A field far stronger than diffusion still leaves walkers on the surface
+        let params = DlaParams {
+            n_particles: 60,
+            drift: Vector3::new(0.0, 0.0, 8.0),
+            ..Default::default()
+        };
+        let result = run_dla_internal(params, 5, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 60);
+        assert_eq!(substeps(Vector3::new(0.0, 0.0, 8.0)), 9);
+        let contacts = calculate_contacts(&result.coordinates, &result.radii, 1e-6);
+        let coordination = coordination_from_contacts(&contacts, 60);
+        assert!(coordination.iter().all(|&c| c >= 1));
+    }
+
+    #[test]
+    fn test_dla_sintered_contacts() {
+        let params = DlaParams {
+            n_particles: 40,
+            sintering: SinteringDistribution::fixed(0.8),
+            ..Default::default()
+        };
+        let result = run_dla_internal(params, 9, &mut NoHooks);
+
+        // Every walker is placed at exactly 0.8 * (r1 + r2) from the particle it hits
+        for (i, a) in result.coordinates.iter().enumerate() {
+            let nearest = result
+                .coordinates
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, b)| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt())
+                .fold(f64::INFINITY, f64::min);
+            assert!((nearest - 1.6).abs() < 1e-6, "particle {} nearest = {}", i, nearest);
+        }
+    }
 }
@@ -0,0 +1,400 @@
+//! Ensemble statistics over repeated simulation runs.
+//!
+//! Parameter sweeps run one configuration with many seeds and report the
+//! distribution of each metric rather than a single agglomerate.
+//! [`aggregate_results`] reduces a list of results to per-metric summaries
+//! (with histograms, and bootstrap confidence intervals for Df and kf) and,
+//! optionally, to a master Rg-N curve averaged over the runs. The mobility
+//! diameters of the agglomerates also give the ensemble's mass-mobility
+//! exponent.
+
+use std::collections::BTreeMap;
+
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::Rng;
+
+use crate::common::rng::create_rng;
+use crate::common::validation::{check_count, check_in_range};
+
+use super::mobility::mass_mobility_exponent;
+use super::result::PySimulationResult;
+
+/// Distribution of one scalar metric across an ensemble.
+#[pyclass(name = "MetricStats")]
+#[derive(Debug, Clone)]
+pub struct MetricStats {
+    #[pyo3(get)]
+    pub mean: f64,
+    /// Sample standard deviation (0 for a single value).
+    #[pyo3(get)]
+    pub std: f64,
+    #[pyo3(get)]
+    pub min: f64,
+    #[pyo3(get)]
+    pub max: f64,
+    #[pyo3(get)]
+    pub median: f64,
+    /// Percentile levels in [0, 100].
+    #[pyo3(get)]
+    pub percentiles: Vec<f64>,
+    /// Values at `percentiles` (linear interpolation, as numpy's default).
+    #[pyo3(get)]
+    pub percentile_values: Vec<f64>,
+    /// Bootstrap confidence interval of the mean, when computed.
+    #[pyo3(get)]
+    pub ci_low: Option<f64>,
+    #[pyo3(get)]
+    pub ci_high: Option<f64>,
+    /// Bin edges of the histogram (empty when not computed).
+    #[pyo3(get)]
+    pub histogram_edges: Vec<f64>,
+    /// Number of values in each histogram bin.
+    #[pyo3(get)]
+    pub histogram_counts: Vec<usize>,
+}
+
+#[pymethods]
+impl MetricStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "MetricStats(mean={:.4}, std={:.4}, min={:.4}, max={:.4})",
+            self.mean, self.std, self.min, self.max
+        )
+    }
+}
+
+impl MetricStats {
+    /// Summarize `values` (must be non-empty) without a confidence interval.
+    pub fn from_values(values: &[f64], percentiles: &[f64]) -> Self {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let std = if values.len() > 1 {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        Self {
+            mean,
+            std,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            median: percentile(&sorted, 50.0),
+            percentiles: percentiles.to_vec(),
+            percentile_values: percentiles.iter().map(|&q| percentile(&sorted, q)).collect(),
+            ci_low: None,
+            ci_high: None,
+            histogram_edges: Vec::new(),
+            histogram_counts: Vec::new(),
+        }
+    }
+
+    /// Attach a histogram of `values` with `n_bins` equal bins (none for 0).
+    fn with_histogram(mut self, values: &[f64], n_bins: usize) -> Self {
+        if n_bins > 0 {
+            let (edges, counts) = histogram(values, n_bins);
+            self.histogram_edges = edges;
+            self.histogram_counts = counts;
+        }
+        self
+    }
+
+    /// Attach a percentile-bootstrap confidence interval of the mean.
+    fn with_bootstrap_ci<R: Rng>(mut self, values: &[f64], n_resamples: usize, confidence: f64, rng: &mut R) -> Self {
+        if n_resamples > 0 {
+            let (low, high) = bootstrap_mean_ci(values, n_resamples, confidence, rng);
+            self.ci_low = Some(low);
+            self.ci_high = Some(high);
+        }
+        self
+    }
+}
+
+/// Percentile `q` (0-100) of sorted data, interpolating linearly between ranks.
+pub fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let rank = q / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Histogram with `n_bins` equal bins spanning the values, as `numpy.histogram`.
+///
+/// The last bin includes its upper edge; constant data gets the range
+/// value ± 0.5 like numpy.
+pub fn histogram(values: &[f64], n_bins: usize) -> (Vec<f64>, Vec<usize>) {
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let (low, high) = if max > min { (min, max) } else { (min - 0.5, max + 0.5) };
+    let width = (high - low) / n_bins as f64;
+
+    let edges: Vec<f64> = (0..=n_bins).map(|k| low + k as f64 * width).collect();
+    let mut counts = vec![0; n_bins];
+    for &v in values {
+        let bin = (((v - low) / width) as usize).min(n_bins - 1);
+        counts[bin] += 1;
+    }
+    (edges, counts)
+}
+
+/// Percentile-bootstrap confidence interval of the mean of `values`.
+pub fn bootstrap_mean_ci<R: Rng>(values: &[f64], n_resamples: usize, confidence: f64, rng: &mut R) -> (f64, f64) {
+    let n = values.len();
+    let mut means: Vec<f64> = (0..n_resamples)
+        .map(|_| (0..n).map(|_| values[rng.gen_range(0..n)]).sum::<f64>() / n as f64)
+        .collect();
+    means.sort_by(f64::total_cmp);
+
+    let tail = (1.0 - confidence) / 2.0 * 100.0;
+    (percentile(&means, tail), percentile(&means, 100.0 - tail))
+}
+
+/// Rg-N curve averaged over several runs.
+#[derive(Debug, Clone, Default)]
+pub struct MasterCurve {
+    pub n: Vec<usize>,
+    pub rg_mean: Vec<f64>,
+    pub rg_std: Vec<f64>,
+    /// Number of runs that sampled each N.
+    pub count: Vec<usize>,
+}
+
+impl MasterCurve {
+    /// Merge `(n_evolution, rg_evolution)` curves, averaging Rg at each N.
+    ///
+    /// Particle-cluster engines sample the same N in every run; cluster-cluster
+    /// runs share fewer N values, which shows up as small `count`s.
+    pub fn merge<'a>(curves: impl IntoIterator<Item = (&'a [usize], &'a [f64])>) -> Self {
+        let mut samples: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+        for (ns, rgs) in curves {
+            for (&n, &rg) in ns.iter().zip(rgs.iter()) {
+                samples.entry(n).or_default().push(rg);
+            }
+        }
+
+        let mut curve = Self::default();
+        for (n, rgs) in samples {
+            let stats = MetricStats::from_values(&rgs, &[]);
+            curve.n.push(n);
+            curve.rg_mean.push(stats.mean);
+            curve.rg_std.push(stats.std);
+            curve.count.push(rgs.len());
+        }
+        curve
+    }
+}
+
+/// Python wrapper for ensemble statistics.
+#[pyclass(name = "EnsembleResult")]
+pub struct PyEnsembleResult {
+    /// Number of results aggregated.
+    #[pyo3(get)]
+    pub n_results: usize,
+    #[pyo3(get)]
+    pub fractal_dimension: MetricStats,
+    #[pyo3(get)]
+    pub prefactor: MetricStats,
+    #[pyo3(get)]
+    pub radius_of_gyration: MetricStats,
+    #[pyo3(get)]
+    pub porosity: MetricStats,
+    #[pyo3(get)]
+    pub coordination_mean: MetricStats,
+    #[pyo3(get)]
+    pub anisotropy: MetricStats,
+    #[pyo3(get)]
+    pub asphericity: MetricStats,
+    #[pyo3(get)]
+    pub acylindricity: MetricStats,
+    #[pyo3(get)]
+    pub n_particles: MetricStats,
+    #[pyo3(get)]
+    pub execution_time_ms: MetricStats,
+    /// Free-molecular mobility diameter of the agglomerates
+    #[pyo3(get)]
+    pub mobility_diameter: MetricStats,
+    /// Continuum mobility diameter of the agglomerates
+    #[pyo3(get)]
+    pub mobility_diameter_continuum: MetricStats,
+    /// Slope of ln(mass) against ln(free-molecular mobility diameter), NaN
+    /// unless the agglomerates differ in size
+    #[pyo3(get)]
+    pub mass_mobility_exponent: f64,
+    /// Mass-mobility exponent with the continuum mobility diameters
+    #[pyo3(get)]
+    pub mass_mobility_exponent_continuum: f64,
+
+    pub(crate) master_curve_data: Option<MasterCurve>,
+}
+
+#[pymethods]
+impl PyEnsembleResult {
+    /// Master curve particle counts as numpy array (M,), or None if not requested.
+    #[getter]
+    fn master_curve_n<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray1<usize>>> {
+        self.master_curve_data.as_ref().map(|c| PyArray1::from_vec(py, c.n.clone()))
+    }
+
+    /// Mean Rg at each master curve N as numpy array (M,), or None.
+    #[getter]
+    fn master_curve_rg_mean<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray1<f64>>> {
+        self.master_curve_data.as_ref().map(|c| PyArray1::from_vec(py, c.rg_mean.clone()))
+    }
+
+    /// Standard deviation of Rg at each master curve N as numpy array (M,), or None.
+    #[getter]
+    fn master_curve_rg_std<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray1<f64>>> {
+        self.master_curve_data.as_ref().map(|c| PyArray1::from_vec(py, c.rg_std.clone()))
+    }
+
+    /// Number of runs contributing to each master curve N as numpy array (M,), or None.
+    #[getter]
+    fn master_curve_count<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray1<usize>>> {
+        self.master_curve_data.as_ref().map(|c| PyArray1::from_vec(py, c.count.clone()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "EnsembleResult(n_results={}, Df={:.4}±{:.4}, kf={:.4}±{:.4})",
+            self.n_results,
+            self.fractal_dimension.mean,
+            self.fractal_dimension.std,
+            self.prefactor.mean,
+            self.prefactor.std
+        )
+    }
+}
+
+/// Aggregate simulation results into ensemble statistics.
+///
+/// # Arguments
+/// * `results` - List of `SimulationResult`s (typically one configuration, several seeds)
+/// * `percentiles` - Percentile levels in [0, 100] reported for every metric
+/// * `n_bootstrap` - Bootstrap resamples for the Df and kf confidence intervals (0 disables them)
+/// * `confidence` - Confidence level of the bootstrap intervals (default: 0.95)
+/// * `seed` - Random seed of the bootstrap
+/// * `master_curve` - Also merge the runs' Rg evolutions into a mean Rg-N curve
+/// * `n_bins` - Histogram bins reported for every metric (default: 10, 0 disables them)
+///
+/// The mobility diameters are the ones of `SimulationResult.mobility_diameter()`;
+/// the mass-mobility exponents use the particle volume sum(r³) as the mass.
+#[pyfunction]
+#[pyo3(signature = (results, percentiles=vec![5.0, 25.0, 50.0, 75.0, 95.0], n_bootstrap=1000, confidence=0.95, seed=0, master_curve=false, n_bins=10))]
+pub fn aggregate_results(
+    results: Vec<PyRef<'_, PySimulationResult>>,
+    percentiles: Vec<f64>,
+    n_bootstrap: usize,
+    confidence: f64,
+    seed: u64,
+    master_curve: bool,
+    n_bins: usize,
+) -> PyResult<PyEnsembleResult> {
+    check_count("results", results.len(), 1)?;
+    for &q in &percentiles {
+        check_in_range("percentiles", q, 0.0, 100.0)?;
+    }
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(PyValueError::new_err(format!(
+            "confidence must be in (0, 1), got {}",
+            confidence
+        )));
+    }
+
+    let stats = |metric: fn(&PySimulationResult) -> f64| {
+        let values: Vec<f64> = results.iter().map(|r| metric(r)).collect();
+        MetricStats::from_values(&values, &percentiles).with_histogram(&values, n_bins)
+    };
+    let with_ci = |metric: fn(&PySimulationResult) -> f64, rng: &mut _| {
+        let values: Vec<f64> = results.iter().map(|r| metric(r)).collect();
+        MetricStats::from_values(&values, &percentiles)
+            .with_histogram(&values, n_bins)
+            .with_bootstrap_ci(&values, n_bootstrap, confidence, rng)
+    };
+
+    let mut rng = create_rng(seed);
+    let master_curve_data = master_curve.then(|| {
+        MasterCurve::merge(
+            results
+                .iter()
+                .map(|r| (r.n_evolution_data.as_slice(), r.rg_evolution_data.as_slice())),
+        )
+    });
+
+    let mobility: Vec<_> = results.iter().map(|r| r.mobility_diameter(r.py())).collect();
+    let masses: Vec<f64> = results.iter().map(|r| r.radii_data.iter().map(|r| r.powi(3)).sum()).collect();
+    let free_molecular: Vec<f64> = mobility.iter().map(|d| d.free_molecular).collect();
+    let continuum: Vec<f64> = mobility.iter().map(|d| d.continuum).collect();
+
+    Ok(PyEnsembleResult {
+        n_results: results.len(),
+        fractal_dimension: with_ci(|r| r.fractal_dimension, &mut rng),
+        prefactor: with_ci(|r| r.prefactor, &mut rng),
+        radius_of_gyration: stats(|r| r.radius_of_gyration),
+        porosity: stats(|r| r.porosity),
+        coordination_mean: stats(|r| r.coordination_mean),
+        anisotropy: stats(|r| r.anisotropy),
+        asphericity: stats(|r| r.asphericity),
+        acylindricity: stats(|r| r.acylindricity),
+        n_particles: stats(|r| r.radii_data.len() as f64),
+        execution_time_ms: stats(|r| r.execution_time_ms as f64),
+        mobility_diameter: MetricStats::from_values(&free_molecular, &percentiles).with_histogram(&free_molecular, n_bins),
+        mobility_diameter_continuum: MetricStats::from_values(&continuum, &percentiles).with_histogram(&continuum, n_bins),
+        mass_mobility_exponent: mass_mobility_exponent(&masses, &free_molecular),
+        mass_mobility_exponent_continuum: mass_mobility_exponent(&masses, &continuum),
+        master_curve_data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric_stats_match_numpy() {
+        let values = [3.0, 1.0, 4.0, 1.0, 5.0];
+        let stats = MetricStats::from_values(&values, &[25.0, 90.0]);
+
+        assert!((stats.mean - 2.8).abs() < 1e-12);
+        // numpy.std(values, ddof=1)
+        assert!((stats.std - 1.7888543819998317).abs() < 1e-12);
+        assert_eq!((stats.min, stats.max, stats.median), (1.0, 5.0, 3.0));
+        // numpy.percentile(values, [25, 90])
+        assert_eq!(stats.percentile_values[0], 1.0);
+        assert!((stats.percentile_values[1] - 4.6).abs() < 1e-12);
+        assert!(stats.ci_low.is_none());
+
+        let mut rng = create_rng(7);
+        let (low, high) = bootstrap_mean_ci(&values, 2000, 0.95, &mut rng);
+        assert!(low < stats.mean && stats.mean < high);
+        assert!(low >= 1.0 && high <= 5.0);
+    }
+
+    #[test]
+    fn test_histogram_matches_numpy() {
+        // numpy.histogram([3, 1, 4, 1, 5], bins=4)
+        let (edges, counts) = histogram(&[3.0, 1.0, 4.0, 1.0, 5.0], 4);
+        assert_eq!(edges, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(counts, vec![2, 0, 1, 2]);
+
+        let (edges, counts) = histogram(&[2.0, 2.0], 2);
+        assert_eq!(edges, vec![1.5, 2.0, 2.5]);
+        assert_eq!(counts, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_master_curve_averages_shared_sizes() {
+        let curve = MasterCurve::merge([
+            (&[1, 2, 4][..], &[1.0, 2.0, 4.0][..]),
+            (&[1, 2, 3][..], &[1.0, 3.0, 5.0][..]),
+        ]);
+        assert_eq!(curve.n, vec![1, 2, 3, 4]);
+        assert_eq!(curve.rg_mean, vec![1.0, 2.5, 5.0, 4.0]);
+        assert_eq!(curve.count, vec![2, 2, 1, 1]);
+        assert_eq!(curve.rg_std[0], 0.0);
+    }
+}
@@ -0,0 +1,281 @@
+//! Df and kf estimators for a single agglomerate.
+//!
+//! The Df/kf a run reports are fitted to its own (N, Rg) growth trace, so
+//! they depend on how the agglomerate grew and the kf from the regression
+//! intercept is known to be biased. These estimators only look at the final
+//! structure:
+//!
+//! * "evolution" - the growth trace is replaced by the Rg of the nested
+//!   sub-clusters of the k particles nearest to the center of gravity
+//! * "nested_spheres" - Df is the slope of the particle count N(r) within a
+//!   sphere of radius r around the center of gravity, ln N(r) ~ Df ln r
+//!
+//! Both anchor kf on a point of the power law N = kf (Rg/rp)^Df: the
+//! centroid of the fitted samples for "evolution", the whole agglomerate
+//! for "nested_spheres". The kf interval follows from moving Df across its
+//! confidence interval about that anchor.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::common::fitting::linear_regression;
+use crate::common::validation::check_count;
+use crate::common::warnings::emit_warnings;
+use crate::projection::extract_structure;
+
+use super::metrics::{
+    calculate_center_of_gravity, calculate_fractal_dimension, calculate_radius_of_gyration, insufficient_fit_warning,
+    mass_radius_profile,
+};
+use super::result::PROFILE_POINTS;
+
+/// How `estimate_df_kf` measures Df.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstimateMethod {
+    Evolution,
+    NestedSpheres,
+}
+
+impl EstimateMethod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "evolution" => Some(Self::Evolution),
+            "nested_spheres" => Some(Self::NestedSpheres),
+            _ => None,
+        }
+    }
+}
+
+/// Df and kf of one agglomerate with 95% confidence intervals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DfKfEstimate {
+    pub df: f64,
+    pub kf: f64,
+    pub df_std_error: f64,
+    pub df_interval: (f64, f64),
+    pub kf_interval: (f64, f64),
+    pub r_squared: f64,
+    /// Samples the Df regression used.
+    pub n_points: usize,
+}
+
+impl DfKfEstimate {
+    /// Estimate from a fitted Df and the anchor (ln N, ln Rg/rp) kf is pinned to.
+    fn anchored(df: f64, df_std_error: f64, r_squared: f64, n_points: usize, anchor: (f64, f64)) -> Self {
+        let kf_of = |df: f64| (anchor.0 - df * anchor.1).exp();
+        let half = 1.96 * df_std_error;
+        let (lo, hi) = (kf_of(df - half), kf_of(df + half));
+        Self {
+            df,
+            kf: kf_of(df),
+            df_std_error,
+            df_interval: (df - half, df + half),
+            kf_interval: (lo.min(hi), lo.max(hi)),
+            r_squared,
+            n_points,
+        }
+    }
+
+    fn fallback(n_points: usize) -> Self {
+        Self {
+            df: 2.0,
+            kf: 1.0,
+            df_std_error: f64::NAN,
+            df_interval: (f64::NAN, f64::NAN),
+            kf_interval: (f64::NAN, f64::NAN),
+            r_squared: 0.0,
+            n_points,
+        }
+    }
+}
+
+/// Estimate Df and kf of an agglomerate, lengths in units of the mean radius.
+pub fn estimate_df_kf_internal(
+    coordinates: &[[f64; 3]],
+    radii: &[f64],
+    method: EstimateMethod,
+    warnings: &mut Vec<String>,
+) -> DfKfEstimate {
+    let n = coordinates.len();
+    let mean_radius = radii.iter().sum::<f64>() / n.max(1) as f64;
+    match method {
+        EstimateMethod::Evolution => {
+            let (sizes, rg) = mass_radius_profile(coordinates, radii, 2, PROFILE_POINTS);
+            let rg: Vec<f64> = rg.iter().map(|r| r / mean_radius).collect();
+            let fit = calculate_fractal_dimension(&sizes, &rg, warnings);
+            let (start, end) = fit.linear_region;
+            if start >= end {
+                return DfKfEstimate::fallback(fit.n_points);
+            }
+            // Centroid of the fitted samples, where the regression line is pinned
+            let samples: Vec<(f64, f64)> = (start..end)
+                .filter(|&i| sizes[i] > 1 && rg[i] > 0.0)
+                .map(|i| ((sizes[i] as f64).ln(), rg[i].ln()))
+                .collect();
+            let k = samples.len() as f64;
+            let anchor = samples.iter().fold((0.0, 0.0), |acc, s| (acc.0 + s.0 / k, acc.1 + s.1 / k));
+            DfKfEstimate::anchored(fit.df, fit.df_std_error, fit.r_squared, fit.n_points, anchor)
+        }
+        EstimateMethod::NestedSpheres => {
+            let cg = calculate_center_of_gravity(coordinates, radii);
+            let mut distances: Vec<f64> = coordinates
+                .iter()
+                .map(|c| ((c[0] - cg.x).powi(2) + (c[1] - cg.y).powi(2) + (c[2] - cg.z).powi(2)).sqrt())
+                .collect();
+            distances.sort_by(f64::total_cmp);
+            let rg = calculate_radius_of_gyration(coordinates, radii);
+
+            // Log-spaced shells from two radii out to Rg, where N(r) still grows as r^Df
+            let (r_min, r_max) = (2.0 * mean_radius, rg);
+            let (xs, ys): (Vec<f64>, Vec<f64>) = if r_max > r_min {
+                (0..PROFILE_POINTS)
+                    .map(|i| r_min * (r_max / r_min).powf(i as f64 / (PROFILE_POINTS - 1) as f64))
+                    .map(|r| (r, distances.partition_point(|&d| d <= r)))
+                    .filter(|&(_, count)| count > 1)
+                    .map(|(r, count)| ((r / mean_radius).ln(), (count as f64).ln()))
+                    .unzip()
+            } else {
+                (Vec::new(), Vec::new())
+            };
+            if xs.len() < 3 {
+                warnings.push(insufficient_fit_warning(xs.len()));
+                return DfKfEstimate::fallback(xs.len());
+            }
+
+            let fit = linear_regression(&xs, &ys);
+            let anchor = ((n as f64).ln(), (rg / mean_radius).ln());
+            DfKfEstimate::anchored(fit.slope, fit.std_error, fit.r_squared, xs.len(), anchor)
+        }
+    }
+}
+
+/// Df and kf of one agglomerate, from `estimate_df_kf`.
+#[pyclass(name = "DfKfEstimate")]
+#[derive(Debug, Clone)]
+pub struct PyDfKfEstimate {
+    #[pyo3(get)]
+    pub method: String,
+    #[pyo3(get)]
+    pub df: f64,
+    #[pyo3(get)]
+    pub kf: f64,
+    /// Standard error of Df (NaN when there is nothing to fit, as are the intervals)
+    #[pyo3(get)]
+    pub df_std_error: f64,
+    /// 95% confidence interval of Df
+    #[pyo3(get)]
+    pub df_interval: (f64, f64),
+    /// kf over the Df confidence interval, about the anchor of the fit
+    #[pyo3(get)]
+    pub kf_interval: (f64, f64),
+    #[pyo3(get)]
+    pub r_squared: f64,
+    /// Samples the Df regression used
+    #[pyo3(get)]
+    pub n_points: usize,
+    #[pyo3(get)]
+    pub warnings: Vec<String>,
+}
+
+#[pymethods]
+impl PyDfKfEstimate {
+    fn __repr__(&self) -> String {
+        format!(
+            "DfKfEstimate(method='{}', df={:.4}, kf={:.4}, df_interval=({:.4}, {:.4}))",
+            self.method, self.df, self.kf, self.df_interval.0, self.df_interval.1
+        )
+    }
+}
+
+/// Estimate Df and kf of a single agglomerate from its structure alone.
+///
+/// Unlike the Df/kf of a `SimulationResult`, fitted to the run's growth
+/// trace, these only depend on the final coordinates, so agglomerates from
+/// different engines, loaded files or tomography can be compared directly.
+/// Lengths are scaled by the mean particle radius rp.
+///
+/// * "evolution" - fit of Rg against N over the nested sub-clusters of the
+///   k particles nearest to the center of gravity
+/// * "nested_spheres" - slope of ln N(r) against ln r, N(r) being the number
+///   of particles within r of the center of gravity, for 2 rp < r < Rg
+///
+/// kf is pinned to a point of N = kf (Rg/rp)^Df: the centroid of the fitted
+/// samples ("evolution") or the whole agglomerate ("nested_spheres"), which
+/// avoids the bias of a regression intercept far from the data.
+///
+/// # Arguments
+/// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
+/// * `method` - "evolution" or "nested_spheres" (default: "nested_spheres")
+///
+/// # Returns
+/// * `DfKfEstimate` with Df, kf and their 95% confidence intervals
+#[pyfunction]
+#[pyo3(signature = (coordinates, radii, method="nested_spheres"))]
+pub fn estimate_df_kf(
+    py: Python<'_>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+    method: &str,
+) -> PyResult<PyDfKfEstimate> {
+    let estimate_method = EstimateMethod::from_name(method).ok_or_else(|| {
+        PyValueError::new_err(format!(
+            "unknown method '{}': use 'evolution' or 'nested_spheres'",
+            method
+        ))
+    })?;
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    check_count("number of coordinates", coords.nrows(), 1)?;
+
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|r| [r[0], r[1], r[2]]).collect();
+    let mut warnings = Vec::new();
+    let estimate = py.allow_threads(|| estimate_df_kf_internal(&points, &radii, estimate_method, &mut warnings));
+    emit_warnings(py, &warnings)?;
+
+    Ok(PyDfKfEstimate {
+        method: method.to_string(),
+        df: estimate.df,
+        kf: estimate.kf,
+        df_std_error: estimate.df_std_error,
+        df_interval: estimate.df_interval,
+        kf_interval: estimate.kf_interval,
+        r_squared: estimate.r_squared,
+        n_points: estimate.n_points,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_estimates() {
+        // Chain of touching unit spheres: Df = 1 and Rg/rp = n/sqrt(3), so kf ~ sqrt(3)
+        let coords: Vec<[f64; 3]> = (0..301).map(|i| [2.0 * i as f64, 0.0, 0.0]).collect();
+        let radii = vec![1.0; coords.len()];
+        for method in [EstimateMethod::Evolution, EstimateMethod::NestedSpheres] {
+            let mut warnings = Vec::new();
+            let estimate = estimate_df_kf_internal(&coords, &radii, method, &mut warnings);
+            assert!((estimate.df - 1.0).abs() < 0.05, "{:?}: Df = {}", method, estimate.df);
+            // The shell counts of a chain grow as r + 1, so nested spheres
+            // read Df slightly low and the anchored kf high
+            let kf_tolerance = if method == EstimateMethod::Evolution { 0.1 } else { 0.3 };
+            assert!(
+                (estimate.kf / 3.0f64.sqrt() - 1.0).abs() < kf_tolerance,
+                "{:?}: kf = {}",
+                method,
+                estimate.kf
+            );
+            assert!(estimate.kf_interval.0 <= estimate.kf && estimate.kf <= estimate.kf_interval.1);
+            assert!(warnings.is_empty());
+        }
+
+        // A lone particle has nothing to fit
+        let mut warnings = Vec::new();
+        let estimate = estimate_df_kf_internal(&[[0.0; 3]], &[1.0], EstimateMethod::NestedSpheres, &mut warnings);
+        assert_eq!(estimate.df, 2.0);
+        assert!(estimate.df_std_error.is_nan() && estimate.df_interval.0.is_nan());
+        assert_eq!(warnings.len(), 1);
+    }
+}
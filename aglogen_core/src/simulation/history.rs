@@ -0,0 +1,356 @@
+//! Growth history recorded while an agglomerate grows.
+//!
+//! The engines report their progress after every particle placed or merge
+//! done (see `hooks`); a [`HistoryRecorder`] samples those reports at a
+//! fixed interval, whatever the engine, and optionally asks for snapshots of
+//! the particle coordinates to animate the growth.
+
+use std::sync::Arc;
+
+use numpy::{PyArray1, PyArray2};
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::geometry::Sphere;
+use crate::common::particles::ParticleStore;
+
+use super::hooks::ProgressEvent;
+
+/// What to record of a run's growth history.
+///
+/// Passed as `history` to any engine, it records a `GrowthHistory`: the
+/// size, Rg, running Df and elapsed time every `every` steps, particles
+/// placed for particle-cluster engines and merges done for cluster-cluster
+/// ones, plus the coordinates every `snapshot_every` steps for animations.
+/// The history is returned as `SimulationResult.history`.
+#[pyclass(name = "HistoryParams")]
+#[derive(Debug, Clone, Serialize)]
+pub struct PyHistoryParams {
+    /// Particles placed (particle-cluster engines) or merges done
+    /// (cluster-cluster engines) between two samples (default: 1)
+    #[pyo3(get, set)]
+    pub every: usize,
+
+    /// Steps between two coordinate snapshots (default: None, no snapshots)
+    #[pyo3(get, set)]
+    pub snapshot_every: Option<usize>,
+}
+
+#[pymethods]
+impl PyHistoryParams {
+    #[new]
+    #[pyo3(signature = (every=1, snapshot_every=None))]
+    pub fn new(every: usize, snapshot_every: Option<usize>) -> PyResult<Self> {
+        let params = Self { every, snapshot_every };
+        params.validate()?;
+        Ok(params)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("HistoryParams(every={}, snapshot_every={:?})", self.every, self.snapshot_every)
+    }
+}
+
+impl PyHistoryParams {
+    pub fn validate(&self) -> PyResult<()> {
+        if self.every == 0 {
+            return Err(PyValueError::new_err("every must be at least 1"));
+        }
+        if self.snapshot_every == Some(0) {
+            return Err(PyValueError::new_err("snapshot_every must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// Coordinates of every particle at one step of a run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub step: usize,
+    pub coordinates: Vec<[f64; 3]>,
+    pub radii: Vec<f64>,
+    /// Cluster of each particle (all 0 for particle-cluster engines)
+    pub cluster_ids: Vec<u32>,
+}
+
+impl Snapshot {
+    /// Snapshot of a particle-cluster agglomerate.
+    pub fn of_store(step: usize, particles: &ParticleStore) -> Self {
+        Self {
+            step,
+            coordinates: particles.coords(),
+            radii: particles.radii().to_vec(),
+            cluster_ids: vec![0; particles.len()],
+        }
+    }
+
+    /// Snapshot of a pool of clusters, numbered in pool order.
+    pub fn of_clusters<'a>(step: usize, clusters: impl IntoIterator<Item = &'a [Sphere]>) -> Self {
+        let mut snapshot = Self {
+            step,
+            ..Default::default()
+        };
+        for (k, cluster) in clusters.into_iter().enumerate() {
+            for sphere in cluster {
+                snapshot.coordinates.push([sphere.center.x, sphere.center.y, sphere.center.z]);
+                snapshot.radii.push(sphere.radius);
+                snapshot.cluster_ids.push(k as u32);
+            }
+        }
+        snapshot
+    }
+}
+
+/// Samples progress reports into a growth history.
+#[derive(Debug)]
+pub struct HistoryRecorder {
+    every: usize,
+    snapshot_every: Option<usize>,
+    next_sample: usize,
+    next_snapshot: usize,
+    history: PyGrowthHistory,
+    /// Running sums of the log-log (N, Rg) regression
+    sums: [f64; 5],
+}
+
+impl HistoryRecorder {
+    pub fn new(params: &PyHistoryParams) -> Self {
+        Self {
+            every: params.every,
+            snapshot_every: params.snapshot_every,
+            next_sample: 0,
+            next_snapshot: 0,
+            history: PyGrowthHistory::default(),
+            sums: [0.0; 5],
+        }
+    }
+
+    /// Record `event` if a sample is due.
+    pub fn record(&mut self, event: &ProgressEvent) {
+        if event.step < self.next_sample {
+            return;
+        }
+        self.next_sample = (event.step / self.every + 1) * self.every;
+
+        if event.n_particles > 1 && event.radius_of_gyration > 0.0 {
+            let (x, y) = ((event.n_particles as f64).ln(), event.radius_of_gyration.ln());
+            for (sum, term) in self.sums.iter_mut().zip([1.0, x, y, x * x, x * y]) {
+                *sum += term;
+            }
+        }
+        let h = &mut self.history;
+        h.steps.push(event.step);
+        h.n_particles.push(event.n_particles);
+        h.n_clusters.push(event.n_clusters);
+        h.radius_of_gyration.push(event.radius_of_gyration);
+        h.fractal_dimension.push(running_dimension(&self.sums));
+        h.elapsed.push(event.elapsed);
+    }
+
+    /// Whether the engine should pass a snapshot with `event`.
+    pub fn wants_snapshot(&self, event: &ProgressEvent) -> bool {
+        self.snapshot_every.is_some() && event.step >= self.next_snapshot
+    }
+
+    /// Whether a progress report at `step` would be sampled or snapshotted.
+    pub fn is_due(&self, step: usize) -> bool {
+        step >= self.next_sample || (self.snapshot_every.is_some() && step >= self.next_snapshot)
+    }
+
+    pub fn add_snapshot(&mut self, snapshot: Snapshot) {
+        if let Some(every) = self.snapshot_every {
+            self.next_snapshot = (snapshot.step / every + 1) * every;
+            Arc::make_mut(&mut self.history.snapshots).push(snapshot);
+        }
+    }
+
+    pub fn finish(self) -> PyGrowthHistory {
+        self.history
+    }
+}
+
+/// Df = 1 / slope of ln Rg against ln N over the samples so far (NaN below 3 samples).
+fn running_dimension(&[n, sx, sy, sxx, sxy]: &[f64; 5]) -> f64 {
+    let denominator = n * sxx - sx * sx;
+    if n < 3.0 || denominator.abs() < 1e-12 {
+        return f64::NAN;
+    }
+    let slope = (n * sxy - sx * sy) / denominator;
+    1.0 / slope
+}
+
+/// Time-stamped growth history of a run.
+///
+/// One sample every `HistoryParams.every` steps (particles placed or merges
+/// done) with the size, Rg and a running Df estimate of the agglomerate
+/// (largest cluster for cluster-cluster engines), plus the coordinate
+/// snapshots asked for with `HistoryParams.snapshot_every`.
+#[pyclass(name = "GrowthHistory")]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PyGrowthHistory {
+    steps: Vec<usize>,
+    n_particles: Vec<usize>,
+    n_clusters: Vec<usize>,
+    radius_of_gyration: Vec<f64>,
+    fractal_dimension: Vec<f64>,
+    elapsed: Vec<f64>,
+    snapshots: Arc<Vec<Snapshot>>,
+}
+
+#[pymethods]
+impl PyGrowthHistory {
+    /// Step of each sample: particles placed or merges done (K,).
+    #[getter]
+    fn steps<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_slice(py, &self.steps)
+    }
+
+    /// Particles in the agglomerate (largest cluster) at each sample (K,).
+    #[getter]
+    fn n_particles<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_slice(py, &self.n_particles)
+    }
+
+    /// Clusters left at each sample (K,).
+    #[getter]
+    fn n_clusters<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_slice(py, &self.n_clusters)
+    }
+
+    /// Radius of gyration of the agglomerate (largest cluster) at each sample (K,).
+    #[getter]
+    fn radius_of_gyration<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_slice(py, &self.radius_of_gyration)
+    }
+
+    /// Df fitted to the (N, Rg) samples up to each sample (K,); NaN for the first ones.
+    #[getter]
+    fn fractal_dimension<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_slice(py, &self.fractal_dimension)
+    }
+
+    /// Seconds since the run started at each sample (K,).
+    #[getter]
+    fn elapsed<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_slice(py, &self.elapsed)
+    }
+
+    /// Step of each coordinate snapshot.
+    #[getter]
+    fn snapshot_steps<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_vec(py, self.snapshots.iter().map(|s| s.step).collect())
+    }
+
+    /// Coordinate snapshot `index` as `(coordinates, radii, cluster_ids)`,
+    /// arrays of shape (N, 3), (N,) and (N,).
+    #[allow(clippy::type_complexity)]
+    fn snapshot<'py>(
+        &self,
+        py: Python<'py>,
+        index: usize,
+    ) -> PyResult<(Bound<'py, PyArray2<f64>>, Bound<'py, PyArray1<f64>>, Bound<'py, PyArray1<u32>>)> {
+        let snapshot = self.snapshots.get(index).ok_or_else(|| {
+            PyIndexError::new_err(format!("snapshot {} out of range ({} snapshots)", index, self.snapshots.len()))
+        })?;
+        let rows: Vec<Vec<f64>> = snapshot.coordinates.iter().map(|c| c.to_vec()).collect();
+        let coordinates = if rows.is_empty() {
+            PyArray2::zeros(py, [0, 3], false)
+        } else {
+            PyArray2::from_vec2(py, &rows).unwrap()
+        };
+        Ok((
+            coordinates,
+            PyArray1::from_slice(py, &snapshot.radii),
+            PyArray1::from_slice(py, &snapshot.cluster_ids),
+        ))
+    }
+
+    fn __len__(&self) -> usize {
+        self.steps.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GrowthHistory({} samples, {} snapshots)",
+            self.steps.len(),
+            self.snapshots.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::simulation::hooks::{EventHooks, Flow};
+    use crate::simulation::tunable::{run_tunable_internal, TunableParams};
+
+    /// Hooks recording the history alone, as `PyCallbacks` does.
+    struct Recorder(HistoryRecorder);
+
+    impl EventHooks for Recorder {
+        fn on_progress(&mut self, event: &ProgressEvent) -> Flow {
+            self.0.record(event);
+            Flow::Continue
+        }
+
+        fn wants_progress(&self, step: usize) -> bool {
+            self.0.is_due(step)
+        }
+
+        fn wants_snapshot(&self, event: &ProgressEvent) -> bool {
+            self.0.wants_snapshot(event)
+        }
+
+        fn on_snapshot(&mut self, snapshot: Snapshot) {
+            self.0.add_snapshot(snapshot);
+        }
+    }
+
+    #[test]
+    fn test_recorder_samples_at_interval() {
+        let params = PyHistoryParams::new(3, Some(5)).unwrap();
+        let mut recorder = HistoryRecorder::new(&params);
+        let start = Instant::now();
+        let mut snapshots = Vec::new();
+        for n in 1..=12usize {
+            // Rg = N^(1/2): Df = 2
+            let event = ProgressEvent::particles(n, 12, (n as f64).sqrt(), start);
+            recorder.record(&event);
+            if recorder.wants_snapshot(&event) {
+                snapshots.push(n);
+                recorder.add_snapshot(Snapshot { step: n, ..Default::default() });
+            }
+        }
+        let history = recorder.finish();
+        assert_eq!(history.steps, vec![1, 3, 6, 9, 12]);
+        assert_eq!(snapshots, vec![1, 5, 10]);
+        assert!(history.fractal_dimension[1].is_nan());
+        assert!((history.fractal_dimension[4] - 2.0).abs() < 1e-9);
+
+        assert!(PyHistoryParams::new(0, None).is_err());
+    }
+
+    #[test]
+    fn test_engine_history_follows_every() {
+        // Tunable PC samples its own Rg evolution every 10 particles only
+        let params = TunableParams {
+            n_particles: 60,
+            ..Default::default()
+        };
+        let mut hooks = Recorder(HistoryRecorder::new(&PyHistoryParams::new(4, Some(25)).unwrap()));
+        let result = run_tunable_internal(params, 5, &mut hooks);
+        let history = hooks.0.finish();
+
+        let first = history.steps[0];
+        let expected: Vec<usize> = std::iter::once(first).chain((first / 4 + 1..=15).map(|k| 4 * k)).collect();
+        assert_eq!(history.steps, expected);
+        assert_eq!(history.n_particles, history.steps);
+        let snapshot_steps: Vec<usize> = history.snapshots.iter().map(|s| s.step).collect();
+        assert_eq!(snapshot_steps, vec![first, 25, 50]);
+        assert_eq!(history.snapshots[2].coordinates.len(), 50);
+        assert_eq!(history.radius_of_gyration.last(), result.rg_evolution.last());
+    }
+}
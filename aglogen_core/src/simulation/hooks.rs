@@ -0,0 +1,465 @@
+//! Event hooks invoked by the engines while an agglomerate grows.
+//!
+//! Engines report every particle that sticks (particle-cluster engines) or
+//! every pair of clusters that merges (cluster-cluster engines) to an
+//! [`EventHooks`] implementation, which may ask the engine to stop early.
+//! After each placement or merge they also report their overall progress,
+//! with a snapshot of the particles when the hooks ask for one, and poll
+//! them every [`POLL_STEPS`] steps of a random walk.
+//! [`PyCallbacks`] forwards the events to Python callables and records the
+//! growth history.
+
+use std::time::{Duration, Instant};
+
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict};
+
+use crate::common::geometry::{Sphere, Vector3};
+use crate::common::particles::ParticleStore;
+
+use super::history::{HistoryRecorder, PyGrowthHistory, PyHistoryParams, Snapshot};
+use super::lineage::MergeEvent;
+
+/// Whether the engine should keep growing after an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Stop,
+}
+
+/// A particle stuck to the agglomerate.
+#[derive(Debug, Clone, Copy)]
+pub struct StickEvent {
+    /// Index (persistent ID) of the new particle.
+    pub particle: u32,
+    /// Index of the particle it touched, when the engine knows it.
+    pub target: Option<u32>,
+    /// Particle center at sticking time, in the engine's frame.
+    pub position: [f64; 3],
+    pub radius: f64,
+}
+
+impl StickEvent {
+    pub fn new(particle: usize, sphere: Sphere, target: Option<usize>) -> Self {
+        Self {
+            particle: particle as u32,
+            target: target.map(|t| t as u32),
+            position: [sphere.center.x, sphere.center.y, sphere.center.z],
+            radius: sphere.radius,
+        }
+    }
+
+    /// Event for the last particle of `particles`.
+    pub fn last_of(particles: &ParticleStore, target: Option<usize>) -> Self {
+        let index = particles.len() - 1;
+        Self::new(index, particles.get(index), target)
+    }
+}
+
+/// Two clusters merged.
+#[derive(Debug, Clone, Copy)]
+pub struct ClusterMergeEvent {
+    /// Labels, sizes and step of the merge (see `lineage`).
+    pub merge: MergeEvent,
+    /// Center of mass of the merged cluster.
+    pub center_of_mass: [f64; 3],
+    /// Number of clusters left after the merge.
+    pub n_clusters: usize,
+}
+
+impl ClusterMergeEvent {
+    pub fn new(merge: MergeEvent, center_of_mass: Vector3, n_clusters: usize) -> Self {
+        Self {
+            merge,
+            center_of_mass: [center_of_mass.x, center_of_mass.y, center_of_mass.z],
+            n_clusters,
+        }
+    }
+}
+
+/// Overall progress of a run.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressEvent {
+    /// Particles placed (particle-cluster engines) or merges done (cluster-cluster engines).
+    pub step: usize,
+    /// Particles in the agglomerate (the largest cluster for cluster-cluster engines).
+    pub n_particles: usize,
+    /// Particles the run was asked for.
+    pub n_target: usize,
+    /// Clusters left (1 for particle-cluster engines).
+    pub n_clusters: usize,
+    /// Radius of gyration of the agglomerate (largest cluster).
+    pub radius_of_gyration: f64,
+    /// Seconds since the run started.
+    pub elapsed: f64,
+}
+
+impl ProgressEvent {
+    /// Progress of a particle-cluster run, whose step is the agglomerate size.
+    pub fn particles(n_particles: usize, n_target: usize, radius_of_gyration: f64, start: Instant) -> Self {
+        Self::merges(n_particles, n_particles, n_target, 1, radius_of_gyration, start)
+    }
+
+    /// Progress of a cluster-cluster run after `merges` merges.
+    pub fn merges(
+        merges: usize,
+        n_particles: usize,
+        n_target: usize,
+        n_clusters: usize,
+        radius_of_gyration: f64,
+        start: Instant,
+    ) -> Self {
+        Self {
+            step: merges,
+            n_particles,
+            n_target,
+            n_clusters,
+            radius_of_gyration,
+            elapsed: start.elapsed().as_secs_f64(),
+        }
+    }
+}
+
+/// Random-walk steps between two [`EventHooks::on_poll`] calls.
+pub const POLL_STEPS: usize = 1024;
+
+/// Receiver of engine events.
+pub trait EventHooks: Send {
+    fn on_stick(&mut self, _event: &StickEvent) -> Flow {
+        Flow::Continue
+    }
+
+    fn on_merge(&mut self, _event: &ClusterMergeEvent) -> Flow {
+        Flow::Continue
+    }
+
+    fn on_progress(&mut self, _event: &ProgressEvent) -> Flow {
+        Flow::Continue
+    }
+
+    /// Whether a progress report at `step` would be used, for engines that
+    /// skip the Rg of a report nobody reads.
+    fn wants_progress(&self, _step: usize) -> bool {
+        false
+    }
+
+    /// Whether the engine should build a snapshot of its particles after `event`.
+    fn wants_snapshot(&self, _event: &ProgressEvent) -> bool {
+        false
+    }
+
+    fn on_snapshot(&mut self, _snapshot: Snapshot) {}
+
+    /// Called while a walker has not landed yet, so a run can stop between
+    /// two placements that are far apart.
+    fn on_poll(&mut self) -> Flow {
+        Flow::Continue
+    }
+}
+
+/// Hooks that ignore every event, for driving the engines from Rust.
+pub struct NoHooks;
+
+impl EventHooks for NoHooks {}
+
+/// Hooks that stop the run after a fixed number of events, for tests.
+#[cfg(test)]
+pub struct StopAfter(pub usize);
+
+#[cfg(test)]
+impl StopAfter {
+    fn count(&mut self) -> Flow {
+        self.0 = self.0.saturating_sub(1);
+        if self.0 == 0 {
+            Flow::Stop
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+impl EventHooks for StopAfter {
+    fn on_stick(&mut self, _event: &StickEvent) -> Flow {
+        self.count()
+    }
+
+    fn on_merge(&mut self, _event: &ClusterMergeEvent) -> Flow {
+        self.count()
+    }
+}
+
+/// Hooks that cancel the run at the n-th poll, for tests.
+#[cfg(test)]
+pub struct CancelAtPoll(pub usize);
+
+#[cfg(test)]
+impl EventHooks for CancelAtPoll {
+    fn on_poll(&mut self) -> Flow {
+        self.0 = self.0.saturating_sub(1);
+        if self.0 == 0 {
+            Flow::Stop
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+/// Time between two checks for Ctrl-C and the cancel event.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Forwards events to Python callables.
+///
+/// The engines run with the GIL released; each call re-acquires it. Only
+/// every `every`-th event is forwarded, so cheap callbacks on large runs do
+/// not serialize the engine on the GIL. A callback returning `False` stops
+/// the run; an exception also stops it and is re-raised by [`PyCallbacks::finish`].
+/// The progress callback fires every `progress_every` particles placed or
+/// merges done, also when an engine reports its progress less often.
+///
+/// On progress reports and polls, at most every 50 ms, the hooks also check for
+/// Ctrl-C (re-raised as `KeyboardInterrupt` by `finish`) and for a set
+/// cancel event, which stops the run with a partial result. With a history
+/// recorder, every progress report is also offered to it.
+pub struct PyCallbacks {
+    on_stick: Option<PyObject>,
+    on_merge: Option<PyObject>,
+    every: usize,
+    stick_count: usize,
+    merge_count: usize,
+    progress: Option<PyObject>,
+    progress_every: usize,
+    next_progress: usize,
+    cancel_event: Option<PyObject>,
+    last_poll: Instant,
+    cancelled: bool,
+    history: Option<HistoryRecorder>,
+    error: Option<PyErr>,
+}
+
+impl PyCallbacks {
+    pub fn new(on_stick: Option<PyObject>, on_merge: Option<PyObject>, every: usize) -> PyResult<Self> {
+        if every == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "callback_every must be at least 1",
+            ));
+        }
+        Ok(Self {
+            on_stick,
+            on_merge,
+            every,
+            stick_count: 0,
+            merge_count: 0,
+            progress: None,
+            progress_every: 1,
+            next_progress: 1,
+            cancel_event: None,
+            last_poll: Instant::now(),
+            cancelled: false,
+            history: None,
+            error: None,
+        })
+    }
+
+    /// Also report the run's progress to `callback` every `every` particles or merges.
+    pub fn with_progress(mut self, callback: Option<PyObject>, every: usize) -> PyResult<Self> {
+        if every == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "progress_every must be at least 1",
+            ));
+        }
+        self.progress = callback;
+        self.progress_every = every;
+        self.next_progress = every;
+        Ok(self)
+    }
+
+    /// Stop the run once `event.is_set()` is true (e.g. a `threading.Event`).
+    pub fn with_cancel_event(mut self, event: Option<PyObject>) -> Self {
+        self.cancel_event = event;
+        self
+    }
+
+    /// Record the growth history as asked by `params`.
+    pub fn with_history(mut self, params: Option<PyHistoryParams>) -> PyResult<Self> {
+        if let Some(params) = params {
+            params.validate()?;
+            self.history = Some(HistoryRecorder::new(&params));
+        }
+        Ok(self)
+    }
+
+    /// The recorded growth history, if one was asked for.
+    pub fn take_history(&mut self) -> Option<PyGrowthHistory> {
+        self.history.take().map(HistoryRecorder::finish)
+    }
+
+    /// Re-raise the first exception raised by a callback or Ctrl-C, if any,
+    /// and note a cancelled run in `warnings`.
+    pub fn finish(self, warnings: &mut Vec<String>) -> PyResult<()> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        if self.cancelled {
+            warnings.push("run cancelled through cancel_event; the result is partial".to_string());
+        }
+        Ok(())
+    }
+
+    /// Check for Ctrl-C and the cancel event, at most every `CANCEL_POLL_INTERVAL`.
+    fn poll_cancel(&mut self) -> Flow {
+        if self.last_poll.elapsed() < CANCEL_POLL_INTERVAL {
+            return Flow::Continue;
+        }
+        self.last_poll = Instant::now();
+        let cancel_event = &self.cancel_event;
+        let polled = Python::with_gil(|py| -> PyResult<bool> {
+            py.check_signals()?;
+            match cancel_event {
+                Some(event) => event.call_method0(py, "is_set")?.is_truthy(py),
+                None => Ok(false),
+            }
+        });
+        match polled {
+            Ok(false) => Flow::Continue,
+            Ok(true) => {
+                self.cancelled = true;
+                Flow::Stop
+            }
+            Err(err) => {
+                self.error = Some(err);
+                Flow::Stop
+            }
+        }
+    }
+
+    /// Call `callback(event)` with the GIL held, mapping the result to a `Flow`.
+    fn call<F>(callback: &PyObject, error: &mut Option<PyErr>, build: F) -> Flow
+    where
+        F: for<'py> FnOnce(&Bound<'py, PyDict>) -> PyResult<()>,
+    {
+        Python::with_gil(|py| match Self::invoke(py, callback, build) {
+            Ok(true) => Flow::Stop,
+            Ok(false) => Flow::Continue,
+            Err(err) => {
+                *error = Some(err);
+                Flow::Stop
+            }
+        })
+    }
+
+    /// Build the event dict and call the callback; returns true if it asked to stop.
+    fn invoke<F>(py: Python<'_>, callback: &PyObject, build: F) -> PyResult<bool>
+    where
+        F: for<'py> FnOnce(&Bound<'py, PyDict>) -> PyResult<()>,
+    {
+        let event = PyDict::new(py);
+        build(&event)?;
+        let ret = callback.call1(py, (event,))?;
+        // Only an explicit `False` stops; `None` and other values continue
+        Ok(ret.bind(py).downcast::<PyBool>().is_ok_and(|b| !b.is_true()))
+    }
+}
+
+impl EventHooks for PyCallbacks {
+    fn on_stick(&mut self, event: &StickEvent) -> Flow {
+        let Some(callback) = &self.on_stick else {
+            return Flow::Continue;
+        };
+        self.stick_count += 1;
+        if !self.stick_count.is_multiple_of(self.every) {
+            return Flow::Continue;
+        }
+        Self::call(callback, &mut self.error, |d| {
+            d.set_item("particle", event.particle)?;
+            d.set_item("target", event.target)?;
+            d.set_item("position", event.position.to_vec())?;
+            d.set_item("radius", event.radius)?;
+            Ok(())
+        })
+    }
+
+    fn on_merge(&mut self, event: &ClusterMergeEvent) -> Flow {
+        let Some(callback) = &self.on_merge else {
+            return Flow::Continue;
+        };
+        self.merge_count += 1;
+        if !self.merge_count.is_multiple_of(self.every) {
+            return Flow::Continue;
+        }
+        let merge = event.merge;
+        Self::call(callback, &mut self.error, |d| {
+            d.set_item("step", merge.step)?;
+            d.set_item("first", merge.first)?;
+            d.set_item("second", merge.second)?;
+            d.set_item("merged", merge.merged)?;
+            d.set_item("first_size", merge.first_size)?;
+            d.set_item("second_size", merge.second_size)?;
+            d.set_item("center_of_mass", event.center_of_mass.to_vec())?;
+            d.set_item("n_clusters", event.n_clusters)?;
+            Ok(())
+        })
+    }
+
+    fn on_progress(&mut self, event: &ProgressEvent) -> Flow {
+        if let Some(history) = &mut self.history {
+            history.record(event);
+        }
+        if self.poll_cancel() == Flow::Stop {
+            return Flow::Stop;
+        }
+        let Some(callback) = &self.progress else {
+            return Flow::Continue;
+        };
+        if event.step < self.next_progress {
+            return Flow::Continue;
+        }
+        self.next_progress = (event.step / self.progress_every + 1) * self.progress_every;
+        Self::call(callback, &mut self.error, |d| {
+            d.set_item("step", event.step)?;
+            d.set_item("n_particles", event.n_particles)?;
+            d.set_item("n_target", event.n_target)?;
+            d.set_item("n_clusters", event.n_clusters)?;
+            d.set_item("radius_of_gyration", event.radius_of_gyration)?;
+            d.set_item("elapsed", event.elapsed)?;
+            Ok(())
+        })
+    }
+
+    fn wants_progress(&self, step: usize) -> bool {
+        self.last_poll.elapsed() >= CANCEL_POLL_INTERVAL
+            || (self.progress.is_some() && step >= self.next_progress)
+            || self.history.as_ref().is_some_and(|h| h.is_due(step))
+    }
+
+    fn wants_snapshot(&self, event: &ProgressEvent) -> bool {
+        self.history.as_ref().is_some_and(|h| h.wants_snapshot(event))
+    }
+
+    fn on_snapshot(&mut self, snapshot: Snapshot) {
+        if let Some(history) = &mut self.history {
+            history.add_snapshot(snapshot);
+        }
+    }
+
+    fn on_poll(&mut self) -> Flow {
+        self.poll_cancel()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stick_event_describes_last_particle() {
+        let mut particles = ParticleStore::with_capacity(2);
+        particles.push(Sphere::new(Vector3::zero(), 1.0));
+        particles.push(Sphere::new(Vector3::new(2.0, 0.0, 0.0), 0.5));
+        let event = StickEvent::last_of(&particles, Some(0));
+        assert_eq!(event.particle, 1);
+        assert_eq!(event.target, Some(0));
+        assert_eq!(event.position, [2.0, 0.0, 0.0]);
+        assert_eq!(event.radius, 0.5);
+    }
+}
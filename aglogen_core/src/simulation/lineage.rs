@@ -0,0 +1,84 @@
+//! Merge history of cluster-cluster aggregation runs.
+//!
+//! Clusters are labelled like the nodes of a dendrogram (scipy `linkage`
+//! convention): the k initial clusters get labels `0..k`, and the m-th merge
+//! creates the new label `k + m`. The list of merge events is therefore the
+//! lineage tree of the final agglomerate.
+
+use serde::{Deserialize, Serialize};
+
+/// One cluster-cluster merge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeEvent {
+    /// Main-loop iteration at which the merge happened.
+    pub step: usize,
+    /// Label of the stationary (impacted) cluster.
+    pub first: u32,
+    /// Label of the moving (impactor) cluster.
+    pub second: u32,
+    /// Label given to the merged cluster.
+    pub merged: u32,
+    /// Number of particles in `first` before the merge.
+    pub first_size: usize,
+    /// Number of particles in `second` before the merge.
+    pub second_size: usize,
+}
+
+/// Records merge events and hands out labels for merged clusters.
+pub(crate) struct Lineage {
+    events: Vec<MergeEvent>,
+    next_label: u32,
+}
+
+impl Lineage {
+    /// Start a history for `n_initial` clusters labelled `0..n_initial`.
+    pub(crate) fn new(n_initial: usize) -> Self {
+        Self {
+            events: Vec::new(),
+            next_label: n_initial as u32,
+        }
+    }
+
+    /// Record a merge of two `(label, size)` clusters; the returned event
+    /// carries the label of the merged cluster.
+    pub(crate) fn record(&mut self, step: usize, first: (u32, usize), second: (u32, usize)) -> MergeEvent {
+        let event = MergeEvent {
+            step,
+            first: first.0,
+            second: second.0,
+            merged: self.next_label,
+            first_size: first.1,
+            second_size: second.1,
+        };
+        self.next_label += 1;
+        self.events.push(event);
+        event
+    }
+
+    /// Number of merges recorded so far.
+    pub(crate) fn n_merges(&self) -> usize {
+        self.events.len()
+    }
+
+    pub(crate) fn into_events(self) -> Vec<MergeEvent> {
+        self.events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merged_labels_follow_initial_ones() {
+        let mut lineage = Lineage::new(3);
+        let a = lineage.record(1, (0, 1), (2, 1)).merged;
+        let b = lineage.record(4, (a, 2), (1, 1)).merged;
+        assert_eq!((a, b), (3, 4));
+
+        let events = lineage.into_events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].first, 3);
+        assert_eq!(events[1].first_size + events[1].second_size, 3);
+    }
+}
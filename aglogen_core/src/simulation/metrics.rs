@@ -1,7 +1,14 @@
 //! Agglomerate metrics calculation.
 
-use crate::common::geometry::Vector3;
+use crate::common::fitting::{fit_linear_region, FitMethod, LinearRegionParams};
+use crate::common::geometry::{Sphere, Vector3};
+use crate::common::hull::ConvexHull;
+use crate::common::spatial::SpatialHash;
+use crate::fractal::box_counting_3d::generate_sphere_points;
+use crate::simulation::tunable::fit_fractal_dimension_from_evolution;
 use nalgebra::{Matrix3, SymmetricEigen};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// Results from inertia tensor analysis.
 #[derive(Debug, Clone)]
@@ -68,96 +75,407 @@ pub fn calculate_radius_of_gyration(coordinates: &[[f64; 3]], radii: &[f64]) ->
     }
 }
 
+/// Rg of the nested sub-clusters formed by the k particles nearest to the
+/// center of gravity, for `points` log-spaced k from `k_min` up to all N.
+///
+/// Returns the sizes and their Rg; a single structure's analogue of the
+/// (N, Rg) evolution the engines record while growing.
+pub fn mass_radius_profile(coordinates: &[[f64; 3]], radii: &[f64], k_min: usize, points: usize) -> (Vec<usize>, Vec<f64>) {
+    let n = coordinates.len();
+    if n == 0 {
+        return (Vec::new(), Vec::new());
+    }
+    let cg = calculate_center_of_gravity(coordinates, radii);
+    let distance = |c: &[f64; 3]| Vector3::new(c[0], c[1], c[2]).distance_squared_to(&cg);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| distance(&coordinates[i]).total_cmp(&distance(&coordinates[j])));
+
+    let k_min = k_min.clamp(1, n) as f64;
+    let steps = points.max(2) - 1;
+    let mut sizes: Vec<usize> = (0..=steps)
+        .map(|i| (k_min * (n as f64 / k_min).powf(i as f64 / steps as f64)).round() as usize)
+        .collect();
+    sizes.dedup();
+
+    let mut subset_coords = Vec::with_capacity(n);
+    let mut subset_radii = Vec::with_capacity(n);
+    let mut rg = Vec::with_capacity(sizes.len());
+    for &k in &sizes {
+        while subset_coords.len() < k {
+            let i = order[subset_coords.len()];
+            subset_coords.push(coordinates[i]);
+            subset_radii.push(radii[i]);
+        }
+        rg.push(calculate_radius_of_gyration(&subset_coords, &subset_radii));
+    }
+    (sizes, rg)
+}
+
+/// Power-law fit N = kf (Rg/rp)^Df to the (N, Rg) evolution of a run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FractalFit {
+    pub df: f64,
+    pub kf: f64,
+    /// R² of the log-log regression over the linear region
+    pub r_squared: f64,
+    /// Standard error of Df from the regression (NaN without a fit)
+    pub df_std_error: f64,
+    /// Number of (N, Rg) samples in the linear region
+    pub n_points: usize,
+    /// `start..end` indices into the samples of the linear region
+    pub linear_region: (usize, usize),
+}
+
+impl FractalFit {
+    /// Default Df = 2, kf = 1 reported when there is nothing to fit.
+    pub fn fallback() -> Self {
+        Self {
+            df: 2.0,
+            kf: 1.0,
+            r_squared: 0.0,
+            df_std_error: f64::NAN,
+            n_points: 0,
+            linear_region: (0, 0),
+        }
+    }
+}
+
 /// Calculate fractal dimension from Rg vs N data using log-log regression.
-/// Returns (Df, kf, R2)
-pub fn calculate_fractal_dimension(n_values: &[usize], rg_values: &[f64]) -> (f64, f64, f64) {
+///
+/// The fit is restricted to the linear region of the log-log data (see
+/// `common::fitting`), reported as a `start..end` range of indices into
+/// `n_values`/`rg_values`. Fallback values and clamping of the fitted
+/// parameters are reported in `warnings`.
+pub fn calculate_fractal_dimension(
+    n_values: &[usize],
+    rg_values: &[f64],
+    warnings: &mut Vec<String>,
+) -> FractalFit {
+    fit_fractal_dimension(n_values, rg_values, FitMethod::Ols, warnings)
+}
+
+/// Fractal dimension from Rg vs N data, with the line through the linear
+/// region fitted by `method`.
+///
+/// WLS weights every sample by its particle count N, the Rg of larger
+/// sub-clusters averaging over more particles.
+pub fn fit_fractal_dimension(
+    n_values: &[usize],
+    rg_values: &[f64],
+    method: FitMethod,
+    warnings: &mut Vec<String>,
+) -> FractalFit {
     if n_values.len() < 3 || n_values.len() != rg_values.len() {
-        return (2.0, 1.0, 0.0);
+        warnings.push(insufficient_fit_warning(n_values.len()));
+        return FractalFit::fallback();
     }
 
-    // Filter valid data points (N > 1, Rg > 0)
-    let data: Vec<(f64, f64)> = n_values
+    // Filter valid data points (N > 1, Rg > 0), remembering where they came from
+    let (indices, (xs, ys)): (Vec<usize>, (Vec<f64>, Vec<f64>)) = n_values
         .iter()
         .zip(rg_values.iter())
-        .filter(|(&n, &rg)| n > 1 && rg > 0.0)
-        .map(|(&n, &rg)| ((n as f64).ln(), rg.ln()))
-        .collect();
-
-    if data.len() < 3 {
-        return (2.0, 1.0, 0.0);
+        .enumerate()
+        .filter(|(_, (&n, &rg))| n > 1 && rg > 0.0)
+        .map(|(i, (&n, &rg))| (i, ((n as f64).ln(), rg.ln())))
+        .unzip();
+
+    if indices.len() < 3 {
+        warnings.push(insufficient_fit_warning(indices.len()));
+        return FractalFit::fallback();
     }
 
-    // Linear regression on log-log data
-    let n = data.len() as f64;
-    let sum_x: f64 = data.iter().map(|(x, _)| x).sum();
-    let sum_y: f64 = data.iter().map(|(_, y)| y).sum();
-    let sum_xx: f64 = data.iter().map(|(x, _)| x * x).sum();
-    let sum_xy: f64 = data.iter().map(|(x, y)| x * y).sum();
-
-    let slope = (n * sum_xy - sum_x * sum_y) / (n * sum_xx - sum_x * sum_x);
-    let intercept = (sum_y - slope * sum_x) / n;
+    // Linear regression on the linear region of the log-log data
+    let linear = fit_linear_region(&xs, &ys, &LinearRegionParams::for_evolution(xs.len()));
+    let sizes: Vec<f64> = xs[linear.start..linear.end].iter().map(|x| x.exp()).collect();
+    let fit = method.fit(&xs[linear.start..linear.end], &ys[linear.start..linear.end], &sizes);
+    let slope = fit.slope;
+    let intercept = fit.intercept;
+    let region = fit_region_indices(&indices, linear.start, linear.end);
 
     // Df = 1/slope (from N ~ Rg^Df, so log(N) ~ Df * log(Rg))
     // Actually: Rg ~ N^(1/Df), so log(Rg) ~ (1/Df) * log(N)
     // slope = 1/Df, so Df = 1/slope
-    let df = if slope.abs() > 0.01 { 1.0 / slope } else { 2.0 };
+    let df = if slope.abs() > 0.01 {
+        1.0 / slope
+    } else {
+        warnings.push(format!(
+            "Rg-N regression slope {:.4} is too flat to invert; reporting Df=2.0",
+            slope
+        ));
+        2.0
+    };
 
     // kf from intercept: log(Rg) = intercept + slope*log(N)
     // Rg = exp(intercept) * N^slope = kf^(1/Df) * N^(1/Df)
     // So kf = exp(intercept * Df)
     let kf = (intercept * df).exp();
 
-    // R-squared
-    let mean_y = sum_y / n;
-    let ss_tot: f64 = data.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
-    let ss_res: f64 = data
+    // Propagate the slope error through Df = 1/slope
+    let df_std_error = fit.std_error / (slope * slope);
+
+    let (df, kf) = clamp_fitted_parameters(df, kf, (1.0, 3.0), (0.1, f64::INFINITY), warnings);
+    FractalFit {
+        df,
+        kf,
+        r_squared: fit.r_squared,
+        df_std_error,
+        n_points: linear.end - linear.start,
+        linear_region: region,
+    }
+}
+
+/// Regression a run fitted to its (N, Rg) evolution, recorded so that a
+/// refit with another estimator reproduces the run's Df and kf under OLS.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EvolutionFit {
+    /// ln(Rg / scale) on ln N, as [`fit_fractal_dimension`]
+    RgOnN { scale: f64 },
+    /// ln N on ln(Rg / rp), as the tunable engines
+    NOnRg { rp: f64 },
+}
+
+impl Default for EvolutionFit {
+    fn default() -> Self {
+        Self::RgOnN { scale: 1.0 }
+    }
+}
+
+impl EvolutionFit {
+    /// Fit Df and kf to the (N, Rg) samples with `method`.
+    pub fn fit(&self, n_values: &[usize], rg_values: &[f64], method: FitMethod, warnings: &mut Vec<String>) -> FractalFit {
+        match *self {
+            Self::RgOnN { scale } => {
+                let rg_scaled: Vec<f64> = rg_values.iter().map(|rg| rg / scale).collect();
+                fit_fractal_dimension(n_values, &rg_scaled, method, warnings)
+            }
+            Self::NOnRg { rp } => fit_fractal_dimension_from_evolution(n_values, rg_values, rp, method, warnings),
+        }
+    }
+}
+
+/// Map a `start..end` region of filtered fit data back to indices of the unfiltered samples.
+pub fn fit_region_indices(indices: &[usize], start: usize, end: usize) -> (usize, usize) {
+    if start >= end {
+        return (0, 0);
+    }
+    (indices[start], indices[end - 1] + 1)
+}
+
+/// Warning text for a fit that had too few usable (N, Rg) samples.
+pub fn insufficient_fit_warning(n_points: usize) -> String {
+    format!(
+        "only {} usable (N, Rg) samples for the Df fit; reporting defaults Df=2.0, kf=1.0",
+        n_points
+    )
+}
+
+/// Clamp fitted (Df, kf) to the given ranges, recording a warning for each change.
+pub fn clamp_fitted_parameters(
+    df: f64,
+    kf: f64,
+    df_range: (f64, f64),
+    kf_range: (f64, f64),
+    warnings: &mut Vec<String>,
+) -> (f64, f64) {
+    let df_clamped = df.clamp(df_range.0, df_range.1);
+    let kf_clamped = kf.clamp(kf_range.0, kf_range.1);
+    if df_clamped != df {
+        warnings.push(format!(
+            "fitted Df={:.4} is outside [{}, {}] and was clamped to {}",
+            df, df_range.0, df_range.1, df_clamped
+        ));
+    }
+    if kf_clamped != kf {
+        warnings.push(format!(
+            "fitted kf={:.4} is outside [{}, {}] and was clamped to {}",
+            kf, kf_range.0, kf_range.1, kf_clamped
+        ));
+    }
+    (df_clamped, kf_clamped)
+}
+
+/// A pair of particles in contact, `i < j`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub i: usize,
+    pub j: usize,
+    /// Center distance over r_i + r_j: 1 when touching, below 1 when sintered.
+    pub sintering_coeff: f64,
+    /// r_i + r_j minus the center distance, the depth of the neck
+    /// (slightly negative for gaps within the tolerance).
+    pub overlap: f64,
+}
+
+/// Find every pair of particles in contact.
+///
+/// Two particles are in contact when they touch at r1+r2 or overlap
+/// (sintered contacts) at < r1+r2. The tolerance parameter adds a small
+/// buffer above contact distance to account for numerical precision in
+/// particle placement.
+///
+/// Candidate pairs come from a spatial hash with cells as wide as the
+/// largest contact distance, so the search is linear in N; contacts are
+/// sorted by `(i, j)`.
+pub fn calculate_contacts(coordinates: &[[f64; 3]], radii: &[f64], tolerance: f64) -> Vec<Contact> {
+    let max_radius = radii.iter().copied().fold(0.0, f64::max);
+    let cell_size = 2.0 * max_radius + tolerance;
+    if coordinates.len() < 2 || cell_size.is_nan() || cell_size <= 0.0 {
+        return Vec::new();
+    }
+
+    let spheres: Vec<Sphere> = coordinates
         .iter()
-        .map(|(x, y)| {
-            let y_pred = intercept + slope * x;
-            (y - y_pred).powi(2)
+        .zip(radii)
+        .map(|(c, &r)| Sphere::new(Vector3::new(c[0], c[1], c[2]), r))
+        .collect();
+    let mut hash = SpatialHash::new(cell_size);
+    for (i, sphere) in spheres.iter().enumerate() {
+        hash.insert(i, sphere);
+    }
+
+    (0..spheres.len())
+        .into_par_iter()
+        .flat_map_iter(|i| {
+            let mut neighbours: Vec<usize> = hash
+                .query_potential_collisions(&spheres[i])
+                .into_iter()
+                .filter(|&j| j > i)
+                .collect();
+            neighbours.sort_unstable();
+            let spheres = &spheres;
+            neighbours.into_iter().filter_map(move |j| {
+                let dist = spheres[i].center.distance_to(&spheres[j].center);
+                let contact_dist = radii[i] + radii[j];
+
+                // dist <= contact_dist catches sintered particles (closer than r1+r2)
+                // tolerance adds buffer for numerical precision at contact distance
+                (dist <= contact_dist + tolerance).then_some(Contact {
+                    i,
+                    j,
+                    sintering_coeff: dist / contact_dist,
+                    overlap: contact_dist - dist,
+                })
+            })
         })
-        .sum();
+        .collect()
+}
+
+/// Coordination number (number of neighbors) of each of `n` particles from their contacts.
+pub fn coordination_from_contacts(contacts: &[Contact], n: usize) -> Vec<u32> {
+    let mut coordination = vec![0u32; n];
+    for contact in contacts {
+        coordination[contact.i] += 1;
+        coordination[contact.j] += 1;
+    }
+    coordination
+}
+
+/// Overlap coefficient Cov = (r_i + r_j - d_ij) / (r_i + r_j) of each contact.
+///
+/// 0 for point contacts (gaps within the contact tolerance count as 0) and
+/// 0.5 for equal spheres one radius apart.
+pub fn overlap_coefficients(contacts: &[Contact]) -> Vec<f64> {
+    contacts.iter().map(|c| (1.0 - c.sintering_coeff).max(0.0)).collect()
+}
+
+/// Summary of the overlap coefficients of an agglomerate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapStatistics {
+    pub mean: f64,
+    /// Population standard deviation.
+    pub std: f64,
+    /// `bins + 1` edges from 0 to the histogram's upper bound.
+    pub bin_edges: Vec<f64>,
+    /// Contacts per bin; the last bin includes its upper edge, values above it are left out.
+    pub counts: Vec<usize>,
+}
 
-    let r2 = if ss_tot > 0.0 {
-        1.0 - ss_res / ss_tot
+/// Mean, standard deviation and histogram of overlap coefficients on
+/// `bins` equal bins over [0, `max_overlap`].
+pub fn overlap_statistics(coefficients: &[f64], bins: usize, max_overlap: f64) -> OverlapStatistics {
+    let n = coefficients.len() as f64;
+    let (mean, std) = if coefficients.is_empty() {
+        (0.0, 0.0)
     } else {
-        0.0
+        let mean = coefficients.iter().sum::<f64>() / n;
+        let variance = coefficients.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / n;
+        (mean, variance.sqrt())
     };
 
-    (df.max(1.0).min(3.0), kf.max(0.1), r2)
+    let width = max_overlap / bins as f64;
+    let mut counts = vec![0; bins];
+    for &c in coefficients {
+        if c <= max_overlap {
+            counts[((c / width) as usize).min(bins - 1)] += 1;
+        }
+    }
+    OverlapStatistics {
+        mean,
+        std,
+        bin_edges: (0..=bins).map(|k| k as f64 * width).collect(),
+        counts,
+    }
 }
 
-/// Calculate coordination number (number of neighbors) for each particle.
-///
-/// Two particles are considered neighbors if they are in contact, which includes:
-/// - Particles touching at r1+r2 (no sintering)
-/// - Particles overlapping at < r1+r2 (sintered contacts)
+/// Exposed surface of an agglomerate of (possibly sintered) spheres.
+#[derive(Debug, Clone, Default)]
+pub struct SurfaceAreaResult {
+    /// Sum of `particle_areas`.
+    pub total: f64,
+    /// Surface of each particle not buried in an overlapping neighbour.
+    pub particle_areas: Vec<f64>,
+    /// Radius of the neck circle of each contact, parallel to the contacts
+    /// (0 for point contacts).
+    pub neck_radii: Vec<f64>,
+}
+
+/// Exposed surface area from the contacts of an agglomerate.
 ///
-/// The tolerance parameter adds a small buffer above contact distance to account
-/// for numerical precision in particle placement.
-pub fn calculate_coordination(coordinates: &[[f64; 3]], radii: &[f64], tolerance: f64) -> Vec<u32> {
-    let n = coordinates.len();
-    let mut coordination = vec![0u32; n];
+/// Two overlapping spheres hide a spherical cap of each other, cut by the
+/// plane of their intersection circle (the neck): a cap of height h on a
+/// sphere of radius r has area 2 pi r h. Each particle keeps 4 pi r² minus
+/// the caps of its contacts. The result is exact as long as the caps on one
+/// particle do not overlap each other, i.e. up to moderate sintering; past
+/// that the area is underestimated (and clamped at 0 per particle).
+pub fn calculate_surface_area(radii: &[f64], contacts: &[Contact]) -> SurfaceAreaResult {
+    use std::f64::consts::PI;
 
-    for i in 0..n {
-        for j in (i + 1)..n {
-            let dx = coordinates[i][0] - coordinates[j][0];
-            let dy = coordinates[i][1] - coordinates[j][1];
-            let dz = coordinates[i][2] - coordinates[j][2];
-            let dist = (dx * dx + dy * dy + dz * dz).sqrt();
-            let contact_dist = radii[i] + radii[j];
-
-            // Particles are neighbors if they touch or overlap (sintered)
-            // dist <= contact_dist catches sintered particles (closer than r1+r2)
-            // tolerance adds buffer for numerical precision at contact distance
-            if dist <= contact_dist + tolerance {
-                coordination[i] += 1;
-                coordination[j] += 1;
-            }
+    let mut particle_areas: Vec<f64> = radii.iter().map(|r| 4.0 * PI * r * r).collect();
+    let mut neck_radii = Vec::with_capacity(contacts.len());
+
+    for contact in contacts {
+        let (ri, rj) = (radii[contact.i], radii[contact.j]);
+        if contact.overlap <= 0.0 {
+            neck_radii.push(0.0);
+            continue;
         }
+        let d = ri + rj - contact.overlap;
+        let (hi, hj, neck) = if d <= (ri - rj).abs() {
+            // One sphere lies entirely inside the other
+            if ri >= rj {
+                (0.0, 2.0 * rj, 0.0)
+            } else {
+                (2.0 * ri, 0.0, 0.0)
+            }
+        } else {
+            // Distance from each center to the plane of the neck
+            let xi = (d * d + ri * ri - rj * rj) / (2.0 * d);
+            let xj = d - xi;
+            (ri - xi, rj - xj, (ri * ri - xi * xi).max(0.0).sqrt())
+        };
+        particle_areas[contact.i] -= 2.0 * PI * ri * hi;
+        particle_areas[contact.j] -= 2.0 * PI * rj * hj;
+        neck_radii.push(neck);
     }
 
-    coordination
+    for area in &mut particle_areas {
+        *area = area.max(0.0);
+    }
+    SurfaceAreaResult {
+        total: particle_areas.iter().sum(),
+        particle_areas,
+        neck_radii,
+    }
 }
 
 /// Calculate porosity of agglomerate within bounding box.
@@ -183,6 +501,251 @@ pub fn calculate_porosity(coordinates: &[[f64; 3]], radii: &[f64]) -> f64 {
     }
 }
 
+/// Envelope the particle volume is compared with to get the porosity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PorosityMethod {
+    /// Sphere of radius 2 Rg, as reported by the engines
+    BoundingSphere,
+    /// Convex hull of the particles
+    ConvexHull,
+    /// Voxelized region a probe sphere of the mean particle radius cannot
+    /// reach from outside
+    Voxel,
+}
+
+impl PorosityMethod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bounding_sphere" => Some(PorosityMethod::BoundingSphere),
+            "convex_hull" => Some(PorosityMethod::ConvexHull),
+            "voxel" => Some(PorosityMethod::Voxel),
+            _ => None,
+        }
+    }
+}
+
+/// Directions along which the convex hull of the particles is sampled.
+const HULL_DIRECTIONS: usize = 2000;
+
+/// Largest number of voxels along an edge of the envelope grid.
+const ENVELOPE_MAX_VOXELS: f64 = 200.0;
+
+/// Volume of the convex hull of the particles (of the spheres, not their centers).
+///
+/// The hull of a union of spheres is approximated by the hull of its
+/// support points along `HULL_DIRECTIONS` directions, which lies inside it
+/// (0.3% smaller for a single sphere).
+pub fn convex_hull_volume(coordinates: &[[f64; 3]], radii: &[f64]) -> f64 {
+    if coordinates.is_empty() {
+        return 0.0;
+    }
+    let support: Vec<Vector3> = generate_sphere_points(0.0, 0.0, 0.0, 1.0, HULL_DIRECTIONS)
+        .into_par_iter()
+        .map(|[x, y, z]| {
+            let u = Vector3::new(x, y, z);
+            let (c, r) = coordinates
+                .iter()
+                .zip(radii)
+                .map(|(c, &r)| (Vector3::new(c[0], c[1], c[2]), r))
+                .max_by(|a, b| (a.0.dot(&u) + a.1).total_cmp(&(b.0.dot(&u) + b.1)))
+                .expect("at least one particle");
+            c + u * r
+        })
+        .collect();
+    ConvexHull::new(&support).map_or(0.0, |hull| hull.volume())
+}
+
+/// Volume a probe sphere of `probe_radius` cannot reach from outside the particles.
+///
+/// The probe rolls over the agglomerate on a voxel grid (spacing a quarter
+/// of the mean radius, coarser for large agglomerates); the envelope is
+/// the particles plus the pores and crevices too narrow for the probe.
+pub fn voxel_envelope_volume(coordinates: &[[f64; 3]], radii: &[f64], probe_radius: f64) -> f64 {
+    let n = coordinates.len();
+    if n == 0 {
+        return 0.0;
+    }
+    let mean_radius = radii.iter().sum::<f64>() / n as f64;
+    let mut lo = [f64::INFINITY; 3];
+    let mut hi = [f64::NEG_INFINITY; 3];
+    for (c, r) in coordinates.iter().zip(radii) {
+        for k in 0..3 {
+            lo[k] = lo[k].min(c[k] - r);
+            hi[k] = hi[k].max(c[k] + r);
+        }
+    }
+    let extent = (0..3).map(|k| hi[k] - lo[k]).fold(0.0, f64::max);
+    let spacing = (mean_radius / 4.0).max(extent / ENVELOPE_MAX_VOXELS);
+    // Free margin so the probe can go all around the agglomerate
+    let pad = probe_radius + 2.0 * spacing;
+    let dims: [usize; 3] = std::array::from_fn(|k| ((hi[k] - lo[k] + 2.0 * pad) / spacing).ceil() as usize + 1);
+    let origin: [f64; 3] = std::array::from_fn(|k| lo[k] - pad);
+    let index = |v: [usize; 3]| (v[0] * dims[1] + v[1]) * dims[2] + v[2];
+    let total = dims[0] * dims[1] * dims[2];
+
+    // Voxels the probe center cannot occupy
+    let mut blocked = vec![false; total];
+    for (c, &r) in coordinates.iter().zip(radii) {
+        let reach = r + probe_radius;
+        let range = |k: usize| {
+            let first = ((c[k] - reach - origin[k]) / spacing).floor().max(0.0) as usize;
+            let last = (((c[k] + reach - origin[k]) / spacing).ceil() as usize).min(dims[k] - 1);
+            first..=last
+        };
+        for x in range(0) {
+            for y in range(1) {
+                for z in range(2) {
+                    let p = [x, y, z].map(|v| v as f64 * spacing);
+                    let d2 = (0..3).map(|k| (origin[k] + p[k] - c[k]).powi(2)).sum::<f64>();
+                    if d2 < reach * reach {
+                        blocked[index([x, y, z])] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // Probe positions reachable from the corner of the grid
+    let mut reachable = vec![false; total];
+    let mut stack = vec![[0, 0, 0]];
+    reachable[0] = true;
+    let mut rim = Vec::new();
+    while let Some(v) = stack.pop() {
+        let mut on_rim = false;
+        for k in 0..3 {
+            for step in [-1isize, 1] {
+                let w = v[k] as isize + step;
+                if w < 0 || w >= dims[k] as isize {
+                    continue;
+                }
+                let mut u = v;
+                u[k] = w as usize;
+                let i = index(u);
+                if blocked[i] {
+                    on_rim = true;
+                } else if !reachable[i] {
+                    reachable[i] = true;
+                    stack.push(u);
+                }
+            }
+        }
+        if on_rim {
+            rim.push(v);
+        }
+    }
+
+    // Everything the probe sweeps is outside; it only reaches new voxels from the rim
+    let mut outside = reachable;
+    let steps = (probe_radius / spacing).floor() as isize;
+    for v in rim {
+        for dx in -steps..=steps {
+            for dy in -steps..=steps {
+                for dz in -steps..=steps {
+                    if ((dx * dx + dy * dy + dz * dz) as f64) * spacing * spacing > probe_radius * probe_radius {
+                        continue;
+                    }
+                    let u = [v[0] as isize + dx, v[1] as isize + dy, v[2] as isize + dz];
+                    if (0..3).all(|k| u[k] >= 0 && u[k] < dims[k] as isize) {
+                        outside[index(u.map(|w| w as usize))] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let inside = outside.iter().filter(|&&o| !o).count();
+    inside as f64 * spacing.powi(3)
+}
+
+/// Porosity of the agglomerate within the envelope chosen by `method`.
+pub fn porosity_by_method(coordinates: &[[f64; 3]], radii: &[f64], method: PorosityMethod) -> f64 {
+    use std::f64::consts::PI;
+
+    let envelope_volume = match method {
+        PorosityMethod::BoundingSphere => return calculate_porosity(coordinates, radii),
+        PorosityMethod::ConvexHull => convex_hull_volume(coordinates, radii),
+        PorosityMethod::Voxel => {
+            let mean_radius = radii.iter().sum::<f64>() / radii.len().max(1) as f64;
+            voxel_envelope_volume(coordinates, radii, mean_radius)
+        }
+    };
+    let particle_volume: f64 = radii.iter().map(|&r| (4.0 / 3.0) * PI * r * r * r).sum();
+
+    if envelope_volume > 0.0 {
+        1.0 - (particle_volume / envelope_volume).min(1.0)
+    } else {
+        1.0
+    }
+}
+
+/// Eigenvalues of a symmetric matrix in ascending order, with the matching
+/// eigenvectors as rows.
+fn sorted_eigen(matrix: Matrix3<f64>) -> ([f64; 3], [[f64; 3]; 3]) {
+    let eigen = SymmetricEigen::new(matrix);
+    let mut indices: Vec<usize> = (0..3).collect();
+    indices.sort_by(|&a, &b| eigen.eigenvalues[a].total_cmp(&eigen.eigenvalues[b]));
+
+    let values = [0, 1, 2].map(|i| eigen.eigenvalues[indices[i]]);
+    let vectors = [0, 1, 2].map(|i| {
+        let idx = indices[i];
+        [eigen.eigenvectors[(0, idx)], eigen.eigenvectors[(1, idx)], eigen.eigenvectors[(2, idx)]]
+    });
+    (values, vectors)
+}
+
+/// Results from gyration tensor analysis.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GyrationTensorResult {
+    /// Gyration tensor (3x3, symmetric)
+    pub tensor: [[f64; 3]; 3],
+    /// Principal moments (sorted: l1 <= l2 <= l3); they add up to Rg²
+    pub principal_moments: [f64; 3],
+    /// Principal axes (eigenvectors), each row is an axis
+    pub principal_axes: [[f64; 3]; 3],
+    /// Relative shape anisotropy kappa²: 0 for spherical symmetry, 1 for a rod
+    pub relative_shape_anisotropy: f64,
+}
+
+/// Calculate the gyration tensor and its principal components.
+///
+/// The gyration tensor S is the mass-weighted covariance of the particle
+/// positions, including the spread of mass inside each sphere:
+/// S = Σ mᵢ [rᵢ⊗rᵢ + (aᵢ²/5) I₃] / Σ mᵢ
+///
+/// where rᵢ is the position relative to the center of mass, aᵢ the radius
+/// and mᵢ ~ aᵢ³, so that trace(S) = Rg² as in `calculate_radius_of_gyration`.
+/// Unlike the inertia tensor its eigenvalues are the squared extents of the
+/// agglomerate along its principal axes.
+pub fn calculate_gyration_tensor(coordinates: &[[f64; 3]], radii: &[f64]) -> GyrationTensorResult {
+    let cg = calculate_center_of_gravity(coordinates, radii);
+    let mut tensor = Matrix3::zeros();
+    let mut total_mass = 0.0;
+    for (coord, &r) in coordinates.iter().zip(radii) {
+        let mass = r * r * r;
+        let d = nalgebra::Vector3::new(coord[0] - cg.x, coord[1] - cg.y, coord[2] - cg.z);
+        tensor += (d * d.transpose() + Matrix3::identity() * (r * r / 5.0)) * mass;
+        total_mass += mass;
+    }
+    if total_mass > 0.0 {
+        tensor /= total_mass;
+    }
+
+    let (principal_moments, principal_axes) = sorted_eigen(tensor);
+    let trace: f64 = principal_moments.iter().sum();
+    let relative_shape_anisotropy = if trace > 0.0 {
+        1.5 * principal_moments.iter().map(|l| l * l).sum::<f64>() / (trace * trace) - 0.5
+    } else {
+        0.0
+    };
+
+    GyrationTensorResult {
+        tensor: [0, 1, 2].map(|i| [tensor[(i, 0)], tensor[(i, 1)], tensor[(i, 2)]]),
+        principal_moments,
+        principal_axes,
+        relative_shape_anisotropy,
+    }
+}
+
 /// Calculate the inertia tensor and its principal components.
 ///
 /// The inertia tensor I is calculated as:
@@ -249,29 +812,8 @@ pub fn calculate_inertia_tensor(coordinates: &[[f64; 3]], radii: &[f64]) -> Iner
     );
 
     // Compute eigendecomposition
-    let eigen = SymmetricEigen::new(inertia_matrix);
-    let mut eigenvalues: Vec<f64> = eigen.eigenvalues.iter().copied().collect();
-    let eigenvectors = eigen.eigenvectors;
-
-    // Sort eigenvalues (and track indices for eigenvectors)
-    let mut indices: Vec<usize> = (0..3).collect();
-    indices.sort_by(|&a, &b| eigenvalues[a].partial_cmp(&eigenvalues[b]).unwrap());
-
-    let sorted_eigenvalues = [
-        eigenvalues[indices[0]].max(1e-10), // Avoid zero/negative
-        eigenvalues[indices[1]].max(1e-10),
-        eigenvalues[indices[2]].max(1e-10),
-    ];
-
-    // Extract sorted eigenvectors (principal axes)
-    let mut principal_axes = [[0.0; 3]; 3];
-    for (i, &idx) in indices.iter().enumerate() {
-        principal_axes[i] = [
-            eigenvectors[(0, idx)],
-            eigenvectors[(1, idx)],
-            eigenvectors[(2, idx)],
-        ];
-    }
+    let (eigenvalues, principal_axes) = sorted_eigen(inertia_matrix);
+    let sorted_eigenvalues = eigenvalues.map(|v| v.max(1e-10)); // Avoid zero/negative
 
     // Calculate shape descriptors
     let i1 = sorted_eigenvalues[0];
@@ -309,6 +851,46 @@ pub fn calculate_inertia_tensor(coordinates: &[[f64; 3]], radii: &[f64]) -> Iner
     }
 }
 
+/// Rotate a structure so its principal axes of inertia lie along x, y and z.
+///
+/// The structure is centered on its center of gravity and rotated so that
+/// the axis of smallest moment (the longest extent) ends on x and the axis
+/// of largest moment on z. Eigenvector signs are arbitrary, so x and y are
+/// oriented towards the side where the mass is skewed and z completes a
+/// right-handed frame, making the orientation canonical.
+///
+/// Returns the aligned coordinates and the rotation applied, whose rows are
+/// the principal axes: `aligned = rotation * (p - center_of_gravity)`.
+pub fn align_to_principal_axes(coordinates: &[[f64; 3]], radii: &[f64]) -> (Vec<[f64; 3]>, [[f64; 3]; 3]) {
+    let cg = calculate_center_of_gravity(coordinates, radii);
+    let centered: Vec<Vector3> = coordinates
+        .iter()
+        .map(|c| Vector3::new(c[0], c[1], c[2]) - cg)
+        .collect();
+
+    let axes = calculate_inertia_tensor(coordinates, radii).principal_axes;
+    let oriented = |axis: [f64; 3]| {
+        let axis = Vector3::new(axis[0], axis[1], axis[2]);
+        let skew: f64 = centered
+            .iter()
+            .zip(radii.iter())
+            .map(|(p, &r)| r * r * r * p.dot(&axis).powi(3))
+            .sum();
+        if skew < 0.0 {
+            axis * -1.0
+        } else {
+            axis
+        }
+    };
+    let x = oriented(axes[0]);
+    let y = oriented(axes[1]);
+    let z = x.cross(&y);
+
+    let rotation = [[x.x, x.y, x.z], [y.x, y.y, y.z], [z.x, z.y, z.z]];
+    let aligned = centered.iter().map(|p| [p.dot(&x), p.dot(&y), p.dot(&z)]).collect();
+    (aligned, rotation)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -377,6 +959,28 @@ mod tests {
         assert!(result.asphericity > 0.1);
     }
 
+    #[test]
+    fn test_gyration_tensor() {
+        // Trace is Rg² for any structure
+        let coords = vec![[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 1.5, 0.3], [0.5, -1.0, 2.0]];
+        let radii = vec![1.0, 0.8, 1.2, 1.0];
+        let gyration = calculate_gyration_tensor(&coords, &radii);
+        let trace: f64 = gyration.principal_moments.iter().sum();
+        assert!((trace - calculate_radius_of_gyration(&coords, &radii).powi(2)).abs() < 1e-10);
+        assert!(((0..3).map(|i| gyration.tensor[i][i]).sum::<f64>() - trace).abs() < 1e-10);
+
+        // A long chain of point-like particles is a rod along x
+        let chain: Vec<[f64; 3]> = (0..50).map(|i| [i as f64, 0.0, 0.0]).collect();
+        let gyration = calculate_gyration_tensor(&chain, &[1e-3; 50]);
+        assert!(gyration.relative_shape_anisotropy > 0.99);
+        assert!(gyration.principal_axes[2][0].abs() > 0.999);
+
+        // A single sphere is isotropic
+        let gyration = calculate_gyration_tensor(&[[1.0, 2.0, 3.0]], &[2.0]);
+        assert!(gyration.relative_shape_anisotropy.abs() < 1e-12);
+        assert!((gyration.principal_moments[0] - 0.8).abs() < 1e-12);
+    }
+
     #[test]
     fn test_coordination_touching_particles() {
         // Two particles touching at distance r1+r2=2.0
@@ -384,7 +988,7 @@ mod tests {
         let radii = vec![1.0, 1.0];
         let tolerance = 0.1;
 
-        let coord = calculate_coordination(&coords, &radii, tolerance);
+        let coord = coordination_from_contacts(&calculate_contacts(&coords, &radii, tolerance), coords.len());
         assert_eq!(coord[0], 1); // Particle 0 has 1 neighbor
         assert_eq!(coord[1], 1); // Particle 1 has 1 neighbor
     }
@@ -396,12 +1000,118 @@ mod tests {
         let radii = vec![1.0, 1.0];
         let tolerance = 0.1;
 
-        let coord = calculate_coordination(&coords, &radii, tolerance);
+        let coord = coordination_from_contacts(&calculate_contacts(&coords, &radii, tolerance), coords.len());
         // Sintered particles (closer than r1+r2) should still be detected as neighbors
         assert_eq!(coord[0], 1);
         assert_eq!(coord[1], 1);
     }
 
+    #[test]
+    fn test_contacts_report_sintering() {
+        let coords = vec![[0.0, 0.0, 0.0], [1.8, 0.0, 0.0], [5.0, 0.0, 0.0]];
+        let radii = vec![1.0, 1.0, 1.0];
+
+        let contacts = calculate_contacts(&coords, &radii, 0.1);
+        assert_eq!(contacts.len(), 1);
+        assert_eq!((contacts[0].i, contacts[0].j), (0, 1));
+        assert!((contacts[0].sintering_coeff - 0.9).abs() < 1e-12);
+        assert!((contacts[0].overlap - 0.2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_overlap_statistics() {
+        // Center distances of 1.0, 1.5 and 2.0 between unit spheres: Cov = 0.5, 0.25, 0
+        let coords = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.5, 0.0, 0.0], [4.5, 0.0, 0.0]];
+        let contacts = calculate_contacts(&coords, &[1.0; 4], 1e-6);
+        let cov = overlap_coefficients(&contacts);
+        assert_eq!(cov.len(), 3);
+        assert!((cov[0] - 0.5).abs() < 1e-12 && (cov[1] - 0.25).abs() < 1e-12 && cov[2] < 1e-12);
+
+        let stats = overlap_statistics(&cov, 5, 0.5);
+        assert!((stats.mean - 0.25).abs() < 1e-12);
+        assert_eq!(stats.counts, vec![1, 0, 1, 0, 1]);
+        assert_eq!(stats.bin_edges.len(), 6);
+        assert!((stats.bin_edges[5] - 0.5).abs() < 1e-12);
+        assert_eq!(overlap_statistics(&cov, 2, 0.3).counts, vec![1, 1]);
+    }
+
+    #[test]
+    fn test_surface_area_subtracts_caps() {
+        use std::f64::consts::PI;
+
+        // Unit spheres one radius apart: caps of height 1/2, neck radius sqrt(3)/2;
+        // the third sphere only touches the second
+        let coords = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [3.0, 0.0, 0.0]];
+        let radii = [1.0, 1.0, 1.0];
+        let contacts = calculate_contacts(&coords, &radii, 1e-6);
+        let surface = calculate_surface_area(&radii, &contacts);
+
+        assert_eq!(surface.neck_radii.len(), 2);
+        assert!((surface.neck_radii[0] - 3.0f64.sqrt() / 2.0).abs() < 1e-12);
+        assert!(surface.neck_radii[1].abs() < 1e-12);
+        assert!((surface.particle_areas[0] - 3.0 * PI).abs() < 1e-12);
+        assert!((surface.particle_areas[2] - 4.0 * PI).abs() < 1e-12);
+        assert!((surface.total - 10.0 * PI).abs() < 1e-12);
+
+        // A sphere inside a larger one is fully buried
+        let contacts = calculate_contacts(&[[0.0; 3], [0.5, 0.0, 0.0]], &[2.0, 0.5], 0.0);
+        let buried = calculate_surface_area(&[2.0, 0.5], &contacts);
+        assert_eq!(buried.particle_areas, vec![16.0 * PI, 0.0]);
+    }
+
+    #[test]
+    fn test_contacts_match_pair_scan() {
+        use rand::Rng;
+
+        let mut rng = crate::common::rng::create_rng(5);
+        let coords: Vec<[f64; 3]> = (0..500)
+            .map(|_| [rng.gen_range(-15.0..15.0), rng.gen_range(-15.0..15.0), rng.gen_range(-15.0..15.0)])
+            .collect();
+        let radii: Vec<f64> = (0..500).map(|_| rng.gen_range(0.5..1.5)).collect();
+
+        let mut expected = Vec::new();
+        for i in 0..coords.len() {
+            for j in (i + 1)..coords.len() {
+                let d = Vector3::new(coords[i][0], coords[i][1], coords[i][2])
+                    .distance_to(&Vector3::new(coords[j][0], coords[j][1], coords[j][2]));
+                if d <= radii[i] + radii[j] + 0.2 {
+                    expected.push((i, j));
+                }
+            }
+        }
+        let contacts = calculate_contacts(&coords, &radii, 0.2);
+        assert!(!expected.is_empty());
+        assert_eq!(contacts.iter().map(|c| (c.i, c.j)).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_envelope_volumes() {
+        use std::f64::consts::PI;
+
+        // A single sphere is its own hull and envelope
+        let sphere = 4.0 / 3.0 * PI;
+        let hull = convex_hull_volume(&[[1.0, 2.0, 3.0]], &[1.0]);
+        assert!(hull < sphere && hull > 0.995 * sphere, "hull = {}", hull);
+        let voxel = voxel_envelope_volume(&[[1.0, 2.0, 3.0]], &[1.0], 1.0);
+        assert!((voxel / sphere - 1.0).abs() < 0.1, "voxel = {}", voxel);
+
+        // Two touching spheres: the hull adds the waist, a large probe fills it too
+        let coords = [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0]];
+        let capsule = sphere + PI * 2.0;
+        let hull = convex_hull_volume(&coords, &[1.0, 1.0]);
+        assert!((hull / capsule - 1.0).abs() < 0.01, "hull = {}", hull);
+        let small_probe = voxel_envelope_volume(&coords, &[1.0, 1.0], 0.25);
+        let large_probe = voxel_envelope_volume(&coords, &[1.0, 1.0], 1.0);
+        assert!(small_probe < large_probe && large_probe < capsule);
+
+        // A hull around a chain is tighter than the 2 Rg sphere, so less porous
+        let chain: Vec<[f64; 3]> = (0..10).map(|i| [2.0 * i as f64, 0.0, 0.0]).collect();
+        let radii = [1.0; 10];
+        let hull_porosity = porosity_by_method(&chain, &radii, PorosityMethod::ConvexHull);
+        assert!(hull_porosity < porosity_by_method(&chain, &radii, PorosityMethod::BoundingSphere));
+        assert!(hull_porosity > 0.0);
+    }
+
     #[test]
     fn test_coordination_non_touching_particles() {
         // Two particles far apart at distance 5.0 (r1+r2=2.0)
@@ -409,7 +1119,7 @@ mod tests {
         let radii = vec![1.0, 1.0];
         let tolerance = 0.1;
 
-        let coord = calculate_coordination(&coords, &radii, tolerance);
+        let coord = coordination_from_contacts(&calculate_contacts(&coords, &radii, tolerance), coords.len());
         // Non-touching particles should have 0 neighbors
         assert_eq!(coord[0], 0);
         assert_eq!(coord[1], 0);
@@ -427,10 +1137,112 @@ mod tests {
         let radii = vec![1.0; 4];
         let tolerance = 0.1;
 
-        let coord = calculate_coordination(&coords, &radii, tolerance);
+        let coord = coordination_from_contacts(&calculate_contacts(&coords, &radii, tolerance), coords.len());
         assert_eq!(coord[0], 1); // End particle: 1 neighbor
         assert_eq!(coord[1], 2); // Middle particle: 2 neighbors
         assert_eq!(coord[2], 2); // Middle particle: 2 neighbors
         assert_eq!(coord[3], 1); // End particle: 1 neighbor
     }
+
+    #[test]
+    fn test_clamp_fitted_parameters_reports_changes() {
+        let mut warnings = Vec::new();
+        let (df, kf) = clamp_fitted_parameters(1.8, 1.3, (1.0, 3.0), (0.1, 10.0), &mut warnings);
+        assert_eq!((df, kf), (1.8, 1.3));
+        assert!(warnings.is_empty());
+
+        let (df, kf) = clamp_fitted_parameters(3.4, 0.01, (1.0, 3.0), (0.1, 10.0), &mut warnings);
+        assert_eq!((df, kf), (3.0, 0.1));
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_fractal_dimension_too_few_points_warns() {
+        let mut warnings = Vec::new();
+        let fit = calculate_fractal_dimension(&[1, 2], &[1.0, 1.5], &mut warnings);
+        assert_eq!((fit.df, fit.kf, fit.n_points), (2.0, 1.0, 0));
+        assert!(fit.df_std_error.is_nan());
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_fractal_dimension_reports_region_in_sample_indices() {
+        // Rg = N^(1/1.8) exactly; the N = 1 sample is filtered out of the fit
+        let n_values: Vec<usize> = (1..=20).collect();
+        let rg_values: Vec<f64> = n_values.iter().map(|&n| (n as f64).powf(1.0 / 1.8)).collect();
+        let mut warnings = Vec::new();
+        let fit = calculate_fractal_dimension(&n_values, &rg_values, &mut warnings);
+
+        assert!((fit.df - 1.8).abs() < 1e-9);
+        assert!((fit.kf - 1.0).abs() < 1e-9);
+        assert!(fit.r_squared > 0.999);
+        assert!(fit.df_std_error < 1e-6);
+        assert_eq!(fit.n_points, 19);
+        assert_eq!(fit.linear_region, (1, 20));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_fractal_dimension_std_error_grows_with_noise() {
+        let n_values: Vec<usize> = (2..=60).collect();
+        let noisy: Vec<f64> = n_values
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (n as f64).powf(1.0 / 1.8) * if i % 2 == 0 { 1.05 } else { 0.95 })
+            .collect();
+        let fit = calculate_fractal_dimension(&n_values, &noisy, &mut Vec::new());
+        assert!(fit.df_std_error > 1e-3 && fit.df_std_error < 0.5, "std = {}", fit.df_std_error);
+        assert!(fit.r_squared < 0.999);
+    }
+
+    #[test]
+    fn test_fractal_dimension_robust_methods_ignore_outliers() {
+        // Rg = N^(1/1.8) with two samples off by a factor of two
+        let n_values: Vec<usize> = (2..=40).collect();
+        let mut rg_values: Vec<f64> = n_values.iter().map(|&n| (n as f64).powf(1.0 / 1.8)).collect();
+        rg_values[10] *= 2.0;
+        rg_values[25] *= 0.5;
+
+        let ols = fit_fractal_dimension(&n_values, &rg_values, FitMethod::Ols, &mut Vec::new());
+        assert!((ols.df - 1.8).abs() > 0.01, "ols Df = {}", ols.df);
+        for method in [FitMethod::TheilSen, FitMethod::Ransac] {
+            let fit = fit_fractal_dimension(&n_values, &rg_values, method, &mut Vec::new());
+            assert!((fit.df - 1.8).abs() < 1e-3, "{:?} Df = {}", method, fit.df);
+        }
+    }
+
+    #[test]
+    fn test_align_to_principal_axes_puts_long_axis_on_x() {
+        // Rod along (1, 1, 0) with a heavier end, plus a short branch along z
+        let mut coords: Vec<[f64; 3]> = (0..6).map(|i| [i as f64 * 2.0, i as f64 * 2.0, 0.0]).collect();
+        coords.push([4.0, 4.0, 2.0]);
+        let mut radii = vec![1.0; coords.len()];
+        radii[0] = 1.5;
+
+        let (aligned, rotation) = align_to_principal_axes(&coords, &radii);
+
+        // Rows form a proper rotation
+        let x = Vector3::new(rotation[0][0], rotation[0][1], rotation[0][2]);
+        let y = Vector3::new(rotation[1][0], rotation[1][1], rotation[1][2]);
+        let z = Vector3::new(rotation[2][0], rotation[2][1], rotation[2][2]);
+        assert!((x.length() - 1.0).abs() < 1e-10 && x.dot(&y).abs() < 1e-10);
+        assert!((x.cross(&y).dot(&z) - 1.0).abs() < 1e-10);
+
+        // The rod ends up on x: spread along x dominates the other axes
+        let spread = |k: usize| aligned.iter().map(|p| p[k] * p[k]).sum::<f64>();
+        assert!(spread(0) > 10.0 * spread(1) && spread(0) > 10.0 * spread(2));
+
+        // Centered on the center of gravity, and distances are preserved
+        let cg = calculate_center_of_gravity(&aligned, &radii);
+        assert!(cg.length() < 1e-10);
+        let d = |p: [f64; 3], q: [f64; 3]| Vector3::new(p[0], p[1], p[2]).distance_to(&Vector3::new(q[0], q[1], q[2]));
+        assert!((d(aligned[0], aligned[5]) - d(coords[0], coords[5])).abs() < 1e-10);
+
+        // Canonical: rotating the input first gives the same aligned structure
+        let rotated: Vec<[f64; 3]> = coords.iter().map(|p| [-p[1], p[0], p[2] + 3.0]).collect();
+        let (again, _) = align_to_principal_axes(&rotated, &radii);
+        for (p, q) in aligned.iter().zip(again.iter()) {
+            assert!(d(*p, *q) < 1e-8);
+        }
+    }
 }
@@ -0,0 +1,263 @@
+//! Mobility diameter and mass-mobility exponent.
+//!
+//! Differential mobility analyzers (SMPS) size an agglomerate by the
+//! diameter of the sphere with the same drag, and mass classifiers (CPMA,
+//! APM) add its mass; the mass-mobility exponent Dfm, m ~ dm^Dfm, is what
+//! they report in place of Df. The drag is estimated from the structure:
+//!
+//! * free-molecular regime - the drag is proportional to the orientation-
+//!   averaged projected area, so dm is the projected-area equivalent
+//!   diameter sqrt(4 <A> / pi)
+//! * continuum regime - small agglomerates drag like their projected area
+//!   as well (Rogak et al., 1993), large ones screen their interior and
+//!   their mobility radius approaches Rg (Sorensen, 2011); dm is the
+//!   larger of the two estimates
+//!
+//! Both are 2r for a single sphere. Lengths are in the units of the
+//! coordinates.
+//!
+//! With the material density the mobility diameter gives the effective
+//! density rho_eff = 6 m / (pi dm³) and the aerodynamic diameter, the
+//! diameter of the unit-density sphere settling at the same speed,
+//! da² Cc(da) = (rho_eff / rho0) dm² Cc(dm), which impactors and
+//! aerodynamic aerosol classifiers measure.
+
+use std::f64::consts::PI;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::common::fitting::linear_regression;
+use crate::common::validation::check_positive;
+use crate::projection::area::orientation_averaged_area;
+
+use super::brownian::{cunningham_correction, PyBrownianParams};
+use super::metrics::calculate_radius_of_gyration;
+use super::result::PySimulationResult;
+
+/// Viewing directions averaged for the projected area.
+const MOBILITY_VIEWS: usize = 64;
+
+/// Unit density rho0 of the aerodynamic diameter, in kg/m³.
+const UNIT_DENSITY: f64 = 1000.0;
+
+/// Mobility diameters of one agglomerate.
+#[pyclass(name = "MobilityDiameter")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PyMobilityDiameter {
+    /// Orientation-averaged projected area <A>
+    #[pyo3(get)]
+    pub projected_area: f64,
+    /// Mobility diameter in the free-molecular regime, sqrt(4 <A> / pi)
+    #[pyo3(get)]
+    pub free_molecular: f64,
+    /// Mobility diameter in the continuum regime, max(free_molecular, 2 Rg)
+    #[pyo3(get)]
+    pub continuum: f64,
+}
+
+#[pymethods]
+impl PyMobilityDiameter {
+    fn __repr__(&self) -> String {
+        format!(
+            "MobilityDiameter(free_molecular={:.4}, continuum={:.4}, projected_area={:.4})",
+            self.free_molecular, self.continuum, self.projected_area
+        )
+    }
+}
+
+/// Mobility diameters of the agglomerate `coordinates`/`radii`.
+pub fn mobility_diameter(coordinates: &[[f64; 3]], radii: &[f64]) -> PyMobilityDiameter {
+    let projected_area = orientation_averaged_area(coordinates, radii, MOBILITY_VIEWS);
+    let free_molecular = (4.0 * projected_area / PI).sqrt();
+    let rg = calculate_radius_of_gyration(coordinates, radii);
+    PyMobilityDiameter {
+        projected_area,
+        free_molecular,
+        continuum: free_molecular.max(2.0 * rg),
+    }
+}
+
+/// Mass-mobility exponent, the slope of ln m against ln dm over an ensemble.
+///
+/// NaN unless the agglomerates span at least two different diameters.
+pub fn mass_mobility_exponent(masses: &[f64], diameters: &[f64]) -> f64 {
+    let (xs, ys): (Vec<f64>, Vec<f64>) = diameters.iter().zip(masses).map(|(d, m)| (d.ln(), m.ln())).unzip();
+    let spread =
+        xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max) - xs.iter().cloned().fold(f64::INFINITY, f64::min);
+    if !spread.is_finite() || spread <= 1e-12 {
+        return f64::NAN;
+    }
+    linear_regression(&xs, &ys).slope
+}
+
+/// Aerodynamic diameter of a particle of mobility diameter `mobility` and effective density `density`.
+///
+/// Solves da² Cc(da) = (density / rho0) dm² Cc(dm) for da by bisection, the
+/// left side growing with da. `mean_free_path` is in the units of the
+/// diameters; None ignores the slip (continuum limit), da = dm sqrt(density / rho0).
+pub fn aerodynamic_diameter(mobility: f64, density: f64, mean_free_path: Option<f64>) -> f64 {
+    let ratio = density / UNIT_DENSITY;
+    let Some(free_path) = mean_free_path else {
+        return mobility * ratio.sqrt();
+    };
+    let slip = |d: f64| d * d * cunningham_correction(2.0 * free_path / d);
+    let target = ratio * slip(mobility);
+    // Cc >= 1 bounds da from above by the no-slip sqrt(target); da >= 0
+    let (mut lo, mut hi) = (0.0, target.sqrt());
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if slip(mid) < target {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// Instrument-space observables of one agglomerate, from `effective_density`.
+#[pyclass(name = "EffectiveDensity")]
+#[derive(Debug, Clone)]
+pub struct PyEffectiveDensity {
+    /// Regime of the mobility diameter, "free_molecular" or "continuum"
+    #[pyo3(get)]
+    pub regime: String,
+    /// Material density of the primary particles in kg/m³
+    #[pyo3(get)]
+    pub material_density: f64,
+    /// Effective density 6 m / (pi dm³) in kg/m³
+    #[pyo3(get)]
+    pub effective_density: f64,
+    /// Mobility diameter, in the length units of the result
+    #[pyo3(get)]
+    pub mobility_diameter: f64,
+    /// Aerodynamic diameter, in the length units of the result
+    #[pyo3(get)]
+    pub aerodynamic_diameter: f64,
+    /// Mass in kg, or None when the result has no `units`
+    #[pyo3(get)]
+    pub mass: Option<f64>,
+    /// Whether the aerodynamic diameter includes the slip correction
+    /// (needs `units`; otherwise it is the continuum limit)
+    #[pyo3(get)]
+    pub slip_corrected: bool,
+}
+
+#[pymethods]
+impl PyEffectiveDensity {
+    fn __repr__(&self) -> String {
+        format!(
+            "EffectiveDensity(effective_density={:.1}, mobility_diameter={:.4}, aerodynamic_diameter={:.4})",
+            self.effective_density, self.mobility_diameter, self.aerodynamic_diameter
+        )
+    }
+}
+
+/// Effective density and aerodynamic diameter of an agglomerate.
+///
+/// The mass is the volume of the primary particles (overlaps counted twice,
+/// as `SimulationResult.mass`) times `material_density`, and the mobility
+/// diameter the one of `SimulationResult.mobility_diameter()`. The effective
+/// density 6 m / (pi dm³) is scale-free; the aerodynamic diameter includes
+/// the Cunningham slip correction when the result has `units` to compare
+/// its lengths with the gas mean free path.
+///
+/// # Arguments
+/// * `result` - `SimulationResult` to evaluate
+/// * `material_density` - Density of the primary particles in kg/m³ (e.g. 1800 for soot)
+/// * `regime` - Mobility diameter to use, "free_molecular" or "continuum" (default: "free_molecular")
+/// * `mean_free_path` - Gas mean free path in nm (default: air at 298.15 K and 1 atm, about 67 nm)
+///
+/// # Returns
+/// * `EffectiveDensity` with the effective density, mobility and aerodynamic diameters
+#[pyfunction]
+#[pyo3(signature = (result, material_density, regime="free_molecular", mean_free_path=None))]
+pub fn effective_density(
+    py: Python<'_>,
+    result: PyRef<'_, PySimulationResult>,
+    material_density: f64,
+    regime: &str,
+    mean_free_path: Option<f64>,
+) -> PyResult<PyEffectiveDensity> {
+    check_positive("material_density", material_density)?;
+    if let Some(free_path) = mean_free_path {
+        check_positive("mean_free_path", free_path)?;
+    }
+    let diameters = result.mobility_diameter(py);
+    let mobility = match regime {
+        "free_molecular" => diameters.free_molecular,
+        "continuum" => diameters.continuum,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unknown regime '{}': use 'free_molecular' or 'continuum'",
+                regime
+            )))
+        }
+    };
+
+    let volume: f64 = result.radii_data.iter().map(|r| 4.0 / 3.0 * PI * r.powi(3)).sum();
+    let density = material_density * volume / (PI / 6.0 * mobility.powi(3));
+    // Mean free path in the units of the result
+    let free_path = result.units.as_ref().map(|units| {
+        let nm = mean_free_path.unwrap_or_else(|| PyBrownianParams::default().free_path() * 1e9);
+        nm / units.to_nm(1.0)
+    });
+    let mass = result
+        .units
+        .as_ref()
+        .map(|units| material_density * volume * units.to_nm(1.0).powi(3) * 1e-27);
+
+    Ok(PyEffectiveDensity {
+        regime: regime.to_string(),
+        material_density,
+        effective_density: density,
+        mobility_diameter: mobility,
+        aerodynamic_diameter: aerodynamic_diameter(mobility, density, free_path),
+        mass,
+        slip_corrected: free_path.is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mobility_diameter_of_spheres_and_chains() {
+        // A sphere drags like itself in both regimes, and spheres have Dfm = 3
+        let spheres: Vec<PyMobilityDiameter> = [1.0, 2.0, 3.5]
+            .iter()
+            .map(|&r| mobility_diameter(&[[1.0, -2.0, 0.5]], &[r]))
+            .collect();
+        for (d, r) in spheres.iter().zip([1.0, 2.0, 3.5]) {
+            assert!((d.free_molecular - 2.0 * r).abs() < 1e-9, "{:?}", d);
+            assert_eq!(d.continuum, d.free_molecular);
+        }
+        let masses = [1.0f64, 8.0, 3.5f64.powi(3)];
+        let diameters: Vec<f64> = spheres.iter().map(|d| d.free_molecular).collect();
+        assert!((mass_mobility_exponent(&masses, &diameters) - 3.0).abs() < 1e-9);
+        assert!(mass_mobility_exponent(&masses[..1], &diameters[..1]).is_nan());
+
+        // A long chain is open: its Rg outgrows its projected area
+        let chain: Vec<[f64; 3]> = (0..50).map(|i| [2.0 * i as f64, 0.0, 0.0]).collect();
+        let d = mobility_diameter(&chain, &[1.0; 50]);
+        assert!(d.free_molecular < d.continuum);
+        assert!(d.free_molecular > 2.0 && d.free_molecular < 100.0);
+    }
+
+    #[test]
+    fn test_aerodynamic_diameter() {
+        // A unit-density sphere is its own aerodynamic sphere, with or without slip
+        assert_eq!(aerodynamic_diameter(100.0, UNIT_DENSITY, None), 100.0);
+        assert!((aerodynamic_diameter(100.0, UNIT_DENSITY, Some(67.0)) - 100.0).abs() < 1e-9);
+        // Stokes settling: da = dm sqrt(rho / rho0) without slip
+        assert!((aerodynamic_diameter(100.0, 4000.0, None) - 200.0).abs() < 1e-12);
+        // The larger aerodynamic sphere slips less, so it must grow past the
+        // no-slip estimate
+        let da = aerodynamic_diameter(100.0, 4000.0, Some(67.0));
+        assert!(da > 200.0 && da < 400.0, "{}", da);
+        let slip = |d: f64| d * d * cunningham_correction(2.0 * 67.0 / d);
+        assert!((slip(da) - 4.0 * slip(100.0)).abs() < 1e-6 * slip(da));
+    }
+}
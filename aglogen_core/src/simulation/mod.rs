@@ -1,11 +1,31 @@
 //! Particle aggregation simulation engines.
 
+pub mod annealing;
 pub mod ballistic;
 pub mod ballistic_cc;
+pub mod batch;
+pub mod brownian;
 pub mod cca;
+pub mod compare;
+pub mod contact_graph;
+pub mod deposition;
 pub mod dla;
+pub mod drift;
+pub mod ensemble;
+pub mod estimate;
+pub mod history;
+pub mod hooks;
+pub mod lineage;
 pub mod metrics;
+pub mod mobility;
+pub mod packing;
+pub mod pipeline;
+pub mod provenance;
+pub mod restart;
 pub mod result;
 pub mod sintering;
+pub mod size_distribution;
+pub mod sticking;
+pub mod surface;
 pub mod tunable;
 pub mod tunable_cc;
@@ -0,0 +1,417 @@
+//! Packing of precomputed agglomerates into a box.
+//!
+//! Deposits and filter cakes are built from many agglomerates sharing a
+//! volume. [`pack_agglomerates`] places randomly rotated copies of a set of
+//! agglomerates into a cubic box by random sequential addition: each copy is
+//! tried at random positions until it overlaps no particle already placed,
+//! and copies are added until the requested volume fraction is reached.
+
+use numpy::{PyArray1, PyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::Rng;
+
+use crate::common::arrays::{extract_f64_array1, extract_f64_array2};
+use crate::common::geometry::{Quaternion, Sphere, Vector3};
+use crate::common::rng::{create_rng, random_rotation};
+use crate::common::spatial::SpatialHash;
+use crate::common::validation::{check_coordinates, check_count, check_positive, check_radii};
+use crate::common::warnings::emit_warnings;
+
+/// Packing parameters.
+#[derive(Debug, Clone)]
+pub struct PackingParams {
+    /// Side of the cubic box, which spans `[0, box_size]` on every axis.
+    pub box_size: f64,
+    /// Target nominal volume fraction (sum of sphere volumes / box volume).
+    pub volume_fraction: f64,
+    /// Random positions tried per copy before the packing counts as jammed.
+    pub max_attempts: usize,
+}
+
+/// One placed copy of an input agglomerate.
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    /// Index of the input agglomerate.
+    pub source: usize,
+    /// Where the agglomerate's centroid was moved to.
+    pub position: Vector3,
+    /// Rotation applied about the centroid.
+    pub orientation: Quaternion,
+}
+
+/// Agglomerate centered on its centroid, with its bounding radius and volume.
+struct Template {
+    offsets: Vec<Vector3>,
+    radii: Vec<f64>,
+    bounding_radius: f64,
+    volume: f64,
+}
+
+impl Template {
+    fn new(coordinates: &[[f64; 3]], radii: &[f64]) -> Self {
+        let n = coordinates.len() as f64;
+        let centroid = coordinates
+            .iter()
+            .fold(Vector3::zero(), |acc, c| acc + Vector3::new(c[0], c[1], c[2]))
+            * (1.0 / n);
+        let offsets: Vec<Vector3> = coordinates
+            .iter()
+            .map(|c| Vector3::new(c[0], c[1], c[2]) - centroid)
+            .collect();
+        let bounding_radius = offsets
+            .iter()
+            .zip(radii.iter())
+            .map(|(p, r)| p.length() + r)
+            .fold(0.0, f64::max);
+        let volume = radii.iter().map(|r| 4.0 / 3.0 * std::f64::consts::PI * r.powi(3)).sum();
+
+        Self {
+            offsets,
+            radii: radii.to_vec(),
+            bounding_radius,
+            volume,
+        }
+    }
+}
+
+/// Internal packing result.
+pub struct PackingResult {
+    pub placements: Vec<Placement>,
+    /// Particles of every placed copy, in placement order.
+    pub coordinates: Vec<[f64; 3]>,
+    pub radii: Vec<f64>,
+    /// Index into `placements` of each particle.
+    pub aggregate_ids: Vec<u32>,
+    /// Nominal volume fraction reached.
+    pub volume_fraction: f64,
+    pub warnings: Vec<String>,
+}
+
+/// Pack copies of `structures` into the box.
+///
+/// Every structure must fit in the box (bounding diameter at most
+/// `box_size`). Copies are drawn uniformly from `structures` until the
+/// placed volume reaches the target, so the final fraction overshoots it by
+/// at most one agglomerate. Overlaps between particles of the same copy
+/// (sintered contacts) are kept as they are.
+pub fn pack_agglomerates_internal(
+    structures: &[(Vec<[f64; 3]>, Vec<f64>)],
+    params: &PackingParams,
+    seed: u64,
+) -> PackingResult {
+    let mut rng = create_rng(seed);
+    let templates: Vec<Template> = structures.iter().map(|(c, r)| Template::new(c, r)).collect();
+    let max_radius = templates
+        .iter()
+        .flat_map(|t| t.radii.iter().copied())
+        .fold(0.0, f64::max);
+
+    let box_volume = params.box_size.powi(3);
+    let target_volume = params.volume_fraction * box_volume;
+
+    // Any overlapping pair is at most 2 * max_radius apart: a 3x3x3 cell lookup finds it
+    let mut hash = SpatialHash::new(2.0 * max_radius);
+    let mut placements = Vec::new();
+    let mut coordinates: Vec<[f64; 3]> = Vec::new();
+    let mut radii: Vec<f64> = Vec::new();
+    let mut aggregate_ids = Vec::new();
+    let mut placed_volume = 0.0;
+    let mut warnings = Vec::new();
+
+    let mut candidate = Vec::new();
+    while placed_volume < target_volume {
+        let source = rng.gen_range(0..templates.len());
+        let template = &templates[source];
+        let lo = template.bounding_radius;
+        let hi = params.box_size - template.bounding_radius;
+
+        let mut placed = None;
+        for _ in 0..params.max_attempts {
+            let orientation = random_rotation(&mut rng);
+            let position = Vector3::new(
+                rng.gen_range(lo..=hi),
+                rng.gen_range(lo..=hi),
+                rng.gen_range(lo..=hi),
+            );
+
+            candidate.clear();
+            candidate.extend(
+                template
+                    .offsets
+                    .iter()
+                    .zip(template.radii.iter())
+                    .map(|(offset, &r)| Sphere::new(position + orientation.rotate(offset), r)),
+            );
+            let overlaps = candidate.iter().any(|sphere| {
+                hash.query_potential_collisions(sphere).into_iter().any(|j| {
+                    let c = coordinates[j];
+                    let contact = sphere.radius + radii[j];
+                    sphere.center.distance_squared_to(&Vector3::new(c[0], c[1], c[2])) < contact * contact
+                })
+            });
+            if !overlaps {
+                placed = Some(Placement {
+                    source,
+                    position,
+                    orientation,
+                });
+                break;
+            }
+        }
+
+        let Some(placement) = placed else {
+            warnings.push(format!(
+                "packing jammed at volume fraction {:.4} (target {}) after {} agglomerates; \
+                 no free position found in {} attempts",
+                placed_volume / box_volume,
+                params.volume_fraction,
+                placements.len(),
+                params.max_attempts
+            ));
+            break;
+        };
+
+        for sphere in &candidate {
+            hash.insert(coordinates.len(), sphere);
+            coordinates.push([sphere.center.x, sphere.center.y, sphere.center.z]);
+            radii.push(sphere.radius);
+            aggregate_ids.push(placements.len() as u32);
+        }
+        placed_volume += template.volume;
+        placements.push(placement);
+    }
+
+    PackingResult {
+        placements,
+        coordinates,
+        radii,
+        aggregate_ids,
+        volume_fraction: placed_volume / box_volume,
+        warnings,
+    }
+}
+
+/// Python wrapper for packing results.
+#[pyclass(name = "PackingResult")]
+pub struct PyPackingResult {
+    /// Nominal volume fraction reached.
+    #[pyo3(get)]
+    pub volume_fraction: f64,
+    #[pyo3(get)]
+    pub box_size: f64,
+    /// Number of agglomerate copies placed.
+    #[pyo3(get)]
+    pub n_agglomerates: usize,
+    /// Warnings raised during packing (also emitted as Python `UserWarning`s).
+    #[pyo3(get)]
+    pub warnings: Vec<String>,
+
+    pub(crate) placements: Vec<Placement>,
+    pub(crate) coordinates_data: Vec<[f64; 3]>,
+    pub(crate) radii_data: Vec<f64>,
+    pub(crate) aggregate_ids_data: Vec<u32>,
+}
+
+#[pymethods]
+impl PyPackingResult {
+    /// Get coordinates of every placed particle as numpy array (M, 3).
+    #[getter]
+    fn coordinates<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        rows_to_array(py, self.coordinates_data.iter().map(|c| c.to_vec()).collect(), 3)
+    }
+
+    /// Get radii of every placed particle as numpy array (M,).
+    #[getter]
+    fn radii<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.radii_data.clone())
+    }
+
+    /// Get the placed copy each particle belongs to as numpy array (M,).
+    #[getter]
+    fn aggregate_ids<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        PyArray1::from_vec(py, self.aggregate_ids_data.clone())
+    }
+
+    /// Get the input agglomerate index of each placed copy as numpy array (K,).
+    #[getter]
+    fn sources<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_vec(py, self.placements.iter().map(|p| p.source).collect())
+    }
+
+    /// Get the centroid position of each placed copy as numpy array (K, 3).
+    #[getter]
+    fn positions<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let rows = self
+            .placements
+            .iter()
+            .map(|p| vec![p.position.x, p.position.y, p.position.z])
+            .collect();
+        rows_to_array(py, rows, 3)
+    }
+
+    /// Get the orientation of each placed copy as unit quaternions (K, 4), (w, x, y, z).
+    ///
+    /// A copy's particles are `position + R(q) @ (p - centroid)` for the
+    /// input coordinates `p`.
+    #[getter]
+    fn orientations<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let rows = self
+            .placements
+            .iter()
+            .map(|p| vec![p.orientation.w, p.orientation.x, p.orientation.y, p.orientation.z])
+            .collect();
+        rows_to_array(py, rows, 4)
+    }
+}
+
+/// Build a (rows, width) array, keeping the width when there are no rows.
+fn rows_to_array(py: Python<'_>, rows: Vec<Vec<f64>>, width: usize) -> Bound<'_, PyArray2<f64>> {
+    if rows.is_empty() {
+        return PyArray2::zeros(py, [0, width], false);
+    }
+    PyArray2::from_vec2(py, &rows).unwrap()
+}
+
+/// Convert and validate one agglomerate, checking that it fits in the box.
+fn extract_packing_structure(
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+    box_size: f64,
+) -> PyResult<(Vec<[f64; 3]>, Vec<f64>)> {
+    let coords = extract_f64_array2(coordinates, "coordinates")?;
+    let radii = extract_f64_array1(radii, "radii")?.to_vec();
+    check_coordinates("coordinates", &coords)?;
+    check_count("coordinates", coords.nrows(), 1)?;
+    check_radii(&radii, coords.nrows())?;
+
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|r| [r[0], r[1], r[2]]).collect();
+    let diameter = 2.0 * Template::new(&points, &radii).bounding_radius;
+    if diameter > box_size {
+        return Err(PyValueError::new_err(format!(
+            "agglomerate of bounding diameter {:.4} does not fit in a box of size {}",
+            diameter, box_size
+        )));
+    }
+    Ok((points, radii))
+}
+
+/// Pack precomputed agglomerates into a cubic box without overlaps.
+///
+/// Randomly rotated copies of the input agglomerates are placed by random
+/// sequential addition until the nominal volume fraction (sum of sphere
+/// volumes over box volume) reaches `volume_fraction`. If no free position
+/// is found for a copy, packing stops early with a warning.
+///
+/// # Arguments
+/// * `structures` - List of (coordinates, radii) pairs, one per agglomerate
+/// * `box_size` - Side of the cubic box `[0, box_size]^3`
+/// * `volume_fraction` - Target nominal volume fraction (0-1)
+/// * `seed` - Random seed for reproducibility
+/// * `max_attempts` - Random positions tried per copy before giving up (default: 10000)
+///
+/// # Returns
+/// * `PackingResult` with the placed particles and each copy's source, position and orientation
+#[pyfunction]
+#[pyo3(signature = (structures, box_size, volume_fraction, seed=None, max_attempts=10000))]
+pub fn pack_agglomerates(
+    py: Python<'_>,
+    structures: Vec<(Bound<'_, PyAny>, Bound<'_, PyAny>)>,
+    box_size: f64,
+    volume_fraction: f64,
+    seed: Option<u64>,
+    max_attempts: usize,
+) -> PyResult<PyPackingResult> {
+    let seed = seed.unwrap_or_else(rand::random);
+    check_count("structures", structures.len(), 1)?;
+    check_positive("box_size", box_size)?;
+    check_positive("volume_fraction", volume_fraction)?;
+    check_count("max_attempts", max_attempts, 1)?;
+    if volume_fraction > 1.0 {
+        return Err(PyValueError::new_err(format!(
+            "volume_fraction must not exceed 1, got {}",
+            volume_fraction
+        )));
+    }
+
+    let converted = structures
+        .iter()
+        .enumerate()
+        .map(|(i, (coordinates, radii))| {
+            extract_packing_structure(coordinates, radii, box_size)
+                .map_err(|e| PyValueError::new_err(format!("structure {}: {}", i, e)))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    let params = PackingParams {
+        box_size,
+        volume_fraction,
+        max_attempts,
+    };
+    let result = py.allow_threads(|| pack_agglomerates_internal(&converted, &params, seed));
+    emit_warnings(py, &result.warnings)?;
+
+    Ok(PyPackingResult {
+        volume_fraction: result.volume_fraction,
+        box_size,
+        n_agglomerates: result.placements.len(),
+        warnings: result.warnings,
+        placements: result.placements,
+        coordinates_data: result.coordinates,
+        radii_data: result.radii,
+        aggregate_ids_data: result.aggregate_ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rod() -> (Vec<[f64; 3]>, Vec<f64>) {
+        (vec![[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [4.0, 0.0, 0.0]], vec![1.0; 3])
+    }
+
+    #[test]
+    fn test_packing_reaches_fraction_without_overlap() {
+        let params = PackingParams {
+            box_size: 30.0,
+            volume_fraction: 0.1,
+            max_attempts: 10_000,
+        };
+        let result = pack_agglomerates_internal(&[rod()], &params, 3);
+
+        assert!(result.warnings.is_empty());
+        assert!(result.volume_fraction >= 0.1);
+        assert_eq!(result.coordinates.len(), 3 * result.placements.len());
+
+        for (i, (a, ra)) in result.coordinates.iter().zip(result.radii.iter()).enumerate() {
+            assert!(a.iter().all(|&v| v >= ra - 1e-9 && v <= 30.0 - ra + 1e-9));
+            for j in (i + 1)..result.coordinates.len() {
+                if result.aggregate_ids[i] == result.aggregate_ids[j] {
+                    continue;
+                }
+                let b = result.coordinates[j];
+                let d2 = (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2);
+                assert!(d2 >= (ra + result.radii[j]).powi(2) - 1e-9);
+            }
+        }
+
+        // The rod keeps its shape under the placement's rigid motion
+        let p = &result.coordinates[0..3];
+        let d = |a: [f64; 3], b: [f64; 3]| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+        assert!((d(p[0], p[2]) - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_packing_reports_jamming() {
+        let params = PackingParams {
+            box_size: 6.0,
+            volume_fraction: 0.9,
+            max_attempts: 50,
+        };
+        let result = pack_agglomerates_internal(&[rod()], &params, 1);
+        assert_eq!(result.placements.len(), 1);
+        assert!(result.volume_fraction < 0.9);
+        assert!(result.warnings[0].contains("jammed"));
+    }
+}
@@ -0,0 +1,331 @@
+//! Multi-stage aggregation pipelines.
+//!
+//! A pipeline chains runs of different algorithms: the clusters left by one
+//! stage are where the next one starts. Particle-cluster stages grow onto
+//! every cluster of the pool and cluster-cluster stages merge the whole pool,
+//! so tunable PC seeds can be merged by ballistic CC and the agglomerate
+//! decorated by DLA without a round trip through Python between stages.
+
+use ndarray::Array2;
+use numpy::{PyArray1, ToPyArray};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::common::geometry::{Sphere, Vector3};
+use crate::common::rng::SeedSequence;
+
+use super::batch::simulation_function;
+use super::metrics::calculate_center_of_gravity;
+use super::restart::group_clusters;
+use super::result::PySimulationResult;
+
+/// Gap between the clusters of a spread pool, as a fraction of their spacing.
+const SPREAD_GAP: f64 = 0.1;
+
+/// Keywords the pipeline sets itself when it calls a stage.
+const RESERVED_KEYWORDS: [&str; 4] = ["seed", "existing_coords", "existing_radii", "existing_cluster_ids"];
+
+/// Whether `algorithm` merges a pool of clusters rather than growing one agglomerate.
+fn is_cluster_cluster(algorithm: &str) -> bool {
+    matches!(algorithm, "cca" | "ballistic_cc" | "tunable_cc")
+}
+
+/// One stage of a pipeline.
+#[derive(Debug)]
+struct PipelineStage {
+    algorithm: String,
+    /// Independent runs of the first stage, each adding its clusters to the pool
+    copies: usize,
+    /// Particles a later stage adds to every cluster (particle-cluster) or to the pool
+    n_additional: usize,
+    /// Remaining keyword arguments of the `run_*` function
+    params: Py<PyDict>,
+}
+
+impl PipelineStage {
+    /// Parse the descriptor of stage `index`.
+    fn from_descriptor(py: Python<'_>, index: usize, descriptor: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let params = descriptor.copy()?;
+        let algorithm: String = match params.get_item("algorithm")? {
+            Some(algorithm) => algorithm.extract()?,
+            None => return Err(PyValueError::new_err(format!("stage {} has no 'algorithm'", index))),
+        };
+        simulation_function(py, &algorithm)?;
+        params.del_item("algorithm")?;
+
+        let take = |key: &str| -> PyResult<Option<usize>> {
+            let value = params.get_item(key)?.map(|v| v.extract()).transpose()?;
+            if value.is_some() {
+                params.del_item(key)?;
+            }
+            Ok(value)
+        };
+        let copies = take("copies")?;
+        let n_additional = take("n_additional")?;
+
+        for key in RESERVED_KEYWORDS {
+            if params.contains(key)? {
+                return Err(PyValueError::new_err(format!(
+                    "stage {} must not set '{}', the pipeline passes it",
+                    index, key
+                )));
+            }
+        }
+        let misplaced = if index == 0 {
+            n_additional.map(|_| "n_additional")
+        } else if copies.is_some() {
+            Some("copies")
+        } else if params.contains("n_particles")? {
+            Some("n_particles")
+        } else {
+            None
+        };
+        if let Some(key) = misplaced {
+            return Err(PyValueError::new_err(format!(
+                "stage {} must not set '{}': the first stage sets n_particles and copies, \
+                 later stages n_additional",
+                index, key
+            )));
+        }
+        if copies == Some(0) {
+            return Err(PyValueError::new_err("copies must be at least 1, got 0"));
+        }
+
+        Ok(Self {
+            algorithm,
+            copies: copies.unwrap_or(1),
+            n_additional: n_additional.unwrap_or(0),
+            params: params.unbind(),
+        })
+    }
+
+    /// Run the stage once with its parameters plus `extra` keyword arguments.
+    fn call(&self, py: Python<'_>, extra: &Bound<'_, PyDict>) -> PyResult<PySimulationResult> {
+        let kwargs = self.params.bind(py).copy()?;
+        kwargs.update(extra.as_mapping())?;
+        simulation_function(py, &self.algorithm)?
+            .call((), Some(&kwargs))?
+            .extract()
+    }
+}
+
+/// Keyword arguments of a run that starts from `clusters` and adds `n_additional` particles.
+fn existing_kwargs<'py>(
+    py: Python<'py>,
+    clusters: &[Vec<Sphere>],
+    n_additional: usize,
+    seed: u64,
+) -> PyResult<Bound<'py, PyDict>> {
+    let spheres: Vec<&Sphere> = clusters.iter().flatten().collect();
+    let coords: Vec<f64> = spheres
+        .iter()
+        .flat_map(|s| [s.center.x, s.center.y, s.center.z])
+        .collect();
+    let radii: Vec<f64> = spheres.iter().map(|s| s.radius).collect();
+    let cluster_ids: Vec<u32> = clusters
+        .iter()
+        .enumerate()
+        .flat_map(|(k, c)| vec![k as u32; c.len()])
+        .collect();
+
+    let coords = Array2::from_shape_vec((spheres.len(), 3), coords).expect("three coordinates per particle");
+
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("n_particles", spheres.len() + n_additional)?;
+    kwargs.set_item("existing_coords", coords.to_pyarray(py))?;
+    kwargs.set_item("existing_radii", PyArray1::from_vec(py, radii))?;
+    kwargs.set_item("seed", seed)?;
+    if clusters.len() > 1 {
+        kwargs.set_item("existing_cluster_ids", cluster_ids)?;
+    }
+    Ok(kwargs)
+}
+
+/// Clusters of a result, split by `cluster_ids`.
+fn result_clusters(result: &PySimulationResult) -> PyResult<Vec<Vec<Sphere>>> {
+    let spheres = result
+        .radii_data
+        .iter()
+        .enumerate()
+        .map(|(i, &r)| {
+            let c = &result.coordinates_data[3 * i..3 * i + 3];
+            Sphere::new(Vector3::new(c[0], c[1], c[2]), r)
+        })
+        .collect();
+    group_clusters(spheres, Some(result.cluster_ids_data.clone()))
+}
+
+/// Move the clusters of `pool` onto a cubic lattice centered at the origin,
+/// far enough apart that none overlap.
+///
+/// The runs of a stage each center their agglomerate at the origin, so a
+/// pool gathered from several runs starts out stacked on top of itself.
+fn spread_clusters(pool: &mut [Vec<Sphere>]) {
+    let centers: Vec<Vector3> = pool
+        .iter()
+        .map(|cluster| {
+            let coords: Vec<[f64; 3]> = cluster.iter().map(|s| [s.center.x, s.center.y, s.center.z]).collect();
+            let radii: Vec<f64> = cluster.iter().map(|s| s.radius).collect();
+            calculate_center_of_gravity(&coords, &radii)
+        })
+        .collect();
+    let reach = pool
+        .iter()
+        .zip(&centers)
+        .flat_map(|(cluster, &center)| cluster.iter().map(move |s| (s.center - center).length() + s.radius))
+        .fold(0.0, f64::max);
+    let spacing = 2.0 * reach * (1.0 + SPREAD_GAP);
+    let side = (pool.len() as f64).cbrt().ceil() as usize;
+    let offset = (side - 1) as f64 / 2.0;
+
+    for (k, (cluster, &center)) in pool.iter_mut().zip(&centers).enumerate() {
+        let [i, j, l] = [k % side, k / side % side, k / (side * side)].map(|c| (c as f64 - offset) * spacing);
+        let shift = Vector3::new(i, j, l) - center;
+        for sphere in cluster.iter_mut() {
+            sphere.center = sphere.center + shift;
+        }
+    }
+}
+
+/// Chain of aggregation stages run one after the other.
+///
+/// Each stage is a dict with the `algorithm` ("dla", "cca", "ballistic",
+/// "ballistic_cc", "tunable", "tunable_cc") and keyword arguments of the
+/// matching `run_*` function. The first stage also takes `copies`, the
+/// number of independent runs whose clusters form the initial pool. Every
+/// later stage starts from the clusters left by the previous one:
+///
+/// * particle-cluster stages grow every cluster by `n_additional` particles
+/// * cluster-cluster stages merge the whole pool plus `n_additional` new monomers,
+///   the clusters first spread on a lattice so that no two overlap
+///
+/// ```python
+/// pipeline = SimulationPipeline([
+///     {"algorithm": "tunable", "n_particles": 50, "target_df": 1.8, "copies": 8},
+///     {"algorithm": "ballistic_cc"},
+///     {"algorithm": "dla", "n_additional": 200},
+/// ], seed=42)
+/// agglomerate, = pipeline.run()
+/// ```
+///
+/// Run `i` of the pipeline gets the `i`-th seed of the sequence rooted at
+/// `seed`, so a pipeline is reproducible as a whole.
+#[pyclass(name = "SimulationPipeline")]
+pub struct PySimulationPipeline {
+    /// Master seed the run seeds are derived from (None = random on every `run`)
+    #[pyo3(get)]
+    pub seed: Option<u64>,
+
+    stages: Vec<PipelineStage>,
+}
+
+#[pymethods]
+impl PySimulationPipeline {
+    #[new]
+    #[pyo3(signature = (stages, seed=None))]
+    fn new(py: Python<'_>, stages: Vec<Bound<'_, PyDict>>, seed: Option<u64>) -> PyResult<Self> {
+        if stages.is_empty() {
+            return Err(PyValueError::new_err("a pipeline needs at least one stage"));
+        }
+        let stages = stages
+            .iter()
+            .enumerate()
+            .map(|(i, descriptor)| PipelineStage::from_descriptor(py, i, descriptor))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self { seed, stages })
+    }
+
+    /// Algorithm of every stage, in order.
+    #[getter]
+    fn algorithms(&self) -> Vec<String> {
+        self.stages.iter().map(|s| s.algorithm.clone()).collect()
+    }
+
+    /// Run all stages.
+    ///
+    /// # Returns
+    /// * List of `SimulationResult` of the last stage: one per cluster of
+    ///   the pool after a particle-cluster stage, a single one after a
+    ///   cluster-cluster stage
+    fn run(&self, py: Python<'_>) -> PyResult<Vec<PySimulationResult>> {
+        let sequence = SeedSequence::new(self.seed.unwrap_or_else(rand::random));
+        let mut n_runs = 0u64;
+        let mut next_seed = || {
+            n_runs += 1;
+            sequence.seed(n_runs - 1)
+        };
+
+        let first = &self.stages[0];
+        let mut results = (0..first.copies)
+            .map(|_| {
+                let extra = PyDict::new(py);
+                extra.set_item("seed", next_seed())?;
+                first.call(py, &extra)
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        for stage in &self.stages[1..] {
+            let mut pool = Vec::new();
+            for result in &results {
+                pool.extend(result_clusters(result)?);
+            }
+
+            results = if is_cluster_cluster(&stage.algorithm) {
+                if results.len() > 1 {
+                    spread_clusters(&mut pool);
+                }
+                let extra = existing_kwargs(py, &pool, stage.n_additional, next_seed())?;
+                vec![stage.call(py, &extra)?]
+            } else {
+                pool.iter()
+                    .map(|cluster| {
+                        let extra =
+                            existing_kwargs(py, std::slice::from_ref(cluster), stage.n_additional, next_seed())?;
+                        stage.call(py, &extra)
+                    })
+                    .collect::<PyResult<Vec<_>>>()?
+            };
+        }
+        Ok(results)
+    }
+
+    fn __len__(&self) -> usize {
+        self.stages.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SimulationPipeline({}, seed={:?})", self.algorithms().join(" -> "), self.seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::hooks::NoHooks;
+    use crate::simulation::tunable::{run_tunable_internal, TunableParams};
+
+    #[test]
+    fn test_spread_pool_does_not_overlap() {
+        // Three runs, each centered at the origin, as gathered by a first stage
+        let mut pool = Vec::new();
+        for seed in 0..3 {
+            let params = TunableParams {
+                n_particles: 30,
+                ..Default::default()
+            };
+            pool.extend(result_clusters(&run_tunable_internal(params, seed, &mut NoHooks).to_py()).unwrap());
+        }
+        let sizes: Vec<usize> = pool.iter().map(Vec::len).collect();
+        spread_clusters(&mut pool);
+
+        assert_eq!(pool.iter().map(Vec::len).collect::<Vec<_>>(), sizes);
+        for (a, first) in pool.iter().enumerate() {
+            for second in &pool[a + 1..] {
+                for (p, q) in first.iter().flat_map(|p| second.iter().map(move |q| (p, q))) {
+                    assert!(p.center.distance_to(&q.center) > p.radius + q.radius);
+                }
+            }
+        }
+    }
+}
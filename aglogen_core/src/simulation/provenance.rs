@@ -0,0 +1,91 @@
+//! Parameter provenance of simulation results.
+//!
+//! Every `run_*` function records the algorithm, the crate version and the
+//! parameters the engine ran with, after defaults, sintering and size options
+//! are resolved, plus the seed it drew and the units of its lengths, so a
+//! result (or its JSON) holds what is needed to reproduce it. Callbacks and
+//! cancel events do not shape the structure and are not recorded.
+
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyString};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Parameters of a run of `algorithm`: its name, the crate version, the
+/// fields of the engine's `params` and the entries of the `run` object.
+pub fn run_parameters(algorithm: &str, params: &impl Serialize, run: Value) -> Value {
+    let mut parameters = Map::new();
+    parameters.insert("algorithm".to_string(), algorithm.into());
+    parameters.insert("version".to_string(), env!("CARGO_PKG_VERSION").into());
+    for fields in [serde_json::to_value(params).unwrap_or(Value::Null), run] {
+        if let Value::Object(fields) = fields {
+            parameters.extend(fields);
+        }
+    }
+    Value::Object(parameters)
+}
+
+/// Python object (None, bool, int, float, str, list or dict) of a JSON value.
+pub(crate) fn json_to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => u.into_pyobject(py)?.into_any(),
+            (None, Some(i)) => i.into_pyobject(py)?.into_any(),
+            _ => PyFloat::new(py, n.as_f64().unwrap_or(f64::NAN)).into_any(),
+        },
+        Value::String(s) => PyString::new(py, s).into_any(),
+        Value::Array(items) => {
+            let items = items.iter().map(|v| json_to_py(py, v)).collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, items)?.into_any()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, v) in map {
+                dict.set_item(key, json_to_py(py, v)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::common::geometry::{Sphere, Vector3};
+    use crate::common::units::PyUnits;
+    use crate::simulation::cca::CcaParams;
+    use crate::simulation::dla::DlaParams;
+    use crate::simulation::size_distribution::SizeDistribution;
+
+    #[test]
+    fn test_run_parameters() {
+        let params = DlaParams {
+            n_particles: 100,
+            sizes: Some(SizeDistribution::Lognormal { cmd: 20.0, gsd: 1.4 }),
+            initial: vec![Sphere::new(Vector3::new(1.0, 2.0, 3.0), 0.5)],
+            ..Default::default()
+        };
+        let parameters = run_parameters("dla", &params, json!({"seed": 42u64, "units": None::<PyUnits>}));
+
+        assert_eq!(parameters["algorithm"], "dla");
+        assert_eq!(parameters["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(parameters["seed"], 42);
+        assert_eq!(parameters["n_particles"], 100);
+        assert_eq!(parameters["max_walk_steps"], params.max_walk_steps);
+        assert_eq!(parameters["size_distribution"], json!({"lognormal": {"cmd": 20.0, "gsd": 1.4}}));
+        assert_eq!(parameters["sintering"], json!({"fixed": 1.0}));
+        assert_eq!(parameters["initial"], json!([{"center": {"x": 1.0, "y": 2.0, "z": 3.0}, "radius": 0.5}]));
+        assert_eq!(parameters["drift"], json!({"x": 0.0, "y": 0.0, "z": 0.0}));
+        assert!(parameters["box_size"].is_null() && parameters["units"].is_null());
+
+        // The resolved units are recorded, not the missing argument
+        let units = PyUnits::new("nm", None).unwrap();
+        let parameters = run_parameters("cca_physical", &CcaParams::default(), json!({"seed": 1, "units": units}));
+        assert_eq!(parameters["units"]["length_unit"], "nm");
+        assert_eq!(parameters["sticking"], "constant");
+    }
+}
@@ -0,0 +1,299 @@
+//! Restarting growth from an existing agglomerate or cluster pool.
+//!
+//! Particle-cluster engines can grow onto a given agglomerate instead of a
+//! single seed particle, and cluster-cluster engines can start from a pool
+//! of given clusters topped up with new monomers. This enables staged growth
+//! experiments (a DLA core with a ballistic shell, ...) and resuming large
+//! runs from a saved structure.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::common::geometry::{Sphere, Vector3};
+use crate::common::particles::ParticleStore;
+use crate::common::spatial::PeriodicSpatialHash;
+use crate::common::validation::check_coordinates;
+use crate::projection::extract_structure;
+
+use super::batch::simulation_function;
+use super::metrics::calculate_center_of_gravity;
+use super::result::PySimulationResult;
+
+/// Draws of a new monomer before it is left overlapping the restarted clusters.
+const MONOMER_PLACEMENT_ATTEMPTS: usize = 1000;
+
+/// Particles of a restarted pool that the monomers topping it up must not
+/// overlap, hashed in a periodic box of `box_size` with cells wide enough
+/// for monomers up to `max_radius`. A box well beyond the pool and the
+/// monomers behaves as open space.
+pub(crate) fn pool_occupancy<'a>(
+    existing: impl IntoIterator<Item = &'a Sphere>,
+    box_size: f64,
+    max_radius: f64,
+) -> PeriodicSpatialHash {
+    let existing: Vec<&Sphere> = existing.into_iter().collect();
+    let existing_radius = existing.iter().map(|s| s.radius).fold(0.0, f64::max);
+    let mut occupied = PeriodicSpatialHash::new(box_size, existing_radius + max_radius);
+    for (i, sphere) in existing.into_iter().enumerate() {
+        occupied.insert(i, sphere);
+    }
+    occupied
+}
+
+/// Draw new monomers with `draw` until one overlaps none of `occupied`,
+/// giving up after `MONOMER_PLACEMENT_ATTEMPTS` draws. Returns the monomer
+/// and whether it is clear of the pool.
+pub(crate) fn draw_clear_monomer(occupied: &PeriodicSpatialHash, mut draw: impl FnMut() -> Sphere) -> (Sphere, bool) {
+    let mut monomer = draw();
+    for _ in 1..MONOMER_PLACEMENT_ATTEMPTS {
+        if !occupied.overlaps(monomer.center, monomer.radius, 1.0, None, 0.0) {
+            return (monomer, true);
+        }
+        monomer = draw();
+    }
+    let clear = !occupied.overlaps(monomer.center, monomer.radius, 1.0, None, 0.0);
+    (monomer, clear)
+}
+
+/// Warning for the monomers `draw_clear_monomer` could not clear of the pool.
+pub(crate) fn overlapping_monomers_warning(n_overlapping: usize) -> String {
+    format!(
+        "{} new monomers could not be placed clear of the existing clusters and start overlapping them",
+        n_overlapping
+    )
+}
+
+/// Spheres of the structure given as `existing_coords`/`existing_radii` (empty when neither is).
+pub(crate) fn extract_existing(
+    coordinates: Option<&Bound<'_, PyAny>>,
+    radii: Option<&Bound<'_, PyAny>>,
+) -> PyResult<Vec<Sphere>> {
+    let (coordinates, radii) = match (coordinates, radii) {
+        (None, None) => return Ok(Vec::new()),
+        (Some(coordinates), Some(radii)) => (coordinates, radii),
+        _ => {
+            return Err(PyValueError::new_err(
+                "existing_coords and existing_radii must be given together",
+            ))
+        }
+    };
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    check_coordinates("existing_coords", &coords)?;
+    Ok(coords
+        .rows()
+        .into_iter()
+        .zip(radii)
+        .map(|(c, r)| Sphere::new(Vector3::new(c[0], c[1], c[2]), r))
+        .collect())
+}
+
+/// Split existing particles into clusters by their cluster ID, in order of
+/// first appearance; without IDs they form a single cluster.
+pub(crate) fn group_clusters(spheres: Vec<Sphere>, cluster_ids: Option<Vec<u32>>) -> PyResult<Vec<Vec<Sphere>>> {
+    let Some(cluster_ids) = cluster_ids else {
+        return Ok(if spheres.is_empty() { Vec::new() } else { vec![spheres] });
+    };
+    if cluster_ids.len() != spheres.len() {
+        return Err(PyValueError::new_err(format!(
+            "existing_cluster_ids length ({}) must match the number of existing particles ({})",
+            cluster_ids.len(),
+            spheres.len()
+        )));
+    }
+    let mut labels: Vec<u32> = Vec::new();
+    let mut clusters: Vec<Vec<Sphere>> = Vec::new();
+    for (sphere, id) in spheres.into_iter().zip(cluster_ids) {
+        match labels.iter().position(|&l| l == id) {
+            Some(k) => clusters[k].push(sphere),
+            None => {
+                labels.push(id);
+                clusters.push(vec![sphere]);
+            }
+        }
+    }
+    Ok(clusters)
+}
+
+/// Require `n_particles` to hold at least the `n_existing` particles a run restarts from.
+pub fn check_existing(n_particles: usize, n_existing: usize) -> PyResult<()> {
+    if n_particles < n_existing {
+        return Err(PyValueError::new_err(format!(
+            "n_particles ({}) must be at least the number of existing particles ({})",
+            n_particles, n_existing
+        )));
+    }
+    Ok(())
+}
+
+/// Particle store holding `initial` moved so its center of mass is at the origin.
+pub(crate) fn centered_store(initial: &[Sphere], capacity: usize) -> ParticleStore {
+    let mut particles = ParticleStore::with_capacity(capacity.max(initial.len()));
+    for &sphere in initial {
+        particles.push(sphere);
+    }
+    if !particles.is_empty() {
+        let center = calculate_center_of_gravity(&particles.coords(), particles.radii());
+        particles.translate(Vector3::zero() - center);
+    }
+    particles
+}
+
+/// Call the `run_*` function of a particle-cluster `algorithm` on top of an
+/// existing agglomerate, adding `n_additional` particles.
+fn continue_run(
+    py: Python<'_>,
+    algorithm: &str,
+    existing_coords: &Bound<'_, PyAny>,
+    existing_radii: &Bound<'_, PyAny>,
+    n_additional: usize,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PySimulationResult> {
+    let n_existing = extract_existing(Some(existing_coords), Some(existing_radii))?.len();
+    let kwargs = match kwargs {
+        Some(kwargs) => kwargs.copy()?,
+        None => PyDict::new(py),
+    };
+    for key in ["n_particles", "existing_coords", "existing_radii"] {
+        if kwargs.contains(key)? {
+            return Err(PyValueError::new_err(format!(
+                "'{}' is set by run_{}_continue and cannot be passed as a keyword",
+                key, algorithm
+            )));
+        }
+    }
+    kwargs.set_item("n_particles", n_existing + n_additional)?;
+    kwargs.set_item("existing_coords", existing_coords)?;
+    kwargs.set_item("existing_radii", existing_radii)?;
+    simulation_function(py, algorithm)?.call((), Some(&kwargs))?.extract()
+}
+
+/// Continue a DLA run from an existing agglomerate.
+///
+/// Walkers stick onto the given particles, which are moved so their center
+/// of mass sits at the origin and keep the first IDs of the result. Use it
+/// to resume a large run from a saved structure or to grow a DLA shell on a
+/// core made by another engine.
+///
+/// # Arguments
+/// * `existing_coords` - Coordinates of the existing agglomerate (N x 3 array)
+/// * `existing_radii` - Radii of the existing particles (N array)
+/// * `n_additional` - Number of particles to add
+/// * `**kwargs` - Any other keyword argument of `run_dla` (`seed`, `sintering`, ...)
+///
+/// # Returns
+/// * `SimulationResult` with the N + `n_additional` particles
+#[pyfunction]
+#[pyo3(signature = (existing_coords, existing_radii, n_additional, **kwargs))]
+pub fn run_dla_continue(
+    py: Python<'_>,
+    existing_coords: &Bound<'_, PyAny>,
+    existing_radii: &Bound<'_, PyAny>,
+    n_additional: usize,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PySimulationResult> {
+    continue_run(py, "dla", existing_coords, existing_radii, n_additional, kwargs)
+}
+
+/// Continue a ballistic aggregation run from an existing agglomerate.
+///
+/// Projectiles stick onto the given particles, which are moved so their
+/// center of mass sits at the origin and keep the first IDs of the result,
+/// e.g. to coat a DLA core with a dense ballistic shell.
+///
+/// # Arguments
+/// * `existing_coords` - Coordinates of the existing agglomerate (N x 3 array)
+/// * `existing_radii` - Radii of the existing particles (N array)
+/// * `n_additional` - Number of particles to add
+/// * `**kwargs` - Any other keyword argument of `run_ballistic` (`seed`, `sintering`, ...)
+///
+/// # Returns
+/// * `SimulationResult` with the N + `n_additional` particles
+#[pyfunction]
+#[pyo3(signature = (existing_coords, existing_radii, n_additional, **kwargs))]
+pub fn run_ballistic_continue(
+    py: Python<'_>,
+    existing_coords: &Bound<'_, PyAny>,
+    existing_radii: &Bound<'_, PyAny>,
+    n_additional: usize,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PySimulationResult> {
+    continue_run(py, "ballistic", existing_coords, existing_radii, n_additional, kwargs)
+}
+
+/// Continue a tunable particle-cluster run from an existing agglomerate.
+///
+/// New particles are placed so that every intermediate size follows
+/// N = kf (Rg/rp)^Df; the given particles are moved so their center of
+/// mass sits at the origin and keep the first IDs of the result. The
+/// existing agglomerate should roughly obey the same targets, otherwise the
+/// first additions compensate for the mismatch.
+///
+/// # Arguments
+/// * `existing_coords` - Coordinates of the existing agglomerate (N x 3 array)
+/// * `existing_radii` - Radii of the existing particles (N array)
+/// * `n_additional` - Number of particles to add
+/// * `**kwargs` - Any other keyword argument of `run_tunable` (`target_df`, `seed`, ...)
+///
+/// # Returns
+/// * `SimulationResult` with the N + `n_additional` particles
+#[pyfunction]
+#[pyo3(signature = (existing_coords, existing_radii, n_additional, **kwargs))]
+pub fn run_tunable_continue(
+    py: Python<'_>,
+    existing_coords: &Bound<'_, PyAny>,
+    existing_radii: &Bound<'_, PyAny>,
+    n_additional: usize,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PySimulationResult> {
+    continue_run(py, "tunable", existing_coords, existing_radii, n_additional, kwargs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_existing_particles_are_grouped_and_centered() {
+        let spheres: Vec<Sphere> = (0..4)
+            .map(|i| Sphere::new(Vector3::new(2.0 * i as f64, 1.0, 0.0), 1.0))
+            .collect();
+
+        let clusters = group_clusters(spheres.clone(), Some(vec![5, 2, 5, 2])).unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0][1].center, Vector3::new(4.0, 1.0, 0.0));
+        assert_eq!(group_clusters(spheres.clone(), None).unwrap().len(), 1);
+        assert!(group_clusters(spheres.clone(), Some(vec![0])).is_err());
+
+        let store = centered_store(&spheres, 10);
+        assert_eq!(store.center(0), Vector3::new(-3.0, 0.0, 0.0));
+        assert!(check_existing(3, spheres.len()).is_err());
+    }
+
+    #[test]
+    fn test_new_monomers_are_drawn_clear_of_the_pool() {
+        use crate::common::rng::create_rng;
+        use rand::Rng;
+
+        // A 3x3x3 block of touching spheres filling most of a box of 10
+        let pool: Vec<Sphere> = (0..27)
+            .map(|i| {
+                let c = Vector3::new((i % 3) as f64, (i / 3 % 3) as f64, (i / 9) as f64) * 2.0;
+                Sphere::new(c - Vector3::new(2.0, 2.0, 2.0), 1.0)
+            })
+            .collect();
+        let occupied = pool_occupancy(&pool, 10.0, 1.0);
+        let mut rng = create_rng(3);
+        for _ in 0..50 {
+            let (monomer, clear) = draw_clear_monomer(&occupied, || {
+                let mut coord = || (rng.gen::<f64>() - 0.5) * 10.0;
+                Sphere::new(Vector3::new(coord(), coord(), coord()), 1.0)
+            });
+            assert!(clear);
+            assert!(pool.iter().all(|p| p.center.distance_to(&monomer.center) >= 2.0));
+        }
+
+        let (_, clear) = draw_clear_monomer(&occupied, || Sphere::new(Vector3::zero(), 1.0));
+        assert!(!clear);
+    }
+}
@@ -1,18 +1,70 @@
 //! Simulation result types.
 
+use std::time::Instant;
+
 use numpy::{PyArray1, PyArray2, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::common::fitting::FitMethod;
+use crate::common::serialization;
+use crate::common::units::PyUnits;
+use crate::common::validation::{check_coordinates, check_count, check_positive};
+use crate::common::warnings::emit_warnings;
+use crate::projection::extract_structure;
+
+use super::brownian::PyBrownianReport;
+use super::contact_graph::ContactGraph;
+use super::deposition::PyFilmReport;
+use super::drift::PyDriftReport;
+use super::history::PyGrowthHistory;
+use super::lineage::MergeEvent;
+use super::metrics::{
+    calculate_contacts, calculate_gyration_tensor, calculate_inertia_tensor, calculate_porosity, calculate_surface_area,
+    coordination_from_contacts, mass_radius_profile, overlap_coefficients, overlap_statistics, porosity_by_method, Contact,
+    EvolutionFit, GyrationTensorResult, PorosityMethod,
+};
+use super::mobility::{mobility_diameter, PyMobilityDiameter};
+use super::provenance::json_to_py;
+use super::sticking::PyAggregationKinetics;
+use super::tunable_cc::PyMergeDiagnostics;
+
+/// Number of nested sub-clusters sampled as the Rg evolution of a loaded structure.
+pub(crate) const PROFILE_POINTS: usize = 20;
 
 /// Python wrapper for simulation results.
 #[pyclass]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PySimulationResult {
     #[pyo3(get)]
     pub fractal_dimension: f64,
+    /// Standard error of `fractal_dimension` from the (N, Rg) regression
+    /// (NaN when the run has too few samples to fit).
     #[pyo3(get)]
     pub fractal_dimension_std: f64,
     #[pyo3(get)]
     pub prefactor: f64,
+    /// R² of the log-log (N, Rg) regression over the linear region.
+    #[pyo3(get)]
+    pub fit_r_squared: f64,
+    /// Estimator of the (N, Rg) regression line ("ols" unless refitted).
+    #[pyo3(get)]
+    pub fit_method: String,
+    /// Regression the engine fitted, which `refit` repeats.
+    #[serde(default)]
+    pub(crate) evolution_fit: EvolutionFit,
+    /// Number of (N, Rg) samples the Df fit used.
+    #[pyo3(get)]
+    pub fit_n_points: usize,
+    /// Start index into `rg_evolution` of the region the Df fit used.
+    #[pyo3(get)]
+    pub linear_region_start: usize,
+    /// End index (exclusive) into `rg_evolution` of the region the Df fit used.
+    #[pyo3(get)]
+    pub linear_region_end: usize,
     #[pyo3(get)]
     pub radius_of_gyration: f64,
     #[pyo3(get)]
@@ -25,6 +77,11 @@ pub struct PySimulationResult {
     pub execution_time_ms: u64,
     #[pyo3(get)]
     pub seed: u64,
+    /// Seeds of the nested runs of this one, in order: the Tunable PC runs
+    /// growing the seed clusters of tunable CC, cluster `k` seeded with
+    /// `SeedSequence(seed).spawn(0).seed(k)`. Empty for other engines.
+    #[pyo3(get)]
+    pub child_seeds: Vec<u64>,
 
     // Inertia tensor results
     #[pyo3(get)]
@@ -34,16 +91,121 @@ pub struct PySimulationResult {
     #[pyo3(get)]
     pub acylindricity: f64,
 
+    /// Relative shape anisotropy kappa² of the gyration tensor (0 = spherical, 1 = rod).
+    #[pyo3(get)]
+    pub relative_shape_anisotropy: f64,
+
+    /// Tag of the `AnalysisSession` that produced this result, if any.
+    #[pyo3(get)]
+    #[serde(default)]
+    pub session: Option<String>,
+
+    /// Warnings raised during the run (also emitted as Python `UserWarning`s).
+    #[pyo3(get)]
+    pub warnings: Vec<String>,
+
+    /// Physical units of the lengths, if the run was given any.
+    #[pyo3(get)]
+    #[serde(default)]
+    pub units: Option<PyUnits>,
+
+    /// How closely a tunable run followed its target Df/kf (None for other engines).
+    #[pyo3(get)]
+    #[serde(default)]
+    pub target_report: Option<TargetReport>,
+
+    /// Physical time scales of a `run_cca_physical` run (None for other engines).
+    #[pyo3(get)]
+    #[serde(default)]
+    pub brownian_report: Option<PyBrownianReport>,
+
+    /// Collision statistics and kernel homogeneity of a CCA run (None for other engines).
+    #[pyo3(get)]
+    #[serde(default)]
+    pub kinetics: Option<PyAggregationKinetics>,
+
+    /// Thickness and packing of a `run_ballistic_deposition` film (None for other engines).
+    #[pyo3(get)]
+    #[serde(default)]
+    pub film: Option<PyFilmReport>,
+
+    /// Shape relative to the field of a DLA run with `drift` (None otherwise).
+    #[pyo3(get)]
+    #[serde(default)]
+    pub drift_report: Option<PyDriftReport>,
+
+    /// How each merge of a tunable CC run was placed (None for other engines).
+    #[pyo3(get)]
+    #[serde(default)]
+    pub merge_diagnostics: Option<PyMergeDiagnostics>,
+
+    /// Growth history, if the run was given `HistoryParams`.
+    #[pyo3(get)]
+    #[serde(default)]
+    pub history: Option<PyGrowthHistory>,
+
+    /// Algorithm, crate version and call arguments of the run, see `parameters`
+    #[serde(rename = "parameters", default)]
+    pub(crate) parameters_data: Option<Value>,
+
     // Internal storage for arrays
+    #[serde(rename = "coordinates")]
     pub(crate) coordinates_data: Vec<f64>,
+    #[serde(rename = "radii")]
     pub(crate) radii_data: Vec<f64>,
+    #[serde(rename = "rg_evolution")]
     pub(crate) rg_evolution_data: Vec<f64>,
+    #[serde(rename = "n_evolution")]
+    pub(crate) n_evolution_data: Vec<usize>,
+    #[serde(rename = "principal_moments")]
     pub(crate) principal_moments_data: [f64; 3],
+    #[serde(rename = "principal_axes")]
     pub(crate) principal_axes_data: [[f64; 3]; 3],
+    pub(crate) gyration: GyrationTensorResult,
+    #[serde(rename = "ids")]
+    pub(crate) ids_data: Vec<u32>,
+    #[serde(rename = "cluster_ids")]
+    pub(crate) cluster_ids_data: Vec<u32>,
+    #[serde(rename = "coordination")]
+    pub(crate) coordination_data: Vec<u32>,
+    #[serde(rename = "contacts")]
+    pub(crate) contacts_data: Vec<Contact>,
+    #[serde(rename = "generations")]
+    pub(crate) generations_data: Vec<u32>,
+    #[serde(rename = "merge_history")]
+    pub(crate) merge_history_data: Vec<MergeEvent>,
 }
 
 #[pymethods]
 impl PySimulationResult {
+    /// Build a result from saved coordinates and radii, recomputing its metrics.
+    ///
+    /// Rg, porosity, coordination and the inertia tensor are computed as at
+    /// the end of a run. A loaded structure has no growth history, so
+    /// `rg_evolution` holds the Rg of the sub-clusters formed by the k
+    /// particles nearest to the center of gravity, and Df/kf are fitted to
+    /// it. IDs follow the row order; `seed` is 0 and `execution_time_ms` is
+    /// the time spent recomputing the metrics.
+    ///
+    /// # Arguments
+    /// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+    /// * `radii` - Particle radii (N array, float32 or float64)
+    /// * `units` - `Units` giving the physical meaning of the lengths
+    #[staticmethod]
+    #[pyo3(signature = (coordinates, radii, units=None))]
+    pub fn from_arrays(
+        py: Python<'_>,
+        coordinates: &Bound<'_, PyAny>,
+        radii: &Bound<'_, PyAny>,
+        units: Option<PyUnits>,
+    ) -> PyResult<Self> {
+        let (coords, radii) = extract_structure(coordinates, radii)?;
+        check_coordinates("coordinates", &coords)?;
+        let coords: Vec<[f64; 3]> = coords.rows().into_iter().map(|c| [c[0], c[1], c[2]]).collect();
+        let result = py.allow_threads(|| SimulationResult::from_structure(coords, radii));
+        result.into_py(py, Vec::new()).map(|r| r.with_units(units))
+    }
+
     /// Get particle coordinates as numpy array (N, 3).
     #[getter]
     fn coordinates<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
@@ -72,6 +234,37 @@ impl PySimulationResult {
         PyArray1::from_vec(py, self.rg_evolution_data.clone())
     }
 
+    /// Get the particle count of each `rg_evolution` sample as numpy array (N,).
+    #[getter]
+    fn n_evolution<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<usize>> {
+        PyArray1::from_vec(py, self.n_evolution_data.clone())
+    }
+
+    /// Radius of gyration in nm, or None without `units`.
+    #[getter]
+    fn radius_of_gyration_nm(&self) -> Option<f64> {
+        self.units.as_ref().map(|u| u.to_nm(self.radius_of_gyration))
+    }
+
+    /// Total volume of the primary particles in nm³ (overlaps counted twice),
+    /// or None without `units`.
+    #[getter]
+    fn volume_nm3(&self) -> Option<f64> {
+        let units = self.units.as_ref()?;
+        Some(
+            self.radii_data
+                .iter()
+                .map(|&r| 4.0 / 3.0 * std::f64::consts::PI * units.to_nm(r).powi(3))
+                .sum(),
+        )
+    }
+
+    /// Mass in kg from `volume_nm3` and the units' density, or None when either is unknown.
+    #[getter]
+    fn mass(&self) -> Option<f64> {
+        self.units.as_ref()?.mass_kg(self.volume_nm3()?)
+    }
+
     /// Get principal moments of inertia as numpy array (3,).
     /// Sorted: I1 <= I2 <= I3
     #[getter]
@@ -89,6 +282,454 @@ impl PySimulationResult {
             .collect();
         PyArray2::from_vec2(py, &arr).unwrap()
     }
+
+    /// Get the gyration tensor as numpy array (3, 3).
+    /// Mass-weighted covariance of the particle positions; its trace is Rg².
+    #[getter]
+    fn gyration_tensor<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let arr: Vec<Vec<f64>> = self.gyration.tensor.iter().map(|row| row.to_vec()).collect();
+        PyArray2::from_vec2(py, &arr).unwrap()
+    }
+
+    /// Get the principal moments of the gyration tensor as numpy array (3,).
+    /// Sorted: l1 <= l2 <= l3, the squared extents along the principal axes.
+    #[getter]
+    fn gyration_moments<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, self.gyration.principal_moments.to_vec())
+    }
+
+    /// Get the principal axes of the gyration tensor as numpy array (3, 3).
+    /// Each row is an axis, in the order of `gyration_moments`.
+    #[getter]
+    fn gyration_axes<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let arr: Vec<Vec<f64>> = self.gyration.principal_axes.iter().map(|axis| axis.to_vec()).collect();
+        PyArray2::from_vec2(py, &arr).unwrap()
+    }
+
+    /// Get the persistent ID of each particle as numpy array (N,).
+    /// IDs are assigned when particles are created and survive merges,
+    /// so particles can be matched across snapshots and related runs.
+    #[getter]
+    fn ids<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        PyArray1::from_vec(py, self.ids_data.clone())
+    }
+
+    /// Get the cluster index of each particle as numpy array (N,).
+    /// All zeros unless the run ended with several agglomerates.
+    #[getter]
+    fn cluster_ids<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        PyArray1::from_vec(py, self.cluster_ids_data.clone())
+    }
+
+    /// Get the coordination number of each particle as numpy array (N,).
+    #[getter]
+    fn coordination<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        PyArray1::from_vec(py, self.coordination_data.clone())
+    }
+
+    /// Get the contacts between particles as a list of
+    /// `(i, j, sintering_coeff, overlap_distance)` tuples, one per touching pair.
+    ///
+    /// `sintering_coeff` is the center distance over r_i + r_j (1 = point
+    /// contact, lower = sintered) and `overlap_distance` is r_i + r_j minus the
+    /// center distance, enough to rebuild the neck geometry of every bond.
+    #[getter]
+    fn contacts(&self) -> Vec<(usize, usize, f64, f64)> {
+        self.contacts_data
+            .iter()
+            .map(|c| (c.i, c.j, c.sintering_coeff, c.overlap))
+            .collect()
+    }
+
+    /// Get the contacting particle pairs as numpy array (M, 2) of row indices `i < j`,
+    /// the edge list of the contact graph, e.g. for `networkx.Graph(pairs.tolist())`.
+    fn contact_pairs<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<u32>> {
+        let rows: Vec<Vec<u32>> = self
+            .contacts_data
+            .iter()
+            .map(|c| vec![c.i as u32, c.j as u32])
+            .collect();
+        if rows.is_empty() {
+            return PyArray2::zeros(py, [0, 2], false);
+        }
+        PyArray2::from_vec2(py, &rows).unwrap()
+    }
+
+    /// Split the particles into connected agglomerates.
+    ///
+    /// Returns one numpy array of particle row indices per connected component
+    /// of the contact graph, largest first, so `coordinates[idx]` and
+    /// `radii[idx]` give each agglomerate of a multi-agglomerate CCA run.
+    fn connected_components<'py>(&self, py: Python<'py>) -> Vec<Bound<'py, PyArray1<usize>>> {
+        ContactGraph::new(self.radii_data.len(), &self.contacts_data)
+            .components()
+            .into_iter()
+            .map(|c| PyArray1::from_vec(py, c))
+            .collect()
+    }
+
+    /// Get the overlap coefficient Cov = (r_i + r_j - d_ij) / (r_i + r_j) of each
+    /// contact as numpy array (M,), in `contacts` order (0 for point contacts).
+    #[getter]
+    fn overlap_coefficients<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, overlap_coefficients(&self.contacts_data))
+    }
+
+    /// Mean, standard deviation and histogram of the contacts' overlap coefficients.
+    ///
+    /// Cov is the standard descriptor of primary particle overlap in
+    /// sintered soot; compare it with TEM measurements or with the
+    /// `SinteringParams` distribution the run was given.
+    ///
+    /// # Arguments
+    /// * `bins` - Number of histogram bins (default: 20)
+    /// * `max_overlap` - Upper edge of the histogram (default: the largest Cov,
+    ///   or 1 when every contact is a point contact)
+    #[pyo3(signature = (bins=20, max_overlap=None))]
+    fn overlap_statistics(&self, bins: usize, max_overlap: Option<f64>) -> PyResult<PyOverlapStatistics> {
+        check_count("bins", bins, 1)?;
+        if let Some(max_overlap) = max_overlap {
+            check_positive("max_overlap", max_overlap)?;
+        }
+        let coefficients = overlap_coefficients(&self.contacts_data);
+        let largest = coefficients.iter().copied().fold(0.0, f64::max);
+        let max_overlap = max_overlap.unwrap_or(if largest > 0.0 { largest } else { 1.0 });
+        let stats = overlap_statistics(&coefficients, bins, max_overlap);
+        Ok(PyOverlapStatistics {
+            n_contacts: coefficients.len(),
+            mean: stats.mean,
+            std: stats.std,
+            bin_edges: stats.bin_edges,
+            counts: stats.counts,
+        })
+    }
+
+    /// Exposed surface area of the agglomerate.
+    ///
+    /// Every sintered contact hides a spherical cap of both particles, cut by
+    /// the plane of their neck; the caps are subtracted from the spheres'
+    /// 4 pi r². Exact while the caps on one particle do not overlap each other.
+    #[getter]
+    fn surface_area(&self) -> f64 {
+        calculate_surface_area(&self.radii_data, &self.contacts_data).total
+    }
+
+    /// Get the exposed surface area of each particle as numpy array (N,).
+    #[getter]
+    fn particle_surface_areas<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, calculate_surface_area(&self.radii_data, &self.contacts_data).particle_areas)
+    }
+
+    /// Get the neck radius of each contact as numpy array (M,), in `contacts` order.
+    /// 0 for point contacts.
+    #[getter]
+    fn neck_radii<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        PyArray1::from_vec(py, calculate_surface_area(&self.radii_data, &self.contacts_data).neck_radii)
+    }
+
+    /// Specific surface area in m²/g, to compare with BET measurements, or
+    /// None without `units` or density.
+    ///
+    /// The mass is the one of `mass`, where overlapping volumes count twice,
+    /// so heavily sintered agglomerates come out slightly low.
+    #[getter]
+    fn specific_surface_area(&self) -> Option<f64> {
+        let units = self.units.as_ref()?;
+        let mass_g = self.mass()? * 1e3;
+        let area_m2 = self.surface_area() * units.to_nm(1.0).powi(2) * 1e-18;
+        Some(area_m2 / mass_g)
+    }
+
+    /// Porosity within a chosen envelope of the agglomerate.
+    ///
+    /// `porosity` compares the particle volume with a sphere of radius
+    /// 2 Rg, which is quick but strongly biased for elongated or compact
+    /// agglomerates. The envelopes here follow the actual outline:
+    ///
+    /// * "convex_hull" - convex hull of the particles
+    /// * "voxel" - region a probe sphere of the mean particle radius cannot
+    ///   reach from outside, computed on a voxel grid; tighter than the hull
+    ///   around the branches of a fractal agglomerate
+    /// * "bounding_sphere" - the same value as `porosity`
+    ///
+    /// # Arguments
+    /// * `porosity_method` - Envelope to use (default: "convex_hull")
+    #[pyo3(signature = (porosity_method="convex_hull"))]
+    fn envelope_porosity(&self, py: Python<'_>, porosity_method: &str) -> PyResult<f64> {
+        let method = PorosityMethod::from_name(porosity_method).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "unknown porosity_method '{}': use 'bounding_sphere', 'convex_hull' or 'voxel'",
+                porosity_method
+            ))
+        })?;
+        let coordinates: Vec<[f64; 3]> = self.coordinates_data.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+        Ok(py.allow_threads(|| porosity_by_method(&coordinates, &self.radii_data, method)))
+    }
+
+    /// Refit Df and kf to the (N, Rg) evolution with another estimator.
+    ///
+    /// The linear region is detected as for a run, then its line is fitted
+    /// with `fit_method`, on the same scaled samples as the engine's fit, so
+    /// "ols" reproduces the original Df and kf. The robust
+    /// estimators keep a few off samples (early restructuring, a late merge)
+    /// from tilting Df.
+    ///
+    /// # Arguments
+    /// * `fit_method` - "ols", "theil_sen", "ransac" or "wls" (each sample
+    ///   weighted by its particle count) (default: "ols")
+    ///
+    /// # Returns
+    /// A copy of the result with the fit replaced and `fit_method` recorded.
+    #[pyo3(signature = (fit_method="ols"))]
+    fn refit(&self, py: Python<'_>, fit_method: &str) -> PyResult<Self> {
+        let method = FitMethod::from_name(fit_method)?;
+        let result = self.refitted(method);
+        emit_warnings(py, &result.warnings[self.warnings.len()..])?;
+        Ok(result)
+    }
+
+    /// Mobility-equivalent diameters in the free-molecular and continuum
+    /// regimes, from the orientation-averaged projected area and Rg.
+    pub(crate) fn mobility_diameter(&self, py: Python<'_>) -> PyMobilityDiameter {
+        let coordinates: Vec<[f64; 3]> = self.coordinates_data.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+        py.allow_threads(|| mobility_diameter(&coordinates, &self.radii_data))
+    }
+
+    /// Get the merge generation of each particle as numpy array (N,).
+    /// Counts the cluster-cluster merges the particle took part in;
+    /// all zeros for particle-cluster engines.
+    #[getter]
+    fn generations<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        PyArray1::from_vec(py, self.generations_data.clone())
+    }
+
+    /// Get the cluster merge history as numpy array (M, 6).
+    ///
+    /// One row per merge: step, first, second, merged, first_size, second_size.
+    /// The k initial clusters are labelled 0..k and the m-th merge creates
+    /// label k + m, as in `scipy.cluster.hierarchy.linkage`.
+    /// Empty for particle-cluster engines.
+    #[getter]
+    fn merge_history<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<u64>> {
+        let rows: Vec<Vec<u64>> = self
+            .merge_history_data
+            .iter()
+            .map(|e| {
+                vec![
+                    e.step as u64,
+                    e.first as u64,
+                    e.second as u64,
+                    e.merged as u64,
+                    e.first_size as u64,
+                    e.second_size as u64,
+                ]
+            })
+            .collect();
+        if rows.is_empty() {
+            return PyArray2::zeros(py, [0, 6], false);
+        }
+        PyArray2::from_vec2(py, &rows).unwrap()
+    }
+
+    /// Get the lineage tree as an edge list (2M, 2) of (child, parent) labels.
+    /// Each merge contributes two edges pointing at the merged cluster, ready
+    /// for e.g. `networkx.DiGraph(edges.tolist())`.
+    #[getter]
+    fn lineage_edges<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<u32>> {
+        let rows: Vec<Vec<u32>> = self
+            .merge_history_data
+            .iter()
+            .flat_map(|e| [vec![e.first, e.merged], vec![e.second, e.merged]])
+            .collect();
+        if rows.is_empty() {
+            return PyArray2::zeros(py, [0, 2], false);
+        }
+        PyArray2::from_vec2(py, &rows).unwrap()
+    }
+
+    /// Get all per-particle data as a single numpy structured array (N,).
+    ///
+    /// Fields: id (uint32), x, y, z, r (float64) and species, cluster_id,
+    /// coordination, generation (uint32). Keeping the columns in one record array rules out index
+    /// misalignment and converts directly with `pandas.DataFrame(arr)`.
+    /// `species` is 0 for every particle; the engines are single-species.
+    fn to_structured<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let n = self.radii_data.len();
+        let dtype = vec![
+            ("id", "u4"),
+            ("x", "f8"),
+            ("y", "f8"),
+            ("z", "f8"),
+            ("r", "f8"),
+            ("species", "u4"),
+            ("cluster_id", "u4"),
+            ("coordination", "u4"),
+            ("generation", "u4"),
+        ];
+        let records = py.import("numpy")?.call_method1("zeros", (n, dtype))?;
+
+        for (axis, name) in ["x", "y", "z"].into_iter().enumerate() {
+            let column: Vec<f64> = self.coordinates_data.iter().skip(axis).step_by(3).copied().collect();
+            records.set_item(name, PyArray1::from_vec(py, column))?;
+        }
+        records.set_item("id", PyArray1::from_vec(py, self.ids_data.clone()))?;
+        records.set_item("r", PyArray1::from_vec(py, self.radii_data.clone()))?;
+        records.set_item("cluster_id", PyArray1::from_vec(py, self.cluster_ids_data.clone()))?;
+        records.set_item("coordination", PyArray1::from_vec(py, self.coordination_data.clone()))?;
+        records.set_item("generation", PyArray1::from_vec(py, self.generations_data.clone()))?;
+
+        Ok(records)
+    }
+
+    /// Parameters of the run as a dict: `algorithm` (the `run_*` function
+    /// called), `version` of aglogen_core, every parameter the engine ran
+    /// with once defaults, sintering and size options are resolved, `seed`
+    /// holding the seed used even when none was given, and the `units` and
+    /// `history` settings. Callbacks and cancel events are left out; `Units`,
+    /// distributions and the other parameter objects become dicts of their
+    /// fields. None for structures loaded from arrays or files.
+    #[getter]
+    fn parameters<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        self.parameters_data.as_ref().map(|p| json_to_py(py, p)).transpose()
+    }
+
+    /// The result as JSON, `parameters` included; `from_json` reads it back.
+    fn to_json(&self) -> PyResult<String> {
+        serialization::to_json(self)
+    }
+
+    /// Result written by `to_json` (non-finite floats come back as NaN, or None when optional).
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        serialization::from_json(json)
+    }
+
+    /// The result as MessagePack bytes; `from_msgpack` reads them back.
+    fn to_msgpack<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new(py, &serialization::to_msgpack(self)?))
+    }
+
+    /// Result written by `to_msgpack`.
+    #[staticmethod]
+    fn from_msgpack(data: &[u8]) -> PyResult<Self> {
+        serialization::from_msgpack(data)
+    }
+}
+
+impl PySimulationResult {
+    /// Attach the physical units the run was given.
+    pub fn with_units(mut self, units: Option<PyUnits>) -> Self {
+        self.units = units;
+        self
+    }
+
+    /// Attach the algorithm, version and parameters of the run.
+    pub fn with_parameters(mut self, parameters: Value) -> Self {
+        self.parameters_data = Some(parameters);
+        self
+    }
+
+    /// Attach the growth history recorded during the run.
+    pub fn with_history(mut self, history: Option<PyGrowthHistory>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Copy of the result with Df and kf refitted by `method`, the fit's
+    /// warnings appended to `warnings`.
+    pub(crate) fn refitted(&self, method: FitMethod) -> Self {
+        let mut warnings = Vec::new();
+        let fit = self.evolution_fit.fit(&self.n_evolution_data, &self.rg_evolution_data, method, &mut warnings);
+
+        let mut result = self.clone();
+        result.fractal_dimension = fit.df;
+        result.fractal_dimension_std = fit.df_std_error;
+        result.prefactor = fit.kf;
+        result.fit_r_squared = fit.r_squared;
+        result.fit_n_points = fit.n_points;
+        (result.linear_region_start, result.linear_region_end) = fit.linear_region;
+        result.fit_method = method.name().to_string();
+        result.warnings.extend(warnings);
+        result
+    }
+}
+
+/// How closely a tunable run followed its target Df/kf.
+///
+/// A merge "satisfies the target" when the clusters were placed at the
+/// distance the power law `N = kf * (Rg/rp)^Df` requires; fallback merges
+/// ignore it. A high fallback share means the targets were effectively
+/// not enforced.
+#[pyclass(name = "TargetReport")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetReport {
+    #[pyo3(get)]
+    pub target_df: f64,
+    #[pyo3(get)]
+    pub target_kf: f64,
+    /// Merges placed at the CoM distance the power law requires.
+    #[pyo3(get)]
+    pub target_merges: usize,
+    /// Merges that fell back to ballistic placement or that the power law had no distance for.
+    #[pyo3(get)]
+    pub fallback_merges: usize,
+    /// Pairings given up in strict mode because the power law could not be satisfied.
+    #[pyo3(get)]
+    pub rejected_pairings: usize,
+    /// Share of the merges that satisfied the power law.
+    #[pyo3(get)]
+    pub target_fraction: f64,
+    /// Measured minus target Df and kf of the final agglomerate.
+    #[pyo3(get)]
+    pub df_deviation: f64,
+    #[pyo3(get)]
+    pub kf_deviation: f64,
+    /// Whether the run used strict mode (no fallback merges).
+    #[pyo3(get)]
+    pub strict: bool,
+    /// Strict mode gave up: no pairing of the remaining clusters could be placed.
+    #[pyo3(get)]
+    pub infeasible: bool,
+}
+
+#[pymethods]
+impl TargetReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "TargetReport(target_fraction={:.3}, df_deviation={:.4}, kf_deviation={:.4}, strict={})",
+            self.target_fraction, self.df_deviation, self.kf_deviation, self.strict
+        )
+    }
+}
+
+/// Overlap coefficients of an agglomerate's contacts, from
+/// `SimulationResult.overlap_statistics`.
+#[pyclass(name = "OverlapStatistics")]
+#[derive(Debug, Clone)]
+pub struct PyOverlapStatistics {
+    #[pyo3(get)]
+    pub n_contacts: usize,
+    /// Mean Cov over all contacts (0 without contacts)
+    #[pyo3(get)]
+    pub mean: f64,
+    #[pyo3(get)]
+    pub std: f64,
+    /// `bins + 1` histogram edges starting at 0
+    #[pyo3(get)]
+    pub bin_edges: Vec<f64>,
+    /// Contacts per bin; Cov above the last edge are not counted
+    #[pyo3(get)]
+    pub counts: Vec<usize>,
+}
+
+#[pymethods]
+impl PyOverlapStatistics {
+    fn __repr__(&self) -> String {
+        format!(
+            "OverlapStatistics(n_contacts={}, mean={:.4}, std={:.4})",
+            self.n_contacts, self.mean, self.std
+        )
+    }
 }
 
 /// Internal simulation result (before conversion to Python).
@@ -96,23 +737,136 @@ pub struct SimulationResult {
     pub coordinates: Vec<[f64; 3]>,
     pub radii: Vec<f64>,
     pub rg_evolution: Vec<f64>,
+    /// Particle count of the cluster at each `rg_evolution` sample.
+    pub n_evolution: Vec<usize>,
     pub fractal_dimension: f64,
+    /// Standard error of the fitted Df.
     pub fractal_dimension_std: f64,
     pub prefactor: f64,
+    /// `start..end` indices into `rg_evolution` of the Df fit's linear region.
+    pub linear_region: (usize, usize),
+    /// Scaling and direction of the Df fit's regression.
+    pub evolution_fit: EvolutionFit,
+    /// R² of the Df fit.
+    pub fit_r_squared: f64,
+    /// Samples in the Df fit's linear region.
+    pub fit_n_points: usize,
     pub porosity: f64,
     pub coordination_mean: f64,
     pub coordination_std: f64,
     pub execution_time_ms: u64,
     pub seed: u64,
+    /// Seeds of the nested runs made with substreams of `seed`.
+    pub child_seeds: Vec<u64>,
     // Inertia tensor results
     pub anisotropy: f64,
     pub asphericity: f64,
     pub acylindricity: f64,
     pub principal_moments: [f64; 3],
     pub principal_axes: [[f64; 3]; 3],
+    /// Persistent ID of each particle (its creation index).
+    pub ids: Vec<u32>,
+    /// Index of the cluster each particle belongs to at the end of the run.
+    pub cluster_ids: Vec<u32>,
+    /// Coordination number of each particle.
+    pub coordination: Vec<u32>,
+    /// Touching and sintered particle pairs.
+    pub contacts: Vec<Contact>,
+    /// Number of cluster-cluster merges each particle took part in.
+    pub generations: Vec<u32>,
+    /// Cluster lineage tree as a list of merges (empty for particle-cluster engines).
+    pub merge_history: Vec<MergeEvent>,
+    /// Silent behaviour changes (fallbacks, clamped values) during the run.
+    pub warnings: Vec<String>,
+    /// Adherence to the target Df/kf, for engines that have one.
+    pub target_report: Option<TargetReport>,
+    /// Physical time scales, for Brownian CCA runs.
+    pub brownian_report: Option<PyBrownianReport>,
+    /// Sticking kinetics, for CCA runs.
+    pub kinetics: Option<PyAggregationKinetics>,
+    /// Film metrics, for deposition runs.
+    pub film: Option<PyFilmReport>,
+    /// Shape relative to the drift, for DLA runs with a drift.
+    pub drift_report: Option<PyDriftReport>,
+    /// Placement of every merge, for tunable CC runs.
+    pub merge_diagnostics: Option<PyMergeDiagnostics>,
 }
 
 impl SimulationResult {
+    /// Recompute the metrics of a finished structure, e.g. one loaded from a file.
+    pub fn from_structure(coordinates: Vec<[f64; 3]>, radii: Vec<f64>) -> SimulationResult {
+        let start_time = Instant::now();
+        let n = coordinates.len();
+        let mut warnings = Vec::new();
+
+        let mean_radius = if n > 0 { radii.iter().sum::<f64>() / n as f64 } else { 1.0 };
+        let (n_evolution, rg_evolution) = mass_radius_profile(&coordinates, &radii, 2, PROFILE_POINTS);
+        // Fit in units of the mean radius so kf does not depend on the length unit
+        let evolution_fit = EvolutionFit::RgOnN { scale: mean_radius };
+        let fit = evolution_fit.fit(&n_evolution, &rg_evolution, FitMethod::Ols, &mut warnings);
+
+        let porosity = calculate_porosity(&coordinates, &radii);
+        let contacts = calculate_contacts(&coordinates, &radii, mean_radius * 0.1);
+        let coordination = coordination_from_contacts(&contacts, n);
+        let inertia = calculate_inertia_tensor(&coordinates, &radii);
+
+        let coord_mean = coordination.iter().map(|&c| c as f64).sum::<f64>() / n.max(1) as f64;
+        let coord_std = (coordination
+            .iter()
+            .map(|&c| (c as f64 - coord_mean).powi(2))
+            .sum::<f64>()
+            / n.max(1) as f64)
+            .sqrt();
+
+        SimulationResult {
+            coordinates,
+            radii,
+            rg_evolution,
+            n_evolution,
+            fractal_dimension: fit.df,
+            fractal_dimension_std: fit.df_std_error,
+            prefactor: fit.kf,
+            linear_region: fit.linear_region,
+            evolution_fit,
+            fit_r_squared: fit.r_squared,
+            fit_n_points: fit.n_points,
+            porosity,
+            coordination_mean: coord_mean,
+            coordination_std: coord_std,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+            seed: 0,
+            child_seeds: Vec::new(),
+            anisotropy: inertia.anisotropy,
+            asphericity: inertia.asphericity,
+            acylindricity: inertia.acylindricity,
+            principal_moments: inertia.principal_moments,
+            principal_axes: inertia.principal_axes,
+            ids: (0..n as u32).collect(),
+            cluster_ids: vec![0; n],
+            coordination,
+            contacts,
+            generations: vec![0; n],
+            merge_history: Vec::new(),
+            warnings,
+            target_report: None,
+            brownian_report: None,
+            kinetics: None,
+            film: None,
+            drift_report: None,
+            merge_diagnostics: None,
+        }
+    }
+
+    /// Emit the run's warnings as Python `UserWarning`s and convert to the Python result.
+    ///
+    /// `setup` holds warnings raised while parsing the call arguments; they
+    /// are reported before the ones collected by the engine.
+    pub fn into_py(mut self, py: Python<'_>, setup: Vec<String>) -> PyResult<PySimulationResult> {
+        self.warnings.splice(0..0, setup);
+        emit_warnings(py, &self.warnings)?;
+        Ok(self.to_py())
+    }
+
     /// Convert to Python result.
     pub fn to_py(self) -> PySimulationResult {
         let rg = if !self.rg_evolution.is_empty() {
@@ -120,25 +874,105 @@ impl SimulationResult {
         } else {
             0.0
         };
+        let gyration = calculate_gyration_tensor(&self.coordinates, &self.radii);
 
         PySimulationResult {
             fractal_dimension: self.fractal_dimension,
             fractal_dimension_std: self.fractal_dimension_std,
             prefactor: self.prefactor,
+            fit_r_squared: self.fit_r_squared,
+            fit_method: FitMethod::Ols.name().to_string(),
+            evolution_fit: self.evolution_fit,
+            fit_n_points: self.fit_n_points,
+            linear_region_start: self.linear_region.0,
+            linear_region_end: self.linear_region.1,
             radius_of_gyration: rg,
             porosity: self.porosity,
             coordination_mean: self.coordination_mean,
             coordination_std: self.coordination_std,
             execution_time_ms: self.execution_time_ms,
             seed: self.seed,
+            child_seeds: self.child_seeds,
             anisotropy: self.anisotropy,
             asphericity: self.asphericity,
             acylindricity: self.acylindricity,
+            relative_shape_anisotropy: gyration.relative_shape_anisotropy,
+            session: None,
+            warnings: self.warnings,
+            units: None,
+            target_report: self.target_report,
+            brownian_report: self.brownian_report,
+            kinetics: self.kinetics,
+            film: self.film,
+            drift_report: self.drift_report,
+            merge_diagnostics: self.merge_diagnostics,
+            history: None,
+            parameters_data: None,
             coordinates_data: self.coordinates.iter().flat_map(|c| c.iter()).copied().collect(),
             radii_data: self.radii,
             rg_evolution_data: self.rg_evolution,
+            n_evolution_data: self.n_evolution,
             principal_moments_data: self.principal_moments,
             principal_axes_data: self.principal_axes,
+            gyration,
+            ids_data: self.ids,
+            cluster_ids_data: self.cluster_ids,
+            coordination_data: self.coordination,
+            contacts_data: self.contacts,
+            generations_data: self.generations,
+            merge_history_data: self.merge_history,
         }
     }
 }
+
+/// Permutation that puts particles in creation order (ascending persistent ID).
+pub(crate) fn creation_order(ids: &[u32]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..ids.len()).collect();
+    order.sort_unstable_by_key(|&i| ids[i]);
+    order
+}
+
+/// Apply a permutation from [`creation_order`] to one per-particle column.
+pub(crate) fn reorder<T: Copy>(values: &[T], order: &[usize]) -> Vec<T> {
+    order.iter().map(|&i| values[i]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::dla::{run_dla_internal, DlaParams};
+    use crate::simulation::hooks::NoHooks;
+    use crate::simulation::tunable::{run_tunable_internal, TunableParams};
+
+    fn assert_same_fit(result: &PySimulationResult) {
+        let refitted = result.refitted(FitMethod::Ols);
+        assert_eq!(refitted.fractal_dimension, result.fractal_dimension);
+        assert_eq!(refitted.prefactor, result.prefactor);
+        assert_eq!(refitted.fit_r_squared, result.fit_r_squared);
+        assert_eq!(
+            (refitted.linear_region_start, refitted.linear_region_end),
+            (result.linear_region_start, result.linear_region_end)
+        );
+    }
+
+    #[test]
+    fn test_ols_refit_reproduces_engine_fit() {
+        // Raw Rg with particles of radius 2.5, and ln N on ln(Rg/rp)
+        let dla = DlaParams {
+            n_particles: 150,
+            radius_min: 2.5,
+            radius_max: 2.5,
+            ..Default::default()
+        };
+        assert_same_fit(&run_dla_internal(dla, 3, &mut NoHooks).to_py());
+
+        let tunable = TunableParams {
+            n_particles: 150,
+            ..Default::default()
+        };
+        assert_same_fit(&run_tunable_internal(tunable, 3, &mut NoHooks).to_py());
+
+        let coords: Vec<[f64; 3]> = (0..40).map(|i| [3.0 * i as f64, 0.0, 0.0]).collect();
+        assert_same_fit(&SimulationResult::from_structure(coords, vec![1.5; 40]).to_py());
+    }
+}
@@ -12,9 +12,11 @@
 use rand::Rng;
 use rand_distr::{Distribution, Normal, Uniform};
 use pyo3::prelude::*;
+use serde::Serialize;
 
 /// Sintering distribution type for particle contacts.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SinteringDistribution {
     /// Fixed sintering coefficient (same for all contacts)
     Fixed(f64),
@@ -78,6 +80,15 @@ impl SinteringDistribution {
         }
     }
 
+    /// Get the smallest coefficient the distribution can draw (the deepest contact overlap).
+    pub fn minimum(&self) -> f64 {
+        match self {
+            SinteringDistribution::Fixed(v) => *v,
+            SinteringDistribution::Uniform { min, .. } => *min,
+            SinteringDistribution::Normal { .. } => 0.5,
+        }
+    }
+
     /// Check if sintering is enabled (coefficient < 1.0).
     pub fn is_enabled(&self) -> bool {
         self.mean() < 0.999
@@ -88,7 +99,7 @@ impl SinteringDistribution {
 ///
 /// Provides a Python-friendly interface for configuring sintering.
 #[pyclass]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PySinteringParams {
     /// Distribution type: "fixed", "uniform", or "normal"
     #[pyo3(get, set)]
@@ -187,6 +198,80 @@ impl PySinteringParams {
     }
 }
 
+/// Resolve the sintering configuration passed to a `run_*` function.
+///
+/// A `PySinteringParams` object takes precedence; otherwise the loose
+/// `sintering_*` keyword arguments (kept for backwards compatibility) are
+/// used, with `coeff` acting as the mean of the normal distribution.
+/// Unknown distribution types raise `ValueError`; values that get clamped
+/// are reported in `warnings`.
+pub fn resolve_sintering(
+    params: Option<&PySinteringParams>,
+    coeff: f64,
+    distribution_type: &str,
+    min: f64,
+    max: f64,
+    std: f64,
+    warnings: &mut Vec<String>,
+) -> PyResult<SinteringDistribution> {
+    let (kind, coeff, min, max, mean, std) = match params {
+        Some(p) => (
+            p.distribution_type.to_lowercase(),
+            p.coefficient,
+            p.min_coefficient,
+            p.max_coefficient,
+            p.mean_coefficient,
+            p.std_coefficient,
+        ),
+        None => (distribution_type.to_lowercase(), coeff, min, max, coeff, std),
+    };
+
+    let distribution = match kind.as_str() {
+        "uniform" => {
+            check_sintering_range("minimum", min, warnings);
+            check_sintering_range("maximum", max, warnings);
+            if max < min {
+                warnings.push(format!(
+                    "sintering maximum {} is below the minimum {}; using the minimum for both",
+                    max, min
+                ));
+            }
+            SinteringDistribution::uniform(min, max)
+        }
+        "normal" => {
+            check_sintering_range("mean", mean, warnings);
+            if std.abs() > 0.2 {
+                warnings.push(format!(
+                    "sintering standard deviation {} is limited to 0.2",
+                    std
+                ));
+            }
+            SinteringDistribution::normal(mean, std)
+        }
+        "fixed" => {
+            check_sintering_range("coefficient", coeff, warnings);
+            SinteringDistribution::fixed(coeff)
+        }
+        other => {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "sintering_type must be 'fixed', 'uniform' or 'normal', got '{}'",
+                other
+            )));
+        }
+    };
+    Ok(distribution)
+}
+
+/// Record a warning if a sintering value will be clamped to [0.5, 1.0].
+fn check_sintering_range(name: &str, value: f64, warnings: &mut Vec<String>) {
+    if !(0.5..=1.0).contains(&value) {
+        warnings.push(format!(
+            "sintering {} {} is outside [0.5, 1.0] and will be clamped",
+            name, value
+        ));
+    }
+}
+
 /// Calculate sintered contact distance between two particles.
 ///
 /// Returns the distance between centers when particles are in contact,
@@ -271,4 +356,35 @@ mod tests {
         let params = PySinteringParams::fixed(1.0);
         assert!(!params.is_enabled());
     }
+
+    #[test]
+    fn test_resolve_sintering_prefers_params_object() {
+        let mut warnings = Vec::new();
+        let params = PySinteringParams::uniform(0.8, 0.9);
+        let dist =
+            resolve_sintering(Some(&params), 1.0, "fixed", 0.85, 0.95, 0.05, &mut warnings).unwrap();
+        assert!(matches!(dist, SinteringDistribution::Uniform { .. }));
+        assert!((dist.mean() - 0.85).abs() < 1e-10);
+
+        let dist = resolve_sintering(None, 0.9, "Normal", 0.85, 0.95, 0.05, &mut warnings).unwrap();
+        assert!(matches!(dist, SinteringDistribution::Normal { .. }));
+        assert!((dist.mean() - 0.9).abs() < 1e-10);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_sintering_warns_on_silent_changes() {
+        let mut warnings = Vec::new();
+        let dist = resolve_sintering(None, 0.3, "fixed", 0.85, 0.95, 0.05, &mut warnings).unwrap();
+        assert!((dist.mean() - 0.5).abs() < 1e-10);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_resolve_sintering_rejects_unknown_type() {
+        let mut warnings = Vec::new();
+        assert!(resolve_sintering(None, 0.9, "gaussian", 0.85, 0.95, 0.05, &mut warnings).is_err());
+        let params = PySinteringParams::new("gaussian", 0.9, 0.85, 0.95, 0.9, 0.05);
+        assert!(resolve_sintering(Some(&params), 1.0, "fixed", 0.85, 0.95, 0.05, &mut warnings).is_err());
+    }
 }
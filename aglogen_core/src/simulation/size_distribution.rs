@@ -0,0 +1,509 @@
+//! Primary-particle size distributions.
+//!
+//! Primary particles are described by their diameter, as in the literature
+//! and in TEM measurements: soot primaries are typically lognormal with a
+//! count median diameter (CMD) and a geometric standard deviation (GSD).
+//! The engines draw their radii through the [`SizeSampler`] of a
+//! [`SizeDistribution`], built once per run, and the same sampler is exposed to Python so studies can generate consistent inputs
+//! from a seed. The unbounded distributions (lognormal, normal) are
+//! truncated at [`TAIL_SIGMAS`] standard deviations so the engines can size
+//! their spatial hashes and launch spheres from the largest possible particle.
+
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::Rng;
+use rand_distr::{Distribution, LogNormal, Normal, WeightedIndex};
+use serde::Serialize;
+
+use crate::common::rng::create_rng;
+use crate::common::validation::{check_count, check_positive};
+
+/// Standard deviations (geometric ones for the lognormal) kept on each side
+/// of the unbounded distributions; samples beyond are redrawn (0.3% of them).
+pub const TAIL_SIGMAS: f64 = 3.0;
+
+/// Smallest diameter of a truncated normal distribution, as a fraction of its mean.
+const NORMAL_MIN_FRACTION: f64 = 1e-3;
+
+/// Distribution of primary-particle diameters.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeDistribution {
+    /// Every particle has the same diameter
+    Fixed(f64),
+    /// Diameters uniform in [min, max]
+    Uniform { min: f64, max: f64 },
+    /// ln(d) ~ N(ln(cmd), ln(gsd))
+    Lognormal { cmd: f64, gsd: f64 },
+    /// d ~ N(mean, std), truncated to positive diameters
+    Normal { mean: f64, std: f64 },
+    /// Diameters drawn from a table of values with relative weights
+    Tabulated { diameters: Vec<f64>, weights: Vec<f64> },
+    /// Measured histogram: a bin is drawn by its count, then a diameter
+    /// uniformly inside it (`edges` has one more entry than `counts`)
+    Histogram { edges: Vec<f64>, counts: Vec<f64> },
+}
+
+impl SizeDistribution {
+    /// Distribution of the engines' `radius_min`/`radius_max` parameters.
+    pub fn from_radius_range(radius_min: f64, radius_max: f64) -> Self {
+        if (radius_max - radius_min).abs() > 1e-10 {
+            SizeDistribution::Uniform {
+                min: 2.0 * radius_min,
+                max: 2.0 * radius_max,
+            }
+        } else {
+            SizeDistribution::Fixed(2.0 * radius_min)
+        }
+    }
+
+    /// Whether sizes vary between particles.
+    pub fn is_polydisperse(&self) -> bool {
+        !matches!(self, SizeDistribution::Fixed(_))
+    }
+
+    /// Raise `ValueError` unless the table weights or histogram counts are
+    /// non-negative with a positive sum, which the Python constructors ensure.
+    pub fn check(&self) -> PyResult<()> {
+        let (name, weights) = match self {
+            SizeDistribution::Tabulated { weights, .. } => ("weights", weights),
+            SizeDistribution::Histogram { counts, .. } => ("counts", counts),
+            _ => return Ok(()),
+        };
+        if weights.iter().any(|w| !(w.is_finite() && *w >= 0.0)) || weights.iter().sum::<f64>() <= 0.0 {
+            return Err(PyValueError::new_err(format!(
+                "{} must be non-negative with a positive sum",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Smallest and largest diameter a sample can take (see `check`).
+    pub fn diameter_range(&self) -> (f64, f64) {
+        match self {
+            SizeDistribution::Fixed(d) => (*d, *d),
+            SizeDistribution::Uniform { min, max } => (*min, *max),
+            SizeDistribution::Lognormal { cmd, gsd } => {
+                let spread = gsd.powf(TAIL_SIGMAS);
+                (cmd / spread, cmd * spread)
+            }
+            SizeDistribution::Normal { mean, std } => (
+                (mean - TAIL_SIGMAS * std).max(NORMAL_MIN_FRACTION * mean),
+                mean + TAIL_SIGMAS * std,
+            ),
+            SizeDistribution::Tabulated { diameters, weights } => diameters
+                .iter()
+                .zip(weights)
+                .filter(|(_, &w)| w > 0.0)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (&d, _)| (lo.min(d), hi.max(d))),
+            SizeDistribution::Histogram { edges, counts } => {
+                let first = counts.iter().position(|&c| c > 0.0).unwrap_or(0);
+                let last = counts.iter().rposition(|&c| c > 0.0).unwrap_or(counts.len() - 1);
+                (edges[first], edges[last + 1])
+            }
+        }
+    }
+
+    /// Sampler drawing from the distribution, to build once and draw many sizes from.
+    pub fn sampler(&self) -> SizeSampler {
+        let draw = match self {
+            SizeDistribution::Fixed(d) => Draw::Fixed(*d),
+            SizeDistribution::Uniform { min, max } => Draw::Uniform(*min, *max),
+            SizeDistribution::Lognormal { cmd, gsd } => match LogNormal::new(cmd.ln(), gsd.ln()) {
+                Ok(dist) => Draw::Lognormal(dist),
+                Err(_) => Draw::Fixed(*cmd),
+            },
+            SizeDistribution::Normal { mean, std } => match Normal::new(*mean, *std) {
+                Ok(dist) => Draw::Normal(dist),
+                Err(_) => Draw::Fixed(*mean),
+            },
+            SizeDistribution::Tabulated { diameters, weights } => match WeightedIndex::new(weights) {
+                Ok(index) => Draw::Table(index, diameters.clone()),
+                Err(_) => Draw::Fixed(diameters[0]),
+            },
+            SizeDistribution::Histogram { edges, counts } => match WeightedIndex::new(counts) {
+                Ok(index) => Draw::Bins(index, edges.clone()),
+                Err(_) => Draw::Fixed(edges[0]),
+            },
+        };
+        SizeSampler {
+            draw,
+            range: self.diameter_range(),
+        }
+    }
+
+    /// Mean diameter of the distribution (before truncation of the tails).
+    pub fn mean_diameter(&self) -> f64 {
+        match self {
+            SizeDistribution::Fixed(d) => *d,
+            SizeDistribution::Uniform { min, max } => (min + max) / 2.0,
+            // Mean of a lognormal: exp(mu + sigma^2 / 2)
+            SizeDistribution::Lognormal { cmd, gsd } => cmd * (gsd.ln().powi(2) / 2.0).exp(),
+            SizeDistribution::Normal { mean, .. } => *mean,
+            SizeDistribution::Tabulated { diameters, weights } => {
+                let total: f64 = weights.iter().sum();
+                diameters.iter().zip(weights.iter()).map(|(d, w)| d * w).sum::<f64>() / total
+            }
+            SizeDistribution::Histogram { edges, counts } => {
+                let total: f64 = counts.iter().sum();
+                counts
+                    .iter()
+                    .enumerate()
+                    .map(|(k, c)| c * (edges[k] + edges[k + 1]) / 2.0)
+                    .sum::<f64>()
+                    / total
+            }
+        }
+    }
+
+    /// Sample `n` diameters from a fresh RNG seeded with `seed`.
+    pub fn sample_diameters(&self, n: usize, seed: u64) -> Vec<f64> {
+        let mut rng = create_rng(seed);
+        let sampler = self.sampler();
+        (0..n).map(|_| sampler.sample_diameter(&mut rng)).collect()
+    }
+}
+
+/// How a `SizeSampler` draws a diameter.
+#[derive(Debug, Clone)]
+enum Draw {
+    Fixed(f64),
+    Uniform(f64, f64),
+    Lognormal(LogNormal<f64>),
+    Normal(Normal<f64>),
+    /// Table entry drawn by weight
+    Table(WeightedIndex<f64>, Vec<f64>),
+    /// Histogram bin drawn by count, given its edges
+    Bins(WeightedIndex<f64>, Vec<f64>),
+}
+
+/// Diameters drawn from a `SizeDistribution`, with its weighted index and
+/// truncation range computed once.
+#[derive(Debug, Clone)]
+pub struct SizeSampler {
+    draw: Draw,
+    /// Samples of the unbounded distributions outside it are redrawn
+    range: (f64, f64),
+}
+
+impl SizeSampler {
+    /// Sample a diameter.
+    pub fn sample_diameter<R: Rng>(&self, rng: &mut R) -> f64 {
+        match &self.draw {
+            Draw::Fixed(d) => *d,
+            Draw::Uniform(min, max) => rng.gen_range(*min..=*max),
+            Draw::Lognormal(dist) => self.sample_truncated(dist, rng),
+            Draw::Normal(dist) => self.sample_truncated(dist, rng),
+            Draw::Table(index, diameters) => diameters[index.sample(rng)],
+            Draw::Bins(index, edges) => {
+                let k = index.sample(rng);
+                rng.gen_range(edges[k]..=edges[k + 1])
+            }
+        }
+    }
+
+    /// Redraw from `dist` until the sample falls inside the diameter range.
+    fn sample_truncated<D: Distribution<f64>, R: Rng>(&self, dist: &D, rng: &mut R) -> f64 {
+        let (lo, hi) = self.range;
+        loop {
+            let d = dist.sample(rng);
+            if (lo..=hi).contains(&d) {
+                return d;
+            }
+        }
+    }
+
+    /// Sample a radius (half a sampled diameter).
+    pub fn sample_radius<R: Rng>(&self, rng: &mut R) -> f64 {
+        self.sample_diameter(rng) / 2.0
+    }
+}
+
+/// Particle sizes of an engine's parameters: an explicit `SizeDistribution`,
+/// or radii uniform in `radius_min..=radius_max` (fixed when they are equal).
+pub trait ParticleSizes {
+    /// The explicit distribution, if any, and the radius range.
+    fn size_parameters(&self) -> (Option<&SizeDistribution>, f64, f64);
+
+    /// Distribution the particle sizes are drawn from.
+    fn size_distribution(&self) -> SizeDistribution {
+        match self.size_parameters() {
+            (Some(sizes), _, _) => sizes.clone(),
+            (None, radius_min, radius_max) => SizeDistribution::from_radius_range(radius_min, radius_max),
+        }
+    }
+
+    /// Sampler of the particle radii, to build once per run.
+    fn radius_sampler(&self) -> SizeSampler {
+        self.size_distribution().sampler()
+    }
+
+    /// Mean radius, for the step and box sizes.
+    fn mean_radius(&self) -> f64 {
+        self.size_distribution().mean_diameter() / 2.0
+    }
+}
+
+/// Resolve the particle sizes passed to a `run_*` function.
+///
+/// Returns the radius range and the distribution the engine samples from.
+/// A `SizeDistribution` object takes precedence over `radius_min`/`radius_max`,
+/// which then become the bounds of its samples; giving both is reported in
+/// `warnings`.
+pub fn resolve_size_distribution(
+    distribution: Option<&PySizeDistribution>,
+    radius_min: f64,
+    radius_max: Option<f64>,
+    warnings: &mut Vec<String>,
+) -> PyResult<(f64, f64, Option<SizeDistribution>)> {
+    match distribution {
+        Some(d) => {
+            d.inner.check()?;
+            if radius_max.is_some() {
+                warnings.push("radius_min and radius_max are ignored when size_distribution is given".to_string());
+            }
+            let (lo, hi) = d.inner.diameter_range();
+            Ok((lo / 2.0, hi / 2.0, Some(d.inner.clone())))
+        }
+        None => Ok((radius_min, radius_max.unwrap_or(radius_min), None)),
+    }
+}
+
+/// Primary-particle size distribution for Python.
+///
+/// Build one with the static constructors (`fixed`, `uniform`, `lognormal`,
+/// `normal`, `tabulated`, `histogram`) and call `sample(n, seed)` to draw
+/// diameters, or pass it as `size_distribution` to any `run_*` function.
+#[pyclass(name = "SizeDistribution")]
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
+pub struct PySizeDistribution {
+    pub(crate) inner: SizeDistribution,
+}
+
+#[pymethods]
+impl PySizeDistribution {
+    /// Every particle has diameter `diameter`.
+    #[staticmethod]
+    pub fn fixed(diameter: f64) -> PyResult<Self> {
+        check_positive("diameter", diameter)?;
+        Ok(Self {
+            inner: SizeDistribution::Fixed(diameter),
+        })
+    }
+
+    /// Diameters uniform in [min_diameter, max_diameter].
+    #[staticmethod]
+    pub fn uniform(min_diameter: f64, max_diameter: f64) -> PyResult<Self> {
+        check_positive("min_diameter", min_diameter)?;
+        check_positive("max_diameter", max_diameter)?;
+        if max_diameter < min_diameter {
+            return Err(PyValueError::new_err(format!(
+                "max_diameter ({}) must not be smaller than min_diameter ({})",
+                max_diameter, min_diameter
+            )));
+        }
+        Ok(Self {
+            inner: SizeDistribution::Uniform {
+                min: min_diameter,
+                max: max_diameter,
+            },
+        })
+    }
+
+    /// Lognormal diameters with count median diameter `cmd` and geometric
+    /// standard deviation `gsd` (>= 1; 1 gives a fixed diameter).
+    #[staticmethod]
+    pub fn lognormal(cmd: f64, gsd: f64) -> PyResult<Self> {
+        check_positive("cmd", cmd)?;
+        if !(gsd.is_finite() && gsd >= 1.0) {
+            return Err(PyValueError::new_err(format!("gsd must be at least 1, got {}", gsd)));
+        }
+        Ok(Self {
+            inner: SizeDistribution::Lognormal { cmd, gsd },
+        })
+    }
+
+    /// Normal diameters with mean `mean` and standard deviation `std`,
+    /// truncated at 3 standard deviations and to positive values.
+    #[staticmethod]
+    pub fn normal(mean: f64, std: f64) -> PyResult<Self> {
+        check_positive("mean", mean)?;
+        if !(std.is_finite() && std >= 0.0) {
+            return Err(PyValueError::new_err(format!("std must be a non-negative number, got {}", std)));
+        }
+        Ok(Self {
+            inner: SizeDistribution::Normal { mean, std },
+        })
+    }
+
+    /// Diameters drawn from `diameters` with relative `weights` (equal weights by default),
+    /// e.g. a size table measured on TEM images.
+    #[staticmethod]
+    #[pyo3(signature = (diameters, weights=None))]
+    pub fn tabulated(diameters: Vec<f64>, weights: Option<Vec<f64>>) -> PyResult<Self> {
+        check_count("diameters", diameters.len(), 1)?;
+        for &d in &diameters {
+            check_positive("diameters", d)?;
+        }
+        let weights = weights.unwrap_or_else(|| vec![1.0; diameters.len()]);
+        if weights.len() != diameters.len() {
+            return Err(PyValueError::new_err(format!(
+                "weights length ({}) must match diameters length ({})",
+                weights.len(),
+                diameters.len()
+            )));
+        }
+        let inner = SizeDistribution::Tabulated { diameters, weights };
+        inner.check()?;
+        Ok(Self { inner })
+    }
+
+    /// Diameters from a measured histogram: `counts[k]` particles between
+    /// `bin_edges[k]` and `bin_edges[k + 1]`, uniformly spread inside each bin.
+    #[staticmethod]
+    pub fn histogram(bin_edges: Vec<f64>, counts: Vec<f64>) -> PyResult<Self> {
+        check_count("counts", counts.len(), 1)?;
+        if bin_edges.len() != counts.len() + 1 {
+            return Err(PyValueError::new_err(format!(
+                "bin_edges length ({}) must be counts length ({}) plus one",
+                bin_edges.len(),
+                counts.len()
+            )));
+        }
+        for &e in &bin_edges {
+            check_positive("bin_edges", e)?;
+        }
+        if bin_edges.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(PyValueError::new_err("bin_edges must be strictly increasing"));
+        }
+        let inner = SizeDistribution::Histogram {
+            edges: bin_edges,
+            counts,
+        };
+        inner.check()?;
+        Ok(Self { inner })
+    }
+
+    /// Draw `n` diameters as numpy array (n,); the same seed gives the same diameters.
+    #[pyo3(signature = (n, seed=None))]
+    fn sample<'py>(&self, py: Python<'py>, n: usize, seed: Option<u64>) -> Bound<'py, PyArray1<f64>> {
+        let seed = seed.unwrap_or_else(rand::random);
+        PyArray1::from_vec(py, self.inner.sample_diameters(n, seed))
+    }
+
+    /// Mean diameter of the distribution.
+    #[getter]
+    fn mean_diameter(&self) -> f64 {
+        self.inner.mean_diameter()
+    }
+
+    /// Whether sizes vary between particles.
+    #[getter]
+    fn is_polydisperse(&self) -> bool {
+        self.inner.is_polydisperse()
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.inner {
+            SizeDistribution::Fixed(d) => format!("SizeDistribution.fixed({})", d),
+            SizeDistribution::Uniform { min, max } => format!("SizeDistribution.uniform({}, {})", min, max),
+            SizeDistribution::Lognormal { cmd, gsd } => format!("SizeDistribution.lognormal({}, {})", cmd, gsd),
+            SizeDistribution::Normal { mean, std } => format!("SizeDistribution.normal({}, {})", mean, std),
+            SizeDistribution::Tabulated { diameters, .. } => {
+                format!("SizeDistribution.tabulated(<{} diameters>)", diameters.len())
+            }
+            SizeDistribution::Histogram { counts, .. } => {
+                format!("SizeDistribution.histogram(<{} bins>)", counts.len())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lognormal_matches_cmd_and_gsd() {
+        let dist = SizeDistribution::Lognormal { cmd: 20.0, gsd: 1.5 };
+        let diameters = dist.sample_diameters(20_000, 42);
+        assert_eq!(diameters, dist.sample_diameters(20_000, 42));
+
+        let logs: Vec<f64> = diameters.iter().map(|d| d.ln()).collect();
+        let mean = logs.iter().sum::<f64>() / logs.len() as f64;
+        let std = (logs.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / logs.len() as f64).sqrt();
+        assert!((mean.exp() - 20.0).abs() < 0.3, "CMD = {}", mean.exp());
+        assert!((std.exp() - 1.5).abs() < 0.02, "GSD = {}", std.exp());
+    }
+
+    #[test]
+    fn test_radius_range_matches_engine_sampling() {
+        // Engines used to draw radii with gen_range(radius_min..=radius_max)
+        let dist = SizeDistribution::from_radius_range(1.0, 1.7).sampler();
+        let mut a = create_rng(5);
+        let mut b = create_rng(5);
+        for _ in 0..100 {
+            assert_eq!(dist.sample_radius(&mut a), b.gen_range(1.0..=1.7));
+        }
+        assert_eq!(SizeDistribution::from_radius_range(1.5, 1.5).sampler().sample_radius(&mut a), 1.5);
+    }
+
+    #[test]
+    fn test_tabulated_respects_weights() {
+        let dist = SizeDistribution::Tabulated {
+            diameters: vec![10.0, 30.0],
+            weights: vec![3.0, 1.0],
+        };
+        assert!((dist.mean_diameter() - 15.0).abs() < 1e-12);
+        let diameters = dist.sample_diameters(4000, 1);
+        let small = diameters.iter().filter(|&&d| d == 10.0).count();
+        assert!((2800..3200).contains(&small));
+    }
+
+    #[test]
+    fn test_truncated_and_histogram_samples_stay_in_range() {
+        let dists = [
+            SizeDistribution::Lognormal { cmd: 20.0, gsd: 1.6 },
+            SizeDistribution::Normal { mean: 20.0, std: 8.0 },
+            SizeDistribution::Histogram {
+                edges: vec![10.0, 20.0, 30.0, 40.0],
+                counts: vec![1.0, 2.0, 0.0],
+            },
+        ];
+        for dist in &dists {
+            let (lo, hi) = dist.diameter_range();
+            assert!(lo > 0.0 && hi > lo);
+            let diameters = dist.sample_diameters(5000, 3);
+            assert!(diameters.iter().all(|d| (lo..=hi).contains(d)), "{:?}", dist);
+            let mean = diameters.iter().sum::<f64>() / diameters.len() as f64;
+            assert!((mean / dist.mean_diameter() - 1.0).abs() < 0.03, "{:?}: mean {}", dist, mean);
+        }
+        // The empty last bin does not widen the range
+        assert_eq!(dists[2].diameter_range(), (10.0, 30.0));
+    }
+
+    #[test]
+    fn test_sampler_draws_like_the_distribution() {
+        let dist = SizeDistribution::Histogram {
+            edges: vec![10.0, 20.0, 30.0],
+            counts: vec![1.0, 3.0],
+        };
+        let sampler = dist.sampler();
+        let mut rng = create_rng(9);
+        let drawn: Vec<f64> = (0..100).map(|_| sampler.sample_diameter(&mut rng)).collect();
+        assert_eq!(drawn, dist.sample_diameters(100, 9));
+        assert!(drawn.iter().all(|d| (10.0..=30.0).contains(d)));
+
+        // Empty histograms are rejected instead of panicking
+        let empty = SizeDistribution::Histogram {
+            edges: vec![10.0, 20.0],
+            counts: vec![0.0],
+        };
+        assert!(empty.check().is_err());
+        assert_eq!(empty.diameter_range(), (10.0, 20.0));
+        assert!(dist.check().is_ok());
+    }
+}
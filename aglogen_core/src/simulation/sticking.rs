@@ -0,0 +1,236 @@
+//! Sticking kinetics of cluster-cluster aggregation.
+//!
+//! With every collision sticking, CCA is diffusion limited (DLCA, Df ~ 1.8).
+//! When only a small share of the collisions sticks, clusters explore many
+//! contacts before bonding and aggregation becomes reaction limited (RLCA,
+//! Df ~ 2.1). The sticking probability of a collision can be
+//!
+//! * "constant" - the run's `sticking_probability` p0
+//! * "size" - p0 (n_i n_j)^(-exponent), falling with the cluster sizes
+//! * "arrhenius" - an activation energy barrier crossed at every particle
+//!   pair the clusters touch at, p0 exp(-Ea / RT) each, so clusters meeting
+//!   at k points stick with 1 - (1 - p0 exp(-Ea / RT))^k
+//!
+//! The kinetics are summarized by the growth of the mean cluster size,
+//! <n> ~ t^z, and the homogeneity of the aggregation kernel it implies,
+//! K(a n, a m) = a^lambda K(n, m) with z = 1 / (1 - lambda): lambda ~ 0 for
+//! DLCA, approaching 1 for RLCA.
+
+use std::collections::HashSet;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::fitting::linear_regression;
+
+/// Molar gas constant in kJ/(mol K).
+const GAS_CONSTANT_KJ: f64 = 8.314462618e-3;
+
+/// How the sticking probability of a collision is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StickingModel {
+    #[default]
+    Constant,
+    /// p0 (n_i n_j)^(-exponent)
+    SizeDependent { exponent: f64 },
+    /// 1 - (1 - p0 exp(-Ea / RT))^k for k touching particle pairs, Ea in kJ/mol and T in K
+    Arrhenius { activation_energy: f64, temperature: f64 },
+}
+
+impl StickingModel {
+    /// Sticking probability of a collision of clusters of `sizes` particles
+    /// touching at `contacts` particle pairs, for a base probability `p0`.
+    pub fn probability(&self, p0: f64, sizes: (usize, usize), contacts: usize) -> f64 {
+        match *self {
+            Self::Constant => p0,
+            Self::SizeDependent { exponent } => p0 * ((sizes.0 * sizes.1) as f64).powf(-exponent),
+            Self::Arrhenius {
+                activation_energy,
+                temperature,
+            } => {
+                let bond = (p0 * (-activation_energy / (GAS_CONSTANT_KJ * temperature)).exp()).min(1.0);
+                1.0 - (1.0 - bond).powi(contacts.max(1) as i32)
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Constant => "constant",
+            Self::SizeDependent { .. } => "size",
+            Self::Arrhenius { .. } => "arrhenius",
+        }
+    }
+}
+
+/// Resolve the sticking model passed to a CCA `run_*` function.
+///
+/// `temperature` (K) only matters for "arrhenius".
+pub fn resolve_sticking(
+    name: &str,
+    exponent: f64,
+    activation_energy: f64,
+    temperature: f64,
+) -> PyResult<StickingModel> {
+    let non_negative = |label: &str, value: f64| {
+        if value.is_finite() && value >= 0.0 {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!(
+                "{} must be a non-negative number, got {}",
+                label, value
+            )))
+        }
+    };
+    match name.to_lowercase().as_str() {
+        "constant" => Ok(StickingModel::Constant),
+        "size" => {
+            non_negative("sticking_exponent", exponent)?;
+            Ok(StickingModel::SizeDependent { exponent })
+        }
+        "arrhenius" => {
+            non_negative("activation_energy", activation_energy)?;
+            Ok(StickingModel::Arrhenius {
+                activation_energy,
+                temperature,
+            })
+        }
+        _ => Err(PyValueError::new_err(format!(
+            "unknown sticking_model '{}': use 'constant', 'size' or 'arrhenius'",
+            name
+        ))),
+    }
+}
+
+/// Collects the collisions and cluster counts of a CCA run.
+///
+/// Clusters that bounce off each other usually stay in touch and draw again
+/// at the next steps; those draws belong to the same collision, which is
+/// counted once, at its first step, and rejected only if none of them stuck.
+#[derive(Debug, Clone)]
+pub struct KineticsRecorder {
+    model: StickingModel,
+    n_particles: usize,
+    collisions: usize,
+    rejected: usize,
+    probability_sum: f64,
+    /// (iteration, clusters left) after every iteration with merges
+    counts: Vec<(usize, usize)>,
+    /// Labels of the cluster pairs in touch during the current and the previous iteration
+    touching: HashSet<(u32, u32)>,
+    touched: HashSet<(u32, u32)>,
+}
+
+impl KineticsRecorder {
+    pub fn new(model: StickingModel, n_particles: usize) -> Self {
+        Self {
+            model,
+            n_particles,
+            collisions: 0,
+            rejected: 0,
+            probability_sum: 0.0,
+            counts: Vec::new(),
+            touching: HashSet::new(),
+            touched: HashSet::new(),
+        }
+    }
+
+    /// Record a draw, with probability `probability`, of the clusters labelled
+    /// `labels` sticking; a pair already in touch at the previous iteration
+    /// is retrying the same collision.
+    pub fn collision(&mut self, labels: (u32, u32), probability: f64, stuck: bool) {
+        let pair = (labels.0.min(labels.1), labels.0.max(labels.1));
+        self.touching.insert(pair);
+        if !self.touched.contains(&pair) {
+            self.collisions += 1;
+            self.probability_sum += probability.min(1.0);
+            self.rejected += 1;
+        }
+        if stuck {
+            self.rejected -= 1;
+        }
+    }
+
+    /// Record the clusters left after `iteration`, if merges changed their
+    /// number, and close the iteration's collisions.
+    pub fn clusters(&mut self, iteration: usize, n_clusters: usize) {
+        if self.counts.last().map(|&(_, n)| n) != Some(n_clusters) {
+            self.counts.push((iteration, n_clusters));
+        }
+        self.touched = std::mem::take(&mut self.touching);
+    }
+
+    /// Growth exponent z of the mean cluster size <n> = N / n_clusters.
+    ///
+    /// Fitted in the scaling regime, mean sizes from 2 up to a tenth of the
+    /// particles, before the finite box takes over. NaN with fewer than
+    /// three samples there.
+    fn growth_exponent(&self) -> f64 {
+        let n = self.n_particles as f64;
+        let (xs, ys): (Vec<f64>, Vec<f64>) = self
+            .counts
+            .iter()
+            .map(|&(iteration, clusters)| (iteration as f64, n / clusters as f64))
+            .filter(|&(_, size)| (2.0..=n / 10.0).contains(&size))
+            .map(|(t, size)| (t.ln(), size.ln()))
+            .unzip();
+        if xs.len() < 3 {
+            return f64::NAN;
+        }
+        linear_regression(&xs, &ys).slope
+    }
+
+    pub fn finish(self) -> PyAggregationKinetics {
+        let z = self.growth_exponent();
+        PyAggregationKinetics {
+            sticking_model: self.model.name().to_string(),
+            collisions: self.collisions,
+            rejected_collisions: self.rejected,
+            mean_sticking_probability: if self.collisions > 0 {
+                self.probability_sum / self.collisions as f64
+            } else {
+                f64::NAN
+            },
+            growth_exponent: z,
+            kernel_homogeneity: 1.0 - 1.0 / z,
+        }
+    }
+}
+
+/// Collision statistics and kernel homogeneity of a CCA run.
+#[pyclass(name = "AggregationKinetics")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PyAggregationKinetics {
+    #[pyo3(get)]
+    pub sticking_model: String,
+    /// Contacts between clusters that could have merged, each counted once
+    /// however many steps the clusters stay in touch
+    #[pyo3(get)]
+    pub collisions: usize,
+    /// Contacts that never stuck
+    #[pyo3(get)]
+    pub rejected_collisions: usize,
+    /// Mean sticking probability at the first step of each collision (NaN without any)
+    #[pyo3(get)]
+    pub mean_sticking_probability: f64,
+    /// Exponent z of the mean cluster size against iterations, <n> ~ t^z
+    /// (NaN when the run is too short to fit)
+    #[pyo3(get)]
+    pub growth_exponent: f64,
+    /// Homogeneity lambda of the aggregation kernel, 1 - 1/z: about 0 for
+    /// DLCA, approaching 1 for RLCA
+    #[pyo3(get)]
+    pub kernel_homogeneity: f64,
+}
+
+#[pymethods]
+impl PyAggregationKinetics {
+    fn __repr__(&self) -> String {
+        format!(
+            "AggregationKinetics(sticking_model='{}', collisions={}, rejected={}, kernel_homogeneity={:.3})",
+            self.sticking_model, self.collisions, self.rejected_collisions, self.kernel_homogeneity
+        )
+    }
+}
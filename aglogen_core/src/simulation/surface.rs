@@ -0,0 +1,159 @@
+//! Solvent-accessible surface area of agglomerates.
+//!
+//! Condensation and adsorption happen where a molecule of finite size can
+//! reach the particles. The accessible surface is traced by the center of a
+//! probe sphere rolled over the agglomerate (Shrake-Rupley): every particle
+//! radius is inflated by the probe radius, each inflated sphere is sampled
+//! with a Fibonacci lattice of points, and points buried in a neighbouring
+//! inflated sphere are discarded.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::common::geometry::{Sphere, Vector3};
+use crate::common::spatial::SpatialHash;
+use crate::common::validation::check_count;
+use crate::fractal::box_counting_3d::generate_sphere_points;
+use crate::projection::extract_structure;
+
+/// Accessible surface area of each particle (Shrake-Rupley).
+///
+/// Each particle's sphere of radius r + `probe_radius` is sampled with
+/// `samples` points; its area is 4 pi (r + probe)² times the share of points
+/// outside every other inflated sphere.
+pub fn accessible_surface_area_internal(
+    coordinates: &[[f64; 3]],
+    radii: &[f64],
+    probe_radius: f64,
+    samples: usize,
+) -> Vec<f64> {
+    let spheres: Vec<Sphere> = coordinates
+        .iter()
+        .zip(radii)
+        .map(|(c, &r)| Sphere::new(Vector3::new(c[0], c[1], c[2]), r + probe_radius))
+        .collect();
+    let max_radius = spheres.iter().map(|s| s.radius).fold(0.0, f64::max);
+    let mut hash = SpatialHash::new(2.0 * max_radius);
+    for (i, sphere) in spheres.iter().enumerate() {
+        hash.insert(i, sphere);
+    }
+
+    spheres
+        .par_iter()
+        .enumerate()
+        .map(|(i, sphere)| {
+            let neighbours: Vec<&Sphere> = hash
+                .query_potential_collisions(sphere)
+                .into_iter()
+                .filter(|&j| j != i)
+                .map(|j| &spheres[j])
+                .filter(|other| sphere.center.distance_to(&other.center) < sphere.radius + other.radius)
+                .collect();
+            let c = sphere.center;
+            let exposed = generate_sphere_points(c.x, c.y, c.z, sphere.radius, samples)
+                .into_iter()
+                .filter(|p| {
+                    let point = Vector3::new(p[0], p[1], p[2]);
+                    neighbours.iter().all(|other| point.distance_to(&other.center) >= other.radius)
+                })
+                .count();
+            4.0 * std::f64::consts::PI * sphere.radius * sphere.radius * exposed as f64 / samples as f64
+        })
+        .collect()
+}
+
+/// Accessible surface area of an agglomerate.
+#[pyclass(name = "AccessibleSurfaceArea")]
+#[derive(Debug, Clone)]
+pub struct PyAccessibleSurfaceArea {
+    /// Total accessible surface area
+    #[pyo3(get)]
+    pub total: f64,
+    /// Probe radius the surface was traced with
+    #[pyo3(get)]
+    pub probe_radius: f64,
+    /// Sample points per particle
+    #[pyo3(get)]
+    pub samples: usize,
+    /// Accessible surface area of each particle
+    #[pyo3(get)]
+    pub particle_areas: Vec<f64>,
+}
+
+#[pymethods]
+impl PyAccessibleSurfaceArea {
+    fn __repr__(&self) -> String {
+        format!(
+            "AccessibleSurfaceArea(total={:.4}, probe_radius={}, samples={})",
+            self.total, self.probe_radius, self.samples
+        )
+    }
+}
+
+/// Solvent-accessible surface area of an agglomerate (Shrake-Rupley).
+///
+/// The surface traced by the center of a probe sphere of `probe_radius`
+/// rolled over the particles, estimated by sampling `samples` points on
+/// every inflated particle sphere. With `probe_radius=0` it is the exposed
+/// surface of the spheres, the sampled counterpart of
+/// `SimulationResult.surface_area` that also handles overlapping caps. The
+/// relative error of each particle area is about 1/`samples`.
+///
+/// # Arguments
+/// * `coordinates` - 3D particle coordinates (N x 3 array, float32 or float64, any layout)
+/// * `radii` - Particle radii (N array, float32 or float64)
+/// * `probe_radius` - Radius of the probe molecule, in the units of the radii (default: 0)
+/// * `samples` - Sample points per particle (default: 500)
+///
+/// # Returns
+/// * `AccessibleSurfaceArea` with the total and the per-particle areas
+#[pyfunction]
+#[pyo3(signature = (coordinates, radii, probe_radius=0.0, samples=500))]
+pub fn accessible_surface_area(
+    py: Python<'_>,
+    coordinates: &Bound<'_, PyAny>,
+    radii: &Bound<'_, PyAny>,
+    probe_radius: f64,
+    samples: usize,
+) -> PyResult<PyAccessibleSurfaceArea> {
+    let (coords, radii) = extract_structure(coordinates, radii)?;
+    if !(probe_radius.is_finite() && probe_radius >= 0.0) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "probe_radius must be a non-negative number, got {}",
+            probe_radius
+        )));
+    }
+    check_count("samples", samples, 1)?;
+
+    let points: Vec<[f64; 3]> = coords.rows().into_iter().map(|r| [r[0], r[1], r[2]]).collect();
+    let particle_areas =
+        py.allow_threads(|| accessible_surface_area_internal(&points, &radii, probe_radius, samples));
+
+    Ok(PyAccessibleSurfaceArea {
+        total: particle_areas.iter().sum(),
+        probe_radius,
+        samples,
+        particle_areas,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn test_accessible_surface_area() {
+        // Unit spheres one radius apart each lose a cap of height 1/2: 3 pi left
+        let coords = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [10.0, 0.0, 0.0]];
+        let areas = accessible_surface_area_internal(&coords, &[1.0, 1.0, 1.0], 0.0, 4000);
+        assert!((areas[0] / (3.0 * PI) - 1.0).abs() < 0.01, "area = {}", areas[0]);
+        assert!((areas[2] - 4.0 * PI).abs() < 1e-12);
+
+        // The probe inflates isolated spheres and buries the gap between close ones
+        let areas = accessible_surface_area_internal(&coords, &[1.0, 1.0, 1.0], 0.5, 4000);
+        assert!((areas[2] - 9.0 * PI).abs() < 1e-12);
+        assert!(areas[0] < 9.0 * PI * 0.8);
+    }
+}
@@ -11,27 +11,51 @@ use std::time::Instant;
 
 use pyo3::prelude::*;
 use rand::Rng;
+use serde::Serialize;
+use serde_json::json;
 
+use crate::common::fitting::{fit_linear_region, FitMethod, LinearRegionParams};
 use crate::common::geometry::{Sphere, Vector3};
+use crate::common::particles::ParticleStore;
 use crate::common::rng::{create_rng, random_point_on_sphere};
+use crate::common::units::PyUnits;
+use crate::common::validation::{
+    check_count, check_fractal_dimension, check_positive, check_radius_range,
+};
+use crate::session::allow_threads;
 
+use super::history::{PyHistoryParams, Snapshot};
+use super::hooks::{EventHooks, Flow, ProgressEvent, PyCallbacks, StickEvent};
 use super::metrics::{
-    calculate_coordination, calculate_inertia_tensor, calculate_porosity,
-    calculate_radius_of_gyration,
+    calculate_contacts, calculate_inertia_tensor, calculate_porosity,
+    calculate_radius_of_gyration, clamp_fitted_parameters, fit_region_indices,
+    insufficient_fit_warning, coordination_from_contacts, EvolutionFit, FractalFit,
 };
+use super::provenance::run_parameters;
+use super::restart::{centered_store, check_existing, extract_existing};
 use super::result::{PySimulationResult, SimulationResult};
-use super::sintering::{sintered_contact_distance, SinteringDistribution};
+use super::sintering::{
+    resolve_sintering, sintered_contact_distance, PySinteringParams, SinteringDistribution,
+};
+use super::size_distribution::{resolve_size_distribution, ParticleSizes, PySizeDistribution, SizeDistribution};
 
 /// Tunable PC simulation parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TunableParams {
     pub n_particles: usize,
     pub target_df: f64,
     pub target_kf: f64,
     pub radius_min: f64,
     pub radius_max: f64,
+    /// Distribution the radii are drawn from instead of the uniform
+    /// `radius_min..=radius_max` range, which then holds its bounds
+    #[serde(rename = "size_distribution")]
+    pub sizes: Option<SizeDistribution>,
     pub max_rotations: usize,
     pub sintering: SinteringDistribution,
+    /// Agglomerate the run grows onto instead of the seed dimer (empty =
+    /// random dimer at the origin); it counts towards `n_particles`
+    pub initial: Vec<Sphere>,
 }
 
 impl Default for TunableParams {
@@ -42,27 +66,29 @@ impl Default for TunableParams {
             target_kf: 1.3,
             radius_min: 1.0,
             radius_max: 1.0,
+            sizes: None,
             max_rotations: 25,
             sintering: SinteringDistribution::default(),
+            initial: Vec::new(),
         }
     }
 }
 
-impl TunableParams {
-    pub fn is_polydisperse(&self) -> bool {
-        (self.radius_max - self.radius_min).abs() > 1e-10
-    }
-
-    pub fn random_radius<R: Rng>(&self, rng: &mut R) -> f64 {
-        if self.is_polydisperse() {
-            rng.gen_range(self.radius_min..=self.radius_max)
-        } else {
-            self.radius_min
-        }
+impl ParticleSizes for TunableParams {
+    fn size_parameters(&self) -> (Option<&SizeDistribution>, f64, f64) {
+        (self.sizes.as_ref(), self.radius_min, self.radius_max)
     }
+}
 
-    pub fn mean_radius(&self) -> f64 {
-        (self.radius_min + self.radius_max) / 2.0
+impl TunableParams {
+    /// Check the parameters before a run, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        // The first two particles form the seed dimer
+        check_count("n_particles", self.n_particles, 2)?;
+        check_existing(self.n_particles, self.initial.len())?;
+        check_fractal_dimension("target_df", self.target_df)?;
+        check_positive("target_kf", self.target_kf)?;
+        check_radius_range(self.radius_min, self.radius_max)
     }
 }
 
@@ -80,8 +106,26 @@ impl TunableParams {
 /// * `sintering_max` - Max for uniform distribution (default: 0.95)
 /// * `sintering_std` - Std dev for normal distribution (default: 0.05)
 /// * `seed` - Random seed for reproducibility
+/// * `sintering` - `PySinteringParams` object; overrides the `sintering_*` arguments when given
+/// * `on_stick` - Callable invoked as `on_stick(event)` with a dict describing the event each time
+///   a particle sticks; returning `False` stops the run early
+/// * `callback_every` - Only forward every N-th event to `on_stick` (default: 1)
+/// * `units` - `Units` giving the physical meaning of the lengths (radii in nm, ...);
+///   enables the physical quantities of the result
+/// * `size_distribution` - `SizeDistribution` the primary particle diameters are drawn from
+///   (lognormal, normal, histogram, ...); overrides `radius_min`/`radius_max` when given
+/// * `progress_callback` - Callable invoked as `progress_callback(info)` every `progress_every`
+///   particles placed, as in `run_dla`
+/// * `progress_every` - Particles placed between two progress reports (default: 100)
+/// * `cancel_event` - Object with an `is_set()` method that stops the run when set, as in `run_dla`
+/// * `existing_coords` - Coordinates (N x 3) of an agglomerate to grow onto instead of the seed
+///   dimer, e.g. a saved checkpoint; it is moved so its center of mass sits at the origin and
+///   counts towards `n_particles` (see `run_tunable_continue`)
+/// * `existing_radii` - Radii (N) of the `existing_coords` particles
+/// * `history` - `HistoryParams` to record a `GrowthHistory`, returned as
+///   `SimulationResult.history` (default: None, no history)
 #[pyfunction]
-#[pyo3(signature = (n_particles, target_df=1.8, target_kf=1.3, radius_min=1.0, radius_max=None, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None))]
+#[pyo3(signature = (n_particles, target_df=1.8, target_kf=1.3, radius_min=1.0, radius_max=None, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None, sintering=None, on_stick=None, callback_every=1, units=None, size_distribution=None, progress_callback=None, progress_every=100, cancel_event=None, existing_coords=None, existing_radii=None, history=None))]
 pub fn run_tunable(
     py: Python<'_>,
     n_particles: usize,
@@ -95,15 +139,33 @@ pub fn run_tunable(
     sintering_max: f64,
     sintering_std: f64,
     seed: Option<u64>,
+    sintering: Option<PySinteringParams>,
+    on_stick: Option<PyObject>,
+    callback_every: usize,
+    units: Option<PyUnits>,
+    size_distribution: Option<PySizeDistribution>,
+    progress_callback: Option<PyObject>,
+    progress_every: usize,
+    cancel_event: Option<PyObject>,
+    existing_coords: Option<&Bound<'_, PyAny>>,
+    existing_radii: Option<&Bound<'_, PyAny>>,
+    history: Option<PyHistoryParams>,
 ) -> PyResult<PySimulationResult> {
     let seed = seed.unwrap_or_else(rand::random);
-    let radius_max = radius_max.unwrap_or(radius_min);
-
-    let sintering = match sintering_type.to_lowercase().as_str() {
-        "uniform" => SinteringDistribution::uniform(sintering_min, sintering_max),
-        "normal" => SinteringDistribution::normal(sintering_coeff, sintering_std),
-        _ => SinteringDistribution::fixed(sintering_coeff),
-    };
+    let existing = extract_existing(existing_coords, existing_radii)?;
+
+    let mut warnings = Vec::new();
+    let sintering = resolve_sintering(
+        sintering.as_ref(),
+        sintering_coeff,
+        sintering_type,
+        sintering_min,
+        sintering_max,
+        sintering_std,
+        &mut warnings,
+    )?;
+    let (radius_min, radius_max, sizes) =
+        resolve_size_distribution(size_distribution.as_ref(), radius_min, radius_max, &mut warnings)?;
 
     let params = TunableParams {
         n_particles,
@@ -111,20 +173,34 @@ pub fn run_tunable(
         target_kf,
         radius_min,
         radius_max,
+        sizes,
         sintering,
+        initial: existing,
         ..Default::default()
     };
+    params.validate()?;
+    let parameters = run_parameters("tunable", &params, json!({"seed": seed, "units": units, "history": history}));
 
+    let mut hooks = PyCallbacks::new(on_stick, None, callback_every)?
+        .with_progress(progress_callback, progress_every)?
+        .with_cancel_event(cancel_event)
+        .with_history(history)?;
     // Release GIL during computation
-    let result = py.allow_threads(|| run_tunable_internal(params, seed));
+    let result = allow_threads(py, || run_tunable_internal(params, seed, &mut hooks));
+    let history = hooks.take_history();
+    hooks.finish(&mut warnings)?;
 
-    Ok(result.to_py())
+    result
+        .into_py(py, warnings)
+        .map(|r| r.with_units(units).with_history(history).with_parameters(parameters))
 }
 
 /// Internal Tunable PC implementation based on Lapuerta/Filippov method.
-fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
+pub(crate) fn run_tunable_internal(params: TunableParams, seed: u64, hooks: &mut dyn EventHooks) -> SimulationResult {
     let start_time = Instant::now();
     let mut rng = create_rng(seed);
+    let radii = params.radius_sampler();
+    let mut warnings = Vec::new();
 
     let rp = params.mean_radius();
     let kf = params.target_kf;
@@ -133,22 +209,28 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
     // Lapuerta constant (3/5 for Lapuerta method, 0 for pure Filippov)
     let constante = 3.0 / 5.0;
 
-    // Start with 2 particles (seed)
-    let mut particles: Vec<Sphere> = Vec::with_capacity(params.n_particles);
+    // Start with 2 particles (seed), or with the existing agglomerate
+    let mut particles = centered_store(&params.initial, params.n_particles);
 
     // First particle at origin
-    let r1 = params.random_radius(&mut rng);
-    particles.push(Sphere::new(Vector3::zero(), r1));
+    if particles.is_empty() {
+        let r1 = radii.sample_radius(&mut rng);
+        particles.push(Sphere::new(Vector3::zero(), r1));
+    }
 
     // Second particle placed at sintered contact distance from first
     // Note: sintering is applied from the start for consistent morphology
-    let r2 = params.random_radius(&mut rng);
-    let (dx, dy, dz) = random_point_on_sphere(&mut rng);
-    let dir = Vector3::new(dx, dy, dz);
-    let sintering_coeff_2 = params.sintering.sample(&mut rng);
-    let contact_dist_2 = sintered_contact_distance(r1, r2, sintering_coeff_2);
-    let pos2 = dir * contact_dist_2;  // Uses sintered distance, not r1+r2
-    particles.push(Sphere::new(pos2, r2));
+    if particles.len() == 1 {
+        let r1 = particles.radius(0);
+        let r2 = radii.sample_radius(&mut rng);
+        let (dx, dy, dz) = random_point_on_sphere(&mut rng);
+        let dir = Vector3::new(dx, dy, dz);
+        let sintering_coeff_2 = params.sintering.sample(&mut rng);
+        let contact_dist_2 = sintered_contact_distance(r1, r2, sintering_coeff_2);
+        let pos2 = particles.center(0) + dir * contact_dist_2;  // Uses sintered distance, not r1+r2
+        particles.push(Sphere::new(pos2, r2));
+    }
+    let n_start = particles.len();
 
     // Track Rg evolution
     let mut rg_evolution = Vec::new();
@@ -158,9 +240,7 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
     let mut center_of_mass = calculate_center_of_mass(&particles);
 
     // Recenter particles around CoM
-    for p in &mut particles {
-        p.center = p.center - center_of_mass;
-    }
+    particles.translate(Vector3::zero() - center_of_mass);
     center_of_mass = Vector3::zero();
 
     // Calculate distances from CoM for each particle
@@ -169,8 +249,11 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
         .map(|p| p.center.distance_to(&center_of_mass))
         .collect();
 
+    // Particles placed by the ballistic fallback instead of the Df/kf relation
+    let mut fallback_placements = 0usize;
+
     // Add particles one by one
-    for np in 3..=params.n_particles {
+    for np in (n_start + 1)..=params.n_particles {
         let np_f = np as f64;
         let np_minus_1 = (np - 1) as f64;
 
@@ -183,10 +266,14 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
 
         if gamma4_sq <= 0.0 {
             // Fallback: place particle using ballistic-like approach
-            let new_radius = params.random_radius(&mut rng);
+            fallback_placements += 1;
+            let new_radius = radii.sample_radius(&mut rng);
             if let Some(pos) = place_particle_ballistic(&particles, &mut rng, new_radius, &params.sintering) {
                 particles.push(Sphere::new(pos, new_radius));
                 distances.push(pos.length());
+                if hooks.on_stick(&StickEvent::last_of(&particles, None)) == Flow::Stop {
+                    break;
+                }
             }
             continue;
         }
@@ -194,17 +281,22 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
         let gamma = rp * gamma4_sq.sqrt();
 
         // Find particles that could be in contact at distance gamma (LA-)
-        // These are particles where: distance_from_com > gamma - 2*rp
-        let new_radius = params.random_radius(&mut rng);
+        // These are particles where: distance_from_com > gamma - (r_i + r_new),
+        // the unsintered contact distance bounding every sintered one
+        let new_radius = radii.sample_radius(&mut rng);
         let la_minus: Vec<usize> = (0..particles.len())
-            .filter(|&i| distances[i] > gamma - 2.0 * rp)
+            .filter(|&i| distances[i] > gamma - (particles.radius(i) + new_radius))
             .collect();
 
         if la_minus.is_empty() {
             // Fallback: use ballistic placement
+            fallback_placements += 1;
             if let Some(pos) = place_particle_ballistic(&particles, &mut rng, new_radius, &params.sintering) {
                 particles.push(Sphere::new(pos, new_radius));
                 distances.push(pos.length());
+                if hooks.on_stick(&StickEvent::last_of(&particles, None)) == Flow::Stop {
+                    break;
+                }
             }
             continue;
         }
@@ -212,6 +304,8 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
         // Try to place particle
         let mut lb = la_minus.clone();
         let mut placed = false;
+        let mut touched = None;
+        let n_before = particles.len();
 
         // Sample sintering coefficient for this particle's contact
         let sintering_coeff = params.sintering.sample(&mut rng);
@@ -219,7 +313,7 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
         while !lb.is_empty() && !placed {
             // Select random reference particle from LB
             let ref_idx = lb[rng.gen_range(0..lb.len())];
-            let ref_particle = &particles[ref_idx];
+            let ref_particle = particles.get(ref_idx);
             let cb = ref_particle.center;
             let rb = ref_particle.radius;
 
@@ -243,22 +337,23 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
 
             let alpha = cos_alpha.acos();
 
-            // Find particles that could intersect (LA+)
-            // Use sintered contact distance for overlap check
+            // Find particles that could intersect (LA+): CA lies at the sintered
+            // contact distance from CB, so particle i can only overlap it within
+            // that plus its own sintered contact distance to the new particle
             let la_plus: Vec<usize> = lb.iter()
                 .filter(|&&i| i != ref_idx)
                 .filter(|&&i| {
-                    let dist_to_ref = particles[i].center.distance_to(&cb);
-                    let overlap_threshold = sintered_contact_distance(rb, particles[i].radius, sintering_coeff);
-                    dist_to_ref < 2.0 * overlap_threshold
+                    let dist_sq = particles.center(i).distance_squared_to(&cb);
+                    let reach = contact_dist + sintered_contact_distance(new_radius, particles.radius(i), sintering_coeff);
+                    dist_sq < reach.powi(2)
                 })
                 .copied()
                 .collect();
 
             // Try different rotation angles (beta)
+            let cb_unit = cb * (1.0 / cb_norm);
             for _ in 0..params.max_rotations {
                 // Generate random rotation axis perpendicular to CB
-                let cb_unit = cb * (1.0 / cb_norm);
                 let rotation_axis = find_perpendicular_axis(&cb_unit, &mut rng);
 
                 // Rotate CB by alpha around rotation_axis to get initial CA direction
@@ -273,23 +368,20 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
 
                 // Check for overlaps with LA+ using sintered contact distances
                 let has_overlap = la_plus.iter().any(|&i| {
-                    let dist = ca.distance_to(&particles[i].center);
-                    let min_dist = sintered_contact_distance(new_radius, particles[i].radius, sintering_coeff);
-                    dist < min_dist - 1e-6
+                    let dist_sq = ca.distance_squared_to(&particles.center(i));
+                    let min_dist = sintered_contact_distance(new_radius, particles.radius(i), sintering_coeff);
+                    dist_sq < (min_dist - 1e-6).max(0.0).powi(2)
                 });
 
                 if !has_overlap {
                     // Also check against all other particles for safety using sintered distances
-                    let safe = !particles.iter().any(|p| {
-                        let dist = ca.distance_to(&p.center);
-                        let min_dist = sintered_contact_distance(new_radius, p.radius, sintering_coeff);
-                        dist < min_dist - 1e-6
-                    });
+                    let safe = !particles.overlaps(ca, new_radius, sintering_coeff, None, 1e-6);
 
                     if safe {
                         particles.push(Sphere::new(ca, new_radius));
                         distances.push(ca.length());
                         placed = true;
+                        touched = Some(ref_idx);
                         break;
                     }
                 }
@@ -302,56 +394,71 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
 
         // Fallback if placement failed
         if !placed {
+            fallback_placements += 1;
             if let Some(pos) = place_particle_ballistic(&particles, &mut rng, new_radius, &params.sintering) {
                 particles.push(Sphere::new(pos, new_radius));
                 distances.push(pos.length());
             }
         }
 
+        let mut stop = particles.len() > n_before
+            && hooks.on_stick(&StickEvent::last_of(&particles, touched)) == Flow::Stop;
+
         // Recenter around new CoM
         center_of_mass = calculate_center_of_mass(&particles);
-        for p in &mut particles {
-            p.center = p.center - center_of_mass;
-        }
+        particles.translate(Vector3::zero() - center_of_mass);
         center_of_mass = Vector3::zero();
 
         // Update distances
-        for (i, p) in particles.iter().enumerate() {
-            if i < distances.len() {
-                distances[i] = p.center.length();
-            }
+        for (i, d) in distances.iter_mut().enumerate() {
+            *d = particles.center(i).length();
         }
         if distances.len() < particles.len() {
-            distances.push(particles.last().unwrap().center.length());
+            distances.push(particles.center(particles.len() - 1).length());
         }
 
-        // Track Rg evolution periodically
-        if np % 10 == 0 || np == params.n_particles {
-            let coords: Vec<[f64; 3]> = particles
-                .iter()
-                .map(|p| [p.center.x, p.center.y, p.center.z])
-                .collect();
-            let radii: Vec<f64> = particles.iter().map(|p| p.radius).collect();
-            let rg = calculate_radius_of_gyration(&coords, &radii);
-            rg_evolution.push(rg);
-            n_values.push(np);
+        // Track Rg evolution periodically, and report progress when the hooks use it
+        let sampled = np % 10 == 0 || np == params.n_particles;
+        if sampled || hooks.wants_progress(np) {
+            let rg = calculate_radius_of_gyration(&particles.coords(), particles.radii());
+            if sampled {
+                rg_evolution.push(rg);
+                n_values.push(np);
+            }
+            let progress = ProgressEvent::particles(np, params.n_particles, rg, start_time);
+            if hooks.wants_snapshot(&progress) {
+                hooks.on_snapshot(Snapshot::of_store(np, &particles));
+            }
+            stop = stop || hooks.on_progress(&progress) == Flow::Stop;
+        }
+
+        if stop {
+            break;
         }
     }
 
+    if fallback_placements > 0 {
+        warnings.push(format!(
+            "{} of {} particles could not satisfy Df={} / kf={} and were placed ballistically",
+            fallback_placements,
+            params.n_particles.saturating_sub(n_start),
+            df,
+            kf
+        ));
+    }
+
     // Calculate final metrics
-    let coords: Vec<[f64; 3]> = particles
-        .iter()
-        .map(|s| [s.center.x, s.center.y, s.center.z])
-        .collect();
-    let radii: Vec<f64> = particles.iter().map(|s| s.radius).collect();
+    let coords = particles.coords();
+    let radii = particles.radii().to_vec();
 
     let final_rg = calculate_radius_of_gyration(&coords, &radii);
 
     // Calculate actual Df and kf from the evolution
-    let (actual_df, actual_kf, _r2) = calculate_fractal_dimension_from_evolution(&n_values, &rg_evolution, rp);
+    let fit = calculate_fractal_dimension_from_evolution(&n_values, &rg_evolution, rp, &mut warnings);
 
     let porosity = calculate_porosity(&coords, &radii);
-    let coordination = calculate_coordination(&coords, &radii, rp * 0.1);
+    let contacts = calculate_contacts(&coords, &radii, rp * 0.1);
+    let coordination = coordination_from_contacts(&contacts, coords.len());
     let inertia = calculate_inertia_tensor(&coords, &radii);
 
     let coord_mean = coordination.iter().map(|&c| c as f64).sum::<f64>() / coordination.len().max(1) as f64;
@@ -366,34 +473,54 @@ fn run_tunable_internal(params: TunableParams, seed: u64) -> SimulationResult {
         0.0
     };
 
+    let n_final = coords.len();
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
     SimulationResult {
         coordinates: coords,
         radii,
         rg_evolution,
-        fractal_dimension: actual_df,
-        fractal_dimension_std: 0.05,
-        prefactor: actual_kf,
+        n_evolution: n_values,
+        fractal_dimension: fit.df,
+        fractal_dimension_std: fit.df_std_error,
+        prefactor: fit.kf,
+        linear_region: fit.linear_region,
+        evolution_fit: EvolutionFit::NOnRg { rp },
+        fit_r_squared: fit.r_squared,
+        fit_n_points: fit.n_points,
         porosity,
         coordination_mean: coord_mean,
         coordination_std: coord_std,
         execution_time_ms,
         seed,
+        child_seeds: Vec::new(),
         anisotropy: inertia.anisotropy,
         asphericity: inertia.asphericity,
         acylindricity: inertia.acylindricity,
         principal_moments: inertia.principal_moments,
         principal_axes: inertia.principal_axes,
+        ids: (0..n_final as u32).collect(),
+        cluster_ids: vec![0; n_final],
+        coordination,
+        contacts,
+        generations: vec![0; n_final],
+        merge_history: Vec::new(),
+        warnings,
+        target_report: None,
+        brownian_report: None,
+        kinetics: None,
+        film: None,
+        drift_report: None,
+        merge_diagnostics: None,
     }
 }
 
 /// Calculate center of mass of particles.
-fn calculate_center_of_mass(particles: &[Sphere]) -> Vector3 {
+fn calculate_center_of_mass(particles: &ParticleStore) -> Vector3 {
     let mut total_mass = 0.0;
     let mut cm = Vector3::zero();
 
-    for p in particles {
+    for p in particles.iter() {
         let mass = p.radius.powi(3);
         cm = cm + p.center * mass;
         total_mass += mass;
@@ -449,7 +576,7 @@ fn rotate_vector(v: &Vector3, axis: &Vector3, angle: f64) -> Vector3 {
 
 /// Fallback: place particle using ballistic-like approach with sintering.
 fn place_particle_ballistic<R: Rng>(
-    particles: &[Sphere],
+    particles: &ParticleStore,
     rng: &mut R,
     radius: f64,
     sintering: &SinteringDistribution,
@@ -476,24 +603,22 @@ fn place_particle_ballistic<R: Rng>(
 
         // Ray march toward center
         let step = radius * 0.5;
+        let step_vec = dir * step;
         let mut pos = start;
 
         for _ in 0..(launch_dist * 4.0 / step) as usize {
             // Check for contact (using sintered contact distance)
-            for p in particles {
-                let dist = pos.distance_to(&p.center);
+            for p in particles.iter() {
+                let dist_sq = pos.distance_squared_to(&p.center);
                 let sintered_dist = sintered_contact_distance(radius, p.radius, sintering_coeff);
 
-                if dist <= sintered_dist * 1.01 {
+                if dist_sq <= (sintered_dist * 1.01).powi(2) {
                     // Place at sintered contact point
                     let to_p = (p.center - pos).normalize();
                     let contact_pos = p.center - to_p * sintered_dist;
 
                     // Verify no overlaps (using sintered distances)
-                    let safe = !particles.iter().any(|other| {
-                        let min_dist = sintered_contact_distance(radius, other.radius, sintering_coeff);
-                        contact_pos.distance_to(&other.center) < min_dist - 1e-6
-                    });
+                    let safe = !particles.overlaps(contact_pos, radius, sintering_coeff, None, 1e-6);
 
                     if safe {
                         return Some(contact_pos);
@@ -501,7 +626,7 @@ fn place_particle_ballistic<R: Rng>(
                 }
             }
 
-            pos = pos + dir * step;
+            pos = pos + step_vec;
         }
     }
 
@@ -509,67 +634,72 @@ fn place_particle_ballistic<R: Rng>(
 }
 
 /// Calculate Df and kf from Rg evolution using proper power law fitting.
-fn calculate_fractal_dimension_from_evolution(
+/// Returns (Df, kf, R2, linear region), the region as indices into `n_values`.
+pub(crate) fn calculate_fractal_dimension_from_evolution(
     n_values: &[usize],
     rg_values: &[f64],
     rp: f64,
-) -> (f64, f64, f64) {
+    warnings: &mut Vec<String>,
+) -> FractalFit {
+    fit_fractal_dimension_from_evolution(n_values, rg_values, rp, FitMethod::Ols, warnings)
+}
+
+/// Df and kf from the regression of ln N on ln(Rg/rp), with the line
+/// through the linear region fitted by `method` (WLS weights by N).
+pub(crate) fn fit_fractal_dimension_from_evolution(
+    n_values: &[usize],
+    rg_values: &[f64],
+    rp: f64,
+    method: FitMethod,
+    warnings: &mut Vec<String>,
+) -> FractalFit {
     if n_values.len() < 3 || n_values.len() != rg_values.len() {
-        return (2.0, 1.0, 0.0);
+        warnings.push(insufficient_fit_warning(n_values.len()));
+        return FractalFit::fallback();
     }
 
     // Use N = kf * (Rg/rp)^Df
     // log(N) = log(kf) + Df * log(Rg/rp)
-    let data: Vec<(f64, f64)> = n_values
+    let (indices, (xs, ys)): (Vec<usize>, (Vec<f64>, Vec<f64>)) = n_values
         .iter()
         .zip(rg_values.iter())
-        .filter(|(&n, &rg)| n > 1 && rg > rp * 0.1)
-        .map(|(&n, &rg)| ((rg / rp).ln(), (n as f64).ln()))
-        .collect();
-
-    if data.len() < 3 {
-        return (2.0, 1.0, 0.0);
+        .enumerate()
+        .filter(|(_, (&n, &rg))| n > 1 && rg > rp * 0.1)
+        .map(|(i, (&n, &rg))| (i, ((rg / rp).ln(), (n as f64).ln())))
+        .unzip();
+
+    if indices.len() < 3 {
+        warnings.push(insufficient_fit_warning(indices.len()));
+        return FractalFit::fallback();
     }
 
-    // Linear regression: y = intercept + slope * x
+    // Linear regression on the linear region: y = intercept + slope * x
     // where x = log(Rg/rp), y = log(N)
     // slope = Df, intercept = log(kf)
-    let n = data.len() as f64;
-    let sum_x: f64 = data.iter().map(|(x, _)| x).sum();
-    let sum_y: f64 = data.iter().map(|(_, y)| y).sum();
-    let sum_xx: f64 = data.iter().map(|(x, _)| x * x).sum();
-    let sum_xy: f64 = data.iter().map(|(x, y)| x * y).sum();
-
-    let denom = n * sum_xx - sum_x * sum_x;
-    if denom.abs() < 1e-10 {
-        return (2.0, 1.0, 0.0);
+    let linear = fit_linear_region(&xs, &ys, &LinearRegionParams::for_evolution(xs.len()));
+    let sizes: Vec<f64> = ys[linear.start..linear.end].iter().map(|y| y.exp()).collect();
+    let fit = method.fit(&xs[linear.start..linear.end], &ys[linear.start..linear.end], &sizes);
+    if fit.std_error.is_infinite() {
+        warnings.push("Rg samples do not vary with N; reporting defaults Df=2.0, kf=1.0".to_string());
+        return FractalFit::fallback();
     }
 
-    let slope = (n * sum_xy - sum_x * sum_y) / denom;
-    let intercept = (sum_y - slope * sum_x) / n;
-
-    let df = slope.max(1.0).min(3.0);
-    let kf = intercept.exp().max(0.1).min(10.0);
+    let (df, kf) = clamp_fitted_parameters(fit.slope, fit.intercept.exp(), (1.0, 3.0), (0.1, 10.0), warnings);
 
-    // R-squared
-    let mean_y = sum_y / n;
-    let ss_tot: f64 = data.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
-    let ss_res: f64 = data
-        .iter()
-        .map(|(x, y)| {
-            let y_pred = intercept + slope * x;
-            (y - y_pred).powi(2)
-        })
-        .sum();
-
-    let r2 = if ss_tot > 0.0 { 1.0 - ss_res / ss_tot } else { 0.0 };
-
-    (df, kf, r2)
+    FractalFit {
+        df,
+        kf,
+        r_squared: fit.r_squared,
+        df_std_error: fit.std_error,
+        n_points: linear.end - linear.start,
+        linear_region: fit_region_indices(&indices, linear.start, linear.end),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::simulation::hooks::NoHooks;
 
     #[test]
     fn test_tunable_deterministic() {
@@ -580,8 +710,8 @@ mod tests {
             ..Default::default()
         };
 
-        let r1 = run_tunable_internal(params.clone(), 42);
-        let r2 = run_tunable_internal(params, 42);
+        let r1 = run_tunable_internal(params.clone(), 42, &mut NoHooks);
+        let r2 = run_tunable_internal(params, 42, &mut NoHooks);
 
         assert_eq!(r1.coordinates.len(), r2.coordinates.len());
         assert_eq!(r1.seed, r2.seed);
@@ -597,7 +727,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = run_tunable_internal(params, 123);
+        let result = run_tunable_internal(params, 123, &mut NoHooks);
 
         assert_eq!(result.coordinates.len(), 200);
         // Should be within reasonable range of target
@@ -615,9 +745,9 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(params.is_polydisperse());
+        assert!(params.size_distribution().is_polydisperse());
 
-        let result = run_tunable_internal(params, 789);
+        let result = run_tunable_internal(params, 789, &mut NoHooks);
 
         let min_r = result.radii.iter().cloned().fold(f64::INFINITY, f64::min);
         let max_r = result.radii.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
@@ -626,4 +756,29 @@ mod tests {
         assert!(min_r >= 0.8 - 1e-10);
         assert!(max_r <= 1.2 + 1e-10);
     }
+
+    #[test]
+    fn test_tunable_sintered_contacts() {
+        let params = TunableParams {
+            n_particles: 60,
+            target_df: 1.8,
+            sintering: SinteringDistribution::fixed(0.85),
+            ..Default::default()
+        };
+        let result = run_tunable_internal(params, 17, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 60);
+
+        // Every particle sits at the sintered contact distance 0.85 * (1 + 1) of its
+        // nearest neighbour, never closer
+        for (i, a) in result.coordinates.iter().enumerate() {
+            let nearest = result
+                .coordinates
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, b)| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt())
+                .fold(f64::INFINITY, f64::min);
+            assert!((nearest - 1.7).abs() < 2e-2, "particle {} nearest = {}", i, nearest);
+        }
+    }
 }
@@ -11,29 +11,46 @@
 use std::f64::consts::PI;
 use std::time::Instant;
 
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
 use rand::seq::SliceRandom;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::common::geometry::{Sphere, Vector3};
-use crate::common::rng::{create_rng, random_point_on_sphere};
+use crate::common::rng::{create_rng, random_point_on_sphere, SeedSequence};
+use crate::common::units::PyUnits;
+use crate::common::validation::{
+    check_count, check_fractal_dimension, check_positive, check_radius_range,
+};
+use crate::session::allow_threads;
 
+use super::history::{PyHistoryParams, Snapshot};
+use super::hooks::{ClusterMergeEvent, EventHooks, Flow, NoHooks, ProgressEvent, PyCallbacks};
+use super::lineage::Lineage;
 use super::metrics::{
-    calculate_coordination, calculate_inertia_tensor, calculate_porosity,
-    calculate_radius_of_gyration,
+    calculate_contacts, calculate_inertia_tensor, calculate_porosity,
+    calculate_radius_of_gyration, coordination_from_contacts, EvolutionFit,
+};
+use super::provenance::run_parameters;
+use super::restart::{check_existing, extract_existing, group_clusters};
+use super::result::{creation_order, reorder, PySimulationResult, SimulationResult, TargetReport};
+use super::sintering::{
+    resolve_sintering, sintered_contact_distance, PySinteringParams, SinteringDistribution,
 };
-use super::result::{PySimulationResult, SimulationResult};
-use super::sintering::{sintered_contact_distance, SinteringDistribution};
-use super::tunable::run_tunable;
+use super::size_distribution::{resolve_size_distribution, ParticleSizes, PySizeDistribution, SizeDistribution, SizeSampler};
+use super::tunable::{calculate_fractal_dimension_from_evolution, run_tunable_internal, TunableParams};
 
 /// Seed cluster generation strategy.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SeedStrategy {
     /// All monomers (like standard Ballistic CC)
     Monomers,
     /// Generate seed clusters using Tunable PC with specified size
     TunablePc { cluster_size: usize },
-    /// Custom distribution of cluster sizes (not yet implemented)
+    /// Seed clusters of the given sizes, grown by Tunable PC (monomers for size 1)
     Custom { sizes: Vec<usize> },
 }
 
@@ -44,19 +61,42 @@ impl Default for SeedStrategy {
 }
 
 /// Tunable CC simulation parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TunableCcParams {
     pub n_particles: usize,
     pub target_df: f64,
     pub target_kf: f64,
     pub radius_min: f64,
     pub radius_max: f64,
+    /// Distribution the radii are drawn from instead of the uniform
+    /// `radius_min..=radius_max` range, which then holds its bounds
+    #[serde(rename = "size_distribution")]
+    pub sizes: Option<SizeDistribution>,
     pub seed_strategy: SeedStrategy,
     pub max_rotation_attempts: usize,
     pub max_particle_selection_attempts: usize,
     pub sintering: SinteringDistribution,
+    /// Never fall back to ballistic merging; pairings the power law cannot
+    /// place are retried with other clusters instead
+    pub strict: bool,
+    /// Clusters the pool restarts from; they count towards `n_particles`
+    /// and the seed strategy only builds the other particles
+    pub initial_clusters: Vec<Vec<Sphere>>,
+    /// Reject merges whose merged cluster has a local Df further than this
+    /// from `target_df` and re-select the pair (None = accept every merge)
+    pub df_tolerance: Option<f64>,
 }
 
+/// Consecutive failed pairings after which strict mode gives up.
+const STRICT_MAX_FAILED_PAIRINGS: usize = 200;
+
+/// Smallest merged cluster whose local Df is checked against `df_tolerance`;
+/// the Df of a few particles is set by their contacts, not the power law.
+const DF_CHECK_MIN_PARTICLES: usize = 10;
+
+/// Child of the run's seed sequence that seeds its Tunable PC seed clusters.
+const SEED_CLUSTER_STREAM: u64 = 0;
+
 impl Default for TunableCcParams {
     fn default() -> Self {
         Self {
@@ -65,29 +105,52 @@ impl Default for TunableCcParams {
             target_kf: 1.3,
             radius_min: 1.0,
             radius_max: 1.0,
+            sizes: None,
             seed_strategy: SeedStrategy::Monomers,
             max_rotation_attempts: 50,
             max_particle_selection_attempts: 25,
             sintering: SinteringDistribution::default(),
+            strict: false,
+            initial_clusters: Vec::new(),
+            df_tolerance: None,
         }
     }
 }
 
-impl TunableCcParams {
-    pub fn is_polydisperse(&self) -> bool {
-        (self.radius_max - self.radius_min).abs() > 1e-10
+impl ParticleSizes for TunableCcParams {
+    fn size_parameters(&self) -> (Option<&SizeDistribution>, f64, f64) {
+        (self.sizes.as_ref(), self.radius_min, self.radius_max)
     }
+}
 
-    pub fn random_radius<R: Rng>(&self, rng: &mut R) -> f64 {
-        if self.is_polydisperse() {
-            rng.gen_range(self.radius_min..=self.radius_max)
-        } else {
-            self.radius_min
+impl TunableCcParams {
+    /// Check the parameters before a run, raising `ValueError` on nonsense input.
+    pub fn validate(&self) -> PyResult<()> {
+        check_count("n_particles", self.n_particles, 1)?;
+        check_existing(self.n_particles, self.initial_clusters.iter().map(Vec::len).sum())?;
+        check_fractal_dimension("target_df", self.target_df)?;
+        check_positive("target_kf", self.target_kf)?;
+        if let Some(tolerance) = self.df_tolerance {
+            check_positive("df_tolerance", tolerance)?;
         }
-    }
-
-    pub fn mean_radius(&self) -> f64 {
-        (self.radius_min + self.radius_max) / 2.0
+        match &self.seed_strategy {
+            SeedStrategy::Monomers => {}
+            SeedStrategy::TunablePc { cluster_size } => check_count("seed_cluster_size", *cluster_size, 1)?,
+            SeedStrategy::Custom { sizes } => {
+                if sizes.contains(&0) {
+                    return Err(PyValueError::new_err("seed_cluster_sizes must all be at least 1"));
+                }
+                let n_new = self.n_particles - self.initial_clusters.iter().map(Vec::len).sum::<usize>();
+                let total: usize = sizes.iter().sum();
+                if total != n_new {
+                    return Err(PyValueError::new_err(format!(
+                        "seed_cluster_sizes add up to {} particles, but {} new particles are needed",
+                        total, n_new
+                    )));
+                }
+            }
+        }
+        check_radius_range(self.radius_min, self.radius_max)
     }
 }
 
@@ -95,6 +158,13 @@ impl TunableCcParams {
 #[derive(Clone)]
 struct TunableCluster {
     particles: Vec<Sphere>,
+    /// Persistent particle IDs, parallel to `particles`.
+    /// Assigned once the seed pool is built (see `assign_particle_ids`).
+    ids: Vec<u32>,
+    /// Merge generation of each particle, parallel to `particles`.
+    generations: Vec<u32>,
+    /// Lineage label of this cluster, also set by `assign_particle_ids`.
+    label: u32,
     center_of_mass: Vector3,
     geometric_center: Vector3,
     bounding_radius: f64,
@@ -111,12 +181,18 @@ impl TunableCluster {
             bounding_radius: sphere.radius,
             radius_of_gyration: rg,
             particles: vec![sphere],
+            ids: vec![0],
+            generations: vec![0],
+            label: 0,
         }
     }
 
     /// Create a cluster from multiple particles.
     fn from_particles(particles: Vec<Sphere>) -> Self {
         let mut cluster = Self {
+            ids: (0..particles.len() as u32).collect(),
+            generations: vec![0; particles.len()],
+            label: 0,
             particles,
             center_of_mass: Vector3::zero(),
             geometric_center: Vector3::zero(),
@@ -196,6 +272,11 @@ impl TunableCluster {
     /// Merge another cluster into this one.
     fn merge_with(&mut self, other: TunableCluster) {
         self.particles.extend(other.particles);
+        self.ids.extend(other.ids);
+        self.generations.extend(other.generations);
+        for generation in &mut self.generations {
+            *generation += 1;
+        }
         self.update_properties();
     }
 
@@ -225,9 +306,11 @@ impl TunableCluster {
 /// Calculate the required distance between centers of mass for two clusters to merge
 /// while maintaining the power law relationship.
 ///
-/// Based on thesis equation (eq:leyPotenciasColisionSimplificada):
-/// (r_G2 - r_G1)² = rp² × [(n_po/kf)^(2/Df) - 3/5]
-///                - n_po × rp² × (n_po1/kf)^(2/Df) × [1/n_po2 + 1/n_po1]
+/// Each cluster's Rg² follows the Lapuerta power law rp² × [(n/kf)^(2/Df) - 3/5]
+/// and the merged Rg² is the mass-weighted sum plus the CoM separation term:
+/// (r_G2 - r_G1)² = n_po²/(n_po1 n_po2) × Rg²(n_po)
+///                - n_po/n_po2 × Rg²(n_po1) - n_po/n_po1 × Rg²(n_po2)
+/// which for a monomer impactor is the Tunable PC step.
 fn calculate_com_distance(
     n_po: usize,    // Total particles after merge
     n_po1: usize,   // Particles in cluster 1 (impacted)
@@ -243,12 +326,10 @@ fn calculate_com_distance(
     // Lapuerta constant
     let constante = 3.0 / 5.0;
 
-    // From thesis equation
-    let term1 = (n_po_f / kf).powf(2.0 / df) - constante;
-    let term2_factor = (n_po1_f / kf).powf(2.0 / df);
-    let term2 = n_po_f * term2_factor * (1.0 / n_po2_f + 1.0 / n_po1_f);
-
-    let distance_sq = rp.powi(2) * term1 - rp.powi(2) * term2;
+    let rg_sq = |n: f64| rp.powi(2) * ((n / kf).powf(2.0 / df) - constante);
+    let distance_sq = n_po_f.powi(2) / (n_po1_f * n_po2_f) * rg_sq(n_po_f)
+        - n_po_f / n_po2_f * rg_sq(n_po1_f)
+        - n_po_f / n_po1_f * rg_sq(n_po2_f);
 
     if distance_sq <= 0.0 {
         // Can happen for very small clusters or extreme Df values
@@ -278,6 +359,13 @@ fn can_clusters_connect(
     cluster1.bounding_radius + cluster2.bounding_radius >= required_distance
 }
 
+/// Whether a merge counts as a fallback: only clusters positioned at the
+/// power-law CoM distance enforce the target, so a merge placed at the
+/// stand-in distance used when the power law has no solution does not.
+fn is_fallback_merge(positioned: bool, com_distance: Option<f64>) -> bool {
+    !positioned || com_distance.is_none()
+}
+
 /// Check for overlap between two clusters with sintering support.
 fn check_overlap(cluster1: &TunableCluster, cluster2: &TunableCluster, sintering_coeff: f64) -> bool {
     // Quick bounding sphere check first (use sintered distance)
@@ -290,9 +378,9 @@ fn check_overlap(cluster1: &TunableCluster, cluster2: &TunableCluster, sintering
     // Detailed particle-level check with sintering
     for p1 in &cluster1.particles {
         for p2 in &cluster2.particles {
-            let d = p1.center.distance_to(&p2.center);
+            let d_sq = p1.center.distance_squared_to(&p2.center);
             let contact_dist = sintered_contact_distance(p1.radius, p2.radius, sintering_coeff);
-            if d < contact_dist - 1e-6 {
+            if d_sq < (contact_dist - 1e-6).max(0.0).powi(2) {
                 return true;
             }
         }
@@ -493,113 +581,222 @@ fn resolve_overlap_by_rotation<R: Rng>(
     false
 }
 
-/// Initialize seed clusters based on strategy.
+/// Number the particles of the seed pool consecutively, cluster by cluster,
+/// and give each seed cluster its lineage label.
+fn assign_particle_ids(clusters: &mut [TunableCluster]) {
+    let mut next_id = 0u32;
+    for (label, cluster) in clusters.iter_mut().enumerate() {
+        let n = cluster.n_particles() as u32;
+        cluster.ids = (next_id..next_id + n).collect();
+        cluster.label = label as u32;
+        next_id += n;
+    }
+}
+
+/// Seed cluster of `size` particles: a monomer, or grown by Tunable PC with the
+/// run's target Df/kf under `seed`, sintered like the merges.
+fn grow_seed_cluster<R: Rng>(
+    params: &TunableCcParams,
+    radii: &SizeSampler,
+    size: usize,
+    seed: u64,
+    rng: &mut R,
+) -> TunableCluster {
+    if size == 1 {
+        let r = radii.sample_radius(rng);
+        return TunableCluster::new(Sphere::new(Vector3::zero(), r));
+    }
+    let seed_params = TunableParams {
+        n_particles: size,
+        target_df: params.target_df,
+        target_kf: params.target_kf,
+        radius_min: params.radius_min,
+        radius_max: params.radius_max,
+        sizes: params.sizes.clone(),
+        sintering: params.sintering.clone(),
+        ..Default::default()
+    };
+    let result = run_tunable_internal(seed_params, seed, &mut NoHooks);
+    let particles = result
+        .coordinates
+        .iter()
+        .zip(&result.radii)
+        .map(|(c, &r)| Sphere::new(Vector3::new(c[0], c[1], c[2]), r))
+        .collect();
+    TunableCluster::from_particles(particles)
+}
+
+/// Initialize seed clusters for `n_particles` new particles based on strategy.
+///
+/// Cluster k is grown under seed k of `seeds`. Returns the clusters and the
+/// seeds of those grown by Tunable PC.
 fn initialize_seed_clusters<R: Rng>(
     params: &TunableCcParams,
+    n_particles: usize,
+    seeds: &SeedSequence,
     rng: &mut R,
-    py: Option<Python<'_>>,
-) -> Vec<TunableCluster> {
-    match &params.seed_strategy {
-        SeedStrategy::Monomers => {
-            // All individual particles
-            (0..params.n_particles)
-                .map(|_| {
-                    let r = params.random_radius(rng);
-                    TunableCluster::new(Sphere::new(Vector3::zero(), r))
-                })
-                .collect()
-        }
-        SeedStrategy::TunablePc { cluster_size } => {
-            // Generate seed clusters using Tunable PC algorithm
-            let n_clusters = (params.n_particles + cluster_size - 1) / *cluster_size;
-            let remaining = params.n_particles;
-
-            let mut clusters = Vec::with_capacity(n_clusters);
-            let mut particles_used = 0;
-
-            for i in 0..n_clusters {
-                let size = if i == n_clusters - 1 && remaining % *cluster_size != 0 {
-                    remaining - particles_used
-                } else {
-                    (*cluster_size).min(remaining - particles_used)
-                };
-
-                if size == 0 {
-                    break;
-                }
+) -> (Vec<TunableCluster>, Vec<u64>) {
+    let sizes: Vec<usize> = match &params.seed_strategy {
+        // All individual particles
+        SeedStrategy::Monomers => vec![1; n_particles],
+        // Clusters of `cluster_size` particles, the last one taking the remainder
+        SeedStrategy::TunablePc { cluster_size } => (0..n_particles)
+            .step_by(*cluster_size)
+            .map(|used| (*cluster_size).min(n_particles - used))
+            .collect(),
+        // Validated to add up to the new particles
+        SeedStrategy::Custom { sizes } => sizes.clone(),
+    };
+    let radii = params.radius_sampler();
+    let clusters = sizes
+        .iter()
+        .enumerate()
+        .map(|(k, &size)| grow_seed_cluster(params, &radii, size, seeds.seed(k as u64), rng))
+        .collect();
+    let child_seeds = sizes
+        .iter()
+        .enumerate()
+        .filter(|&(_, &size)| size > 1)
+        .map(|(k, _)| seeds.seed(k as u64))
+        .collect();
+    (clusters, child_seeds)
+}
 
-                if size == 1 {
-                    // Single particle
-                    let r = params.random_radius(rng);
-                    clusters.push(TunableCluster::new(Sphere::new(Vector3::zero(), r)));
-                } else if let Some(py) = py {
-                    // Use Tunable PC to generate seed cluster
-                    let seed: u64 = rng.gen();
-                    // Use mean sintering coefficient for seed clusters
-                    let sintering_coeff = params.sintering.mean();
-                    if let Ok(result) = run_tunable(
-                        py,
-                        size,
-                        params.target_df,
-                        params.target_kf,
-                        params.radius_min,
-                        Some(params.radius_max),
-                        sintering_coeff,
-                        "fixed",
-                        0.85,
-                        0.95,
-                        0.05,
-                        Some(seed),
-                    ) {
-                        // Convert PySimulationResult to TunableCluster
-                        let particles: Vec<Sphere> = (0..result.radii_data.len())
-                            .map(|j| {
-                                Sphere::new(
-                                    Vector3::new(
-                                        result.coordinates_data[j * 3],
-                                        result.coordinates_data[j * 3 + 1],
-                                        result.coordinates_data[j * 3 + 2],
-                                    ),
-                                    result.radii_data[j],
-                                )
-                            })
-                            .collect();
-                        clusters.push(TunableCluster::from_particles(particles));
-                    } else {
-                        // Fallback: create monomers
-                        for _ in 0..size {
-                            let r = params.random_radius(rng);
-                            clusters.push(TunableCluster::new(Sphere::new(Vector3::zero(), r)));
-                        }
-                    }
-                } else {
-                    // No Python context, create monomers
-                    for _ in 0..size {
-                        let r = params.random_radius(rng);
-                        clusters.push(TunableCluster::new(Sphere::new(Vector3::zero(), r)));
-                    }
-                }
+/// Radius of gyration of the union of two clusters at their current positions.
+///
+/// Parallel axis theorem with the r³ masses of `calculate_radius_of_gyration`.
+fn merged_radius_of_gyration(cluster1: &TunableCluster, cluster2: &TunableCluster) -> f64 {
+    let mass = |c: &TunableCluster| c.particles.iter().map(|p| p.radius.powi(3)).sum::<f64>();
+    let (m1, m2) = (mass(cluster1), mass(cluster2));
+    let total = m1 + m2;
+    let d2 = cluster1.center_of_mass.distance_squared_to(&cluster2.center_of_mass);
+    ((m1 * cluster1.radius_of_gyration.powi(2) + m2 * cluster2.radius_of_gyration.powi(2)) / total
+        + m1 * m2 * d2 / (total * total))
+        .sqrt()
+}
 
-                particles_used += size;
-            }
+/// Df of a cluster of `n` particles and radius of gyration `rg` under N = kf (Rg/rp)^Df.
+fn local_fractal_dimension(n: usize, rg: f64, kf: f64, rp: f64) -> f64 {
+    (n as f64 / kf).ln() / (rg / rp).ln()
+}
 
-            clusters
-        }
-        SeedStrategy::Custom { sizes } => {
-            // Generate clusters of specified sizes
-            sizes
+/// Collects how every merge of a tunable CC run was placed.
+#[derive(Debug, Default)]
+struct MergeDiagnostics {
+    fallback: Vec<bool>,
+    target_distances: Vec<f64>,
+    achieved_distances: Vec<f64>,
+    /// Df of the merged cluster from the power law with the target kf (NaN below `DF_CHECK_MIN_PARTICLES`)
+    local_dfs: Vec<f64>,
+    failed_positionings: usize,
+    df_rejections: usize,
+}
+
+impl MergeDiagnostics {
+    /// Record a merge at CoM distance `achieved`, the power law asking for `target` (None when it has no solution).
+    fn record(&mut self, fallback: bool, target: Option<f64>, achieved: f64, local_df: Option<f64>) {
+        self.fallback.push(fallback);
+        self.target_distances.push(target.unwrap_or(f64::NAN));
+        self.achieved_distances.push(achieved);
+        self.local_dfs.push(local_df.unwrap_or(f64::NAN));
+    }
+
+    fn finish(self, target_df: f64, df_tolerance: Option<f64>) -> PyMergeDiagnostics {
+        let distance_errors: Vec<f64> = self
+            .target_distances
+            .iter()
+            .zip(&self.achieved_distances)
+            .map(|(target, achieved)| achieved / target - 1.0)
+            .collect();
+        let max_target_error = distance_errors
+            .iter()
+            .zip(&self.fallback)
+            .filter(|&(_, &fallback)| !fallback)
+            .map(|(error, _)| error.abs())
+            .fold(0.0, f64::max);
+        let max_df_deviation = self
+            .local_dfs
+            .iter()
+            .filter(|df| !df.is_nan())
+            .map(|df| (df - target_df).abs())
+            .fold(0.0, f64::max);
+        PyMergeDiagnostics {
+            merge_types: self
+                .fallback
                 .iter()
-                .map(|&size| {
-                    let particles: Vec<Sphere> = (0..size)
-                        .map(|_| Sphere::new(Vector3::zero(), params.random_radius(rng)))
-                        .collect();
-                    TunableCluster::from_particles(particles)
-                })
-                .collect()
+                .map(|&fallback| if fallback { "fallback" } else { "target" }.to_string())
+                .collect(),
+            target_merges: self.fallback.iter().filter(|&&fallback| !fallback).count(),
+            fallback_merges: self.fallback.iter().filter(|&&fallback| fallback).count(),
+            failed_positionings: self.failed_positionings,
+            target_distances: self.target_distances,
+            achieved_distances: self.achieved_distances,
+            distance_errors,
+            max_target_error,
+            local_dfs: self.local_dfs,
+            max_df_deviation,
+            df_tolerance,
+            df_rejections: self.df_rejections,
         }
     }
 }
 
+/// How each merge of a tunable CC run was placed, from `SimulationResult.merge_diagnostics`.
+///
+/// The per-merge lists are in merge order. A run that mostly fell back to
+/// ballistic merges, or whose distance errors are large, did not enforce
+/// its target Df/kf.
+#[pyclass(name = "MergeDiagnostics")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PyMergeDiagnostics {
+    /// "target" for merges placed by the power law, "fallback" for ballistic ones
+    /// and for merges the power law had no distance for
+    #[pyo3(get)]
+    pub merge_types: Vec<String>,
+    #[pyo3(get)]
+    pub target_merges: usize,
+    #[pyo3(get)]
+    pub fallback_merges: usize,
+    /// Contact particle selections whose positioning or overlap resolution failed
+    #[pyo3(get)]
+    pub failed_positionings: usize,
+    /// CoM distance the power law requires for each merge (NaN without a solution)
+    #[pyo3(get)]
+    pub target_distances: Vec<f64>,
+    /// CoM distance each merge was placed at
+    #[pyo3(get)]
+    pub achieved_distances: Vec<f64>,
+    /// achieved / target - 1 for each merge
+    #[pyo3(get)]
+    pub distance_errors: Vec<f64>,
+    /// Largest |distance error| of the target merges
+    #[pyo3(get)]
+    pub max_target_error: f64,
+    /// Df of each merged cluster from its Rg and the target kf, ln(N / kf) / ln(Rg / rp)
+    /// (NaN for clusters of fewer than 10 particles, too small for a meaningful Df)
+    #[pyo3(get)]
+    pub local_dfs: Vec<f64>,
+    /// Largest |local Df - target Df| over the merges
+    #[pyo3(get)]
+    pub max_df_deviation: f64,
+    /// Tolerance on the local Df the run enforced (None = not enforced)
+    #[pyo3(get)]
+    pub df_tolerance: Option<f64>,
+    /// Merges rejected for a local Df outside the tolerance
+    #[pyo3(get)]
+    pub df_rejections: usize,
+}
+
+#[pymethods]
+impl PyMergeDiagnostics {
+    fn __repr__(&self) -> String {
+        format!(
+            "MergeDiagnostics(target_merges={}, fallback_merges={}, failed_positionings={}, max_df_deviation={:.4})",
+            self.target_merges, self.fallback_merges, self.failed_positionings, self.max_df_deviation
+        )
+    }
+}
+
 /// Fallback: merge clusters using ballistic-like approach with sintering support.
 fn merge_ballistic<R: Rng>(
     cluster1: &TunableCluster,
@@ -623,15 +820,16 @@ fn merge_ballistic<R: Rng>(
         // March toward cluster1
         let trajectory = (cluster1.center_of_mass - cluster2.center_of_mass).normalize();
         let step = cluster2.particles.iter().map(|p| p.radius).fold(f64::INFINITY, f64::min) * 0.5;
+        let step_vec = trajectory * step;
 
         for _ in 0..(launch_dist * 4.0 / step) as usize {
             // Check for contact (any particle pair touching with sintering)
             for p1 in &cluster1.particles {
                 for p2 in &cluster2.particles {
-                    let dist = p1.center.distance_to(&p2.center);
+                    let dist_sq = p1.center.distance_squared_to(&p2.center);
                     let contact_dist = sintered_contact_distance(p1.radius, p2.radius, sintering_coeff);
 
-                    if dist <= contact_dist * 1.01 && dist >= contact_dist * 0.9 {
+                    if dist_sq <= (contact_dist * 1.01).powi(2) && dist_sq >= (contact_dist * 0.9).powi(2) {
                         // Found contact, check no overlap with sintering
                         if !check_overlap(cluster1, cluster2, sintering_coeff) {
                             return true;
@@ -641,7 +839,7 @@ fn merge_ballistic<R: Rng>(
             }
 
             // Step forward
-            cluster2.translate(trajectory * step);
+            cluster2.translate(step_vec);
         }
     }
 
@@ -656,7 +854,8 @@ fn merge_ballistic<R: Rng>(
 /// * `target_kf` - Target prefactor (typically 1.0-2.0)
 /// * `radius_min` - Minimum particle radius
 /// * `radius_max` - Maximum particle radius
-/// * `seed_cluster_size` - Size of seed clusters (None = monomers)
+/// * `seed_cluster_size` - Size of seed clusters, grown by Tunable PC with the run's target
+///   Df/kf and sintering (None = monomers)
 /// * `max_rotation_attempts` - Max attempts to resolve overlap by rotation
 /// * `sintering_coeff` - Sintering coefficient (0.5-1.0, where 1.0 = no sintering)
 /// * `sintering_type` - Distribution type: "fixed", "uniform", or "normal"
@@ -664,8 +863,41 @@ fn merge_ballistic<R: Rng>(
 /// * `sintering_max` - Max for uniform distribution (default: 0.95)
 /// * `sintering_std` - Std dev for normal distribution (default: 0.05)
 /// * `seed` - Random seed for reproducibility
+/// * `sintering` - `PySinteringParams` object; overrides the `sintering_*` arguments when given
+/// * `on_merge` - Callable invoked as `on_merge(event)` with a dict describing the event each time
+///   two clusters merge; returning `False` stops the run early
+/// * `callback_every` - Only forward every N-th event to `on_merge` (default: 1)
+/// * `units` - `Units` giving the physical meaning of the lengths (radii in nm, ...);
+///   enables the physical quantities of the result
+/// * `strict` - Disallow the ballistic fallback merges: pairings the power law cannot place
+///   are retried with other clusters, and `RuntimeError` is raised if no pairing works
+///   (default: false). Either way `target_report` tells how many merges satisfied the target
+/// * `size_distribution` - `SizeDistribution` the primary particle diameters are drawn from
+///   (lognormal, normal, histogram, ...); overrides `radius_min`/`radius_max` when given
+/// * `progress_callback` - Callable invoked as `progress_callback(info)` every `progress_every`
+///   merges, as in `run_dla`
+/// * `progress_every` - Merges between two progress reports (default: 100)
+/// * `cancel_event` - Object with an `is_set()` method that stops the run when set, as in `run_dla`
+/// * `existing_coords` - Coordinates (N x 3) of particles the cluster pool restarts from, e.g.
+///   agglomerates of earlier runs; they count towards `n_particles` and take the first IDs, and
+///   the remaining particles are built by the seed strategy
+/// * `existing_radii` - Radii (N) of the `existing_coords` particles
+/// * `existing_cluster_ids` - Cluster of each existing particle, as in
+///   `SimulationResult.cluster_ids` (default: all in one cluster)
+/// * `history` - `HistoryParams` to record a `GrowthHistory`, returned as
+///   `SimulationResult.history` (default: None, no history)
+/// * `seed_cluster_sizes` - Sizes of the seed clusters, e.g. a measured primary-aggregate
+///   population; each is grown by Tunable PC (size 1 = monomer) and they must add up to the
+///   particles not given by `existing_coords`. Pass ready-made seed clusters as
+///   `existing_coords`/`existing_radii`/`existing_cluster_ids` instead. Excludes
+///   `seed_cluster_size` (default: None)
+/// * `df_tolerance` - Reject every merge whose merged cluster (10 particles or more) deviates
+///   from the power law by more than this in Df, ln(N / kf) / ln(Rg / rp) against `target_df`,
+///   and re-select the pair, so the agglomerate stays within e.g. ±0.05 of the target; raises
+///   `RuntimeError` like `strict` when no remaining pairing passes. The local Df of every merge
+///   is in `SimulationResult.merge_diagnostics` (default: None, no check)
 #[pyfunction]
-#[pyo3(signature = (n_particles, target_df=1.8, target_kf=1.3, radius_min=1.0, radius_max=None, seed_cluster_size=None, max_rotation_attempts=50, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None))]
+#[pyo3(signature = (n_particles, target_df=1.8, target_kf=1.3, radius_min=1.0, radius_max=None, seed_cluster_size=None, max_rotation_attempts=50, sintering_coeff=1.0, sintering_type="fixed", sintering_min=0.85, sintering_max=0.95, sintering_std=0.05, seed=None, sintering=None, on_merge=None, callback_every=1, units=None, strict=false, size_distribution=None, progress_callback=None, progress_every=100, cancel_event=None, existing_coords=None, existing_radii=None, existing_cluster_ids=None, history=None, seed_cluster_sizes=None, df_tolerance=None))]
 pub fn run_tunable_cc(
     py: Python<'_>,
     n_particles: usize,
@@ -681,20 +913,49 @@ pub fn run_tunable_cc(
     sintering_max: f64,
     sintering_std: f64,
     seed: Option<u64>,
+    sintering: Option<PySinteringParams>,
+    on_merge: Option<PyObject>,
+    callback_every: usize,
+    units: Option<PyUnits>,
+    strict: bool,
+    size_distribution: Option<PySizeDistribution>,
+    progress_callback: Option<PyObject>,
+    progress_every: usize,
+    cancel_event: Option<PyObject>,
+    existing_coords: Option<&Bound<'_, PyAny>>,
+    existing_radii: Option<&Bound<'_, PyAny>>,
+    existing_cluster_ids: Option<Vec<u32>>,
+    history: Option<PyHistoryParams>,
+    seed_cluster_sizes: Option<Vec<usize>>,
+    df_tolerance: Option<f64>,
 ) -> PyResult<PySimulationResult> {
     let seed = seed.unwrap_or_else(rand::random);
-    let radius_max = radius_max.unwrap_or(radius_min);
+    let existing = extract_existing(existing_coords, existing_radii)?;
 
-    let seed_strategy = match seed_cluster_size {
-        Some(size) if size > 1 => SeedStrategy::TunablePc { cluster_size: size },
+    let seed_strategy = match (seed_cluster_size, seed_cluster_sizes) {
+        (Some(_), Some(_)) => {
+            return Err(PyValueError::new_err(
+                "give either seed_cluster_size or seed_cluster_sizes, not both",
+            ))
+        }
+        (_, Some(sizes)) => SeedStrategy::Custom { sizes },
+        (Some(size), None) if size > 1 => SeedStrategy::TunablePc { cluster_size: size },
         _ => SeedStrategy::Monomers,
     };
 
-    let sintering = match sintering_type.to_lowercase().as_str() {
-        "uniform" => SinteringDistribution::uniform(sintering_min, sintering_max),
-        "normal" => SinteringDistribution::normal(sintering_coeff, sintering_std),
-        _ => SinteringDistribution::fixed(sintering_coeff),
-    };
+    let mut warnings = Vec::new();
+    let sintering = resolve_sintering(
+        sintering.as_ref(),
+        sintering_coeff,
+        sintering_type,
+        sintering_min,
+        sintering_max,
+        sintering_std,
+        &mut warnings,
+    )?;
+    let (radius_min, radius_max, sizes) =
+        resolve_size_distribution(size_distribution.as_ref(), radius_min, radius_max, &mut warnings)?;
+    let initial_clusters = group_clusters(existing, existing_cluster_ids)?;
 
     let params = TunableCcParams {
         n_particles,
@@ -702,33 +963,66 @@ pub fn run_tunable_cc(
         target_kf,
         radius_min,
         radius_max,
+        sizes,
         seed_strategy,
         max_rotation_attempts,
         sintering,
+        strict,
+        initial_clusters,
+        df_tolerance,
         ..Default::default()
     };
+    params.validate()?;
+    let parameters = run_parameters("tunable_cc", &params, json!({"seed": seed, "units": units, "history": history}));
+
+    let mut hooks = PyCallbacks::new(None, on_merge, callback_every)?
+        .with_progress(progress_callback, progress_every)?
+        .with_cancel_event(cancel_event)
+        .with_history(history)?;
+    // Release GIL during computation
+    let result = allow_threads(py, || run_tunable_cc_internal(params, seed, &mut hooks));
+    let history = hooks.take_history();
+    hooks.finish(&mut warnings)?;
+
+    if let Some(report) = result.target_report.as_ref().filter(|r| r.infeasible) {
+        return Err(PyRuntimeError::new_err(format!(
+            "strict tunable CC could not place any remaining pairing at Df={} / kf={} \
+             ({} merges done, {} pairings rejected); relax the targets or df_tolerance, or disable strict mode",
+            report.target_df, report.target_kf, report.target_merges, report.rejected_pairings
+        )));
+    }
 
-    // Release GIL during computation (except for seed cluster generation)
-    let result = py.allow_threads(|| run_tunable_cc_internal(params, seed, None));
-
-    Ok(result.to_py())
+    result
+        .into_py(py, warnings)
+        .map(|r| r.with_units(units).with_history(history).with_parameters(parameters))
 }
 
 /// Internal Tunable CC implementation following thesis Chapter 6.
 fn run_tunable_cc_internal(
     params: TunableCcParams,
     seed: u64,
-    py: Option<Python<'_>>,
+    hooks: &mut dyn EventHooks,
 ) -> SimulationResult {
     let start_time = Instant::now();
     let mut rng = create_rng(seed);
+    let mut warnings = Vec::new();
 
     let rp = params.mean_radius();
     let kf = params.target_kf;
     let df = params.target_df;
 
-    // Step 1: Initialize pool with seed clusters
-    let mut clusters = initialize_seed_clusters(&params, &mut rng, py);
+    // Step 1: Initialize pool with the clusters of a restarted pool and new seed clusters
+    let n_existing: usize = params.initial_clusters.iter().map(Vec::len).sum();
+    let mut clusters: Vec<TunableCluster> = params
+        .initial_clusters
+        .iter()
+        .map(|particles| TunableCluster::from_particles(particles.clone()))
+        .collect();
+    let seeds = SeedSequence::new(seed).spawn(SEED_CLUSTER_STREAM);
+    let (seed_clusters, child_seeds) =
+        initialize_seed_clusters(&params, params.n_particles - n_existing, &seeds, &mut rng);
+    clusters.extend(seed_clusters);
+    assign_particle_ids(&mut clusters);
 
     // Spread clusters out to avoid initial overlaps
     let spread = (clusters.len() as f64).cbrt() * rp * 5.0;
@@ -745,6 +1039,11 @@ fn run_tunable_cc_internal(
     // Count successful tunable merges vs fallback
     let mut tunable_merges = 0;
     let mut fallback_merges = 0;
+    let mut rejected_pairings = 0;
+    let mut diagnostics = MergeDiagnostics::default();
+    let mut failed_in_a_row = 0;
+    let mut infeasible = false;
+    let mut lineage = Lineage::new(clusters.len());
 
     // Step 2: Main aggregation loop - continue until only one cluster remains
     let mut iterations = 0;
@@ -779,8 +1078,14 @@ fn run_tunable_cc_internal(
         let n_po1 = impacted.n_particles();
         let n_po2 = impactor.n_particles();
 
-        // Step 3: Calculate required CoM distance using power law
-        let required_distance = match calculate_com_distance(n_po, n_po1, n_po2, kf, df, rp) {
+        // Step 3: Calculate required CoM distance using power law; two
+        // monomers can only meet in contact
+        let com_distance = if n_po == 2 {
+            Some(impacted.particles[0].radius + impactor.particles[0].radius)
+        } else {
+            calculate_com_distance(n_po, n_po1, n_po2, kf, df, rp)
+        };
+        let required_distance = match com_distance {
             Some(d) => d,
             None => {
                 // Use fallback for problematic cases
@@ -789,8 +1094,9 @@ fn run_tunable_cc_internal(
             }
         };
 
-        // Step 4: Check if clusters CAN connect
-        let can_connect = can_clusters_connect(&impacted, &impactor, required_distance);
+        // Step 4: Check if clusters CAN connect (strict mode only accepts the power-law distance)
+        let can_connect = (com_distance.is_some() || !params.strict)
+            && can_clusters_connect(&impacted, &impactor, required_distance);
 
         let mut merge_success = false;
 
@@ -825,7 +1131,6 @@ fn run_tunable_cc_internal(
                             // Step 8: Check and resolve overlaps (with sintering)
                             if !check_overlap(&impacted, &impactor, sintering_coeff) {
                                 merge_success = true;
-                                tunable_merges += 1;
                                 break;
                             } else if resolve_overlap_by_rotation(
                                 &impacted,
@@ -836,24 +1141,52 @@ fn run_tunable_cc_internal(
                                 &mut rng,
                             ) {
                                 merge_success = true;
-                                tunable_merges += 1;
                                 break;
                             }
                         }
                     }
+                    diagnostics.failed_positionings += 1;
                 }
             }
         }
 
-        // Fallback: ballistic merge if tunable positioning failed
-        if !merge_success {
-            if merge_ballistic(&impacted, &mut impactor, sintering_coeff, &mut rng) {
-                merge_success = true;
-                fallback_merges += 1;
+        // Fallback: ballistic merge if tunable positioning failed; strict mode
+        // retries with another pairing instead
+        let fallback = is_fallback_merge(merge_success, com_distance);
+        if !merge_success && !params.strict {
+            merge_success = merge_ballistic(&impacted, &mut impactor, sintering_coeff, &mut rng);
+        }
+
+        // With a tolerance the merged cluster must follow the target power law
+        let merged_rg = merged_radius_of_gyration(&impacted, &impactor);
+        let local_df = local_fractal_dimension(n_po, merged_rg, kf, rp);
+        if let Some(tolerance) = params.df_tolerance {
+            if merge_success && n_po >= DF_CHECK_MIN_PARTICLES && (local_df - df).abs() > tolerance {
+                merge_success = false;
+                diagnostics.df_rejections += 1;
             }
         }
 
+        if !merge_success && (params.strict || params.df_tolerance.is_some()) {
+            rejected_pairings += 1;
+            failed_in_a_row += 1;
+            if failed_in_a_row >= STRICT_MAX_FAILED_PAIRINGS {
+                infeasible = true;
+                break;
+            }
+            continue;
+        }
+        failed_in_a_row = 0;
+
         if merge_success {
+            if fallback {
+                fallback_merges += 1;
+            } else {
+                tunable_merges += 1;
+            }
+            let achieved = impacted.center_of_mass.distance_to(&impactor.center_of_mass);
+            diagnostics.record(fallback, com_distance, achieved, (n_po >= DF_CHECK_MIN_PARTICLES).then_some(local_df));
+
             // Remove both original clusters (higher index first to avoid shifting issues)
             let (higher_idx, lower_idx) = if impactor_idx > impacted_idx {
                 (impactor_idx, impacted_idx)
@@ -867,22 +1200,72 @@ fn run_tunable_cc_internal(
             // Create merged cluster from our clones
             // impacted was stationary, impactor was moved into position
             let mut merged = impacted;
+            let merge = lineage.record(
+                iterations,
+                (merged.label, merged.n_particles()),
+                (impactor.label, impactor.n_particles()),
+            );
             merged.merge_with(impactor);
+            merged.label = merge.merged;
+            let event = ClusterMergeEvent::new(merge, merged.center_of_mass, clusters.len() + 1);
             clusters.push(merged);
 
             // Track evolution
+            let mut progress = Flow::Continue;
             if let Some(largest) = clusters.iter().max_by_key(|c| c.n_particles()) {
                 rg_evolution.push(largest.radius_of_gyration);
                 n_values.push(largest.n_particles());
+                let event = ProgressEvent::merges(
+                    lineage.n_merges(),
+                    largest.n_particles(),
+                    params.n_particles,
+                    clusters.len(),
+                    largest.radius_of_gyration,
+                    start_time,
+                );
+                if hooks.wants_snapshot(&event) {
+                    let pool = clusters.iter().map(|c| c.particles.as_slice());
+                    hooks.on_snapshot(Snapshot::of_clusters(event.step, pool));
+                }
+                progress = hooks.on_progress(&event);
+            }
+
+            if hooks.on_merge(&event) == Flow::Stop || progress == Flow::Stop {
+                break;
             }
         }
     }
 
-    // Collect final result
-    let final_particles: Vec<Sphere> = if clusters.is_empty() {
-        Vec::new()
-    } else {
-        clusters.remove(0).particles
+    if fallback_merges > 0 {
+        warnings.push(format!(
+            "{} of {} merges could not be placed at the Df={} / kf={} power-law distance",
+            fallback_merges,
+            tunable_merges + fallback_merges,
+            df,
+            kf
+        ));
+    }
+
+    // Collect the final cluster in creation order. If the run stopped early
+    // (callback or iteration limit), keep the largest cluster.
+    if clusters.len() > 1 {
+        warnings.push(format!(
+            "aggregation stopped with {} clusters left; returning the largest one",
+            clusters.len()
+        ));
+    }
+    let largest = (0..clusters.len()).max_by_key(|&i| clusters[i].n_particles());
+    let (ids, generations, final_particles) = match largest {
+        None => (Vec::new(), Vec::new(), Vec::new()),
+        Some(index) => {
+            let cluster = clusters.swap_remove(index);
+            let order = creation_order(&cluster.ids);
+            (
+                reorder(&cluster.ids, &order),
+                reorder(&cluster.generations, &order),
+                reorder(&cluster.particles, &order),
+            )
+        }
     };
 
     // Calculate final metrics
@@ -893,10 +1276,25 @@ fn run_tunable_cc_internal(
     let radii: Vec<f64> = final_particles.iter().map(|s| s.radius).collect();
 
     // Calculate Df and kf from evolution
-    let (actual_df, actual_kf, _r2) = calculate_fractal_dimension_from_evolution(&n_values, &rg_evolution, rp);
+    let fit = calculate_fractal_dimension_from_evolution(&n_values, &rg_evolution, rp, &mut warnings);
+
+    let merges = tunable_merges + fallback_merges;
+    let target_report = TargetReport {
+        target_df: df,
+        target_kf: kf,
+        target_merges: tunable_merges,
+        fallback_merges,
+        rejected_pairings,
+        target_fraction: if merges > 0 { tunable_merges as f64 / merges as f64 } else { 1.0 },
+        df_deviation: fit.df - df,
+        kf_deviation: fit.kf - kf,
+        strict: params.strict,
+        infeasible,
+    };
 
     let porosity = calculate_porosity(&coords, &radii);
-    let coordination = calculate_coordination(&coords, &radii, rp * 0.1);
+    let contacts = calculate_contacts(&coords, &radii, rp * 0.1);
+    let coordination = coordination_from_contacts(&contacts, coords.len());
     let inertia = calculate_inertia_tensor(&coords, &radii);
 
     let coord_mean = coordination.iter().map(|&c| c as f64).sum::<f64>() / coordination.len().max(1) as f64;
@@ -911,88 +1309,52 @@ fn run_tunable_cc_internal(
         0.0
     };
 
+    let n_final = coords.len();
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
     SimulationResult {
         coordinates: coords,
         radii,
         rg_evolution,
-        fractal_dimension: actual_df,
-        fractal_dimension_std: 0.05,
-        prefactor: actual_kf,
+        n_evolution: n_values,
+        fractal_dimension: fit.df,
+        fractal_dimension_std: fit.df_std_error,
+        prefactor: fit.kf,
+        linear_region: fit.linear_region,
+        evolution_fit: EvolutionFit::NOnRg { rp },
+        fit_r_squared: fit.r_squared,
+        fit_n_points: fit.n_points,
         porosity,
         coordination_mean: coord_mean,
         coordination_std: coord_std,
         execution_time_ms,
         seed,
+        child_seeds,
         anisotropy: inertia.anisotropy,
         asphericity: inertia.asphericity,
         acylindricity: inertia.acylindricity,
         principal_moments: inertia.principal_moments,
         principal_axes: inertia.principal_axes,
+        ids,
+        cluster_ids: vec![0; n_final],
+        coordination,
+        contacts,
+        generations,
+        merge_history: lineage.into_events(),
+        warnings,
+        target_report: Some(target_report),
+        brownian_report: None,
+        kinetics: None,
+        film: None,
+        drift_report: None,
+        merge_diagnostics: Some(diagnostics.finish(df, params.df_tolerance)),
     }
 }
 
-/// Calculate Df and kf from Rg evolution using power law fitting.
-fn calculate_fractal_dimension_from_evolution(
-    n_values: &[usize],
-    rg_values: &[f64],
-    rp: f64,
-) -> (f64, f64, f64) {
-    if n_values.len() < 3 || n_values.len() != rg_values.len() {
-        return (2.0, 1.0, 0.0);
-    }
-
-    // Use N = kf * (Rg/rp)^Df
-    // log(N) = log(kf) + Df * log(Rg/rp)
-    let data: Vec<(f64, f64)> = n_values
-        .iter()
-        .zip(rg_values.iter())
-        .filter(|(&n, &rg)| n > 1 && rg > rp * 0.1)
-        .map(|(&n, &rg)| ((rg / rp).ln(), (n as f64).ln()))
-        .collect();
-
-    if data.len() < 3 {
-        return (2.0, 1.0, 0.0);
-    }
-
-    // Linear regression
-    let n = data.len() as f64;
-    let sum_x: f64 = data.iter().map(|(x, _)| x).sum();
-    let sum_y: f64 = data.iter().map(|(_, y)| y).sum();
-    let sum_xx: f64 = data.iter().map(|(x, _)| x * x).sum();
-    let sum_xy: f64 = data.iter().map(|(x, y)| x * y).sum();
-
-    let denom = n * sum_xx - sum_x * sum_x;
-    if denom.abs() < 1e-10 {
-        return (2.0, 1.0, 0.0);
-    }
-
-    let slope = (n * sum_xy - sum_x * sum_y) / denom;
-    let intercept = (sum_y - slope * sum_x) / n;
-
-    let df = slope.max(1.0).min(3.0);
-    let kf = intercept.exp().max(0.1).min(10.0);
-
-    // R-squared
-    let mean_y = sum_y / n;
-    let ss_tot: f64 = data.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
-    let ss_res: f64 = data
-        .iter()
-        .map(|(x, y)| {
-            let y_pred = intercept + slope * x;
-            (y - y_pred).powi(2)
-        })
-        .sum();
-
-    let r2 = if ss_tot > 0.0 { 1.0 - ss_res / ss_tot } else { 0.0 };
-
-    (df, kf, r2)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::simulation::hooks::NoHooks;
 
     #[test]
     fn test_tunable_cc_deterministic() {
@@ -1003,8 +1365,8 @@ mod tests {
             ..Default::default()
         };
 
-        let r1 = run_tunable_cc_internal(params.clone(), 42, None);
-        let r2 = run_tunable_cc_internal(params, 42, None);
+        let r1 = run_tunable_cc_internal(params.clone(), 42, &mut NoHooks);
+        let r2 = run_tunable_cc_internal(params, 42, &mut NoHooks);
 
         assert_eq!(r1.coordinates.len(), r2.coordinates.len());
         assert_eq!(r1.seed, r2.seed);
@@ -1019,7 +1381,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = run_tunable_cc_internal(params, 123, None);
+        let result = run_tunable_cc_internal(params, 123, &mut NoHooks);
 
         // Should produce all particles
         assert_eq!(result.coordinates.len(), 50);
@@ -1032,6 +1394,165 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tunable_cc_sintered_seed_clusters() {
+        let params = TunableCcParams {
+            n_particles: 20,
+            seed_strategy: SeedStrategy::TunablePc { cluster_size: 5 },
+            sintering: SinteringDistribution::fixed(0.9),
+            ..Default::default()
+        };
+        let (clusters, _) = initialize_seed_clusters(&params, 20, &SeedSequence::new(3), &mut create_rng(3));
+
+        // Seed clusters are grown by Tunable PC, sintered like the merges
+        assert_eq!(clusters.iter().map(|c| c.particles.len()).collect::<Vec<_>>(), vec![5; 4]);
+        for cluster in &clusters {
+            for (i, a) in cluster.particles.iter().enumerate() {
+                let nearest = cluster
+                    .particles
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, b)| a.center.distance_to(&b.center))
+                    .fold(f64::INFINITY, f64::min);
+                assert!((nearest - 1.8).abs() < 2e-2, "nearest = {}", nearest);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tunable_cc_custom_seed_sizes() {
+        let params = TunableCcParams {
+            n_particles: 30,
+            seed_strategy: SeedStrategy::Custom { sizes: vec![12, 8, 5, 1, 4] },
+            ..Default::default()
+        };
+        params.validate().unwrap();
+        let (clusters, _) = initialize_seed_clusters(&params, 30, &SeedSequence::new(8), &mut create_rng(8));
+        let sizes: Vec<usize> = clusters.iter().map(|c| c.particles.len()).collect();
+        assert_eq!(sizes, vec![12, 8, 5, 1, 4]);
+        // Grown clusters, not particles stacked at the origin
+        assert!(clusters[0].particles.iter().all(|a| clusters[0]
+            .particles
+            .iter()
+            .filter(|b| a.center.distance_to(&b.center) < 1.0)
+            .count()
+            == 1));
+
+        let result = run_tunable_cc_internal(params.clone(), 8, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 30);
+        assert!(result.cluster_ids.iter().all(|&k| k == 0));
+        // Seeds of the grown clusters, reproducible from the run's seed
+        let seeds = SeedSequence::new(8).spawn(SEED_CLUSTER_STREAM);
+        assert_eq!(result.child_seeds, [0, 1, 2, 4].map(|k| seeds.seed(k)));
+
+        // The sizes must account for exactly the new particles
+        let short = TunableCcParams {
+            seed_strategy: SeedStrategy::Custom { sizes: vec![12, 8] },
+            ..params.clone()
+        };
+        assert!(short.validate().is_err());
+        let empty = TunableCcParams {
+            seed_strategy: SeedStrategy::Custom { sizes: vec![30, 0] },
+            ..params
+        };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn test_tunable_cc_merge_diagnostics() {
+        let params = TunableCcParams {
+            n_particles: 40,
+            ..Default::default()
+        };
+        let result = run_tunable_cc_internal(params, 123, &mut NoHooks);
+        let report = result.target_report.unwrap();
+        let diagnostics = result.merge_diagnostics.expect("tunable CC reports its merges");
+
+        assert_eq!(diagnostics.merge_types.len(), 39);
+        assert_eq!(diagnostics.target_merges, report.target_merges);
+        assert_eq!(diagnostics.fallback_merges, report.fallback_merges);
+        assert_eq!(diagnostics.achieved_distances.len(), 39);
+        // Power-law merges sit at the CoM distance the power law asks for
+        assert!(diagnostics.max_target_error < 1e-9, "{}", diagnostics.max_target_error);
+        for (kind, error) in diagnostics.merge_types.iter().zip(&diagnostics.distance_errors) {
+            assert!(kind == "fallback" || error.abs() < 1e-9);
+        }
+        // Only merges with a power-law distance count toward the target
+        for (kind, target) in diagnostics.merge_types.iter().zip(&diagnostics.target_distances) {
+            assert!(kind == "fallback" || target.is_finite());
+        }
+        assert_eq!(report.target_fraction, report.target_merges as f64 / 39.0);
+    }
+
+    #[test]
+    fn test_tunable_cc_df_tolerance() {
+        let tolerance = 0.05;
+        let params = TunableCcParams {
+            n_particles: 60,
+            df_tolerance: Some(tolerance),
+            ..Default::default()
+        };
+        let result = run_tunable_cc_internal(params, 7, &mut NoHooks);
+        assert!(!result.target_report.unwrap().infeasible);
+        assert_eq!(result.coordinates.len(), 60);
+
+        let diagnostics = result.merge_diagnostics.unwrap();
+        assert_eq!(diagnostics.df_tolerance, Some(tolerance));
+        assert!(diagnostics.max_df_deviation <= tolerance, "{}", diagnostics.max_df_deviation);
+        // The last merge builds the whole agglomerate
+        let rg = calculate_radius_of_gyration(&result.coordinates, &result.radii);
+        let expected = local_fractal_dimension(60, rg, 1.3, 1.0);
+        let last = *diagnostics.local_dfs.last().unwrap();
+        assert!((last - expected).abs() < 1e-9, "{} vs {}", last, expected);
+        assert!((last - 1.8).abs() <= tolerance);
+    }
+
+    #[test]
+    fn test_tunable_cc_unreachable_df_tolerance_is_infeasible() {
+        // No merged cluster of 10 or more matches the target this closely,
+        // ballistic fallbacks included, so the run gives up instead of looping
+        let params = TunableCcParams {
+            n_particles: 40,
+            df_tolerance: Some(1e-9),
+            ..Default::default()
+        };
+        let result = run_tunable_cc_internal(params, 7, &mut NoHooks);
+        let report = result.target_report.unwrap();
+        assert!(report.infeasible);
+        assert!(!report.strict);
+        assert!(report.rejected_pairings >= STRICT_MAX_FAILED_PAIRINGS);
+        assert!(result.merge_diagnostics.unwrap().df_rejections >= STRICT_MAX_FAILED_PAIRINGS);
+    }
+
+    #[test]
+    fn test_tunable_cc_restarts_from_cluster_pool() {
+        let params = TunableCcParams {
+            n_particles: 20,
+            ..Default::default()
+        };
+        let core = run_tunable_cc_internal(params, 5, &mut NoHooks);
+        let initial: Vec<Sphere> = core
+            .coordinates
+            .iter()
+            .zip(&core.radii)
+            .map(|(c, &r)| Sphere::new(Vector3::new(c[0], c[1], c[2]), r))
+            .collect();
+
+        let params = TunableCcParams {
+            n_particles: 30,
+            initial_clusters: vec![initial],
+            ..Default::default()
+        };
+        let result = run_tunable_cc_internal(params, 123, &mut NoHooks);
+        assert_eq!(result.coordinates.len(), 30);
+        assert_eq!(result.merge_history.len(), 10);
+        // The existing cluster keeps the first IDs and its shape
+        let distance = |a: [f64; 3], b: [f64; 3]| Vector3::new(a[0] - b[0], a[1] - b[1], a[2] - b[2]).length();
+        let d_before = distance(core.coordinates[0], core.coordinates[19]);
+        assert!((distance(result.coordinates[0], result.coordinates[19]) - d_before).abs() < 1e-9);
+    }
+
     #[test]
     fn test_tunable_cc_no_overlaps() {
         let params = TunableCcParams {
@@ -1039,7 +1560,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = run_tunable_cc_internal(params, 456, None);
+        let result = run_tunable_cc_internal(params, 456, &mut NoHooks);
 
         // Verify no particles overlap
         for i in 0..result.coordinates.len() {
@@ -1072,9 +1593,9 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(params.is_polydisperse());
+        assert!(params.size_distribution().is_polydisperse());
 
-        let result = run_tunable_cc_internal(params, 789, None);
+        let result = run_tunable_cc_internal(params, 789, &mut NoHooks);
 
         let min_r = result.radii.iter().cloned().fold(f64::INFINITY, f64::min);
         let max_r = result.radii.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
@@ -1101,5 +1622,55 @@ mod tests {
         let d = dist.unwrap();
         assert!(d > 0.0, "Distance should be positive");
         assert!(d < 100.0, "Distance should be reasonable");
+
+        // A monomer impactor reproduces the Tunable PC step
+        let n = 10.0f64;
+        let g = |m: f64| (m / kf).powf(2.0 / df) - 0.6;
+        let gamma_sq = n * n / (n - 1.0) * g(n) - n * g(n - 1.0) - n / (n - 1.0) * g(1.0);
+        let d = calculate_com_distance(10, 9, 1, kf, df, rp).unwrap();
+        assert!((d - gamma_sq.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_merge_without_power_law_distance_is_fallback() {
+        assert!(!is_fallback_merge(true, Some(3.0)));
+        assert!(is_fallback_merge(false, Some(3.0)));
+        assert!(is_fallback_merge(true, None));
+
+        let mut diagnostics = MergeDiagnostics::default();
+        diagnostics.record(is_fallback_merge(true, Some(2.0)), Some(2.0), 2.0, None);
+        diagnostics.record(is_fallback_merge(true, None), None, 3.0, None);
+        let diagnostics = diagnostics.finish(1.8, None);
+        assert_eq!(diagnostics.merge_types, ["target", "fallback"]);
+        assert_eq!((diagnostics.target_merges, diagnostics.fallback_merges), (1, 1));
+        assert_eq!(diagnostics.max_target_error, 0.0);
     }
+
+    #[test]
+    fn test_tunable_cc_strict_never_falls_back() {
+        let params = TunableCcParams {
+            n_particles: 40,
+            strict: true,
+            ..Default::default()
+        };
+        let result = run_tunable_cc_internal(params, 11, &mut NoHooks);
+        let report = result.target_report.expect("tunable CC reports its targets");
+        assert!(report.strict);
+        assert_eq!(report.fallback_merges, 0);
+        assert!(!report.infeasible);
+        assert_eq!(result.coordinates.len(), 40);
+        assert_eq!(report.target_merges, 39);
+        assert_eq!(report.target_fraction, 1.0);
+
+        let params = TunableCcParams {
+            n_particles: 40,
+            ..Default::default()
+        };
+        let lenient = run_tunable_cc_internal(params, 11, &mut NoHooks);
+        let report = lenient.target_report.unwrap();
+        assert_eq!(report.rejected_pairings, 0);
+        assert!((0.0..=1.0).contains(&report.target_fraction));
+        assert!((report.df_deviation - (lenient.fractal_dimension - 1.8)).abs() < 1e-12);
+    }
+
 }
@@ -59,6 +59,12 @@ class TestDLASimulation:
         assert result.coordination_std >= 0
         assert len(result.rg_evolution) > 0
 
+    def test_dla_without_fit_has_nan_std(self):
+        """Test that a run too small to fit reports NaN, not infinity."""
+        result = aglogen_core.run_dla(n_particles=2, seed=42)
+
+        assert np.isnan(result.fractal_dimension_std)
+
 
 class TestCCASimulation:
     """Tests for CCA (Cluster-Cluster Aggregation) simulation."""
@@ -143,6 +149,80 @@ class TestBallisticSimulation:
         assert cca_result.fractal_dimension > 0.5
 
 
+class TestSimulationPipeline:
+    """Tests for multi-stage pipelines."""
+
+    STAGES = [
+        {"algorithm": "tunable", "n_particles": 30, "copies": 3},
+        {"algorithm": "ballistic_cc"},
+        {"algorithm": "dla", "n_additional": 20},
+    ]
+
+    def test_stages_chain(self):
+        """Test that every stage starts from the clusters of the previous one."""
+        results = aglogen_core.SimulationPipeline(self.STAGES, seed=42).run()
+
+        # Three tunable seeds merged into one agglomerate, then decorated by DLA
+        assert len(results) == 1
+        result = results[0]
+        assert result.coordinates.shape == (110, 3)
+        assert len(set(result.cluster_ids.tolist())) == 1
+
+        # The seeds were spread apart before they were merged: nothing overlaps
+        coords, radii = result.coordinates, result.radii
+        distances = np.linalg.norm(coords[:, None] - coords[None, :], axis=-1)
+        contact = radii[:, None] + radii[None, :]
+        np.fill_diagonal(distances, np.inf)
+        assert np.all(distances >= contact * (1.0 - 1e-6))
+
+    def test_pipeline_deterministic_with_seed(self):
+        """Test that a seeded pipeline is reproducible as a whole."""
+        first = aglogen_core.SimulationPipeline(self.STAGES, seed=7).run()[0]
+        second = aglogen_core.SimulationPipeline(self.STAGES, seed=7).run()[0]
+
+        np.testing.assert_array_equal(first.coordinates, second.coordinates)
+
+
+class TestAnalysisSession:
+    """Tests for analysis sessions."""
+
+    def test_session_runs_on_its_pool(self):
+        """Test that session calls match direct calls and reach Python callbacks."""
+        steps = []
+        with aglogen_core.AnalysisSession(seed=3, n_threads=2) as session:
+            result = session.run(
+                "dla",
+                n_particles=40,
+                seed=11,
+                progress_callback=lambda info: steps.append(info["step"]),
+                progress_every=10,
+            )
+            fit = session.analyze("box_counting_3d", result.coordinates)
+
+        direct = aglogen_core.run_dla(n_particles=40, seed=11)
+        np.testing.assert_array_equal(result.coordinates, direct.coordinates)
+        assert steps
+        assert result.session == "session#0"
+        assert fit.session == "session#1"
+        assert [r[1] for r in session.records] == ["run", "analyze"]
+
+    def test_callbacks_can_use_the_session(self):
+        """Test that a callback can read the session while its call runs."""
+        seen = []
+        with aglogen_core.AnalysisSession(seed=3) as session:
+            session.analyze("box_counting_3d", aglogen_core.run_dla(n_particles=20, seed=1).coordinates)
+            result = session.run(
+                "dla",
+                n_particles=40,
+                progress_callback=lambda info: seen.append(len(session.records)),
+                progress_every=10,
+            )
+
+        assert seen and all(n == 1 for n in seen)
+        assert result.session == "session#1"
+        assert [r[1] for r in session.records] == ["analyze", "run"]
+
+
 class TestModuleMetadata:
     """Tests for module metadata and version."""
 
@@ -185,6 +265,43 @@ class TestResultAttributes:
         assert result.coordinates.ndim == 2
         assert result.coordinates.shape[1] == 3  # x, y, z
 
+    def test_result_to_structured(self):
+        """Test that the structured array holds every per-particle column."""
+        result = aglogen_core.run_dla(n_particles=20, seed=42)
+
+        arr = result.to_structured()
+        assert arr.dtype.names == (
+            "id", "x", "y", "z", "r", "species", "cluster_id", "coordination", "generation"
+        )
+        assert arr.shape == (20,)
+        for k, name in enumerate(["x", "y", "z"]):
+            np.testing.assert_array_equal(arr[name], result.coordinates[:, k])
+        np.testing.assert_array_equal(arr["r"], result.radii)
+        np.testing.assert_array_equal(arr["species"], np.zeros(20))
+        np.testing.assert_array_equal(arr["cluster_id"], result.cluster_ids)
+        np.testing.assert_array_equal(arr["coordination"], result.coordination)
+        np.testing.assert_array_equal(arr["id"], result.ids)
+        np.testing.assert_array_equal(arr["generation"], result.generations)
+
+    def test_result_records_parameters(self):
+        """Test that a run records the algorithm, seed and resolved parameters."""
+        result = aglogen_core.run_dla(n_particles=20, sticking_probability=0.5, seed=42)
+
+        parameters = result.parameters
+        assert parameters["algorithm"] == "dla"
+        assert parameters["version"] == aglogen_core.version()
+        assert parameters["seed"] == 42
+        assert parameters["n_particles"] == 20
+        assert parameters["sticking_probability"] == 0.5
+        assert type(result).from_json(result.to_json()).parameters == parameters
+
+    def test_physical_run_records_default_units(self):
+        """Test that the default nm units of run_cca_physical are recorded."""
+        result = aglogen_core.run_cca_physical(n_particles=10, box_size=60.0, seed=1)
+
+        assert result.parameters["algorithm"] == "cca_physical"
+        assert result.parameters["units"]["length_unit"] == "nm"
+
 
 class TestEdgeCases:
     """Tests for edge cases and boundary conditions."""
@@ -215,3 +332,72 @@ class TestEdgeCases:
 
         # All radii should be 2.5
         np.testing.assert_array_equal(result.radii, np.full(20, 2.5))
+
+
+def _read_vtk(path):
+    """Parse the legacy VTK PolyData written by export_vtk."""
+    lines = path.read_text().splitlines()
+    header, body = lines[:4], iter(lines[4:])
+    points, cells, scalars = None, [], {}
+    for line in body:
+        keyword, *fields = line.split()
+        if keyword == "POINTS":
+            points = np.array([next(body).split() for _ in range(int(fields[0]))], dtype=float)
+        elif keyword == "VERTICES":
+            for _ in range(int(fields[0])):
+                next(body)
+        elif keyword == "LINES":
+            cells = [tuple(int(v) for v in next(body).split()[1:]) for _ in range(int(fields[0]))]
+        elif keyword == "SCALARS":
+            next(body)  # LOOKUP_TABLE default
+            scalars[fields[0]] = np.array([float(next(body)) for _ in range(len(points))])
+    return header, points, cells, scalars
+
+
+class TestVTKExport:
+    """Tests for legacy VTK export of simulation and projection results."""
+
+    def test_export_simulation(self, tmp_path):
+        """Test that particles, contacts and point scalars are written."""
+        result = aglogen_core.run_dla(n_particles=30, seed=42)
+        path = tmp_path / "agglomerate.vtk"
+        aglogen_core.export_vtk(result, path)
+
+        header, points, cells, scalars = _read_vtk(path)
+        assert header[1] == "aglogen agglomerate, 30 particles"
+        np.testing.assert_allclose(points, result.coordinates, atol=1e-5)
+        assert len(cells) > 0
+        assert cells == [(i, j) for i, j, _, _ in result.contacts]
+        assert list(scalars) == ["radius", "cluster_id", "coordination"]
+        np.testing.assert_allclose(scalars["radius"], result.radii, atol=1e-5)
+        np.testing.assert_array_equal(scalars["cluster_id"], result.cluster_ids)
+        np.testing.assert_array_equal(scalars["coordination"], result.coordination)
+
+    def test_export_simulation_without_contacts(self, tmp_path):
+        """Test that contacts=False leaves out the line cells."""
+        result = aglogen_core.run_dla(n_particles=30, seed=42)
+        path = tmp_path / "agglomerate.vtk"
+        aglogen_core.export_vtk(result, path, contacts=False, title="no contacts")
+
+        header, points, cells, _ = _read_vtk(path)
+        assert header[1] == "no contacts"
+        assert points.shape == (30, 3)
+        assert cells == []
+
+    def test_export_projection(self, tmp_path):
+        """Test that a projection is written in the z = 0 plane."""
+        result = aglogen_core.run_dla(n_particles=30, seed=42)
+        projection = aglogen_core.project_to_2d(
+            result.coordinates, result.radii, azimuth=30.0, elevation=45.0
+        )
+        path = tmp_path / "projection.vtk"
+        aglogen_core.export_vtk(projection, path)
+
+        header, points, cells, scalars = _read_vtk(path)
+        assert header[1] == "aglogen projection, azimuth 30 deg, elevation 45 deg"
+        np.testing.assert_allclose(points[:, 0], projection.x, atol=1e-5)
+        np.testing.assert_allclose(points[:, 1], projection.y, atol=1e-5)
+        np.testing.assert_array_equal(points[:, 2], 0.0)
+        assert cells == []
+        assert list(scalars) == ["radius"]
+        np.testing.assert_allclose(scalars["radius"], projection.radii, atol=1e-5)